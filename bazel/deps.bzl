@@ -82,6 +82,14 @@ def perfetto_deps():
         build_file = "//bazel:zlib.BUILD",
     )
 
+    _add_repo_if_not_existing(
+        new_git_repository,
+        name = "perfetto_dep_zstd",
+        remote = "https://android.googlesource.com/platform/external/zstd.git",
+        commit = "77211fcc5e08c781734a386402ada93d0d18d093",
+        build_file = "//bazel:zstd.BUILD",
+    )
+
     _add_repo_if_not_existing(
         http_archive,
         name = "perfetto_dep_llvm_demangle",
@@ -0,0 +1,75 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef INCLUDE_PERFETTO_EXT_BASE_SHA256_H_
+#define INCLUDE_PERFETTO_EXT_BASE_SHA256_H_
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include <array>
+#include <string>
+#include <string_view>
+
+namespace perfetto::base {
+
+// Computes the SHA-256 digest (FIPS 180-4) of the input data. Unlike
+// FnvHasher, this is suitable to identify data by its contents where
+// collisions must not happen in practice (e.g. content-addressed caches).
+//
+// Usage:
+//   Sha256Hasher hasher;
+//   hasher.Update(data, size);
+//   std::string hex = hasher.HexDigest();
+class Sha256Hasher {
+ public:
+  static constexpr size_t kDigestSize = 32;
+  using Digest = std::array<uint8_t, kDigestSize>;
+
+  Sha256Hasher();
+
+  // Hashes a byte array.
+  void Update(const void* data, size_t size);
+  void Update(std::string_view s) { Update(s.data(), s.size()); }
+
+  // Returns the digest of the data hashed so far. Doesn't modify the state of
+  // the hasher, so more data can be hashed afterwards.
+  Digest digest() const;
+
+  // Same as digest(), formatted as a lowercase hex string.
+  std::string HexDigest() const;
+
+  // Returns the hex digest of |s|.
+  static std::string HexDigestOf(std::string_view s) {
+    Sha256Hasher hasher;
+    hasher.Update(s);
+    return hasher.HexDigest();
+  }
+
+ private:
+  static constexpr size_t kBlockSize = 64;
+
+  void ProcessBlock(const uint8_t* block);
+
+  std::array<uint32_t, 8> state_;
+  std::array<uint8_t, kBlockSize> buffer_{};
+  size_t buffer_size_ = 0;
+  uint64_t total_size_ = 0;
+};
+
+}  // namespace perfetto::base
+
+#endif  // INCLUDE_PERFETTO_EXT_BASE_SHA256_H_
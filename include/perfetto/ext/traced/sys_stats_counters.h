@@ -361,6 +361,48 @@ const KeyAndId kVmstatKeys[] = {
      protos::pbzero::VmstatCounters::VMSTAT_WORKINGSET_RESTORE_FILE},
 };
 
+// Keys are in the form "<section>:<name>", where <section> is the prefix of
+// the header and value lines of /proc/net/snmp and /proc/net/netstat.
+constexpr KeyAndId kNetstatKeys[] = {
+    {"Tcp:ActiveOpens",
+     protos::pbzero::NetstatCounters::NETSTAT_TCP_ACTIVE_OPENS},
+    {"Tcp:PassiveOpens",
+     protos::pbzero::NetstatCounters::NETSTAT_TCP_PASSIVE_OPENS},
+    {"Tcp:AttemptFails",
+     protos::pbzero::NetstatCounters::NETSTAT_TCP_ATTEMPT_FAILS},
+    {"Tcp:EstabResets",
+     protos::pbzero::NetstatCounters::NETSTAT_TCP_ESTAB_RESETS},
+    {"Tcp:CurrEstab", protos::pbzero::NetstatCounters::NETSTAT_TCP_CURR_ESTAB},
+    {"Tcp:InSegs", protos::pbzero::NetstatCounters::NETSTAT_TCP_IN_SEGS},
+    {"Tcp:OutSegs", protos::pbzero::NetstatCounters::NETSTAT_TCP_OUT_SEGS},
+    {"Tcp:RetransSegs",
+     protos::pbzero::NetstatCounters::NETSTAT_TCP_RETRANS_SEGS},
+    {"Tcp:InErrs", protos::pbzero::NetstatCounters::NETSTAT_TCP_IN_ERRS},
+    {"Tcp:OutRsts", protos::pbzero::NetstatCounters::NETSTAT_TCP_OUT_RSTS},
+    {"TcpExt:ListenOverflows",
+     protos::pbzero::NetstatCounters::NETSTAT_TCPEXT_LISTEN_OVERFLOWS},
+    {"TcpExt:ListenDrops",
+     protos::pbzero::NetstatCounters::NETSTAT_TCPEXT_LISTEN_DROPS},
+    {"TcpExt:TCPLostRetransmit",
+     protos::pbzero::NetstatCounters::NETSTAT_TCPEXT_TCP_LOST_RETRANSMIT},
+    {"TcpExt:TCPFastRetrans",
+     protos::pbzero::NetstatCounters::NETSTAT_TCPEXT_TCP_FAST_RETRANS},
+    {"TcpExt:TCPSlowStartRetrans",
+     protos::pbzero::NetstatCounters::NETSTAT_TCPEXT_TCP_SLOW_START_RETRANS},
+    {"TcpExt:TCPTimeouts",
+     protos::pbzero::NetstatCounters::NETSTAT_TCPEXT_TCP_TIMEOUTS},
+    {"TcpExt:TCPSynRetrans",
+     protos::pbzero::NetstatCounters::NETSTAT_TCPEXT_TCP_SYN_RETRANS},
+    {"TcpExt:TCPAbortOnData",
+     protos::pbzero::NetstatCounters::NETSTAT_TCPEXT_TCP_ABORT_ON_DATA},
+    {"TcpExt:TCPAbortOnClose",
+     protos::pbzero::NetstatCounters::NETSTAT_TCPEXT_TCP_ABORT_ON_CLOSE},
+    {"TcpExt:TCPAbortOnTimeout",
+     protos::pbzero::NetstatCounters::NETSTAT_TCPEXT_TCP_ABORT_ON_TIMEOUT},
+    {"TcpExt:TCPRetransFail",
+     protos::pbzero::NetstatCounters::NETSTAT_TCPEXT_TCP_RETRANS_FAIL},
+};
+
 // Returns a lookup table of meminfo counter names addressable by counter id.
 inline std::vector<const char*> BuildMeminfoCounterNames() {
   int max_id = 0;
@@ -384,6 +426,17 @@ inline std::vector<const char*> BuildVmstatCounterNames() {
   return v;
 }
 
+inline std::vector<const char*> BuildNetstatCounterNames() {
+  int max_id = 0;
+  for (size_t i = 0; i < base::ArraySize(kNetstatKeys); i++)
+    max_id = std::max(max_id, kNetstatKeys[i].id);
+  std::vector<const char*> v;
+  v.resize(static_cast<size_t>(max_id) + 1);
+  for (size_t i = 0; i < base::ArraySize(kNetstatKeys); i++)
+    v[static_cast<size_t>(kNetstatKeys[i].id)] = kNetstatKeys[i].str;
+  return v;
+}
+
 }  // namespace perfetto
 
 #endif  // INCLUDE_PERFETTO_EXT_TRACED_SYS_STATS_COUNTERS_H_
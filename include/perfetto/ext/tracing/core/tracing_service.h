@@ -306,7 +306,10 @@ struct PERFETTO_EXPORT_COMPONENT TracingServiceInitOpts {
   // a vector of TracePackets and replaces the packets in the vector with
   // compressed ones.
   using CompressorFn = void (*)(std::vector<TracePacket>*);
+  // Used for COMPRESSION_TYPE_DEFLATE.
   CompressorFn compressor_fn = nullptr;
+  // Used for COMPRESSION_TYPE_ZSTD.
+  CompressorFn zstd_compressor_fn = nullptr;
 
   // Whether the relay endpoint is enabled on producer transport(s).
   bool enable_relay_endpoint = false;
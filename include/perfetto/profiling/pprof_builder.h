@@ -65,6 +65,26 @@ bool TraceToPprof(trace_processor::TraceProcessor* tp,
                   uint64_t pid = 0,
                   const std::vector<uint64_t>& timestamps = {});
 
+// The native heap profile dump that TraceToHeapDiffPprof compares the other
+// dumps with.
+struct HeapDiffBaseline {
+  // Process of the baseline dump. If 0, every dump is compared with the dump
+  // of its own process.
+  uint64_t pid = 0;
+  // Timestamp of the baseline dump.
+  uint64_t ts = 0;
+};
+
+// Same as TraceToPprof with ConversionMode::kHeapProfile, but the values of
+// the samples are the difference between each dump and |baseline|, like the
+// profiles produced by `pprof -diff_base`. The values can be negative.
+bool TraceToHeapDiffPprof(trace_processor::TraceProcessor* tp,
+                          std::vector<SerializedProfile>* output,
+                          const HeapDiffBaseline& baseline,
+                          uint64_t flags = 0,
+                          uint64_t pid = 0,
+                          const std::vector<uint64_t>& timestamps = {});
+
 }  // namespace trace_to_text
 }  // namespace perfetto
 
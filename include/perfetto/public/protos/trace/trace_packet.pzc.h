@@ -34,6 +34,7 @@ PERFETTO_PB_MSG_DECL(perfetto_protos_AndroidSystemProperty);
 PERFETTO_PB_MSG_DECL(perfetto_protos_AppWakelockBundle);
 PERFETTO_PB_MSG_DECL(perfetto_protos_BatteryCounters);
 PERFETTO_PB_MSG_DECL(perfetto_protos_BluetoothTraceEvent);
+PERFETTO_PB_MSG_DECL(perfetto_protos_CgroupStats);
 PERFETTO_PB_MSG_DECL(perfetto_protos_ChromeBenchmarkMetadata);
 PERFETTO_PB_MSG_DECL(perfetto_protos_ChromeEventBundle);
 PERFETTO_PB_MSG_DECL(perfetto_protos_ChromeMetadataPacket);
@@ -88,6 +89,7 @@ PERFETTO_PB_MSG_DECL(perfetto_protos_StreamingAllocation);
 PERFETTO_PB_MSG_DECL(perfetto_protos_StreamingFree);
 PERFETTO_PB_MSG_DECL(perfetto_protos_StreamingProfilePacket);
 PERFETTO_PB_MSG_DECL(perfetto_protos_SysStats);
+PERFETTO_PB_MSG_DECL(perfetto_protos_SyscallLatency);
 PERFETTO_PB_MSG_DECL(perfetto_protos_SystemInfo);
 PERFETTO_PB_MSG_DECL(perfetto_protos_TestEvent);
 PERFETTO_PB_MSG_DECL(perfetto_protos_ThreadDescriptor);
@@ -558,6 +560,16 @@ PERFETTO_PB_FIELD(perfetto_protos_TracePacket,
                   perfetto_protos_EvdevEvent,
                   evdev_event,
                   121);
+PERFETTO_PB_FIELD(perfetto_protos_TracePacket,
+                  MSG,
+                  perfetto_protos_CgroupStats,
+                  cgroup_stats,
+                  122);
+PERFETTO_PB_FIELD(perfetto_protos_TracePacket,
+                  MSG,
+                  perfetto_protos_SyscallLatency,
+                  syscall_latency,
+                  123);
 PERFETTO_PB_FIELD(perfetto_protos_TracePacket,
                   MSG,
                   perfetto_protos_TestEvent,
@@ -17,6 +17,7 @@
 #ifndef INCLUDE_PERFETTO_TRACE_PROCESSOR_BASIC_TYPES_H_
 #define INCLUDE_PERFETTO_TRACE_PROCESSOR_BASIC_TYPES_H_
 
+#include <atomic>
 #include <cassert>
 #include <cstdarg>
 #include <cstddef>
@@ -204,6 +205,49 @@ struct PERFETTO_EXPORT_COMPONENT Config {
   // When set to true, trace processor will perform additional runtime checks
   // to catch additional classes of SQL errors.
   bool enable_extra_checks = false;
+
+  // When non-empty, the tables created by CREATE PERFETTO TABLE statements in
+  // the stdlib modules are persisted in this directory and reused, instead of
+  // being recomputed, when the same trace (identified by the SHA-256 of its
+  // contents) is loaded again with the same version of trace processor.
+  //
+  // Note: the tables created outside of modules or by other packages are
+  // never cached, and the cache is disabled entirely if a stdlib package is
+  // overridden, as their contents can depend on state which is not tracked.
+  std::string table_cache_dir;
+
+  // The maximum total size of the files in |table_cache_dir|. When this is
+  // exceeded, the least recently used tables are deleted.
+  uint64_t table_cache_max_size_bytes = 1024ull * 1024 * 1024;
+
+  // The default maximum wall time of a query, measured from the call to
+  // ExecuteQuery() until the returned Iterator is destroyed. Queries running
+  // for longer fail with an error. 0 means no limit. Can be overridden for
+  // a single query with QueryOptions.
+  uint64_t query_timeout_ms = 0;
+
+  // The default maximum memory which can be allocated by the tables
+  // materialized by a query (e.g. by CREATE PERFETTO TABLE statements,
+  // including the ones in the included stdlib modules). Queries exceeding it
+  // fail with an error. 0 means no limit. Can be overridden for a single query
+  // with QueryOptions.
+  uint64_t query_max_table_memory_bytes = 0;
+};
+
+// Options for a single call to TraceProcessor::ExecuteQuery.
+struct PERFETTO_EXPORT_COMPONENT QueryOptions {
+  // Maximum wall time of the query. 0 means the default in Config.
+  uint64_t timeout_ms = 0;
+
+  // Maximum memory allocated by the tables materialized by the query. 0 means
+  // the default in Config.
+  uint64_t max_table_memory_bytes = 0;
+
+  // If set, the query is cancelled as soon as possible after this becomes
+  // true. Unlike TraceProcessor::InterruptQuery, this cannot race with the
+  // start of the query, as the flag is checked before any statement is run.
+  // Must outlive the Iterator returned by ExecuteQuery.
+  const std::atomic<bool>* cancel_flag = nullptr;
 };
 
 // Represents a dynamically typed value returned by SQL.
@@ -61,6 +61,12 @@ class PERFETTO_EXPORT_COMPONENT TraceProcessor : public TraceProcessorStorage {
   // the returned iterator.
   virtual Iterator ExecuteQuery(const std::string& sql) = 0;
 
+  // Same as ExecuteQuery(sql) but with per-query |options|, e.g. to limit the
+  // time and memory the query can use or to cancel it from another thread.
+  // A query exceeding a limit stops with an error in Iterator::Status().
+  virtual Iterator ExecuteQuery(const std::string& sql,
+                                const QueryOptions& options) = 0;
+
   // Registers SQL files with the associated path under the package named
   // |sql_package.name|.
   //
@@ -148,7 +154,9 @@ class PERFETTO_EXPORT_COMPONENT TraceProcessor : public TraceProcessorStorage {
   virtual base::Status RegisterFileContent(const std::string& path,
                                            TraceBlobView content) = 0;
 
-  // Interrupts the current query. Typically used by Ctrl-C handler.
+  // Interrupts the current query, which then stops with an error. Does
+  // nothing if no query is running. Can be called from any thread and from
+  // signal handlers. Typically used by Ctrl-C handler.
   virtual void InterruptQuery() = 0;
 
   // Restores Trace Processor to its pristine state. It preserves the built-in
@@ -157,6 +165,20 @@ class PERFETTO_EXPORT_COMPONENT TraceProcessor : public TraceProcessorStorage {
   // NOTE: No Iterators can active when called.
   virtual size_t RestoreInitialTables() = 0;
 
+  // Writes a snapshot of the tables built from the trace to |path|. Restoring
+  // the snapshot with |RestoreSnapshot()| is much faster than parsing the
+  // trace again. Tables, views and functions created by queries are not part
+  // of the snapshot.
+  // NOTE: Must be called after |NotifyEndOfFile()|.
+  virtual base::Status SaveSnapshot(const std::string& path) = 0;
+
+  // Restores a snapshot written by |SaveSnapshot()|, possibly by another
+  // version of Trace Processor. This replaces parsing a trace and calling
+  // |NotifyEndOfFile()|: it must be called on an instance on which nothing
+  // has been parsed. If this fails, the instance should be discarded.
+  virtual base::Status RestoreSnapshot(const std::string& path) = 0;
+  virtual base::Status RestoreSnapshot(const uint8_t* data, size_t size) = 0;
+
   // Deprecated. Use |RegisterSqlPackage()| instead, which is identical in
   // functionality to |RegisterSqlModule()| and the only difference is in
   // the argument, which is directly translatable to |SqlPackage|.
@@ -32,6 +32,7 @@
 #include "perfetto/tracing/data_source.h"
 #include "perfetto/tracing/interceptor.h"
 #include "perfetto/tracing/platform.h"
+#include "perfetto/tracing/sdk_cpu_profiler.h"
 #include "perfetto/tracing/tracing.h"
 #include "perfetto/tracing/tracing_backend.h"
 #include "perfetto/tracing/track_event.h"
@@ -0,0 +1,51 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef INCLUDE_PERFETTO_TRACING_SDK_CPU_PROFILER_H_
+#define INCLUDE_PERFETTO_TRACING_SDK_CPU_PROFILER_H_
+
+#include "perfetto/base/export.h"
+
+namespace perfetto {
+
+// In-process CPU profiler. Once registered, tracing sessions can enable the
+// "sdk_cpu_profiler" data source (see SdkCpuProfilerConfig) to periodically
+// sample the callstacks of the threads of this process.
+//
+// The profiler uses per-thread CPU timers delivering SIGPROF, and unwinds the
+// stacks with frame pointers, so the code of interest must be built with
+// -fno-omit-frame-pointer. The samples are written as PerfSample packets
+// which can be analyzed (and symbolized offline) like the ones produced by
+// traced_perf.
+//
+// Caveats:
+// * Only supported on Linux and Android, on x86_64 and arm64. Register() is a
+//   no-op elsewhere.
+// * The profiler refuses to start if the process has installed its own SIGPROF
+//   handler. Once started, its handler stays installed for the lifetime of the
+//   process (it's inert while the data source is not active).
+// * Like any signal-based profiler, the sampling can cause blocking syscalls
+//   which can't be restarted (e.g. nanosleep) to fail with EINTR.
+class PERFETTO_EXPORT_COMPONENT SdkCpuProfiler {
+ public:
+  // Registers the "sdk_cpu_profiler" data source. Must be called after
+  // Tracing::Initialize().
+  static void Register();
+};
+
+}  // namespace perfetto
+
+#endif  // INCLUDE_PERFETTO_TRACING_SDK_CPU_PROFILER_H_
@@ -141,6 +141,7 @@ class TestCaseRunner:
   trace_descriptor_path: str
   colors: ColorFormatter
   override_sql_package_paths: List[str]
+  snapshot_roundtrip: bool = False
 
   def __output_to_text_proto(self, actual: str, out: BinaryProto) -> str:
     """Deserializes a binary proto and returns its text representation.
@@ -314,8 +315,43 @@ class TestCaseRunner:
           [line.decode('utf8') for line in tmp_perf_file.readlines()],
       )
 
+  def __save_snapshot(self, trace_path: str,
+                      snapshot_path: str) -> Optional[TestResult]:
+    """Saves a snapshot of |trace_path|, returning a result on failure."""
+    cmd = [
+        self.trace_processor_path,
+        '--analyze-trace-proto-content',
+        '--crop-track-events',
+        '--snapshot-out',
+        snapshot_path,
+        trace_path,
+    ]
+    if self.test.register_files_dir:
+      cmd += ['--register-files-dir', self.test.register_files_dir]
+    tp = subprocess.Popen(
+        cmd,
+        stdout=subprocess.PIPE,
+        stderr=subprocess.PIPE,
+        env=get_env(ROOT_DIR))
+    (_, stderr) = tp.communicate()
+    if tp.returncode == 0:
+      return None
+    return TestResult(self.test, trace_path, cmd, self.test.expected_str, '',
+                      stderr.decode('utf8'), tp.returncode, [])
+
   # Run a query based Diff Test.
   def __run_query_test(self, trace_path: str) -> TestResult:
+    # When testing snapshots, the query runs on the snapshot of the trace
+    # instead of on the trace, so both must produce the same output.
+    snapshot_path = None
+    if self.snapshot_roundtrip:
+      with tempfile.NamedTemporaryFile(delete=False) as tmp_snapshot_file:
+        snapshot_path = tmp_snapshot_file.name
+      failure = self.__save_snapshot(trace_path, snapshot_path)
+      if failure:
+        os.remove(snapshot_path)
+        return failure
+
     with tempfile.NamedTemporaryFile(delete=False) as tmp_perf_file:
       cmd = [
           self.trace_processor_path,
@@ -324,8 +360,11 @@ class TestCaseRunner:
           '--extra-checks',
           '--perf-file',
           tmp_perf_file.name,
-          trace_path,
       ]
+      if snapshot_path:
+        cmd += ['--snapshot-in', snapshot_path]
+      else:
+        cmd += [trace_path]
       if self.test.blueprint.is_query_file():
         cmd += ['-q', self.test.query_path]
       else:
@@ -348,6 +387,8 @@ class TestCaseRunner:
         actual = self.__output_to_text_proto(actual, self.test.blueprint.out)
 
       os.remove(tmp_perf_file.name)
+      if snapshot_path:
+        os.remove(snapshot_path)
 
       return TestResult(
           self.test,
@@ -534,6 +575,7 @@ class DiffTestsRunner:
       override_sql_package_paths: List[str],
       test_dir: str,
       quiet: bool,
+      snapshot_roundtrip: bool = False,
   ):
     self.tests = read_all_tests(name_filter, test_dir)
     self.trace_processor_path = trace_processor_path
@@ -554,6 +596,7 @@ class DiffTestsRunner:
               self.trace_descriptor_path,
               color_formatter,
               override_sql_package_paths,
+              snapshot_roundtrip,
           ))
 
   def run_all_tests(
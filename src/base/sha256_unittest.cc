@@ -0,0 +1,66 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "perfetto/ext/base/sha256.h"
+
+#include <string>
+
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto::base {
+namespace {
+
+// Test vectors from NIST FIPS 180-4 examples.
+TEST(Sha256Test, KnownDigests) {
+  EXPECT_EQ(Sha256Hasher::HexDigestOf(""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+  EXPECT_EQ(Sha256Hasher::HexDigestOf("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+  EXPECT_EQ(
+      Sha256Hasher::HexDigestOf(
+          "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+      "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+}
+
+TEST(Sha256Test, MillionA) {
+  Sha256Hasher hasher;
+  std::string chunk(1000, 'a');
+  for (int i = 0; i < 1000; ++i) {
+    hasher.Update(chunk);
+  }
+  EXPECT_EQ(hasher.HexDigest(),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
+}
+
+TEST(Sha256Test, SplitUpdates) {
+  std::string data(300, '\0');
+  for (size_t i = 0; i < data.size(); ++i) {
+    data[i] = static_cast<char>(i);
+  }
+  std::string expected = Sha256Hasher::HexDigestOf(data);
+  for (size_t split : {1u, 55u, 56u, 63u, 64u, 65u, 200u}) {
+    Sha256Hasher hasher;
+    hasher.Update(data.data(), split);
+    // digest() doesn't change the state of the hasher.
+    EXPECT_EQ(hasher.HexDigest(), Sha256Hasher::HexDigestOf(
+                                      std::string_view(data).substr(0, split)));
+    hasher.Update(data.data() + split, data.size() - split);
+    EXPECT_EQ(hasher.HexDigest(), expected) << split;
+  }
+}
+
+}  // namespace
+}  // namespace perfetto::base
@@ -0,0 +1,106 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_PROFILING_COMMON_FRAME_RECORD_H_
+#define SRC_PROFILING_COMMON_FRAME_RECORD_H_
+
+#include <stdint.h>
+
+// Validation and decoding of frame records, shared by the out-of-process
+// frame pointer unwinder of traced_perf (profiling::FramePointerUnwinder) and
+// the in-process one of the SDK CPU profiler (internal::WalkFramePointers).
+// Reading the memory of the record is left to the caller.
+//
+// Everything here is async-signal-safe.
+
+namespace perfetto {
+namespace profiling {
+
+enum class FrameRecordArch {
+  kUnsupported,
+  kArm64,
+  kX86_64,
+  kRiscv64,
+};
+
+// A frame record is the caller's frame pointer, followed by the return
+// address.
+constexpr uint64_t kFrameRecordSize = sizeof(uint64_t) * 2;
+
+// Frame records are expected to be at least this aligned. On x86-64 the SysV
+// ABI keeps the stack 16-byte aligned at calls, and the record is pushed right
+// after the return address, so the frame pointer is 16-byte aligned too.
+constexpr uint64_t FrameRecordAlignMask(FrameRecordArch arch) {
+  switch (arch) {
+    case FrameRecordArch::kArm64:
+      return 0x1;
+    case FrameRecordArch::kX86_64:
+      return 0xf;
+    case FrameRecordArch::kRiscv64:
+      return 0x7;
+    case FrameRecordArch::kUnsupported:
+      break;
+  }
+  return 0;
+}
+
+// Return addresses can carry a pointer authentication code in the top bits.
+constexpr uint64_t FrameRecordPcMask(FrameRecordArch arch) {
+  return arch == FrameRecordArch::kArm64 ? (uint64_t{1} << 48) - 1
+                                         : ~uint64_t{0};
+}
+
+// Returns whether a frame record at |fp| lies entirely within the stack
+// [sp, stack_end) and is properly aligned.
+inline bool IsFrameRecordValid(FrameRecordArch arch,
+                               uint64_t fp,
+                               uint64_t sp,
+                               uint64_t stack_end) {
+  // The frame record of a leaf function which only uses the red zone is at
+  // the top of the stack, hence fp == sp is allowed.
+  if (fp == 0 || fp < sp)
+    return false;
+
+  // Ensure there's space on the stack to read two values: the caller's
+  // frame pointer and the return address.
+  uint64_t result;
+  if (__builtin_add_overflow(fp, kFrameRecordSize, &result))
+    return false;
+
+  return result <= stack_end && (fp & FrameRecordAlignMask(arch)) == 0;
+}
+
+// Given the contents |record| of the frame record at |fp|, returns the frame
+// pointer of the calling stack frame, places the return address of the calling
+// stack frame into |next_pc| and its stack pointer into |next_sp|. Returns 0
+// if the record would overflow the address space.
+inline uint64_t DecodeFrameRecord(FrameRecordArch arch,
+                                  uint64_t fp,
+                                  const uint64_t (&record)[2],
+                                  uint64_t* next_pc,
+                                  uint64_t* next_sp) {
+  // Ensure there's not a stack overflow.
+  if (__builtin_add_overflow(fp, kFrameRecordSize, next_sp))
+    return 0;
+
+  *next_pc = record[1] & FrameRecordPcMask(arch);
+  return record[0];
+}
+
+}  // namespace profiling
+}  // namespace perfetto
+
+#endif  // SRC_PROFILING_COMMON_FRAME_RECORD_H_
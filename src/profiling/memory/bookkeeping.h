@@ -17,6 +17,7 @@
 #ifndef SRC_PROFILING_MEMORY_BOOKKEEPING_H_
 #define SRC_PROFILING_MEMORY_BOOKKEEPING_H_
 
+#include <algorithm>
 #include <map>
 #include <vector>
 
@@ -112,6 +113,11 @@ class HeapTracker {
 
     uint64_t allocs = 0;
 
+    // Bytes and number of the live allocations at this callstack that were
+    // unreachable in the last leak scan (see ClassifyUnreachable).
+    uint64_t unreachable = 0;
+    uint64_t unreachable_count = 0;
+
     union {
       CallstackMaxAllocations retain_max;
       CallstackTotalAllocations totals;
@@ -185,6 +191,24 @@ class HeapTracker {
     }
   }
 
+  // Sets the unreachable bytes and count of every callstack from the live
+  // allocations whose addresses are in |unreachable|, which must be sorted.
+  void ClassifyUnreachable(const std::vector<uint64_t>& unreachable) {
+    for (auto& p : callstack_allocations_) {
+      p.second.unreachable = 0;
+      p.second.unreachable_count = 0;
+    }
+    for (const auto& addr_and_allocation : allocations_) {
+      if (!std::binary_search(unreachable.begin(), unreachable.end(),
+                              addr_and_allocation.first)) {
+        continue;
+      }
+      const Allocation& alloc = addr_and_allocation.second;
+      alloc.callstack_allocations()->unreachable += alloc.sample_size;
+      alloc.callstack_allocations()->unreachable_count++;
+    }
+  }
+
   void RecordFree(uint64_t address,
                   uint64_t sequence_number,
                   uint64_t timestamp) {
@@ -65,6 +65,11 @@ void DumpState::WriteAllocation(const HeapTracker::CallstackAllocations& alloc,
 
     sample->set_alloc_count(alloc.value.totals.allocation_count);
     sample->set_free_count(alloc.value.totals.free_count);
+
+    if (alloc.unreachable_count) {
+      sample->set_self_unreachable(alloc.unreachable);
+      sample->set_unreachable_count(alloc.unreachable_count);
+    }
   }
 }
 
@@ -31,6 +31,7 @@
 #include <type_traits>
 
 #include "perfetto/base/build_config.h"
+#include "perfetto/base/compiler.h"
 #include "perfetto/base/logging.h"
 #include "perfetto/ext/base/string_utils.h"
 #include "perfetto/ext/base/unix_socket.h"
@@ -132,6 +133,14 @@ perfetto::profiling::Spinlock g_client_lock{};
 
 std::atomic<uint32_t> g_next_heap_id{kMinHeapId};
 
+#if defined(__GLIBC__)
+// Nesting depth of AHeapProfile_beginMmapBackedAllocation on this thread.
+// mmap is only intercepted by the glibc preload library. Elsewhere this is not
+// needed, and thread_local could allocate (and re-enter the hooks) on first
+// access.
+thread_local uint32_t g_mmap_backed_allocation_depth = 0;
+#endif
+
 // This can get called while holding the spinlock (in normal operation), or
 // without holding the spinlock (from OnSpinlockTimeout).
 void DisableAllHeaps() {
@@ -486,6 +495,34 @@ __attribute__((visibility("default"))) void AHeapProfile_reportFree(
     ShutdownLazy(client);
 }
 
+__attribute__((visibility("default"))) void
+AHeapProfile_beginMmapBackedAllocation(uint32_t heap_id) {
+#if defined(__GLIBC__)
+  if (heap_id != 0)
+    g_mmap_backed_allocation_depth++;
+#else
+  base::ignore_result(heap_id);
+#endif
+}
+
+__attribute__((visibility("default"))) void
+AHeapProfile_endMmapBackedAllocation(uint32_t heap_id) {
+#if defined(__GLIBC__)
+  if (heap_id != 0 && g_mmap_backed_allocation_depth > 0)
+    g_mmap_backed_allocation_depth--;
+#else
+  base::ignore_result(heap_id);
+#endif
+}
+
+bool AHeapProfile_isInMmapBackedAllocation() {
+#if defined(__GLIBC__)
+  return g_mmap_backed_allocation_depth > 0;
+#else
+  return false;
+#endif
+}
+
 __attribute__((visibility("default"))) bool AHeapProfile_initSession(
     void* (*malloc_fn)(size_t),
     void (*free_fn)(void*)) {
@@ -62,6 +62,12 @@ AHeapProfile_reportSample(uint32_t, uint64_t, uint64_t) {
 __attribute__((visibility("default"))) void AHeapProfile_reportFree(uint32_t,
                                                                     uint64_t) {}
 
+__attribute__((visibility("default"))) void
+AHeapProfile_beginMmapBackedAllocation(uint32_t) {}
+
+__attribute__((visibility("default"))) void
+AHeapProfile_endMmapBackedAllocation(uint32_t) {}
+
 __attribute__((visibility("default"))) bool AHeapProfile_initSession(
     void* (*)(size_t),
     void (*)(void*)) {
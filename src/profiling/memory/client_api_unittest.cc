@@ -14,8 +14,10 @@
  * limitations under the License.
  */
 
+#include "perfetto/ext/base/string_utils.h"
 #include "perfetto/ext/base/unix_socket.h"
 #include "perfetto/ext/base/unix_task_runner.h"
+#include "perfetto/ext/base/utils.h"
 #include "perfetto/heap_profile.h"
 #include "src/profiling/memory/heap_profile_internal.h"
 
@@ -23,9 +25,16 @@
 #include "src/profiling/memory/client_api_factory.h"
 #include "src/profiling/memory/shared_ring_buffer.h"
 #include "src/profiling/memory/wire_protocol.h"
+#include "src/profiling/memory/wrap_allocators.h"
 #include "test/gtest_and_gmock.h"
 
+#include <fcntl.h>
+#include <sys/mman.h>
+
+#include <cinttypes>
 #include <memory>
+#include <string>
+#include <vector>
 
 namespace perfetto {
 namespace profiling {
@@ -122,6 +131,200 @@ TEST(ClientApiTest, ClientAllHeaps) {
   EXPECT_FALSE(AHeapProfile_reportAllocation(heap_id, 1, 1));
 }
 
+// Starts a session in which every allocation of |heap_name| is reported.
+// Returns the shared memory buffer the allocations are written to.
+SharedRingBuffer StartSession(const char* heap_name) {
+  ClientConfiguration client_config{};
+  client_config.default_interval = 1;
+  strcpy(&client_config.heaps[0].name[0], heap_name);
+  client_config.heaps[0].interval = 1;
+  client_config.num_heaps = 1;
+  g_client_config = client_config;
+
+  AHeapProfile_initSession(malloc, free);
+  PERFETTO_CHECK(g_shmem_fd);
+  auto ringbuf = SharedRingBuffer::Attach(base::ScopedFile(dup(g_shmem_fd)));
+  g_shmem_fd = 0;
+  PERFETTO_CHECK(ringbuf);
+  return std::move(*ringbuf);
+}
+
+void StopSession(SharedRingBuffer* ringbuf, uint32_t heap_id) {
+  DisconnectGlobalServerSocket();
+  ringbuf->SetShuttingDown();
+  // Makes the client notice that the session is over.
+  EXPECT_FALSE(AHeapProfile_reportAllocation(heap_id, 1, 1));
+}
+
+std::string Alloc(const void* addr, uint64_t size) {
+  return base::StackString<64>("alloc %" PRIx64 " %" PRIu64,
+                               reinterpret_cast<uint64_t>(addr), size)
+      .ToStdString();
+}
+
+std::string Free(const void* addr) {
+  return base::StackString<64>("free %" PRIx64,
+                               reinterpret_cast<uint64_t>(addr))
+      .ToStdString();
+}
+
+// Returns the allocations and frees of |heap_id| written to |ringbuf| since
+// the last call, formatted as by Alloc() and Free().
+std::vector<std::string> ReadRecords(SharedRingBuffer* ringbuf,
+                                     uint32_t heap_id) {
+  std::vector<std::string> records;
+  for (;;) {
+    SharedRingBuffer::Buffer buf = ringbuf->BeginRead();
+    if (!buf)
+      break;
+    WireMessage msg;
+    PERFETTO_CHECK(ReceiveWireMessage(reinterpret_cast<char*>(buf.data),
+                                      buf.size, &msg));
+    if (msg.record_type == RecordType::Malloc &&
+        msg.alloc_header->heap_id == heap_id) {
+      records.push_back(
+          Alloc(reinterpret_cast<void*>(msg.alloc_header->alloc_address),
+                msg.alloc_header->alloc_size));
+    } else if (msg.record_type == RecordType::Free &&
+               msg.free_header->heap_id == heap_id) {
+      records.push_back(Free(reinterpret_cast<void*>(msg.free_header->addr)));
+    }
+    ringbuf->EndRead(std::move(buf));
+  }
+  return records;
+}
+
+void* FailingMremap(void*, size_t, size_t, int, void*) {
+  return MAP_FAILED;
+}
+
+void* g_remapped_address;
+void* FakeMremap(void*, size_t, size_t, int, void*) {
+  return g_remapped_address;
+}
+
+TEST(ClientApiTest, WrapMmapAndMunmap) {
+  uint32_t heap_id =
+      AHeapProfile_registerHeap(AHeapInfo_create("WrapMmapAndMunmap"));
+  SharedRingBuffer ringbuf = StartSession("WrapMmapAndMunmap");
+  const size_t page_size = base::GetSysPageSize();
+
+  void* anon = wrap_mmap(heap_id, syscall_mmap, nullptr, 4 * page_size,
+                         PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS,
+                         -1, 0);
+  ASSERT_NE(anon, MAP_FAILED);
+  // Only anonymous mappings are reported.
+  int fd = open("/dev/zero", O_RDONLY);
+  ASSERT_GE(fd, 0);
+  void* file = wrap_mmap(heap_id, syscall_mmap, nullptr, page_size, PROT_READ,
+                         MAP_PRIVATE, fd, 0);
+  close(fd);
+  ASSERT_NE(file, MAP_FAILED);
+  // Failed mappings are not reported.
+  ASSERT_EQ(wrap_mmap(heap_id, syscall_mmap, nullptr, page_size, PROT_READ,
+                      MAP_PRIVATE, /*fd=*/-1, 0),
+            MAP_FAILED);
+  EXPECT_THAT(ReadRecords(&ringbuf, heap_id),
+              testing::ElementsAre(Alloc(anon, 4 * page_size)));
+
+  auto* anon_bytes = static_cast<char*>(anon);
+  // Unmapping the end of the mapping doesn't free it: the free of an address
+  // which is not the start of a mapping is ignored by heapprofd.
+  ASSERT_EQ(wrap_munmap(heap_id, syscall_munmap, anon_bytes + 3 * page_size,
+                        page_size),
+            0);
+  // Unmapping the start of the mapping frees all of it.
+  ASSERT_EQ(wrap_munmap(heap_id, syscall_munmap, anon, page_size), 0);
+  ASSERT_EQ(wrap_munmap(heap_id, syscall_munmap, file, page_size), 0);
+  EXPECT_THAT(ReadRecords(&ringbuf, heap_id),
+              testing::ElementsAre(Free(anon_bytes + 3 * page_size),
+                                   Free(anon), Free(file)));
+  syscall_munmap(anon_bytes + page_size, 2 * page_size);
+
+  StopSession(&ringbuf, heap_id);
+}
+
+TEST(ClientApiTest, WrapMremap) {
+  uint32_t heap_id = AHeapProfile_registerHeap(AHeapInfo_create("WrapMremap"));
+  SharedRingBuffer ringbuf = StartSession("WrapMremap");
+  const size_t page_size = base::GetSysPageSize();
+
+  void* addr = wrap_mmap(heap_id, syscall_mmap, nullptr, page_size,
+                         PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS,
+                         -1, 0);
+  ASSERT_NE(addr, MAP_FAILED);
+  void* grown = wrap_mremap(heap_id, syscall_mremap, addr, page_size,
+                            16 * page_size, MREMAP_MAYMOVE, nullptr);
+  ASSERT_NE(grown, MAP_FAILED);
+  void* shrunk = wrap_mremap(heap_id, syscall_mremap, grown, 16 * page_size,
+                             2 * page_size, 0, nullptr);
+  ASSERT_EQ(shrunk, grown);
+  EXPECT_THAT(ReadRecords(&ringbuf, heap_id),
+              testing::ElementsAre(Alloc(addr, page_size), Free(addr),
+                                   Alloc(grown, 16 * page_size), Free(grown),
+                                   Alloc(grown, 2 * page_size)));
+
+  // If mremap fails, the mapping is still there.
+  ASSERT_EQ(wrap_mremap(heap_id, FailingMremap, shrunk, 2 * page_size,
+                        4 * page_size, MREMAP_MAYMOVE, nullptr),
+            MAP_FAILED);
+  EXPECT_THAT(ReadRecords(&ringbuf, heap_id),
+              testing::ElementsAre(Free(shrunk), Alloc(shrunk, 2 * page_size)));
+
+  // With MREMAP_DONTUNMAP the old range stays mapped.
+  g_remapped_address = reinterpret_cast<void*>(0x1000);
+  ASSERT_EQ(wrap_mremap(heap_id, FakeMremap, shrunk, 2 * page_size,
+                        2 * page_size, MREMAP_MAYMOVE | MREMAP_DONTUNMAP,
+                        nullptr),
+            g_remapped_address);
+  EXPECT_THAT(ReadRecords(&ringbuf, heap_id),
+              testing::ElementsAre(Alloc(g_remapped_address, 2 * page_size)));
+
+  syscall_munmap(shrunk, 2 * page_size);
+  StopSession(&ringbuf, heap_id);
+}
+
+#if defined(__GLIBC__)
+TEST(ClientApiTest, MmapBackedAllocation) {
+  uint32_t heap_id =
+      AHeapProfile_registerHeap(AHeapInfo_create("MmapBackedAllocation"));
+  EXPECT_FALSE(AHeapProfile_isInMmapBackedAllocation());
+  AHeapProfile_beginMmapBackedAllocation(heap_id);
+  AHeapProfile_beginMmapBackedAllocation(heap_id);
+  EXPECT_TRUE(AHeapProfile_isInMmapBackedAllocation());
+  AHeapProfile_endMmapBackedAllocation(heap_id);
+  EXPECT_TRUE(AHeapProfile_isInMmapBackedAllocation());
+  AHeapProfile_endMmapBackedAllocation(heap_id);
+  EXPECT_FALSE(AHeapProfile_isInMmapBackedAllocation());
+  // Unbalanced calls to end are ignored.
+  AHeapProfile_endMmapBackedAllocation(heap_id);
+  EXPECT_FALSE(AHeapProfile_isInMmapBackedAllocation());
+}
+
+TEST(ClientApiTest, WrapMmapInMmapBackedAllocation) {
+  uint32_t heap_id = AHeapProfile_registerHeap(
+      AHeapInfo_create("WrapMmapInMmapBackedAllocation"));
+  SharedRingBuffer ringbuf = StartSession("WrapMmapInMmapBackedAllocation");
+  const size_t page_size = base::GetSysPageSize();
+
+  // The mappings of an allocator which reports its sub-allocations itself
+  // are not reported.
+  AHeapProfile_beginMmapBackedAllocation(heap_id);
+  void* addr = wrap_mmap(heap_id, syscall_mmap, nullptr, page_size,
+                         PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS,
+                         -1, 0);
+  ASSERT_NE(addr, MAP_FAILED);
+  void* grown = wrap_mremap(heap_id, syscall_mremap, addr, page_size,
+                            2 * page_size, MREMAP_MAYMOVE, nullptr);
+  ASSERT_NE(grown, MAP_FAILED);
+  ASSERT_EQ(wrap_munmap(heap_id, syscall_munmap, grown, 2 * page_size), 0);
+  AHeapProfile_endMmapBackedAllocation(heap_id);
+  EXPECT_THAT(ReadRecords(&ringbuf, heap_id), testing::IsEmpty());
+
+  StopSession(&ringbuf, heap_id);
+}
+#endif
+
 }  // namespace
 
 }  // namespace profiling
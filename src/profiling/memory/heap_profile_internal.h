@@ -39,6 +39,11 @@ extern "C" {
 bool AHeapProfile_initSession(void* _Nullable (*_Nonnull malloc_fn)(size_t),
                               void (*_Nonnull free_fn)(void* _Nullable));
 
+// Returns whether the calling thread is between
+// AHeapProfile_beginMmapBackedAllocation and
+// AHeapProfile_endMmapBackedAllocation.
+bool AHeapProfile_isInMmapBackedAllocation();
+
 #ifdef __cplusplus
 }
 #endif
@@ -527,6 +527,62 @@ void RunCustomLifetime() {
   }
 }
 
+constexpr size_t kLeakedSize = 1234;
+constexpr size_t kKeptSize = 4321;
+void* g_kept_allocation = nullptr;
+
+void PERFETTO_NO_INLINE LeakAllocation(uint32_t heap_id) {
+  void* leaked = malloc(kLeakedSize);
+  if (!AHeapProfile_reportAllocation(
+          heap_id, reinterpret_cast<uint64_t>(leaked), kLeakedSize))
+    PERFETTO_FATAL("Expected allocation to be sampled.");
+}
+
+// Overwrites the stack used by LeakAllocation, so that no stale copy of the
+// leaked pointer keeps it reachable.
+void PERFETTO_NO_INLINE ClobberStack() {
+  volatile char buf[16384];
+  for (size_t i = 0; i < sizeof(buf); ++i)
+    buf[i] = 0;
+}
+
+void RunLeak() {
+  const char* a0 = getenv("HEAPPROFD_TESTING_RUN_LEAK");
+  if (a0 == nullptr)
+    return;
+
+  static std::atomic<bool> initialized{false};
+  static uint32_t heap_id =
+      AHeapProfile_registerHeap(AHeapInfo_setEnabledCallback(
+          AHeapInfo_create("test"),
+          [](void*, const AHeapProfileEnableCallbackInfo*) {
+            initialized = true;
+          },
+          nullptr));
+
+  ChildFinishHandshake();
+
+  // heapprofd_client needs malloc to see the signal.
+  while (!initialized)
+    AllocateAndFree(1);
+  // We call the callback before setting enabled=true on the heap, so we
+  // wait a bit for the assignment to happen.
+  usleep(100000);
+  g_kept_allocation = malloc(kKeptSize);
+  if (!AHeapProfile_reportAllocation(
+          heap_id, reinterpret_cast<uint64_t>(g_kept_allocation), kKeptSize))
+    PERFETTO_FATAL("Expected allocation to be sampled.");
+  LeakAllocation(heap_id);
+  ClobberStack();
+
+  // Wait around so we can verify it did't crash.
+  for (;;) {
+    // Call sleep, otherwise an empty busy loop is undefined behavior:
+    // http://en.cppreference.com/w/cpp/language/memory_model#Progress_guarantee
+    sleep(1);
+  }
+}
+
 void MainInitializer() {
   // *** TRICKY ***
   //
@@ -547,6 +603,7 @@ void MainInitializer() {
   RunReInit();
   RunCustomLifetime();
   RunAccurateSample();
+  RunLeak();
 }
 
 int PERFETTO_UNUSED initializer =
@@ -1173,6 +1230,61 @@ TEST_P(HeapprofdEndToEnd, AccurateDumpAtMaxCustom) {
   EXPECT_EQ(total_count, 2u);
 }
 
+// The system heapprofd on Android is not allowed to read the memory of other
+// processes.
+#if PERFETTO_BUILDFLAG(PERFETTO_OS_ANDROID)
+#define MAYBE_LeakDetection DISABLED_LeakDetection
+#else
+#define MAYBE_LeakDetection LeakDetection
+#endif
+
+TEST_P(HeapprofdEndToEnd, MAYBE_LeakDetection) {
+  if (allocator_mode() != AllocatorMode::kCustom)
+    GTEST_SKIP();
+
+  base::Subprocess child({"/proc/self/exe"});
+  child.args.posix_argv0_override_for_testing = "heapprofd_continuous_malloc";
+  child.args.env.push_back("HEAPPROFD_TESTING_RUN_LEAK=1");
+  StartAndWaitForHandshake(&child);
+
+  const uint64_t pid = static_cast<uint64_t>(child.pid());
+
+  TraceConfig trace_config = MakeTraceConfig([pid](HeapprofdConfig* cfg) {
+    cfg->set_sampling_interval_bytes(1);
+    cfg->add_pid(pid);
+    cfg->add_heaps("test");
+    cfg->set_leak_detection(true);
+  });
+
+  auto helper = Trace(trace_config);
+  WRITE_TRACE(helper->full_trace());
+  PrintStats(helper.get());
+  KillAssertRunning(&child);
+
+  ValidateOnlyPID(helper.get(), pid);
+
+  uint64_t total_alloc = 0;
+  uint64_t total_unreachable = 0;
+  uint64_t unreachable_count = 0;
+  for (const protos::gen::TracePacket& packet : helper->trace()) {
+    for (const auto& dump : packet.profile_packet().process_dumps()) {
+      for (const auto& sample : dump.samples()) {
+        total_alloc += sample.self_allocated();
+        total_unreachable += sample.self_unreachable();
+        unreachable_count += sample.unreachable_count();
+      }
+    }
+  }
+  EXPECT_EQ(total_alloc, kLeakedSize + kKeptSize);
+  EXPECT_EQ(total_unreachable, kLeakedSize);
+  EXPECT_EQ(unreachable_count, 1u);
+
+  auto it = helper->tp().ExecuteQuery(
+      "SELECT SUM(size) FROM heap_profile_unreachable_allocation");
+  ASSERT_TRUE(it.Next());
+  EXPECT_EQ(it.Get(0).AsLong(), static_cast<int64_t>(kLeakedSize));
+}
+
 TEST_P(HeapprofdEndToEnd, CustomLifetime) {
   if (allocator_mode() != AllocatorMode::kCustom)
     GTEST_SKIP();
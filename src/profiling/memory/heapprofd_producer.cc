@@ -16,6 +16,7 @@
 
 #include "src/profiling/memory/heapprofd_producer.h"
 
+#include <fcntl.h>
 #include <signal.h>
 #include <sys/stat.h>
 #include <sys/types.h>
@@ -44,6 +45,7 @@
 #include "protos/perfetto/trace/profiling/profile_packet.pbzero.h"
 #include "src/profiling/common/producer_support.h"
 #include "src/profiling/common/profiler_guardrails.h"
+#include "src/profiling/memory/leak_scanner.h"
 #include "src/profiling/memory/shared_ring_buffer.h"
 #include "src/profiling/memory/unwound_messages.h"
 #include "src/profiling/memory/wire_protocol.h"
@@ -380,6 +382,8 @@ void HeapprofdProducer::SetupDataSource(DataSourceInstanceID id,
     PERFETTO_ELOG("No point setting all and pid");
   if (heapprofd_config.all() && !heapprofd_config.process_cmdline().empty())
     PERFETTO_ELOG("No point setting all and process_cmdline");
+  if (heapprofd_config.leak_detection() && heapprofd_config.dump_at_max())
+    PERFETTO_ELOG("leak_detection is not supported with dump_at_max");
 
   if (ds_config.name() != kHeapprofdDataSource) {
     PERFETTO_DLOG("Invalid data source name.");
@@ -641,7 +645,12 @@ void HeapprofdProducer::DoDrainAndContinuousDump(DataSourceInstanceID id) {
   PERFETTO_DCHECK(data_source.pending_free_drains == 0);
 
   for (auto& [pid, process_state] : data_source.process_states) {
-    UnwinderForPID(pid).PostDrainFree(data_source.id, pid);
+    // The leak scan needs the allocations and frees that are still buffered
+    // to be recorded, otherwise freed allocations are reported as leaks.
+    if (data_source.config.leak_detection())
+      UnwinderForPID(pid).PostDrainRecords(data_source.id, pid);
+    else
+      UnwinderForPID(pid).PostDrainFree(data_source.id, pid);
     data_source.pending_free_drains++;
   }
 
@@ -654,6 +663,8 @@ void HeapprofdProducer::DoContinuousDump(DataSource* ds) {
     return;
   }
 
+  for (auto& [pid, process_state] : ds->process_states)
+    MaybeScanForLeaks(ds, pid, &process_state);
   DumpProcessesInDataSource(ds);
   auto id = ds->id;
   auto weak_producer = weak_factory_.GetWeakPtr();
@@ -706,6 +717,56 @@ void HeapprofdProducer::SetStats(
   }
 }
 
+void HeapprofdProducer::ScanForLeaks(pid_t pid, ProcessState* process_state) {
+  for (const auto& heap_id_and_heap_info : process_state->heap_infos) {
+    const ProcessState::HeapInfo& heap_info = heap_id_and_heap_info.second;
+    if (heap_info.sampling_interval != 1) {
+      PERFETTO_ELOG("Not scanning %d for leaks: heap %s is sampled.", pid,
+                    heap_info.heap_name.c_str());
+      return;
+    }
+  }
+
+  std::string proc_path = "/proc/" + std::to_string(pid);
+  base::ScopedFile mem_fd = base::OpenFile(proc_path + "/mem", O_RDONLY);
+  std::string maps;
+  if (!mem_fd || !base::ReadFile(proc_path + "/maps", &maps)) {
+    PERFETTO_PLOG("Not scanning %d for leaks: failed to read memory.", pid);
+    return;
+  }
+
+  LeakScanner scanner(std::move(mem_fd));
+  for (auto& heap_id_and_heap_info : process_state->heap_infos) {
+    heap_id_and_heap_info.second.heap_tracker.GetAllocations(
+        [&scanner](uint64_t addr, uint64_t, uint64_t alloc_size, uint64_t) {
+          scanner.AddAllocation(addr, alloc_size);
+        });
+  }
+  scanner.AddRootMappings(maps);
+
+  std::vector<uint64_t> unreachable;
+  {
+    ScopedProcessFreeze freeze(pid);
+    for (uint64_t value : freeze.register_values())
+      scanner.AddRootValue(value);
+    unreachable = scanner.FindUnreachable();
+  }
+  PERFETTO_DLOG("Scanned %" PRIu64 " bytes of %d, %zu unreachable allocations",
+                scanner.bytes_scanned(), pid, unreachable.size());
+
+  for (auto& heap_id_and_heap_info : process_state->heap_infos)
+    heap_id_and_heap_info.second.heap_tracker.ClassifyUnreachable(unreachable);
+}
+
+void HeapprofdProducer::MaybeScanForLeaks(DataSource* data_source,
+                                          pid_t pid,
+                                          ProcessState* process_state) {
+  if (data_source->config.leak_detection() &&
+      !data_source->config.dump_at_max() && !process_state->disconnected) {
+    ScanForLeaks(pid, process_state);
+  }
+}
+
 void HeapprofdProducer::DumpProcessState(DataSource* data_source,
                                          pid_t pid,
                                          ProcessState* process_state) {
@@ -1232,6 +1293,9 @@ void HeapprofdProducer::HandleSocketDisconnected(
   process_state.buffer_corrupted =
       stats.num_writes_corrupt > 0 || stats.num_reads_corrupt > 0;
 
+  // The unwinder has read out the buffer of the process before disconnecting
+  // it, so all its records have been handled.
+  MaybeScanForLeaks(&ds, pid, &process_state);
   DumpProcessState(&ds, pid, &process_state);
   ds.process_states.erase(pid);
   MaybeFinishDataSource(&ds);
@@ -263,6 +263,10 @@ class HeapprofdProducer : public Producer, public UnwindingWorker::Delegate {
   void FinishDataSourceFlush(FlushRequestID flush_id);
   void DumpProcessesInDataSource(DataSource* ds);
   void DumpProcessState(DataSource* ds, pid_t pid, ProcessState* process);
+  // Scans |process| for leaks if the data source asks for it. Must only be
+  // called after the records buffered by the process have been handled.
+  void MaybeScanForLeaks(DataSource* ds, pid_t pid, ProcessState* process);
+  void ScanForLeaks(pid_t pid, ProcessState* process);
   static void SetStats(protos::pbzero::ProfilePacket::ProcessStats* stats,
                        const ProcessState& process_state);
 
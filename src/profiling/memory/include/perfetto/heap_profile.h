@@ -185,6 +185,21 @@ bool AHeapProfile_reportSample(uint32_t heap_id,
 // change the output.
 void AHeapProfile_reportFree(uint32_t heap_id, uint64_t alloc_id);
 
+// Reports that the calling thread is about to map memory with mmap (or to
+// remap or unmap it) on behalf of the allocator of |heap_id|, which
+// sub-allocates from these mappings and reports the sub-allocations itself.
+//
+// Until the matching AHeapProfile_endMmapBackedAllocation, the mmap, mremap
+// and munmap calls of the thread are not reported to the "libc.mmap" heap, so
+// that the memory is not counted twice. Calls can be nested.
+//
+// This only has an effect when the mmap interception of the glibc preload
+// library is active, but it is always safe to call.
+void AHeapProfile_beginMmapBackedAllocation(uint32_t heap_id);
+
+// Ends the scope started by AHeapProfile_beginMmapBackedAllocation.
+void AHeapProfile_endMmapBackedAllocation(uint32_t heap_id);
+
 #ifdef __cplusplus
 }
 #endif
@@ -0,0 +1,242 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/profiling/memory/leak_scanner.h"
+
+#include <dirent.h>
+#include <errno.h>
+#include <elf.h>
+#include <inttypes.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/ptrace.h>
+#include <sys/uio.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include <algorithm>
+#include <optional>
+#include <string>
+
+#include "perfetto/base/logging.h"
+#include "perfetto/ext/base/string_splitter.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "perfetto/ext/base/utils.h"
+
+namespace perfetto {
+namespace profiling {
+namespace {
+
+constexpr uint64_t kPageSize = 4096;
+constexpr size_t kReadChunkSize = 64 * 1024;
+
+template <typename T, typename F>
+void ForEachWord(const void* data, size_t size, F fn) {
+  for (size_t i = 0; i + sizeof(T) <= size; i += sizeof(T)) {
+    T word;
+    memcpy(&word, static_cast<const char*>(data) + i, sizeof(T));
+    fn(word);
+  }
+}
+
+}  // namespace
+
+LeakScanner::LeakScanner(base::ScopedFile mem_fd)
+    : mem_fd_(std::move(mem_fd)), buf_(kReadChunkSize / sizeof(uint64_t)) {}
+
+void LeakScanner::AddAllocation(uint64_t address, uint64_t size) {
+  // Zero-sized allocations still have a unique address that can be referenced.
+  Allocation alloc{address, address + std::max<uint64_t>(size, 1), false};
+  if (!allocations_.empty() && allocations_.back().begin > address)
+    allocations_sorted_ = false;
+  allocations_.push_back(alloc);
+}
+
+void LeakScanner::SortAllocations() {
+  if (allocations_sorted_)
+    return;
+  std::sort(allocations_.begin(), allocations_.end(),
+            [](const Allocation& a, const Allocation& b) {
+              return a.begin < b.begin;
+            });
+  allocations_sorted_ = true;
+}
+
+void LeakScanner::AddRootMappings(const std::string& proc_maps) {
+  SortAllocations();
+  uint64_t max_end = 0;
+  for (base::StringSplitter lines(proc_maps, '\n'); lines.Next();) {
+    uint64_t begin;
+    uint64_t end;
+    char perms[5] = {};
+    int name_pos = 0;
+    if (sscanf(lines.cur_token(),
+               "%" SCNx64 "-%" SCNx64 " %4s %*s %*s %*s %n",
+               &begin, &end, perms, &name_pos) != 3) {
+      PERFETTO_DLOG("Failed to parse maps line: %s", lines.cur_token());
+      continue;
+    }
+    max_end = std::max(max_end, end);
+    // Only private writable mappings can contain pointers to allocations
+    // that are not themselves allocations. This excludes the code, the
+    // read-only data and the shared memory buffer of the heapprofd client.
+    if (perms[0] != 'r' || perms[1] != 'w' || perms[3] != 'p')
+      continue;
+    // Reading device memory can have side effects.
+    const char* name = lines.cur_token() + name_pos;
+    if (base::StartsWith(name, "/dev/"))
+      continue;
+    // Mappings that contain allocations are the heaps themselves (e.g. the
+    // malloc arenas), whose reachable parts get scanned as allocations.
+    auto it = std::lower_bound(allocations_.begin(), allocations_.end(), begin,
+                               [](const Allocation& a, uint64_t addr) {
+                                 return a.begin < addr;
+                               });
+    if (it != allocations_.end() && it->begin < end)
+      continue;
+    AddRootRange({begin, end});
+  }
+  // A 64-bit process always has mappings above 4GiB (e.g. the stack), so this
+  // is a 32-bit process, whose pointers are 4 bytes.
+  if (max_end != 0 && max_end <= (uint64_t{1} << 32))
+    pointer_size_ = 4;
+}
+
+LeakScanner::Allocation* LeakScanner::FindAllocation(uint64_t pointer) {
+  auto it = std::upper_bound(allocations_.begin(), allocations_.end(), pointer,
+                             [](uint64_t addr, const Allocation& a) {
+                               return addr < a.begin;
+                             });
+  if (it == allocations_.begin())
+    return nullptr;
+  --it;
+  if (pointer >= it->end)
+    return nullptr;
+  return &*it;
+}
+
+void LeakScanner::Mark(uint64_t pointer) {
+  Allocation* alloc = FindAllocation(pointer);
+  if (!alloc || alloc->reachable)
+    return;
+  alloc->reachable = true;
+  worklist_.push_back(alloc);
+}
+
+void LeakScanner::ScanRange(const Range& range) {
+  auto mark = [this](uint64_t word) { Mark(word); };
+  uint64_t addr = range.begin;
+  while (addr < range.end) {
+    size_t size = static_cast<size_t>(
+        std::min<uint64_t>(range.end - addr, kReadChunkSize));
+    ssize_t rd = PERFETTO_EINTR(
+        pread64(*mem_fd_, buf_.data(), size, static_cast<off64_t>(addr)));
+    if (rd <= 0) {
+      // Skip the page that could not be read, e.g. a guard page.
+      addr = (addr + kPageSize) & ~(kPageSize - 1);
+      continue;
+    }
+    size_t read_size = static_cast<size_t>(rd);
+    bytes_scanned_ += read_size;
+    if (pointer_size_ == 4)
+      ForEachWord<uint32_t>(buf_.data(), read_size, mark);
+    else
+      ForEachWord<uint64_t>(buf_.data(), read_size, mark);
+    addr += read_size;
+  }
+}
+
+std::vector<uint64_t> LeakScanner::FindUnreachable() {
+  SortAllocations();
+  for (Allocation& alloc : allocations_)
+    alloc.reachable = false;
+
+  for (uint64_t value : root_values_)
+    Mark(value);
+  for (const Range& range : root_ranges_)
+    ScanRange(range);
+  while (!worklist_.empty()) {
+    Allocation* alloc = worklist_.back();
+    worklist_.pop_back();
+    ScanRange({alloc->begin, alloc->end});
+  }
+
+  std::vector<uint64_t> unreachable;
+  for (const Allocation& alloc : allocations_) {
+    if (!alloc.reachable)
+      unreachable.push_back(alloc.begin);
+  }
+  return unreachable;
+}
+
+ScopedProcessFreeze::ScopedProcessFreeze(pid_t pid) {
+  std::string task_path = "/proc/" + std::to_string(pid) + "/task";
+  base::ScopedDir task_dir(opendir(task_path.c_str()));
+  if (!task_dir) {
+    PERFETTO_PLOG("Failed to open %s", task_path.c_str());
+    return;
+  }
+  while (struct dirent* entry = readdir(*task_dir)) {
+    std::optional<int32_t> tid = base::CStringToInt32(entry->d_name);
+    if (!tid)
+      continue;
+    if (ptrace(PTRACE_SEIZE, *tid, nullptr, nullptr) == -1) {
+      // Threads can exit while we are iterating. Any other failure means that
+      // we are not permitted to trace the process.
+      if (errno == ESRCH)
+        continue;
+      PERFETTO_PLOG("Failed to stop %d, scanning it while running.", pid);
+      Detach();
+      return;
+    }
+    tids_.push_back(*tid);
+    if (ptrace(PTRACE_INTERRUPT, *tid, nullptr, nullptr) == -1)
+      continue;
+    int status;
+    if (PERFETTO_EINTR(waitpid(*tid, &status, __WALL)) != *tid)
+      continue;
+
+    uint64_t regs[128];
+    struct iovec iov {
+      regs, sizeof(regs)
+    };
+    if (ptrace(PTRACE_GETREGSET, *tid, reinterpret_cast<void*>(NT_PRSTATUS),
+               &iov) == -1) {
+      continue;
+    }
+    // The registers of a 32-bit process are 4 bytes each, so consider each
+    // half of every 8 bytes as well.
+    for (size_t i = 0; i < iov.iov_len / sizeof(uint64_t); ++i) {
+      register_values_.push_back(regs[i]);
+      register_values_.push_back(regs[i] & 0xffffffff);
+      register_values_.push_back(regs[i] >> 32);
+    }
+  }
+}
+
+ScopedProcessFreeze::~ScopedProcessFreeze() {
+  Detach();
+}
+
+void ScopedProcessFreeze::Detach() {
+  for (pid_t tid : tids_)
+    ptrace(PTRACE_DETACH, tid, nullptr, nullptr);
+  tids_.clear();
+}
+
+}  // namespace profiling
+}  // namespace perfetto
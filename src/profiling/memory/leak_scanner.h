@@ -0,0 +1,125 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_PROFILING_MEMORY_LEAK_SCANNER_H_
+#define SRC_PROFILING_MEMORY_LEAK_SCANNER_H_
+
+#include <sys/types.h>
+
+#include <cinttypes>
+#include <string>
+#include <vector>
+
+#include "perfetto/ext/base/scoped_file.h"
+
+namespace perfetto {
+namespace profiling {
+
+// Conservatively finds the allocations of a process that are not referenced,
+// directly or through other allocations, from any root. Every aligned
+// pointer-sized word of the roots and of the reachable allocations is treated
+// as a potential pointer, including pointers into the middle of an
+// allocation.
+//
+// The memory is read through |mem_fd|, usually /proc/pid/mem. If the process
+// is not stopped during the scan (see ScopedProcessFreeze), the result is
+// approximate.
+class LeakScanner {
+ public:
+  struct Range {
+    uint64_t begin;
+    uint64_t end;
+
+    bool operator==(const Range& other) const {
+      return begin == other.begin && end == other.end;
+    }
+  };
+
+  explicit LeakScanner(base::ScopedFile mem_fd);
+
+  // Adds a range of memory that is scanned for pointers, regardless of
+  // whether it is reachable.
+  void AddRootRange(Range range) { root_ranges_.push_back(range); }
+
+  // Adds a value that is treated as a pointer, e.g. the value of a register.
+  void AddRootValue(uint64_t value) { root_values_.push_back(value); }
+
+  void AddAllocation(uint64_t address, uint64_t size);
+
+  // Adds the private writable mappings of |proc_maps| (the content of
+  // /proc/pid/maps) that do not contain any of the allocations as roots. These
+  // are the globals, the stacks and the thread local storage. Must be called
+  // after all allocations have been added.
+  void AddRootMappings(const std::string& proc_maps);
+
+  // Returns the sorted addresses of the allocations that are not reachable
+  // from the roots.
+  std::vector<uint64_t> FindUnreachable();
+
+  uint64_t bytes_scanned() const { return bytes_scanned_; }
+  const std::vector<Range>& root_ranges() const { return root_ranges_; }
+
+ private:
+  struct Allocation {
+    uint64_t begin;
+    uint64_t end;
+    bool reachable;
+  };
+
+  void SortAllocations();
+  Allocation* FindAllocation(uint64_t pointer);
+  void Mark(uint64_t pointer);
+  void ScanRange(const Range& range);
+
+  base::ScopedFile mem_fd_;
+  std::vector<Range> root_ranges_;
+  std::vector<uint64_t> root_values_;
+  std::vector<Allocation> allocations_;
+  bool allocations_sorted_ = true;
+  std::vector<Allocation*> worklist_;
+  std::vector<uint64_t> buf_;
+  size_t pointer_size_ = sizeof(uint64_t);
+  uint64_t bytes_scanned_ = 0;
+};
+
+// Stops all threads of a process with ptrace for the lifetime of this object,
+// and collects the values of their general purpose registers, so they can be
+// used as roots of a LeakScanner. This is not permitted in all environments,
+// in which case frozen() returns false and the process keeps running.
+class ScopedProcessFreeze {
+ public:
+  explicit ScopedProcessFreeze(pid_t pid);
+  ~ScopedProcessFreeze();
+
+  ScopedProcessFreeze(const ScopedProcessFreeze&) = delete;
+  ScopedProcessFreeze& operator=(const ScopedProcessFreeze&) = delete;
+
+  bool frozen() const { return !tids_.empty(); }
+  const std::vector<uint64_t>& register_values() const {
+    return register_values_;
+  }
+
+ private:
+  void Detach();
+
+  std::vector<pid_t> tids_;
+  std::vector<uint64_t> register_values_;
+};
+
+}  // namespace profiling
+}  // namespace perfetto
+
+#endif  // SRC_PROFILING_MEMORY_LEAK_SCANNER_H_
@@ -0,0 +1,104 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/profiling/memory/leak_scanner.h"
+
+#include <fcntl.h>
+
+#include <algorithm>
+#include <memory>
+
+#include "perfetto/ext/base/file_utils.h"
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto {
+namespace profiling {
+namespace {
+
+using ::testing::ElementsAre;
+using ::testing::IsEmpty;
+
+using Range = LeakScanner::Range;
+
+uint64_t Addr(const void* ptr) {
+  return reinterpret_cast<uint64_t>(ptr);
+}
+
+LeakScanner CreateSelfScanner() {
+  base::ScopedFile fd = base::OpenFile("/proc/self/mem", O_RDONLY);
+  PERFETTO_CHECK(fd);
+  return LeakScanner(std::move(fd));
+}
+
+// The allocations are kept alive by the test itself, so they can be read
+// through /proc/self/mem, but only the explicit roots are scanned.
+TEST(LeakScannerTest, FindUnreachable) {
+  std::unique_ptr<uintptr_t[]> root(new uintptr_t[4]());
+  std::unique_ptr<uintptr_t[]> a(new uintptr_t[8]());
+  std::unique_ptr<uintptr_t[]> b(new uintptr_t[8]());
+  std::unique_ptr<uintptr_t[]> c(new uintptr_t[8]());
+  std::unique_ptr<uintptr_t[]> d(new uintptr_t[8]());
+
+  // root -> a (interior pointer) -> b. c and d only reference each other.
+  root[1] = reinterpret_cast<uintptr_t>(&a[3]);
+  a[5] = reinterpret_cast<uintptr_t>(b.get());
+  c[0] = reinterpret_cast<uintptr_t>(d.get());
+  d[0] = reinterpret_cast<uintptr_t>(c.get());
+
+  LeakScanner scanner = CreateSelfScanner();
+  scanner.AddRootRange({Addr(root.get()), Addr(root.get() + 4)});
+  for (const auto* alloc : {d.get(), c.get(), b.get(), a.get()})
+    scanner.AddAllocation(Addr(alloc), 8 * sizeof(uintptr_t));
+
+  std::vector<uint64_t> expected = {Addr(c.get()), Addr(d.get())};
+  std::sort(expected.begin(), expected.end());
+  EXPECT_EQ(scanner.FindUnreachable(), expected);
+  EXPECT_GE(scanner.bytes_scanned(), 4 * sizeof(uintptr_t));
+}
+
+TEST(LeakScannerTest, RootValue) {
+  std::unique_ptr<uintptr_t[]> a(new uintptr_t[8]());
+  std::unique_ptr<uintptr_t[]> b(new uintptr_t[8]());
+  a[0] = reinterpret_cast<uintptr_t>(b.get());
+
+  LeakScanner scanner = CreateSelfScanner();
+  scanner.AddAllocation(Addr(a.get()), 8 * sizeof(uintptr_t));
+  scanner.AddAllocation(Addr(b.get()), 8 * sizeof(uintptr_t));
+  EXPECT_EQ(scanner.FindUnreachable().size(), 2u);
+
+  scanner.AddRootValue(Addr(a.get()));
+  EXPECT_THAT(scanner.FindUnreachable(), IsEmpty());
+}
+
+TEST(LeakScannerTest, RootMappings) {
+  LeakScanner scanner(base::ScopedFile{});
+  scanner.AddAllocation(0x1010, 16);
+  scanner.AddRootMappings(
+      "00001000-00002000 rw-p 00000000 00:00 0          [heap]\n"
+      "00003000-00004000 rw-p 00002000 fd:01 1234       /system/lib/libc.so\n"
+      "00005000-00006000 r--p 00000000 fd:01 1234       /system/lib/libc.so\n"
+      "00007000-00008000 rw-s 00000000 00:01 5678       /memfd:heapprofd\n"
+      "00009000-0000a000 rw-p 00000000 00:06 910        /dev/kgsl-3d0\n"
+      "0000b000-0000c000 rw-p 00000000 00:00 0\n"
+      "7ffd0000-7ffd1000 rw-p 00000000 00:00 0          [stack]\n");
+  EXPECT_THAT(scanner.root_ranges(),
+              ElementsAre(Range{0x3000, 0x4000}, Range{0xb000, 0xc000},
+                          Range{0x7ffd0000, 0x7ffd1000}));
+}
+
+}  // namespace
+}  // namespace profiling
+}  // namespace perfetto
@@ -14,7 +14,9 @@
  * limitations under the License.
  */
 
+#include <stdarg.h>
 #include <malloc.h>
+#include <sys/mman.h>
 #include <unistd.h>
 
 #include "perfetto/base/logging.h"
@@ -27,6 +29,8 @@ namespace {
 #pragma GCC diagnostic push
 #pragma GCC diagnostic ignored "-Wglobal-constructors"
 uint32_t g_heap_id = AHeapProfile_registerHeap(AHeapInfo_create("libc.malloc"));
+uint32_t g_mmap_heap_id =
+    AHeapProfile_registerHeap(AHeapInfo_create("libc.mmap"));
 #pragma GCC diagnostic pop
 
 bool IsPowerOfTwo(size_t v) {
@@ -178,5 +182,70 @@ void* reallocarray(void* ptr, size_t nmemb, size_t size) {
                                                 ptr, nmemb, size);
 }
 
+// Calls to mmap from within glibc (including the ones malloc uses to get
+// memory from the kernel) do not go through these, so memory is not counted
+// both as libc.malloc and libc.mmap.
+// With _FILE_OFFSET_BITS=64, the headers redirect mmap to mmap64.
+#if !defined(__USE_FILE_OFFSET64)
+void* mmap(void* addr, size_t length, int prot, int flags, int fd, off_t off) {
+  if (PERFETTO_UNLIKELY(ScopedReentrancyPreventer::is_inside())) {
+    return perfetto::profiling::syscall_mmap(addr, length, prot, flags, fd,
+                                             off);
+  }
+  ScopedReentrancyPreventer p;
+
+  return perfetto::profiling::wrap_mmap(g_mmap_heap_id,
+                                        perfetto::profiling::syscall_mmap, addr,
+                                        length, prot, flags, fd, off);
+}
+#endif
+
+void* mmap64(void* addr,
+             size_t length,
+             int prot,
+             int flags,
+             int fd,
+             off64_t off) {
+  if (PERFETTO_UNLIKELY(ScopedReentrancyPreventer::is_inside())) {
+    return perfetto::profiling::syscall_mmap(addr, length, prot, flags, fd,
+                                             off);
+  }
+  ScopedReentrancyPreventer p;
+
+  return perfetto::profiling::wrap_mmap(g_mmap_heap_id,
+                                        perfetto::profiling::syscall_mmap, addr,
+                                        length, prot, flags, fd, off);
+}
+
+int munmap(void* addr, size_t length) {
+  if (PERFETTO_UNLIKELY(ScopedReentrancyPreventer::is_inside())) {
+    return perfetto::profiling::syscall_munmap(addr, length);
+  }
+  ScopedReentrancyPreventer p;
+
+  return perfetto::profiling::wrap_munmap(
+      g_mmap_heap_id, perfetto::profiling::syscall_munmap, addr, length);
+}
+
+void* mremap(void* old_address, size_t old_size, size_t new_size, int flags,
+             ...) {
+  void* new_address = nullptr;
+  if (flags & MREMAP_FIXED) {
+    va_list ap;
+    va_start(ap, flags);
+    new_address = va_arg(ap, void*);
+    va_end(ap);
+  }
+  if (PERFETTO_UNLIKELY(ScopedReentrancyPreventer::is_inside())) {
+    return perfetto::profiling::syscall_mremap(old_address, old_size,
+                                               new_size, flags, new_address);
+  }
+  ScopedReentrancyPreventer p;
+
+  return perfetto::profiling::wrap_mremap(
+      g_mmap_heap_id, perfetto::profiling::syscall_mremap, old_address,
+      old_size, new_size, flags, new_address);
+}
+
 #pragma GCC visibility pop
 }
@@ -490,6 +490,20 @@ void UnwindingWorker::HandleDrainFree(DataSourceInstanceID ds_id, pid_t pid) {
   delegate_->PostDrainDone(this, ds_id);
 }
 
+void UnwindingWorker::HandleDrainRecords(DataSourceInstanceID ds_id,
+                                         pid_t pid) {
+  auto it = client_data_.find(pid);
+  // If the process is disconnecting, DrainJob is already reading out the rest
+  // of its buffer.
+  if (it != client_data_.end() && it->second.drain_bytes == 0) {
+    ClientData& client_data = it->second;
+    while (ReadAndUnwindBatch(&client_data).status ==
+           ReadAndUnwindBatchResult::Status::kHasMore) {
+    }
+  }
+  HandleDrainFree(ds_id, pid);
+}
+
 void UnwindingWorker::PostDisconnectSocket(pid_t pid) {
   // We do not need to use a WeakPtr here because the task runner will not
   // outlive its UnwindingWorker.
@@ -516,6 +530,13 @@ void UnwindingWorker::PostDrainFree(DataSourceInstanceID ds_id, pid_t pid) {
       [this, ds_id, pid] { HandleDrainFree(ds_id, pid); });
 }
 
+void UnwindingWorker::PostDrainRecords(DataSourceInstanceID ds_id, pid_t pid) {
+  // We do not need to use a WeakPtr here because the task runner will not
+  // outlive its UnwindingWorker.
+  thread_task_runner_.get()->PostTask(
+      [this, ds_id, pid] { HandleDrainRecords(ds_id, pid); });
+}
+
 void UnwindingWorker::HandleDisconnectSocket(pid_t pid) {
   auto it = client_data_.find(pid);
   if (it == client_data_.end()) {
@@ -94,6 +94,9 @@ class UnwindingWorker : public base::UnixSocket::EventListener {
   void PostPurgeProcess(pid_t pid);
   void PostHandoffSocket(HandoffData);
   void PostDrainFree(DataSourceInstanceID, pid_t pid);
+  // Like PostDrainFree, but first reads and unwinds the records that are
+  // already in the shared memory buffer of the process.
+  void PostDrainRecords(DataSourceInstanceID, pid_t pid);
   void ReturnAllocRecord(std::unique_ptr<AllocRecord> record) {
     alloc_record_arena_.ReturnAllocRecord(std::move(record));
   }
@@ -132,6 +135,7 @@ class UnwindingWorker : public base::UnixSocket::EventListener {
   void HandleHandoffSocket(HandoffData data);
   void HandleDisconnectSocket(pid_t pid);
   void HandleDrainFree(DataSourceInstanceID, pid_t);
+  void HandleDrainRecords(DataSourceInstanceID, pid_t);
   void RemoveClientData(
       std::map<pid_t, ClientData>::iterator client_data_iterator);
   void FinishDisconnect(
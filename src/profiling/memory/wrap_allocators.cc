@@ -14,12 +14,17 @@
  * limitations under the License.
  */
 
+#include <errno.h>
 #include <stdlib.h>
+#include <sys/mman.h>
+#include <sys/syscall.h>
+#include <unistd.h>
 
 #include <cinttypes>
 
 #include "perfetto/ext/base/utils.h"
 #include "perfetto/heap_profile.h"
+#include "src/profiling/memory/heap_profile_internal.h"
 #include "src/profiling/memory/wrap_allocators.h"
 
 namespace perfetto {
@@ -134,5 +139,101 @@ void* wrap_reallocarray(uint32_t heap_id,
   return addr;
 }
 
+void* wrap_mmap(uint32_t heap_id,
+                void* (*fn)(void*, size_t, int, int, int, off64_t),
+                void* addr,
+                size_t length,
+                int prot,
+                int flags,
+                int fd,
+                off64_t offset) {
+  void* res = fn(addr, length, prot, flags, fd, offset);
+  if (res != MAP_FAILED && (flags & MAP_ANONYMOUS) &&
+      !AHeapProfile_isInMmapBackedAllocation()) {
+    AHeapProfile_reportAllocation(heap_id, reinterpret_cast<uint64_t>(res),
+                                  length);
+  }
+  return res;
+}
+
+// As with free, we record the deallocation before unmapping, so the address
+// can't be mapped again before we've processed it.
+int wrap_munmap(uint32_t heap_id,
+                int (*fn)(void*, size_t),
+                void* addr,
+                size_t length) {
+  if (!AHeapProfile_isInMmapBackedAllocation())
+    AHeapProfile_reportFree(heap_id, reinterpret_cast<uint64_t>(addr));
+  return fn(addr, length);
+}
+
+void* wrap_mremap(uint32_t heap_id,
+                  void* (*fn)(void*, size_t, size_t, int, void*),
+                  void* old_address,
+                  size_t old_size,
+                  size_t new_size,
+                  int flags,
+                  void* new_address) {
+  if (AHeapProfile_isInMmapBackedAllocation())
+    return fn(old_address, old_size, new_size, flags, new_address);
+
+  // The old range stays mapped if mremap fails, with MREMAP_DONTUNMAP and
+  // when duplicating a shared mapping (|old_size| == 0).
+  bool unmaps_old = old_size != 0 && !(flags & MREMAP_DONTUNMAP);
+
+  // As with munmap, the free is recorded before remapping, so the old range
+  // can't be mapped again before we've processed it. If the mapping wasn't
+  // reported (e.g. because it is not anonymous), the free is a no-op, but the
+  // new mapping is reported. This is uncommon enough not to warrant tracking
+  // which mappings were reported.
+  if (unmaps_old)
+    AHeapProfile_reportFree(heap_id, reinterpret_cast<uint64_t>(old_address));
+  void* res = fn(old_address, old_size, new_size, flags, new_address);
+  if (res == MAP_FAILED) {
+    if (unmaps_old) {
+      // The old range was not touched: undo the free.
+      AHeapProfile_reportAllocation(
+          heap_id, reinterpret_cast<uint64_t>(old_address), old_size);
+    }
+    return res;
+  }
+  AHeapProfile_reportAllocation(heap_id, reinterpret_cast<uint64_t>(res),
+                                new_size);
+  return res;
+}
+
+void* syscall_mmap(void* addr,
+                   size_t length,
+                   int prot,
+                   int flags,
+                   int fd,
+                   off64_t offset) {
+#if defined(__NR_mmap2)
+  constexpr off64_t kMmap2Unit = 4096;
+  if (offset % kMmap2Unit) {
+    errno = EINVAL;
+    return MAP_FAILED;
+  }
+  return reinterpret_cast<void*>(syscall(__NR_mmap2, addr, length, prot, flags,
+                                         fd, offset / kMmap2Unit));
+#else
+  return reinterpret_cast<void*>(
+      syscall(__NR_mmap, addr, length, prot, flags, fd, offset));
+#endif
+}
+
+int syscall_munmap(void* addr, size_t length) {
+  return static_cast<int>(syscall(__NR_munmap, addr, length));
+}
+
+void* syscall_mremap(void* old_address,
+                     size_t old_size,
+                     size_t new_size,
+                     int flags,
+                     void* new_address) {
+  return reinterpret_cast<void*>(syscall(__NR_mremap, old_address, old_size,
+                                         new_size, flags, new_address));
+}
+
 }  // namespace profiling
 }  // namespace perfetto
@@ -18,9 +18,16 @@
 #define SRC_PROFILING_MEMORY_WRAP_ALLOCATORS_H_
 
 #include <stdlib.h>
+#include <sys/mman.h>
+#include <sys/types.h>
 
 #include <cinttypes>
 
+// Not defined by older libc headers.
+#ifndef MREMAP_DONTUNMAP
+#define MREMAP_DONTUNMAP 4
+#endif
+
 namespace perfetto {
 namespace profiling {
 
@@ -52,6 +59,50 @@ void* wrap_reallocarray(uint32_t heap_id,
                         size_t nmemb,
                         size_t size);
 
+// Only anonymous mappings are reported. Mappings created between
+// AHeapProfile_beginMmapBackedAllocation and
+// AHeapProfile_endMmapBackedAllocation are not reported either, as the
+// allocator which created them reports its sub-allocations itself.
+void* wrap_mmap(uint32_t heap_id,
+                void* (*fn)(void*, size_t, int, int, int, off64_t),
+                void* addr,
+                size_t length,
+                int prot,
+                int flags,
+                int fd,
+                off64_t offset);
+// The whole mapping starting at |addr| is reported as freed, even if only
+// part of it is unmapped.
+int wrap_munmap(uint32_t heap_id,
+                int (*fn)(void*, size_t),
+                void* addr,
+                size_t length);
+// The old mapping is reported as freed only if mremap actually unmaps it,
+// i.e. if it succeeds without MREMAP_DONTUNMAP.
+void* wrap_mremap(uint32_t heap_id,
+                  void* (*fn)(void*, size_t, size_t, int, void*),
+                  void* old_address,
+                  size_t old_size,
+                  size_t new_size,
+                  int flags,
+                  void* new_address);
+
+// The mmap, munmap and mremap syscalls. glibc does not export its internal
+// entry points for these, so the interceptors which replace them have to
+// issue the syscalls directly.
+void* syscall_mmap(void* addr,
+                   size_t length,
+                   int prot,
+                   int flags,
+                   int fd,
+                   off64_t offset);
+int syscall_munmap(void* addr, size_t length);
+void* syscall_mremap(void* old_address,
+                     size_t old_size,
+                     size_t new_size,
+                     int flags,
+                     void* new_address);
+
 }  // namespace profiling
 }  // namespace perfetto
 
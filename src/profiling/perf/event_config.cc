@@ -21,6 +21,7 @@
 
 #include <cinttypes>
 #include <optional>
+#include <string>
 #include <vector>
 
 #include <unwindstack/Regs.h>
@@ -274,6 +275,45 @@ std::optional<PerfCounter> MakePerfCounter(
   }
 }
 
+// Off-cpu sampling records the outgoing thread of every sched_switch where
+// that thread is blocked. The tracepoint reports the state of the outgoing
+// thread as a bitmask in |prev_state|, in which a preempted thread has neither
+// of the sleeping bits set.
+constexpr uint32_t kTaskInterruptible = 0x1;
+constexpr uint32_t kTaskUninterruptible = 0x2;
+
+// Returns a copy of |pb_config| with the timebase set up for off-cpu sampling.
+std::optional<protos::gen::PerfEventConfig> MakeOffCpuConfig(
+    const protos::gen::PerfEventConfig& pb_config) {
+  const auto& timebase = pb_config.timebase();
+  if (timebase.has_counter() || timebase.has_tracepoint() ||
+      timebase.has_raw_event() || timebase.frequency() ||
+      timebase.poll_period_ms() || pb_config.sampling_frequency()) {
+    PERFETTO_ELOG(
+        "Off-cpu sampling is incompatible with a custom timebase event or "
+        "sampling frequency");
+    return std::nullopt;
+  }
+
+  const auto& off_cpu = pb_config.off_cpu_sampling();
+  uint32_t state_mask = 0;
+  if (off_cpu.interruptible())
+    state_mask |= kTaskInterruptible;
+  if (off_cpu.uninterruptible())
+    state_mask |= kTaskUninterruptible;
+  if (!state_mask)
+    state_mask = kTaskInterruptible | kTaskUninterruptible;
+
+  protos::gen::PerfEventConfig ret = pb_config;
+  auto* mutable_timebase = ret.mutable_timebase();
+  if (!mutable_timebase->period())
+    mutable_timebase->set_period(1);
+  auto* mutable_tracepoint = mutable_timebase->mutable_tracepoint();
+  mutable_tracepoint->set_name("sched:sched_switch");
+  mutable_tracepoint->set_filter("prev_state & " + std::to_string(state_mask));
+  return ret;
+}
+
 bool IsSupportedUnwindMode(
     protos::gen::PerfEventConfig::UnwindMode unwind_mode) {
   using protos::gen::PerfEventConfig;
@@ -346,11 +386,22 @@ std::optional<EventConfig> EventConfig::Create(
     const DataSourceConfig& raw_ds_config,
     std::optional<ProcessSharding> process_sharding,
     const tracepoint_id_fn_t& tracepoint_id_lookup) {
+  // Off-cpu sampling replaces the timebase with a filtered sched_switch
+  // tracepoint, after which the config is handled as any other.
+  std::optional<protos::gen::PerfEventConfig> off_cpu_config;
+  if (pb_config.has_off_cpu_sampling()) {
+    off_cpu_config = MakeOffCpuConfig(pb_config);
+    if (!off_cpu_config)
+      return std::nullopt;
+  }
+  const protos::gen::PerfEventConfig& config =
+      off_cpu_config ? *off_cpu_config : pb_config;
+
   // Timebase (leader) event. Default: CPU timer.
   PerfCounter timebase_event;
-  std::string timebase_name = pb_config.timebase().name();
+  std::string timebase_name = config.timebase().name();
   auto maybe_perf_counter = MakePerfCounter(tracepoint_id_lookup, timebase_name,
-                                            pb_config.timebase());
+                                            config.timebase());
   if (!maybe_perf_counter) {
     return std::nullopt;
   }
@@ -358,7 +409,7 @@ std::optional<EventConfig> EventConfig::Create(
 
   // Follower events.
   std::vector<PerfCounter> followers;
-  for (const auto& event : pb_config.followers()) {
+  for (const auto& event : config.followers()) {
     auto maybe_follower_counter =
         MakePerfCounter(tracepoint_id_lookup, event.name(), event);
     if (!maybe_follower_counter) {
@@ -369,12 +420,12 @@ std::optional<EventConfig> EventConfig::Create(
 
   // The usual mode is sampling into a ring buffer, but we also support periodic
   // polling from userspace as some PMUs do not support sampling.
-  if (pb_config.timebase().poll_period_ms()) {
+  if (config.timebase().poll_period_ms()) {
     return CreatePolling(std::move(timebase_event), std::move(followers),
-                         pb_config, raw_ds_config);
+                         config, raw_ds_config);
   }
   return CreateSampling(std::move(timebase_event), std::move(followers),
-                        process_sharding, pb_config, raw_ds_config);
+                        process_sharding, config, raw_ds_config);
 }
 
 // Builds a config that is analogous to:
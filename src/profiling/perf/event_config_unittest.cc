@@ -475,6 +475,57 @@ TEST(EventConfigTest, GroupMultipleType) {
   EXPECT_TRUE(tracepoint.sample_type & PERF_SAMPLE_READ);
 }
 
+TEST(EventConfigTest, OffCpuSampling) {
+  auto id_lookup = [](const std::string& group, const std::string& name) {
+    return (group == "sched" && name == "sched_switch") ? 42 : 0;
+  };
+
+  {  // default: both kinds of sleep, every event
+    protos::gen::PerfEventConfig cfg;
+    cfg.mutable_off_cpu_sampling();
+    cfg.mutable_callstack_sampling()->set_kernel_frames(true);
+    std::optional<EventConfig> event_config = CreateEventConfig(cfg, id_lookup);
+
+    ASSERT_TRUE(event_config.has_value());
+    EXPECT_EQ(event_config->perf_attr()->type, PERF_TYPE_TRACEPOINT);
+    EXPECT_EQ(event_config->perf_attr()->config, 42u);
+    EXPECT_FALSE(event_config->perf_attr()->freq);
+    EXPECT_EQ(event_config->perf_attr()->sample_period, 1u);
+    EXPECT_TRUE(event_config->sample_callstacks());
+    EXPECT_EQ(event_config->timebase_event().tracepoint_name,
+              "sched:sched_switch");
+    EXPECT_EQ(event_config->timebase_event().tracepoint_filter,
+              "prev_state & 3");
+  }
+  {  // uninterruptible sleep only, with an explicit period
+    protos::gen::PerfEventConfig cfg;
+    cfg.mutable_off_cpu_sampling()->set_uninterruptible(true);
+    cfg.mutable_timebase()->set_period(10);
+    std::optional<EventConfig> event_config = CreateEventConfig(cfg, id_lookup);
+
+    ASSERT_TRUE(event_config.has_value());
+    EXPECT_EQ(event_config->perf_attr()->sample_period, 10u);
+    EXPECT_EQ(event_config->timebase_event().tracepoint_filter,
+              "prev_state & 2");
+  }
+  {  // incompatible with a sampling frequency
+    protos::gen::PerfEventConfig cfg;
+    cfg.mutable_off_cpu_sampling();
+    cfg.mutable_timebase()->set_frequency(100);
+    std::optional<EventConfig> event_config = CreateEventConfig(cfg, id_lookup);
+
+    EXPECT_FALSE(event_config.has_value());
+  }
+  {  // incompatible with a custom timebase event
+    protos::gen::PerfEventConfig cfg;
+    cfg.mutable_off_cpu_sampling();
+    cfg.mutable_timebase()->set_counter(protos::gen::PerfEvents::HW_CPU_CYCLES);
+    std::optional<EventConfig> event_config = CreateEventConfig(cfg, id_lookup);
+
+    EXPECT_FALSE(event_config.has_value());
+  }
+}
+
 }  // namespace
 }  // namespace profiling
 }  // namespace perfetto
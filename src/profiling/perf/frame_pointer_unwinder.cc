@@ -103,53 +103,31 @@ void FramePointerUnwinder::TryUnwind() {
 uint64_t FramePointerUnwinder::DecodeFrame(uint64_t fp,
                                            uint64_t* next_pc,
                                            uint64_t* next_sp) {
-  uint64_t next_fp;
-  if (!process_memory_->ReadFully(static_cast<uint64_t>(fp), &next_fp,
-                                  sizeof(next_fp)))
+  uint64_t record[2];
+  if (!process_memory_->ReadFully(fp, record, sizeof(record)))
     return 0;
-
-  uint64_t pc;
-  if (!process_memory_->ReadFully(static_cast<uint64_t>(fp + sizeof(uint64_t)),
-                                  &pc, sizeof(pc)))
-    return 0;
-
-  // Ensure there's not a stack overflow.
-  if (__builtin_add_overflow(fp, sizeof(uint64_t) * 2, next_sp))
-    return 0;
-
-  *next_pc = static_cast<uint64_t>(pc);
-  return next_fp;
+  return DecodeFrameRecord(GetFrameRecordArch(), fp, record, next_pc, next_sp);
 }
 
 bool FramePointerUnwinder::IsFrameValid(uint64_t fp, uint64_t sp) {
-  uint64_t align_mask = 0;
+  return IsFrameRecordValid(GetFrameRecordArch(), fp, sp, stack_end_);
+}
+
+FrameRecordArch FramePointerUnwinder::GetFrameRecordArch() const {
   switch (arch_) {
     case unwindstack::ARCH_ARM64:
-      align_mask = 0x1;
-      break;
+      return FrameRecordArch::kArm64;
     case unwindstack::ARCH_X86_64:
-      align_mask = 0xf;
-      break;
+      return FrameRecordArch::kX86_64;
     case unwindstack::ARCH_RISCV64:
-      align_mask = 0x7;
-      break;
+      return FrameRecordArch::kRiscv64;
     case unwindstack::ARCH_UNKNOWN:
     case unwindstack::ARCH_ARM:
     case unwindstack::ARCH_X86:
         // not supported
         ;
   }
-
-  if (fp == 0 || fp <= sp)
-    return false;
-
-  // Ensure there's space on the stack to read two values: the caller's
-  // frame pointer and the return address.
-  uint64_t result;
-  if (__builtin_add_overflow(fp, sizeof(uint64_t) * 2, &result))
-    return false;
-
-  return result <= stack_end_ && (fp & align_mask) == 0;
+  return FrameRecordArch::kUnsupported;
 }
 
 }  // namespace profiling
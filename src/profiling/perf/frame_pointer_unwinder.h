@@ -26,6 +26,8 @@
 #include <unwindstack/MachineX86_64.h>
 #include <unwindstack/Unwinder.h>
 
+#include "src/profiling/common/frame_record.h"
+
 namespace perfetto {
 namespace profiling {
 
@@ -95,6 +97,7 @@ class FramePointerUnwinder {
   // `ret_addr` and stack pointer into `sp`.
   uint64_t DecodeFrame(uint64_t fp, uint64_t* ret_addr, uint64_t* sp);
   bool IsFrameValid(uint64_t fp, uint64_t sp);
+  FrameRecordArch GetFrameRecordArch() const;
 };
 
 }  // namespace profiling
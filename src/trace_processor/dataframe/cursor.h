@@ -17,6 +17,7 @@
 #ifndef SRC_TRACE_PROCESSOR_DATAFRAME_CURSOR_H_
 #define SRC_TRACE_PROCESSOR_DATAFRAME_CURSOR_H_
 
+#include <atomic>
 #include <cstddef>
 #include <cstdint>
 #include <limits>
@@ -81,7 +82,17 @@ class Cursor {
   // Parameters:
   //   fvf: A subclass of `ValueFetcher` that defines the logic for fetching
   //        filter values for each filter spec.
-  PERFETTO_ALWAYS_INLINE void Execute(FilterValueFetcherImpl&);
+  //
+  // Returns false if the execution was interrupted (see SetInterruptFlag), in
+  // which case the cursor is empty.
+  PERFETTO_ALWAYS_INLINE bool Execute(FilterValueFetcherImpl&);
+
+  // Sets a flag which, when true, interrupts the execution of the query. This
+  // allows long running queries to be cancelled from another thread. |flag|
+  // must outlive the cursor.
+  void SetInterruptFlag(const std::atomic<bool>* flag) {
+    interpreter_.SetInterruptFlag(flag);
+  }
 
   // Returns the index of the row in the table this cursor is pointing to.
   PERFETTO_ALWAYS_INLINE uint32_t RowIndex() const { return *pos_; }
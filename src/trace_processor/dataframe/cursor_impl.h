@@ -27,15 +27,20 @@
 namespace perfetto::trace_processor::dataframe {
 
 template <typename FilterValueFetcherImpl>
-void Cursor<FilterValueFetcherImpl>::Execute(
+bool Cursor<FilterValueFetcherImpl>::Execute(
     FilterValueFetcherImpl& filter_value_fetcher) {
   using S = impl::Span<uint32_t>;
-  interpreter_.Execute(filter_value_fetcher);
+  if (!interpreter_.Execute(filter_value_fetcher)) {
+    pos_ = nullptr;
+    end_ = nullptr;
+    return false;
+  }
 
   const auto& span =
       *interpreter_.template GetRegisterValue<S>(params_.output_register);
   pos_ = span.b;
   end_ = span.e;
+  return true;
 }
 
 }  // namespace perfetto::trace_processor::dataframe
@@ -16,19 +16,25 @@
 
 #include "src/trace_processor/dataframe/dataframe.h"
 
+#include <cinttypes>
 #include <cstddef>
 #include <cstdint>
+#include <cstring>
 #include <memory>
 #include <string>
 #include <utility>
 #include <vector>
 
 #include "perfetto/base/logging.h"
+#include "perfetto/base/status.h"
 #include "perfetto/ext/base/status_macros.h"
 #include "perfetto/ext/base/status_or.h"
 #include "src/trace_processor/containers/string_pool.h"
 #include "src/trace_processor/dataframe/cursor_impl.h"  // IWYU pragma: keep
+#include "src/trace_processor/dataframe/impl/bit_vector.h"
+#include "src/trace_processor/dataframe/impl/flex_vector.h"
 #include "src/trace_processor/dataframe/impl/query_plan.h"
+#include "src/trace_processor/dataframe/impl/slab.h"
 #include "src/trace_processor/dataframe/impl/types.h"
 #include "src/trace_processor/dataframe/specs.h"
 #include "src/trace_processor/dataframe/typed_cursor.h"
@@ -285,4 +291,247 @@ std::vector<std::shared_ptr<impl::Column>> Dataframe::CreateColumnVector(
   return columns;
 }
 
+Dataframe::Serializer::~Serializer() = default;
+Dataframe::Deserializer::~Deserializer() = default;
+
+namespace {
+
+// Header of each column in the serialized dataframe, used to check that the
+// serialized columns match the ones of the dataframe being deserialized.
+struct SerializedColumnHeader {
+  uint32_t storage_type;
+  uint32_t nullability;
+  uint32_t sort_state;
+  uint32_t duplicate_state;
+  uint64_t storage_size;
+};
+
+template <typename T>
+void WriteValue(Dataframe::Serializer* serializer, const T& value) {
+  serializer->Write(&value, sizeof(T));
+}
+
+template <typename T>
+bool ReadValue(Dataframe::Deserializer* deserializer, T* value) {
+  return deserializer->Read(value, sizeof(T));
+}
+
+template <typename T>
+bool ReadFlexVector(Dataframe::Deserializer* deserializer,
+                    uint64_t size,
+                    impl::FlexVector<T>* out) {
+  auto vec = impl::FlexVector<T>::CreateWithSize(size);
+  if (size > 0 && !deserializer->Read(vec.data(), size * sizeof(T))) {
+    return false;
+  }
+  *out = std::move(vec);
+  return true;
+}
+
+uint64_t StorageSize(const impl::Storage& storage) {
+  switch (storage.type().index()) {
+    case StorageType::GetTypeIndex<Id>():
+      return storage.unchecked_get<Id>().size;
+    case StorageType::GetTypeIndex<Uint32>():
+      return storage.unchecked_get<Uint32>().size();
+    case StorageType::GetTypeIndex<Int32>():
+      return storage.unchecked_get<Int32>().size();
+    case StorageType::GetTypeIndex<Int64>():
+      return storage.unchecked_get<Int64>().size();
+    case StorageType::GetTypeIndex<Double>():
+      return storage.unchecked_get<Double>().size();
+    case StorageType::GetTypeIndex<String>():
+      return storage.unchecked_get<String>().size();
+    default:
+      PERFETTO_FATAL("Invalid storage type");
+  }
+}
+
+}  // namespace
+
+void Dataframe::Serialize(Serializer* serializer) const {
+  PERFETTO_CHECK(finalized_);
+  WriteValue(serializer, static_cast<uint32_t>(columns_.size()));
+  WriteValue(serializer, row_count_);
+  for (uint32_t i = 0; i < columns_.size(); ++i) {
+    const impl::Column& c = *columns_[i];
+    const std::string& name = column_names_[i];
+    WriteValue(serializer, static_cast<uint32_t>(name.size()));
+    serializer->Write(name.data(), name.size());
+
+    SerializedColumnHeader header{
+        c.storage.type().index(), c.null_storage.nullability().index(),
+        c.sort_state.index(), c.duplicate_state.index(),
+        StorageSize(c.storage)};
+    WriteValue(serializer, header);
+
+    // The null bit vector comes first as it determines how many values are
+    // stored. The prefix popcounts of sparse null columns are not serialized
+    // as they can be cheaply recomputed from the bit vector.
+    if (const impl::BitVector* bv = c.null_storage.MaybeGetNullBitVector();
+        bv) {
+      WriteValue(serializer, bv->size());
+      serializer->Write(bv->words().data(),
+                        (bv->size() + 63u) / 64u * sizeof(uint64_t));
+    }
+
+    switch (c.storage.type().index()) {
+      case StorageType::GetTypeIndex<Id>():
+        break;
+      case StorageType::GetTypeIndex<Uint32>():
+        serializer->Write(c.storage.unchecked_data<Uint32>(),
+                          header.storage_size * sizeof(uint32_t));
+        break;
+      case StorageType::GetTypeIndex<Int32>():
+        serializer->Write(c.storage.unchecked_data<Int32>(),
+                          header.storage_size * sizeof(int32_t));
+        break;
+      case StorageType::GetTypeIndex<Int64>():
+        serializer->Write(c.storage.unchecked_data<Int64>(),
+                          header.storage_size * sizeof(int64_t));
+        break;
+      case StorageType::GetTypeIndex<Double>():
+        serializer->Write(c.storage.unchecked_data<Double>(),
+                          header.storage_size * sizeof(double));
+        break;
+      case StorageType::GetTypeIndex<String>():
+        serializer->WriteStrings(c.storage.unchecked_data<String>(),
+                                 header.storage_size);
+        break;
+      default:
+        PERFETTO_FATAL("Invalid storage type");
+    }
+  }
+}
+
+base::Status Dataframe::Deserialize(Deserializer* deserializer) {
+  PERFETTO_CHECK(!finalized_);
+  base::Status status = DeserializeInternal(deserializer);
+  if (!status.ok()) {
+    Clear();
+  }
+  return status;
+}
+
+base::Status Dataframe::DeserializeInternal(Deserializer* deserializer) {
+  uint32_t column_count;
+  uint32_t row_count;
+  if (!ReadValue(deserializer, &column_count) ||
+      !ReadValue(deserializer, &row_count)) {
+    return base::ErrStatus("Truncated dataframe");
+  }
+  if (column_count != columns_.size()) {
+    return base::ErrStatus("Dataframe has %u columns, expected %zu",
+                           column_count, columns_.size());
+  }
+  for (uint32_t i = 0; i < column_count; ++i) {
+    impl::Column& c = *columns_[i];
+    const std::string& name = column_names_[i];
+
+    uint32_t name_size;
+    if (!ReadValue(deserializer, &name_size) || name_size > 1024) {
+      return base::ErrStatus("Invalid column name");
+    }
+    std::string serialized_name(name_size, '\0');
+    if (!deserializer->Read(serialized_name.data(), name_size)) {
+      return base::ErrStatus("Truncated dataframe");
+    }
+    if (serialized_name != name) {
+      return base::ErrStatus("Column %u is named '%s', expected '%s'", i,
+                             serialized_name.c_str(), name.c_str());
+    }
+
+    SerializedColumnHeader header;
+    if (!ReadValue(deserializer, &header)) {
+      return base::ErrStatus("Truncated dataframe");
+    }
+    if (header.storage_type != c.storage.type().index() ||
+        header.nullability != c.null_storage.nullability().index() ||
+        header.sort_state != c.sort_state.index() ||
+        header.duplicate_state != c.duplicate_state.index()) {
+      return base::ErrStatus("Column '%s' has a different type", name.c_str());
+    }
+
+    uint64_t non_null_count = row_count;
+    if (c.null_storage.nullability().index() !=
+        Nullability::GetTypeIndex<NonNull>()) {
+      uint64_t bit_count;
+      if (!ReadValue(deserializer, &bit_count) || bit_count != row_count) {
+        return base::ErrStatus("Invalid null bit vector in column '%s'",
+                               name.c_str());
+      }
+      impl::FlexVector<uint64_t> words;
+      if (!ReadFlexVector(deserializer, (bit_count + 63u) / 64u, &words)) {
+        return base::ErrStatus("Truncated dataframe");
+      }
+      auto bv = impl::BitVector::CreateFromWords(std::move(words), bit_count);
+      uint32_t word_count = static_cast<uint32_t>(bv.words().size());
+      if (c.null_storage.nullability().Is<DenseNull>()) {
+        c.null_storage.unchecked_get<DenseNull>().bit_vector = std::move(bv);
+      } else {
+        auto& null = c.null_storage.unchecked_get<SparseNull>();
+        null.bit_vector = std::move(bv);
+        impl::Slab<uint32_t> popcount = null.bit_vector.PrefixPopcount();
+        non_null_count =
+            word_count == 0 ? 0
+                            : popcount[word_count - 1] +
+                                  null.bit_vector.count_set_bits_in_word(
+                                      (word_count - 1) * 64u);
+        if (!c.null_storage.nullability().Is<SparseNull>()) {
+          null.prefix_popcount_for_cell_get =
+              impl::FlexVector<uint32_t>::CreateWithSize(word_count);
+          if (word_count > 0) {
+            memcpy(null.prefix_popcount_for_cell_get.data(), popcount.data(),
+                   word_count * sizeof(uint32_t));
+          }
+        }
+      }
+    }
+    if (header.storage_size != non_null_count) {
+      return base::ErrStatus(
+          "Column '%s' has %" PRIu64 " values, expected %" PRIu64,
+          name.c_str(), header.storage_size, non_null_count);
+    }
+
+    bool ok = true;
+    switch (c.storage.type().index()) {
+      case StorageType::GetTypeIndex<Id>():
+        c.storage.unchecked_get<Id>().size = row_count;
+        break;
+      case StorageType::GetTypeIndex<Uint32>():
+        ok = ReadFlexVector(deserializer, header.storage_size,
+                            &c.storage.unchecked_get<Uint32>());
+        break;
+      case StorageType::GetTypeIndex<Int32>():
+        ok = ReadFlexVector(deserializer, header.storage_size,
+                            &c.storage.unchecked_get<Int32>());
+        break;
+      case StorageType::GetTypeIndex<Int64>():
+        ok = ReadFlexVector(deserializer, header.storage_size,
+                            &c.storage.unchecked_get<Int64>());
+        break;
+      case StorageType::GetTypeIndex<Double>():
+        ok = ReadFlexVector(deserializer, header.storage_size,
+                            &c.storage.unchecked_get<Double>());
+        break;
+      case StorageType::GetTypeIndex<String>(): {
+        auto vec = impl::FlexVector<StringPool::Id>::CreateWithSize(
+            header.storage_size);
+        ok = deserializer->ReadStrings(vec.data(), header.storage_size);
+        c.storage.unchecked_get<String>() = std::move(vec);
+        break;
+      }
+      default:
+        PERFETTO_FATAL("Invalid storage type");
+    }
+    if (!ok) {
+      return base::ErrStatus("Invalid values in column '%s'", name.c_str());
+    }
+    ++c.mutations;
+  }
+  row_count_ = row_count;
+  ++non_column_mutations_;
+  return base::OkStatus();
+}
+
 }  // namespace perfetto::trace_processor::dataframe
@@ -29,6 +29,7 @@
 #include <vector>
 
 #include "perfetto/base/logging.h"
+#include "perfetto/base/status.h"
 #include "perfetto/ext/base/status_or.h"
 #include "perfetto/ext/base/variant.h"
 #include "perfetto/public/compiler.h"
@@ -102,6 +103,35 @@ class Dataframe {
     impl::QueryPlan plan_;
   };
 
+  // Receives the contents of a dataframe from `Serialize()`.
+  class Serializer {
+   public:
+    virtual ~Serializer();
+
+    // Writes `size` bytes starting at `data`.
+    virtual void Write(const void* data, size_t size) = 0;
+
+    // Writes `count` string ids. As ids are only meaningful for the string
+    // pool of the dataframe, implementations should write the strings (or a
+    // reference to them) instead.
+    virtual void WriteStrings(const StringPool::Id* ids, size_t count) = 0;
+  };
+
+  // Provides the contents of a dataframe to `Deserialize()`.
+  class Deserializer {
+   public:
+    virtual ~Deserializer();
+
+    // Reads `size` bytes into `data`. Returns false if there is not enough
+    // data.
+    virtual bool Read(void* data, size_t size) = 0;
+
+    // Reads `count` string ids written by `Serializer::WriteStrings()`,
+    // converted to ids in the string pool of the dataframe. Returns false if
+    // the data is invalid.
+    virtual bool ReadStrings(StringPool::Id* ids, size_t count) = 0;
+  };
+
   // Constructs a Dataframe with the specified column names and types.
   Dataframe(StringPool* string_pool,
             uint32_t column_count,
@@ -256,6 +286,16 @@ class Dataframe {
   // Creates a spec object for this dataframe.
   DataframeSpec CreateSpec() const;
 
+  // Writes the rows of a finalized dataframe to `serializer` in a format
+  // which can be read back by `Deserialize()`. Indexes are not serialized.
+  void Serialize(Serializer* serializer) const;
+
+  // Replaces the rows of a non-finalized dataframe with the ones read from
+  // `deserializer`. Returns an error (and leaves the dataframe empty) if the
+  // serialized dataframe does not have exactly the same columns as this one
+  // or if the data is invalid.
+  base::Status Deserialize(Deserializer* deserializer);
+
   // Returns whether the dataframe has been finalized.
   bool finalized() const { return finalized_; }
 
@@ -532,6 +572,8 @@ class Dataframe {
       const ColumnSpec*,
       uint32_t);
 
+  base::Status DeserializeInternal(Deserializer*);
+
   // Private copy constructor for special methods.
   Dataframe(const Dataframe&) = default;
   Dataframe& operator=(const Dataframe&) = default;
@@ -19,6 +19,7 @@
 #include <cctype>
 #include <cstddef>
 #include <cstdint>
+#include <cstring>
 #include <memory>
 #include <numeric>
 #include <optional>
@@ -30,6 +31,7 @@
 
 #include "perfetto/base/logging.h"
 #include "perfetto/ext/base/string_utils.h"
+#include "perfetto/ext/base/string_view.h"
 #include "src/base/test/status_matchers.h"
 #include "src/trace_processor/containers/string_pool.h"
 #include "src/trace_processor/dataframe/dataframe_test_utils.h"
@@ -1689,4 +1691,165 @@ TEST(DataframeTest, SortedFilterWithDuplicatesAndRowCountOfZero) {
   EXPECT_EQ(plan.GetImplForTesting().params.estimated_row_count, 0u);
 }
 
+// Serializes a dataframe to a buffer, storing strings in a separate vector so
+// that they can be interned in another string pool.
+class BufferSerializer : public Dataframe::Serializer {
+ public:
+  explicit BufferSerializer(const StringPool* pool) : pool_(pool) {}
+
+  void Write(const void* data, size_t size) override {
+    buf_.append(static_cast<const char*>(data), size);
+  }
+  void WriteStrings(const StringPool::Id* ids, size_t count) override {
+    for (size_t i = 0; i < count; ++i) {
+      std::string str = pool_->Get(ids[i]).ToStdString();
+      uint32_t size = static_cast<uint32_t>(str.size());
+      Write(&size, sizeof(size));
+      Write(str.data(), str.size());
+    }
+  }
+
+  const std::string& buf() const { return buf_; }
+
+ private:
+  const StringPool* pool_;
+  std::string buf_;
+};
+
+class BufferDeserializer : public Dataframe::Deserializer {
+ public:
+  BufferDeserializer(std::string buf, StringPool* pool)
+      : buf_(std::move(buf)), pool_(pool) {}
+
+  bool Read(void* data, size_t size) override {
+    if (buf_.size() - offset_ < size) {
+      return false;
+    }
+    memcpy(data, buf_.data() + offset_, size);
+    offset_ += size;
+    return true;
+  }
+  bool ReadStrings(StringPool::Id* ids, size_t count) override {
+    for (size_t i = 0; i < count; ++i) {
+      uint32_t size;
+      if (!Read(&size, sizeof(size)) || buf_.size() - offset_ < size) {
+        return false;
+      }
+      ids[i] = pool_->InternString(
+          base::StringView(buf_.data() + offset_, size));
+      offset_ += size;
+    }
+    return true;
+  }
+
+ private:
+  std::string buf_;
+  size_t offset_ = 0;
+  StringPool* pool_;
+};
+
+TEST(DataframeTest, SerializeDeserialize) {
+  static constexpr auto kSpec = CreateTypedDataframeSpec(
+      {"id", "col2", "col3", "col4", "col5"},
+      CreateTypedColumnSpec(Id(), NonNull(), IdSorted()),
+      CreateTypedColumnSpec(Uint32(), NonNull(), Unsorted()),
+      CreateTypedColumnSpec(Int64(), DenseNull(), Unsorted()),
+      CreateTypedColumnSpec(String(), SparseNullWithPopcountAlways(),
+                            Unsorted()),
+      CreateTypedColumnSpec(Double(), SparseNull(), Unsorted()));
+  StringPool pool;
+  Dataframe df = Dataframe::CreateFromTypedSpec(kSpec, &pool);
+  for (uint32_t i = 0; i < 100; ++i) {
+    std::optional<StringPool::Id> str;
+    if (i % 3 == 0) {
+      str = pool.InternString(base::StringView("str" + std::to_string(i)));
+    }
+    df.InsertUnchecked(
+        kSpec, std::monostate(), i * 10,
+        i % 2 == 0 ? std::make_optional(int64_t{i}) : std::nullopt, str,
+        i % 5 == 0 ? std::make_optional(i / 2.0) : std::nullopt);
+  }
+  df.Finalize();
+
+  BufferSerializer serializer(&pool);
+  df.Serialize(&serializer);
+
+  // Deserialize into a different string pool, where the same strings have
+  // different ids.
+  StringPool other_pool;
+  other_pool.InternString("unrelated");
+  Dataframe other = Dataframe::CreateFromTypedSpec(kSpec, &other_pool);
+  BufferDeserializer deserializer(serializer.buf(), &other_pool);
+  ASSERT_OK(other.Deserialize(&deserializer));
+  ASSERT_EQ(other.row_count(), 100u);
+
+  for (uint32_t i = 0; i < 100; ++i) {
+    ASSERT_EQ(other.GetCellUnchecked<0>(kSpec, i), i);
+    ASSERT_EQ(other.GetCellUnchecked<1>(kSpec, i), i * 10);
+    ASSERT_EQ(other.GetCellUnchecked<2>(kSpec, i),
+              i % 2 == 0 ? std::make_optional(int64_t{i}) : std::nullopt);
+    auto str = other.GetCellUnchecked<3>(kSpec, i);
+    if (i % 3 == 0) {
+      ASSERT_TRUE(str.has_value());
+      ASSERT_EQ(other_pool.Get(*str).ToStdString(), "str" + std::to_string(i));
+    } else {
+      ASSERT_EQ(str, std::nullopt);
+    }
+  }
+  other.Finalize();
+
+  // Sparse null columns without popcount can only be read with a cursor.
+  std::vector<std::string> strs;
+  for (uint32_t i = 0; i < 100; ++i) {
+    strs.push_back("str" + std::to_string(i));
+  }
+  std::vector<std::vector<ValueVerifier::ValueVariant>> expected;
+  for (uint32_t i = 0; i < 100; ++i) {
+    std::vector<ValueVerifier::ValueVariant> row{i, i * 10};
+    if (i % 2 == 0) {
+      row.emplace_back(int64_t{i});
+    } else {
+      row.emplace_back(nullptr);
+    }
+    if (i % 3 == 0) {
+      row.emplace_back(NullTermStringView(strs[i]));
+    } else {
+      row.emplace_back(nullptr);
+    }
+    if (i % 5 == 0) {
+      row.emplace_back(i / 2.0);
+    } else {
+      row.emplace_back(nullptr);
+    }
+    expected.push_back(std::move(row));
+  }
+  VerifyData(other, 0b11111, expected);
+}
+
+TEST(DataframeTest, DeserializeMismatchedColumns) {
+  static constexpr auto kSpec = CreateTypedDataframeSpec(
+      {"col"}, CreateTypedColumnSpec(Int64(), NonNull(), Unsorted()));
+  static constexpr auto kOtherSpec = CreateTypedDataframeSpec(
+      {"col"}, CreateTypedColumnSpec(Uint32(), NonNull(), Unsorted()));
+  StringPool pool;
+  Dataframe df = Dataframe::CreateFromTypedSpec(kSpec, &pool);
+  df.InsertUnchecked(kSpec, int64_t{1});
+  df.Finalize();
+
+  BufferSerializer serializer(&pool);
+  df.Serialize(&serializer);
+
+  Dataframe other = Dataframe::CreateFromTypedSpec(kOtherSpec, &pool);
+  BufferDeserializer deserializer(serializer.buf(), &pool);
+  ASSERT_FALSE(other.Deserialize(&deserializer).ok());
+  ASSERT_EQ(other.row_count(), 0u);
+
+  // Truncated data is rejected.
+  Dataframe truncated = Dataframe::CreateFromTypedSpec(kSpec, &pool);
+  BufferDeserializer truncated_deserializer(
+      serializer.buf().substr(0, serializer.buf().size() - 1), &pool);
+  ASSERT_FALSE(truncated.Deserialize(&truncated_deserializer).ok());
+  ASSERT_EQ(truncated.row_count(), 0u);
+}
+
 }  // namespace perfetto::trace_processor::dataframe
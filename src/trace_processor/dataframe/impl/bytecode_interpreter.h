@@ -17,6 +17,7 @@
 #ifndef SRC_TRACE_PROCESSOR_DATAFRAME_IMPL_BYTECODE_INTERPRETER_H_
 #define SRC_TRACE_PROCESSOR_DATAFRAME_IMPL_BYTECODE_INTERPRETER_H_
 
+#include <atomic>
 #include <cstdint>
 #include <cstring>
 #include <type_traits>
@@ -64,9 +65,18 @@ class Interpreter {
 
   // Executes the bytecode sequence, processing each bytecode instruction in
   // turn, and dispatching to the appropriate function in this class.
-  PERFETTO_ALWAYS_INLINE void Execute(
+  //
+  // Returns false if the execution was interrupted (see SetInterruptFlag), in
+  // which case the registers are in an unspecified state.
+  PERFETTO_ALWAYS_INLINE bool Execute(
       FilterValueFetcherImpl& filter_value_fetcher);
 
+  // Sets a flag which, when true, stops the execution before the next
+  // bytecode instruction. |flag| must outlive this object.
+  void SetInterruptFlag(const std::atomic<bool>* flag) {
+    state_.interrupt_flag = flag;
+  }
+
   // Returns the value of the specified register if it contains the expected
   // type. Returns nullptr if the register holds a different type or is empty.
   template <typename T>
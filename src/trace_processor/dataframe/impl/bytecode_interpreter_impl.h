@@ -167,14 +167,22 @@ class InterpreterImpl {
 
   // Executes the bytecode sequence, processing each bytecode instruction in
   // turn, and dispatching to the appropriate function in this class.
-  PERFETTO_ALWAYS_INLINE void Execute() {
+  //
+  // Returns false if the execution was interrupted before completing.
+  PERFETTO_ALWAYS_INLINE bool Execute() {
     for (const auto& bytecode : state_.bytecode) {
+      if (PERFETTO_UNLIKELY(state_.interrupt_flag &&
+                            state_.interrupt_flag->load(
+                                std::memory_order_relaxed))) {
+        return false;
+      }
       switch (bytecode.option) {
         PERFETTO_DATAFRAME_BYTECODE_LIST(PERFETTO_DATAFRAME_BYTECODE_CASE_FN)
         default:
           PERFETTO_ASSUME(false);
       }
     }
+    return true;
   }
 
  private:
@@ -1720,10 +1728,10 @@ class InterpreterImpl {
 };
 
 template <typename FilterValueFetcherImpl>
-void Interpreter<FilterValueFetcherImpl>::Execute(
+bool Interpreter<FilterValueFetcherImpl>::Execute(
     FilterValueFetcherImpl& filter_value_fetcher) {
   InterpreterImpl<FilterValueFetcherImpl> impl(filter_value_fetcher, state_);
-  impl.Execute();
+  return impl.Execute();
 }
 
 }  // namespace perfetto::trace_processor::dataframe::impl::bytecode
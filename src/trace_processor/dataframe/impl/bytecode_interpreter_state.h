@@ -17,6 +17,7 @@
 #ifndef SRC_TRACE_PROCESSOR_DATAFRAME_IMPL_BYTECODE_INTERPRETER_STATE_H_
 #define SRC_TRACE_PROCESSOR_DATAFRAME_IMPL_BYTECODE_INTERPRETER_STATE_H_
 
+#include <atomic>
 #include <cstdint>
 #include <cstring>
 
@@ -44,6 +45,9 @@ struct InterpreterState {
   const dataframe::Index* indexes;
   // Pointer to the string pool (for string operations)
   const StringPool* string_pool;
+  // If non-null, execution stops before the next instruction once this
+  // becomes true.
+  const std::atomic<bool>* interrupt_flag = nullptr;
 
   /******************************************************************
    * Helper functions for accessing the interpreter state           *
@@ -57,7 +57,10 @@ std::optional<TraceSorter::SortingMode> GetMinimumSortingMode(
     case kSystraceTraceType:
     case kGzipTraceType:
     case kCtraceTraceType:
+    case kZstdTraceType:
     case kArtHprofTraceType:
+    case kFoldedStackTraceType:
+    case kOtlpTraceType:
       return std::nullopt;
 
     case kPerfDataTraceType:
@@ -74,6 +77,7 @@ std::optional<TraceSorter::SortingMode> GetMinimumSortingMode(
     case kGeckoTraceType:
     case kArtMethodTraceType:
     case kPerfTextTraceType:
+    case kCtfTraceType:
       return TraceSorter::SortingMode::kFullSort;
 
     case kProtoTraceType:
@@ -89,6 +89,31 @@ TEST(TraceProcessorImplTest, GuessTraceType_Fuchsia) {
   EXPECT_EQ(kFuchsiaTraceType, GuessTraceType(prefix, sizeof(prefix)));
 }
 
+TEST(TraceProcessorImplTest, GuessTraceType_FoldedStack) {
+  const uint8_t prefix[] = "main;foo;bar 12\nmain;foo 3\nmain;b";
+  EXPECT_EQ(kFoldedStackTraceType, GuessTraceType(prefix, sizeof(prefix)));
+}
+
+TEST(TraceProcessorImplTest, GuessTraceType_OtlpJson) {
+  const uint8_t prefix[] = "{\"resourceSpans\": [{\"resource\": {";
+  EXPECT_EQ(kOtlpTraceType, GuessTraceType(prefix, sizeof(prefix)));
+}
+
+TEST(TraceProcessorImplTest, GuessTraceType_OtlpProto) {
+  // TracesData { resource_spans { scope_spans { spans { trace_id, span_id }}}}
+  const uint8_t prefix[] = {0x0a, 0x20, 0x12, 0x1e, 0x12, 0x1c, 0x0a, 0x10,
+                            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,
+                            0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10,
+                            0x12, 0x08, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06,
+                            0x07, 0x08};
+  EXPECT_EQ(kOtlpTraceType, GuessTraceType(prefix, sizeof(prefix)));
+}
+
+TEST(TraceProcessorImplTest, GuessTraceType_Zstd) {
+  const uint8_t prefix[] = {0x28, 0xb5, 0x2f, 0xfd, 0x04, 0x58, 0x45, 0x00};
+  EXPECT_EQ(kZstdTraceType, GuessTraceType(prefix, sizeof(prefix)));
+}
+
 TEST(TraceProcessorImplTest, GuessTraceType_Bmp) {
   const uint8_t prefix[] = {0x42, 0x4d, 0x1e, 0x00, 0x00, 0x00, 0x00,
                             0x00, 0x00, 0x00, 0x1a, 0x00, 0x00, 0x00,
@@ -16,7 +16,11 @@
 
 #include "src/trace_processor/importers/archive/archive_entry.h"
 
+#include <cstddef>
+#include <string>
+#include <string_view>
 #include <tuple>
+#include <utility>
 
 namespace perfetto::trace_processor {
 
@@ -33,18 +37,37 @@ bool ArchiveEntry::operator<(const ArchiveEntry& rhs) const {
       // Proto traces should always parsed first as they might contains clock
       // sync data needed to correctly parse other traces.
       return 0;
-    if (type == TraceType::kGzipTraceType)
+    if (type == TraceType::kGzipTraceType ||
+        type == TraceType::kZstdTraceType)
       return 1;  // Middle priority
     return 2;    // Default for other trace types
   };
 
+  // CTF traces are directories containing a metadata file describing the
+  // layout of all the other (stream) files in the same directory: group files
+  // by directory and make sure the metadata is always read first.
+  auto group_key = [](const ArchiveEntry& entry) {
+    if (entry.trace_type != kCtfTraceType) {
+      return std::make_pair(std::string_view(entry.name), 0);
+    }
+    std::string_view path(entry.name);
+    size_t slash = path.rfind('/');
+    std::string_view dir = slash == std::string_view::npos
+                               ? std::string_view()
+                               : path.substr(0, slash + 1);
+    std::string_view file = path.substr(dir.size());
+    return std::make_pair(dir, file == "metadata" ? 0 : 1);
+  };
+
   // Compare first by trace type priority, then by name,
   // and finally by index to ensure strict ordering.
   int lhs_priority = trace_priority(trace_type);
   int rhs_priority = trace_priority(rhs.trace_type);
+  auto lhs_group = group_key(*this);
+  auto rhs_group = group_key(rhs);
 
-  return std::tie(lhs_priority, name, index) <
-         std::tie(rhs_priority, rhs.name, rhs.index);
+  return std::tie(lhs_priority, lhs_group, name, index) <
+         std::tie(rhs_priority, rhs_group, rhs.name, rhs.index);
 }
 
 }  // namespace perfetto::trace_processor
@@ -29,5 +29,6 @@ SpeRecordParser::~SpeRecordParser() = default;
 GeckoTraceParser::~GeckoTraceParser() = default;
 ArtMethodParser::~ArtMethodParser() = default;
 PerfTextTraceParser::~PerfTextTraceParser() = default;
+CtfTraceParser::~CtfTraceParser() = default;
 
 }  // namespace perfetto::trace_processor
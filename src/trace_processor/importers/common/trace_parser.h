@@ -37,6 +37,9 @@ struct ArtMethodEvent;
 namespace perf_text_importer {
 struct PerfTextEvent;
 }
+namespace ctf_importer {
+struct CtfEvent;
+}
 
 struct AndroidDumpstateEvent;
 struct AndroidLogEvent;
@@ -124,6 +127,12 @@ class PerfTextTraceParser {
                                   perf_text_importer::PerfTextEvent) = 0;
 };
 
+class CtfTraceParser {
+ public:
+  virtual ~CtfTraceParser();
+  virtual void ParseCtfEvent(int64_t, ctf_importer::CtfEvent) = 0;
+};
+
 }  // namespace perfetto::trace_processor
 
 #endif  // SRC_TRACE_PROCESSOR_IMPORTERS_COMMON_TRACE_PARSER_H_
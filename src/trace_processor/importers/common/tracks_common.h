@@ -89,6 +89,20 @@ static constexpr auto kChromeProcessInstantBlueprint = tracks::SliceBlueprint(
     "chrome_process_instant",
     tracks::Dimensions(tracks::kProcessDimensionBlueprint));
 
+inline constexpr auto kCpuIrqBlueprint = tracks::SliceBlueprint(
+    "cpu_irq",
+    tracks::DimensionBlueprints(tracks::kCpuDimensionBlueprint),
+    tracks::FnNameBlueprint([](uint32_t cpu) {
+      return base::StackString<255>("Irq Cpu %u", cpu);
+    }));
+
+inline constexpr auto kCpuSoftIrqBlueprint = tracks::SliceBlueprint(
+    "cpu_softirq",
+    tracks::DimensionBlueprints(tracks::kCpuDimensionBlueprint),
+    tracks::FnNameBlueprint([](uint32_t cpu) {
+      return base::StackString<255>("SoftIrq Cpu %u", cpu);
+    }));
+
 // End slice blueprints.
 
 // Begin counter blueprints.
@@ -0,0 +1,54 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_EVENT_H_
+#define SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_EVENT_H_
+
+#include <cstdint>
+#include <optional>
+#include <vector>
+
+#include "src/trace_processor/containers/string_pool.h"
+#include "src/trace_processor/types/variadic.h"
+
+namespace perfetto::trace_processor::ctf_importer {
+
+struct alignas(8) CtfEvent {
+  struct Field {
+    StringPool::Id name;
+    Variadic value;
+  };
+
+  StringPool::Id name;
+
+  // Whether this event was emitted by the kernel tracer (as opposed to a
+  // userspace tracer).
+  bool is_kernel = false;
+
+  std::optional<uint32_t> cpu;
+
+  // Thread information from the event context (if available).
+  std::optional<int64_t> tid;
+  std::optional<int64_t> pid;
+  std::optional<StringPool::Id> comm;
+
+  // The payload of the event.
+  std::vector<Field> fields;
+};
+
+}  // namespace perfetto::trace_processor::ctf_importer
+
+#endif  // SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_EVENT_H_
@@ -0,0 +1,1130 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/ctf/ctf_metadata.h"
+
+#include <algorithm>
+#include <cctype>
+#include <cinttypes>
+#include <cstddef>
+#include <cstdint>
+#include <cstring>
+#include <map>
+#include <memory>
+#include <optional>
+#include <string>
+#include <string_view>
+#include <utility>
+#include <vector>
+
+#include "perfetto/base/logging.h"
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/endian.h"
+#include "perfetto/ext/base/flat_hash_map.h"
+#include "perfetto/ext/base/status_macros.h"
+#include "perfetto/ext/base/status_or.h"
+#include "perfetto/ext/base/string_utils.h"
+
+namespace perfetto::trace_processor::ctf_importer {
+namespace {
+
+constexpr std::string_view kTextMetadataPrefix = "/* CTF 1.8";
+
+// Size of the header of each packet in a packetized metadata file.
+constexpr size_t kMetadataPacketHeaderSize = 37;
+
+uint32_t ReadU32(const uint8_t* data, bool big_endian) {
+  uint32_t value;
+  memcpy(&value, data, sizeof(value));
+  if (big_endian) {
+    value = base::BE32ToHost(value);
+  }
+  return value;
+}
+
+// Returns std::nullopt if |data| does not start with |magic| in either byte
+// order, true if it starts with |magic| in big endian order and false if it
+// starts with |magic| in little endian order.
+std::optional<bool> MatchesMagic(const uint8_t* data,
+                                 size_t size,
+                                 uint32_t magic) {
+  if (size < sizeof(uint32_t)) {
+    return std::nullopt;
+  }
+  if (ReadU32(data, false) == magic) {
+    return false;
+  }
+  if (ReadU32(data, true) == magic) {
+    return true;
+  }
+  return std::nullopt;
+}
+
+// Lexer + recursive descent parser for the subset of TSDL used by real-world
+// CTF 1.8 producers (LTTng, barectf, babeltrace).
+class TsdlParser {
+ public:
+  explicit TsdlParser(std::string_view text) : text_(text) {}
+
+  base::StatusOr<std::unique_ptr<CtfMetadata>> Parse() {
+    RETURN_IF_ERROR(Tokenize());
+    metadata_ = std::make_unique<CtfMetadata>();
+    while (Peek().type != TokenType::kEof) {
+      RETURN_IF_ERROR(ParseTopLevelStatement());
+    }
+    RETURN_IF_ERROR(AssignEventsToStreams());
+    return std::move(metadata_);
+  }
+
+ private:
+  enum class TokenType : uint8_t {
+    kIdentifier,
+    kNumber,
+    kString,
+    kPunctuation,
+    kEof,
+  };
+
+  struct Token {
+    TokenType type;
+    std::string_view text;
+  };
+
+  struct Value {
+    TokenType type;
+    std::string text;
+  };
+
+  struct Declarator {
+    std::string name;
+    CtfTypePtr type;
+  };
+
+  // Lexing.
+
+  base::Status Tokenize() {
+    size_t i = 0;
+    while (i < text_.size()) {
+      char c = text_[i];
+      if (isspace(static_cast<unsigned char>(c))) {
+        ++i;
+        continue;
+      }
+      if (text_.compare(i, 2, "/*") == 0) {
+        size_t end = text_.find("*/", i + 2);
+        if (end == std::string_view::npos) {
+          return base::ErrStatus("CTF: unterminated comment in metadata");
+        }
+        i = end + 2;
+        continue;
+      }
+      if (text_.compare(i, 2, "//") == 0) {
+        size_t end = text_.find('\n', i);
+        i = end == std::string_view::npos ? text_.size() : end + 1;
+        continue;
+      }
+      if (c == '"') {
+        size_t end = i + 1;
+        while (end < text_.size() && text_[end] != '"') {
+          end += text_[end] == '\\' ? 2 : 1;
+        }
+        if (end >= text_.size()) {
+          return base::ErrStatus("CTF: unterminated string in metadata");
+        }
+        tokens_.push_back(
+            {TokenType::kString, text_.substr(i + 1, end - i - 1)});
+        i = end + 1;
+        continue;
+      }
+      if (isalpha(static_cast<unsigned char>(c)) || c == '_') {
+        size_t end = i;
+        while (end < text_.size() &&
+               (isalnum(static_cast<unsigned char>(text_[end])) ||
+                text_[end] == '_')) {
+          ++end;
+        }
+        tokens_.push_back({TokenType::kIdentifier, text_.substr(i, end - i)});
+        i = end;
+        continue;
+      }
+      bool is_negative_number =
+          c == '-' && i + 1 < text_.size() &&
+          isdigit(static_cast<unsigned char>(text_[i + 1]));
+      if (isdigit(static_cast<unsigned char>(c)) || is_negative_number) {
+        size_t end = i + 1;
+        while (end < text_.size() &&
+               (isalnum(static_cast<unsigned char>(text_[end])))) {
+          ++end;
+        }
+        tokens_.push_back({TokenType::kNumber, text_.substr(i, end - i)});
+        i = end;
+        continue;
+      }
+      if (text_.compare(i, 2, ":=") == 0) {
+        tokens_.push_back({TokenType::kPunctuation, text_.substr(i, 2)});
+        i += 2;
+        continue;
+      }
+      if (text_.compare(i, 3, "...") == 0) {
+        tokens_.push_back({TokenType::kPunctuation, text_.substr(i, 3)});
+        i += 3;
+        continue;
+      }
+      if (strchr("{}[]()<>;=,:.*+-", c) != nullptr) {
+        tokens_.push_back({TokenType::kPunctuation, text_.substr(i, 1)});
+        ++i;
+        continue;
+      }
+      return base::ErrStatus("CTF: unexpected character '%c' in metadata", c);
+    }
+    return base::OkStatus();
+  }
+
+  const Token& Peek(size_t ahead = 0) const {
+    static const Token kEofToken{TokenType::kEof, {}};
+    return pos_ + ahead < tokens_.size() ? tokens_[pos_ + ahead] : kEofToken;
+  }
+
+  Token Next() {
+    Token token = Peek();
+    if (pos_ < tokens_.size()) {
+      ++pos_;
+    }
+    return token;
+  }
+
+  bool PeekIs(std::string_view text, size_t ahead = 0) const {
+    const Token& token = Peek(ahead);
+    return token.type != TokenType::kString &&
+           token.type != TokenType::kEof && token.text == text;
+  }
+
+  bool ConsumeIf(std::string_view text) {
+    if (!PeekIs(text)) {
+      return false;
+    }
+    Next();
+    return true;
+  }
+
+  base::Status Expect(std::string_view text) {
+    if (!ConsumeIf(text)) {
+      return base::ErrStatus("CTF: expected '%s' but found '%s' in metadata",
+                             std::string(text).c_str(),
+                             std::string(Peek().text).c_str());
+    }
+    return base::OkStatus();
+  }
+
+  base::StatusOr<std::string> ExpectIdentifier() {
+    Token token = Next();
+    if (token.type != TokenType::kIdentifier) {
+      return base::ErrStatus("CTF: expected identifier but found '%s'",
+                             std::string(token.text).c_str());
+    }
+    return std::string(token.text);
+  }
+
+  static bool IsTypeKeyword(std::string_view text) {
+    return text == "integer" || text == "floating_point" || text == "string" ||
+           text == "struct" || text == "variant" || text == "enum";
+  }
+
+  // Statements.
+
+  base::Status ParseTopLevelStatement() {
+    if (ConsumeIf(";")) {
+      return base::OkStatus();
+    }
+    if (PeekIs("typealias")) {
+      return ParseTypealias();
+    }
+    if (PeekIs("typedef")) {
+      return ParseTypedef();
+    }
+    if (ConsumeIf("trace")) {
+      return ParseBlock([this](const std::string& key) {
+        return ParseTraceAttribute(key);
+      });
+    }
+    if (ConsumeIf("env")) {
+      return ParseBlock(
+          [this](const std::string& key) { return ParseEnvAttribute(key); });
+    }
+    if (ConsumeIf("clock")) {
+      metadata_->clocks.emplace_back();
+      return ParseBlock([this](const std::string& key) {
+        return ParseClockAttribute(key, metadata_->clocks.back());
+      });
+    }
+    if (ConsumeIf("stream")) {
+      CtfStreamClass stream;
+      RETURN_IF_ERROR(ParseBlock([this, &stream](const std::string& key) {
+        return ParseStreamAttribute(key, stream);
+      }));
+      uint64_t id = stream.id;
+      if (!metadata_->streams.Insert(id, std::move(stream)).second) {
+        return base::ErrStatus("CTF: duplicate stream id %" PRIu64, id);
+      }
+      return base::OkStatus();
+    }
+    if (ConsumeIf("event")) {
+      events_.emplace_back();
+      return ParseBlock([this](const std::string& key) {
+        return ParseEventAttribute(key, events_.back());
+      });
+    }
+    if (PeekIs("callsite")) {
+      // Callsite blocks only carry debug information about the location of
+      // the tracepoints in the source code.
+      Next();
+      return SkipBlock();
+    }
+    if (Peek().type == TokenType::kIdentifier && IsTypeKeyword(Peek().text)) {
+      // Named type definition (e.g. "struct foo { ... };").
+      RETURN_IF_ERROR(ParseTypeSpecifier().status());
+      return Expect(";");
+    }
+    return base::ErrStatus("CTF: unexpected token '%s' in metadata",
+                           std::string(Peek().text).c_str());
+  }
+
+  // typealias <type> := <alias name>;
+  base::Status ParseTypealias() {
+    RETURN_IF_ERROR(Expect("typealias"));
+    ASSIGN_OR_RETURN(CtfTypePtr type, ParseTypeSpecifier());
+    RETURN_IF_ERROR(Expect(":="));
+    std::vector<std::string> parts;
+    while (Peek().type == TokenType::kIdentifier) {
+      parts.emplace_back(Next().text);
+    }
+    if (parts.empty()) {
+      return base::ErrStatus("CTF: missing typealias name");
+    }
+    while (ConsumeIf("*")) {
+      // Pointer aliases (e.g. "void *") are encoded as integers.
+    }
+    aliases_[base::Join(parts, " ")] = std::move(type);
+    return Expect(";");
+  }
+
+  // typedef <type> <declarator>;
+  base::Status ParseTypedef() {
+    RETURN_IF_ERROR(Expect("typedef"));
+    ASSIGN_OR_RETURN(CtfTypePtr type, ParseTypeSpecifier(true));
+    ASSIGN_OR_RETURN(Declarator declarator, ParseDeclarator(type));
+    aliases_[declarator.name] = std::move(declarator.type);
+    return Expect(";");
+  }
+
+  // Parses a "{ key = value; key := type; ... };" block, calling
+  // |on_attribute| for each key. |on_attribute| must consume the rest of the
+  // attribute (after the key) including the terminating semicolon.
+  template <typename Fn>
+  base::Status ParseBlock(Fn on_attribute) {
+    RETURN_IF_ERROR(Expect("{"));
+    while (!ConsumeIf("}")) {
+      if (Peek().type == TokenType::kEof) {
+        return base::ErrStatus("CTF: unterminated block in metadata");
+      }
+      if (ConsumeIf(";")) {
+        continue;
+      }
+      if (PeekIs("typealias")) {
+        RETURN_IF_ERROR(ParseTypealias());
+        continue;
+      }
+      if (PeekIs("typedef")) {
+        RETURN_IF_ERROR(ParseTypedef());
+        continue;
+      }
+      ASSIGN_OR_RETURN(std::string key, ParseDottedIdentifier());
+      RETURN_IF_ERROR(on_attribute(key));
+    }
+    ConsumeIf(";");
+    return base::OkStatus();
+  }
+
+  base::Status SkipBlock() {
+    RETURN_IF_ERROR(Expect("{"));
+    for (uint32_t depth = 1; depth > 0;) {
+      Token token = Next();
+      if (token.type == TokenType::kEof) {
+        return base::ErrStatus("CTF: unterminated block in metadata");
+      }
+      if (token.type != TokenType::kPunctuation) {
+        continue;
+      }
+      if (token.text == "{") {
+        ++depth;
+      } else if (token.text == "}") {
+        --depth;
+      }
+    }
+    ConsumeIf(";");
+    return base::OkStatus();
+  }
+
+  base::StatusOr<std::string> ParseDottedIdentifier() {
+    ASSIGN_OR_RETURN(std::string result, ExpectIdentifier());
+    while (ConsumeIf(".")) {
+      ASSIGN_OR_RETURN(std::string part, ExpectIdentifier());
+      result += "." + part;
+    }
+    return result;
+  }
+
+  // Parses "= <value>;".
+  base::StatusOr<Value> ParseAssignedValue() {
+    RETURN_IF_ERROR(Expect("="));
+    Value value;
+    if (Peek().type == TokenType::kIdentifier) {
+      value.type = TokenType::kIdentifier;
+      ASSIGN_OR_RETURN(value.text, ParseDottedIdentifier());
+    } else {
+      Token token = Next();
+      if (token.type != TokenType::kNumber &&
+          token.type != TokenType::kString) {
+        return base::ErrStatus("CTF: unexpected value '%s' in metadata",
+                               std::string(token.text).c_str());
+      }
+      value.type = token.type;
+      value.text = std::string(token.text);
+    }
+    RETURN_IF_ERROR(Expect(";"));
+    return value;
+  }
+
+  // Parses ":= <type>;".
+  base::StatusOr<CtfTypePtr> ParseAssignedType() {
+    RETURN_IF_ERROR(Expect(":="));
+    ASSIGN_OR_RETURN(CtfTypePtr type, ParseTypeSpecifier());
+    RETURN_IF_ERROR(Expect(";"));
+    return type;
+  }
+
+  static base::StatusOr<int64_t> ToInt(const Value& value) {
+    if (value.type != TokenType::kNumber) {
+      return base::ErrStatus("CTF: expected number but found '%s'",
+                             value.text.c_str());
+    }
+    return ParseNumber(value.text);
+  }
+
+  static base::StatusOr<int64_t> ParseNumber(const std::string& raw) {
+    std::string text = raw;
+    // Strip the C integer suffixes (e.g. "1000ULL").
+    while (!text.empty() && (text.back() == 'u' || text.back() == 'U' ||
+                             text.back() == 'l' || text.back() == 'L')) {
+      text.pop_back();
+    }
+    bool negative = !text.empty() && text[0] == '-';
+    std::string digits = negative ? text.substr(1) : text;
+    int base = 10;
+    if (base::StartsWith(digits, "0x") || base::StartsWith(digits, "0X")) {
+      base = 16;
+      digits = digits.substr(2);
+    } else if (digits.size() > 1 && digits[0] == '0') {
+      base = 8;
+      digits = digits.substr(1);
+    }
+    std::optional<uint64_t> value = base::StringToUInt64(digits, base);
+    if (!value) {
+      return base::ErrStatus("CTF: invalid number '%s'", raw.c_str());
+    }
+    auto result = static_cast<int64_t>(*value);
+    return negative ? -result : result;
+  }
+
+  static base::StatusOr<bool> ToBool(const Value& value) {
+    std::string lower = base::ToLower(value.text);
+    if (lower == "true" || lower == "1") {
+      return true;
+    }
+    if (lower == "false" || lower == "0") {
+      return false;
+    }
+    return base::ErrStatus("CTF: invalid boolean '%s'", value.text.c_str());
+  }
+
+  static base::StatusOr<CtfByteOrder> ToByteOrder(const Value& value) {
+    if (value.text == "native") {
+      return CtfByteOrder::kNative;
+    }
+    if (value.text == "le") {
+      return CtfByteOrder::kLittleEndian;
+    }
+    if (value.text == "be" || value.text == "network") {
+      return CtfByteOrder::kBigEndian;
+    }
+    return base::ErrStatus("CTF: invalid byte order '%s'", value.text.c_str());
+  }
+
+  base::Status ParseTraceAttribute(const std::string& key) {
+    if (key == "packet.header") {
+      ASSIGN_OR_RETURN(metadata_->packet_header, ParseAssignedType());
+      return base::OkStatus();
+    }
+    ASSIGN_OR_RETURN(Value value, ParseAssignedValue());
+    if (key == "major") {
+      ASSIGN_OR_RETURN(int64_t major, ToInt(value));
+      metadata_->major = static_cast<uint32_t>(major);
+      if (major != 1) {
+        return base::ErrStatus("CTF: unsupported major version %" PRId64,
+                               major);
+      }
+    } else if (key == "minor") {
+      ASSIGN_OR_RETURN(int64_t minor, ToInt(value));
+      metadata_->minor = static_cast<uint32_t>(minor);
+    } else if (key == "uuid") {
+      metadata_->uuid = value.text;
+    } else if (key == "byte_order") {
+      ASSIGN_OR_RETURN(CtfByteOrder order, ToByteOrder(value));
+      if (order == CtfByteOrder::kNative) {
+        return base::ErrStatus("CTF: trace byte order cannot be native");
+      }
+      metadata_->byte_order = order;
+    }
+    return base::OkStatus();
+  }
+
+  base::Status ParseEnvAttribute(const std::string& key) {
+    ASSIGN_OR_RETURN(Value value, ParseAssignedValue());
+    metadata_->env.emplace_back(key, value.text);
+    return base::OkStatus();
+  }
+
+  base::Status ParseClockAttribute(const std::string& key,
+                                   CtfClockClass& clock) {
+    ASSIGN_OR_RETURN(Value value, ParseAssignedValue());
+    if (key == "name") {
+      clock.name = value.text;
+    } else if (key == "freq") {
+      ASSIGN_OR_RETURN(int64_t freq, ToInt(value));
+      if (freq <= 0) {
+        return base::ErrStatus("CTF: invalid clock frequency %" PRId64, freq);
+      }
+      clock.freq = static_cast<uint64_t>(freq);
+    } else if (key == "offset_s") {
+      ASSIGN_OR_RETURN(clock.offset_s, ToInt(value));
+    } else if (key == "offset") {
+      ASSIGN_OR_RETURN(clock.offset, ToInt(value));
+    }
+    return base::OkStatus();
+  }
+
+  base::Status ParseStreamAttribute(const std::string& key,
+                                    CtfStreamClass& stream) {
+    if (key == "packet.context") {
+      ASSIGN_OR_RETURN(stream.packet_context, ParseAssignedType());
+      return base::OkStatus();
+    }
+    if (key == "event.header") {
+      ASSIGN_OR_RETURN(stream.event_header, ParseAssignedType());
+      return base::OkStatus();
+    }
+    if (key == "event.context") {
+      ASSIGN_OR_RETURN(stream.event_context, ParseAssignedType());
+      return base::OkStatus();
+    }
+    ASSIGN_OR_RETURN(Value value, ParseAssignedValue());
+    if (key == "id") {
+      ASSIGN_OR_RETURN(int64_t id, ToInt(value));
+      stream.id = static_cast<uint64_t>(id);
+    }
+    return base::OkStatus();
+  }
+
+  base::Status ParseEventAttribute(const std::string& key,
+                                   CtfEventClass& event) {
+    if (key == "context") {
+      ASSIGN_OR_RETURN(event.context, ParseAssignedType());
+      return base::OkStatus();
+    }
+    if (key == "fields") {
+      ASSIGN_OR_RETURN(event.fields, ParseAssignedType());
+      return base::OkStatus();
+    }
+    ASSIGN_OR_RETURN(Value value, ParseAssignedValue());
+    if (key == "name") {
+      event.name = value.text;
+    } else if (key == "id") {
+      ASSIGN_OR_RETURN(int64_t id, ToInt(value));
+      event.id = static_cast<uint64_t>(id);
+    } else if (key == "stream_id") {
+      ASSIGN_OR_RETURN(int64_t stream_id, ToInt(value));
+      event.stream_id = static_cast<uint64_t>(stream_id);
+    } else if (key == "loglevel") {
+      ASSIGN_OR_RETURN(event.loglevel, ToInt(value));
+    }
+    return base::OkStatus();
+  }
+
+  base::Status AssignEventsToStreams() {
+    if (metadata_->streams.size() == 0) {
+      // Traces with a single stream are allowed to omit the stream block.
+      metadata_->streams.Insert(0, CtfStreamClass());
+    }
+    for (CtfEventClass& event : events_) {
+      CtfStreamClass* stream = metadata_->streams.Find(event.stream_id);
+      if (!stream) {
+        return base::ErrStatus(
+            "CTF: event '%s' references unknown stream %" PRIu64,
+            event.name.c_str(), event.stream_id);
+      }
+      uint64_t id = event.id;
+      if (!stream->events.Insert(id, std::move(event)).second) {
+        return base::ErrStatus("CTF: duplicate event id %" PRIu64, id);
+      }
+    }
+    return base::OkStatus();
+  }
+
+  // Types.
+
+  // Parses a type specifier. If |has_declarator| is true and the type is
+  // referenced through a (possibly multi-word) alias, the last identifier is
+  // left unconsumed as it is the name of the declared field.
+  base::StatusOr<CtfTypePtr> ParseTypeSpecifier(bool has_declarator = false) {
+    while (ConsumeIf("const") || ConsumeIf("volatile")) {
+    }
+    if (ConsumeIf("integer")) {
+      return ParseInteger();
+    }
+    if (ConsumeIf("floating_point")) {
+      return ParseFloatingPoint();
+    }
+    if (ConsumeIf("string")) {
+      return ParseString();
+    }
+    if (ConsumeIf("struct")) {
+      return ParseStruct();
+    }
+    if (ConsumeIf("variant")) {
+      return ParseVariant();
+    }
+    if (ConsumeIf("enum")) {
+      return ParseEnum();
+    }
+    size_t count = 0;
+    while (Peek(count).type == TokenType::kIdentifier) {
+      ++count;
+    }
+    if (has_declarator && count > 0) {
+      --count;
+    }
+    if (count == 0) {
+      return base::ErrStatus("CTF: expected type but found '%s'",
+                             std::string(Peek().text).c_str());
+    }
+    std::vector<std::string> parts;
+    for (size_t i = 0; i < count; ++i) {
+      parts.emplace_back(Next().text);
+    }
+    std::string name = base::Join(parts, " ");
+    auto it = aliases_.find(name);
+    if (it == aliases_.end()) {
+      return base::ErrStatus("CTF: unknown type '%s'", name.c_str());
+    }
+    return it->second;
+  }
+
+  base::StatusOr<CtfTypePtr> ParseInteger() {
+    auto type = std::make_shared<CtfType>();
+    type->kind = CtfType::Kind::kInteger;
+    std::optional<uint32_t> align;
+    auto on_attribute = [&](const std::string& key,
+                            const Value& value) -> base::Status {
+      if (key == "size") {
+        ASSIGN_OR_RETURN(int64_t size, ToInt(value));
+        if (size <= 0 || size > 64) {
+          return base::ErrStatus("CTF: unsupported integer size %" PRId64,
+                                 size);
+        }
+        type->size_bits = static_cast<uint32_t>(size);
+      } else if (key == "align") {
+        ASSIGN_OR_RETURN(int64_t a, ToInt(value));
+        align = static_cast<uint32_t>(a);
+      } else if (key == "signed") {
+        ASSIGN_OR_RETURN(type->is_signed, ToBool(value));
+      } else if (key == "byte_order") {
+        ASSIGN_OR_RETURN(type->byte_order, ToByteOrder(value));
+      } else if (key == "base") {
+        ASSIGN_OR_RETURN(type->base, ToBase(value));
+      } else if (key == "encoding") {
+        ASSIGN_OR_RETURN(type->encoding, ToEncoding(value));
+      } else if (key == "map") {
+        // The value looks like "clock.<name>.value".
+        std::vector<std::string> parts = base::SplitString(value.text, ".");
+        if (parts.size() != 3 || parts[0] != "clock" || parts[2] != "value") {
+          return base::ErrStatus("CTF: unsupported integer mapping '%s'",
+                                 value.text.c_str());
+        }
+        type->mapped_clock = parts[1];
+      }
+      return base::OkStatus();
+    };
+    RETURN_IF_ERROR(ParseBlockAttributes(on_attribute));
+    if (type->size_bits == 0) {
+      return base::ErrStatus("CTF: integer without size");
+    }
+    type->alignment_bits = align.value_or(type->size_bits % 8 == 0 ? 8 : 1);
+    return CtfTypePtr(std::move(type));
+  }
+
+  base::StatusOr<CtfTypePtr> ParseFloatingPoint() {
+    auto type = std::make_shared<CtfType>();
+    type->kind = CtfType::Kind::kFloatingPoint;
+    type->alignment_bits = 8;
+    auto on_attribute = [&](const std::string& key,
+                            const Value& value) -> base::Status {
+      if (key == "exp_dig") {
+        ASSIGN_OR_RETURN(int64_t v, ToInt(value));
+        type->exp_dig = static_cast<uint32_t>(v);
+      } else if (key == "mant_dig") {
+        ASSIGN_OR_RETURN(int64_t v, ToInt(value));
+        type->mant_dig = static_cast<uint32_t>(v);
+      } else if (key == "align") {
+        ASSIGN_OR_RETURN(int64_t v, ToInt(value));
+        type->alignment_bits = static_cast<uint32_t>(v);
+      } else if (key == "byte_order") {
+        ASSIGN_OR_RETURN(type->byte_order, ToByteOrder(value));
+      }
+      return base::OkStatus();
+    };
+    RETURN_IF_ERROR(ParseBlockAttributes(on_attribute));
+    type->size_bits = type->exp_dig + type->mant_dig;
+    if (type->size_bits != 32 && type->size_bits != 64) {
+      return base::ErrStatus("CTF: unsupported floating point size %u",
+                             type->size_bits);
+    }
+    return CtfTypePtr(std::move(type));
+  }
+
+  base::StatusOr<CtfTypePtr> ParseString() {
+    auto type = std::make_shared<CtfType>();
+    type->kind = CtfType::Kind::kString;
+    type->alignment_bits = 8;
+    type->encoding = CtfEncoding::kUtf8;
+    if (PeekIs("{")) {
+      RETURN_IF_ERROR(ParseBlockAttributes(
+          [&](const std::string& key, const Value& value) -> base::Status {
+            if (key == "encoding") {
+              ASSIGN_OR_RETURN(type->encoding, ToEncoding(value));
+            }
+            return base::OkStatus();
+          }));
+    }
+    return CtfTypePtr(std::move(type));
+  }
+
+  // Parses "{ key = value; ... }" (without trailing semicolon) used by the
+  // integer, floating_point and string type specifiers.
+  template <typename Fn>
+  base::Status ParseBlockAttributes(Fn on_attribute) {
+    RETURN_IF_ERROR(Expect("{"));
+    while (!ConsumeIf("}")) {
+      if (ConsumeIf(";")) {
+        continue;
+      }
+      ASSIGN_OR_RETURN(std::string key, ExpectIdentifier());
+      ASSIGN_OR_RETURN(Value value, ParseAssignedValue());
+      RETURN_IF_ERROR(on_attribute(key, value));
+    }
+    return base::OkStatus();
+  }
+
+  static base::StatusOr<uint32_t> ToBase(const Value& value) {
+    std::string lower = base::ToLower(value.text);
+    if (lower == "decimal" || lower == "dec" || lower == "d" || lower == "i" ||
+        lower == "u" || lower == "10") {
+      return 10u;
+    }
+    if (lower == "hexadecimal" || lower == "hex" || lower == "x" ||
+        lower == "p" || lower == "16") {
+      return 16u;
+    }
+    if (lower == "octal" || lower == "oct" || lower == "o" || lower == "8") {
+      return 8u;
+    }
+    if (lower == "binary" || lower == "b" || lower == "2") {
+      return 2u;
+    }
+    return base::ErrStatus("CTF: invalid integer base '%s'",
+                           value.text.c_str());
+  }
+
+  static base::StatusOr<CtfEncoding> ToEncoding(const Value& value) {
+    std::string upper = base::ToUpper(value.text);
+    if (upper == "NONE") {
+      return CtfEncoding::kNone;
+    }
+    if (upper == "UTF8") {
+      return CtfEncoding::kUtf8;
+    }
+    if (upper == "ASCII") {
+      return CtfEncoding::kAscii;
+    }
+    return base::ErrStatus("CTF: invalid encoding '%s'", value.text.c_str());
+  }
+
+  // struct [name] [{ fields }] [align(n)]
+  base::StatusOr<CtfTypePtr> ParseStruct() {
+    std::optional<std::string> name;
+    if (Peek().type == TokenType::kIdentifier && !PeekIs("align")) {
+      name = std::string(Next().text);
+    }
+    if (!PeekIs("{")) {
+      if (!name) {
+        return base::ErrStatus("CTF: anonymous struct without body");
+      }
+      auto it = structs_.find(*name);
+      if (it == structs_.end()) {
+        return base::ErrStatus("CTF: unknown struct '%s'", name->c_str());
+      }
+      return it->second;
+    }
+    auto type = std::make_shared<CtfType>();
+    type->kind = CtfType::Kind::kStruct;
+    ASSIGN_OR_RETURN(type->fields, ParseFieldList());
+    uint32_t alignment = 1;
+    for (const auto& field : type->fields) {
+      alignment = std::max(alignment, field.type->alignment_bits);
+    }
+    if (ConsumeIf("align")) {
+      RETURN_IF_ERROR(Expect("("));
+      Token token = Next();
+      ASSIGN_OR_RETURN(int64_t align, ParseNumber(std::string(token.text)));
+      RETURN_IF_ERROR(Expect(")"));
+      alignment = std::max(alignment, static_cast<uint32_t>(align));
+    }
+    type->alignment_bits = alignment;
+    CtfTypePtr result(std::move(type));
+    if (name) {
+      structs_[*name] = result;
+    }
+    return result;
+  }
+
+  // variant [name] [<tag>] [{ fields }]
+  base::StatusOr<CtfTypePtr> ParseVariant() {
+    std::optional<std::string> name;
+    if (Peek().type == TokenType::kIdentifier) {
+      name = std::string(Next().text);
+    }
+    std::string tag;
+    if (ConsumeIf("<")) {
+      ASSIGN_OR_RETURN(tag, ParseDottedIdentifier());
+      RETURN_IF_ERROR(Expect(">"));
+    }
+    if (!PeekIs("{")) {
+      if (!name) {
+        return base::ErrStatus("CTF: anonymous variant without body");
+      }
+      auto it = variants_.find(*name);
+      if (it == variants_.end()) {
+        return base::ErrStatus("CTF: unknown variant '%s'", name->c_str());
+      }
+      if (tag.empty()) {
+        return it->second;
+      }
+      auto tagged = std::make_shared<CtfType>(*it->second);
+      tagged->tag = tag;
+      return CtfTypePtr(std::move(tagged));
+    }
+    auto type = std::make_shared<CtfType>();
+    type->kind = CtfType::Kind::kVariant;
+    type->alignment_bits = 1;
+    type->tag = tag;
+    ASSIGN_OR_RETURN(type->fields, ParseFieldList());
+    CtfTypePtr result(std::move(type));
+    if (name) {
+      variants_[*name] = result;
+    }
+    return result;
+  }
+
+  // enum [name] [: container] [{ entries }]
+  base::StatusOr<CtfTypePtr> ParseEnum() {
+    std::optional<std::string> name;
+    if (Peek().type == TokenType::kIdentifier) {
+      name = std::string(Next().text);
+    }
+    CtfTypePtr container;
+    if (ConsumeIf(":")) {
+      ASSIGN_OR_RETURN(container, ParseTypeSpecifier());
+    } else if (PeekIs("{")) {
+      auto it = aliases_.find("int");
+      if (it == aliases_.end()) {
+        return base::ErrStatus("CTF: enum without container type");
+      }
+      container = it->second;
+    }
+    if (!PeekIs("{")) {
+      if (!name) {
+        return base::ErrStatus("CTF: anonymous enum without body");
+      }
+      auto it = enums_.find(*name);
+      if (it == enums_.end()) {
+        return base::ErrStatus("CTF: unknown enum '%s'", name->c_str());
+      }
+      return it->second;
+    }
+    if (container->kind != CtfType::Kind::kInteger) {
+      return base::ErrStatus("CTF: enum container must be an integer");
+    }
+    auto type = std::make_shared<CtfType>(*container);
+    type->kind = CtfType::Kind::kEnum;
+    type->container = container;
+    RETURN_IF_ERROR(Expect("{"));
+    int64_t next_value = 0;
+    while (!ConsumeIf("}")) {
+      Token label = Next();
+      if (label.type != TokenType::kIdentifier &&
+          label.type != TokenType::kString) {
+        return base::ErrStatus("CTF: invalid enum label '%s'",
+                               std::string(label.text).c_str());
+      }
+      CtfType::EnumMapping mapping{std::string(label.text), next_value,
+                                   next_value};
+      if (ConsumeIf("=")) {
+        ASSIGN_OR_RETURN(mapping.begin, ParseNumber(std::string(Next().text)));
+        mapping.end = mapping.begin;
+        if (ConsumeIf("...")) {
+          ASSIGN_OR_RETURN(mapping.end, ParseNumber(std::string(Next().text)));
+        }
+      }
+      next_value = mapping.end + 1;
+      type->mappings.push_back(std::move(mapping));
+      if (!ConsumeIf(",") && !PeekIs("}")) {
+        return base::ErrStatus("CTF: expected ',' in enum but found '%s'",
+                               std::string(Peek().text).c_str());
+      }
+    }
+    CtfTypePtr result(std::move(type));
+    if (name) {
+      enums_[*name] = result;
+    }
+    return result;
+  }
+
+  // Parses "{ <type> <declarator>; ... }" used by structs and variants.
+  base::StatusOr<std::vector<CtfType::Field>> ParseFieldList() {
+    RETURN_IF_ERROR(Expect("{"));
+    std::vector<CtfType::Field> fields;
+    while (!ConsumeIf("}")) {
+      if (Peek().type == TokenType::kEof) {
+        return base::ErrStatus("CTF: unterminated field list");
+      }
+      if (ConsumeIf(";")) {
+        continue;
+      }
+      if (PeekIs("typealias")) {
+        RETURN_IF_ERROR(ParseTypealias());
+        continue;
+      }
+      if (PeekIs("typedef")) {
+        RETURN_IF_ERROR(ParseTypedef());
+        continue;
+      }
+      ASSIGN_OR_RETURN(CtfTypePtr type, ParseTypeSpecifier(true));
+      for (;;) {
+        ASSIGN_OR_RETURN(Declarator declarator, ParseDeclarator(type));
+        fields.push_back(
+            {std::move(declarator.name), std::move(declarator.type)});
+        if (!ConsumeIf(",")) {
+          break;
+        }
+      }
+      RETURN_IF_ERROR(Expect(";"));
+    }
+    return fields;
+  }
+
+  // Parses "<name>[len]...", wrapping |type| into arrays and sequences.
+  base::StatusOr<Declarator> ParseDeclarator(CtfTypePtr type) {
+    Declarator declarator;
+    ASSIGN_OR_RETURN(declarator.name, ExpectIdentifier());
+    std::vector<std::string> lengths;
+    while (ConsumeIf("[")) {
+      if (Peek().type == TokenType::kNumber) {
+        lengths.emplace_back(Next().text);
+      } else {
+        ASSIGN_OR_RETURN(std::string length, ParseDottedIdentifier());
+        lengths.push_back(std::move(length));
+      }
+      RETURN_IF_ERROR(Expect("]"));
+    }
+    // "int a[2][3]" is an array of two arrays of three integers so wrap from
+    // the innermost dimension outwards.
+    for (auto it = lengths.rbegin(); it != lengths.rend(); ++it) {
+      auto wrapped = std::make_shared<CtfType>();
+      wrapped->element = type;
+      wrapped->alignment_bits = type->alignment_bits;
+      if (isdigit(static_cast<unsigned char>((*it)[0]))) {
+        wrapped->kind = CtfType::Kind::kArray;
+        ASSIGN_OR_RETURN(int64_t length, ParseNumber(*it));
+        wrapped->length = static_cast<uint64_t>(length);
+      } else {
+        wrapped->kind = CtfType::Kind::kSequence;
+        wrapped->length_field = *it;
+      }
+      type = std::move(wrapped);
+    }
+    declarator.type = std::move(type);
+    return declarator;
+  }
+
+  std::string_view text_;
+  std::vector<Token> tokens_;
+  size_t pos_ = 0;
+
+  std::unique_ptr<CtfMetadata> metadata_;
+  std::vector<CtfEventClass> events_;
+
+  std::map<std::string, CtfTypePtr> aliases_;
+  std::map<std::string, CtfTypePtr> structs_;
+  std::map<std::string, CtfTypePtr> variants_;
+  std::map<std::string, CtfTypePtr> enums_;
+};
+
+}  // namespace
+
+std::optional<std::string_view> CtfType::EnumLabel(int64_t value) const {
+  for (const EnumMapping& mapping : mappings) {
+    if (value >= mapping.begin && value <= mapping.end) {
+      return std::string_view(mapping.label);
+    }
+  }
+  return std::nullopt;
+}
+
+const CtfType::Field* CtfType::FindField(std::string_view name) const {
+  for (const Field& field : fields) {
+    if (field.name == name) {
+      return &field;
+    }
+  }
+  return nullptr;
+}
+
+bool CtfType::IsCharSequence() const {
+  if (kind != Kind::kArray && kind != Kind::kSequence) {
+    return false;
+  }
+  return element->kind == Kind::kInteger && element->size_bits == 8 &&
+         element->encoding != CtfEncoding::kNone;
+}
+
+int64_t CtfClockClass::CyclesToNs(uint64_t cycles) const {
+  constexpr uint64_t kNsPerSecond = 1000000000;
+  if (freq == kNsPerSecond) {
+    return static_cast<int64_t>(cycles);
+  }
+  uint64_t seconds = cycles / freq;
+  uint64_t remainder = cycles % freq;
+  return static_cast<int64_t>(seconds * kNsPerSecond) +
+         static_cast<int64_t>(static_cast<double>(remainder) * 1e9 /
+                              static_cast<double>(freq));
+}
+
+int64_t CtfClockClass::OffsetNs() const {
+  int64_t offset_ns = offset < 0
+                          ? -CyclesToNs(static_cast<uint64_t>(-offset))
+                          : CyclesToNs(static_cast<uint64_t>(offset));
+  return offset_s * 1000000000 + offset_ns;
+}
+
+const CtfClockClass* CtfMetadata::FindClock(std::string_view name) const {
+  for (const CtfClockClass& clock : clocks) {
+    if (clock.name == name) {
+      return &clock;
+    }
+  }
+  return nullptr;
+}
+
+std::optional<std::string_view> CtfMetadata::FindEnv(
+    std::string_view key) const {
+  for (const auto& [k, v] : env) {
+    if (k == key) {
+      return std::string_view(v);
+    }
+  }
+  return std::nullopt;
+}
+
+bool CtfMetadata::IsKernelTrace() const {
+  return FindEnv("domain") == "kernel";
+}
+
+bool IsCtfMetadata(const uint8_t* data, size_t size) {
+  if (size >= kTextMetadataPrefix.size() &&
+      memcmp(data, kTextMetadataPrefix.data(), kTextMetadataPrefix.size()) ==
+          0) {
+    return true;
+  }
+  return MatchesMagic(data, size, kCtfMetadataPacketMagic).has_value();
+}
+
+bool IsCtfStream(const uint8_t* data, size_t size) {
+  return MatchesMagic(data, size, kCtfStreamPacketMagic).has_value();
+}
+
+base::StatusOr<std::string> ExtractCtfMetadataText(const uint8_t* data,
+                                                   size_t size) {
+  std::optional<bool> big_endian =
+      MatchesMagic(data, size, kCtfMetadataPacketMagic);
+  if (!big_endian) {
+    return std::string(reinterpret_cast<const char*>(data), size);
+  }
+  std::string text;
+  size_t offset = 0;
+  while (offset < size) {
+    if (size - offset < kMetadataPacketHeaderSize) {
+      return base::ErrStatus("CTF: truncated metadata packet header");
+    }
+    const uint8_t* packet = data + offset;
+    if (ReadU32(packet, *big_endian) != kCtfMetadataPacketMagic) {
+      return base::ErrStatus("CTF: invalid metadata packet magic");
+    }
+    // Layout: magic (4), uuid (16), checksum (4), content_size (4),
+    // packet_size (4), compression, encryption and checksum schemes (1 each),
+    // major (1), minor (1). Sizes are expressed in bits.
+    uint32_t content_size = ReadU32(packet + 24, *big_endian) / 8;
+    uint32_t packet_size = ReadU32(packet + 28, *big_endian) / 8;
+    if (packet[32] != 0 || packet[33] != 0) {
+      return base::ErrStatus(
+          "CTF: compressed or encrypted metadata is not supported");
+    }
+    if (content_size < kMetadataPacketHeaderSize ||
+        packet_size < content_size || packet_size > size - offset) {
+      return base::ErrStatus("CTF: invalid metadata packet size");
+    }
+    text.append(reinterpret_cast<const char*>(packet) +
+                    kMetadataPacketHeaderSize,
+                content_size - kMetadataPacketHeaderSize);
+    offset += packet_size;
+  }
+  return text;
+}
+
+base::StatusOr<std::unique_ptr<CtfMetadata>> ParseCtfMetadata(
+    std::string_view tsdl) {
+  return TsdlParser(tsdl).Parse();
+}
+
+}  // namespace perfetto::trace_processor::ctf_importer
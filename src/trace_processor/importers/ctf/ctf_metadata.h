@@ -0,0 +1,205 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_METADATA_H_
+#define SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_METADATA_H_
+
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <optional>
+#include <string>
+#include <string_view>
+#include <utility>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/flat_hash_map.h"
+#include "perfetto/ext/base/status_or.h"
+
+namespace perfetto::trace_processor::ctf_importer {
+
+// In-memory representation of the metadata of a Common Trace Format (CTF 1.8)
+// trace. The metadata describes, using the Trace Stream Description Language
+// (TSDL), the binary layout of every packet and event in the stream files.
+// See https://diamon.org/ctf/v1.8.3/ for the specification.
+
+enum class CtfByteOrder : uint8_t {
+  // The byte order of the trace (as specified in the `trace` block).
+  kNative,
+  kLittleEndian,
+  kBigEndian,
+};
+
+enum class CtfEncoding : uint8_t {
+  kNone,
+  kUtf8,
+  kAscii,
+};
+
+struct CtfType;
+using CtfTypePtr = std::shared_ptr<const CtfType>;
+
+// A single type in the TSDL type system. Only the fields relevant to |kind|
+// are meaningful.
+struct CtfType {
+  enum class Kind : uint8_t {
+    kInteger,
+    kFloatingPoint,
+    kEnum,
+    kString,
+    kStruct,
+    kVariant,
+    kArray,
+    kSequence,
+  };
+
+  struct Field {
+    std::string name;
+    CtfTypePtr type;
+  };
+
+  struct EnumMapping {
+    std::string label;
+    int64_t begin;
+    int64_t end;
+  };
+
+  Kind kind = Kind::kInteger;
+
+  // Alignment of the type in bits.
+  uint32_t alignment_bits = 8;
+
+  // kInteger and kFloatingPoint.
+  uint32_t size_bits = 0;
+  bool is_signed = false;
+  CtfByteOrder byte_order = CtfByteOrder::kNative;
+  CtfEncoding encoding = CtfEncoding::kNone;
+  uint32_t base = 10;
+
+  // kInteger: name of the clock this integer is mapped to (i.e. the integer is
+  // a timestamp in clock cycles), empty otherwise.
+  std::string mapped_clock;
+
+  // kFloatingPoint.
+  uint32_t exp_dig = 0;
+  uint32_t mant_dig = 0;
+
+  // kEnum: the integer type used to store the value.
+  CtfTypePtr container;
+  std::vector<EnumMapping> mappings;
+
+  // kStruct and kVariant.
+  std::vector<Field> fields;
+
+  // kVariant: the (possibly dotted) path of the enum field selecting the
+  // variant option.
+  std::string tag;
+
+  // kArray and kSequence.
+  CtfTypePtr element;
+
+  // kArray: number of elements.
+  uint64_t length = 0;
+
+  // kSequence: the (possibly dotted) path of the integer field storing the
+  // number of elements.
+  std::string length_field;
+
+  // Returns the label associated with |value| for kEnum types.
+  std::optional<std::string_view> EnumLabel(int64_t value) const;
+
+  // Returns the field with the given name for kStruct and kVariant types.
+  const Field* FindField(std::string_view name) const;
+
+  // Returns true if this is an array or sequence of 8-bit characters which
+  // should be decoded as a string.
+  bool IsCharSequence() const;
+};
+
+struct CtfClockClass {
+  std::string name;
+  uint64_t freq = 1000000000;
+  int64_t offset_s = 0;
+  int64_t offset = 0;
+
+  // Converts a number of cycles of this clock into nanoseconds, ignoring the
+  // clock offset.
+  int64_t CyclesToNs(uint64_t cycles) const;
+
+  // Returns the offset of this clock, in nanoseconds.
+  int64_t OffsetNs() const;
+};
+
+struct CtfEventClass {
+  std::string name;
+  uint64_t id = 0;
+  uint64_t stream_id = 0;
+  int64_t loglevel = -1;
+  CtfTypePtr context;
+  CtfTypePtr fields;
+};
+
+struct CtfStreamClass {
+  uint64_t id = 0;
+  CtfTypePtr packet_context;
+  CtfTypePtr event_header;
+  CtfTypePtr event_context;
+  base::FlatHashMap<uint64_t, CtfEventClass> events;
+};
+
+struct CtfMetadata {
+  uint32_t major = 1;
+  uint32_t minor = 8;
+  CtfByteOrder byte_order = CtfByteOrder::kLittleEndian;
+  std::string uuid;
+  CtfTypePtr packet_header;
+  std::vector<CtfClockClass> clocks;
+  base::FlatHashMap<uint64_t, CtfStreamClass> streams;
+  std::vector<std::pair<std::string, std::string>> env;
+
+  const CtfClockClass* FindClock(std::string_view name) const;
+  std::optional<std::string_view> FindEnv(std::string_view key) const;
+
+  // Returns true if the trace was produced by the kernel tracer (as opposed to
+  // a userspace tracer, e.g. LTTng-UST).
+  bool IsKernelTrace() const;
+};
+
+// Magic number at the start of each packet of a packetized metadata file.
+inline constexpr uint32_t kCtfMetadataPacketMagic = 0x75D11D57;
+
+// Magic number at the start of each packet of a stream file.
+inline constexpr uint32_t kCtfStreamPacketMagic = 0xC1FC1FC1;
+
+// Returns true if |data| looks like the start of a CTF metadata file (either
+// plain text or packetized).
+bool IsCtfMetadata(const uint8_t* data, size_t size);
+
+// Returns true if |data| looks like the start of a CTF stream file.
+bool IsCtfStream(const uint8_t* data, size_t size);
+
+// Converts a (possibly packetized) metadata file into TSDL text.
+base::StatusOr<std::string> ExtractCtfMetadataText(const uint8_t* data,
+                                                   size_t size);
+
+// Parses the TSDL text of a metadata file.
+base::StatusOr<std::unique_ptr<CtfMetadata>> ParseCtfMetadata(
+    std::string_view tsdl);
+
+}  // namespace perfetto::trace_processor::ctf_importer
+
+#endif  // SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_METADATA_H_
@@ -0,0 +1,261 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+
+#include "src/trace_processor/importers/ctf/ctf_metadata.h"
+
+#include <cstdint>
+#include <memory>
+#include <string>
+#include <vector>
+
+#include "perfetto/ext/base/status_or.h"
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto::trace_processor::ctf_importer {
+namespace {
+
+// A trimmed down version of the metadata written by LTTng for a kernel
+// session.
+constexpr char kKernelMetadata[] = R"(/* CTF 1.8 */
+
+typealias integer { size = 5; align = 1; signed = false; } := uint5_t;
+typealias integer { size = 8; align = 8; signed = false; } := uint8_t;
+typealias integer { size = 32; align = 8; signed = false; } := uint32_t;
+typealias integer { size = 64; align = 8; signed = false; } := uint64_t;
+typealias integer { size = 27; align = 1; signed = false; } := uint27_t;
+typealias integer { size = 64; align = 8; signed = false; } := unsigned long;
+
+trace {
+  major = 1;
+  minor = 8;
+  uuid = "2a6422d0-6cee-11e0-8c08-cb07d7b3a564";
+  byte_order = le;
+  packet.header := struct {
+    uint32_t magic;
+    uint8_t  uuid[16];
+    uint32_t stream_id;
+    uint64_t stream_instance_id;
+  };
+};
+
+env {
+  hostname = "host";
+  domain = "kernel";
+  tracer_name = "lttng-modules";
+  tracer_major = 2;
+};
+
+clock {
+  name = "monotonic";
+  uuid = "cb07d7b3-a564-2a64-22d0-6cee11e08c08";
+  description = "Monotonic Clock";
+  freq = 1000000000; /* Frequency, in Hz */
+  offset_s = 1700000000;
+  offset = 123;
+};
+
+typealias integer {
+  size = 27; align = 1; signed = false;
+  map = clock.monotonic.value;
+} := uint27_clock_monotonic_t;
+
+typealias integer {
+  size = 64; align = 8; signed = false;
+  map = clock.monotonic.value;
+} := uint64_clock_monotonic_t;
+
+struct packet_context {
+  uint64_clock_monotonic_t timestamp_begin;
+  uint64_clock_monotonic_t timestamp_end;
+  uint64_t content_size;
+  uint64_t packet_size;
+  unsigned long events_discarded;
+  uint32_t cpu_id;
+};
+
+struct event_header_compact {
+  enum : uint5_t { compact = 0 ... 30, extended = 31 } id;
+  variant <id> {
+    struct {
+      uint27_clock_monotonic_t timestamp;
+    } compact;
+    struct {
+      uint32_t id;
+      uint64_clock_monotonic_t timestamp;
+    } extended;
+  } v;
+} align(8);
+
+stream {
+  id = 0;
+  event.header := struct event_header_compact;
+  packet.context := struct packet_context;
+};
+
+event {
+  name = "sched_switch";
+  id = 0;
+  stream_id = 0;
+  fields := struct {
+    integer {
+      size = 8; align = 8; signed = 0; encoding = UTF8; base = 10;
+    } _prev_comm[16];
+    integer { size = 32; align = 8; signed = 1; base = 10; } _prev_tid;
+    enum : integer { size = 64; align = 8; signed = 1; base = 10; } {
+      "TASK_RUNNING" = 0,
+      "TASK_INTERRUPTIBLE" = 1,
+      "TASK_UNINTERRUPTIBLE" = 2,
+    } _prev_state;
+    string _next_comm;
+  };
+};
+)";
+
+TEST(CtfMetadataTest, DetectsTextMetadata) {
+  const std::string text = "/* CTF 1.8 */\ntrace {};";
+  EXPECT_TRUE(IsCtfMetadata(reinterpret_cast<const uint8_t*>(text.data()),
+                            text.size()));
+  EXPECT_FALSE(IsCtfStream(reinterpret_cast<const uint8_t*>(text.data()),
+                           text.size()));
+}
+
+TEST(CtfMetadataTest, DetectsStreamMagic) {
+  const uint8_t le[] = {0xC1, 0xFC, 0x1F, 0xC1};
+  const uint8_t be[] = {0xC1, 0x1F, 0xFC, 0xC1};
+  const uint8_t other[] = {0x00, 0x01, 0x02, 0x03};
+  EXPECT_TRUE(IsCtfStream(le, sizeof(le)));
+  EXPECT_TRUE(IsCtfStream(be, sizeof(be)));
+  EXPECT_FALSE(IsCtfStream(other, sizeof(other)));
+  EXPECT_FALSE(IsCtfStream(le, 2));
+}
+
+TEST(CtfMetadataTest, ExtractsPacketizedMetadata) {
+  const std::string content = "/* CTF 1.8 */ trace { major = 1; };";
+  const uint32_t header_size = 37;
+  const uint32_t content_bits =
+      static_cast<uint32_t>(header_size + content.size()) * 8;
+  const uint32_t packet_bits = content_bits + 3 * 8;
+
+  std::vector<uint8_t> packet(header_size, 0);
+  auto write_u32 = [&](size_t offset, uint32_t value) {
+    for (size_t i = 0; i < 4; ++i) {
+      packet[offset + i] = static_cast<uint8_t>(value >> (8 * i));
+    }
+  };
+  write_u32(0, kCtfMetadataPacketMagic);
+  write_u32(24, content_bits);
+  write_u32(28, packet_bits);
+  packet.insert(packet.end(), content.begin(), content.end());
+  packet.resize(packet_bits / 8, 0);
+
+  // Two identical packets back to back.
+  std::vector<uint8_t> data = packet;
+  data.insert(data.end(), packet.begin(), packet.end());
+
+  ASSERT_TRUE(IsCtfMetadata(data.data(), data.size()));
+  auto text = ExtractCtfMetadataText(data.data(), data.size());
+  ASSERT_TRUE(text.ok()) << text.status().message();
+  EXPECT_EQ(*text, content + content);
+
+  auto truncated = ExtractCtfMetadataText(data.data(), data.size() - 1);
+  EXPECT_FALSE(truncated.ok());
+}
+
+TEST(CtfMetadataTest, ParsesTraceEnvAndClock) {
+  auto metadata = ParseCtfMetadata(kKernelMetadata);
+  ASSERT_TRUE(metadata.ok()) << metadata.status().message();
+  const CtfMetadata& m = **metadata;
+
+  EXPECT_EQ(m.major, 1u);
+  EXPECT_EQ(m.minor, 8u);
+  EXPECT_EQ(m.byte_order, CtfByteOrder::kLittleEndian);
+  EXPECT_EQ(m.uuid, "2a6422d0-6cee-11e0-8c08-cb07d7b3a564");
+  EXPECT_TRUE(m.IsKernelTrace());
+  EXPECT_EQ(m.FindEnv("hostname"), "host");
+  EXPECT_EQ(m.FindEnv("tracer_major"), "2");
+  EXPECT_EQ(m.FindEnv("missing"), std::nullopt);
+
+  const CtfClockClass* clock = m.FindClock("monotonic");
+  ASSERT_NE(clock, nullptr);
+  EXPECT_EQ(clock->freq, 1000000000u);
+  EXPECT_EQ(clock->OffsetNs(), 1700000000ll * 1000000000ll + 123);
+  EXPECT_EQ(m.FindClock("realtime"), nullptr);
+
+  ASSERT_NE(m.packet_header, nullptr);
+  ASSERT_EQ(m.packet_header->fields.size(), 4u);
+  const CtfType::Field* uuid = m.packet_header->FindField("uuid");
+  ASSERT_NE(uuid, nullptr);
+  EXPECT_EQ(uuid->type->kind, CtfType::Kind::kArray);
+  EXPECT_EQ(uuid->type->length, 16u);
+}
+
+TEST(CtfMetadataTest, ParsesStreamAndEventClasses) {
+  auto metadata = ParseCtfMetadata(kKernelMetadata);
+  ASSERT_TRUE(metadata.ok()) << metadata.status().message();
+  const CtfMetadata& m = **metadata;
+
+  const CtfStreamClass* stream = m.streams.Find(0);
+  ASSERT_NE(stream, nullptr);
+  ASSERT_NE(stream->packet_context, nullptr);
+  EXPECT_NE(stream->packet_context->FindField("events_discarded"), nullptr);
+
+  // The event header is a variant selected by an enum whose compact option
+  // holds a 27-bit timestamp mapped to the monotonic clock.
+  ASSERT_NE(stream->event_header, nullptr);
+  const CtfType::Field* id = stream->event_header->FindField("id");
+  ASSERT_NE(id, nullptr);
+  EXPECT_EQ(id->type->kind, CtfType::Kind::kEnum);
+  EXPECT_EQ(id->type->EnumLabel(12), "compact");
+  EXPECT_EQ(id->type->EnumLabel(31), "extended");
+  const CtfType::Field* v = stream->event_header->FindField("v");
+  ASSERT_NE(v, nullptr);
+  EXPECT_EQ(v->type->kind, CtfType::Kind::kVariant);
+  EXPECT_EQ(v->type->tag, "id");
+  const CtfType::Field* compact = v->type->FindField("compact");
+  ASSERT_NE(compact, nullptr);
+  const CtfType::Field* ts = compact->type->FindField("timestamp");
+  ASSERT_NE(ts, nullptr);
+  EXPECT_EQ(ts->type->size_bits, 27u);
+  EXPECT_EQ(ts->type->alignment_bits, 1u);
+  EXPECT_EQ(ts->type->mapped_clock, "monotonic");
+
+  const CtfEventClass* event = stream->events.Find(0);
+  ASSERT_NE(event, nullptr);
+  EXPECT_EQ(event->name, "sched_switch");
+  ASSERT_NE(event->fields, nullptr);
+  ASSERT_EQ(event->fields->fields.size(), 4u);
+
+  const CtfType& comm = *event->fields->fields[0].type;
+  EXPECT_EQ(comm.kind, CtfType::Kind::kArray);
+  EXPECT_TRUE(comm.IsCharSequence());
+
+  const CtfType& state = *event->fields->fields[2].type;
+  EXPECT_EQ(state.kind, CtfType::Kind::kEnum);
+  EXPECT_EQ(state.EnumLabel(2), "TASK_UNINTERRUPTIBLE");
+  EXPECT_EQ(state.EnumLabel(3), std::nullopt);
+
+  EXPECT_EQ(event->fields->fields[3].type->kind, CtfType::Kind::kString);
+}
+
+TEST(CtfMetadataTest, RejectsMalformedMetadata) {
+  EXPECT_FALSE(ParseCtfMetadata("/* CTF 1.8 */ trace { major = 1; ").ok());
+  EXPECT_FALSE(
+      ParseCtfMetadata("/* CTF 1.8 */ event { fields := foo; };").ok());
+}
+
+}  // namespace
+}  // namespace perfetto::trace_processor::ctf_importer
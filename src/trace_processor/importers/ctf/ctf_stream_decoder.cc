@@ -0,0 +1,528 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/ctf/ctf_stream_decoder.h"
+
+#include <cinttypes>
+#include <cstddef>
+#include <cstdint>
+#include <cstring>
+#include <optional>
+#include <string>
+#include <string_view>
+#include <utility>
+#include <variant>
+
+#include "perfetto/base/logging.h"
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/status_macros.h"
+#include "perfetto/ext/base/status_or.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "src/trace_processor/importers/ctf/ctf_metadata.h"
+
+namespace perfetto::trace_processor::ctf_importer {
+namespace {
+
+// Prefixes of absolute field paths (e.g. the length of a sequence in the
+// event payload can be referenced as "event.fields.len").
+constexpr std::string_view kScopePrefixes[] = {
+    "trace.packet.header.", "stream.packet.context.", "stream.event.header.",
+    "stream.event.context.", "event.context.",        "event.fields.",
+};
+
+std::string_view StripScopePrefix(std::string_view path) {
+  for (std::string_view prefix : kScopePrefixes) {
+    if (base::StartsWith(std::string(path), std::string(prefix))) {
+      return path.substr(prefix.size());
+    }
+  }
+  return path;
+}
+
+bool MatchesPath(const std::string& name, std::string_view path) {
+  if (name.size() < path.size()) {
+    return false;
+  }
+  if (name.compare(name.size() - path.size(), path.size(), path) != 0) {
+    return false;
+  }
+  return name.size() == path.size() ||
+         name[name.size() - path.size() - 1] == '.';
+}
+
+const CtfFieldValue* FindInScope(const CtfFields& fields,
+                                 std::string_view path) {
+  for (auto it = fields.rbegin(); it != fields.rend(); ++it) {
+    if (MatchesPath(it->name, path)) {
+      return &*it;
+    }
+  }
+  return nullptr;
+}
+
+std::string JoinPath(const std::string& parent, const std::string& child) {
+  if (parent.empty()) {
+    return child;
+  }
+  return parent + "." + child;
+}
+
+std::string_view LastPathComponent(const std::string& name) {
+  size_t pos = name.rfind('.');
+  return pos == std::string::npos ? std::string_view(name)
+                                  : std::string_view(name).substr(pos + 1);
+}
+
+}  // namespace
+
+// Reads bit fields from a buffer following the CTF rules: in little endian
+// fields, bits are consumed from the least significant bit of each byte; in
+// big endian fields, from the most significant one.
+class CtfStreamDecoder::BitReader {
+ public:
+  BitReader(const uint8_t* data, uint64_t size_bits, uint64_t pos_bits)
+      : data_(data), size_bits_(size_bits), pos_(pos_bits) {}
+
+  void Align(uint32_t alignment_bits) {
+    if (alignment_bits > 1) {
+      uint64_t rem = pos_ % alignment_bits;
+      if (rem != 0) {
+        pos_ += alignment_bits - rem;
+      }
+    }
+    if (pos_ > size_bits_) {
+      overflow_ = true;
+      pos_ = size_bits_;
+    }
+  }
+
+  uint64_t Read(uint32_t bits, bool big_endian) {
+    PERFETTO_DCHECK(bits <= 64);
+    if (bits > size_bits_ - pos_) {
+      overflow_ = true;
+      pos_ = size_bits_;
+      return 0;
+    }
+    uint64_t value = 0;
+    if (pos_ % 8 == 0 && bits % 8 == 0) {
+      // Fast path for byte aligned fields, which is by far the most common
+      // case.
+      const uint8_t* ptr = data_ + pos_ / 8;
+      uint32_t bytes = bits / 8;
+      for (uint32_t i = 0; i < bytes; ++i) {
+        uint32_t idx = big_endian ? i : bytes - 1 - i;
+        value = (value << 8) | ptr[idx];
+      }
+    } else if (big_endian) {
+      for (uint32_t i = 0; i < bits; ++i) {
+        uint64_t bit = pos_ + i;
+        value = (value << 1) | ((data_[bit / 8] >> (7 - bit % 8)) & 1u);
+      }
+    } else {
+      for (uint32_t i = 0; i < bits; ++i) {
+        uint64_t bit = pos_ + i;
+        value |= static_cast<uint64_t>((data_[bit / 8] >> (bit % 8)) & 1u)
+                 << i;
+      }
+    }
+    pos_ += bits;
+    return value;
+  }
+
+  // Reads a NUL terminated string. The terminator is consumed but not
+  // returned.
+  std::string ReadString() {
+    PERFETTO_DCHECK(pos_ % 8 == 0);
+    const char* start = reinterpret_cast<const char*>(data_ + pos_ / 8);
+    size_t max_len = static_cast<size_t>((size_bits_ - pos_) / 8);
+    const void* end = memchr(start, '\0', max_len);
+    if (!end) {
+      overflow_ = true;
+      pos_ = size_bits_;
+      return std::string();
+    }
+    size_t len = static_cast<size_t>(static_cast<const char*>(end) - start);
+    pos_ += (len + 1) * 8;
+    return std::string(start, len);
+  }
+
+  // Reads |len| bytes, truncating the result at the first NUL byte.
+  std::string ReadChars(uint64_t len) {
+    PERFETTO_DCHECK(pos_ % 8 == 0);
+    if (len > (size_bits_ - pos_) / 8) {
+      overflow_ = true;
+      pos_ = size_bits_;
+      return std::string();
+    }
+    const char* start = reinterpret_cast<const char*>(data_ + pos_ / 8);
+    pos_ += len * 8;
+    return std::string(start, strnlen(start, static_cast<size_t>(len)));
+  }
+
+  uint64_t pos() const { return pos_; }
+  uint64_t remaining_bits() const { return size_bits_ - pos_; }
+  bool overflow() const { return overflow_; }
+
+ private:
+  const uint8_t* data_;
+  uint64_t size_bits_;
+  uint64_t pos_;
+  bool overflow_ = false;
+};
+
+std::optional<int64_t> CtfFieldValue::AsInt() const {
+  if (const auto* i = std::get_if<int64_t>(&value)) {
+    return *i;
+  }
+  if (const auto* u = std::get_if<uint64_t>(&value)) {
+    return static_cast<int64_t>(*u);
+  }
+  return std::nullopt;
+}
+
+const CtfFieldValue* FindCtfField(const CtfFields& fields,
+                                  std::string_view name) {
+  for (auto it = fields.rbegin(); it != fields.rend(); ++it) {
+    if (it->name == name) {
+      return &*it;
+    }
+  }
+  return nullptr;
+}
+
+CtfStreamDecoder::CtfStreamDecoder(const CtfMetadata* metadata)
+    : metadata_(metadata) {}
+
+CtfStreamDecoder::~CtfStreamDecoder() = default;
+
+bool CtfStreamDecoder::IsBigEndian(const CtfType& type) const {
+  CtfByteOrder order = type.byte_order == CtfByteOrder::kNative
+                           ? metadata_->byte_order
+                           : type.byte_order;
+  return order == CtfByteOrder::kBigEndian;
+}
+
+const CtfFieldValue* CtfStreamDecoder::LookupField(
+    const CtfFields& current,
+    const std::string& path) const {
+  std::string_view relative = StripScopePrefix(path);
+  if (const auto* field = FindInScope(current, relative); field) {
+    return field;
+  }
+  for (auto it = outer_scopes_.rbegin(); it != outer_scopes_.rend(); ++it) {
+    if (const auto* field = FindInScope(**it, relative); field) {
+      return field;
+    }
+  }
+  return nullptr;
+}
+
+base::Status CtfStreamDecoder::DecodeInteger(const CtfType& type,
+                                             const std::string& name,
+                                             BitReader& reader,
+                                             CtfFields& out) {
+  reader.Align(type.alignment_bits);
+  uint64_t raw = reader.Read(type.size_bits, IsBigEndian(type));
+  CtfFieldValue field;
+  field.name = name;
+  if (type.is_signed) {
+    uint32_t shift = 64 - type.size_bits;
+    field.value = static_cast<int64_t>(raw << shift) >> shift;
+  } else {
+    field.value = raw;
+  }
+
+  // Integers mapped to a clock are timestamps which only encode the low
+  // |size_bits| bits of the clock value: the high bits are inferred from the
+  // previous value of the clock, assuming it wrapped at most once.
+  if (!type.mapped_clock.empty() && !reader.overflow() &&
+      LastPathComponent(name) != "timestamp_end") {
+    uint64_t* current = clock_values_.Find(type.mapped_clock);
+    if (!current) {
+      current = clock_values_.Insert(type.mapped_clock, 0).first;
+    }
+    if (type.size_bits >= 64) {
+      *current = raw;
+    } else {
+      uint64_t mask = (uint64_t(1) << type.size_bits) - 1;
+      uint64_t updated = (*current & ~mask) | raw;
+      if (updated < *current) {
+        updated += mask + 1;
+      }
+      *current = updated;
+    }
+    last_clock_ = metadata_->FindClock(type.mapped_clock);
+  }
+  out.push_back(std::move(field));
+  return base::OkStatus();
+}
+
+base::Status CtfStreamDecoder::DecodeCompound(const CtfType& type,
+                                              const std::string& name,
+                                              BitReader& reader,
+                                              CtfFields& out) {
+  switch (type.kind) {
+    case CtfType::Kind::kStruct:
+      reader.Align(type.alignment_bits);
+      for (const CtfType::Field& field : type.fields) {
+        RETURN_IF_ERROR(
+            DecodeType(*field.type, JoinPath(name, field.name), reader, out));
+      }
+      return base::OkStatus();
+    case CtfType::Kind::kVariant: {
+      const CtfFieldValue* tag = LookupField(out, type.tag);
+      if (!tag || !tag->enum_label) {
+        return base::ErrStatus("CTF: unable to resolve tag '%s' of variant %s",
+                               type.tag.c_str(), name.c_str());
+      }
+      const CtfType::Field* option = type.FindField(*tag->enum_label);
+      if (!option) {
+        return base::ErrStatus("CTF: variant %s has no option '%s'",
+                               name.c_str(), tag->enum_label->c_str());
+      }
+      // The name of the selected option is omitted from the path of the
+      // fields: this way e.g. the timestamp of both the compact and extended
+      // LTTng event headers is called "v.timestamp".
+      return DecodeType(*option->type, name, reader, out);
+    }
+    case CtfType::Kind::kArray:
+    case CtfType::Kind::kSequence: {
+      uint64_t length = type.length;
+      if (type.kind == CtfType::Kind::kSequence) {
+        const CtfFieldValue* length_field =
+            LookupField(out, type.length_field);
+        std::optional<int64_t> value =
+            length_field ? length_field->AsInt() : std::nullopt;
+        if (!value || *value < 0) {
+          return base::ErrStatus(
+              "CTF: unable to resolve length '%s' of sequence %s",
+              type.length_field.c_str(), name.c_str());
+        }
+        length = static_cast<uint64_t>(*value);
+      }
+      reader.Align(type.alignment_bits);
+      if (type.IsCharSequence()) {
+        out.push_back({name, reader.ReadChars(length), std::nullopt});
+        return base::OkStatus();
+      }
+      for (uint64_t i = 0; i < length && !reader.overflow(); ++i) {
+        RETURN_IF_ERROR(DecodeType(*type.element,
+                                   name + "[" + std::to_string(i) + "]",
+                                   reader, out));
+      }
+      return base::OkStatus();
+    }
+    case CtfType::Kind::kInteger:
+    case CtfType::Kind::kFloatingPoint:
+    case CtfType::Kind::kEnum:
+    case CtfType::Kind::kString:
+      break;
+  }
+  PERFETTO_FATAL("For GCC");
+}
+
+base::Status CtfStreamDecoder::DecodeType(const CtfType& type,
+                                          const std::string& name,
+                                          BitReader& reader,
+                                          CtfFields& out) {
+  switch (type.kind) {
+    case CtfType::Kind::kInteger:
+      return DecodeInteger(type, name, reader, out);
+    case CtfType::Kind::kEnum: {
+      RETURN_IF_ERROR(DecodeInteger(*type.container, name, reader, out));
+      CtfFieldValue& field = out.back();
+      std::optional<std::string_view> label =
+          type.EnumLabel(field.AsInt().value_or(0));
+      if (label) {
+        field.enum_label = std::string(*label);
+      }
+      return base::OkStatus();
+    }
+    case CtfType::Kind::kFloatingPoint: {
+      reader.Align(type.alignment_bits);
+      uint64_t raw = reader.Read(type.size_bits, IsBigEndian(type));
+      double value;
+      if (type.size_bits == 32) {
+        float f;
+        uint32_t raw32 = static_cast<uint32_t>(raw);
+        memcpy(&f, &raw32, sizeof(f));
+        value = static_cast<double>(f);
+      } else {
+        memcpy(&value, &raw, sizeof(value));
+      }
+      out.push_back({name, value, std::nullopt});
+      return base::OkStatus();
+    }
+    case CtfType::Kind::kString:
+      reader.Align(8);
+      out.push_back({name, reader.ReadString(), std::nullopt});
+      return base::OkStatus();
+    case CtfType::Kind::kStruct:
+    case CtfType::Kind::kVariant:
+    case CtfType::Kind::kArray:
+    case CtfType::Kind::kSequence:
+      return DecodeCompound(type, name, reader, out);
+  }
+  PERFETTO_FATAL("For GCC");
+}
+
+base::StatusOr<std::optional<CtfPacketInfo>>
+CtfStreamDecoder::ReadPacketPreamble(const uint8_t* data, size_t size) {
+  BitReader reader(data, static_cast<uint64_t>(size) * 8, 0);
+  packet_header_.clear();
+  packet_context_.clear();
+  outer_scopes_.clear();
+
+  CtfPacketInfo info;
+  if (metadata_->packet_header) {
+    RETURN_IF_ERROR(
+        DecodeType(*metadata_->packet_header, "", reader, packet_header_));
+    if (reader.overflow()) {
+      return std::optional<CtfPacketInfo>();
+    }
+    if (const auto* magic = FindCtfField(packet_header_, "magic"); magic) {
+      if (magic->AsInt() != kCtfStreamPacketMagic) {
+        return base::ErrStatus("CTF: invalid packet magic 0x%" PRIx64,
+                               static_cast<uint64_t>(*magic->AsInt()));
+      }
+    }
+    if (const auto* id = FindCtfField(packet_header_, "stream_id"); id) {
+      info.stream_id = static_cast<uint64_t>(id->AsInt().value_or(0));
+    }
+  }
+  const CtfStreamClass* stream = metadata_->streams.Find(info.stream_id);
+  if (!stream) {
+    return base::ErrStatus("CTF: unknown stream id %" PRIu64, info.stream_id);
+  }
+
+  outer_scopes_.push_back(&packet_header_);
+  if (stream->packet_context) {
+    RETURN_IF_ERROR(
+        DecodeType(*stream->packet_context, "", reader, packet_context_));
+    if (reader.overflow()) {
+      outer_scopes_.clear();
+      return std::optional<CtfPacketInfo>();
+    }
+    if (const auto* f = FindCtfField(packet_context_, "content_size"); f) {
+      info.content_size_bits = static_cast<uint64_t>(f->AsInt().value_or(0));
+    }
+    if (const auto* f = FindCtfField(packet_context_, "packet_size"); f) {
+      info.packet_size_bits = static_cast<uint64_t>(f->AsInt().value_or(0));
+    }
+    if (const auto* f = FindCtfField(packet_context_, "cpu_id"); f) {
+      info.cpu = static_cast<uint32_t>(f->AsInt().value_or(0));
+    }
+    if (const auto* f = FindCtfField(packet_context_, "events_discarded"); f) {
+      info.events_discarded = static_cast<uint64_t>(f->AsInt().value_or(0));
+    }
+  }
+  outer_scopes_.push_back(&packet_context_);
+
+  if (info.content_size_bits && !info.packet_size_bits) {
+    info.packet_size_bits = info.content_size_bits;
+  }
+  if (info.packet_size_bits && !info.content_size_bits) {
+    info.content_size_bits = info.packet_size_bits;
+  }
+  if (info.content_size_bits &&
+      (*info.content_size_bits > *info.packet_size_bits ||
+       *info.content_size_bits < reader.pos())) {
+    return base::ErrStatus("CTF: invalid packet size");
+  }
+  info.events_offset_bits = reader.pos();
+  return std::make_optional(info);
+}
+
+base::Status CtfStreamDecoder::ReadPacketEvents(const CtfPacketInfo& info,
+                                                const uint8_t* data,
+                                                size_t size,
+                                                const EventCallback& callback) {
+  const CtfStreamClass* stream = metadata_->streams.Find(info.stream_id);
+  PERFETTO_CHECK(stream);
+  PERFETTO_CHECK(outer_scopes_.size() == 2);
+
+  uint64_t end_bits =
+      info.content_size_bits.value_or(static_cast<uint64_t>(size) * 8);
+  if (end_bits > static_cast<uint64_t>(size) * 8) {
+    return base::ErrStatus("CTF: packet truncated");
+  }
+  BitReader reader(data, end_bits, info.events_offset_bits);
+  CtfFields header;
+  while (reader.remaining_bits() > 0) {
+    header.clear();
+
+    CtfDecodedEvent event;
+    uint64_t event_id = 0;
+    if (stream->event_header) {
+      RETURN_IF_ERROR(DecodeType(*stream->event_header, "", reader, header));
+      for (auto it = header.rbegin(); it != header.rend(); ++it) {
+        if (LastPathComponent(it->name) == "id") {
+          event_id = static_cast<uint64_t>(it->AsInt().value_or(0));
+          break;
+        }
+      }
+    }
+    if (reader.overflow()) {
+      // Some tracers pad the end of the content with zeros which are too
+      // short to contain a whole header: just ignore them.
+      break;
+    }
+
+    event.event_class = stream->events.Find(event_id);
+    if (!event.event_class) {
+      return base::ErrStatus("CTF: unknown event id %" PRIu64
+                             " in stream %" PRIu64,
+                             event_id, info.stream_id);
+    }
+
+    outer_scopes_.push_back(&header);
+    if (stream->event_context) {
+      RETURN_IF_ERROR(DecodeType(*stream->event_context, "", reader,
+                                 event.stream_context));
+    }
+    outer_scopes_.push_back(&event.stream_context);
+    if (event.event_class->context) {
+      RETURN_IF_ERROR(DecodeType(*event.event_class->context, "", reader,
+                                 event.event_context));
+    }
+    outer_scopes_.push_back(&event.event_context);
+    if (event.event_class->fields) {
+      RETURN_IF_ERROR(
+          DecodeType(*event.event_class->fields, "", reader, event.payload));
+    }
+    outer_scopes_.resize(2);
+    if (reader.overflow()) {
+      return base::ErrStatus("CTF: event %s overflows its packet",
+                             event.event_class->name.c_str());
+    }
+
+    // Events whose header does not contain a timestamp (e.g. when the
+    // timestamp is in the packet context only) happened at the same time as
+    // the previous event.
+    if (!last_clock_ && !metadata_->clocks.empty()) {
+      last_clock_ = &metadata_->clocks.front();
+    }
+    if (last_clock_) {
+      uint64_t* cycles = clock_values_.Find(last_clock_->name);
+      event.clock = last_clock_;
+      event.timestamp_ns = last_clock_->CyclesToNs(cycles ? *cycles : 0);
+    }
+    RETURN_IF_ERROR(callback(std::move(event)));
+  }
+  return base::OkStatus();
+}
+
+}  // namespace perfetto::trace_processor::ctf_importer
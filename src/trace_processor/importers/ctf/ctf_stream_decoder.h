@@ -0,0 +1,152 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_STREAM_DECODER_H_
+#define SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_STREAM_DECODER_H_
+
+#include <cstddef>
+#include <cstdint>
+#include <functional>
+#include <optional>
+#include <string>
+#include <string_view>
+#include <variant>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/flat_hash_map.h"
+#include "perfetto/ext/base/status_or.h"
+#include "src/trace_processor/importers/ctf/ctf_metadata.h"
+
+namespace perfetto::trace_processor::ctf_importer {
+
+// A scalar value decoded from a CTF stream. Compound types (structs, arrays,
+// variants) are flattened: the name of each scalar is the dotted path of the
+// field relative to the root of its scope (e.g. "v.timestamp" or "arr[1]").
+struct CtfFieldValue {
+  using Value = std::variant<int64_t, uint64_t, double, std::string>;
+
+  std::string name;
+  Value value;
+
+  // For enum fields, the label matching the value (if any).
+  std::optional<std::string> enum_label;
+
+  std::optional<int64_t> AsInt() const;
+};
+
+using CtfFields = std::vector<CtfFieldValue>;
+
+// Returns the last field named |name| in |fields|.
+const CtfFieldValue* FindCtfField(const CtfFields& fields,
+                                  std::string_view name);
+
+struct CtfPacketInfo {
+  uint64_t stream_id = 0;
+
+  // Offset of the first event of the packet, in bits from the start of the
+  // packet.
+  uint64_t events_offset_bits = 0;
+
+  // Size of the packet content and of the whole packet (including padding),
+  // in bits. Unset if the packet context does not specify them, in which case
+  // the packet extends until the end of the stream file.
+  std::optional<uint64_t> content_size_bits;
+  std::optional<uint64_t> packet_size_bits;
+
+  std::optional<uint32_t> cpu;
+  std::optional<uint64_t> events_discarded;
+};
+
+struct CtfDecodedEvent {
+  const CtfEventClass* event_class = nullptr;
+
+  // Name of the clock the timestamp is expressed in and the timestamp itself
+  // in nanoseconds (not including the clock offset).
+  const CtfClockClass* clock = nullptr;
+  int64_t timestamp_ns = 0;
+
+  CtfFields stream_context;
+  CtfFields event_context;
+  CtfFields payload;
+};
+
+// Decodes the packets of a single CTF stream file. Instances are stateful as
+// CTF timestamps are often encoded relative to the previous one; a new decoder
+// must be used for each stream file.
+class CtfStreamDecoder {
+ public:
+  using EventCallback = std::function<base::Status(CtfDecodedEvent)>;
+
+  explicit CtfStreamDecoder(const CtfMetadata*);
+  ~CtfStreamDecoder();
+
+  // Decodes the packet header and context at the start of |data|. Returns
+  // std::nullopt if |data| is too short to contain them.
+  base::StatusOr<std::optional<CtfPacketInfo>> ReadPacketPreamble(
+      const uint8_t* data,
+      size_t size);
+
+  // Decodes all the events in the packet |data| whose preamble was previously
+  // decoded into |info|, invoking |callback| for each of them.
+  base::Status ReadPacketEvents(const CtfPacketInfo& info,
+                                const uint8_t* data,
+                                size_t size,
+                                const EventCallback& callback);
+
+ private:
+  class BitReader;
+
+  base::Status DecodeType(const CtfType& type,
+                          const std::string& name,
+                          BitReader& reader,
+                          CtfFields& out);
+  base::Status DecodeInteger(const CtfType& type,
+                             const std::string& name,
+                             BitReader& reader,
+                             CtfFields& out);
+  base::Status DecodeCompound(const CtfType& type,
+                              const std::string& name,
+                              BitReader& reader,
+                              CtfFields& out);
+
+  // Finds the value of the field referenced by |path| (e.g. the tag of a
+  // variant or the length of a sequence) in the current scope or in one of
+  // the scopes decoded before it.
+  const CtfFieldValue* LookupField(const CtfFields& current,
+                                   const std::string& path) const;
+
+  bool IsBigEndian(const CtfType& type) const;
+
+  const CtfMetadata* const metadata_;
+
+  // Header and context of the packet being decoded.
+  CtfFields packet_header_;
+  CtfFields packet_context_;
+
+  // Scopes already decoded for the current packet/event, from the outermost
+  // to the innermost.
+  std::vector<const CtfFields*> outer_scopes_;
+
+  // Current value (in cycles) of each clock, updated as timestamps are
+  // decoded.
+  base::FlatHashMap<std::string, uint64_t> clock_values_;
+  const CtfClockClass* last_clock_ = nullptr;
+};
+
+}  // namespace perfetto::trace_processor::ctf_importer
+
+#endif  // SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_STREAM_DECODER_H_
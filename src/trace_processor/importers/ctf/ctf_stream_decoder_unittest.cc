@@ -0,0 +1,287 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+
+#include "src/trace_processor/importers/ctf/ctf_stream_decoder.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <optional>
+#include <string>
+#include <utility>
+#include <variant>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "src/trace_processor/importers/ctf/ctf_metadata.h"
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto::trace_processor::ctf_importer {
+namespace {
+
+constexpr char kMetadata[] = R"(/* CTF 1.8 */
+typealias integer { size = 5; align = 1; signed = false; } := uint5_t;
+typealias integer { size = 8; align = 8; signed = false; } := uint8_t;
+typealias integer { size = 16; align = 8; signed = false; } := uint16_t;
+typealias integer { size = 32; align = 8; signed = false; } := uint32_t;
+typealias integer { size = 64; align = 8; signed = false; } := uint64_t;
+typealias integer { size = 32; align = 8; signed = true; } := int32_t;
+
+trace {
+  major = 1;
+  minor = 8;
+  byte_order = le;
+  packet.header := struct {
+    uint32_t magic;
+    uint32_t stream_id;
+  };
+};
+
+clock {
+  name = "monotonic";
+  freq = 1000000000;
+};
+
+typealias integer {
+  size = 27; align = 1; signed = false;
+  map = clock.monotonic.value;
+} := uint27_clock_monotonic_t;
+
+typealias integer {
+  size = 64; align = 8; signed = false;
+  map = clock.monotonic.value;
+} := uint64_clock_monotonic_t;
+
+stream {
+  id = 0;
+  packet.context := struct {
+    uint64_clock_monotonic_t timestamp_begin;
+    uint64_clock_monotonic_t timestamp_end;
+    uint64_t content_size;
+    uint64_t packet_size;
+    uint64_t events_discarded;
+    uint32_t cpu_id;
+  };
+  event.header := struct {
+    enum : uint5_t { compact = 0 ... 30, extended = 31 } id;
+    variant <id> {
+      struct {
+        uint27_clock_monotonic_t timestamp;
+      } compact;
+      struct {
+        uint32_t id;
+        uint64_clock_monotonic_t timestamp;
+      } extended;
+    } v;
+  } align(8);
+  event.context := struct {
+    int32_t _tid;
+  };
+};
+
+event {
+  name = "sched_waking";
+  id = 0;
+  stream_id = 0;
+  fields := struct {
+    integer { size = 8; align = 8; signed = 0; encoding = UTF8; } _comm[4];
+    int32_t _tid;
+    uint16_t _len;
+    uint8_t _data[_len];
+    string _msg;
+  };
+};
+
+event {
+  name = "irq_handler_entry";
+  id = 40;
+  stream_id = 0;
+  fields := struct {
+    int32_t _irq;
+  };
+};
+)";
+
+constexpr uint64_t kWrap = uint64_t(1) << 27;
+
+// Appends little endian integers and raw bytes to a buffer.
+class PacketWriter {
+ public:
+  template <typename T>
+  void Write(T value) {
+    for (size_t i = 0; i < sizeof(T); ++i) {
+      data_.push_back(static_cast<uint8_t>(static_cast<uint64_t>(value) >>
+                                           (8 * i)));
+    }
+  }
+  void WriteBytes(const std::string& bytes) {
+    data_.insert(data_.end(), bytes.begin(), bytes.end());
+  }
+  void Patch64(size_t offset, uint64_t value) {
+    for (size_t i = 0; i < sizeof(value); ++i) {
+      data_[offset + i] = static_cast<uint8_t>(value >> (8 * i));
+    }
+  }
+  size_t size() const { return data_.size(); }
+  std::vector<uint8_t>& data() { return data_; }
+
+ private:
+  std::vector<uint8_t> data_;
+};
+
+// Offsets of the size fields in the packet context written below.
+constexpr size_t kContentSizeOffset = 24;
+constexpr size_t kPacketSizeOffset = 32;
+
+std::vector<uint8_t> BuildPacket(uint64_t timestamp_begin,
+                                 uint32_t magic = kCtfStreamPacketMagic) {
+  PacketWriter w;
+  w.Write<uint32_t>(magic);
+  w.Write<uint32_t>(0);                 // stream_id
+  w.Write<uint64_t>(timestamp_begin);   // timestamp_begin
+  w.Write<uint64_t>(0);                 // timestamp_end
+  w.Write<uint64_t>(0);                 // content_size (patched below)
+  w.Write<uint64_t>(0);                 // packet_size (patched below)
+  w.Write<uint64_t>(3);                 // events_discarded
+  w.Write<uint32_t>(2);                 // cpu_id
+
+  // Compact header: 5-bit id followed by the low 27 bits of the timestamp.
+  w.Write<uint32_t>(0 | (50u << 5));
+  w.Write<int32_t>(42);  // _tid (stream event context)
+  w.WriteBytes(std::string("ab\0\0", 4));
+  w.Write<int32_t>(7);
+  w.Write<uint16_t>(3);
+  w.WriteBytes("\x01\x02\x03");
+  w.WriteBytes(std::string("hi\0", 3));
+
+  // Extended header: the id is 31, the actual id and the full timestamp
+  // follow.
+  w.Write<uint8_t>(31);
+  w.Write<uint32_t>(40);
+  w.Write<uint64_t>(5 * kWrap + 7);
+  w.Write<int32_t>(43);  // _tid
+  w.Write<int32_t>(9);   // _irq
+
+  uint64_t content_size = w.size();
+  w.WriteBytes(std::string(4, '\0'));  // Padding.
+  w.Patch64(kContentSizeOffset, content_size * 8);
+  w.Patch64(kPacketSizeOffset, w.size() * 8);
+  return std::move(w.data());
+}
+
+class CtfStreamDecoderTest : public ::testing::Test {
+ protected:
+  void SetUp() override {
+    auto metadata = ParseCtfMetadata(kMetadata);
+    ASSERT_TRUE(metadata.ok()) << metadata.status().message();
+    metadata_ = std::move(*metadata);
+    decoder_ = std::make_unique<CtfStreamDecoder>(metadata_.get());
+  }
+
+  std::unique_ptr<CtfMetadata> metadata_;
+  std::unique_ptr<CtfStreamDecoder> decoder_;
+};
+
+TEST_F(CtfStreamDecoderTest, ReadsPacketPreamble) {
+  std::vector<uint8_t> packet = BuildPacket(3 * kWrap + 100);
+  auto info = decoder_->ReadPacketPreamble(packet.data(), packet.size());
+  ASSERT_TRUE(info.ok()) << info.status().message();
+  ASSERT_TRUE(info->has_value());
+
+  EXPECT_EQ((*info)->stream_id, 0u);
+  EXPECT_EQ((*info)->events_offset_bits, 52u * 8);
+  EXPECT_EQ((*info)->content_size_bits, (packet.size() - 4) * 8);
+  EXPECT_EQ((*info)->packet_size_bits, packet.size() * 8);
+  EXPECT_EQ((*info)->cpu, 2u);
+  EXPECT_EQ((*info)->events_discarded, 3u);
+}
+
+TEST_F(CtfStreamDecoderTest, PreambleNeedsMoreData) {
+  std::vector<uint8_t> packet = BuildPacket(0);
+  auto info = decoder_->ReadPacketPreamble(packet.data(), 20);
+  ASSERT_TRUE(info.ok()) << info.status().message();
+  EXPECT_FALSE(info->has_value());
+}
+
+TEST_F(CtfStreamDecoderTest, RejectsInvalidMagic) {
+  std::vector<uint8_t> packet = BuildPacket(0, /*magic=*/0x12345678);
+  auto info = decoder_->ReadPacketPreamble(packet.data(), packet.size());
+  EXPECT_FALSE(info.ok());
+}
+
+TEST_F(CtfStreamDecoderTest, DecodesEvents) {
+  std::vector<uint8_t> packet = BuildPacket(3 * kWrap + 100);
+  auto info = decoder_->ReadPacketPreamble(packet.data(), packet.size());
+  ASSERT_TRUE(info.ok() && info->has_value());
+
+  std::vector<CtfDecodedEvent> events;
+  base::Status status = decoder_->ReadPacketEvents(
+      **info, packet.data(), packet.size(), [&](CtfDecodedEvent event) {
+        events.push_back(std::move(event));
+        return base::OkStatus();
+      });
+  ASSERT_TRUE(status.ok()) << status.message();
+  ASSERT_EQ(events.size(), 2u);
+
+  // The low bits of the compact timestamp (50) are smaller than the ones of
+  // the packet start time (100): the clock must have wrapped.
+  const CtfDecodedEvent& waking = events[0];
+  EXPECT_EQ(waking.event_class->name, "sched_waking");
+  ASSERT_NE(waking.clock, nullptr);
+  EXPECT_EQ(waking.clock->name, "monotonic");
+  EXPECT_EQ(waking.timestamp_ns, static_cast<int64_t>(4 * kWrap + 50));
+
+  const CtfFieldValue* tid = FindCtfField(waking.stream_context, "_tid");
+  ASSERT_NE(tid, nullptr);
+  EXPECT_EQ(tid->AsInt(), 42);
+
+  const CtfFieldValue* comm = FindCtfField(waking.payload, "_comm");
+  ASSERT_NE(comm, nullptr);
+  EXPECT_EQ(std::get<std::string>(comm->value), "ab");
+  EXPECT_EQ(FindCtfField(waking.payload, "_tid")->AsInt(), 7);
+  EXPECT_EQ(FindCtfField(waking.payload, "_len")->AsInt(), 3);
+  ASSERT_NE(FindCtfField(waking.payload, "_data[2]"), nullptr);
+  EXPECT_EQ(FindCtfField(waking.payload, "_data[2]")->AsInt(), 3);
+  EXPECT_EQ(FindCtfField(waking.payload, "_data[3]"), nullptr);
+  const CtfFieldValue* msg = FindCtfField(waking.payload, "_msg");
+  ASSERT_NE(msg, nullptr);
+  EXPECT_EQ(std::get<std::string>(msg->value), "hi");
+
+  const CtfDecodedEvent& irq = events[1];
+  EXPECT_EQ(irq.event_class->name, "irq_handler_entry");
+  EXPECT_EQ(irq.timestamp_ns, static_cast<int64_t>(5 * kWrap + 7));
+  EXPECT_EQ(FindCtfField(irq.stream_context, "_tid")->AsInt(), 43);
+  EXPECT_EQ(FindCtfField(irq.payload, "_irq")->AsInt(), 9);
+}
+
+TEST_F(CtfStreamDecoderTest, PropagatesCallbackErrors) {
+  std::vector<uint8_t> packet = BuildPacket(0);
+  auto info = decoder_->ReadPacketPreamble(packet.data(), packet.size());
+  ASSERT_TRUE(info.ok() && info->has_value());
+
+  size_t count = 0;
+  base::Status status = decoder_->ReadPacketEvents(
+      **info, packet.data(), packet.size(), [&](CtfDecodedEvent) {
+        ++count;
+        return base::ErrStatus("stop");
+      });
+  EXPECT_FALSE(status.ok());
+  EXPECT_EQ(count, 1u);
+}
+
+}  // namespace
+}  // namespace perfetto::trace_processor::ctf_importer
@@ -0,0 +1,423 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/ctf/ctf_trace_parser_impl.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <cstring>
+#include <optional>
+#include <string>
+
+#include "perfetto/base/logging.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "perfetto/ext/base/utils.h"
+#include "perfetto/ext/base/string_view.h"
+#include "src/trace_processor/importers/common/args_tracker.h"
+#include "src/trace_processor/importers/common/cpu_tracker.h"
+#include "src/trace_processor/importers/common/process_tracker.h"
+#include "src/trace_processor/importers/common/slice_tracker.h"
+#include "src/trace_processor/importers/common/thread_state_tracker.h"
+#include "src/trace_processor/importers/common/track_tracker.h"
+#include "src/trace_processor/importers/common/tracks.h"
+#include "src/trace_processor/importers/common/tracks_common.h"
+#include "src/trace_processor/importers/ctf/ctf_event.h"
+#include "src/trace_processor/importers/ftrace/ftrace_sched_event_tracker.h"
+#include "src/trace_processor/storage/stats.h"
+#include "src/trace_processor/storage/trace_storage.h"
+#include "src/trace_processor/tables/metadata_tables_py.h"
+#include "src/trace_processor/types/softirq_action.h"
+#include "src/trace_processor/types/trace_processor_context.h"
+#include "src/trace_processor/types/variadic.h"
+
+namespace perfetto::trace_processor::ctf_importer {
+namespace {
+
+// LTTng reports priorities relative to MAX_RT_PRIO while ftrace (and the
+// |sched| table) uses the raw kernel priority.
+constexpr int32_t kLttngPrioOffset = 100;
+
+// Track for userspace events which cannot be attributed to a thread.
+constexpr auto kUstEventsBlueprint =
+    tracks::SliceBlueprint("ctf_ust_events",
+                           tracks::DimensionBlueprints(),
+                           tracks::StaticNameBlueprint("CTF userspace events"));
+
+const CtfEvent::Field* FindField(const CtfEvent& event, StringId name) {
+  for (const CtfEvent::Field& field : event.fields) {
+    if (field.name == name) {
+      return &field;
+    }
+  }
+  return nullptr;
+}
+
+std::optional<int64_t> GetInt(const CtfEvent& event, StringId name) {
+  const CtfEvent::Field* field = FindField(event, name);
+  if (!field) {
+    return std::nullopt;
+  }
+  switch (field->value.type) {
+    case Variadic::kInt:
+      return field->value.int_value;
+    case Variadic::kUint:
+      return static_cast<int64_t>(field->value.uint_value);
+    case Variadic::kString:
+    case Variadic::kReal:
+    case Variadic::kPointer:
+    case Variadic::kBool:
+    case Variadic::kJson:
+    case Variadic::kNull:
+      return std::nullopt;
+  }
+  PERFETTO_FATAL("For GCC");
+}
+
+StringId GetString(const CtfEvent& event, StringId name) {
+  const CtfEvent::Field* field = FindField(event, name);
+  if (!field || field->value.type != Variadic::kString) {
+    return kNullStringId;
+  }
+  return field->value.string_value;
+}
+
+}  // namespace
+
+CtfTraceParserImpl::CtfTraceParserImpl(TraceProcessorContext* context)
+    : context_(context),
+      sched_switch_id_(context->storage->InternString("sched_switch")),
+      sched_waking_id_(context->storage->InternString("sched_waking")),
+      sched_wakeup_id_(context->storage->InternString("sched_wakeup")),
+      sched_wakeup_new_id_(context->storage->InternString("sched_wakeup_new")),
+      sched_process_fork_id_(
+          context->storage->InternString("sched_process_fork")),
+      sched_process_free_id_(
+          context->storage->InternString("sched_process_free")),
+      statedump_process_state_id_(
+          context->storage->InternString("lttng_statedump_process_state")),
+      irq_handler_entry_id_(
+          context->storage->InternString("irq_handler_entry")),
+      irq_handler_exit_id_(context->storage->InternString("irq_handler_exit")),
+      irq_softirq_entry_id_(
+          context->storage->InternString("irq_softirq_entry")),
+      irq_softirq_exit_id_(context->storage->InternString("irq_softirq_exit")),
+      prev_comm_id_(context->storage->InternString("prev_comm")),
+      prev_tid_id_(context->storage->InternString("prev_tid")),
+      prev_prio_id_(context->storage->InternString("prev_prio")),
+      prev_state_id_(context->storage->InternString("prev_state")),
+      next_comm_id_(context->storage->InternString("next_comm")),
+      next_tid_id_(context->storage->InternString("next_tid")),
+      next_prio_id_(context->storage->InternString("next_prio")),
+      comm_id_(context->storage->InternString("comm")),
+      tid_id_(context->storage->InternString("tid")),
+      pid_id_(context->storage->InternString("pid")),
+      name_id_(context->storage->InternString("name")),
+      parent_tid_id_(context->storage->InternString("parent_tid")),
+      child_comm_id_(context->storage->InternString("child_comm")),
+      child_tid_id_(context->storage->InternString("child_tid")),
+      child_pid_id_(context->storage->InternString("child_pid")),
+      irq_id_(context->storage->InternString("irq")),
+      ret_id_(context->storage->InternString("ret")),
+      vec_id_(context->storage->InternString("vec")) {}
+
+CtfTraceParserImpl::~CtfTraceParserImpl() = default;
+
+void CtfTraceParserImpl::ParseCtfEvent(int64_t ts, CtfEvent event) {
+  if (event.is_kernel) {
+    ParseKernelEvent(ts, event);
+  } else {
+    ParseUserspaceEvent(ts, event);
+  }
+}
+
+void CtfTraceParserImpl::ParseKernelEvent(int64_t ts, const CtfEvent& event) {
+  // All the events of the LTTng kernel tracer are emitted in per-CPU streams.
+  uint32_t cpu = event.cpu.value_or(0);
+  if (event.name == sched_switch_id_) {
+    ParseSchedSwitch(ts, cpu, event);
+  } else if (event.name == sched_waking_id_ || event.name == sched_wakeup_id_ ||
+             event.name == sched_wakeup_new_id_) {
+    ParseSchedWaking(ts, cpu, event);
+  } else if (event.name == sched_process_fork_id_) {
+    ParseSchedProcessFork(ts, event);
+  } else if (event.name == sched_process_free_id_) {
+    ParseSchedProcessFree(ts, event);
+  } else if (event.name == statedump_process_state_id_) {
+    ParseStatedumpProcessState(event);
+  } else if (event.name == irq_handler_entry_id_) {
+    ParseIrqHandlerEntry(ts, cpu, event);
+  } else if (event.name == irq_handler_exit_id_) {
+    ParseIrqHandlerExit(ts, cpu, event);
+  } else if (event.name == irq_softirq_entry_id_) {
+    ParseSoftIrqEntry(ts, cpu, event);
+  } else if (event.name == irq_softirq_exit_id_) {
+    ParseSoftIrqExit(ts, cpu, event);
+  }
+
+  // sched_switch populates the |ftrace_event| table by itself.
+  if (event.name != sched_switch_id_) {
+    ParseGenericKernelEvent(ts, cpu, event);
+  }
+}
+
+void CtfTraceParserImpl::ParseSchedSwitch(int64_t ts,
+                                          uint32_t cpu,
+                                          const CtfEvent& event) {
+  int64_t prev_tid = GetInt(event, prev_tid_id_).value_or(0);
+  int64_t next_tid = GetInt(event, next_tid_id_).value_or(0);
+  auto prio = [&event](StringId name) {
+    return static_cast<int32_t>(GetInt(event, name).value_or(0)) +
+           kLttngPrioOffset;
+  };
+  StringId prev_comm = GetString(event, prev_comm_id_);
+  StringId next_comm = GetString(event, next_comm_id_);
+  auto* storage = context_->storage.get();
+  FtraceSchedEventTracker::GetOrCreate(context_)->PushSchedSwitch(
+      cpu, ts, prev_tid,
+      prev_comm.is_null() ? base::StringView() : storage->GetString(prev_comm),
+      prio(prev_prio_id_), GetInt(event, prev_state_id_).value_or(0), next_tid,
+      next_comm.is_null() ? base::StringView() : storage->GetString(next_comm),
+      prio(next_prio_id_));
+  cpu_current_tid_[cpu] = next_tid;
+}
+
+void CtfTraceParserImpl::ParseSchedWaking(int64_t ts,
+                                          uint32_t cpu,
+                                          const CtfEvent& event) {
+  std::optional<int64_t> wakee_tid = GetInt(event, tid_id_);
+  if (!wakee_tid) {
+    return;
+  }
+  auto* process_tracker = context_->process_tracker.get();
+  UniqueTid wakee_utid = process_tracker->GetOrCreateThread(*wakee_tid);
+  if (StringId comm = GetString(event, comm_id_); !comm.is_null()) {
+    process_tracker->UpdateThreadName(wakee_utid, comm,
+                                      ThreadNamePriority::kFtrace);
+  }
+  UniqueTid waker_utid = process_tracker->GetOrCreateThread(
+      GetTid(cpu, event).value_or(0));
+  ThreadStateTracker::GetOrCreate(context_)->PushWakingEvent(ts, wakee_utid,
+                                                             waker_utid);
+}
+
+void CtfTraceParserImpl::ParseSchedProcessFork(int64_t ts,
+                                               const CtfEvent& event) {
+  std::optional<int64_t> parent_tid = GetInt(event, parent_tid_id_);
+  std::optional<int64_t> child_tid = GetInt(event, child_tid_id_);
+  std::optional<int64_t> child_pid = GetInt(event, child_pid_id_);
+  if (!parent_tid || !child_tid) {
+    return;
+  }
+  StringId child_comm = GetString(event, child_comm_id_);
+  auto* proc_tracker = context_->process_tracker.get();
+
+  // Unlike task_newtask, LTTng reports the pid of the child: a new process was
+  // created if the child is the main thread of its process.
+  UniqueTid new_utid;
+  if (!child_pid || *child_pid == *child_tid) {
+    proc_tracker->StartNewProcess(ts, *parent_tid, *child_tid, child_comm,
+                                  ThreadNamePriority::kFtrace);
+    new_utid = proc_tracker->GetOrCreateThread(*child_tid);
+  } else {
+    new_utid = proc_tracker->StartNewThread(ts, *child_tid);
+    proc_tracker->UpdateThreadName(new_utid, child_comm,
+                                   ThreadNamePriority::kFtrace);
+    proc_tracker->UpdateThread(*child_tid, *child_pid);
+  }
+  UniqueTid parent_utid = proc_tracker->GetOrCreateThread(*parent_tid);
+  ThreadStateTracker::GetOrCreate(context_)->PushNewTaskEvent(ts, new_utid,
+                                                              parent_utid);
+}
+
+void CtfTraceParserImpl::ParseSchedProcessFree(int64_t ts,
+                                               const CtfEvent& event) {
+  if (std::optional<int64_t> tid = GetInt(event, tid_id_); tid) {
+    context_->process_tracker->EndThread(ts, *tid);
+  }
+}
+
+void CtfTraceParserImpl::ParseStatedumpProcessState(const CtfEvent& event) {
+  // Emitted by LTTng for every thread alive when the tracing session starts.
+  std::optional<int64_t> tid = GetInt(event, tid_id_);
+  std::optional<int64_t> pid = GetInt(event, pid_id_);
+  if (!tid || !pid) {
+    return;
+  }
+  context_->process_tracker->UpdateThread(*tid, *pid);
+  if (StringId name = GetString(event, name_id_); !name.is_null()) {
+    context_->process_tracker->UpdateThreadNameAndMaybeProcessName(
+        *tid, name, ThreadNamePriority::kFtrace);
+  }
+}
+
+void CtfTraceParserImpl::ParseIrqHandlerEntry(int64_t ts,
+                                              uint32_t cpu,
+                                              const CtfEvent& event) {
+  TrackId track = context_->track_tracker->InternTrack(tracks::kCpuIrqBlueprint,
+                                                       tracks::Dimensions(cpu));
+  base::StringView irq_name;
+  if (StringId name = GetString(event, name_id_); !name.is_null()) {
+    irq_name = context_->storage->GetString(name);
+  }
+  base::StackString<255> slice_name("IRQ (%.*s)", int(irq_name.size()),
+                                    irq_name.data());
+  StringId slice_name_id =
+      context_->storage->InternString(slice_name.string_view());
+  context_->slice_tracker->Begin(ts, track, irq_id_, slice_name_id);
+}
+
+void CtfTraceParserImpl::ParseIrqHandlerExit(int64_t ts,
+                                             uint32_t cpu,
+                                             const CtfEvent& event) {
+  TrackId track = context_->track_tracker->InternTrack(tracks::kCpuIrqBlueprint,
+                                                       tracks::Dimensions(cpu));
+  std::optional<int64_t> ret = GetInt(event, ret_id_);
+  context_->slice_tracker->End(
+      ts, track, irq_id_, {}, [&, this](ArgsTracker::BoundInserter* inserter) {
+        inserter->AddArg(ret_id_,
+                         Variadic::String(context_->storage->InternString(
+                             ret == 1 ? "handled" : "unhandled")));
+      });
+}
+
+void CtfTraceParserImpl::ParseSoftIrqEntry(int64_t ts,
+                                           uint32_t cpu,
+                                           const CtfEvent& event) {
+  std::optional<int64_t> vec = GetInt(event, vec_id_);
+  if (!vec || *vec < 0 ||
+      static_cast<size_t>(*vec) >= base::ArraySize(kActionNames)) {
+    return;
+  }
+  TrackId track = context_->track_tracker->InternTrack(
+      tracks::kCpuSoftIrqBlueprint, tracks::Dimensions(cpu));
+  StringId slice_name_id =
+      context_->storage->InternString(kActionNames[*vec]);
+  context_->slice_tracker->Begin(ts, track, irq_id_, slice_name_id);
+}
+
+void CtfTraceParserImpl::ParseSoftIrqExit(int64_t ts,
+                                          uint32_t cpu,
+                                          const CtfEvent& event) {
+  TrackId track = context_->track_tracker->InternTrack(
+      tracks::kCpuSoftIrqBlueprint, tracks::Dimensions(cpu));
+  std::optional<int64_t> vec = GetInt(event, vec_id_);
+  context_->slice_tracker->End(
+      ts, track, irq_id_, {}, [&, this](ArgsTracker::BoundInserter* inserter) {
+        inserter->AddArg(vec_id_, Variadic::Integer(vec.value_or(0)));
+      });
+}
+
+void CtfTraceParserImpl::ParseGenericKernelEvent(int64_t ts,
+                                                 uint32_t cpu,
+                                                 const CtfEvent& event) {
+  if (PERFETTO_UNLIKELY(!context_->config.ingest_ftrace_in_raw_table))
+    return;
+
+  UniqueTid utid = context_->process_tracker->GetOrCreateThread(
+      GetTid(cpu, event).value_or(0));
+  auto ucpu = context_->cpu_tracker->GetOrCreateCpu(cpu);
+  tables::FtraceEventTable::Id id =
+      context_->storage->mutable_ftrace_event_table()
+          ->Insert({ts, event.name, utid, {}, {}, ucpu})
+          .id;
+  auto inserter = context_->args_tracker->AddArgsTo(id);
+  for (const CtfEvent::Field& field : event.fields) {
+    inserter.AddArg(field.name, field.value);
+  }
+}
+
+void CtfTraceParserImpl::ParseUserspaceEvent(int64_t ts,
+                                             const CtfEvent& event) {
+  TrackId track;
+  if (event.tid) {
+    auto* process_tracker = context_->process_tracker.get();
+    UniqueTid utid = event.pid
+                         ? process_tracker->UpdateThread(*event.tid, *event.pid)
+                         : process_tracker->GetOrCreateThread(*event.tid);
+    if (event.comm) {
+      process_tracker->UpdateThreadNameAndMaybeProcessName(
+          *event.tid, *event.comm, ThreadNamePriority::kOther);
+    }
+    track = context_->track_tracker->InternThreadTrack(utid);
+  } else {
+    context_->storage->IncrementStats(stats::ctf_ust_event_without_tid);
+    track = context_->track_tracker->InternTrack(kUstEventsBlueprint);
+  }
+
+  // Tracepoints are named "<provider>:<name>": use the provider as category.
+  std::string full_name =
+      context_->storage->GetString(event.name).ToStdString();
+  StringId category = kNullStringId;
+  std::string name = full_name;
+  if (size_t colon = full_name.find(':'); colon != std::string::npos) {
+    category = context_->storage->InternString(
+        base::StringView(full_name.data(), colon));
+    name = full_name.substr(colon + 1);
+  }
+
+  auto args_callback = [&event, this](ArgsTracker::BoundInserter* inserter) {
+    for (const CtfEvent::Field& field : event.fields) {
+      inserter->AddArg(ArgKey(field.name), field.value);
+    }
+  };
+
+  // By convention, pairs of tracepoints with the "_entry"/"_exit" (or
+  // "_begin"/"_end", or "_start"/"_stop") suffixes delimit a slice. Any other
+  // tracepoint is an instant.
+  for (const char* suffix : {"_entry", "_begin", "_start"}) {
+    if (base::EndsWith(name, suffix)) {
+      StringId slice_name = context_->storage->InternString(
+          base::StringView(name.data(), name.size() - strlen(suffix)));
+      context_->slice_tracker->Begin(ts, track, category, slice_name,
+                                     args_callback);
+      return;
+    }
+  }
+  for (const char* suffix : {"_exit", "_end", "_stop"}) {
+    if (base::EndsWith(name, suffix)) {
+      context_->slice_tracker->End(ts, track, {}, {}, args_callback);
+      return;
+    }
+  }
+  context_->slice_tracker->Scoped(ts, track, category,
+                                  context_->storage->InternString(
+                                      base::StringView(name)),
+                                  0, args_callback);
+}
+
+std::optional<int64_t> CtfTraceParserImpl::GetTid(uint32_t cpu,
+                                                  const CtfEvent& event) {
+  if (event.tid) {
+    return event.tid;
+  }
+  if (int64_t* tid = cpu_current_tid_.Find(cpu); tid) {
+    return *tid;
+  }
+  return std::nullopt;
+}
+
+StringId CtfTraceParserImpl::ArgKey(StringId field_name) {
+  if (StringId* key = arg_keys_.Find(field_name); key) {
+    return *key;
+  }
+  std::string key =
+      "args." + context_->storage->GetString(field_name).ToStdString();
+  StringId key_id = context_->storage->InternString(base::StringView(key));
+  arg_keys_.Insert(field_name, key_id);
+  return key_id;
+}
+
+}  // namespace perfetto::trace_processor::ctf_importer
@@ -0,0 +1,104 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_TRACE_PARSER_IMPL_H_
+#define SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_TRACE_PARSER_IMPL_H_
+
+#include <cstdint>
+#include <optional>
+
+#include "perfetto/ext/base/flat_hash_map.h"
+#include "src/trace_processor/importers/common/trace_parser.h"
+#include "src/trace_processor/importers/ctf/ctf_event.h"
+#include "src/trace_processor/storage/trace_storage.h"
+#include "src/trace_processor/types/trace_processor_context.h"
+
+namespace perfetto::trace_processor::ctf_importer {
+
+// Imports the events of CTF traces. The well known events of the LTTng kernel
+// tracer (scheduling, irqs, ...) are imported in the same tables as their
+// ftrace equivalent, all other kernel events end up in the |ftrace_event|
+// table. Userspace events are imported as slices on the track of the thread
+// which emitted them.
+class CtfTraceParserImpl : public CtfTraceParser {
+ public:
+  explicit CtfTraceParserImpl(TraceProcessorContext*);
+  ~CtfTraceParserImpl() override;
+
+  void ParseCtfEvent(int64_t ts, CtfEvent) override;
+
+ private:
+  void ParseKernelEvent(int64_t ts, const CtfEvent&);
+  void ParseSchedSwitch(int64_t ts, uint32_t cpu, const CtfEvent&);
+  void ParseSchedWaking(int64_t ts, uint32_t cpu, const CtfEvent&);
+  void ParseSchedProcessFork(int64_t ts, const CtfEvent&);
+  void ParseSchedProcessFree(int64_t ts, const CtfEvent&);
+  void ParseStatedumpProcessState(const CtfEvent&);
+  void ParseIrqHandlerEntry(int64_t ts, uint32_t cpu, const CtfEvent&);
+  void ParseIrqHandlerExit(int64_t ts, uint32_t cpu, const CtfEvent&);
+  void ParseSoftIrqEntry(int64_t ts, uint32_t cpu, const CtfEvent&);
+  void ParseSoftIrqExit(int64_t ts, uint32_t cpu, const CtfEvent&);
+  void ParseGenericKernelEvent(int64_t ts, uint32_t cpu, const CtfEvent&);
+  void ParseUserspaceEvent(int64_t ts, const CtfEvent&);
+
+  // Returns the tid of the thread which emitted |event|.
+  std::optional<int64_t> GetTid(uint32_t cpu, const CtfEvent& event);
+
+  StringId ArgKey(StringId field_name);
+
+  TraceProcessorContext* const context_;
+
+  // The thread currently running on each CPU, based on sched_switch events.
+  base::FlatHashMap<uint32_t, int64_t> cpu_current_tid_;
+
+  // Cache of "args.<field name>" keys.
+  base::FlatHashMap<StringId, StringId> arg_keys_;
+
+  const StringId sched_switch_id_;
+  const StringId sched_waking_id_;
+  const StringId sched_wakeup_id_;
+  const StringId sched_wakeup_new_id_;
+  const StringId sched_process_fork_id_;
+  const StringId sched_process_free_id_;
+  const StringId statedump_process_state_id_;
+  const StringId irq_handler_entry_id_;
+  const StringId irq_handler_exit_id_;
+  const StringId irq_softirq_entry_id_;
+  const StringId irq_softirq_exit_id_;
+
+  const StringId prev_comm_id_;
+  const StringId prev_tid_id_;
+  const StringId prev_prio_id_;
+  const StringId prev_state_id_;
+  const StringId next_comm_id_;
+  const StringId next_tid_id_;
+  const StringId next_prio_id_;
+  const StringId comm_id_;
+  const StringId tid_id_;
+  const StringId pid_id_;
+  const StringId name_id_;
+  const StringId parent_tid_id_;
+  const StringId child_comm_id_;
+  const StringId child_tid_id_;
+  const StringId child_pid_id_;
+  const StringId irq_id_;
+  const StringId ret_id_;
+  const StringId vec_id_;
+};
+
+}  // namespace perfetto::trace_processor::ctf_importer
+
+#endif  // SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_TRACE_PARSER_IMPL_H_
@@ -0,0 +1,236 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/ctf/ctf_trace_tokenizer.h"
+
+#include <algorithm>
+#include <cinttypes>
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <optional>
+#include <string>
+#include <utility>
+#include <variant>
+
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/status_macros.h"
+#include "perfetto/ext/base/status_or.h"
+#include "perfetto/ext/base/string_view.h"
+#include "perfetto/trace_processor/trace_blob_view.h"
+#include "src/trace_processor/importers/ctf/ctf_event.h"
+#include "src/trace_processor/importers/ctf/ctf_metadata.h"
+#include "src/trace_processor/importers/ctf/ctf_stream_decoder.h"
+#include "src/trace_processor/importers/ctf/ctf_tracker.h"
+#include "src/trace_processor/sorter/trace_sorter.h"
+#include "src/trace_processor/storage/stats.h"
+#include "src/trace_processor/storage/trace_storage.h"
+#include "src/trace_processor/types/trace_processor_context.h"
+#include "src/trace_processor/types/variadic.h"
+
+namespace perfetto::trace_processor::ctf_importer {
+namespace {
+
+// Upper bound for the size of the packet header and context. Packets are
+// usually a few pages long while their header and context only take a few
+// dozen bytes.
+constexpr size_t kMaxPacketPreambleSize = 64 * 1024;
+
+// LTTng prefixes all field names with an underscore to avoid clashes with
+// TSDL keywords.
+base::StringView FieldName(const std::string& name) {
+  base::StringView view(name);
+  return !view.empty() && view.at(0) == '_' ? view.substr(1) : view;
+}
+
+}  // namespace
+
+CtfTraceTokenizer::CtfTraceTokenizer(TraceProcessorContext* ctx)
+    : context_(ctx) {}
+CtfTraceTokenizer::~CtfTraceTokenizer() = default;
+
+base::Status CtfTraceTokenizer::Parse(TraceBlobView blob) {
+  reader_.PushBack(std::move(blob));
+  if (file_type_ == FileType::kUnknown) {
+    auto magic = reader_.SliceOff(reader_.start_offset(), sizeof(uint32_t));
+    if (!magic) {
+      return base::OkStatus();
+    }
+    file_type_ = IsCtfStream(magic->data(), magic->size())
+                     ? FileType::kStream
+                     : FileType::kMetadata;
+  }
+  // The metadata is parsed all at once at the end of the file.
+  if (file_type_ == FileType::kMetadata) {
+    return base::OkStatus();
+  }
+  return ParseStreamPackets(/*end_of_file=*/false);
+}
+
+base::Status CtfTraceTokenizer::NotifyEndOfFile() {
+  switch (file_type_) {
+    case FileType::kUnknown:
+      return base::ErrStatus("CTF: file is too short");
+    case FileType::kMetadata: {
+      auto data = reader_.SliceOff(reader_.start_offset(), reader_.avail());
+      PERFETTO_CHECK(data);
+      ASSIGN_OR_RETURN(std::string tsdl,
+                       ExtractCtfMetadataText(data->data(), data->size()));
+      ASSIGN_OR_RETURN(std::unique_ptr<CtfMetadata> metadata,
+                       ParseCtfMetadata(tsdl));
+      return CtfTracker::GetOrCreate(context_)->SetMetadata(
+          std::move(metadata));
+    }
+    case FileType::kStream:
+      RETURN_IF_ERROR(ParseStreamPackets(/*end_of_file=*/true));
+      if (reader_.avail() != 0) {
+        return base::ErrStatus("CTF: stream file ends with a partial packet");
+      }
+      return base::OkStatus();
+  }
+  PERFETTO_FATAL("For GCC");
+}
+
+base::Status CtfTraceTokenizer::ParseStreamPackets(bool end_of_file) {
+  if (!decoder_ && !skip_stream_) {
+    metadata_ = CtfTracker::GetOrCreate(context_)->metadata();
+    if (metadata_) {
+      decoder_ = std::make_unique<CtfStreamDecoder>(metadata_);
+    } else {
+      context_->storage->IncrementStats(stats::ctf_stream_without_metadata);
+      skip_stream_ = true;
+    }
+  }
+  if (skip_stream_) {
+    reader_.PopFrontBytes(reader_.avail());
+    return base::OkStatus();
+  }
+
+  while (reader_.avail() > 0) {
+    size_t preamble_size = std::min(reader_.avail(), kMaxPacketPreambleSize);
+    auto preamble = reader_.SliceOff(reader_.start_offset(), preamble_size);
+    PERFETTO_CHECK(preamble);
+    ASSIGN_OR_RETURN(
+        std::optional<CtfPacketInfo> packet,
+        decoder_->ReadPacketPreamble(preamble->data(), preamble->size()));
+    if (!packet) {
+      if (end_of_file || preamble_size == kMaxPacketPreambleSize) {
+        return base::ErrStatus("CTF: truncated packet header");
+      }
+      return base::OkStatus();
+    }
+
+    // Packets without an explicit size extend until the end of the file.
+    if (!packet->packet_size_bits && !end_of_file) {
+      return base::OkStatus();
+    }
+    uint64_t packet_size_bits = packet->packet_size_bits.value_or(
+        static_cast<uint64_t>(reader_.avail()) * 8);
+    if (packet_size_bits == 0 || packet_size_bits % 8 != 0) {
+      return base::ErrStatus("CTF: invalid packet size %" PRIu64 " bits",
+                             packet_size_bits);
+    }
+    auto packet_size = static_cast<size_t>(packet_size_bits / 8);
+    if (reader_.avail() < packet_size) {
+      if (end_of_file) {
+        return base::ErrStatus("CTF: truncated packet");
+      }
+      return base::OkStatus();
+    }
+
+    // The number of discarded events is a running counter for the stream.
+    if (packet->events_discarded) {
+      uint64_t previous = last_events_discarded_.value_or(0);
+      if (*packet->events_discarded > previous) {
+        context_->storage->IncrementStats(
+            stats::ctf_events_discarded,
+            static_cast<int64_t>(*packet->events_discarded - previous));
+      }
+      last_events_discarded_ = packet->events_discarded;
+    }
+
+    auto data = reader_.SliceOff(reader_.start_offset(), packet_size);
+    PERFETTO_CHECK(data);
+    RETURN_IF_ERROR(decoder_->ReadPacketEvents(
+        *packet, data->data(), data->size(), [&](CtfDecodedEvent event) {
+          return PushEvent(*packet, std::move(event));
+        }));
+    reader_.PopFrontBytes(packet_size);
+  }
+  return base::OkStatus();
+}
+
+base::Status CtfTraceTokenizer::PushEvent(const CtfPacketInfo& packet,
+                                          CtfDecodedEvent event) {
+  ASSIGN_OR_RETURN(int64_t ts, CtfTracker::GetOrCreate(context_)->ToTraceTime(
+                                   event.clock, event.timestamp_ns));
+  auto* storage = context_->storage.get();
+
+  CtfEvent evt;
+  evt.name = storage->InternString(base::StringView(event.event_class->name));
+  evt.is_kernel = metadata_->IsKernelTrace();
+  evt.cpu = packet.cpu;
+
+  std::optional<int64_t> vtid;
+  std::optional<int64_t> vpid;
+  for (const CtfFields* scope :
+       {&event.stream_context, &event.event_context}) {
+    for (const CtfFieldValue& field : *scope) {
+      base::StringView name = FieldName(field.name);
+      if (name == "tid") {
+        evt.tid = field.AsInt();
+      } else if (name == "vtid") {
+        vtid = field.AsInt();
+      } else if (name == "pid") {
+        evt.pid = field.AsInt();
+      } else if (name == "vpid") {
+        vpid = field.AsInt();
+      } else if (name == "procname") {
+        if (const auto* comm = std::get_if<std::string>(&field.value); comm) {
+          evt.comm = storage->InternString(base::StringView(*comm));
+        }
+      }
+    }
+  }
+  // The ids in the root namespace are preferred but userspace tracers usually
+  // only have the ones in the namespace of the process.
+  if (!evt.tid) {
+    evt.tid = vtid;
+  }
+  if (!evt.pid) {
+    evt.pid = vpid;
+  }
+
+  evt.fields.reserve(event.payload.size());
+  for (CtfFieldValue& field : event.payload) {
+    StringId name = storage->InternString(FieldName(field.name));
+    Variadic value = Variadic::Null();
+    if (const auto* i = std::get_if<int64_t>(&field.value); i) {
+      value = Variadic::Integer(*i);
+    } else if (const auto* u = std::get_if<uint64_t>(&field.value); u) {
+      value = Variadic::UnsignedInteger(*u);
+    } else if (const auto* d = std::get_if<double>(&field.value); d) {
+      value = Variadic::Real(*d);
+    } else if (const auto* s = std::get_if<std::string>(&field.value); s) {
+      value = Variadic::String(storage->InternString(base::StringView(*s)));
+    }
+    evt.fields.push_back({name, value});
+  }
+  context_->sorter->PushCtfEvent(ts, std::move(evt));
+  return base::OkStatus();
+}
+
+}  // namespace perfetto::trace_processor::ctf_importer
@@ -0,0 +1,69 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_TRACE_TOKENIZER_H_
+#define SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_TRACE_TOKENIZER_H_
+
+#include <cstdint>
+#include <memory>
+#include <optional>
+
+#include "perfetto/base/status.h"
+#include "perfetto/trace_processor/trace_blob_view.h"
+#include "src/trace_processor/importers/common/chunked_trace_reader.h"
+#include "src/trace_processor/importers/ctf/ctf_metadata.h"
+#include "src/trace_processor/importers/ctf/ctf_stream_decoder.h"
+#include "src/trace_processor/types/trace_processor_context.h"
+#include "src/trace_processor/util/trace_blob_view_reader.h"
+
+namespace perfetto::trace_processor::ctf_importer {
+
+// Tokenizes a single file of a CTF trace: either the metadata file or one of
+// the stream files. Stream files are decoded using the metadata file which
+// was most recently parsed: when opening a directory (e.g. as a zip or tar
+// archive) the metadata of each directory is always parsed before its stream
+// files.
+class CtfTraceTokenizer : public ChunkedTraceReader {
+ public:
+  explicit CtfTraceTokenizer(TraceProcessorContext*);
+  ~CtfTraceTokenizer() override;
+
+  base::Status Parse(TraceBlobView) override;
+  base::Status NotifyEndOfFile() override;
+
+ private:
+  enum class FileType {
+    kUnknown,
+    kMetadata,
+    kStream,
+  };
+
+  base::Status ParseStreamPackets(bool end_of_file);
+  base::Status PushEvent(const CtfPacketInfo& packet, CtfDecodedEvent event);
+
+  TraceProcessorContext* const context_;
+  util::TraceBlobViewReader reader_;
+  FileType file_type_ = FileType::kUnknown;
+
+  const CtfMetadata* metadata_ = nullptr;
+  std::unique_ptr<CtfStreamDecoder> decoder_;
+  std::optional<uint64_t> last_events_discarded_;
+  bool skip_stream_ = false;
+};
+
+}  // namespace perfetto::trace_processor::ctf_importer
+
+#endif  // SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_TRACE_TOKENIZER_H_
@@ -0,0 +1,72 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/ctf/ctf_tracker.h"
+
+#include <cstdint>
+#include <memory>
+#include <utility>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/status_macros.h"
+#include "perfetto/ext/base/status_or.h"
+#include "src/trace_processor/importers/common/clock_tracker.h"
+#include "src/trace_processor/importers/ctf/ctf_metadata.h"
+#include "src/trace_processor/types/trace_processor_context.h"
+
+#include "protos/perfetto/common/builtin_clock.pbzero.h"
+
+namespace perfetto::trace_processor::ctf_importer {
+namespace {
+
+// Name of the clock LTTng uses for timestamps. Its offset is the difference
+// between CLOCK_REALTIME and CLOCK_MONOTONIC at the start of the trace.
+constexpr char kMonotonicClockName[] = "monotonic";
+
+}  // namespace
+
+CtfTracker::CtfTracker(TraceProcessorContext* context) : context_(context) {}
+
+CtfTracker::~CtfTracker() = default;
+
+base::Status CtfTracker::SetMetadata(std::unique_ptr<CtfMetadata> metadata) {
+  context_->clock_tracker->SetTraceTimeClock(
+      protos::pbzero::BUILTIN_CLOCK_MONOTONIC);
+  if (const CtfClockClass* clock = metadata->FindClock(kMonotonicClockName);
+      clock) {
+    std::vector<ClockTracker::ClockTimestamp> snapshot;
+    snapshot.emplace_back(protos::pbzero::BUILTIN_CLOCK_MONOTONIC, 0);
+    snapshot.emplace_back(protos::pbzero::BUILTIN_CLOCK_REALTIME,
+                          clock->OffsetNs());
+    RETURN_IF_ERROR(context_->clock_tracker->AddSnapshot(snapshot).status());
+  }
+  metadata_ = std::move(metadata);
+  return base::OkStatus();
+}
+
+base::StatusOr<int64_t> CtfTracker::ToTraceTime(const CtfClockClass* clock,
+                                                int64_t ns) {
+  // Timestamps of any other clock are assumed to already be in the trace time
+  // domain once the offset is applied.
+  if (clock && clock->name != kMonotonicClockName) {
+    ns += clock->OffsetNs();
+  }
+  return context_->clock_tracker->ToTraceTime(
+      protos::pbzero::BUILTIN_CLOCK_MONOTONIC, ns);
+}
+
+}  // namespace perfetto::trace_processor::ctf_importer
@@ -0,0 +1,64 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_TRACKER_H_
+#define SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_TRACKER_H_
+
+#include <cstdint>
+#include <memory>
+
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/status_or.h"
+#include "src/trace_processor/importers/ctf/ctf_metadata.h"
+#include "src/trace_processor/types/destructible.h"
+#include "src/trace_processor/types/trace_processor_context.h"
+
+namespace perfetto::trace_processor::ctf_importer {
+
+// Keeps track of the CTF metadata across the files of a trace. A CTF trace is
+// a directory containing a metadata file and several stream files (usually one
+// per CPU) and each of them is tokenized by a different CtfTraceTokenizer.
+class CtfTracker : public Destructible {
+ public:
+  static CtfTracker* GetOrCreate(TraceProcessorContext* context) {
+    if (!context->ctf_tracker) {
+      context->ctf_tracker.reset(new CtfTracker(context));
+    }
+    return static_cast<CtfTracker*>(context->ctf_tracker.get());
+  }
+  ~CtfTracker() override;
+
+  // Sets the metadata describing the stream files which will follow.
+  base::Status SetMetadata(std::unique_ptr<CtfMetadata> metadata);
+
+  // Returns the metadata of the last metadata file seen, if any.
+  const CtfMetadata* metadata() const { return metadata_.get(); }
+
+  // Converts |ns|, a timestamp in nanoseconds (not including the clock
+  // offset) of |clock| into trace time.
+  base::StatusOr<int64_t> ToTraceTime(const CtfClockClass* clock, int64_t ns);
+
+ private:
+  explicit CtfTracker(TraceProcessorContext* context);
+
+  TraceProcessorContext* const context_;
+
+  std::unique_ptr<CtfMetadata> metadata_;
+};
+
+}  // namespace perfetto::trace_processor::ctf_importer
+
+#endif  // SRC_TRACE_PROCESSOR_IMPORTERS_CTF_CTF_TRACKER_H_
@@ -0,0 +1,149 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/folded_stack/folded_stack_line_parser.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <limits>
+#include <optional>
+#include <string_view>
+
+namespace perfetto::trace_processor::folded_stack_importer {
+
+namespace {
+
+// Number of lines looked at when guessing whether a file is a folded stack
+// file.
+constexpr size_t kMaxLinesToCheck = 4;
+
+std::string_view Trim(std::string_view str) {
+  constexpr std::string_view kWhitespace = " \t\r\n";
+  size_t start = str.find_first_not_of(kWhitespace);
+  if (start == std::string_view::npos) {
+    return {};
+  }
+  size_t end = str.find_last_not_of(kWhitespace);
+  return str.substr(start, end - start + 1);
+}
+
+template <typename T>
+std::optional<T> ParseUnsigned(std::string_view str) {
+  if (str.empty() || str.size() > 19) {
+    return std::nullopt;
+  }
+  uint64_t value = 0;
+  for (char c : str) {
+    if (c < '0' || c > '9') {
+      return std::nullopt;
+    }
+    value = value * 10 + static_cast<uint64_t>(c - '0');
+  }
+  if (value > static_cast<uint64_t>(std::numeric_limits<T>::max())) {
+    return std::nullopt;
+  }
+  return static_cast<T>(value);
+}
+
+}  // namespace
+
+std::optional<FoldedStackLine> ParseFoldedStackLine(std::string_view line) {
+  line = Trim(line);
+  size_t count_start = line.find_last_of(" \t");
+  if (count_start == std::string_view::npos) {
+    return std::nullopt;
+  }
+  // Some tools (e.g. the differential flamegraph scripts) emit floating point
+  // counts: only integers are supported.
+  std::optional<int64_t> count =
+      ParseUnsigned<int64_t>(line.substr(count_start + 1));
+  if (!count) {
+    return std::nullopt;
+  }
+
+  FoldedStackLine result;
+  result.count = *count;
+  std::string_view stack = Trim(line.substr(0, count_start));
+  while (!stack.empty()) {
+    size_t end = stack.find(';');
+    std::string_view frame = stack.substr(0, end);
+    if (!frame.empty()) {
+      result.frames.push_back(frame);
+    }
+    if (end == std::string_view::npos) {
+      break;
+    }
+    stack = stack.substr(end + 1);
+  }
+  if (result.frames.empty()) {
+    return std::nullopt;
+  }
+  return result;
+}
+
+std::optional<FoldedStackThread> ParseFoldedStackThread(
+    std::string_view frame) {
+  size_t dash = frame.rfind('-');
+  if (dash == std::string_view::npos || dash == 0) {
+    return std::nullopt;
+  }
+  FoldedStackThread thread;
+  thread.comm = frame.substr(0, dash);
+
+  std::string_view ids = frame.substr(dash + 1);
+  size_t slash = ids.find('/');
+  std::optional<uint32_t> pid = ParseUnsigned<uint32_t>(ids.substr(0, slash));
+  if (!pid) {
+    return std::nullopt;
+  }
+  thread.pid = *pid;
+  if (slash != std::string_view::npos) {
+    thread.tid = ParseUnsigned<uint32_t>(ids.substr(slash + 1));
+    if (!thread.tid) {
+      return std::nullopt;
+    }
+  }
+  return thread;
+}
+
+bool IsFoldedStackFormatTrace(const uint8_t* ptr, size_t size) {
+  std::string_view data(reinterpret_cast<const char*>(ptr), size);
+  bool has_nested_stack = false;
+  size_t lines = 0;
+  while (lines < kMaxLinesToCheck) {
+    size_t end = data.find('\n');
+    // Only look at complete lines: the last one is likely truncated.
+    if (end == std::string_view::npos) {
+      break;
+    }
+    std::string_view raw_line = data.substr(0, end);
+    data = data.substr(end + 1);
+    if (Trim(raw_line).empty()) {
+      continue;
+    }
+    std::optional<FoldedStackLine> line = ParseFoldedStackLine(raw_line);
+    if (!line) {
+      return false;
+    }
+    has_nested_stack |= line->frames.size() > 1;
+    ++lines;
+  }
+  // A single frame followed by a number is too generic to be used for
+  // detection: require at least one stack with more than one frame.
+  return has_nested_stack;
+}
+
+}  // namespace perfetto::trace_processor::folded_stack_importer
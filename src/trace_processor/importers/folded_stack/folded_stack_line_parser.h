@@ -0,0 +1,60 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_IMPORTERS_FOLDED_STACK_FOLDED_STACK_LINE_PARSER_H_
+#define SRC_TRACE_PROCESSOR_IMPORTERS_FOLDED_STACK_FOLDED_STACK_LINE_PARSER_H_
+
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <string_view>
+#include <vector>
+
+namespace perfetto::trace_processor::folded_stack_importer {
+
+// A single line of a folded (a.k.a. "collapsed") stack file as produced by
+// stackcollapse-*.pl and consumed by flamegraph.pl, e.g.:
+//   main;foo;bar 42
+struct FoldedStackLine {
+  // The frames of the stack, from the root to the leaf.
+  std::vector<std::string_view> frames;
+  // Number of times the stack was seen.
+  int64_t count = 0;
+};
+
+// The thread a stack belongs to, parsed from a root frame with the format
+// used by `stackcollapse-perf.pl --pid --tid` (e.g. "comm-1234/1235").
+struct FoldedStackThread {
+  std::string_view comm;
+  uint32_t pid = 0;
+  std::optional<uint32_t> tid;
+};
+
+// Parses a single line (without the trailing newline) of a folded stack file.
+// Returns std::nullopt if |line| is not a valid folded stack line.
+std::optional<FoldedStackLine> ParseFoldedStackLine(std::string_view line);
+
+// Parses the root |frame| of a stack as a thread. Returns std::nullopt if the
+// frame does not identify a thread.
+std::optional<FoldedStackThread> ParseFoldedStackThread(std::string_view frame);
+
+// Given a chunk of a trace file (starting at `ptr` and containing `size`
+// bytes), returns whether the file is a folded stack trace.
+bool IsFoldedStackFormatTrace(const uint8_t* ptr, size_t size);
+
+}  // namespace perfetto::trace_processor::folded_stack_importer
+
+#endif  // SRC_TRACE_PROCESSOR_IMPORTERS_FOLDED_STACK_FOLDED_STACK_LINE_PARSER_H_
@@ -0,0 +1,97 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/folded_stack/folded_stack_line_parser.h"
+
+#include <cstdint>
+#include <optional>
+#include <string>
+
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto::trace_processor::folded_stack_importer {
+namespace {
+
+using ::testing::ElementsAre;
+
+bool IsFoldedStack(const std::string& data) {
+  return IsFoldedStackFormatTrace(
+      reinterpret_cast<const uint8_t*>(data.data()), data.size());
+}
+
+TEST(FoldedStackLineParserTest, ParseLine) {
+  std::optional<FoldedStackLine> line =
+      ParseFoldedStackLine("main;foo(int);bar 42");
+  ASSERT_TRUE(line.has_value());
+  EXPECT_THAT(line->frames, ElementsAre("main", "foo(int)", "bar"));
+  EXPECT_EQ(line->count, 42);
+}
+
+TEST(FoldedStackLineParserTest, FramesWithSpaces) {
+  std::optional<FoldedStackLine> line = ParseFoldedStackLine(
+      "java;operator new(unsigned long);[unknown] 3\r");
+  ASSERT_TRUE(line.has_value());
+  EXPECT_THAT(line->frames,
+              ElementsAre("java", "operator new(unsigned long)", "[unknown]"));
+  EXPECT_EQ(line->count, 3);
+}
+
+TEST(FoldedStackLineParserTest, SkipsEmptyFrames) {
+  std::optional<FoldedStackLine> line = ParseFoldedStackLine("a;;b; 1");
+  ASSERT_TRUE(line.has_value());
+  EXPECT_THAT(line->frames, ElementsAre("a", "b"));
+}
+
+TEST(FoldedStackLineParserTest, InvalidLines) {
+  EXPECT_FALSE(ParseFoldedStackLine("main;foo").has_value());
+  EXPECT_FALSE(ParseFoldedStackLine("main;foo 1.5").has_value());
+  EXPECT_FALSE(ParseFoldedStackLine("main;foo -1").has_value());
+  EXPECT_FALSE(ParseFoldedStackLine(" 12").has_value());
+  EXPECT_FALSE(ParseFoldedStackLine("").has_value());
+}
+
+TEST(FoldedStackLineParserTest, ParseThread) {
+  std::optional<FoldedStackThread> thread =
+      ParseFoldedStackThread("my-app-1234/1240");
+  ASSERT_TRUE(thread.has_value());
+  EXPECT_EQ(thread->comm, "my-app");
+  EXPECT_EQ(thread->pid, 1234u);
+  EXPECT_EQ(thread->tid, 1240u);
+
+  thread = ParseFoldedStackThread("java-42");
+  ASSERT_TRUE(thread.has_value());
+  EXPECT_EQ(thread->comm, "java");
+  EXPECT_EQ(thread->pid, 42u);
+  EXPECT_FALSE(thread->tid.has_value());
+
+  EXPECT_FALSE(ParseFoldedStackThread("main").has_value());
+  EXPECT_FALSE(ParseFoldedStackThread("-42").has_value());
+  EXPECT_FALSE(ParseFoldedStackThread("foo-bar").has_value());
+  EXPECT_FALSE(ParseFoldedStackThread("foo-1/x").has_value());
+}
+
+TEST(FoldedStackLineParserTest, DetectFormat) {
+  EXPECT_TRUE(IsFoldedStack("main;foo 1\nmain;bar 2\n"));
+  EXPECT_TRUE(IsFoldedStack("main 10\n\nmain;bar 2\nmain;ba"));
+  // Lines with a single frame are too generic.
+  EXPECT_FALSE(IsFoldedStack("main 10\nfoo 2\n"));
+  EXPECT_FALSE(IsFoldedStack("main;foo 1\nnot a stack\n"));
+  EXPECT_FALSE(IsFoldedStack("# tracer: nop\n"));
+  EXPECT_FALSE(IsFoldedStack("\x0a\x05hello"));
+}
+
+}  // namespace
+}  // namespace perfetto::trace_processor::folded_stack_importer
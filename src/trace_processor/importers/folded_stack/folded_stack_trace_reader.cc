@@ -0,0 +1,196 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/folded_stack/folded_stack_trace_reader.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <string_view>
+#include <utility>
+
+#include "perfetto/base/logging.h"
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/string_view.h"
+#include "perfetto/trace_processor/trace_blob_view.h"
+#include "src/trace_processor/importers/common/mapping_tracker.h"
+#include "src/trace_processor/importers/common/process_tracker.h"
+#include "src/trace_processor/importers/common/stack_profile_tracker.h"
+#include "src/trace_processor/importers/common/virtual_memory_mapping.h"
+#include "src/trace_processor/importers/folded_stack/folded_stack_line_parser.h"
+#include "src/trace_processor/storage/stats.h"
+#include "src/trace_processor/storage/trace_storage.h"
+#include "src/trace_processor/tables/profiler_tables_py.h"
+#include "src/trace_processor/types/trace_processor_context.h"
+#include "src/trace_processor/util/trace_blob_view_reader.h"
+
+namespace perfetto::trace_processor::folded_stack_importer {
+
+namespace {
+
+// Folded stacks do not have timestamps: samples are spaced by this interval
+// so that the profile can be shown on a timeline.
+constexpr int64_t kSampleIntervalNs = 1000 * 1000;
+
+// Each sample is imported as a row, so lines with larger counts (likely
+// weights in bytes or microseconds rather than sample counts) are skipped.
+constexpr int64_t kMaxSampleCount = 1000 * 1000;
+
+constexpr char kDefaultMappingName[] = "[unknown]";
+constexpr char kKernelMappingName[] = "[kernel.kallsyms]";
+
+std::string_view ToStringView(const TraceBlobView& tbv) {
+  return {reinterpret_cast<const char*>(tbv.data()), tbv.size()};
+}
+
+bool ConsumeSuffix(std::string_view& str, std::string_view suffix) {
+  if (str.size() < suffix.size() ||
+      str.substr(str.size() - suffix.size()) != suffix) {
+    return false;
+  }
+  str.remove_suffix(suffix.size());
+  return true;
+}
+
+}  // namespace
+
+FoldedStackTraceReader::FoldedStackTraceReader(TraceProcessorContext* ctx)
+    : context_(ctx) {}
+FoldedStackTraceReader::~FoldedStackTraceReader() = default;
+
+base::Status FoldedStackTraceReader::Parse(TraceBlobView blob) {
+  reader_.PushBack(std::move(blob));
+  for (;;) {
+    auto it = reader_.GetIterator();
+    auto line = it.MaybeFindAndRead('\n');
+    if (!line) {
+      return base::OkStatus();
+    }
+    ParseLine(ToStringView(*line));
+    reader_.PopFrontUntil(it.file_offset());
+  }
+}
+
+base::Status FoldedStackTraceReader::NotifyEndOfFile() {
+  // The last line might not be terminated by a newline.
+  if (reader_.avail() > 0) {
+    auto line = reader_.SliceOff(reader_.start_offset(), reader_.avail());
+    PERFETTO_CHECK(line);
+    ParseLine(ToStringView(*line));
+    reader_.PopFrontBytes(reader_.avail());
+  }
+  return base::OkStatus();
+}
+
+void FoldedStackTraceReader::ParseLine(std::string_view line) {
+  if (line.find_first_not_of(" \t\r") == std::string_view::npos) {
+    return;
+  }
+  std::optional<FoldedStackLine> stack = ParseFoldedStackLine(line);
+  if (!stack) {
+    context_->storage->IncrementStats(stats::folded_stack_parse_errors);
+    return;
+  }
+  if (stack->count == 0) {
+    return;
+  }
+  if (stack->count > kMaxSampleCount) {
+    context_->storage->IncrementStats(stats::folded_stack_count_too_large);
+    return;
+  }
+  AddSamples(*stack);
+}
+
+void FoldedStackTraceReader::AddSamples(const FoldedStackLine& stack) {
+  std::optional<FoldedStackThread> thread;
+  if (stack.frames.size() > 1) {
+    thread = ParseFoldedStackThread(stack.frames.front());
+  }
+
+  UniqueTid utid;
+  if (thread) {
+    // Stacks aggregated per process ("comm-pid") are attributed to the main
+    // thread of the process.
+    int64_t tid = thread->tid.value_or(thread->pid);
+    utid = context_->process_tracker->UpdateThread(tid, thread->pid);
+    context_->process_tracker->UpdateThreadNameAndMaybeProcessName(
+        tid,
+        context_->storage->InternString(base::StringView(
+            thread->comm.data(), thread->comm.size())),
+        ThreadNamePriority::kOther);
+  } else {
+    utid = GetDefaultThread();
+  }
+
+  std::optional<CallsiteId> callsite_id;
+  uint32_t depth = 0;
+  for (size_t i = thread ? 1 : 0; i < stack.frames.size(); ++i) {
+    callsite_id = context_->stack_profile_tracker->InternCallsite(
+        callsite_id, InternFrame(stack.frames[i]), depth++);
+  }
+
+  auto* samples = context_->storage->mutable_cpu_profile_stack_sample_table();
+  tables::CpuProfileStackSampleTable::Row row;
+  row.callsite_id = *callsite_id;
+  row.utid = utid;
+  for (int64_t i = 0; i < stack.count; ++i) {
+    row.ts = next_ts_;
+    next_ts_ += kSampleIntervalNs;
+    samples->Insert(row);
+  }
+}
+
+FrameId FoldedStackTraceReader::InternFrame(std::string_view frame) {
+  // stackcollapse-perf.pl --all annotates frames with their type: "_[k]" for
+  // kernel, "_[j]" for JIT, "_[i]" for inlined and "_[w]" for waker frames.
+  std::string_view mapping_name = kDefaultMappingName;
+  if (ConsumeSuffix(frame, "_[k]")) {
+    mapping_name = kKernelMappingName;
+  } else if (ConsumeSuffix(frame, "_[j]") || ConsumeSuffix(frame, "_[i]") ||
+             ConsumeSuffix(frame, "_[w]")) {
+    // The annotation does not identify a mapping.
+  } else if (size_t tick = frame.find('`');
+             tick != std::string_view::npos && tick > 0) {
+    // DTrace and some of the stackcollapse scripts use "module`function".
+    mapping_name = frame.substr(0, tick);
+    frame = frame.substr(tick + 1);
+  }
+  return GetOrCreateMapping(mapping_name)
+      .InternDummyFrame(base::StringView(frame.data(), frame.size()),
+                        base::StringView());
+}
+
+DummyMemoryMapping& FoldedStackTraceReader::GetOrCreateMapping(
+    std::string_view name) {
+  std::string key(name);
+  if (DummyMemoryMapping** mapping = mappings_.Find(key); mapping) {
+    return **mapping;
+  }
+  DummyMemoryMapping& mapping =
+      context_->mapping_tracker->CreateDummyMapping(key);
+  PERFETTO_CHECK(mappings_.Insert(std::move(key), &mapping).second);
+  return mapping;
+}
+
+UniqueTid FoldedStackTraceReader::GetDefaultThread() {
+  if (!default_utid_) {
+    default_utid_ = context_->process_tracker->StartNewThread(std::nullopt, 0);
+  }
+  return *default_utid_;
+}
+
+}  // namespace perfetto::trace_processor::folded_stack_importer
@@ -0,0 +1,75 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_IMPORTERS_FOLDED_STACK_FOLDED_STACK_TRACE_READER_H_
+#define SRC_TRACE_PROCESSOR_IMPORTERS_FOLDED_STACK_FOLDED_STACK_TRACE_READER_H_
+
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <string_view>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/flat_hash_map.h"
+#include "src/trace_processor/importers/common/chunked_trace_reader.h"
+#include "src/trace_processor/importers/common/virtual_memory_mapping.h"
+#include "src/trace_processor/storage/trace_storage.h"
+#include "src/trace_processor/util/trace_blob_view_reader.h"
+
+namespace perfetto::trace_processor {
+
+class TraceProcessorContext;
+
+namespace folded_stack_importer {
+
+struct FoldedStackLine;
+
+// Imports folded (a.k.a. "collapsed") stacks, the text format used by
+// Brendan Gregg's FlameGraph scripts, as a synthetic CPU profile.
+//
+// Folded stacks carry no timing information: each line with count N is turned
+// into N samples in cpu_profile_stack_sample, one every millisecond, in the
+// order in which they appear in the file. If the root frame of a stack has the
+// "comm-pid[/tid]" format emitted by `stackcollapse-perf.pl --pid --tid`, it is
+// used to attribute the samples to a thread instead of being added as a frame.
+class FoldedStackTraceReader : public ChunkedTraceReader {
+ public:
+  explicit FoldedStackTraceReader(TraceProcessorContext*);
+  ~FoldedStackTraceReader() override;
+
+  base::Status Parse(TraceBlobView) override;
+  base::Status NotifyEndOfFile() override;
+
+ private:
+  void ParseLine(std::string_view line);
+  void AddSamples(const FoldedStackLine& stack);
+  FrameId InternFrame(std::string_view frame);
+  DummyMemoryMapping& GetOrCreateMapping(std::string_view name);
+  UniqueTid GetDefaultThread();
+
+  TraceProcessorContext* const context_;
+  util::TraceBlobViewReader reader_;
+  base::FlatHashMap<std::string, DummyMemoryMapping*> mappings_;
+  std::optional<UniqueTid> default_utid_;
+  std::vector<FrameId> frames_;
+  int64_t next_ts_ = 0;
+};
+
+}  // namespace folded_stack_importer
+}  // namespace perfetto::trace_processor
+
+#endif  // SRC_TRACE_PROCESSOR_IMPORTERS_FOLDED_STACK_FOLDED_STACK_TRACE_READER_H_
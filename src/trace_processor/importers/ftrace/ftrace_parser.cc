@@ -61,6 +61,7 @@
 #include "src/trace_processor/importers/ftrace/virtio_video_tracker.h"
 #include "src/trace_processor/importers/i2c/i2c_tracker.h"
 #include "src/trace_processor/importers/proto/packet_sequence_state_generation.h"
+#include "src/trace_processor/importers/syscalls/futex_tracker.h"
 #include "src/trace_processor/importers/syscalls/syscall_tracker.h"
 #include "src/trace_processor/importers/systrace/systrace_parser.h"
 #include "src/trace_processor/storage/metadata.h"
@@ -2381,7 +2382,19 @@ void FtraceParser::ParseSysEnterEvent(int64_t timestamp,
       ++count;
     }
   };
-  syscall_tracker->Enter(timestamp, utid, syscall_num, args_callback);
+  std::optional<SliceId> slice_id =
+      syscall_tracker->Enter(timestamp, utid, syscall_num, args_callback);
+
+  if (syscall_tracker->IsFutex(syscall_num)) {
+    // futex(uaddr, op, val, timeout, uaddr2, val3)
+    uint64_t futex_args[5] = {};
+    size_t i = 0;
+    for (auto it = evt.args(); it && i < base::ArraySize(futex_args); ++it)
+      futex_args[i++] = *it;
+    FutexTracker::GetOrCreate(context_)->Enter(
+        timestamp, utid, futex_args[0], static_cast<uint32_t>(futex_args[1]),
+        futex_args[4], slice_id);
+  }
 }
 
 void FtraceParser::ParseSysExitEvent(int64_t timestamp,
@@ -2402,7 +2415,13 @@ void FtraceParser::ParseSysExitEvent(int64_t timestamp,
       inserter->AddArg(syscall_ret_id_, Variadic::Integer(ret));
     }
   };
-  syscall_tracker->Exit(timestamp, utid, syscall_num, args_callback);
+  std::optional<SliceId> slice_id =
+      syscall_tracker->Exit(timestamp, utid, syscall_num, args_callback);
+
+  if (syscall_tracker->IsFutex(syscall_num)) {
+    FutexTracker::GetOrCreate(context_)->Exit(timestamp, utid, evt.ret(),
+                                              slice_id);
+  }
 }
 
 void FtraceParser::ParseI2cReadEvent(int64_t timestamp,
@@ -2854,23 +2873,12 @@ void FtraceParser::ParseWorkqueueExecuteEnd(int64_t timestamp,
   context_->slice_tracker->End(timestamp, track, workqueue_id_);
 }
 
-namespace {
-
-constexpr auto kIrqBlueprint = tracks::SliceBlueprint(
-    "cpu_irq",
-    tracks::DimensionBlueprints(tracks::kCpuDimensionBlueprint),
-    tracks::FnNameBlueprint([](uint32_t cpu) {
-      return base::StackString<255>("Irq Cpu %u", cpu);
-    }));
-
-}  // namespace
-
 void FtraceParser::ParseIrqHandlerEntry(uint32_t cpu,
                                         int64_t timestamp,
                                         protozero::ConstBytes blob) {
   protos::pbzero::IrqHandlerEntryFtraceEvent::Decoder evt(blob);
 
-  TrackId track = context_->track_tracker->InternTrack(kIrqBlueprint,
+  TrackId track = context_->track_tracker->InternTrack(tracks::kCpuIrqBlueprint,
                                                        tracks::Dimensions(cpu));
 
   base::StringView irq_name = evt.name();
@@ -2886,7 +2894,7 @@ void FtraceParser::ParseIrqHandlerExit(uint32_t cpu,
                                        protozero::ConstBytes blob) {
   protos::pbzero::IrqHandlerExitFtraceEvent::Decoder evt(blob);
 
-  TrackId track = context_->track_tracker->InternTrack(kIrqBlueprint,
+  TrackId track = context_->track_tracker->InternTrack(tracks::kCpuIrqBlueprint,
                                                        tracks::Dimensions(cpu));
   context_->slice_tracker->End(
       timestamp, track, irq_id_, {},
@@ -2898,28 +2906,17 @@ void FtraceParser::ParseIrqHandlerExit(uint32_t cpu,
 }
 
 void FtraceParser::ParseLocalTimerEntry(uint32_t cpu, int64_t timestamp) {
-  TrackId track = context_->track_tracker->InternTrack(kIrqBlueprint,
+  TrackId track = context_->track_tracker->InternTrack(tracks::kCpuIrqBlueprint,
                                                        tracks::Dimensions(cpu));
   context_->slice_tracker->Begin(timestamp, track, irq_id_, local_timer_id_);
 }
 
 void FtraceParser::ParseLocalTimerExit(uint32_t cpu, int64_t timestamp) {
-  TrackId track = context_->track_tracker->InternTrack(kIrqBlueprint,
+  TrackId track = context_->track_tracker->InternTrack(tracks::kCpuIrqBlueprint,
                                                        tracks::Dimensions(cpu));
   context_->slice_tracker->End(timestamp, track, irq_id_, {});
 }
 
-namespace {
-
-constexpr auto kSoftIrqBlueprint = tracks::SliceBlueprint(
-    "cpu_softirq",
-    tracks::DimensionBlueprints(tracks::kCpuDimensionBlueprint),
-    tracks::FnNameBlueprint([](uint32_t cpu) {
-      return base::StackString<255>("SoftIrq Cpu %u", cpu);
-    }));
-
-}  // namespace
-
 void FtraceParser::ParseSoftIrqEntry(uint32_t cpu,
                                      int64_t timestamp,
                                      protozero::ConstBytes blob) {
@@ -2929,8 +2926,8 @@ void FtraceParser::ParseSoftIrqEntry(uint32_t cpu,
     return;
   }
 
-  TrackId track = context_->track_tracker->InternTrack(kSoftIrqBlueprint,
-                                                       tracks::Dimensions(cpu));
+  TrackId track = context_->track_tracker->InternTrack(
+      tracks::kCpuSoftIrqBlueprint, tracks::Dimensions(cpu));
   StringId slice_name_id =
       context_->storage->InternString(kActionNames[evt.vec()]);
   context_->slice_tracker->Begin(timestamp, track, irq_id_, slice_name_id);
@@ -2941,8 +2938,8 @@ void FtraceParser::ParseSoftIrqExit(uint32_t cpu,
                                     protozero::ConstBytes blob) {
   protos::pbzero::SoftirqExitFtraceEvent::Decoder evt(blob);
 
-  TrackId track = context_->track_tracker->InternTrack(kSoftIrqBlueprint,
-                                                       tracks::Dimensions(cpu));
+  TrackId track = context_->track_tracker->InternTrack(
+      tracks::kCpuSoftIrqBlueprint, tracks::Dimensions(cpu));
   context_->slice_tracker->End(timestamp, track, irq_id_, {},
                                [&, this](ArgsTracker::BoundInserter* inserter) {
                                  inserter->AddArg(vec_arg_id_,
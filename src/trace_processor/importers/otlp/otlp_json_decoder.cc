@@ -0,0 +1,295 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/otlp/otlp_json_decoder.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <string_view>
+#include <utility>
+#include <vector>
+
+#include "perfetto/base/build_config.h"
+#include "perfetto/base/compiler.h"
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/base64.h"
+#include "perfetto/ext/base/status_macros.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "perfetto/ext/base/string_view.h"
+#include "src/trace_processor/importers/json/json_parser.h"
+#include "src/trace_processor/importers/json/json_utils.h"
+#include "src/trace_processor/importers/otlp/otlp_trace.h"
+
+#include "protos/third_party/opentelemetry/trace.pbzero.h"
+
+#if PERFETTO_BUILDFLAG(PERFETTO_TP_JSON)
+#include <json/value.h>
+#endif
+
+namespace perfetto::trace_processor::otlp_importer {
+
+#if PERFETTO_BUILDFLAG(PERFETTO_TP_JSON)
+namespace {
+
+namespace otel = ::perfetto::third_party::opentelemetry::proto::pbzero;
+
+// Nested arrays and key-value lists deeper than this are dropped.
+constexpr uint32_t kMaxAttributeDepth = 16;
+
+// The proto3 JSON mapping encodes 64-bit integers as strings, but numbers are
+// accepted as well.
+std::optional<uint64_t> JsonToUint64(const Json::Value& value) {
+  if (value.isString()) {
+    return base::StringToUInt64(value.asString());
+  }
+  if (value.isUInt64()) {
+    return value.asUInt64();
+  }
+  return std::nullopt;
+}
+
+std::optional<int64_t> JsonToInt64(const Json::Value& value) {
+  if (value.isString()) {
+    return base::StringToInt64(value.asString());
+  }
+  if (value.isInt64()) {
+    return value.asInt64();
+  }
+  return std::nullopt;
+}
+
+// Enums can either be encoded by value or by name.
+template <typename Enum>
+int32_t JsonToEnum(const Json::Value& value,
+                   const char* (*enum_name)(Enum),
+                   Enum max) {
+  if (value.isInt()) {
+    return value.asInt();
+  }
+  if (value.isString()) {
+    std::string name = value.asString();
+    for (int32_t i = 0; i <= static_cast<int32_t>(max); ++i) {
+      if (name == enum_name(static_cast<Enum>(i))) {
+        return i;
+      }
+    }
+  }
+  return 0;
+}
+
+// Unlike the proto3 JSON mapping, OTLP encodes trace and span ids as hex
+// strings. Returns the raw bytes of the id, or an empty string if |value| is
+// not a valid hex string.
+std::string HexIdToBytes(const Json::Value& value) {
+  if (!value.isString()) {
+    return {};
+  }
+  std::string hex = value.asString();
+  if (hex.size() % 2 != 0) {
+    return {};
+  }
+  std::string bytes;
+  bytes.reserve(hex.size() / 2);
+  for (size_t i = 0; i < hex.size(); i += 2) {
+    std::optional<uint64_t> byte =
+        base::StringToUInt64(hex.substr(i, 2), /*base=*/16);
+    if (!byte) {
+      return {};
+    }
+    bytes.push_back(static_cast<char>(*byte));
+  }
+  return bytes;
+}
+
+void DecodeKeyValue(const std::string& prefix,
+                    const Json::Value& kv,
+                    uint32_t depth,
+                    std::vector<OtlpAttribute>* out);
+
+void DecodeAnyValue(std::string key,
+                    const Json::Value& value,
+                    uint32_t depth,
+                    std::vector<OtlpAttribute>* out) {
+  if (!value.isObject()) {
+    return;
+  }
+  if (value.isMember("stringValue")) {
+    out->push_back({std::move(key), value["stringValue"].asString()});
+  } else if (value.isMember("boolValue")) {
+    out->push_back({std::move(key), value["boolValue"].asBool()});
+  } else if (value.isMember("intValue")) {
+    if (auto int_value = JsonToInt64(value["intValue"]); int_value) {
+      out->push_back({std::move(key), *int_value});
+    }
+  } else if (value.isMember("doubleValue")) {
+    if (value["doubleValue"].isNumeric()) {
+      out->push_back({std::move(key), value["doubleValue"].asDouble()});
+    }
+  } else if (value.isMember("bytesValue")) {
+    // Bytes are base64 encoded by the proto3 JSON mapping.
+    std::optional<std::string> bytes =
+        base::Base64Decode(base::StringView(value["bytesValue"].asString()));
+    if (bytes) {
+      out->push_back({std::move(key), base::ToHex(*bytes)});
+    }
+  } else if (depth >= kMaxAttributeDepth) {
+    return;
+  } else if (value.isMember("arrayValue")) {
+    uint32_t index = 0;
+    for (const Json::Value& element : value["arrayValue"]["values"]) {
+      DecodeAnyValue(key + "[" + std::to_string(index++) + "]", element,
+                     depth + 1, out);
+    }
+  } else if (value.isMember("kvlistValue")) {
+    for (const Json::Value& kv : value["kvlistValue"]["values"]) {
+      DecodeKeyValue(key + ".", kv, depth + 1, out);
+    }
+  }
+}
+
+void DecodeKeyValue(const std::string& prefix,
+                    const Json::Value& kv,
+                    uint32_t depth,
+                    std::vector<OtlpAttribute>* out) {
+  if (!kv.isObject()) {
+    return;
+  }
+  DecodeAnyValue(prefix + kv["key"].asString(), kv["value"], depth, out);
+}
+
+std::vector<OtlpAttribute> DecodeAttributes(const Json::Value& attributes) {
+  std::vector<OtlpAttribute> out;
+  for (const Json::Value& kv : attributes) {
+    DecodeKeyValue("", kv, 0, &out);
+  }
+  return out;
+}
+
+void DecodeSpan(const Json::Value& value, OtlpSpan* span) {
+  span->trace_id = HexIdToBytes(value["traceId"]);
+  span->span_id = HexIdToBytes(value["spanId"]);
+  span->parent_span_id = HexIdToBytes(value["parentSpanId"]);
+  span->trace_state = value["traceState"].asString();
+  span->name = value["name"].asString();
+  span->kind = JsonToEnum(value["kind"], &otel::Span_SpanKind_Name,
+                          otel::Span_SpanKind_MAX);
+  span->start_time_unix_nano =
+      JsonToUint64(value["startTimeUnixNano"]).value_or(0);
+  span->end_time_unix_nano = JsonToUint64(value["endTimeUnixNano"]).value_or(0);
+  span->attributes = DecodeAttributes(value["attributes"]);
+  if (const Json::Value& status = value["status"]; status.isObject()) {
+    span->status_code =
+        JsonToEnum(status["code"], &otel::Status_StatusCode_Name,
+                   otel::Status_StatusCode_MAX);
+    span->status_message = status["message"].asString();
+  }
+  for (const Json::Value& event : value["events"]) {
+    if (!event.isObject()) {
+      continue;
+    }
+    span->events.push_back({JsonToUint64(event["timeUnixNano"]).value_or(0),
+                            event["name"].asString(),
+                            DecodeAttributes(event["attributes"])});
+  }
+  for (const Json::Value& link : value["links"]) {
+    if (!link.isObject()) {
+      continue;
+    }
+    span->links.push_back({HexIdToBytes(link["traceId"]),
+                           HexIdToBytes(link["spanId"]),
+                           DecodeAttributes(link["attributes"])});
+  }
+}
+
+base::Status DecodeTracesData(const Json::Value& value, OtlpTrace* trace) {
+  if (!value.isObject()) {
+    return base::ErrStatus("OTLP: expected TracesData JSON object");
+  }
+  for (const Json::Value& resource_spans : value["resourceSpans"]) {
+    if (!resource_spans.isObject()) {
+      continue;
+    }
+    auto resource_index = static_cast<uint32_t>(trace->resources.size());
+    OtlpResource& resource = trace->resources.emplace_back();
+    if (const Json::Value& r = resource_spans["resource"]; r.isObject()) {
+      resource.attributes = DecodeAttributes(r["attributes"]);
+    }
+    for (const Json::Value& scope_spans : resource_spans["scopeSpans"]) {
+      if (!scope_spans.isObject()) {
+        continue;
+      }
+      std::string scope_name;
+      std::string scope_version;
+      if (const Json::Value& scope = scope_spans["scope"]; scope.isObject()) {
+        scope_name = scope["name"].asString();
+        scope_version = scope["version"].asString();
+      }
+      for (const Json::Value& span_value : scope_spans["spans"]) {
+        if (!span_value.isObject()) {
+          continue;
+        }
+        OtlpSpan& span = trace->spans.emplace_back();
+        span.resource_index = resource_index;
+        span.scope_name = scope_name;
+        span.scope_version = scope_version;
+        DecodeSpan(span_value, &span);
+      }
+    }
+  }
+  return base::OkStatus();
+}
+
+}  // namespace
+#endif  // PERFETTO_BUILDFLAG(PERFETTO_TP_JSON)
+
+base::Status DecodeOtlpJson(std::string_view json, OtlpTrace* trace) {
+#if PERFETTO_BUILDFLAG(PERFETTO_TP_JSON)
+  const char* cur = json.data();
+  const char* end = json.data() + json.size();
+  while (json::internal::SkipWhitespace(cur, end)) {
+    if (*cur != '{') {
+      return base::ErrStatus("OTLP: expected JSON object at offset %zu",
+                             static_cast<size_t>(cur - json.data()));
+    }
+    const char* object_end;
+    base::Status status;
+    if (json::internal::ScanToEndOfDelimitedBlock(
+            cur, end, '{', '}', object_end, status) !=
+        json::internal::ReturnCode::kOk) {
+      return base::ErrStatus("OTLP: unterminated JSON object at offset %zu",
+                             static_cast<size_t>(cur - json.data()));
+    }
+    std::optional<Json::Value> value = json::ParseJsonString(
+        base::StringView(cur, static_cast<size_t>(object_end - cur)));
+    if (!value) {
+      return base::ErrStatus(
+          "OTLP: syntactic error in JSON object at offset %zu",
+          static_cast<size_t>(cur - json.data()));
+    }
+    RETURN_IF_ERROR(DecodeTracesData(*value, trace));
+    cur = object_end;
+  }
+  return base::OkStatus();
+#else
+  base::ignore_result(json, trace);
+  return base::ErrStatus(
+      "OTLP: cannot import JSON traces, JSON support is disabled");
+#endif
+}
+
+}  // namespace perfetto::trace_processor::otlp_importer
@@ -0,0 +1,37 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_IMPORTERS_OTLP_OTLP_JSON_DECODER_H_
+#define SRC_TRACE_PROCESSOR_IMPORTERS_OTLP_OTLP_JSON_DECODER_H_
+
+#include <string_view>
+
+#include "perfetto/base/status.h"
+#include "src/trace_processor/importers/otlp/otlp_trace.h"
+
+namespace perfetto::trace_processor::otlp_importer {
+
+// Decodes an OTLP JSON trace and appends its resources and spans to |trace|.
+// |json| can either contain a single TracesData message or a sequence of them
+// (e.g. one per line, which is what the OpenTelemetry collector file exporter
+// writes).
+//
+// Returns an error if trace processor was built without JSON support.
+base::Status DecodeOtlpJson(std::string_view json, OtlpTrace* trace);
+
+}  // namespace perfetto::trace_processor::otlp_importer
+
+#endif  // SRC_TRACE_PROCESSOR_IMPORTERS_OTLP_OTLP_JSON_DECODER_H_
@@ -0,0 +1,131 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/otlp/otlp_json_decoder.h"
+
+#include <cstdint>
+#include <string>
+
+#include "src/trace_processor/importers/otlp/otlp_trace.h"
+#include "test/gtest_and_gmock.h"
+
+#include "protos/third_party/opentelemetry/trace.pbzero.h"
+
+namespace perfetto::trace_processor::otlp_importer {
+namespace {
+
+namespace otel = ::perfetto::third_party::opentelemetry::proto::pbzero;
+
+using ::testing::ElementsAre;
+using ::testing::Field;
+using ::testing::VariantWith;
+
+constexpr char kTrace[] = R"({"resourceSpans": [{
+  "resource": {"attributes": [
+    {"key": "service.name", "value": {"stringValue": "backend"}},
+    {"key": "process.pid", "value": {"intValue": "42"}}
+  ]},
+  "scopeSpans": [{
+    "scope": {"name": "my.library", "version": "1.0"},
+    "spans": [{
+      "traceId": "5b8efff798038103d269b633813fc60c",
+      "spanId": "eee19b7ec3c1b174",
+      "parentSpanId": "eee19b7ec3c1b173",
+      "name": "query",
+      "kind": "SPAN_KIND_CLIENT",
+      "startTimeUnixNano": "1544712660000000000",
+      "endTimeUnixNano": 1544712661000000000,
+      "attributes": [
+        {"key": "rows", "value": {"arrayValue": {"values": [
+          {"intValue": 3}, {"doubleValue": 0.5}]}}},
+        {"key": "blob", "value": {"bytesValue": "AQI="}}
+      ],
+      "events": [{"timeUnixNano": "1544712660500000000", "name": "retry"}],
+      "links": [{"traceId": "5b8efff798038103d269b633813fc60c",
+                 "spanId": "eee19b7ec3c1b172"}],
+      "status": {"code": 2, "message": "timeout"}
+    }]
+  }]
+}]})";
+
+TEST(OtlpJsonDecoderTest, DecodeSpan) {
+  OtlpTrace trace;
+  ASSERT_TRUE(DecodeOtlpJson(kTrace, &trace).ok());
+
+  ASSERT_EQ(trace.resources.size(), 1u);
+  EXPECT_THAT(
+      trace.resources[0].attributes,
+      ElementsAre(
+          AllOf(Field(&OtlpAttribute::key, "service.name"),
+                Field(&OtlpAttribute::value,
+                      VariantWith<std::string>("backend"))),
+          AllOf(Field(&OtlpAttribute::key, "process.pid"),
+                Field(&OtlpAttribute::value, VariantWith<int64_t>(42)))));
+
+  ASSERT_EQ(trace.spans.size(), 1u);
+  const OtlpSpan& span = trace.spans[0];
+  EXPECT_EQ(span.scope_name, "my.library");
+  EXPECT_EQ(span.scope_version, "1.0");
+  EXPECT_EQ(span.trace_id,
+            std::string("\x5b\x8e\xff\xf7\x98\x03\x81\x03"
+                        "\xd2\x69\xb6\x33\x81\x3f\xc6\x0c"));
+  EXPECT_EQ(span.span_id, std::string("\xee\xe1\x9b\x7e\xc3\xc1\xb1\x74"));
+  EXPECT_EQ(span.parent_span_id,
+            std::string("\xee\xe1\x9b\x7e\xc3\xc1\xb1\x73"));
+  EXPECT_EQ(span.name, "query");
+  EXPECT_EQ(span.kind, otel::Span::SPAN_KIND_CLIENT);
+  EXPECT_EQ(span.start_time_unix_nano, 1544712660000000000u);
+  EXPECT_EQ(span.end_time_unix_nano, 1544712661000000000u);
+  EXPECT_EQ(span.status_code, otel::Status::STATUS_CODE_ERROR);
+  EXPECT_EQ(span.status_message, "timeout");
+
+  EXPECT_THAT(
+      span.attributes,
+      ElementsAre(
+          AllOf(Field(&OtlpAttribute::key, "rows[0]"),
+                Field(&OtlpAttribute::value, VariantWith<int64_t>(3))),
+          AllOf(Field(&OtlpAttribute::key, "rows[1]"),
+                Field(&OtlpAttribute::value, VariantWith<double>(0.5))),
+          AllOf(Field(&OtlpAttribute::key, "blob"),
+                Field(&OtlpAttribute::value,
+                      VariantWith<std::string>("0102")))));
+
+  ASSERT_EQ(span.events.size(), 1u);
+  EXPECT_EQ(span.events[0].time_unix_nano, 1544712660500000000u);
+  EXPECT_EQ(span.events[0].name, "retry");
+
+  ASSERT_EQ(span.links.size(), 1u);
+  EXPECT_EQ(span.links[0].span_id,
+            std::string("\xee\xe1\x9b\x7e\xc3\xc1\xb1\x72"));
+}
+
+TEST(OtlpJsonDecoderTest, JsonLines) {
+  OtlpTrace trace;
+  std::string json = std::string(kTrace) + "\n" + kTrace + "\n";
+  ASSERT_TRUE(DecodeOtlpJson(json, &trace).ok());
+  EXPECT_EQ(trace.resources.size(), 2u);
+  ASSERT_EQ(trace.spans.size(), 2u);
+  EXPECT_EQ(trace.spans[1].resource_index, 1u);
+}
+
+TEST(OtlpJsonDecoderTest, InvalidJson) {
+  OtlpTrace trace;
+  EXPECT_FALSE(DecodeOtlpJson(R"({"resourceSpans": [})", &trace).ok());
+  EXPECT_FALSE(DecodeOtlpJson("[]", &trace).ok());
+}
+
+}  // namespace
+}  // namespace perfetto::trace_processor::otlp_importer
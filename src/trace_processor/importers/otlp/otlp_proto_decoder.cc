@@ -0,0 +1,281 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/otlp/otlp_proto_decoder.h"
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "perfetto/protozero/field.h"
+#include "perfetto/protozero/proto_utils.h"
+#include "src/trace_processor/importers/otlp/otlp_trace.h"
+
+#include "protos/third_party/opentelemetry/trace.pbzero.h"
+
+namespace perfetto::trace_processor::otlp_importer {
+
+namespace {
+
+namespace otel = ::perfetto::third_party::opentelemetry::proto::pbzero;
+
+using protozero::proto_utils::ProtoWireType;
+
+// Nested arrays and key-value lists deeper than this are dropped.
+constexpr uint32_t kMaxAttributeDepth = 16;
+
+constexpr size_t kTraceIdSize = 16;
+constexpr size_t kSpanIdSize = 8;
+
+// Reads the tag of the field starting at |*ptr| and, for length-delimited
+// fields, its length. Returns false if the field is malformed.
+bool ReadFieldHeader(const uint8_t** ptr,
+                     const uint8_t* end,
+                     uint32_t* field_id,
+                     ProtoWireType* wire_type,
+                     uint64_t* length) {
+  uint64_t tag;
+  const uint8_t* next = protozero::proto_utils::ParseVarInt(*ptr, end, &tag);
+  if (next == *ptr || (tag >> 3) == 0 || (tag >> 3) > UINT32_MAX) {
+    return false;
+  }
+  *ptr = next;
+  *field_id = static_cast<uint32_t>(tag >> 3);
+  *wire_type = static_cast<ProtoWireType>(tag & 7);
+  *length = 0;
+  switch (*wire_type) {
+    case ProtoWireType::kLengthDelimited:
+      next = protozero::proto_utils::ParseVarInt(*ptr, end, length);
+      if (next == *ptr) {
+        return false;
+      }
+      *ptr = next;
+      return true;
+    case ProtoWireType::kVarInt: {
+      uint64_t unused;
+      next = protozero::proto_utils::ParseVarInt(*ptr, end, &unused);
+      if (next == *ptr) {
+        return false;
+      }
+      *ptr = next;
+      return true;
+    }
+    case ProtoWireType::kFixed32:
+      *length = sizeof(uint32_t);
+      return true;
+    case ProtoWireType::kFixed64:
+      *length = sizeof(uint64_t);
+      return true;
+  }
+  return false;
+}
+
+// Looks for the length-delimited field |wanted_id| in a message which only
+// has length-delimited fields with ids <= |max_id| (true for TracesData,
+// ResourceSpans and ScopeSpans). On success, |*ptr| and |*field_end| are set
+// to the bounds of the (possibly truncated) payload of the field.
+bool FindNestedMessage(const uint8_t** ptr,
+                       const uint8_t* end,
+                       uint32_t wanted_id,
+                       uint32_t max_id,
+                       const uint8_t** field_end) {
+  while (*ptr < end) {
+    uint32_t field_id;
+    ProtoWireType wire_type;
+    uint64_t length;
+    if (!ReadFieldHeader(ptr, end, &field_id, &wire_type, &length) ||
+        wire_type != ProtoWireType::kLengthDelimited || field_id > max_id) {
+      return false;
+    }
+    auto avail = static_cast<uint64_t>(end - *ptr);
+    if (field_id == wanted_id) {
+      *field_end = *ptr + std::min(length, avail);
+      return true;
+    }
+    if (length > avail) {
+      return false;
+    }
+    *ptr += length;
+  }
+  return false;
+}
+
+void DecodeKeyValue(const std::string& prefix,
+                    protozero::ConstBytes bytes,
+                    uint32_t depth,
+                    std::vector<OtlpAttribute>* out);
+
+void DecodeAnyValue(std::string key,
+                    protozero::ConstBytes bytes,
+                    uint32_t depth,
+                    std::vector<OtlpAttribute>* out) {
+  otel::AnyValue::Decoder value(bytes);
+  if (value.has_string_value()) {
+    out->push_back({std::move(key), value.string_value().ToStdString()});
+  } else if (value.has_bool_value()) {
+    out->push_back({std::move(key), value.bool_value()});
+  } else if (value.has_int_value()) {
+    out->push_back({std::move(key), value.int_value()});
+  } else if (value.has_double_value()) {
+    out->push_back({std::move(key), value.double_value()});
+  } else if (value.has_bytes_value()) {
+    protozero::ConstBytes data = value.bytes_value();
+    out->push_back({std::move(key),
+                    base::ToHex(reinterpret_cast<const char*>(data.data),
+                                data.size)});
+  } else if (depth >= kMaxAttributeDepth) {
+    return;
+  } else if (value.has_array_value()) {
+    otel::ArrayValue::Decoder array(value.array_value());
+    uint32_t index = 0;
+    for (auto it = array.values(); it; ++it, ++index) {
+      DecodeAnyValue(key + "[" + std::to_string(index) + "]", *it, depth + 1,
+                     out);
+    }
+  } else if (value.has_kvlist_value()) {
+    otel::KeyValueList::Decoder kvlist(value.kvlist_value());
+    for (auto it = kvlist.values(); it; ++it) {
+      DecodeKeyValue(key + ".", *it, depth + 1, out);
+    }
+  }
+}
+
+void DecodeKeyValue(const std::string& prefix,
+                    protozero::ConstBytes bytes,
+                    uint32_t depth,
+                    std::vector<OtlpAttribute>* out) {
+  otel::KeyValue::Decoder kv(bytes);
+  DecodeAnyValue(prefix + kv.key().ToStdString(), kv.value(), depth, out);
+}
+
+template <typename Iterator>
+std::vector<OtlpAttribute> DecodeAttributes(Iterator it) {
+  std::vector<OtlpAttribute> attributes;
+  for (; it; ++it) {
+    DecodeKeyValue("", *it, 0, &attributes);
+  }
+  return attributes;
+}
+
+void DecodeSpan(protozero::ConstBytes bytes, OtlpSpan* span) {
+  otel::Span::Decoder decoder(bytes);
+  span->trace_id = decoder.trace_id().ToStdString();
+  span->span_id = decoder.span_id().ToStdString();
+  span->parent_span_id = decoder.parent_span_id().ToStdString();
+  span->trace_state = decoder.trace_state().ToStdString();
+  span->name = decoder.name().ToStdString();
+  span->kind = decoder.kind();
+  span->start_time_unix_nano = decoder.start_time_unix_nano();
+  span->end_time_unix_nano = decoder.end_time_unix_nano();
+  span->attributes = DecodeAttributes(decoder.attributes());
+  if (decoder.has_status()) {
+    otel::Status::Decoder status(decoder.status());
+    span->status_code = status.code();
+    span->status_message = status.message().ToStdString();
+  }
+  for (auto it = decoder.events(); it; ++it) {
+    otel::Span::Event::Decoder event(*it);
+    span->events.push_back({event.time_unix_nano(), event.name().ToStdString(),
+                            DecodeAttributes(event.attributes())});
+  }
+  for (auto it = decoder.links(); it; ++it) {
+    otel::Span::Link::Decoder link(*it);
+    span->links.push_back({link.trace_id().ToStdString(),
+                           link.span_id().ToStdString(),
+                           DecodeAttributes(link.attributes())});
+  }
+}
+
+}  // namespace
+
+bool IsOtlpProtoTrace(const uint8_t* data, size_t size) {
+  const uint8_t* ptr = data;
+  const uint8_t* end = data + size;
+
+  // TracesData.resource_spans -> ResourceSpans.scope_spans -> ScopeSpans.spans.
+  if (!FindNestedMessage(&ptr, end, /*wanted_id=*/1, /*max_id=*/1, &end) ||
+      !FindNestedMessage(&ptr, end, /*wanted_id=*/2, /*max_id=*/3, &end) ||
+      !FindNestedMessage(&ptr, end, /*wanted_id=*/2, /*max_id=*/3, &end)) {
+    return false;
+  }
+
+  // Only accept spans which have ids of the right size: this is what tells
+  // OTLP traces apart from Perfetto traces, whose first field is also a
+  // length-delimited field with id 1.
+  bool has_trace_id = false;
+  bool has_span_id = false;
+  while (ptr < end && !(has_trace_id && has_span_id)) {
+    uint32_t field_id;
+    ProtoWireType wire_type;
+    uint64_t length;
+    if (!ReadFieldHeader(&ptr, end, &field_id, &wire_type, &length) ||
+        length > static_cast<uint64_t>(end - ptr)) {
+      return false;
+    }
+    if (field_id == otel::Span::kTraceIdFieldNumber) {
+      if (wire_type != ProtoWireType::kLengthDelimited ||
+          length != kTraceIdSize) {
+        return false;
+      }
+      has_trace_id = true;
+    } else if (field_id == otel::Span::kSpanIdFieldNumber) {
+      if (wire_type != ProtoWireType::kLengthDelimited ||
+          length != kSpanIdSize) {
+        return false;
+      }
+      has_span_id = true;
+    }
+    ptr += length;
+  }
+  return has_trace_id && has_span_id;
+}
+
+base::Status DecodeOtlpProto(const uint8_t* data,
+                             size_t size,
+                             OtlpTrace* trace) {
+  otel::TracesData::Decoder traces_data(data, size);
+  for (auto rs_it = traces_data.resource_spans(); rs_it; ++rs_it) {
+    otel::ResourceSpans::Decoder resource_spans(*rs_it);
+    auto resource_index = static_cast<uint32_t>(trace->resources.size());
+    OtlpResource& resource = trace->resources.emplace_back();
+    if (resource_spans.has_resource()) {
+      otel::Resource::Decoder decoder(resource_spans.resource());
+      resource.attributes = DecodeAttributes(decoder.attributes());
+    }
+    for (auto ss_it = resource_spans.scope_spans(); ss_it; ++ss_it) {
+      otel::ScopeSpans::Decoder scope_spans(*ss_it);
+      otel::InstrumentationScope::Decoder scope(scope_spans.scope());
+      for (auto span_it = scope_spans.spans(); span_it; ++span_it) {
+        OtlpSpan& span = trace->spans.emplace_back();
+        span.resource_index = resource_index;
+        span.scope_name = scope.name().ToStdString();
+        span.scope_version = scope.version().ToStdString();
+        DecodeSpan(*span_it, &span);
+      }
+    }
+  }
+  if (traces_data.bytes_left() != 0) {
+    return base::ErrStatus("OTLP: failed to decode TracesData proto");
+  }
+  return base::OkStatus();
+}
+
+}  // namespace perfetto::trace_processor::otlp_importer
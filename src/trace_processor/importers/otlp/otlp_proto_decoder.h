@@ -0,0 +1,41 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_IMPORTERS_OTLP_OTLP_PROTO_DECODER_H_
+#define SRC_TRACE_PROCESSOR_IMPORTERS_OTLP_OTLP_PROTO_DECODER_H_
+
+#include <cstddef>
+#include <cstdint>
+
+#include "perfetto/base/status.h"
+#include "src/trace_processor/importers/otlp/otlp_trace.h"
+
+namespace perfetto::trace_processor::otlp_importer {
+
+// Returns true if |data| looks like the start of a protobuf encoded OTLP
+// TracesData (or ExportTraceServiceRequest, which has the same wire format)
+// message. |data| does not need to contain the whole message.
+bool IsOtlpProtoTrace(const uint8_t* data, size_t size);
+
+// Decodes a protobuf encoded TracesData message and appends its resources and
+// spans to |trace|.
+base::Status DecodeOtlpProto(const uint8_t* data,
+                             size_t size,
+                             OtlpTrace* trace);
+
+}  // namespace perfetto::trace_processor::otlp_importer
+
+#endif  // SRC_TRACE_PROCESSOR_IMPORTERS_OTLP_OTLP_PROTO_DECODER_H_
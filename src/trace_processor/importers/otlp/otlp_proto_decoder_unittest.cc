@@ -0,0 +1,162 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/otlp/otlp_proto_decoder.h"
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "perfetto/protozero/scattered_heap_buffer.h"
+#include "src/trace_processor/importers/otlp/otlp_trace.h"
+#include "test/gtest_and_gmock.h"
+
+#include "protos/third_party/opentelemetry/trace.pbzero.h"
+
+namespace perfetto::trace_processor::otlp_importer {
+namespace {
+
+namespace otel = ::perfetto::third_party::opentelemetry::proto::pbzero;
+
+using ::testing::ElementsAre;
+using ::testing::Field;
+using ::testing::VariantWith;
+
+const char kTraceId[] = "0123456789abcdef";
+const char kSpanId[] = "01234567";
+const char kParentSpanId[] = "76543210";
+
+std::vector<uint8_t> CreateTrace() {
+  protozero::HeapBuffered<otel::TracesData> traces_data;
+  auto* resource_spans = traces_data->add_resource_spans();
+  auto* service_name = resource_spans->set_resource()->add_attributes();
+  service_name->set_key("service.name");
+  service_name->set_value()->set_string_value("frontend");
+
+  auto* scope_spans = resource_spans->add_scope_spans();
+  scope_spans->set_scope()->set_name("my.library");
+
+  auto* span = scope_spans->add_spans();
+  span->set_trace_id(std::string(kTraceId));
+  span->set_span_id(std::string(kSpanId));
+  span->set_parent_span_id(std::string(kParentSpanId));
+  span->set_name("GET /index.html");
+  span->set_kind(otel::Span::SPAN_KIND_SERVER);
+  span->set_start_time_unix_nano(1000);
+  span->set_end_time_unix_nano(3000);
+
+  auto* array = span->add_attributes();
+  array->set_key("http.headers");
+  auto* values = array->set_value()->set_array_value();
+  values->add_values()->set_int_value(1);
+  values->add_values()->set_bool_value(true);
+
+  auto* kvlist = span->add_attributes();
+  kvlist->set_key("db");
+  auto* kv = kvlist->set_value()->set_kvlist_value()->add_values();
+  kv->set_key("rows");
+  kv->set_value()->set_double_value(1.5);
+
+  auto* event = span->add_events();
+  event->set_time_unix_nano(2000);
+  event->set_name("exception");
+
+  auto* link = span->add_links();
+  link->set_trace_id(std::string(kTraceId));
+  link->set_span_id(std::string(kParentSpanId));
+
+  auto* status = span->set_status();
+  status->set_code(otel::Status::STATUS_CODE_ERROR);
+  status->set_message("failed");
+  return traces_data.SerializeAsArray();
+}
+
+TEST(OtlpProtoDecoderTest, IsOtlpProtoTrace) {
+  std::vector<uint8_t> trace = CreateTrace();
+  EXPECT_TRUE(IsOtlpProtoTrace(trace.data(), trace.size()));
+
+  // Only the start of the trace is needed.
+  EXPECT_TRUE(IsOtlpProtoTrace(trace.data(), trace.size() / 2));
+}
+
+TEST(OtlpProtoDecoderTest, IsNotOtlpProtoTrace) {
+  // A Perfetto trace: a TracePacket with a timestamp and a clock_id.
+  const uint8_t perfetto_trace[] = {0x0a, 0x05, 0x40, 0x01, 0xd0, 0x03, 0x06};
+  EXPECT_FALSE(IsOtlpProtoTrace(perfetto_trace, sizeof(perfetto_trace)));
+
+  // A span with ids of the wrong size.
+  protozero::HeapBuffered<otel::TracesData> traces_data;
+  auto* span =
+      traces_data->add_resource_spans()->add_scope_spans()->add_spans();
+  span->set_trace_id(std::string("abc"));
+  span->set_span_id(std::string(kSpanId));
+  std::vector<uint8_t> trace = traces_data.SerializeAsArray();
+  EXPECT_FALSE(IsOtlpProtoTrace(trace.data(), trace.size()));
+}
+
+TEST(OtlpProtoDecoderTest, DecodeSpan) {
+  std::vector<uint8_t> data = CreateTrace();
+  OtlpTrace trace;
+  ASSERT_TRUE(DecodeOtlpProto(data.data(), data.size(), &trace).ok());
+
+  ASSERT_EQ(trace.resources.size(), 1u);
+  EXPECT_THAT(trace.resources[0].attributes,
+              ElementsAre(AllOf(
+                  Field(&OtlpAttribute::key, "service.name"),
+                  Field(&OtlpAttribute::value,
+                        VariantWith<std::string>("frontend")))));
+
+  ASSERT_EQ(trace.spans.size(), 1u);
+  const OtlpSpan& span = trace.spans[0];
+  EXPECT_EQ(span.resource_index, 0u);
+  EXPECT_EQ(span.scope_name, "my.library");
+  EXPECT_EQ(span.trace_id, kTraceId);
+  EXPECT_EQ(span.span_id, kSpanId);
+  EXPECT_EQ(span.parent_span_id, kParentSpanId);
+  EXPECT_EQ(span.name, "GET /index.html");
+  EXPECT_EQ(span.kind, otel::Span::SPAN_KIND_SERVER);
+  EXPECT_EQ(span.start_time_unix_nano, 1000u);
+  EXPECT_EQ(span.end_time_unix_nano, 3000u);
+  EXPECT_EQ(span.status_code, otel::Status::STATUS_CODE_ERROR);
+  EXPECT_EQ(span.status_message, "failed");
+
+  EXPECT_THAT(
+      span.attributes,
+      ElementsAre(
+          AllOf(Field(&OtlpAttribute::key, "http.headers[0]"),
+                Field(&OtlpAttribute::value, VariantWith<int64_t>(1))),
+          AllOf(Field(&OtlpAttribute::key, "http.headers[1]"),
+                Field(&OtlpAttribute::value, VariantWith<bool>(true))),
+          AllOf(Field(&OtlpAttribute::key, "db.rows"),
+                Field(&OtlpAttribute::value, VariantWith<double>(1.5)))));
+
+  ASSERT_EQ(span.events.size(), 1u);
+  EXPECT_EQ(span.events[0].time_unix_nano, 2000u);
+  EXPECT_EQ(span.events[0].name, "exception");
+
+  ASSERT_EQ(span.links.size(), 1u);
+  EXPECT_EQ(span.links[0].trace_id, kTraceId);
+  EXPECT_EQ(span.links[0].span_id, kParentSpanId);
+}
+
+TEST(OtlpProtoDecoderTest, MalformedProto) {
+  const uint8_t data[] = {0x0a, 0x10, 0x01};
+  OtlpTrace trace;
+  EXPECT_FALSE(DecodeOtlpProto(data, sizeof(data), &trace).ok());
+}
+
+}  // namespace
+}  // namespace perfetto::trace_processor::otlp_importer
@@ -0,0 +1,89 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_IMPORTERS_OTLP_OTLP_TRACE_H_
+#define SRC_TRACE_PROCESSOR_IMPORTERS_OTLP_OTLP_TRACE_H_
+
+#include <cstdint>
+#include <string>
+#include <variant>
+#include <vector>
+
+namespace perfetto::trace_processor::otlp_importer {
+
+// In-memory representation of the spans of an OpenTelemetry (OTLP) trace,
+// which is shared by the protobuf and the JSON decoders.
+
+struct OtlpAttribute {
+  // Arrays and key-value lists are flattened by the decoders: e.g. the
+  // attribute {"a": {"b": [1, 2]}} is turned into the "a.b[0]" and "a.b[1]"
+  // attributes.
+  std::string key;
+  // Bytes values are stored as hex strings.
+  std::variant<std::string, int64_t, double, bool> value;
+};
+
+struct OtlpResource {
+  std::vector<OtlpAttribute> attributes;
+};
+
+struct OtlpSpanEvent {
+  uint64_t time_unix_nano = 0;
+  std::string name;
+  std::vector<OtlpAttribute> attributes;
+};
+
+struct OtlpSpanLink {
+  std::string trace_id;
+  std::string span_id;
+  std::vector<OtlpAttribute> attributes;
+};
+
+struct OtlpSpan {
+  // Index in OtlpTrace::resources.
+  uint32_t resource_index = 0;
+  std::string scope_name;
+  std::string scope_version;
+
+  // Ids are stored as raw bytes: 16 bytes for trace ids, 8 bytes for span
+  // ids. |parent_span_id| is empty for root spans.
+  std::string trace_id;
+  std::string span_id;
+  std::string parent_span_id;
+  std::string trace_state;
+
+  std::string name;
+  // Values of the Span::SpanKind and Status::StatusCode enums.
+  int32_t kind = 0;
+  int32_t status_code = 0;
+  std::string status_message;
+
+  uint64_t start_time_unix_nano = 0;
+  uint64_t end_time_unix_nano = 0;
+
+  std::vector<OtlpAttribute> attributes;
+  std::vector<OtlpSpanEvent> events;
+  std::vector<OtlpSpanLink> links;
+};
+
+struct OtlpTrace {
+  std::vector<OtlpResource> resources;
+  std::vector<OtlpSpan> spans;
+};
+
+}  // namespace perfetto::trace_processor::otlp_importer
+
+#endif  // SRC_TRACE_PROCESSOR_IMPORTERS_OTLP_OTLP_TRACE_H_
@@ -0,0 +1,479 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/otlp/otlp_trace_reader.h"
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <string_view>
+#include <tuple>
+#include <utility>
+#include <variant>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/status_macros.h"
+#include "perfetto/ext/base/status_or.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "perfetto/ext/base/string_view.h"
+#include "perfetto/trace_processor/trace_blob_view.h"
+#include "src/trace_processor/importers/common/args_tracker.h"
+#include "src/trace_processor/importers/common/clock_tracker.h"
+#include "src/trace_processor/importers/common/flow_tracker.h"
+#include "src/trace_processor/importers/common/process_tracker.h"
+#include "src/trace_processor/importers/common/slice_tracker.h"
+#include "src/trace_processor/importers/common/track_tracker.h"
+#include "src/trace_processor/importers/common/tracks.h"
+#include "src/trace_processor/importers/common/tracks_common.h"
+#include "src/trace_processor/importers/otlp/otlp_json_decoder.h"
+#include "src/trace_processor/importers/otlp/otlp_proto_decoder.h"
+#include "src/trace_processor/importers/otlp/otlp_trace.h"
+#include "src/trace_processor/storage/stats.h"
+#include "src/trace_processor/storage/trace_storage.h"
+#include "src/trace_processor/types/trace_processor_context.h"
+#include "src/trace_processor/types/variadic.h"
+
+#include "protos/perfetto/trace/clock_snapshot.pbzero.h"
+#include "protos/third_party/opentelemetry/trace.pbzero.h"
+
+namespace perfetto::trace_processor::otlp_importer {
+
+namespace {
+
+namespace otel = ::perfetto::third_party::opentelemetry::proto::pbzero;
+
+constexpr auto kOtlpSpanBlueprint = tracks::SliceBlueprint(
+    "otlp_span",
+    tracks::DimensionBlueprints(tracks::kProcessDimensionBlueprint,
+                                tracks::UintDimensionBlueprint("otlp_lane")),
+    tracks::StaticNameBlueprint("Spans"));
+
+// Attributes defined by the OpenTelemetry semantic conventions.
+constexpr std::string_view kServiceNameAttribute = "service.name";
+constexpr std::string_view kServiceInstanceIdAttribute = "service.instance.id";
+constexpr std::string_view kHostNameAttribute = "host.name";
+constexpr std::string_view kProcessPidAttribute = "process.pid";
+constexpr std::string_view kThreadIdAttribute = "thread.id";
+constexpr std::string_view kThreadNameAttribute = "thread.name";
+
+// Spans and span events, sorted in the order in which they are added to
+// SliceTracker.
+struct SliceItem {
+  int64_t ts;
+  int64_t dur;
+  uint32_t span_index;
+  std::optional<uint32_t> event_index;
+
+  // Outer slices must come before the slices they contain, so longer slices
+  // come first on ties. Events come after the span they belong to.
+  bool operator<(const SliceItem& other) const {
+    return std::make_tuple(ts, -dur, event_index.has_value(), span_index,
+                           event_index.value_or(0)) <
+           std::make_tuple(other.ts, -other.dur,
+                           other.event_index.has_value(), other.span_index,
+                           other.event_index.value_or(0));
+  }
+};
+
+const OtlpAttribute* FindAttribute(const std::vector<OtlpAttribute>& attrs,
+                                   std::string_view key) {
+  auto it =
+      std::find_if(attrs.begin(), attrs.end(),
+                   [key](const OtlpAttribute& a) { return a.key == key; });
+  return it == attrs.end() ? nullptr : &*it;
+}
+
+std::optional<int64_t> GetIntAttribute(const std::vector<OtlpAttribute>& attrs,
+                                       std::string_view key) {
+  const OtlpAttribute* attr = FindAttribute(attrs, key);
+  if (!attr) {
+    return std::nullopt;
+  }
+  if (const auto* value = std::get_if<int64_t>(&attr->value); value) {
+    return *value;
+  }
+  // Some SDKs record ids as strings.
+  if (const auto* value = std::get_if<std::string>(&attr->value); value) {
+    return base::StringToInt64(*value);
+  }
+  return std::nullopt;
+}
+
+std::string GetStringAttribute(const std::vector<OtlpAttribute>& attrs,
+                               std::string_view key) {
+  const OtlpAttribute* attr = FindAttribute(attrs, key);
+  if (!attr) {
+    return {};
+  }
+  if (const auto* value = std::get_if<std::string>(&attr->value); value) {
+    return *value;
+  }
+  return {};
+}
+
+// Returns |key| without the array indexes, e.g. "a[0].b" -> "a.b".
+std::string ToFlatKey(std::string_view key) {
+  std::string flat_key;
+  flat_key.reserve(key.size());
+  bool in_index = false;
+  for (char c : key) {
+    if (c == '[') {
+      in_index = true;
+    } else if (c == ']') {
+      in_index = false;
+    } else if (!in_index) {
+      flat_key.push_back(c);
+    }
+  }
+  return flat_key;
+}
+
+std::string SpanKey(const std::string& trace_id, const std::string& span_id) {
+  return trace_id + span_id;
+}
+
+}  // namespace
+
+OtlpTraceReader::OtlpTraceReader(TraceProcessorContext* context)
+    : context_(context) {}
+OtlpTraceReader::~OtlpTraceReader() = default;
+
+base::Status OtlpTraceReader::Parse(TraceBlobView blob) {
+  buffer_.append(reinterpret_cast<const char*>(blob.data()), blob.size());
+  return base::OkStatus();
+}
+
+base::Status OtlpTraceReader::NotifyEndOfFile() {
+  OtlpTrace trace;
+  size_t first = buffer_.find_first_not_of(" \t\r\n");
+  if (first != std::string::npos && buffer_[first] == '{') {
+    RETURN_IF_ERROR(DecodeOtlpJson(buffer_, &trace));
+  } else {
+    RETURN_IF_ERROR(DecodeOtlpProto(
+        reinterpret_cast<const uint8_t*>(buffer_.data()), buffer_.size(),
+        &trace));
+  }
+  std::string().swap(buffer_);
+
+  context_->clock_tracker->SetTraceTimeClock(
+      protos::pbzero::ClockSnapshot::Clock::REALTIME);
+  ImportTrace(trace);
+  return base::OkStatus();
+}
+
+void OtlpTraceReader::ImportTrace(const OtlpTrace& trace) {
+  auto* storage = context_->storage.get();
+  std::vector<Process> processes;
+  processes.reserve(trace.resources.size());
+  for (const OtlpResource& resource : trace.resources) {
+    processes.push_back(ImportResource(resource));
+  }
+
+  base::FlatHashMap<std::string, uint32_t> span_indexes;
+  std::vector<SliceItem> items;
+  for (uint32_t i = 0; i < trace.spans.size(); ++i) {
+    const OtlpSpan& span = trace.spans[i];
+    span_indexes.Insert(SpanKey(span.trace_id, span.span_id), i);
+
+    if (span.start_time_unix_nano == 0) {
+      storage->IncrementStats(stats::otlp_invalid_spans);
+      continue;
+    }
+    base::StatusOr<int64_t> ts = context_->clock_tracker->ToTraceTime(
+        protos::pbzero::ClockSnapshot::Clock::REALTIME,
+        static_cast<int64_t>(span.start_time_unix_nano));
+    if (!ts.ok()) {
+      storage->IncrementStats(stats::otlp_invalid_spans);
+      continue;
+    }
+    int64_t dur = 0;
+    if (span.end_time_unix_nano > span.start_time_unix_nano) {
+      dur = static_cast<int64_t>(span.end_time_unix_nano -
+                                 span.start_time_unix_nano);
+    }
+    items.push_back({*ts, dur, i, std::nullopt});
+
+    // Events are clamped to the bounds of their span so that they nest.
+    for (uint32_t j = 0; j < span.events.size(); ++j) {
+      uint64_t time = span.events[j].time_unix_nano;
+      int64_t offset = 0;
+      if (time > span.start_time_unix_nano) {
+        offset = std::min(
+            static_cast<int64_t>(time - span.start_time_unix_nano), dur);
+      }
+      items.push_back({*ts + offset, 0, i, j});
+    }
+  }
+  std::sort(items.begin(), items.end());
+
+  std::vector<std::optional<SliceId>> span_slices(trace.spans.size());
+  std::vector<uint32_t> span_lanes(trace.spans.size());
+  for (const SliceItem& item : items) {
+    const OtlpSpan& span = trace.spans[item.span_index];
+    StringId category = span.scope_name.empty()
+                            ? kNullStringId
+                            : storage->InternString(span.scope_name);
+
+    if (item.event_index) {
+      if (!span_slices[item.span_index]) {
+        continue;
+      }
+      const OtlpSpanEvent& event = span.events[*item.event_index];
+      Lane& lane = lanes_[span_lanes[item.span_index]];
+      PopEndedSlices(&lane, item.ts, 0);
+      context_->slice_tracker->Scoped(
+          item.ts, lane.track_id, category, storage->InternString(event.name),
+          0, [&](ArgsTracker::BoundInserter* inserter) {
+            AddAttributes("attributes", event.attributes, inserter);
+          });
+      lane.stack.push_back({item.ts, true, std::nullopt});
+      continue;
+    }
+
+    const Process& process = processes[span.resource_index];
+    std::optional<UniqueTid> utid;
+    if (auto tid = GetIntAttribute(span.attributes, kThreadIdAttribute);
+        tid && process.pid) {
+      utid = context_->process_tracker->UpdateThread(*tid, *process.pid);
+      std::string name = GetStringAttribute(span.attributes,
+                                            kThreadNameAttribute);
+      if (!name.empty()) {
+        context_->process_tracker->UpdateThreadName(
+            *utid, storage->InternString(name), ThreadNamePriority::kOther);
+      }
+    }
+    std::optional<uint32_t> parent_index;
+    if (!span.parent_span_id.empty()) {
+      if (uint32_t* index =
+              span_indexes.Find(SpanKey(span.trace_id, span.parent_span_id));
+          index) {
+        parent_index = *index;
+      }
+    }
+
+    uint32_t lane_index =
+        ChooseLane(utid, process.upid, parent_index, item.ts, item.dur);
+    Lane& lane = lanes_[lane_index];
+    std::optional<SliceId> slice_id = context_->slice_tracker->Scoped(
+        item.ts, lane.track_id, category, storage->InternString(span.name),
+        item.dur, [&](ArgsTracker::BoundInserter* inserter) {
+          AddSpanArgs(span, inserter);
+        });
+    if (!slice_id) {
+      continue;
+    }
+    lane.stack.push_back({item.ts + item.dur, item.dur == 0, item.span_index});
+    span_slices[item.span_index] = slice_id;
+    span_lanes[item.span_index] = lane_index;
+  }
+
+  // Turn the links and the parent-child relations which are not already
+  // visible through the nesting of the slices into flows.
+  for (uint32_t i = 0; i < trace.spans.size(); ++i) {
+    const OtlpSpan& span = trace.spans[i];
+    if (!span_slices[i]) {
+      continue;
+    }
+    if (!span.parent_span_id.empty()) {
+      uint32_t* parent =
+          span_indexes.Find(SpanKey(span.trace_id, span.parent_span_id));
+      if (parent && span_slices[*parent] &&
+          span_lanes[*parent] != span_lanes[i]) {
+        context_->flow_tracker->InsertFlow(*span_slices[*parent],
+                                           *span_slices[i]);
+      }
+    }
+    for (const OtlpSpanLink& link : span.links) {
+      uint32_t* linked =
+          span_indexes.Find(SpanKey(link.trace_id, link.span_id));
+      if (!linked || !span_slices[*linked]) {
+        storage->IncrementStats(stats::otlp_unresolved_links);
+        continue;
+      }
+      context_->flow_tracker->InsertFlow(*span_slices[*linked],
+                                         *span_slices[i]);
+    }
+  }
+}
+
+OtlpTraceReader::Process OtlpTraceReader::ImportResource(
+    const OtlpResource& resource) {
+  const auto& attrs = resource.attributes;
+  std::optional<int64_t> pid = GetIntAttribute(attrs, kProcessPidAttribute);
+  std::string service_name = GetStringAttribute(attrs, kServiceNameAttribute);
+
+  // Different batches of spans from the same process usually come in
+  // different ResourceSpans, which all have the same resource.
+  std::string key = service_name + '\0' +
+                    GetStringAttribute(attrs, kServiceInstanceIdAttribute) +
+                    '\0' + GetStringAttribute(attrs, kHostNameAttribute) +
+                    '\0' + (pid ? std::to_string(*pid) : "");
+  if (UniquePid* upid = processes_.Find(key); upid) {
+    return {*upid, pid};
+  }
+
+  auto* storage = context_->storage.get();
+  UniquePid upid;
+  if (pid) {
+    upid = context_->process_tracker->GetOrCreateProcess(*pid);
+  } else {
+    upid = context_->process_tracker->StartNewProcess(
+        std::nullopt, std::nullopt, 0, kNullStringId,
+        ThreadNamePriority::kOther);
+  }
+  if (!service_name.empty()) {
+    context_->process_tracker->SetProcessNameIfUnset(
+        upid, storage->InternString(service_name));
+  }
+  auto inserter = context_->process_tracker->AddArgsTo(upid);
+  AddAttributes("resource", attrs, &inserter);
+  processes_.Insert(std::move(key), upid);
+  return {upid, pid};
+}
+
+void OtlpTraceReader::PopEndedSlices(Lane* lane, int64_t ts, int64_t dur) {
+  while (!lane->stack.empty()) {
+    const Lane::OpenSlice& top = lane->stack.back();
+    bool ends_before = top.end < ts;
+    bool ends_same_and_should_drop =
+        top.end == ts && !(top.is_instant && dur == 0);
+    if (!ends_before && !ends_same_and_should_drop) {
+      return;
+    }
+    lane->stack.pop_back();
+  }
+}
+
+uint32_t OtlpTraceReader::ChooseLane(std::optional<UniqueTid> utid,
+                                     UniquePid upid,
+                                     std::optional<uint32_t> parent_index,
+                                     int64_t ts,
+                                     int64_t dur) {
+  auto fits = [&](uint32_t lane_index) {
+    Lane& lane = lanes_[lane_index];
+    PopEndedSlices(&lane, ts, dur);
+    return lane.stack.empty() || ts + dur <= lane.stack.back().end;
+  };
+
+  if (utid) {
+    auto [lane_index, inserted] = thread_lanes_.Insert(*utid, 0);
+    if (inserted) {
+      *lane_index = static_cast<uint32_t>(lanes_.size());
+      lanes_.push_back({context_->track_tracker->InternThreadTrack(*utid), {}});
+    }
+    if (fits(*lane_index)) {
+      return *lane_index;
+    }
+  }
+
+  // Prefer the lane where the parent span is the innermost open slice, so
+  // that the span is nested under it.
+  std::vector<uint32_t>& process_lanes = async_lanes_[upid];
+  std::optional<uint32_t> first_fit;
+  for (uint32_t lane_index : process_lanes) {
+    if (!fits(lane_index)) {
+      continue;
+    }
+    const auto& stack = lanes_[lane_index].stack;
+    if (parent_index && !stack.empty() &&
+        stack.back().span_index == parent_index) {
+      return lane_index;
+    }
+    if (!first_fit) {
+      first_fit = lane_index;
+    }
+  }
+  if (first_fit) {
+    return *first_fit;
+  }
+
+  TrackId track_id = context_->track_tracker->InternTrack(
+      kOtlpSpanBlueprint,
+      tracks::Dimensions(upid, static_cast<uint32_t>(process_lanes.size())));
+  auto lane_index = static_cast<uint32_t>(lanes_.size());
+  lanes_.push_back({track_id, {}});
+  process_lanes.push_back(lane_index);
+  return lane_index;
+}
+
+void OtlpTraceReader::AddSpanArgs(const OtlpSpan& span,
+                                  ArgsTracker::BoundInserter* inserter) {
+  auto add_string = [&](const std::string& key, const std::string& value) {
+    AddArg(key, Variadic::String(context_->storage->InternString(value)),
+           inserter);
+  };
+  add_string("otlp.trace_id", base::ToHex(span.trace_id));
+  add_string("otlp.span_id", base::ToHex(span.span_id));
+  if (!span.parent_span_id.empty()) {
+    add_string("otlp.parent_span_id", base::ToHex(span.parent_span_id));
+  }
+  if (!span.trace_state.empty()) {
+    add_string("otlp.trace_state", span.trace_state);
+  }
+  add_string("otlp.kind", otel::Span_SpanKind_Name(
+                              static_cast<otel::Span_SpanKind>(span.kind)));
+  if (span.status_code != otel::Status::STATUS_CODE_UNSET) {
+    add_string("otlp.status.code",
+               otel::Status_StatusCode_Name(
+                   static_cast<otel::Status_StatusCode>(span.status_code)));
+  }
+  if (!span.status_message.empty()) {
+    add_string("otlp.status.message", span.status_message);
+  }
+  if (!span.scope_version.empty()) {
+    add_string("otlp.scope.version", span.scope_version);
+  }
+  AddAttributes("attributes", span.attributes, inserter);
+  for (size_t i = 0; i < span.links.size(); ++i) {
+    const OtlpSpanLink& link = span.links[i];
+    std::string prefix = "otlp.links[" + std::to_string(i) + "]";
+    add_string(prefix + ".trace_id", base::ToHex(link.trace_id));
+    add_string(prefix + ".span_id", base::ToHex(link.span_id));
+    AddAttributes(prefix + ".attributes", link.attributes, inserter);
+  }
+}
+
+void OtlpTraceReader::AddAttributes(
+    const std::string& prefix,
+    const std::vector<OtlpAttribute>& attributes,
+    ArgsTracker::BoundInserter* inserter) {
+  for (const OtlpAttribute& attr : attributes) {
+    Variadic value = Variadic::Null();
+    if (const auto* s = std::get_if<std::string>(&attr.value); s) {
+      value = Variadic::String(context_->storage->InternString(*s));
+    } else if (const auto* i = std::get_if<int64_t>(&attr.value); i) {
+      value = Variadic::Integer(*i);
+    } else if (const auto* d = std::get_if<double>(&attr.value); d) {
+      value = Variadic::Real(*d);
+    } else if (const auto* b = std::get_if<bool>(&attr.value); b) {
+      value = Variadic::Boolean(*b);
+    }
+    AddArg(prefix + "." + attr.key, value, inserter);
+  }
+}
+
+void OtlpTraceReader::AddArg(const std::string& key,
+                             Variadic value,
+                             ArgsTracker::BoundInserter* inserter) {
+  auto* storage = context_->storage.get();
+  inserter->AddArg(storage->InternString(ToFlatKey(key)),
+                   storage->InternString(key), value);
+}
+
+}  // namespace perfetto::trace_processor::otlp_importer
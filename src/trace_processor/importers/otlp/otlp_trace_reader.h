@@ -0,0 +1,119 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_IMPORTERS_OTLP_OTLP_TRACE_READER_H_
+#define SRC_TRACE_PROCESSOR_IMPORTERS_OTLP_OTLP_TRACE_READER_H_
+
+#include <cstdint>
+#include <deque>
+#include <optional>
+#include <string>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/flat_hash_map.h"
+#include "src/trace_processor/importers/common/args_tracker.h"
+#include "src/trace_processor/importers/common/chunked_trace_reader.h"
+#include "src/trace_processor/storage/trace_storage.h"
+#include "src/trace_processor/types/variadic.h"
+
+namespace perfetto::trace_processor {
+
+class TraceProcessorContext;
+
+namespace otlp_importer {
+
+struct OtlpAttribute;
+struct OtlpResource;
+struct OtlpSpan;
+struct OtlpTrace;
+
+// Imports OpenTelemetry (OTLP) trace files, encoded either as protobuf or as
+// JSON, into the slice table.
+//
+// Each resource (i.e. each instance of a service) is mapped to a process and
+// the spans are turned into slices:
+//  * on the thread track, if the span has the "thread.id" attribute and the
+//    resource has the "process.pid" attribute and if the span nests properly
+//    with the other spans of the thread.
+//  * on process scoped async tracks otherwise. Spans are distributed across
+//    the tracks so that spans nest under their parent span whenever
+//    possible.
+// Span links, as well as parent-child relations across tracks (e.g. a client
+// span in one service and the server span in another), are turned into flows.
+//
+// The trace and span ids are stored in the args of the slices so that they
+// can be joined with other data sources.
+class OtlpTraceReader : public ChunkedTraceReader {
+ public:
+  explicit OtlpTraceReader(TraceProcessorContext*);
+  ~OtlpTraceReader() override;
+
+  base::Status Parse(TraceBlobView) override;
+  base::Status NotifyEndOfFile() override;
+
+ private:
+  // A stack of properly nested slices on a track, used to decide on which
+  // track each span goes.
+  struct Lane {
+    struct OpenSlice {
+      int64_t end;
+      bool is_instant;
+      std::optional<uint32_t> span_index;
+    };
+    TrackId track_id;
+    std::vector<OpenSlice> stack;
+  };
+
+  // The process a resource is mapped to.
+  struct Process {
+    UniquePid upid;
+    std::optional<int64_t> pid;
+  };
+
+  void ImportTrace(const OtlpTrace&);
+  Process ImportResource(const OtlpResource&);
+  uint32_t ChooseLane(std::optional<UniqueTid> utid,
+                      UniquePid upid,
+                      std::optional<uint32_t> parent_index,
+                      int64_t ts,
+                      int64_t dur);
+  void AddSpanArgs(const OtlpSpan&, ArgsTracker::BoundInserter*);
+  void AddAttributes(const std::string& prefix,
+                     const std::vector<OtlpAttribute>&,
+                     ArgsTracker::BoundInserter*);
+  void AddArg(const std::string& key, Variadic, ArgsTracker::BoundInserter*);
+
+  // Pops the slices of |lane| which end before a new slice starting at |ts|
+  // with duration |dur|, using the same logic as SliceTracker.
+  static void PopEndedSlices(Lane*, int64_t ts, int64_t dur);
+
+  TraceProcessorContext* const context_;
+  std::string buffer_;
+
+  // Processes, keyed by the attributes of their resource.
+  base::FlatHashMap<std::string, UniquePid> processes_;
+
+  std::deque<Lane> lanes_;
+  // Indexes in |lanes_|.
+  base::FlatHashMap<UniqueTid, uint32_t> thread_lanes_;
+  base::FlatHashMap<UniquePid, std::vector<uint32_t>> async_lanes_;
+};
+
+}  // namespace otlp_importer
+}  // namespace perfetto::trace_processor
+
+#endif  // SRC_TRACE_PROCESSOR_IMPORTERS_OTLP_OTLP_TRACE_READER_H_
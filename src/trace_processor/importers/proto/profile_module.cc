@@ -410,6 +410,8 @@ void ProfileModule::ParseProfilePacket(
         src_allocation.self_freed = sample.self_freed();
         src_allocation.alloc_count = sample.alloc_count();
         src_allocation.free_count = sample.free_count();
+        src_allocation.self_unreachable = sample.self_unreachable();
+        src_allocation.unreachable_count = sample.unreachable_count();
       }
 
       profile_packet_sequence_state.StoreAllocation(src_allocation);
@@ -275,6 +275,36 @@ void ProfilePacketSequenceState::AddAllocation(const SourceAllocation& alloc) {
 
   *prev_alloc = alloc_row;
   *prev_free = free_row;
+
+  // Unreachable allocations are a snapshot at the time of the dump rather
+  // than a running total, so they are also stored as the delta to the
+  // previous dump.
+  auto* prev_unreachable = prev_unreachable_.Find({upid, callstack_id});
+  if (!prev_unreachable && alloc.unreachable_count == 0)
+    return;
+  if (!prev_unreachable) {
+    prev_unreachable =
+        prev_unreachable_
+            .Insert(std::make_pair(upid, callstack_id),
+                    tables::HeapProfileUnreachableAllocationTable::Row{})
+            .first;
+  }
+  tables::HeapProfileUnreachableAllocationTable::Row unreachable_row{
+      alloc.timestamp,
+      upid,
+      alloc.heap_name,
+      callstack_id,
+      static_cast<int64_t>(alloc.unreachable_count),
+      static_cast<int64_t>(alloc.self_unreachable)};
+  tables::HeapProfileUnreachableAllocationTable::Row unreachable_delta =
+      unreachable_row;
+  unreachable_delta.count -= prev_unreachable->count;
+  unreachable_delta.size -= prev_unreachable->size;
+  if (unreachable_delta.count || unreachable_delta.size) {
+    context_->storage->mutable_heap_profile_unreachable_allocation_table()
+        ->Insert(unreachable_delta);
+  }
+  *prev_unreachable = unreachable_row;
 }
 
 std::optional<CallsiteId> ProfilePacketSequenceState::FindOrInsertCallstack(
@@ -68,6 +68,8 @@ class ProfilePacketSequenceState final
     uint64_t self_freed = 0;
     uint64_t alloc_count = 0;
     uint64_t free_count = 0;
+    uint64_t self_unreachable = 0;
+    uint64_t unreachable_count = 0;
   };
 
   explicit ProfilePacketSequenceState(TraceProcessorContext* context);
@@ -146,6 +148,10 @@ class ProfilePacketSequenceState final
                     tables::HeapProfileAllocationTable::Row,
                     Hasher>
       prev_free_;
+  base::FlatHashMap<std::pair<UniquePid, CallsiteId>,
+                    tables::HeapProfileUnreachableAllocationTable::Row,
+                    Hasher>
+      prev_unreachable_;
 
   // For continuous dumps, we only store the delta in the data-base. To do
   // this, we subtract the previous dump's value. Sometimes, we should not
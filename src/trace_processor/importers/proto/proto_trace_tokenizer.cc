@@ -50,5 +50,31 @@ base::Status ProtoTraceTokenizer::Decompress(TraceBlobView input,
   return base::OkStatus();
 }
 
+base::Status ProtoTraceTokenizer::ZstdDecompress(TraceBlobView input,
+                                                 TraceBlobView* output) {
+  PERFETTO_DCHECK(util::IsZstdSupported());
+
+  std::vector<uint8_t> data;
+  data.reserve(input.length());
+
+  // Ensure that the decompressor is able to cope with a new frame.
+  zstd_decompressor_.Reset();
+  using ResultCode = util::ZstdDecompressor::ResultCode;
+  ResultCode ret = zstd_decompressor_.FeedAndExtract(
+      input.data(), input.length(),
+      [&data](const uint8_t* buffer, size_t buffer_len) {
+        data.insert(data.end(), buffer, buffer + buffer_len);
+      });
+
+  if (ret == ResultCode::kError || ret == ResultCode::kNeedsMoreInput) {
+    return base::ErrStatus("Failed to decompress zstd (error code: %d)",
+                           static_cast<int>(ret));
+  }
+
+  TraceBlob out_blob = TraceBlob::CopyFrom(data.data(), data.size());
+  *output = TraceBlobView(std::move(out_blob));
+  return base::OkStatus();
+}
+
 }  // namespace trace_processor
 }  // namespace perfetto
@@ -31,6 +31,7 @@
 #include "perfetto/trace_processor/trace_blob_view.h"
 #include "protos/perfetto/trace/trace_packet.pbzero.h"
 #include "src/trace_processor/util/gzip_utils.h"
+#include "src/trace_processor/util/zstd_utils.h"
 
 #include "perfetto/ext/base/status_macros.h"
 #include "protos/perfetto/trace/trace.pbzero.h"
@@ -178,15 +179,24 @@ class ProtoTraceTokenizer {
         continue;
       }
 
-      if (!util::IsGzipSupported()) {
+      protozero::ConstBytes field = decoder.compressed_packets();
+      bool is_zstd = util::IsZstdFrame(field.data, field.size);
+      if (is_zstd && !util::IsZstdSupported()) {
+        return base::ErrStatus(
+            "Cannot decode zstd compressed packets. Zstd not enabled");
+      }
+      if (!is_zstd && !util::IsGzipSupported()) {
         return base::ErrStatus(
             "Cannot decode compressed packets. Zlib not enabled");
       }
 
-      protozero::ConstBytes field = decoder.compressed_packets();
-      TraceBlobView compressed_packets = packet->slice(field.data, field.size);
+      TraceBlobView compressed = packet->slice(field.data, field.size);
       TraceBlobView packets;
-      RETURN_IF_ERROR(Decompress(std::move(compressed_packets), &packets));
+      if (is_zstd) {
+        RETURN_IF_ERROR(ZstdDecompress(std::move(compressed), &packets));
+      } else {
+        RETURN_IF_ERROR(Decompress(std::move(compressed), &packets));
+      }
 
       const uint8_t* start = packets.data();
       const uint8_t* end = packets.data() + packets.length();
@@ -216,6 +226,7 @@ class ProtoTraceTokenizer {
           protos::pbzero::Trace::kPacketFieldNumber);
 
   base::Status Decompress(TraceBlobView input, TraceBlobView* output);
+  base::Status ZstdDecompress(TraceBlobView input, TraceBlobView* output);
 
   // Used to glue together trace packets that span across two (or more)
   // Parse() boundaries.
@@ -223,6 +234,9 @@ class ProtoTraceTokenizer {
 
   // Allows support for compressed trace packets.
   util::GzipDecompressor decompressor_;
+
+  // Allows support for zstd compressed trace packets.
+  util::ZstdDecompressor zstd_decompressor_;
 };
 
 }  // namespace perfetto::trace_processor
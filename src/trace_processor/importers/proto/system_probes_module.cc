@@ -31,6 +31,8 @@ SystemProbesModule::SystemProbesModule(TraceProcessorContext* context)
   RegisterForField(TracePacket::kProcessTreeFieldNumber, context);
   RegisterForField(TracePacket::kProcessStatsFieldNumber, context);
   RegisterForField(TracePacket::kSysStatsFieldNumber, context);
+  RegisterForField(TracePacket::kCgroupStatsFieldNumber, context);
+  RegisterForField(TracePacket::kSyscallLatencyFieldNumber, context);
   RegisterForField(TracePacket::kSystemInfoFieldNumber, context);
   RegisterForField(TracePacket::kCpuInfoFieldNumber, context);
 }
@@ -67,6 +69,12 @@ void SystemProbesModule::ParseTracePacketData(
     case TracePacket::kSysStatsFieldNumber:
       parser_.ParseSysStats(ts, decoder.sys_stats());
       return;
+    case TracePacket::kCgroupStatsFieldNumber:
+      parser_.ParseCgroupStats(ts, decoder.cgroup_stats());
+      return;
+    case TracePacket::kSyscallLatencyFieldNumber:
+      parser_.ParseSyscallLatency(ts, decoder.syscall_latency());
+      return;
   }
 }
 
@@ -52,6 +52,7 @@
 #include "src/trace_processor/storage/stats.h"
 #include "src/trace_processor/storage/trace_storage.h"
 #include "src/trace_processor/tables/metadata_tables_py.h"
+#include "src/trace_processor/tables/sched_tables_py.h"
 #include "src/trace_processor/types/trace_processor_context.h"
 #include "src/trace_processor/types/variadic.h"
 
@@ -59,7 +60,9 @@
 #include "protos/perfetto/common/system_info.pbzero.h"
 #include "protos/perfetto/trace/ps/process_stats.pbzero.h"
 #include "protos/perfetto/trace/ps/process_tree.pbzero.h"
+#include "protos/perfetto/trace/sys_stats/cgroup_stats.pbzero.h"
 #include "protos/perfetto/trace/sys_stats/sys_stats.pbzero.h"
+#include "protos/perfetto/trace/sys_stats/syscall_latency.pbzero.h"
 #include "protos/perfetto/trace/system_info/cpu_info.pbzero.h"
 
 namespace perfetto::trace_processor {
@@ -236,7 +239,8 @@ SystemProbesParser::SystemProbesParser(TraceProcessorContext* context)
       arm_cpu_part(context->storage->InternString("arm_cpu_part")),
       arm_cpu_revision(context->storage->InternString("arm_cpu_revision")),
       meminfo_strs_(BuildMeminfoCounterNames()),
-      vmstat_strs_(BuildVmstatCounterNames()) {}
+      vmstat_strs_(BuildVmstatCounterNames()),
+      netstat_strs_(BuildNetstatCounterNames()) {}
 
 void SystemProbesParser::ParseDiskStats(int64_t ts, ConstBytes blob) {
   protos::pbzero::SysStats::DiskStat::Decoder ds(blob);
@@ -560,6 +564,60 @@ void SystemProbesParser::ParseSysStats(int64_t ts, ConstBytes blob) {
         tracks::kGpuFrequencyBlueprint, tracks::Dimensions(0));
     context_->event_tracker->PushCounter(ts, static_cast<double>(*it), track);
   }
+
+  // The counters of /proc/net/dev are cumulative since boot and are keyed by
+  // the interface name and by the name of the counter (e.g. "rx_bytes").
+  static constexpr auto kNetDevBlueprint = tracks::CounterBlueprint(
+      "netdev", tracks::UnknownUnitBlueprint(),
+      tracks::DimensionBlueprints(
+          tracks::StringDimensionBlueprint("netdev_interface"),
+          tracks::StringDimensionBlueprint("netdev_counter")),
+      tracks::FnNameBlueprint(
+          [](base::StringView interface, base::StringView counter) {
+            return base::StackString<1024>(
+                "netdev.%.*s.%.*s", int(interface.size()), interface.data(),
+                int(counter.size()), counter.data());
+          }));
+  for (auto it = sys_stats.netdev(); it; ++it) {
+    protos::pbzero::SysStats::NetDevStat::Decoder nd(*it);
+    base::StringView interface(nd.interface_name());
+    auto push_counter = [&, this](const char* counter, uint64_t value) {
+      TrackId track = context_->track_tracker->InternTrack(
+          kNetDevBlueprint, tracks::Dimensions(interface, counter));
+      context_->event_tracker->PushCounter(ts, static_cast<double>(value),
+                                           track);
+    };
+    push_counter("rx_bytes", nd.rx_bytes());
+    push_counter("rx_packets", nd.rx_packets());
+    push_counter("rx_errors", nd.rx_errors());
+    push_counter("rx_drops", nd.rx_drops());
+    push_counter("tx_bytes", nd.tx_bytes());
+    push_counter("tx_packets", nd.tx_packets());
+    push_counter("tx_errors", nd.tx_errors());
+    push_counter("tx_drops", nd.tx_drops());
+  }
+
+  static constexpr auto kNetstatBlueprint = tracks::CounterBlueprint(
+      "netstat", tracks::UnknownUnitBlueprint(),
+      tracks::DimensionBlueprints(
+          tracks::StringDimensionBlueprint("netstat_key")),
+      tracks::FnNameBlueprint([](base::StringView name) {
+        return base::StackString<1024>("netstat.%.*s", int(name.size()),
+                                       name.data());
+      }));
+  for (auto it = sys_stats.netstat(); it; ++it) {
+    protos::pbzero::SysStats::NetstatValue::Decoder ns(*it);
+    auto key = static_cast<size_t>(ns.key());
+    if (PERFETTO_UNLIKELY(key >= netstat_strs_.size() || !netstat_strs_[key])) {
+      PERFETTO_ELOG("Netstat key %zu is not recognized.", key);
+      context_->storage->IncrementStats(stats::netstat_unknown_keys);
+      continue;
+    }
+    TrackId track = context_->track_tracker->InternTrack(
+        kNetstatBlueprint, tracks::Dimensions(netstat_strs_[key]));
+    context_->event_tracker->PushCounter(ts, static_cast<double>(ns.value()),
+                                         track);
+  }
 }
 
 void SystemProbesParser::ParseCpuIdleStats(int64_t ts, ConstBytes blob) {
@@ -587,6 +645,180 @@ void SystemProbesParser::ParseCpuIdleStats(int64_t ts, ConstBytes blob) {
   }
 }
 
+void SystemProbesParser::ParseCgroupStats(int64_t ts, ConstBytes blob) {
+  protos::pbzero::CgroupStats::Decoder cgroup_stats(blob);
+
+  // The counters of each cgroup are keyed by the path of the cgroup and by the
+  // name of the counter (e.g. "cpu.usage_ns", "io.[8:0].read_bytes").
+  static constexpr auto kBlueprint = tracks::CounterBlueprint(
+      "cgroup_stat", tracks::UnknownUnitBlueprint(),
+      tracks::DimensionBlueprints(
+          tracks::StringDimensionBlueprint("cgroup_path"),
+          tracks::StringDimensionBlueprint("cgroup_counter")),
+      tracks::FnNameBlueprint(
+          [](base::StringView path, base::StringView counter) {
+            return base::StackString<1024>("cgroup[%.*s].%.*s",
+                                           int(path.size()), path.data(),
+                                           int(counter.size()), counter.data());
+          }));
+
+  for (auto it = cgroup_stats.cgroups(); it; ++it) {
+    protos::pbzero::CgroupStats::Cgroup::Decoder cgroup(*it);
+    base::StringView path(cgroup.path());
+    auto push_counter = [&, this](base::StringView counter, uint64_t value) {
+      TrackId track = context_->track_tracker->InternTrack(
+          kBlueprint, tracks::Dimensions(path, counter));
+      context_->event_tracker->PushCounter(ts, static_cast<double>(value),
+                                           track);
+    };
+
+    if (cgroup.has_cpu_usage_ns())
+      push_counter("cpu.usage_ns", cgroup.cpu_usage_ns());
+    if (cgroup.has_cpu_user_ns())
+      push_counter("cpu.user_ns", cgroup.cpu_user_ns());
+    if (cgroup.has_cpu_system_ns())
+      push_counter("cpu.system_ns", cgroup.cpu_system_ns());
+    if (cgroup.has_cpu_nr_periods())
+      push_counter("cpu.nr_periods", cgroup.cpu_nr_periods());
+    if (cgroup.has_cpu_nr_throttled())
+      push_counter("cpu.nr_throttled", cgroup.cpu_nr_throttled());
+    if (cgroup.has_cpu_throttled_ns())
+      push_counter("cpu.throttled_ns", cgroup.cpu_throttled_ns());
+    if (cgroup.has_memory_current_bytes())
+      push_counter("memory.current_bytes", cgroup.memory_current_bytes());
+
+    for (auto ms_it = cgroup.memory_stat(); ms_it; ++ms_it) {
+      protos::pbzero::CgroupStats::MemoryStatValue::Decoder ms(*ms_it);
+      base::StackString<256> counter("memory.stat.%.*s", int(ms.key().size),
+                                     ms.key().data);
+      push_counter(counter.string_view(), ms.value());
+    }
+
+    for (auto io_it = cgroup.io_stat(); io_it; ++io_it) {
+      protos::pbzero::CgroupStats::IoStat::Decoder io(*io_it);
+      auto push_io_counter = [&](const char* name, uint64_t value) {
+        base::StackString<256> counter("io.[%u:%u].%s", io.major(), io.minor(),
+                                       name);
+        push_counter(counter.string_view(), value);
+      };
+      if (io.has_read_bytes())
+        push_io_counter("read_bytes", io.read_bytes());
+      if (io.has_write_bytes())
+        push_io_counter("write_bytes", io.write_bytes());
+      if (io.has_read_ios())
+        push_io_counter("read_ios", io.read_ios());
+      if (io.has_write_ios())
+        push_io_counter("write_ios", io.write_ios());
+      if (io.has_discard_bytes())
+        push_io_counter("discard_bytes", io.discard_bytes());
+      if (io.has_discard_ios())
+        push_io_counter("discard_ios", io.discard_ios());
+    }
+
+    // Pressure Stall Information: total stall times in nanoseconds.
+    auto push_pressure = [&](const char* resource, ConstBytes pressure_blob) {
+      protos::pbzero::CgroupStats::Pressure::Decoder pressure(pressure_blob);
+      if (pressure.has_some_total_ns()) {
+        base::StackString<64> counter("%s.pressure.some_ns", resource);
+        push_counter(counter.string_view(), pressure.some_total_ns());
+      }
+      if (pressure.has_full_total_ns()) {
+        base::StackString<64> counter("%s.pressure.full_ns", resource);
+        push_counter(counter.string_view(), pressure.full_total_ns());
+      }
+    };
+    if (cgroup.has_cpu_pressure())
+      push_pressure("cpu", cgroup.cpu_pressure());
+    if (cgroup.has_memory_pressure())
+      push_pressure("memory", cgroup.memory_pressure());
+    if (cgroup.has_io_pressure())
+      push_pressure("io", cgroup.io_pressure());
+  }
+}
+
+void SystemProbesParser::ParseSyscallLatency(int64_t ts, ConstBytes blob) {
+  protos::pbzero::SyscallLatency::Decoder syscall_latency(blob);
+  SyscallTracker* syscall_tracker = SyscallTracker::GetOrCreate(context_);
+  auto syscall_name = [&](uint32_t syscall_id) {
+    StringId name = syscall_tracker->SyscallNumberToStringId(syscall_id);
+    if (!name.is_null())
+      return name;
+    base::StackString<64> unknown_str("sys_%u", syscall_id);
+    return context_->storage->InternString(unknown_str.string_view());
+  };
+
+  auto* histogram_table =
+      context_->storage->mutable_syscall_latency_histogram_table();
+  for (auto it = syscall_latency.syscalls(); it; ++it) {
+    protos::pbzero::SyscallLatency::ThreadSyscall::Decoder syscall(*it);
+    bool parse_error = false;
+    std::vector<uint32_t> buckets;
+    for (auto b = syscall.buckets(&parse_error); b; ++b)
+      buckets.push_back(*b);
+    std::vector<uint64_t> counts;
+    for (auto c = syscall.counts(&parse_error); c; ++c)
+      counts.push_back(*c);
+    if (parse_error || buckets.size() != counts.size()) {
+      context_->storage->IncrementStats(
+          stats::syscall_latency_has_parse_errors);
+      continue;
+    }
+
+    UniqueTid utid = context_->process_tracker->UpdateThread(
+        static_cast<uint32_t>(syscall.tid()),
+        static_cast<uint32_t>(syscall.pid()));
+    StringId name = syscall_name(syscall.syscall_id());
+    for (size_t i = 0; i < buckets.size(); i++) {
+      // Bucket N counts the calls which took [2^N, 2^(N+1)) ns.
+      if (buckets[i] >= 62) {
+        context_->storage->IncrementStats(
+            stats::syscall_latency_has_parse_errors);
+        continue;
+      }
+      tables::SyscallLatencyHistogramTable::Row row;
+      row.ts = ts;
+      row.utid = utid;
+      row.syscall_id = syscall.syscall_id();
+      row.name = name;
+      row.min_dur = buckets[i] == 0 ? 0 : int64_t(1) << buckets[i];
+      row.max_dur = int64_t(1) << (buckets[i] + 1);
+      row.count = static_cast<int64_t>(counts[i]);
+      histogram_table->Insert(row);
+    }
+  }
+
+  auto* sample_table = context_->storage->mutable_syscall_arg_sample_table();
+  StringId args_key_id = context_->storage->InternString("args");
+  for (auto it = syscall_latency.arg_samples(); it; ++it) {
+    protos::pbzero::SyscallLatency::ArgSample::Decoder sample(*it);
+    base::StatusOr<int64_t> sample_ts = context_->clock_tracker->ToTraceTime(
+        protos::pbzero::BUILTIN_CLOCK_BOOTTIME,
+        static_cast<int64_t>(sample.ts()));
+    if (!sample_ts.ok())
+      continue;
+
+    tables::SyscallArgSampleTable::Row row;
+    row.ts = *sample_ts;
+    row.utid = context_->process_tracker->UpdateThread(
+        static_cast<uint32_t>(sample.tid()),
+        static_cast<uint32_t>(sample.pid()));
+    row.syscall_id = sample.syscall_id();
+    row.name = syscall_name(sample.syscall_id());
+    auto id = sample_table->Insert(row).id;
+
+    ArgsTracker args_tracker(context_);
+    auto inserter = args_tracker.AddArgsTo(id);
+    bool parse_error = false;
+    uint32_t i = 0;
+    for (auto arg = sample.args(&parse_error); arg; ++arg, ++i) {
+      base::StackString<32> key("args[%u]", i);
+      inserter.AddArg(args_key_id,
+                      context_->storage->InternString(key.string_view()),
+                      Variadic::UnsignedInteger(*arg));
+    }
+  }
+}
+
 void SystemProbesParser::ParseProcessTree(ConstBytes blob) {
   protos::pbzero::ProcessTree::Decoder ps(blob);
 
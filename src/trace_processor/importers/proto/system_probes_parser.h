@@ -38,6 +38,8 @@ class SystemProbesParser {
   void ParseProcessTree(ConstBytes);
   void ParseProcessStats(int64_t ts, ConstBytes);
   void ParseSysStats(int64_t ts, ConstBytes);
+  void ParseCgroupStats(int64_t ts, ConstBytes);
+  void ParseSyscallLatency(int64_t ts, ConstBytes);
   void ParseSystemInfo(ConstBytes);
   void ParseCpuInfo(ConstBytes);
 
@@ -61,6 +63,7 @@ class SystemProbesParser {
 
   std::vector<const char*> meminfo_strs_;
   std::vector<const char*> vmstat_strs_;
+  std::vector<const char*> netstat_strs_;
 
   uint32_t page_size_ = 0;
 
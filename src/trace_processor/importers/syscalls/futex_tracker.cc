@@ -0,0 +1,204 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/syscalls/futex_tracker.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <limits>
+#include <optional>
+
+#include "src/trace_processor/importers/common/flow_tracker.h"
+#include "src/trace_processor/storage/trace_storage.h"
+#include "src/trace_processor/tables/sched_tables_py.h"
+#include "src/trace_processor/types/trace_processor_context.h"
+
+namespace perfetto::trace_processor {
+
+namespace {
+
+// From include/uapi/linux/futex.h.
+constexpr uint32_t kFutexWait = 0;
+constexpr uint32_t kFutexWake = 1;
+constexpr uint32_t kFutexRequeue = 3;
+constexpr uint32_t kFutexCmpRequeue = 4;
+constexpr uint32_t kFutexWakeOp = 5;
+constexpr uint32_t kFutexLockPi = 6;
+constexpr uint32_t kFutexUnlockPi = 7;
+constexpr uint32_t kFutexWaitBitset = 9;
+constexpr uint32_t kFutexWakeBitset = 10;
+constexpr uint32_t kFutexWaitRequeuePi = 11;
+constexpr uint32_t kFutexCmpRequeuePi = 12;
+constexpr uint32_t kFutexLockPi2 = 13;
+
+constexpr uint32_t kFutexPrivateFlag = 128;
+constexpr uint32_t kFutexClockRealtime = 256;
+
+// The wait was not performed as the futex word didn't hold the expected
+// value: there was no contention worth reporting.
+constexpr int64_t kEagain = -11;
+
+// Key process used for shared futexes.
+constexpr uint32_t kSharedFutexUpid = std::numeric_limits<uint32_t>::max();
+
+// The number of wakes remembered for each futex. Wakes older than these can
+// only match waits which were blocked for many wake ups of the same futex.
+constexpr size_t kMaxWakesPerFutex = 16;
+
+}  // namespace
+
+FutexTracker::FutexTracker(TraceProcessorContext* context)
+    : context_(context),
+      futex_wait_id_(context->storage->InternString("FUTEX_WAIT")),
+      futex_wait_bitset_id_(
+          context->storage->InternString("FUTEX_WAIT_BITSET")),
+      futex_lock_pi_id_(context->storage->InternString("FUTEX_LOCK_PI")),
+      futex_lock_pi2_id_(context->storage->InternString("FUTEX_LOCK_PI2")),
+      futex_wait_requeue_pi_id_(
+          context->storage->InternString("FUTEX_WAIT_REQUEUE_PI")) {}
+
+FutexTracker::~FutexTracker() = default;
+
+void FutexTracker::Enter(int64_t ts,
+                         UniqueTid utid,
+                         uint64_t uaddr,
+                         uint32_t op,
+                         uint64_t uaddr2,
+                         std::optional<SliceId> slice_id) {
+  if (utid >= pending_waits_.size())
+    pending_waits_.resize(utid + 1);
+  // A call which never returned (e.g. the thread exited) is dropped.
+  pending_waits_[utid].valid = false;
+
+  StringId op_name_id;
+  switch (op & ~(kFutexPrivateFlag | kFutexClockRealtime)) {
+    case kFutexWait:
+      op_name_id = futex_wait_id_;
+      break;
+    case kFutexWaitBitset:
+      op_name_id = futex_wait_bitset_id_;
+      break;
+    case kFutexLockPi:
+      op_name_id = futex_lock_pi_id_;
+      break;
+    case kFutexLockPi2:
+      op_name_id = futex_lock_pi2_id_;
+      break;
+    case kFutexWaitRequeuePi:
+      op_name_id = futex_wait_requeue_pi_id_;
+      break;
+    case kFutexWakeOp:
+      // FUTEX_WAKE_OP can wake waiters on both futex words.
+      RecordWake(ts, utid, KeyFor(utid, uaddr2, op), slice_id);
+      RecordWake(ts, utid, KeyFor(utid, uaddr, op), slice_id);
+      return;
+    case kFutexWake:
+    case kFutexWakeBitset:
+    case kFutexRequeue:
+    case kFutexCmpRequeue:
+    case kFutexCmpRequeuePi:
+    case kFutexUnlockPi:
+      RecordWake(ts, utid, KeyFor(utid, uaddr, op), slice_id);
+      return;
+    default:
+      return;
+  }
+
+  PendingWait& wait = pending_waits_[utid];
+  wait.valid = true;
+  wait.ts = ts;
+  wait.uaddr = uaddr;
+  wait.key = KeyFor(utid, uaddr, op);
+  wait.op_name_id = op_name_id;
+  wait.slice_id = slice_id;
+}
+
+void FutexTracker::Exit(int64_t ts,
+                        UniqueTid utid,
+                        int64_t ret,
+                        std::optional<SliceId> slice_id) {
+  if (utid >= pending_waits_.size() || !pending_waits_[utid].valid)
+    return;
+  PendingWait& wait = pending_waits_[utid];
+  wait.valid = false;
+  if (ret == kEagain)
+    return;
+
+  tables::FutexContentionTable::Row row;
+  row.ts = wait.ts;
+  row.dur = ts - wait.ts;
+  row.utid = utid;
+  row.address = static_cast<int64_t>(wait.uaddr);
+  row.op = wait.op_name_id;
+  row.ret = ret;
+  row.wait_slice_id = slice_id ? slice_id : wait.slice_id;
+
+  // Only successful waits were ended by a wake: the others timed out or were
+  // interrupted by a signal.
+  const Wake* wake = ret == 0 ? FindWake(wait.key, wait.ts, utid) : nullptr;
+  if (wake) {
+    row.waker_utid = wake->utid;
+    row.wake_ts = wake->ts;
+    row.wake_slice_id = wake->slice_id;
+    if (wake->slice_id && row.wait_slice_id) {
+      context_->flow_tracker->InsertFlow(*wake->slice_id, *row.wait_slice_id);
+    }
+  }
+  context_->storage->mutable_futex_contention_table()->Insert(row);
+}
+
+FutexTracker::FutexKey FutexTracker::KeyFor(UniqueTid utid,
+                                            uint64_t uaddr,
+                                            uint32_t op) const {
+  if (op & kFutexPrivateFlag) {
+    std::optional<UniquePid> upid =
+        context_->storage->thread_table()[utid].upid();
+    if (upid)
+      return {*upid, uaddr};
+  }
+  return {kSharedFutexUpid, uaddr};
+}
+
+void FutexTracker::RecordWake(int64_t ts,
+                              UniqueTid utid,
+                              const FutexKey& key,
+                              std::optional<SliceId> slice_id) {
+  base::CircularQueue<Wake>& wakes = wakes_[key];
+  if (wakes.size() >= kMaxWakesPerFutex)
+    wakes.pop_front();
+  wakes.emplace_back(Wake{ts, utid, slice_id});
+}
+
+FutexTracker::Wake* FutexTracker::FindWake(const FutexKey& key,
+                                           int64_t since,
+                                           UniqueTid waiter) {
+  base::CircularQueue<Wake>* wakes = wakes_.Find(key);
+  if (!wakes)
+    return nullptr;
+  Wake* latest = nullptr;
+  for (Wake& wake : *wakes) {
+    if (wake.ts < since || wake.utid == waiter)
+      continue;
+    if (!wake.attributed) {
+      wake.attributed = true;
+      return &wake;
+    }
+    latest = &wake;
+  }
+  return latest;
+}
+
+}  // namespace perfetto::trace_processor
@@ -0,0 +1,129 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_IMPORTERS_SYSCALLS_FUTEX_TRACKER_H_
+#define SRC_TRACE_PROCESSOR_IMPORTERS_SYSCALLS_FUTEX_TRACKER_H_
+
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <utility>
+#include <vector>
+
+#include "perfetto/ext/base/circular_queue.h"
+#include "perfetto/ext/base/flat_hash_map.h"
+#include "perfetto/ext/base/hash.h"
+#include "src/trace_processor/storage/trace_storage.h"
+#include "src/trace_processor/types/destructible.h"
+#include "src/trace_processor/types/trace_processor_context.h"
+
+namespace perfetto::trace_processor {
+
+// Pairs futex(2) waits with the wakes which ended them, populating the
+// futex_contention table.
+//
+// A wait is attributed to the earliest wake on the same futex word which
+// started while the waiter was blocked and wasn't already attributed to another
+// waiter. If all of them were (e.g. a single wake of all the waiters), the
+// latest one is used instead.
+//
+// Private futexes (FUTEX_PRIVATE_FLAG) are keyed by process, as the same
+// address in two processes refers to different words. Shared futexes are keyed
+// by address only: the trace doesn't say which memory backs an address, so a
+// word shared by processes which map it at different addresses isn't matched,
+// and unrelated shared futexes of two processes at the same address are
+// conflated.
+class FutexTracker : public Destructible {
+ public:
+  FutexTracker(const FutexTracker&) = delete;
+  FutexTracker& operator=(const FutexTracker&) = delete;
+  ~FutexTracker() override;
+  static FutexTracker* GetOrCreate(TraceProcessorContext* context) {
+    if (!context->futex_tracker) {
+      context->futex_tracker.reset(new FutexTracker(context));
+    }
+    return static_cast<FutexTracker*>(context->futex_tracker.get());
+  }
+
+  // Processes the entry of a futex syscall. |slice_id| is the slice created
+  // for the syscall by the SyscallTracker, if any.
+  void Enter(int64_t ts,
+             UniqueTid utid,
+             uint64_t uaddr,
+             uint32_t op,
+             uint64_t uaddr2,
+             std::optional<SliceId> slice_id);
+
+  // Processes the exit of a futex syscall. |slice_id| is the slice ended by
+  // the SyscallTracker, if any.
+  void Exit(int64_t ts,
+            UniqueTid utid,
+            int64_t ret,
+            std::optional<SliceId> slice_id);
+
+ private:
+  // Futex address, qualified by the process for private futexes.
+  using FutexKey = std::pair<uint32_t, uint64_t>;
+  struct FutexKeyHasher {
+    size_t operator()(const FutexKey& k) const {
+      return static_cast<size_t>(base::FnvHasher::Combine(k.first, k.second));
+    }
+  };
+
+  struct PendingWait {
+    bool valid = false;
+    int64_t ts = 0;
+    uint64_t uaddr = 0;
+    FutexKey key;
+    StringId op_name_id;
+    std::optional<SliceId> slice_id;
+  };
+
+  struct Wake {
+    int64_t ts = 0;
+    UniqueTid utid = 0;
+    std::optional<SliceId> slice_id;
+    // Whether a wait was already attributed to this wake.
+    bool attributed = false;
+  };
+
+  explicit FutexTracker(TraceProcessorContext*);
+
+  FutexKey KeyFor(UniqueTid utid, uint64_t uaddr, uint32_t op) const;
+  void RecordWake(int64_t ts,
+                  UniqueTid utid,
+                  const FutexKey& key,
+                  std::optional<SliceId> slice_id);
+  Wake* FindWake(const FutexKey& key, int64_t since, UniqueTid waiter);
+
+  TraceProcessorContext* const context_;
+
+  // Indexed by utid.
+  std::vector<PendingWait> pending_waits_;
+  // The most recent wakes of each futex, oldest first.
+  base::FlatHashMap<FutexKey, base::CircularQueue<Wake>, FutexKeyHasher>
+      wakes_;
+
+  const StringId futex_wait_id_;
+  const StringId futex_wait_bitset_id_;
+  const StringId futex_lock_pi_id_;
+  const StringId futex_lock_pi2_id_;
+  const StringId futex_wait_requeue_pi_id_;
+};
+
+}  // namespace perfetto::trace_processor
+
+#endif  // SRC_TRACE_PROCESSOR_IMPORTERS_SYSCALLS_FUTEX_TRACKER_H_
@@ -0,0 +1,167 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/importers/syscalls/futex_tracker.h"
+
+#include <cstdint>
+#include <memory>
+#include <optional>
+
+#include "src/trace_processor/importers/common/flow_tracker.h"
+#include "src/trace_processor/storage/trace_storage.h"
+#include "src/trace_processor/tables/sched_tables_py.h"
+#include "src/trace_processor/types/trace_processor_context.h"
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto::trace_processor {
+namespace {
+
+constexpr uint32_t kFutexWait = 0;
+constexpr uint32_t kFutexWake = 1;
+constexpr uint32_t kFutexWakeOp = 5;
+constexpr uint32_t kFutexPrivateFlag = 128;
+
+constexpr uint64_t kAddr = 0x1000;
+
+class FutexTrackerTest : public ::testing::Test {
+ public:
+  FutexTrackerTest() {
+    context_.storage = std::make_unique<TraceStorage>();
+    context_.flow_tracker = std::make_unique<FlowTracker>(&context_);
+    // utids 0 and 1 belong to upid 0, utid 2 to upid 1.
+    for (uint32_t upid : {0u, 0u, 1u}) {
+      tables::ThreadTable::Row row;
+      row.upid = upid;
+      context_.storage->mutable_thread_table()->Insert(row);
+    }
+    tracker_ = FutexTracker::GetOrCreate(&context_);
+  }
+
+ protected:
+  const tables::FutexContentionTable& futex() {
+    return context_.storage->futex_contention_table();
+  }
+
+  TraceProcessorContext context_;
+  FutexTracker* tracker_ = nullptr;
+};
+
+TEST_F(FutexTrackerTest, PairsWaitWithWake) {
+  tracker_->Enter(100, 0, kAddr, kFutexWait, 0, SliceId(0));
+  tracker_->Enter(150, 1, kAddr, kFutexWake, 0, SliceId(1));
+  tracker_->Exit(160, 1, 1, SliceId(1));
+  tracker_->Exit(200, 0, 0, SliceId(0));
+
+  ASSERT_EQ(futex().row_count(), 1u);
+  auto row = futex()[0];
+  EXPECT_EQ(row.ts(), 100);
+  EXPECT_EQ(row.dur(), 100);
+  EXPECT_EQ(row.utid(), 0u);
+  EXPECT_EQ(row.address(), static_cast<int64_t>(kAddr));
+  EXPECT_EQ(context_.storage->GetString(row.op()), "FUTEX_WAIT");
+  EXPECT_EQ(row.waker_utid(), 1u);
+  EXPECT_EQ(row.wake_ts(), 150);
+  EXPECT_EQ(row.wake_slice_id(), SliceId(1));
+
+  const auto& flows = context_.storage->flow_table();
+  ASSERT_EQ(flows.row_count(), 1u);
+  EXPECT_EQ(flows[0].slice_out(), SliceId(1));
+  EXPECT_EQ(flows[0].slice_in(), SliceId(0));
+}
+
+TEST_F(FutexTrackerTest, IgnoresWakeBeforeWait) {
+  tracker_->Enter(50, 1, kAddr, kFutexWake, 0, SliceId(1));
+  tracker_->Exit(60, 1, 0, SliceId(1));
+  tracker_->Enter(100, 0, kAddr, kFutexWait, 0, SliceId(0));
+  tracker_->Exit(200, 0, 0, SliceId(0));
+
+  ASSERT_EQ(futex().row_count(), 1u);
+  EXPECT_EQ(futex()[0].waker_utid(), std::nullopt);
+  EXPECT_EQ(context_.storage->flow_table().row_count(), 0u);
+}
+
+TEST_F(FutexTrackerTest, EachWakeEndsOneWait) {
+  tracker_->Enter(100, 0, kAddr, kFutexWait, 0, SliceId(0));
+  tracker_->Enter(110, 2, kAddr, kFutexWait, 0, SliceId(1));
+  tracker_->Enter(150, 1, kAddr, kFutexWake, 0, SliceId(2));
+  tracker_->Enter(160, 1, kAddr, kFutexWake, 0, SliceId(3));
+  tracker_->Exit(170, 0, 0, SliceId(0));
+  tracker_->Exit(180, 2, 0, SliceId(1));
+
+  ASSERT_EQ(futex().row_count(), 2u);
+  EXPECT_EQ(futex()[0].wake_ts(), 150);
+  EXPECT_EQ(futex()[1].wake_ts(), 160);
+}
+
+TEST_F(FutexTrackerTest, OneWakeCanEndManyWaits) {
+  tracker_->Enter(100, 0, kAddr, kFutexWait, 0, SliceId(0));
+  tracker_->Enter(110, 2, kAddr, kFutexWait, 0, SliceId(1));
+  tracker_->Enter(150, 1, kAddr, kFutexWake, 0, SliceId(2));
+  tracker_->Exit(170, 0, 0, SliceId(0));
+  tracker_->Exit(180, 2, 0, SliceId(1));
+
+  ASSERT_EQ(futex().row_count(), 2u);
+  EXPECT_EQ(futex()[0].wake_ts(), 150);
+  EXPECT_EQ(futex()[1].wake_ts(), 150);
+}
+
+TEST_F(FutexTrackerTest, SkipsEagain) {
+  tracker_->Enter(100, 0, kAddr, kFutexWait, 0, SliceId(0));
+  tracker_->Exit(101, 0, -11, SliceId(0));
+  EXPECT_EQ(futex().row_count(), 0u);
+}
+
+TEST_F(FutexTrackerTest, TimedOutWaitHasNoWaker) {
+  tracker_->Enter(100, 0, kAddr, kFutexWait, 0, SliceId(0));
+  tracker_->Enter(150, 1, kAddr, kFutexWake, 0, SliceId(1));
+  tracker_->Exit(200, 0, -110 /* ETIMEDOUT */, SliceId(0));
+
+  ASSERT_EQ(futex().row_count(), 1u);
+  EXPECT_EQ(futex()[0].ret(), -110);
+  EXPECT_EQ(futex()[0].waker_utid(), std::nullopt);
+}
+
+TEST_F(FutexTrackerTest, PrivateFutexesAreKeyedByProcess) {
+  // utid 2 is in a different process, so its wake doesn't match.
+  tracker_->Enter(100, 0, kAddr, kFutexWait | kFutexPrivateFlag, 0,
+                  SliceId(0));
+  tracker_->Enter(150, 2, kAddr, kFutexWake | kFutexPrivateFlag, 0,
+                  SliceId(2));
+  tracker_->Exit(200, 0, 0, SliceId(0));
+  ASSERT_EQ(futex().row_count(), 1u);
+  EXPECT_EQ(futex()[0].waker_utid(), std::nullopt);
+
+  tracker_->Enter(300, 0, kAddr, kFutexWait | kFutexPrivateFlag, 0,
+                  SliceId(3));
+  tracker_->Enter(350, 1, kAddr, kFutexWake | kFutexPrivateFlag, 0,
+                  SliceId(4));
+  tracker_->Exit(400, 0, 0, SliceId(3));
+  ASSERT_EQ(futex().row_count(), 2u);
+  EXPECT_EQ(futex()[1].waker_utid(), 1u);
+}
+
+TEST_F(FutexTrackerTest, WakeOpWakesSecondAddress) {
+  constexpr uint64_t kAddr2 = 0x2000;
+  tracker_->Enter(100, 0, kAddr2, kFutexWait, 0, SliceId(0));
+  tracker_->Enter(150, 1, kAddr, kFutexWakeOp, kAddr2, SliceId(1));
+  tracker_->Exit(200, 0, 0, SliceId(0));
+
+  ASSERT_EQ(futex().row_count(), 1u);
+  EXPECT_EQ(futex()[0].waker_utid(), 1u);
+}
+
+}  // namespace
+}  // namespace perfetto::trace_processor
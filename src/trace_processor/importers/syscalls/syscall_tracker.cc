@@ -51,6 +51,10 @@ void SyscallTracker::SetArchitecture(Architecture arch) {
         sys_write_string_id_ = id;
       } else if (!strcmp(name, "sys_rt_sigreturn")) {
         sys_rt_sigreturn_string_id_ = id;
+      } else if (!strcmp(name, "sys_futex")) {
+        sys_futex_string_id_ = id;
+      } else if (!strcmp(name, "sys_futex_time64")) {
+        sys_futex_time64_string_id_ = id;
       }
     } else {
       base::StackString<64> unknown_str("sys_%zu", i);
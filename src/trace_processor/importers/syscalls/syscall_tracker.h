@@ -18,6 +18,7 @@
 #define SRC_TRACE_PROCESSOR_IMPORTERS_SYSCALLS_SYSCALL_TRACKER_H_
 
 #include <limits>
+#include <optional>
 #include <vector>
 
 #include "src/kernel_utils/syscall_table.h"
@@ -44,25 +45,27 @@ class SyscallTracker : public Destructible {
 
   void SetArchitecture(Architecture architecture);
 
-  void Enter(int64_t ts,
-             UniqueTid utid,
-             uint32_t syscall_num,
-             EventTracker::SetArgsCallback args_callback =
-                 EventTracker::SetArgsCallback()) {
+  // Returns the id of the slice of the syscall, if any.
+  std::optional<SliceId> Enter(int64_t ts,
+                               UniqueTid utid,
+                               uint32_t syscall_num,
+                               EventTracker::SetArgsCallback args_callback =
+                                   EventTracker::SetArgsCallback()) {
     StringId name = SyscallNumberToStringId(syscall_num);
     if (name.is_null())
-      return;
+      return std::nullopt;
 
     TrackId track_id = context_->track_tracker->InternThreadTrack(utid);
 
     // sys_rt_sigreturn does not return so should be inserted as an instant
     // event. See https://github.com/google/perfetto/issues/733 for details.
+    std::optional<SliceId> slice_id;
     if (name == sys_rt_sigreturn_string_id_) {
-      context_->slice_tracker->Scoped(ts, track_id, kNullStringId, name, 0,
-                                      args_callback);
+      slice_id = context_->slice_tracker->Scoped(ts, track_id, kNullStringId,
+                                                 name, 0, args_callback);
     } else {
-      context_->slice_tracker->Begin(ts, track_id, kNullStringId /* cat */,
-                                     name, args_callback);
+      slice_id = context_->slice_tracker->Begin(
+          ts, track_id, kNullStringId /* cat */, name, args_callback);
     }
 
     if (name == sys_write_string_id_) {
@@ -71,16 +74,18 @@ class SyscallTracker : public Destructible {
 
       in_sys_write_[utid] = true;
     }
+    return slice_id;
   }
 
-  void Exit(int64_t ts,
-            UniqueTid utid,
-            uint32_t syscall_num,
-            EventTracker::SetArgsCallback args_callback =
-                EventTracker::SetArgsCallback()) {
+  // Returns the id of the slice of the syscall, if any.
+  std::optional<SliceId> Exit(int64_t ts,
+                              UniqueTid utid,
+                              uint32_t syscall_num,
+                              EventTracker::SetArgsCallback args_callback =
+                                  EventTracker::SetArgsCallback()) {
     StringId name = SyscallNumberToStringId(syscall_num);
     if (name.is_null())
-      return;
+      return std::nullopt;
 
     if (name == sys_write_string_id_) {
       if (utid >= in_sys_write_.size())
@@ -89,13 +94,30 @@ class SyscallTracker : public Destructible {
       // start of the trace, or the slice was closed by
       // MaybeTruncateOngoingWriteSlice.
       if (!in_sys_write_[utid])
-        return;
+        return std::nullopt;
       in_sys_write_[utid] = false;
     }
 
     TrackId track_id = context_->track_tracker->InternThreadTrack(utid);
-    context_->slice_tracker->End(ts, track_id, kNullStringId /* cat */, name,
-                                 args_callback);
+    return context_->slice_tracker->End(ts, track_id, kNullStringId /* cat */,
+                                        name, args_callback);
+  }
+
+  // Returns the name of the syscall (e.g. "sys_read"), or "sys_N" if the
+  // architecture of the trace is not known. Returns kNullStringId if
+  // |syscall_num| is out of range.
+  inline StringId SyscallNumberToStringId(uint32_t syscall_num) {
+    if (syscall_num >= kMaxSyscalls)
+      return kNullStringId;
+    return arch_syscall_to_string_id_[syscall_num];
+  }
+
+  // Returns whether |syscall_num| is futex(2), which is futex_time64 on 32-bit
+  // architectures.
+  bool IsFutex(uint32_t syscall_num) {
+    StringId name = SyscallNumberToStringId(syscall_num);
+    return !name.is_null() && (name == sys_futex_string_id_ ||
+                               name == sys_futex_time64_string_id_);
   }
 
   // Resolves slice nesting issues when the sys_write is for an atrace slice on
@@ -119,17 +141,13 @@ class SyscallTracker : public Destructible {
 
   TraceProcessorContext* const context_;
 
-  inline StringId SyscallNumberToStringId(uint32_t syscall_num) {
-    if (syscall_num >= kMaxSyscalls)
-      return kNullStringId;
-    return arch_syscall_to_string_id_[syscall_num];
-  }
-
   // This is table from platform specific syscall number directly to
   // the relevant StringId (this avoids having to always do two conversions).
   std::array<StringId, kMaxSyscalls> arch_syscall_to_string_id_{};
   StringId sys_write_string_id_ = std::numeric_limits<StringId>::max();
   StringId sys_rt_sigreturn_string_id_ = std::numeric_limits<StringId>::max();
+  StringId sys_futex_string_id_ = std::numeric_limits<StringId>::max();
+  StringId sys_futex_time64_string_id_ = std::numeric_limits<StringId>::max();
   // UniqueTids currently in a sys_write syscall.
   // This is a BitVector, but we use a vector for simplicity.
   std::vector<uint8_t> in_sys_write_;
@@ -29,10 +29,12 @@ namespace trace_processor {
 IteratorImpl::IteratorImpl(
     TraceProcessorImpl* trace_processor,
     base::StatusOr<PerfettoSqlEngine::ExecutionResult> result,
-    uint32_t sql_stats_row)
+    uint32_t sql_stats_row,
+    QueryBudget* query_budget)
     : trace_processor_(trace_processor),
       result_(std::move(result)),
-      sql_stats_row_(sql_stats_row) {}
+      sql_stats_row_(sql_stats_row),
+      query_budget_(query_budget) {}
 
 IteratorImpl::~IteratorImpl() {
   if (trace_processor_) {
@@ -40,6 +42,7 @@ IteratorImpl::~IteratorImpl() {
     auto* sql_stats =
         trace_processor_.get()->context_.storage->mutable_sql_stats();
     sql_stats->RecordQueryEnd(sql_stats_row_, t_end.count());
+    query_budget_->EndQuery();
   }
 }
 
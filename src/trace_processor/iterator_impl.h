@@ -29,6 +29,7 @@
 #include "perfetto/trace_processor/basic_types.h"
 #include "perfetto/trace_processor/iterator.h"
 #include "src/trace_processor/perfetto_sql/engine/perfetto_sql_engine.h"
+#include "src/trace_processor/perfetto_sql/engine/query_budget.h"
 #include "src/trace_processor/sqlite/sqlite_engine.h"
 
 namespace perfetto {
@@ -38,9 +39,12 @@ class TraceProcessorImpl;
 
 class IteratorImpl {
  public:
+  // |query_budget| must have been started with QueryBudget::BeginQuery and is
+  // ended when this iterator is destroyed.
   IteratorImpl(TraceProcessorImpl* impl,
                base::StatusOr<PerfettoSqlEngine::ExecutionResult>,
-               uint32_t sql_stats_row);
+               uint32_t sql_stats_row,
+               QueryBudget* query_budget);
   ~IteratorImpl();
 
   IteratorImpl(IteratorImpl&) noexcept = delete;
@@ -75,7 +79,10 @@ class IteratorImpl {
     bool has_more = result_->stmt.Step();
     if (!result_->stmt.status().ok()) {
       PERFETTO_DCHECK(!has_more);
-      result_ = result_->stmt.status();
+      // Report why the query was stopped rather than SQLite's generic
+      // "interrupted" error.
+      result_ = query_budget_->exceeded() ? query_budget_->status()
+                                          : result_->stmt.status();
     }
     return has_more;
   }
@@ -156,6 +163,7 @@ class IteratorImpl {
   ScopedTraceProcessor trace_processor_;
   base::StatusOr<PerfettoSqlEngine::ExecutionResult> result_;
   uint32_t sql_stats_row_ = 0;
+  QueryBudget* query_budget_ = nullptr;
   bool called_next_ = false;
 };
 
@@ -162,6 +162,7 @@ int DataframeModule::Create(sqlite3* db,
   std::unique_ptr<Vtab> res = std::make_unique<Vtab>();
   res->state = ctx->OnCreate(argc, argv, std::move(state));
   res->name = argv[2];
+  res->interrupt_flag = ctx->interrupt_flag;
   *vtab = res.release();
   return SQLITE_OK;
 }
@@ -180,7 +181,8 @@ int DataframeModule::Connect(sqlite3* db,
                              char**) {
   PERFETTO_CHECK(argc == 3);
 
-  auto* vtab_state = GetContext(raw_ctx)->OnConnect(argc, argv);
+  auto* ctx = GetContext(raw_ctx);
+  auto* vtab_state = ctx->OnConnect(argc, argv);
   auto* state =
       sqlite::ModuleStateManager<DataframeModule>::GetState(vtab_state);
   std::string create_stmt = CreateTableStmt(state->dataframe->CreateSpec());
@@ -190,6 +192,7 @@ int DataframeModule::Connect(sqlite3* db,
   std::unique_ptr<Vtab> res = std::make_unique<Vtab>();
   res->state = vtab_state;
   res->name = argv[2];
+  res->interrupt_flag = ctx->interrupt_flag;
   *vtab = res.release();
   return SQLITE_OK;
 }
@@ -411,8 +414,9 @@ int DataframeModule::BestIndex(sqlite3_vtab* tab, sqlite3_index_info* info) {
   return SQLITE_OK;
 }
 
-int DataframeModule::Open(sqlite3_vtab*, sqlite3_vtab_cursor** cursor) {
+int DataframeModule::Open(sqlite3_vtab* tab, sqlite3_vtab_cursor** cursor) {
   std::unique_ptr<Cursor> c = std::make_unique<Cursor>();
+  c->df_cursor.SetInterruptFlag(GetVtab(tab)->interrupt_flag);
   *cursor = c.release();
   return SQLITE_OK;
 }
@@ -453,7 +457,9 @@ int DataframeModule::Filter(sqlite3_vtab_cursor* cur,
   memcpy(static_cast<void*>(fetcher.sqlite_value.data()),
          static_cast<void*>(argv),
          sizeof(sqlite3_value*) * static_cast<size_t>(argc));
-  c->df_cursor.Execute(fetcher);
+  if (!c->df_cursor.Execute(fetcher)) {
+    return SQLITE_INTERRUPT;
+  }
   return SQLITE_OK;
 }
 
@@ -18,6 +18,7 @@
 #define SRC_TRACE_PROCESSOR_PERFETTO_SQL_ENGINE_DATAFRAME_MODULE_H_
 
 #include <array>
+#include <atomic>
 #include <cstddef>
 #include <cstdint>
 #include <memory>
@@ -61,6 +62,9 @@ struct DataframeModule : sqlite::Module<DataframeModule> {
   };
   struct Context : sqlite::ModuleStateManager<DataframeModule> {
     std::unique_ptr<State> temporary_create_state;
+    // If non-null, queries on the dataframes are stopped, and fail with
+    // SQLITE_INTERRUPT, when this becomes true.
+    const std::atomic<bool>* interrupt_flag = nullptr;
   };
   struct SqliteValueFetcher : dataframe::ValueFetcher {
     using Type = sqlite::Type;
@@ -105,6 +109,7 @@ struct DataframeModule : sqlite::Module<DataframeModule> {
     sqlite::ModuleStateManager<DataframeModule>::PerVtabState* state;
     std::string name;
     int best_idx_num = 0;
+    const std::atomic<bool>* interrupt_flag = nullptr;
   };
   using DfCursor = dataframe::Cursor<SqliteValueFetcher>;
   struct Cursor : sqlite::Module<DataframeModule>::Cursor {
@@ -48,6 +48,7 @@
 #include "src/trace_processor/perfetto_sql/engine/dataframe_module.h"
 #include "src/trace_processor/perfetto_sql/engine/dataframe_shared_storage.h"
 #include "src/trace_processor/perfetto_sql/engine/runtime_table_function.h"
+#include "src/trace_processor/perfetto_sql/engine/sql_table_cache.h"
 #include "src/trace_processor/perfetto_sql/intrinsics/table_functions/static_table_function.h"
 #include "src/trace_processor/perfetto_sql/parser/function_util.h"
 #include "src/trace_processor/perfetto_sql/parser/perfetto_sql_parser.h"
@@ -89,6 +90,10 @@
 namespace perfetto::trace_processor {
 namespace {
 
+// The number of SQLite virtual machine instructions between two checks of the
+// query budget.
+constexpr int kProgressCallbackInstructions = 10000;
+
 struct SqliteStmtValueFetcher : public dataframe::ValueFetcher {
   using Type = int;
   [[maybe_unused]] static constexpr Type kInt64 = SQLITE_INTEGER;
@@ -355,6 +360,47 @@ ArgumentTypeToDataframeType(sql_argument::Type type, bool bytes_as_int64) {
   PERFETTO_FATAL("For GCC");
 }
 
+// Returns an estimate of the memory used to store a row of |column_count|
+// cells, the current values of |fetcher|, in a dataframe. Strings are only
+// counted if the row added new strings to the string pool, whose size went
+// from |pool_size_before| to |pool_size_after|.
+template <typename ValueFetcherImpl>
+uint64_t EstimateRowMemory(ValueFetcherImpl* fetcher,
+                           uint32_t column_count,
+                           size_t pool_size_before,
+                           size_t pool_size_after) {
+  uint64_t bytes = column_count * sizeof(int64_t);
+  if (pool_size_after == pool_size_before) {
+    return bytes;
+  }
+  for (uint32_t i = 0; i < column_count; ++i) {
+    if (fetcher->GetValueType(i) == ValueFetcherImpl::kString) {
+      bytes += strlen(fetcher->GetStringValue(i));
+    }
+  }
+  return bytes;
+}
+
+// Adds a row of |fetcher| to |builder|, accounting its memory in |budget| if
+// non-null.
+template <typename ValueFetcherImpl>
+base::Status AddRowWithinBudget(dataframe::RuntimeDataframeBuilder& builder,
+                                StringPool* pool,
+                                uint32_t column_count,
+                                ValueFetcherImpl* fetcher,
+                                QueryBudget* budget) {
+  size_t pool_size = pool->size();
+  if (!builder.AddRow(fetcher)) {
+    PERFETTO_CHECK(!builder.status().ok());
+    return builder.status();
+  }
+  if (budget && !budget->AddTableMemory(EstimateRowMemory(
+                    fetcher, column_count, pool_size, pool->size()))) {
+    return budget->status();
+  }
+  return base::OkStatus();
+}
+
 template <typename ValueFetcherImpl>
 base::StatusOr<dataframe::Dataframe> CreateDataframeFromSqliteStatement(
     sqlite3* db,
@@ -364,16 +410,19 @@ base::StatusOr<dataframe::Dataframe> CreateDataframeFromSqliteStatement(
     sqlite3_stmt* sqlite_stmt,
     const std::string& name,
     ValueFetcherImpl* fetcher,
-    const char* tag) {
+    const char* tag,
+    QueryBudget* budget = nullptr) {
+  auto column_count = static_cast<uint32_t>(column_names.size());
   dataframe::RuntimeDataframeBuilder builder(std::move(column_names), pool,
                                              types);
   int res;
   for (res = sqlite3_step(sqlite_stmt); res == SQLITE_ROW;
        res = sqlite3_step(sqlite_stmt)) {
-    if (!builder.AddRow(fetcher)) {
-      PERFETTO_CHECK(!builder.status().ok());
+    base::Status status =
+        AddRowWithinBudget(builder, pool, column_count, fetcher, budget);
+    if (!status.ok()) {
       return base::ErrStatus("%s(%s): %s", tag, name.c_str(),
-                             builder.status().c_message());
+                             status.c_message());
     }
   }
   if (res != SQLITE_DONE) {
@@ -409,9 +458,11 @@ GetTypesFromSelectStatement(
 
 PerfettoSqlEngine::PerfettoSqlEngine(StringPool* pool,
                                      DataframeSharedStorage* storage,
+                                     SqlTableCache* table_cache,
                                      bool enable_extra_checks)
     : pool_(pool),
       dataframe_shared_storage_(storage),
+      table_cache_(table_cache),
       enable_extra_checks_(enable_extra_checks),
       engine_(new SqliteEngine()) {
   // Initialize `perfetto_tables` table, which will contain the names of all of
@@ -434,6 +485,12 @@ PerfettoSqlEngine::PerfettoSqlEngine(StringPool* pool,
   engine_->SetRollbackCallback(
       [](void* ctx) { static_cast<PerfettoSqlEngine*>(ctx)->OnRollback(); },
       this);
+  engine_->SetProgressCallback(
+      kProgressCallbackInstructions,
+      [](void* ctx) {
+        return static_cast<QueryBudget*>(ctx)->Check() ? 1 : 0;
+      },
+      &query_budget_);
 
   {
     auto ctx = std::make_unique<RuntimeTableFunctionModule::Context>();
@@ -449,6 +506,7 @@ PerfettoSqlEngine::PerfettoSqlEngine(StringPool* pool,
   }
   {
     auto ctx = std::make_unique<DataframeModule::Context>();
+    ctx->interrupt_flag = query_budget_.interrupted_flag();
     dataframe_context_ = ctx.get();
     RegisterVirtualTableModule<DataframeModule>("__intrinsic_dataframe",
                                                 std::move(ctx));
@@ -768,14 +826,48 @@ base::Status PerfettoSqlEngine::ExecuteCreateTable(
                      GetTypesFromSelectStatement(false, schema, column_names,
                                                  create_table.name,
                                                  "CREATE PERFETTO TABLE"));
-    auto* sqlite_stmt = stmt.sqlite_stmt();
-    SqliteStmtValueFetcher fetcher{{}, sqlite_stmt};
-    ASSIGN_OR_RETURN(
-        auto table,
-        CreateDataframeFromSqliteStatement(
-            engine_->db(), pool_, std::move(column_names), std::move(types),
-            sqlite_stmt, create_table.name, &fetcher, "CREATE PERFETTO TABLE"));
-    df = dataframe_shared_storage_->Insert(key, std::move(table));
+    std::optional<dataframe::Dataframe> table;
+    std::optional<std::string> cache_key;
+    // Only the tables of the built-in stdlib modules are cached: the tables
+    // created outside of modules or by other packages can depend on state
+    // which is not part of the cache key.
+    std::string module_name =
+        module_include_stack_.empty() ? "" : module_include_stack_.back();
+    if (table_cache_ && table_cache_->enabled() &&
+        table_cache_->IsCacheable(module_name)) {
+      cache_key = table_cache_->MakeKey(module_name, create_table.sql.sql());
+      size_t pool_size = pool_->size();
+      table = table_cache_->Find(*cache_key, pool_);
+      if (table) {
+        // Same estimate as EstimateRowMemory() for the rows of the table.
+        uint64_t bytes = uint64_t{table->row_count()} * column_names.size() *
+                             sizeof(int64_t) +
+                         (pool_->size() - pool_size);
+        if (!query_budget_.AddTableMemory(bytes)) {
+          return base::ErrStatus("CREATE PERFETTO TABLE(%s): %s",
+                                 create_table.name.c_str(),
+                                 query_budget_.status().c_message());
+        }
+        cache_key = std::nullopt;
+      }
+    }
+    if (!table) {
+      auto* sqlite_stmt = stmt.sqlite_stmt();
+      SqliteStmtValueFetcher fetcher{{}, sqlite_stmt};
+      ASSIGN_OR_RETURN(
+          table, CreateDataframeFromSqliteStatement(
+                     engine_->db(), pool_, std::move(column_names),
+                     std::move(types), sqlite_stmt, create_table.name, &fetcher,
+                     "CREATE PERFETTO TABLE", &query_budget_));
+    }
+    if (cache_key) {
+      base::Status status = table_cache_->Insert(*cache_key, *table, pool_);
+      if (!status.ok()) {
+        PERFETTO_ELOG("CREATE PERFETTO TABLE(%s): %s",
+                      create_table.name.c_str(), status.c_message());
+      }
+    }
+    df = dataframe_shared_storage_->Insert(key, *std::move(table));
   }
   base::StackString<1024> drop("DROP TABLE IF EXISTS %s;",
                                create_table.name.c_str());
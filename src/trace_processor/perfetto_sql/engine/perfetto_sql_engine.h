@@ -34,7 +34,9 @@
 #include "src/trace_processor/dataframe/dataframe.h"
 #include "src/trace_processor/perfetto_sql/engine/dataframe_module.h"
 #include "src/trace_processor/perfetto_sql/engine/dataframe_shared_storage.h"
+#include "src/trace_processor/perfetto_sql/engine/query_budget.h"
 #include "src/trace_processor/perfetto_sql/engine/runtime_table_function.h"
+#include "src/trace_processor/perfetto_sql/engine/sql_table_cache.h"
 #include "src/trace_processor/perfetto_sql/engine/static_table_function_module.h"
 #include "src/trace_processor/perfetto_sql/intrinsics/functions/sql_function.h"
 #include "src/trace_processor/perfetto_sql/intrinsics/table_functions/static_table_function.h"
@@ -75,8 +77,12 @@ class PerfettoSqlEngine {
     DataframeSharedStorage::DataframeHandle handle;
     std::string name;
   };
+  // |table_cache| is optional. If non-null, it is used to persist the tables
+  // created by CREATE PERFETTO TABLE statements in the stdlib modules across
+  // sessions.
   PerfettoSqlEngine(StringPool* pool,
                     DataframeSharedStorage* storage,
+                    SqlTableCache* table_cache,
                     bool enable_extra_checks);
 
   // Initializes the static tables and functions in the engine.
@@ -251,6 +257,10 @@ class PerfettoSqlEngine {
 
   SqliteEngine* sqlite_engine() { return engine_.get(); }
 
+  // Limits the resources used by the queries run by this engine and allows
+  // them to be cancelled.
+  QueryBudget* query_budget() { return &query_budget_; }
+
   // Makes new SQL package available to include.
   void RegisterPackage(const std::string& name,
                        sql_modules::RegisteredPackage package) {
@@ -374,6 +384,12 @@ class PerfettoSqlEngine {
   // instances which are operating on different threads.
   DataframeSharedStorage* dataframe_shared_storage_;
 
+  // Persistent cache for the tables created by CREATE PERFETTO TABLE. Can be
+  // null.
+  SqlTableCache* table_cache_;
+
+  QueryBudget query_budget_;
+
   // If true, engine will perform additional consistency checks when e.g.
   // creating tables and views.
   const bool enable_extra_checks_;
@@ -16,12 +16,18 @@
 
 #include "src/trace_processor/perfetto_sql/engine/perfetto_sql_engine.h"
 
+#include <atomic>
+#include <cstdio>
 #include <string>
 #include <utility>
 #include <vector>
 
+#include "perfetto/ext/base/file_utils.h"
+#include "perfetto/ext/base/temp_file.h"
 #include "src/trace_processor/containers/string_pool.h"
 #include "src/trace_processor/perfetto_sql/engine/dataframe_shared_storage.h"
+#include "src/trace_processor/perfetto_sql/engine/query_budget.h"
+#include "src/trace_processor/perfetto_sql/engine/sql_table_cache.h"
 #include "src/trace_processor/sqlite/bindings/sqlite_result.h"
 #include "src/trace_processor/sqlite/sql_source.h"
 #include "src/trace_processor/util/sql_modules.h"
@@ -34,7 +40,8 @@ class PerfettoSqlEngineTest : public ::testing::Test {
  protected:
   StringPool pool_;
   DataframeSharedStorage dataframe_shared_storage_;
-  PerfettoSqlEngine engine_{&pool_, &dataframe_shared_storage_, true};
+  PerfettoSqlEngine engine_{&pool_, &dataframe_shared_storage_, nullptr,
+                            true};
 };
 
 sql_modules::RegisteredPackage CreateTestPackage(
@@ -206,6 +213,67 @@ TEST_F(PerfettoSqlEngineTest, Table_Duplicates) {
   ASSERT_TRUE(res.ok()) << res.status().c_message();
 }
 
+TEST_F(PerfettoSqlEngineTest, Table_PersistentCache) {
+  base::TempDir tmp = base::TempDir::Create();
+  SqlTableCache cache(tmp.path(), 1024 * 1024, "stdlib", {"pkg"});
+  cache.SetTraceKey("trace");
+  auto make_package = [] {
+    return CreateTestPackage(
+        {{"pkg.foo", "CREATE PERFETTO TABLE foo AS SELECT bar FROM src"}});
+  };
+  {
+    DataframeSharedStorage storage;
+    PerfettoSqlEngine engine(&pool_, &storage, &cache, true);
+    engine.RegisterPackage("pkg", make_package());
+    auto res = engine.Execute(SqlSource::FromExecuteQuery(
+        "CREATE PERFETTO TABLE src AS SELECT 1 AS bar;"
+        "CREATE PERFETTO TABLE user AS SELECT bar FROM src;"
+        "INCLUDE PERFETTO MODULE pkg.foo"));
+    ASSERT_TRUE(res.ok()) << res.status().c_message();
+  }
+
+  // As the SQL of `foo` did not change, its contents should be loaded from the
+  // cache even if `src` changed. `user`, created outside of a module, is not
+  // cached.
+  DataframeSharedStorage storage;
+  PerfettoSqlEngine engine(&pool_, &storage, &cache, true);
+  engine.RegisterPackage("pkg", make_package());
+  auto res = engine.ExecuteUntilLastStatement(SqlSource::FromExecuteQuery(
+      "CREATE PERFETTO TABLE src AS SELECT 2 AS bar;"
+      "CREATE PERFETTO TABLE user AS SELECT bar FROM src;"
+      "INCLUDE PERFETTO MODULE pkg.foo;"
+      "SELECT foo.bar, user.bar FROM foo, user"));
+  ASSERT_TRUE(res.ok()) << res.status().c_message();
+  ASSERT_FALSE(res->stmt.IsDone());
+  ASSERT_EQ(sqlite3_column_int64(res->stmt.sqlite_stmt(), 0), 1);
+  ASSERT_EQ(sqlite3_column_int64(res->stmt.sqlite_stmt(), 1), 2);
+  ASSERT_FALSE(res->stmt.Step());
+
+  std::vector<std::string> files;
+  ASSERT_TRUE(base::ListFilesRecursive(tmp.path(), files).ok());
+  ASSERT_EQ(files.size(), 1u);
+  for (const std::string& file : files) {
+    remove((tmp.path() + "/" + file).c_str());
+  }
+}
+
+TEST_F(PerfettoSqlEngineTest, Table_PersistentCacheNonStdlibModule) {
+  base::TempDir tmp = base::TempDir::Create();
+  SqlTableCache cache(tmp.path(), 1024 * 1024, "stdlib", {"pkg"});
+  cache.SetTraceKey("trace");
+  PerfettoSqlEngine engine(&pool_, &dataframe_shared_storage_, &cache, true);
+  engine.RegisterPackage(
+      "other", CreateTestPackage(
+                   {{"other.foo", "CREATE PERFETTO TABLE foo AS SELECT 1"}}));
+  auto res = engine.Execute(
+      SqlSource::FromExecuteQuery("INCLUDE PERFETTO MODULE other.foo"));
+  ASSERT_TRUE(res.ok()) << res.status().c_message();
+
+  std::vector<std::string> files;
+  ASSERT_TRUE(base::ListFilesRecursive(tmp.path(), files).ok());
+  ASSERT_TRUE(files.empty());
+}
+
 TEST_F(PerfettoSqlEngineTest, View_Create) {
   auto res = engine_.Execute(SqlSource::FromExecuteQuery(
       "CREATE PERFETTO VIEW foo AS SELECT 42 AS bar"));
@@ -324,5 +392,72 @@ TEST_F(PerfettoSqlEngineTest, Include_Module) {
   ASSERT_FALSE(engine_.FindPackage("bar")->modules["bar.bar"].included);
 }
 
+constexpr char kInfiniteQuery[] =
+    "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) "
+    "SELECT count(*) FROM c";
+
+TEST_F(PerfettoSqlEngineTest, QueryBudget_Timeout) {
+  QueryBudget::Limits limits;
+  limits.timeout_ms = 1;
+  engine_.query_budget()->BeginQuery(limits);
+  auto res = engine_.Execute(SqlSource::FromExecuteQuery(kInfiniteQuery));
+  ASSERT_FALSE(res.ok());
+  ASSERT_TRUE(engine_.query_budget()->exceeded());
+  ASSERT_THAT(engine_.query_budget()->status().message(),
+              testing::HasSubstr("time limit of 1 ms"));
+  engine_.query_budget()->EndQuery();
+
+  // The next query starts with a fresh budget.
+  engine_.query_budget()->BeginQuery(QueryBudget::Limits());
+  res = engine_.Execute(SqlSource::FromExecuteQuery("SELECT 1"));
+  ASSERT_TRUE(res.ok()) << res.status().c_message();
+  engine_.query_budget()->EndQuery();
+}
+
+TEST_F(PerfettoSqlEngineTest, QueryBudget_CancelFlag) {
+  std::atomic<bool> cancelled{true};
+  QueryBudget::Limits limits;
+  limits.cancel_flag = &cancelled;
+  engine_.query_budget()->BeginQuery(limits);
+  auto res = engine_.Execute(SqlSource::FromExecuteQuery(kInfiniteQuery));
+  ASSERT_FALSE(res.ok());
+  ASSERT_TRUE(engine_.query_budget()->exceeded());
+  ASSERT_EQ(engine_.query_budget()->status().message(), "Query cancelled");
+  engine_.query_budget()->EndQuery();
+}
+
+TEST_F(PerfettoSqlEngineTest, QueryBudget_InterruptWhenIdle) {
+  // Interrupting when no query is running must not affect the next one.
+  engine_.query_budget()->Interrupt();
+  engine_.query_budget()->BeginQuery(QueryBudget::Limits());
+  auto res = engine_.Execute(SqlSource::FromExecuteQuery(
+      "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c "
+      "WHERE x < 100000) SELECT count(*) FROM c"));
+  ASSERT_TRUE(res.ok()) << res.status().c_message();
+  ASSERT_FALSE(engine_.query_budget()->exceeded());
+  engine_.query_budget()->EndQuery();
+}
+
+TEST_F(PerfettoSqlEngineTest, QueryBudget_TableMemory) {
+  QueryBudget::Limits limits;
+  limits.max_table_memory_bytes = 1024;
+  engine_.query_budget()->BeginQuery(limits);
+  auto res = engine_.Execute(SqlSource::FromExecuteQuery(
+      "CREATE PERFETTO TABLE foo AS "
+      "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c "
+      "WHERE x < 10000) SELECT x FROM c"));
+  ASSERT_FALSE(res.ok());
+  ASSERT_THAT(res.status().message(),
+              testing::HasSubstr("memory limit of 1024 bytes"));
+  engine_.query_budget()->EndQuery();
+
+  // Small tables fit in the limit.
+  engine_.query_budget()->BeginQuery(limits);
+  res = engine_.Execute(SqlSource::FromExecuteQuery(
+      "CREATE PERFETTO TABLE bar AS SELECT 1 AS x"));
+  ASSERT_TRUE(res.ok()) << res.status().c_message();
+  engine_.query_budget()->EndQuery();
+}
+
 }  // namespace
 }  // namespace perfetto::trace_processor
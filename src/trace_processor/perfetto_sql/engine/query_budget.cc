@@ -0,0 +1,127 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/perfetto_sql/engine/query_budget.h"
+
+#include <atomic>
+#include <cinttypes>
+#include <cstdint>
+
+#include "perfetto/base/logging.h"
+#include "perfetto/base/status.h"
+#include "perfetto/base/time.h"
+
+namespace perfetto::trace_processor {
+
+QueryBudget::QueryBudget() = default;
+
+void QueryBudget::BeginQuery(const Limits& limits) {
+  if (depth_++ > 0) {
+    return;
+  }
+  limits_ = limits;
+  reason_ = Reason::kNone;
+  table_memory_bytes_ = 0;
+  deadline_ = limits.timeout_ms == 0
+                  ? base::TimeNanos(0)
+                  : base::GetWallTimeNs() + base::TimeMillis(limits.timeout_ms);
+  interrupted_.store(false, std::memory_order_relaxed);
+  active_.store(true, std::memory_order_release);
+  if (limits.cancel_flag && limits.cancel_flag->load()) {
+    Stop(Reason::kCancelled);
+  }
+}
+
+void QueryBudget::EndQuery() {
+  PERFETTO_DCHECK(depth_ > 0);
+  if (--depth_ > 0) {
+    return;
+  }
+  active_.store(false, std::memory_order_release);
+  interrupted_.store(false, std::memory_order_relaxed);
+}
+
+void QueryBudget::Interrupt() {
+  if (active_.load(std::memory_order_acquire)) {
+    interrupted_.store(true, std::memory_order_relaxed);
+  }
+}
+
+bool QueryBudget::Check() {
+  if (depth_ == 0) {
+    return false;
+  }
+  if (reason_ != Reason::kNone) {
+    return true;
+  }
+  if (interrupted_.load(std::memory_order_relaxed) ||
+      (limits_.cancel_flag &&
+       limits_.cancel_flag->load(std::memory_order_relaxed))) {
+    Stop(Reason::kCancelled);
+    return true;
+  }
+  if (deadline_.count() != 0 && base::GetWallTimeNs() > deadline_) {
+    Stop(Reason::kTimeout);
+    return true;
+  }
+  return false;
+}
+
+bool QueryBudget::AddTableMemory(uint64_t bytes) {
+  if (depth_ == 0) {
+    return true;
+  }
+  table_memory_bytes_ += bytes;
+  if (limits_.max_table_memory_bytes != 0 &&
+      table_memory_bytes_ > limits_.max_table_memory_bytes) {
+    Stop(Reason::kTableMemory);
+    return false;
+  }
+  return true;
+}
+
+bool QueryBudget::exceeded() const {
+  // Interrupt() does not set |reason_| as it can be called from another
+  // thread.
+  return reason_ != Reason::kNone ||
+         (depth_ > 0 && interrupted_.load(std::memory_order_relaxed));
+}
+
+base::Status QueryBudget::status() const {
+  switch (reason_) {
+    case Reason::kTimeout:
+      return base::ErrStatus("Query exceeded the time limit of %" PRIu64 " ms",
+                             limits_.timeout_ms);
+    case Reason::kTableMemory:
+      return base::ErrStatus(
+          "Query exceeded the memory limit of %" PRIu64
+          " bytes for materialized tables",
+          limits_.max_table_memory_bytes);
+    case Reason::kNone:
+    case Reason::kCancelled:
+      return base::ErrStatus("Query cancelled");
+  }
+  PERFETTO_FATAL("For GCC");
+}
+
+void QueryBudget::Stop(Reason reason) {
+  if (reason_ == Reason::kNone) {
+    reason_ = reason;
+  }
+  interrupted_.store(true, std::memory_order_relaxed);
+}
+
+}  // namespace perfetto::trace_processor
@@ -0,0 +1,104 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_PERFETTO_SQL_ENGINE_QUERY_BUDGET_H_
+#define SRC_TRACE_PROCESSOR_PERFETTO_SQL_ENGINE_QUERY_BUDGET_H_
+
+#include <atomic>
+#include <cstdint>
+
+#include "perfetto/base/status.h"
+#include "perfetto/base/time.h"
+
+namespace perfetto::trace_processor {
+
+// Tracks the wall time and the memory of the tables materialized by the
+// query being executed, and allows it to be cancelled from another thread or
+// from a signal handler.
+//
+// Queries are stopped cooperatively: the SQLite progress handler and the
+// dataframe bytecode interpreter periodically check whether the query should
+// stop and, if so, fail the current statement.
+class QueryBudget {
+ public:
+  struct Limits {
+    // Maximum wall time of the query. 0 means no limit.
+    uint64_t timeout_ms = 0;
+    // Maximum memory allocated by the tables materialized by the query. 0
+    // means no limit.
+    uint64_t max_table_memory_bytes = 0;
+    // If non-null, the query is cancelled when this becomes true.
+    const std::atomic<bool>* cancel_flag = nullptr;
+  };
+
+  QueryBudget();
+
+  QueryBudget(const QueryBudget&) = delete;
+  QueryBudget& operator=(const QueryBudget&) = delete;
+
+  // Starts tracking a query with the given |limits|. Queries started while
+  // another one is being tracked (e.g. by iterating two iterators at once)
+  // are considered part of the outer query and share its budget: calls must
+  // be balanced with EndQuery().
+  void BeginQuery(const Limits& limits);
+  void EndQuery();
+
+  // Requests the current query to stop. Does nothing if no query is being
+  // tracked. Thread-safe and async-signal-safe.
+  void Interrupt();
+
+  // Returns true if the current query should stop, either because it was
+  // interrupted or cancelled or because it ran out of time.
+  bool Check();
+
+  // Records that |bytes| were allocated by a table materialized by the
+  // current query. Returns false if this exceeds the limit, in which case the
+  // query should stop.
+  bool AddTableMemory(uint64_t bytes);
+
+  // Returns true if the current query was stopped.
+  bool exceeded() const;
+
+  // Returns the error explaining why the query was stopped. Must only be
+  // called if exceeded() is true.
+  base::Status status() const;
+
+  // Becomes true when the current query should stop. Checked by the dataframe
+  // interpreter, which cannot call Check() without knowing about this class.
+  const std::atomic<bool>* interrupted_flag() const { return &interrupted_; }
+
+ private:
+  enum class Reason {
+    kNone,
+    kCancelled,
+    kTimeout,
+    kTableMemory,
+  };
+
+  void Stop(Reason reason);
+
+  std::atomic<bool> active_{false};
+  std::atomic<bool> interrupted_{false};
+  uint32_t depth_ = 0;
+  Reason reason_ = Reason::kNone;
+  Limits limits_;
+  base::TimeNanos deadline_{0};
+  uint64_t table_memory_bytes_ = 0;
+};
+
+}  // namespace perfetto::trace_processor
+
+#endif  // SRC_TRACE_PROCESSOR_PERFETTO_SQL_ENGINE_QUERY_BUDGET_H_
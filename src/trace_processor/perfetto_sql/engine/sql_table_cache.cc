@@ -0,0 +1,437 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/perfetto_sql/engine/sql_table_cache.h"
+
+#include <fcntl.h>
+#include <sys/stat.h>
+#include <sys/types.h>
+
+#include <algorithm>
+#include <atomic>
+#include <cerrno>
+#include <cstddef>
+#include <cstdint>
+#include <cstdio>
+#include <cstring>
+#include <optional>
+#include <set>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "perfetto/base/build_config.h"
+#include "perfetto/base/logging.h"
+#include "perfetto/base/proc_utils.h"
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/file_utils.h"
+#include "perfetto/ext/base/flat_hash_map.h"
+#include "perfetto/ext/base/scoped_file.h"
+#include "perfetto/ext/base/sha256.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "perfetto/ext/base/string_view.h"
+#include "src/trace_processor/containers/string_pool.h"
+#include "src/trace_processor/dataframe/dataframe.h"
+#include "src/trace_processor/dataframe/specs.h"
+
+#if PERFETTO_BUILDFLAG(PERFETTO_OS_WIN)
+#include <sys/utime.h>
+#else
+#include <utime.h>
+#endif
+
+namespace perfetto::trace_processor {
+namespace {
+
+// Bump the version whenever the format of the files changes. Changes to the
+// serialization of dataframes are detected when they are deserialized.
+constexpr char kMagic[] = "PFTABLECACHE2";
+constexpr char kExtension[] = ".tablecache";
+
+// Strings longer than this are considered corrupted.
+constexpr uint32_t kMaxStringSize = 1u << 30;
+
+// Serializes a table into memory. Strings are written inline the first time
+// they are referenced and as their ordinal afterwards.
+class CacheWriter : public dataframe::Dataframe::Serializer {
+ public:
+  explicit CacheWriter(const StringPool* pool) : pool_(pool) {}
+
+  void Write(const void* data, size_t size) override {
+    buf_.append(static_cast<const char*>(data), size);
+  }
+
+  void WriteStrings(const StringPool::Id* ids, size_t count) override {
+    for (size_t i = 0; i < count; ++i) {
+      // Ordinal 0 is reserved for the null string.
+      if (ids[i].is_null()) {
+        WriteValue(uint32_t{0});
+        continue;
+      }
+      auto [ordinal, inserted] = ordinals_.Insert(ids[i].raw_id(), next_);
+      WriteValue(*ordinal);
+      if (inserted) {
+        ++next_;
+        WriteString(pool_->Get(ids[i]));
+      }
+    }
+  }
+
+  template <typename T>
+  void WriteValue(const T& value) {
+    Write(&value, sizeof(T));
+  }
+
+  void WriteString(base::StringView str) {
+    WriteValue(static_cast<uint32_t>(str.size()));
+    Write(str.data(), str.size());
+  }
+
+  std::string ReleaseBuffer() { return std::move(buf_); }
+
+ private:
+  const StringPool* pool_;
+  std::string buf_;
+  base::FlatHashMap<uint32_t, uint32_t> ordinals_;
+  uint32_t next_ = 1;
+};
+
+// Reads back a table written by CacheWriter.
+class CacheReader : public dataframe::Dataframe::Deserializer {
+ public:
+  CacheReader(const std::string& buf, StringPool* pool)
+      : buf_(buf), pool_(pool), strings_{StringPool::Id::Null()} {}
+
+  bool Read(void* data, size_t size) override {
+    if (buf_.size() - offset_ < size) {
+      return false;
+    }
+    if (size > 0) {
+      memcpy(data, buf_.data() + offset_, size);
+    }
+    offset_ += size;
+    return true;
+  }
+
+  bool ReadStrings(StringPool::Id* ids, size_t count) override {
+    for (size_t i = 0; i < count; ++i) {
+      uint32_t ordinal;
+      if (!ReadValue(&ordinal) || ordinal > strings_.size()) {
+        return false;
+      }
+      if (ordinal == strings_.size()) {
+        base::StringView str;
+        if (!ReadString(&str)) {
+          return false;
+        }
+        strings_.push_back(pool_->InternString(str));
+      }
+      ids[i] = strings_[ordinal];
+    }
+    return true;
+  }
+
+  template <typename T>
+  bool ReadValue(T* value) {
+    return Read(value, sizeof(T));
+  }
+
+  bool ReadString(base::StringView* str) {
+    uint32_t size;
+    if (!ReadValue(&size) || size > kMaxStringSize ||
+        buf_.size() - offset_ < size) {
+      return false;
+    }
+    *str = base::StringView(buf_.data() + offset_, size);
+    offset_ += size;
+    return true;
+  }
+
+  bool at_end() const { return offset_ == buf_.size(); }
+
+ private:
+  const std::string& buf_;
+  StringPool* pool_;
+  size_t offset_ = 0;
+  std::vector<StringPool::Id> strings_;
+};
+
+// Returns the member of |TypeSetT| with the given index.
+template <typename TypeSetT, size_t I = 0>
+std::optional<TypeSetT> TypeFromIndex(uint32_t index) {
+  if constexpr (I == TypeSetT::kSize) {
+    return std::nullopt;
+  } else {
+    if (index == I) {
+      return TypeSetT(typename TypeSetT::template GetTypeAtIndex<I>{});
+    }
+    return TypeFromIndex<TypeSetT, I + 1>(index);
+  }
+}
+
+struct SerializedColumnSpec {
+  uint32_t type;
+  uint32_t nullability;
+  uint32_t sort_state;
+  uint32_t duplicate_state;
+};
+
+std::optional<dataframe::ColumnSpec> ColumnSpecFromSerialized(
+    const SerializedColumnSpec& s) {
+  auto type = TypeFromIndex<dataframe::StorageType>(s.type);
+  auto nullability = TypeFromIndex<dataframe::Nullability>(s.nullability);
+  auto sort_state = TypeFromIndex<dataframe::SortState>(s.sort_state);
+  auto duplicate_state =
+      TypeFromIndex<dataframe::DuplicateState>(s.duplicate_state);
+  if (!type || !nullability || !sort_state || !duplicate_state) {
+    return std::nullopt;
+  }
+  return dataframe::ColumnSpec{*type, *nullability, *sort_state,
+                               *duplicate_state};
+}
+
+struct FileInfo {
+  std::string path;
+  uint64_t size;
+  int64_t mtime;
+};
+
+std::optional<FileInfo> StatFile(const std::string& path) {
+#if PERFETTO_BUILDFLAG(PERFETTO_OS_WIN)
+  struct _stat64 st{};
+  if (_stat64(path.c_str(), &st) != 0) {
+    return std::nullopt;
+  }
+#else
+  struct stat st{};
+  if (stat(path.c_str(), &st) != 0) {
+    return std::nullopt;
+  }
+#endif
+  return FileInfo{path, static_cast<uint64_t>(st.st_size),
+                  static_cast<int64_t>(st.st_mtime)};
+}
+
+// Bumps the modification time of the file to now: this is used to keep track
+// of the least recently used files.
+void TouchFile(const std::string& path) {
+#if PERFETTO_BUILDFLAG(PERFETTO_OS_WIN)
+  _utime(path.c_str(), nullptr);
+#else
+  utime(path.c_str(), nullptr);
+#endif
+}
+
+}  // namespace
+
+SqlTableCache::SqlTableCache(std::string dir,
+                             uint64_t max_size_bytes,
+                             std::string stdlib_version,
+                             std::set<std::string> stdlib_packages)
+    : dir_(std::move(dir)),
+      max_size_bytes_(max_size_bytes),
+      stdlib_version_(std::move(stdlib_version)),
+      stdlib_packages_(std::move(stdlib_packages)) {}
+
+SqlTableCache::~SqlTableCache() = default;
+
+bool SqlTableCache::IsCacheable(const std::string& module_name) const {
+  std::string package = module_name.substr(0, module_name.find('.'));
+  return !stdlib_overridden_ && !module_name.empty() &&
+         stdlib_packages_.count(package) > 0;
+}
+
+void SqlTableCache::OnPackageRegistered(const std::string& name) {
+  if (stdlib_packages_.count(name)) {
+    stdlib_overridden_ = true;
+  }
+}
+
+std::string SqlTableCache::MakeKey(const std::string& module_name,
+                                   const std::string& sql) const {
+  PERFETTO_DCHECK(enabled() && IsCacheable(module_name));
+  std::string key;
+  key.append(trace_key_).push_back('\n');
+  key.append(stdlib_version_).push_back('\n');
+  key.append(module_name).push_back('\n');
+  key.append(sql);
+  return key;
+}
+
+std::string SqlTableCache::PathForKey(const std::string& key) const {
+  // The full key is also stored in the file and checked by Find().
+  return dir_ + "/" + base::Sha256Hasher::HexDigestOf(key) + kExtension;
+}
+
+std::optional<dataframe::Dataframe> SqlTableCache::Find(const std::string& key,
+                                                       StringPool* pool) {
+  std::string path = PathForKey(key);
+  std::string buf;
+  if (!base::ReadFile(path, &buf)) {
+    return std::nullopt;
+  }
+  if (buf.size() < sizeof(kMagic) ||
+      memcmp(buf.data(), kMagic, sizeof(kMagic)) != 0) {
+    PERFETTO_ELOG("Table cache: ignoring invalid file %s", path.c_str());
+    return std::nullopt;
+  }
+  CacheReader reader(buf, pool);
+  char magic[sizeof(kMagic)];
+  PERFETTO_CHECK(reader.Read(magic, sizeof(magic)));
+  base::StringView stored_key;
+  if (!reader.ReadString(&stored_key) || stored_key != base::StringView(key)) {
+    return std::nullopt;
+  }
+
+  // The columns are read first as the dataframe needs to be created with the
+  // right specs before its contents can be deserialized.
+  uint32_t column_count;
+  std::vector<std::string> names;
+  std::vector<dataframe::ColumnSpec> specs;
+  bool valid = reader.ReadValue(&column_count);
+  for (uint32_t i = 0; valid && i < column_count; ++i) {
+    base::StringView name;
+    SerializedColumnSpec serialized_spec;
+    valid = reader.ReadString(&name) && reader.ReadValue(&serialized_spec);
+    std::optional<dataframe::ColumnSpec> spec;
+    if (valid) {
+      spec = ColumnSpecFromSerialized(serialized_spec);
+      valid = spec.has_value();
+    }
+    if (valid) {
+      names.push_back(name.ToStdString());
+      specs.push_back(*spec);
+    }
+  }
+  if (!valid) {
+    PERFETTO_ELOG("Table cache: ignoring invalid file %s", path.c_str());
+    return std::nullopt;
+  }
+  std::vector<const char*> name_ptrs;
+  for (const std::string& name : names) {
+    name_ptrs.push_back(name.c_str());
+  }
+  dataframe::Dataframe table(pool, column_count, name_ptrs.data(),
+                             specs.data());
+  base::Status status = table.Deserialize(&reader);
+  if (!status.ok() || !reader.at_end()) {
+    PERFETTO_ELOG("Table cache: ignoring invalid file %s: %s", path.c_str(),
+                  status.ok() ? "trailing data" : status.c_message());
+    return std::nullopt;
+  }
+  table.Finalize();
+  TouchFile(path);
+  return std::make_optional(std::move(table));
+}
+
+base::Status SqlTableCache::Insert(const std::string& key,
+                                   const dataframe::Dataframe& table,
+                                   const StringPool* pool) {
+  CacheWriter writer(pool);
+  writer.Write(kMagic, sizeof(kMagic));
+  writer.WriteString(base::StringView(key));
+  dataframe::DataframeSpec spec = table.CreateSpec();
+  writer.WriteValue(static_cast<uint32_t>(spec.column_names.size()));
+  for (size_t i = 0; i < spec.column_names.size(); ++i) {
+    const dataframe::ColumnSpec& c = spec.column_specs[i];
+    writer.WriteString(base::StringView(spec.column_names[i]));
+    writer.WriteValue(SerializedColumnSpec{
+        c.type.index(), c.nullability.index(), c.sort_state.index(),
+        c.duplicate_state.index()});
+  }
+  table.Serialize(&writer);
+  std::string buf = writer.ReleaseBuffer();
+
+  if (buf.size() > max_size_bytes_) {
+    // Don't bother storing tables which would evict everything else.
+    return base::OkStatus();
+  }
+  if (!base::Mkdir(dir_) && errno != EEXIST) {
+    return base::ErrStatus("Table cache: failed to create directory %s",
+                           dir_.c_str());
+  }
+  std::string path = PathForKey(key);
+  // The temporary file needs to be unique as the directory can be shared
+  // between processes and between the engines of a process.
+  static std::atomic<uint32_t> tmp_counter{0};
+  std::string tmp_path =
+      path + "." + std::to_string(base::GetProcessId()) + "." +
+      std::to_string(tmp_counter.fetch_add(1, std::memory_order_relaxed)) +
+      ".tmp";
+  {
+    base::ScopedFile fd(base::OpenFile(
+        tmp_path, O_WRONLY | O_CREAT | O_TRUNC, 0644));
+    if (!fd) {
+      return base::ErrStatus("Table cache: failed to open %s",
+                             tmp_path.c_str());
+    }
+    ssize_t written = base::WriteAll(*fd, buf.data(), buf.size());
+    if (written < 0 || static_cast<size_t>(written) != buf.size()) {
+      fd.reset();
+      remove(tmp_path.c_str());
+      return base::ErrStatus("Table cache: failed to write %s",
+                             tmp_path.c_str());
+    }
+  }
+  // Renaming makes sure that concurrent readers never see partial files.
+  // Note: on Windows this fails if |path| exists which is fine as it would
+  // have the same contents.
+  if (rename(tmp_path.c_str(), path.c_str()) != 0) {
+    remove(tmp_path.c_str());
+  }
+  EvictIfNeeded(path);
+  return base::OkStatus();
+}
+
+void SqlTableCache::EvictIfNeeded(const std::string& keep_path) {
+  std::vector<std::string> files;
+  if (!base::ListFilesRecursive(dir_, files).ok()) {
+    return;
+  }
+  std::vector<FileInfo> infos;
+  uint64_t total_size = 0;
+  for (const std::string& file : files) {
+    if (!base::EndsWith(file, kExtension)) {
+      continue;
+    }
+    std::optional<FileInfo> info = StatFile(dir_ + "/" + file);
+    if (!info) {
+      continue;
+    }
+    total_size += info->size;
+    infos.emplace_back(std::move(*info));
+  }
+  if (total_size <= max_size_bytes_) {
+    return;
+  }
+  std::sort(infos.begin(), infos.end(),
+            [](const FileInfo& a, const FileInfo& b) {
+              return a.mtime < b.mtime;
+            });
+  for (const FileInfo& info : infos) {
+    if (total_size <= max_size_bytes_) {
+      break;
+    }
+    if (info.path == keep_path) {
+      continue;
+    }
+    if (remove(info.path.c_str()) == 0) {
+      total_size -= info.size;
+    }
+  }
+}
+
+}  // namespace perfetto::trace_processor
@@ -0,0 +1,122 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_PERFETTO_SQL_ENGINE_SQL_TABLE_CACHE_H_
+#define SRC_TRACE_PROCESSOR_PERFETTO_SQL_ENGINE_SQL_TABLE_CACHE_H_
+
+#include <cstdint>
+#include <optional>
+#include <set>
+#include <string>
+
+#include "perfetto/base/status.h"
+#include "src/trace_processor/containers/string_pool.h"
+#include "src/trace_processor/dataframe/dataframe.h"
+
+namespace perfetto::trace_processor {
+
+// Persistent, content-addressed cache for the tables materialized by
+// CREATE PERFETTO TABLE statements.
+//
+// Every time trace processor is started on a trace (e.g. when the UI
+// reconnects to trace_processor_shell --httpd), the tables in the stdlib
+// modules are recomputed from scratch. This class allows skipping this work by
+// storing these tables in files on disk. Each file is keyed by:
+//  1) the identity of the trace (see |SetTraceKey|).
+//  2) the version of the stdlib.
+//  3) the name of the module containing the table.
+//  4) the SQL text of the statement (after macro expansion).
+//
+// Note: this assumes that the result of a statement only depends on the above.
+// This is only the case for the tables of the stdlib built into trace
+// processor (see |IsCacheable|): the tables created by the user or by other
+// packages can depend on tables, functions and modules whose contents change
+// between sessions.
+//
+// The tables are stored with Dataframe::Serialize(), preceded by the spec of
+// their columns so that they can be deserialized without running the
+// statement. The total size of the files in the directory is capped: when the
+// cap is exceeded, the least recently used files are deleted.
+//
+// Usage:
+//  std::optional<Dataframe> table = cache.Find(key, pool);
+//  if (!table) {
+//    table = <run the statement>;
+//    cache.Insert(key, *table, pool);
+//  }
+class SqlTableCache {
+ public:
+  // |stdlib_version| is a string identifying the contents of the stdlib
+  // modules and |stdlib_packages| are the names of their packages.
+  SqlTableCache(std::string dir,
+                uint64_t max_size_bytes,
+                std::string stdlib_version,
+                std::set<std::string> stdlib_packages);
+  ~SqlTableCache();
+
+  // Sets the string identifying the trace which is loaded. Until this is
+  // called, the cache is disabled: the contents of the tables are not stable
+  // until the whole trace has been parsed.
+  void SetTraceKey(std::string trace_key) { trace_key_ = std::move(trace_key); }
+
+  // Returns whether the cache can be used.
+  bool enabled() const { return !trace_key_.empty(); }
+
+  // Returns whether the tables created by the module |module_name| can be
+  // cached, i.e. whether it is a module of the built-in stdlib and no stdlib
+  // package has been replaced (see |OnPackageRegistered|).
+  bool IsCacheable(const std::string& module_name) const;
+
+  // Must be called when the package |name| is registered after the stdlib.
+  // If it replaces a stdlib package (e.g. --override-sql-package), the cache
+  // is disabled for all modules as stdlib modules depend on each other.
+  void OnPackageRegistered(const std::string& name);
+
+  // Returns the key of a table created by a statement with text |sql| in the
+  // module |module_name|, which must be cacheable.
+  std::string MakeKey(const std::string& module_name,
+                      const std::string& sql) const;
+
+  // Looks up the table with the given key in the cache, interning its strings
+  // in |pool|. Returns nullopt if no such table was found or if the file is
+  // corrupted. The returned dataframe is finalized.
+  std::optional<dataframe::Dataframe> Find(const std::string& key,
+                                           StringPool* pool);
+
+  // Stores |table|, which must be finalized and whose strings are in |pool|,
+  // in the cache, evicting the least recently used tables if the size cap is
+  // exceeded.
+  base::Status Insert(const std::string& key,
+                      const dataframe::Dataframe& table,
+                      const StringPool* pool);
+
+  const std::string& dir() const { return dir_; }
+
+ private:
+  std::string PathForKey(const std::string& key) const;
+  void EvictIfNeeded(const std::string& keep_path);
+
+  const std::string dir_;
+  const uint64_t max_size_bytes_;
+  const std::string stdlib_version_;
+  const std::set<std::string> stdlib_packages_;
+  bool stdlib_overridden_ = false;
+  std::string trace_key_;
+};
+
+}  // namespace perfetto::trace_processor
+
+#endif  // SRC_TRACE_PROCESSOR_PERFETTO_SQL_ENGINE_SQL_TABLE_CACHE_H_
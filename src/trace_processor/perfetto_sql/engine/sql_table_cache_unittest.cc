@@ -0,0 +1,255 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/perfetto_sql/engine/sql_table_cache.h"
+
+#include <fcntl.h>
+
+#include <cstdint>
+#include <cstdio>
+#include <optional>
+#include <string>
+#include <utility>
+#include <variant>
+#include <vector>
+
+#include "perfetto/ext/base/file_utils.h"
+#include "perfetto/ext/base/scoped_file.h"
+#include "perfetto/ext/base/temp_file.h"
+#include "src/trace_processor/containers/string_pool.h"
+#include "src/trace_processor/dataframe/dataframe.h"
+#include "src/trace_processor/dataframe/runtime_dataframe_builder.h"
+#include "src/trace_processor/dataframe/value_fetcher.h"
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto::trace_processor {
+namespace {
+
+using Cell = std::variant<std::nullptr_t, int64_t, double, std::string>;
+using Row = std::vector<Cell>;
+
+struct RowFetcher : public dataframe::ValueFetcher {
+  using Type = int;
+  static constexpr Type kNull = 0;
+  static constexpr Type kInt64 = 1;
+  static constexpr Type kDouble = 2;
+  static constexpr Type kString = 3;
+
+  int64_t GetInt64Value(uint32_t i) const {
+    return std::get<int64_t>((*row)[i]);
+  }
+  double GetDoubleValue(uint32_t i) const {
+    return std::get<double>((*row)[i]);
+  }
+  const char* GetStringValue(uint32_t i) const {
+    return std::get<std::string>((*row)[i]).c_str();
+  }
+  Type GetValueType(uint32_t i) const {
+    return static_cast<Type>((*row)[i].index());
+  }
+  static bool IteratorInit(uint32_t) { PERFETTO_FATAL("Unsupported"); }
+  static bool IteratorNext(uint32_t) { PERFETTO_FATAL("Unsupported"); }
+
+  const Row* row = nullptr;
+};
+
+// Serializes the contents of a dataframe, with its strings written out, so
+// that dataframes using different string pools can be compared.
+class ContentsSerializer : public dataframe::Dataframe::Serializer {
+ public:
+  explicit ContentsSerializer(const StringPool* pool) : pool_(pool) {}
+
+  void Write(const void* data, size_t size) override {
+    out.append(static_cast<const char*>(data), size);
+  }
+  void WriteStrings(const StringPool::Id* ids, size_t count) override {
+    for (size_t i = 0; i < count; ++i) {
+      out.append(ids[i].is_null() ? "<null>" : pool_->Get(ids[i]).c_str());
+      out.push_back('\0');
+    }
+  }
+
+  std::string out;
+
+ private:
+  const StringPool* pool_;
+};
+
+class SqlTableCacheTest : public ::testing::Test {
+ protected:
+  ~SqlTableCacheTest() override {
+    std::vector<std::string> files;
+    PERFETTO_CHECK(base::ListFilesRecursive(tmp_.path(), files).ok());
+    for (const std::string& file : files) {
+      remove((tmp_.path() + "/" + file).c_str());
+    }
+  }
+
+  dataframe::Dataframe Build(const std::vector<Row>& rows) {
+    std::vector<std::string> names;
+    for (size_t i = 0; i < rows[0].size(); ++i) {
+      names.push_back("c" + std::to_string(i));
+    }
+    dataframe::RuntimeDataframeBuilder builder(names, &pool_);
+    for (const Row& row : rows) {
+      RowFetcher fetcher;
+      fetcher.row = &row;
+      PERFETTO_CHECK(builder.AddRow(&fetcher));
+    }
+    auto df = std::move(builder).Build();
+    PERFETTO_CHECK(df.ok());
+    return std::move(*df);
+  }
+
+  static std::string Contents(const dataframe::Dataframe& df,
+                              const StringPool* pool) {
+    ContentsSerializer serializer(pool);
+    df.Serialize(&serializer);
+    return serializer.out;
+  }
+
+  base::TempDir tmp_ = base::TempDir::Create();
+  StringPool pool_;
+};
+
+TEST_F(SqlTableCacheTest, DisabledUntilTraceKeyIsSet) {
+  SqlTableCache cache(tmp_.path(), 1024 * 1024, "stdlib", {"foo"});
+  ASSERT_FALSE(cache.enabled());
+  cache.SetTraceKey("trace");
+  ASSERT_TRUE(cache.enabled());
+}
+
+TEST_F(SqlTableCacheTest, OnlyBuiltinStdlibModulesAreCacheable) {
+  SqlTableCache cache(tmp_.path(), 1024 * 1024, "stdlib", {"foo"});
+  ASSERT_TRUE(cache.IsCacheable("foo.bar"));
+  ASSERT_FALSE(cache.IsCacheable(""));
+  ASSERT_FALSE(cache.IsCacheable("other.bar"));
+
+  // Packages which don't replace the stdlib ones don't affect it.
+  cache.OnPackageRegistered("other");
+  ASSERT_TRUE(cache.IsCacheable("foo.bar"));
+
+  cache.OnPackageRegistered("foo");
+  ASSERT_FALSE(cache.IsCacheable("foo.bar"));
+}
+
+TEST_F(SqlTableCacheTest, RoundTrip) {
+  SqlTableCache cache(tmp_.path(), 1024 * 1024, "stdlib", {"foo"});
+  cache.SetTraceKey("trace");
+  std::string key = cache.MakeKey("foo.bar", "SELECT * FROM slice");
+  ASSERT_FALSE(cache.Find(key, &pool_));
+
+  dataframe::Dataframe table = Build({
+      {int64_t(1), 1.5, std::string("a")},
+      {nullptr, -2.0, std::string("")},
+      {int64_t(-3), nullptr, nullptr},
+      {int64_t(4), 8.0, std::string("a")},
+  });
+  ASSERT_TRUE(cache.Insert(key, table, &pool_).ok());
+
+  // The strings are interned in the pool passed to Find().
+  StringPool other_pool;
+  std::optional<dataframe::Dataframe> found = cache.Find(key, &other_pool);
+  ASSERT_TRUE(found);
+  ASSERT_TRUE(found->finalized());
+  ASSERT_EQ(found->column_names(), table.column_names());
+  ASSERT_EQ(found->row_count(), 4u);
+  ASSERT_EQ(Contents(*found, &other_pool), Contents(table, &pool_));
+}
+
+TEST_F(SqlTableCacheTest, KeyMismatch) {
+  SqlTableCache cache(tmp_.path(), 1024 * 1024, "stdlib", {"foo"});
+  cache.SetTraceKey("trace");
+  std::string key = cache.MakeKey("foo.a", "SELECT 1 AS x");
+  ASSERT_TRUE(cache.Insert(key, Build({{int64_t(1)}}), &pool_).ok());
+
+  ASSERT_FALSE(cache.Find(cache.MakeKey("foo.a", "SELECT 2 AS x"), &pool_));
+  ASSERT_FALSE(cache.Find(cache.MakeKey("foo.b", "SELECT 1 AS x"), &pool_));
+
+  cache.SetTraceKey("other_trace");
+  ASSERT_FALSE(cache.Find(cache.MakeKey("foo.a", "SELECT 1 AS x"), &pool_));
+}
+
+TEST_F(SqlTableCacheTest, SharedBetweenInstances) {
+  const std::string& dir = tmp_.path();
+  std::string key;
+  {
+    SqlTableCache cache(dir, 1024 * 1024, "stdlib", {"foo"});
+    cache.SetTraceKey("trace");
+    key = cache.MakeKey("foo.a", "SELECT 1 AS x");
+    ASSERT_TRUE(cache.Insert(key, Build({{int64_t(1)}}), &pool_).ok());
+  }
+  SqlTableCache cache(dir, 1024 * 1024, "stdlib", {"foo"});
+  cache.SetTraceKey("trace");
+  std::optional<dataframe::Dataframe> found = cache.Find(key, &pool_);
+  ASSERT_TRUE(found);
+  ASSERT_EQ(found->row_count(), 1u);
+}
+
+TEST_F(SqlTableCacheTest, IgnoresCorruptedFiles) {
+  SqlTableCache cache(tmp_.path(), 1024 * 1024, "stdlib", {"foo"});
+  cache.SetTraceKey("trace");
+  std::string key = cache.MakeKey("foo.a", "SELECT 1 AS x");
+  ASSERT_TRUE(cache.Insert(key, Build({{int64_t(1)}, {int64_t(2)}}), &pool_)
+                  .ok());
+
+  std::vector<std::string> files;
+  ASSERT_TRUE(base::ListFilesRecursive(tmp_.path(), files).ok());
+  ASSERT_EQ(files.size(), 1u);
+  std::string path = tmp_.path() + "/" + files[0];
+  std::string contents;
+  ASSERT_TRUE(base::ReadFile(path, &contents));
+  contents.resize(contents.size() - 1);
+  auto fd = base::OpenFile(path, O_WRONLY | O_TRUNC);
+  ASSERT_TRUE(fd);
+  ASSERT_EQ(base::WriteAll(*fd, contents.data(), contents.size()),
+            static_cast<ssize_t>(contents.size()));
+  fd.reset();
+
+  ASSERT_FALSE(cache.Find(key, &pool_));
+}
+
+TEST_F(SqlTableCacheTest, EvictsWhenFull) {
+  std::vector<Row> rows;
+  for (int i = 0; i < 100; ++i) {
+    rows.push_back(Row{std::string(100, 'x') + std::to_string(i)});
+  }
+  dataframe::Dataframe table = Build(rows);
+  // Only enough space for a single table.
+  SqlTableCache cache(tmp_.path(), 15000, "stdlib", {"foo"});
+  cache.SetTraceKey("trace");
+
+  std::string first = cache.MakeKey("foo.a", "SELECT 1");
+  ASSERT_TRUE(cache.Insert(first, table, &pool_).ok());
+  ASSERT_TRUE(cache.Find(first, &pool_));
+
+  std::string second = cache.MakeKey("foo.a", "SELECT 2");
+  ASSERT_TRUE(cache.Insert(second, table, &pool_).ok());
+  ASSERT_TRUE(cache.Find(second, &pool_));
+  ASSERT_FALSE(cache.Find(first, &pool_));
+}
+
+TEST_F(SqlTableCacheTest, SkipsTablesLargerThanCache) {
+  SqlTableCache cache(tmp_.path(), 100, "stdlib", {"foo"});
+  cache.SetTraceKey("trace");
+  std::string key = cache.MakeKey("foo.a", "SELECT 1");
+  ASSERT_TRUE(
+      cache.Insert(key, Build({{std::string(1000, 'x')}}), &pool_).ok());
+  ASSERT_FALSE(cache.Find(key, &pool_));
+}
+
+}  // namespace
+}  // namespace perfetto::trace_processor
@@ -15,6 +15,8 @@
 #include "src/trace_processor/perfetto_sql/intrinsics/functions/math.h"
 
 #include <cmath>
+#include <cstddef>
+#include <optional>
 
 #include "perfetto/base/logging.h"
 #include "perfetto/base/status.h"
@@ -98,11 +100,122 @@ struct Sqrt : public LegacySqlFunction {
   }
 };
 
+// Evaluates the continued fraction expansion of the regularized incomplete beta
+// function I_x(a, b) with the modified Lentz's method (see "Numerical
+// Recipes", 6.4).
+double IncompleteBetaContinuedFraction(double a, double b, double x) {
+  constexpr int kMaxIterations = 300;
+  constexpr double kEpsilon = 1e-14;
+  constexpr double kTiny = 1e-300;
+
+  double c = 1.0;
+  double d = 1.0 - (a + b) * x / (a + 1.0);
+  if (std::fabs(d) < kTiny)
+    d = kTiny;
+  d = 1.0 / d;
+  double h = d;
+  for (int m = 1; m <= kMaxIterations; ++m) {
+    double m2 = 2.0 * m;
+    double aa = m * (b - m) * x / ((a + m2 - 1.0) * (a + m2));
+    d = 1.0 + aa * d;
+    if (std::fabs(d) < kTiny)
+      d = kTiny;
+    c = 1.0 + aa / c;
+    if (std::fabs(c) < kTiny)
+      c = kTiny;
+    d = 1.0 / d;
+    h *= d * c;
+
+    aa = -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.0));
+    d = 1.0 + aa * d;
+    if (std::fabs(d) < kTiny)
+      d = kTiny;
+    c = 1.0 + aa / c;
+    if (std::fabs(c) < kTiny)
+      c = kTiny;
+    d = 1.0 / d;
+    double delta = d * c;
+    h *= delta;
+    if (std::fabs(delta - 1.0) < kEpsilon)
+      break;
+  }
+  return h;
+}
+
+double RegularizedIncompleteBeta(double a, double b, double x) {
+  if (x <= 0.0)
+    return 0.0;
+  if (x >= 1.0)
+    return 1.0;
+  double front = std::exp(std::lgamma(a + b) - std::lgamma(a) -
+                          std::lgamma(b) + a * std::log(x) +
+                          b * std::log(1.0 - x));
+  // The continued fraction converges quickly only for x < (a + 1) / (a + b + 2)
+  // so use the symmetry relation I_x(a, b) = 1 - I_(1-x)(b, a) otherwise.
+  if (x < (a + 1.0) / (a + b + 2.0))
+    return front * IncompleteBetaContinuedFraction(a, b, x) / a;
+  return 1.0 - front * IncompleteBetaContinuedFraction(b, a, 1.0 - x) / b;
+}
+
+std::optional<double> NumericArg(sqlite3_value* value) {
+  switch (sqlite3_value_numeric_type(value)) {
+    case SQLITE_INTEGER:
+    case SQLITE_FLOAT:
+      return sqlite3_value_double(value);
+    default:
+      return std::nullopt;
+  }
+}
+
+// Returns the two-sided p-value of the statistic |t| for a Student's
+// t-distribution with |df| degrees of freedom (which do not need to be
+// integral, as for the Welch's t-test). Returns NULL if either argument is
+// not a number or if |df| is not positive.
+struct StudentTPValue : public LegacySqlFunction {
+  static base::Status Run(Context*,
+                          size_t argc,
+                          sqlite3_value** argv,
+                          SqlValue& out,
+                          Destructors&) {
+    PERFETTO_CHECK(argc == 2);
+    std::optional<double> t = NumericArg(argv[0]);
+    std::optional<double> df = NumericArg(argv[1]);
+    if (!t || !df || !std::isfinite(*t) || !(*df > 0.0)) {
+      return base::OkStatus();
+    }
+    double x = *df / (*df + *t * *t);
+    out = SqlValue::Double(RegularizedIncompleteBeta(*df / 2.0, 0.5, x));
+    return base::OkStatus();
+  }
+};
+
+// Returns the two-sided p-value of the statistic |z| for a standard normal
+// distribution. Returns NULL if the argument is not a number.
+struct NormalPValue : public LegacySqlFunction {
+  static base::Status Run(Context*,
+                          size_t argc,
+                          sqlite3_value** argv,
+                          SqlValue& out,
+                          Destructors&) {
+    PERFETTO_CHECK(argc == 1);
+    std::optional<double> z = NumericArg(argv[0]);
+    if (!z || std::isnan(*z)) {
+      return base::OkStatus();
+    }
+    out = SqlValue::Double(std::erfc(std::fabs(*z) / std::sqrt(2.0)));
+    return base::OkStatus();
+  }
+};
+
 }  // namespace
 
 base::Status RegisterMathFunctions(PerfettoSqlEngine& engine) {
   RETURN_IF_ERROR(engine.RegisterStaticFunction<Ln>("ln", 1, nullptr, true));
   RETURN_IF_ERROR(engine.RegisterStaticFunction<Exp>("exp", 1, nullptr, true));
+  RETURN_IF_ERROR(engine.RegisterStaticFunction<StudentTPValue>(
+      "__intrinsic_student_t_p_value", 2, nullptr, true));
+  RETURN_IF_ERROR(engine.RegisterStaticFunction<NormalPValue>(
+      "__intrinsic_normal_p_value", 1, nullptr, true));
   return engine.RegisterStaticFunction<Sqrt>("sqrt", 1, nullptr, true);
 }
 
@@ -24,6 +24,10 @@ class PerfettoSqlEngine;
 // Registers LN, EXP, and SQRT.
 // We do not compile the SQLite library with -DSQLITE_ENABLE_MATH_FUNCTIONS so
 // these functions are not provided by default.
+//
+// Also registers the __intrinsic_student_t_p_value and
+// __intrinsic_normal_p_value functions which return the two-sided p-value of
+// a t (resp. z) statistic, used by the statistical tests in the stdlib.
 base::Status RegisterMathFunctions(PerfettoSqlEngine& engine);
 
 }  // namespace perfetto::trace_processor
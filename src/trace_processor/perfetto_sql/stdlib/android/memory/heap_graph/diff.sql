@@ -0,0 +1,113 @@
+--
+-- Copyright 2025 The Android Open Source Project
+--
+-- Licensed under the Apache License, Version 2.0 (the 'License');
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     https://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an 'AS IS' BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+-- Compares the reachable objects of a Java heap dump (the "baseline") with
+-- the reachable objects of another heap dump of the same or of another
+-- process (the "candidate"), aggregated by class name.
+CREATE PERFETTO FUNCTION android_heap_graph_class_diff(
+    -- Upid of the process of the baseline heap dump.
+    baseline_upid JOINID(process.id),
+    -- Timestamp of the baseline heap dump.
+    baseline_ts TIMESTAMP,
+    -- Upid of the process of the candidate heap dump.
+    candidate_upid JOINID(process.id),
+    -- Timestamp of the candidate heap dump.
+    candidate_ts TIMESTAMP
+)
+RETURNS TABLE (
+  -- Class name (deobfuscated if available).
+  type_name STRING,
+  -- Count of class instances in the baseline heap dump.
+  baseline_obj_count LONG,
+  -- Count of class instances in the candidate heap dump.
+  candidate_obj_count LONG,
+  -- Difference of the counts (candidate - baseline).
+  delta_obj_count LONG,
+  -- Size of class instances in the baseline heap dump.
+  baseline_size_bytes LONG,
+  -- Size of class instances in the candidate heap dump.
+  candidate_size_bytes LONG,
+  -- Difference of the sizes (candidate - baseline).
+  delta_size_bytes LONG,
+  -- Native size of class instances in the baseline heap dump.
+  baseline_native_size_bytes LONG,
+  -- Native size of class instances in the candidate heap dump.
+  candidate_native_size_bytes LONG,
+  -- Difference of the native sizes (candidate - baseline).
+  delta_native_size_bytes LONG
+) AS
+WITH
+  objects AS (
+    SELECT
+      type_id,
+      upid = $baseline_upid AND graph_sample_ts = $baseline_ts AS in_baseline,
+      upid = $candidate_upid AND graph_sample_ts = $candidate_ts AS in_candidate,
+      self_size,
+      native_size
+    FROM heap_graph_object
+    WHERE
+      reachable
+      AND (
+        (
+          upid = $baseline_upid AND graph_sample_ts = $baseline_ts
+        )
+        OR (
+          upid = $candidate_upid AND graph_sample_ts = $candidate_ts
+        )
+      )
+  ),
+  -- First level aggregation to avoid joining with class for every object.
+  base AS (
+    SELECT
+      type_id,
+      sum(in_baseline) AS baseline_obj_count,
+      sum(in_candidate) AS candidate_obj_count,
+      sum(iif(in_baseline, self_size, 0)) AS baseline_size_bytes,
+      sum(iif(in_candidate, self_size, 0)) AS candidate_size_bytes,
+      sum(iif(in_baseline, native_size, 0)) AS baseline_native_size_bytes,
+      sum(iif(in_candidate, native_size, 0)) AS candidate_native_size_bytes
+    FROM objects
+    GROUP BY
+      type_id
+  ),
+  by_name AS (
+    SELECT
+      coalesce(cls.deobfuscated_name, cls.name) AS type_name,
+      sum(baseline_obj_count) AS baseline_obj_count,
+      sum(candidate_obj_count) AS candidate_obj_count,
+      sum(baseline_size_bytes) AS baseline_size_bytes,
+      sum(candidate_size_bytes) AS candidate_size_bytes,
+      sum(baseline_native_size_bytes) AS baseline_native_size_bytes,
+      sum(candidate_native_size_bytes) AS candidate_native_size_bytes
+    FROM base
+    JOIN heap_graph_class AS cls
+      ON base.type_id = cls.id
+    GROUP BY
+      1
+  )
+SELECT
+  type_name,
+  baseline_obj_count,
+  candidate_obj_count,
+  candidate_obj_count - baseline_obj_count AS delta_obj_count,
+  baseline_size_bytes,
+  candidate_size_bytes,
+  candidate_size_bytes - baseline_size_bytes AS delta_size_bytes,
+  baseline_native_size_bytes,
+  candidate_native_size_bytes,
+  candidate_native_size_bytes - baseline_native_size_bytes AS delta_native_size_bytes
+FROM by_name
+ORDER BY
+  type_name;
@@ -0,0 +1,229 @@
+--
+-- Copyright 2025 The Android Open Source Project
+--
+-- Licensed under the Apache License, Version 2.0 (the 'License');
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     https://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an 'AS IS' BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+INCLUDE PERFETTO MODULE callstacks.stack_profile;
+
+-- The frames of all the callstacks of heap_profile_allocation, keyed by the
+-- path of (function name, mapping name) pairs from the root. Callstacks of
+-- different processes with the same frames have the same path, even if their
+-- callsites are distinct.
+CREATE PERFETTO TABLE _android_heap_profile_callstack_paths AS
+WITH RECURSIVE
+  forest AS MATERIALIZED (
+    SELECT
+      *
+    FROM _callstacks_for_stack_profile_samples!(
+      (SELECT DISTINCT callsite_id FROM heap_profile_allocation)
+    )
+  ),
+  paths(forest_id, id, parent_id) AS (
+    SELECT
+      f.id AS forest_id,
+      hash(coalesce(f.name, ''), coalesce(f.mapping_name, '')) AS id,
+      NULL AS parent_id
+    FROM forest AS f
+    WHERE
+      f.parent_id IS NULL
+    UNION ALL
+    SELECT
+      f.id AS forest_id,
+      hash(p.id, coalesce(f.name, ''), coalesce(f.mapping_name, '')) AS id,
+      p.id AS parent_id
+    FROM paths AS p
+    JOIN forest AS f
+      ON f.parent_id = p.forest_id
+  )
+SELECT
+  p.forest_id,
+  f.parent_id AS forest_parent_id,
+  p.id,
+  p.parent_id,
+  f.callsite_id,
+  f.is_leaf_function_in_callsite_frame,
+  f.name,
+  f.mapping_name
+FROM paths AS p
+JOIN forest AS f
+  ON p.forest_id = f.id;
+
+-- For every callsite of heap_profile_allocation, the paths of all the frames
+-- of its callstack, from the leaf to the root.
+CREATE PERFETTO TABLE _android_heap_profile_callsite_ancestor_paths AS
+WITH RECURSIVE
+  ancestors(callsite_id, forest_id, is_leaf) AS (
+    SELECT
+      callsite_id,
+      forest_id,
+      1 AS is_leaf
+    FROM _android_heap_profile_callstack_paths
+    WHERE
+      is_leaf_function_in_callsite_frame
+    UNION ALL
+    SELECT
+      a.callsite_id,
+      p.forest_parent_id AS forest_id,
+      0 AS is_leaf
+    FROM ancestors AS a
+    JOIN _android_heap_profile_callstack_paths AS p
+      USING (forest_id)
+    WHERE
+      p.forest_parent_id IS NOT NULL
+  )
+SELECT
+  a.callsite_id,
+  p.id AS path_id,
+  a.is_leaf
+FROM ancestors AS a
+JOIN _android_heap_profile_callstack_paths AS p
+  USING (forest_id);
+
+CREATE PERFETTO INDEX _android_heap_profile_callsite_ancestor_paths_idx
+ON _android_heap_profile_callsite_ancestor_paths(callsite_id);
+
+-- Compares the native heap profile of a process at a dump (the "baseline")
+-- with the native heap profile of the same or of another process at a dump
+-- (the "candidate"), e.g. to find what grew between two dumps of a process
+-- or how two processes running the same binary differ.
+--
+-- The callstacks of both profiles are merged by the function and mapping
+-- names of their frames, so the result is a single tree which can be
+-- displayed as a flamegraph. Only the allocations of the given heap are
+-- considered, as the sizes of different heaps (e.g. malloc and a custom
+-- allocator on top of it) can't be added up. Only memory that was allocated
+-- and *not freed* at the time of the dump is considered.
+CREATE PERFETTO FUNCTION android_heap_profile_diff(
+    -- Upid of the process of the baseline profile.
+    baseline_upid JOINID(process.id),
+    -- Timestamp of the dump of the baseline profile.
+    baseline_ts TIMESTAMP,
+    -- Upid of the process of the candidate profile.
+    candidate_upid JOINID(process.id),
+    -- Timestamp of the dump of the candidate profile.
+    candidate_ts TIMESTAMP,
+    -- Name of the heap to compare, e.g. 'libc.malloc'.
+    heap_name STRING
+)
+RETURNS TABLE (
+  -- The id of the merged callstack. A callstack in this context is a unique
+  -- path of frames up to the root.
+  id LONG,
+  -- The id of the parent callstack. NULL if this is a root.
+  parent_id LONG,
+  -- The function name of the frame for this callstack.
+  name STRING,
+  -- The name of the mapping containing the frame.
+  mapping_name STRING,
+  -- The amount of memory not freed in the baseline profile with this function
+  -- as the leaf frame.
+  baseline_self_size LONG,
+  -- The amount of memory not freed in the candidate profile with this
+  -- function as the leaf frame.
+  candidate_self_size LONG,
+  -- Difference of the self sizes (candidate - baseline).
+  delta_self_size LONG,
+  -- The amount of memory not freed in the baseline profile with this function
+  -- appearing anywhere on the callstack.
+  baseline_cumulative_size LONG,
+  -- The amount of memory not freed in the candidate profile with this
+  -- function appearing anywhere on the callstack.
+  candidate_cumulative_size LONG,
+  -- Difference of the cumulative sizes (candidate - baseline).
+  delta_cumulative_size LONG,
+  -- The number of allocations not freed in the baseline profile with this
+  -- function as the leaf frame.
+  baseline_self_count LONG,
+  -- The number of allocations not freed in the candidate profile with this
+  -- function as the leaf frame.
+  candidate_self_count LONG,
+  -- Difference of the self counts (candidate - baseline).
+  delta_self_count LONG,
+  -- The number of allocations not freed in the baseline profile with this
+  -- function appearing anywhere on the callstack.
+  baseline_cumulative_count LONG,
+  -- The number of allocations not freed in the candidate profile with this
+  -- function appearing anywhere on the callstack.
+  candidate_cumulative_count LONG,
+  -- Difference of the cumulative counts (candidate - baseline).
+  delta_cumulative_count LONG
+) AS
+WITH
+  metrics AS MATERIALIZED (
+    SELECT
+      callsite_id,
+      sum(iif(upid = $baseline_upid AND ts <= $baseline_ts, size, 0)) AS baseline_size,
+      sum(iif(upid = $candidate_upid AND ts <= $candidate_ts, size, 0)) AS candidate_size,
+      sum(iif(upid = $baseline_upid AND ts <= $baseline_ts, count, 0)) AS baseline_count,
+      sum(iif(upid = $candidate_upid AND ts <= $candidate_ts, count, 0)) AS candidate_count
+    FROM heap_profile_allocation
+    WHERE
+      heap_name = $heap_name
+      AND (
+        (
+          upid = $baseline_upid AND ts <= $baseline_ts
+        )
+        OR (
+          upid = $candidate_upid AND ts <= $candidate_ts
+        )
+      )
+    GROUP BY
+      callsite_id
+  ),
+  aggregated AS (
+    SELECT
+      a.path_id AS id,
+      sum(iif(a.is_leaf, m.baseline_size, 0)) AS baseline_self_size,
+      sum(iif(a.is_leaf, m.candidate_size, 0)) AS candidate_self_size,
+      sum(m.baseline_size) AS baseline_cumulative_size,
+      sum(m.candidate_size) AS candidate_cumulative_size,
+      sum(iif(a.is_leaf, m.baseline_count, 0)) AS baseline_self_count,
+      sum(iif(a.is_leaf, m.candidate_count, 0)) AS candidate_self_count,
+      sum(m.baseline_count) AS baseline_cumulative_count,
+      sum(m.candidate_count) AS candidate_cumulative_count
+    FROM metrics AS m
+    JOIN _android_heap_profile_callsite_ancestor_paths AS a
+      USING (callsite_id)
+    GROUP BY
+      a.path_id
+  ),
+  nodes AS (
+    SELECT DISTINCT
+      id,
+      parent_id,
+      name,
+      mapping_name
+    FROM _android_heap_profile_callstack_paths
+  )
+SELECT
+  a.id,
+  n.parent_id,
+  n.name,
+  n.mapping_name,
+  a.baseline_self_size,
+  a.candidate_self_size,
+  a.candidate_self_size - a.baseline_self_size AS delta_self_size,
+  a.baseline_cumulative_size,
+  a.candidate_cumulative_size,
+  a.candidate_cumulative_size - a.baseline_cumulative_size AS delta_cumulative_size,
+  a.baseline_self_count,
+  a.candidate_self_count,
+  a.candidate_self_count - a.baseline_self_count AS delta_self_count,
+  a.baseline_cumulative_count,
+  a.candidate_cumulative_count,
+  a.candidate_cumulative_count - a.baseline_cumulative_count AS delta_cumulative_count
+FROM aggregated AS a
+JOIN nodes AS n
+  USING (id)
+ORDER BY
+  a.id;
@@ -172,6 +172,35 @@ RETURNS TableOrSubquery AS
     USING (callsite_id)
 );
 
+-- Same as `_callstacks_for_callsites` but each sample contributes its `weight`
+-- column to `self_count`, instead of 1.
+CREATE PERFETTO MACRO _callstacks_for_weighted_callsites(
+    samples TableOrSubquery
+)
+RETURNS TableOrSubquery AS
+(
+  WITH
+    metrics AS MATERIALIZED (
+      SELECT
+        callsite_id,
+        sum(weight) AS self_count
+      FROM $samples
+      GROUP BY
+        callsite_id
+    )
+  SELECT
+    c.id,
+    c.parent_id,
+    c.name,
+    c.mapping_name,
+    c.source_file,
+    c.line_number,
+    iif(c.is_leaf_function_in_callsite_frame, coalesce(m.self_count, 0), 0) AS self_count
+  FROM _callstacks_for_stack_profile_samples!(metrics) AS c
+  LEFT JOIN metrics AS m
+    USING (callsite_id)
+);
+
 CREATE PERFETTO MACRO _callstacks_self_to_cumulative(
     callstacks TableOrSubquery
 )
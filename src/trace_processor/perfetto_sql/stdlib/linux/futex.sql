@@ -0,0 +1,221 @@
+--
+-- Copyright 2025 The Android Open Source Project
+--
+-- Licensed under the Apache License, Version 2.0 (the 'License');
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     https://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an 'AS IS' BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+INCLUDE PERFETTO MODULE sched.thread_executing_span;
+
+-- Futex waits with the process of the waiting thread.
+CREATE PERFETTO VIEW _linux_futex_contention_with_upid AS
+SELECT
+  f.id,
+  f.ts,
+  f.dur,
+  f.utid,
+  t.upid,
+  f.address,
+  f.op,
+  f.ret,
+  f.wait_slice_id,
+  f.waker_utid,
+  f.wake_ts,
+  f.wake_slice_id
+FROM futex_contention AS f
+JOIN thread AS t
+  USING (utid);
+
+-- Contended futex words (i.e. userspace locks), aggregated over all the waits
+-- on them. Sorting by |total_dur| gives the hottest locks of the trace.
+CREATE PERFETTO TABLE linux_futex_lock_summary (
+  -- Process of the waiting threads. Futex addresses are only meaningful
+  -- within a process.
+  upid JOINID(process.id),
+  -- Userspace address of the futex word.
+  address LONG,
+  -- Number of waits on the futex.
+  wait_count LONG,
+  -- Number of distinct threads which waited on the futex.
+  waiter_count LONG,
+  -- Number of distinct threads which woke a waiter of the futex.
+  waker_count LONG,
+  -- Total time spent waiting on the futex.
+  total_dur DURATION,
+  -- Longest wait on the futex.
+  max_dur DURATION,
+  -- Average wait on the futex.
+  avg_dur DOUBLE
+) AS
+SELECT
+  upid,
+  address,
+  count() AS wait_count,
+  count(DISTINCT utid) AS waiter_count,
+  count(DISTINCT waker_utid) AS waker_count,
+  sum(dur) AS total_dur,
+  max(dur) AS max_dur,
+  avg(dur) AS avg_dur
+FROM _linux_futex_contention_with_upid
+GROUP BY
+  upid,
+  address
+ORDER BY
+  total_dur DESC;
+
+-- Futex waits aggregated by waiting thread and the thread which woke it,
+-- for each contended futex word.
+CREATE PERFETTO TABLE linux_futex_contention_by_thread (
+  -- Process of the waiting thread.
+  upid JOINID(process.id),
+  -- Userspace address of the futex word.
+  address LONG,
+  -- Waiting thread.
+  utid JOINID(thread.id),
+  -- Thread which woke the waiter. NULL for waits which timed out, were
+  -- interrupted or whose wake was not traced.
+  waker_utid JOINID(thread.id),
+  -- Number of waits.
+  wait_count LONG,
+  -- Total time spent waiting.
+  total_dur DURATION
+) AS
+SELECT
+  upid,
+  address,
+  utid,
+  waker_utid,
+  count() AS wait_count,
+  sum(dur) AS total_dur
+FROM _linux_futex_contention_with_upid
+GROUP BY
+  upid,
+  address,
+  utid,
+  waker_utid
+ORDER BY
+  total_dur DESC;
+
+-- Follows the chain of threads blocking a futex wait: the thread which woke
+-- the waiter was often itself waiting on a futex (e.g. for the same lock, or a
+-- lock it needed before releasing this one). Each step of the chain is the
+-- contended wait of the previous waker which ended while the previous waiter
+-- was blocked.
+CREATE PERFETTO FUNCTION linux_futex_blocking_chain(
+    -- Id of the futex wait to start from.
+    futex_contention_id LONG
+)
+RETURNS TABLE (
+  -- Position in the chain. 0 for the wait passed in.
+  depth LONG,
+  -- Id of the futex wait. Alias of |futex_contention.id|.
+  id LONG,
+  -- Start of the wait.
+  ts TIMESTAMP,
+  -- Duration of the wait.
+  dur DURATION,
+  -- Waiting thread.
+  utid JOINID(thread.id),
+  -- Userspace address of the futex word.
+  address LONG,
+  -- Thread which ended the wait.
+  waker_utid JOINID(thread.id),
+  -- Timestamp of the wake which ended the wait.
+  wake_ts TIMESTAMP
+) AS
+WITH RECURSIVE
+  chain(depth, id, ts, dur, utid, address, waker_utid, wake_ts) AS (
+    SELECT
+      0,
+      id,
+      ts,
+      dur,
+      utid,
+      address,
+      waker_utid,
+      wake_ts
+    FROM futex_contention
+    WHERE
+      id = $futex_contention_id
+    UNION ALL
+    SELECT
+      chain.depth + 1,
+      f.id,
+      f.ts,
+      f.dur,
+      f.utid,
+      f.address,
+      f.waker_utid,
+      f.wake_ts
+    FROM chain
+    -- Only the last wait of the waker before the wake can have delayed it.
+    JOIN futex_contention AS f
+      ON f.id = (
+        SELECT
+          id
+        FROM futex_contention AS last
+        WHERE
+          last.utid = chain.waker_utid
+          AND last.ts + last.dur BETWEEN chain.ts AND chain.wake_ts
+        ORDER BY
+          last.ts DESC
+        LIMIT 1
+      )
+    WHERE
+      chain.depth < 32
+  )
+SELECT
+  depth,
+  id,
+  ts,
+  dur,
+  utid,
+  address,
+  waker_utid,
+  wake_ts
+FROM chain
+ORDER BY
+  depth;
+
+-- The critical path of a futex wait: the threads whose execution the waiting
+-- thread was (transitively) waiting on, computed from the scheduler wakeup
+-- graph. Requires sched_switch and sched_waking events.
+CREATE PERFETTO FUNCTION linux_futex_wait_critical_path(
+    -- Id of the futex wait. Alias of |futex_contention.id|.
+    futex_contention_id LONG
+)
+RETURNS TABLE (
+  -- Id of the first (runnable) thread state of the thread executing span on
+  -- the critical path.
+  id LONG,
+  -- Start of the span, clipped to the wait.
+  ts TIMESTAMP,
+  -- Duration of the span, clipped to the wait.
+  dur DURATION,
+  -- Thread on the critical path.
+  utid JOINID(thread.id)
+) AS
+SELECT
+  id,
+  ts,
+  dur,
+  utid
+FROM _critical_path_by_intervals!(
+  (
+    SELECT
+      utid,
+      ts,
+      dur
+    FROM futex_contention
+    WHERE
+      id = $futex_contention_id
+  ),
+  _wakeup_graph);
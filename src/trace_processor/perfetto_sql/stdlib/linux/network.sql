@@ -0,0 +1,154 @@
+--
+-- Copyright 2025 The Android Open Source Project
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     https://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+-- The /proc/net/dev counters of each interface, one row per poll.
+CREATE PERFETTO VIEW _linux_netdev_sample AS
+WITH
+  netdev_counter AS (
+    SELECT
+      c.ts,
+      extract_arg(t.dimension_arg_set_id, 'netdev_interface') AS interface_name,
+      extract_arg(t.dimension_arg_set_id, 'netdev_counter') AS counter_name,
+      c.value
+    FROM counter AS c
+    JOIN counter_track AS t
+      ON c.track_id = t.id
+    WHERE
+      t.type = 'netdev'
+  )
+SELECT
+  ts,
+  interface_name,
+  max(iif(counter_name = 'rx_bytes', value, NULL)) AS rx_bytes,
+  max(iif(counter_name = 'tx_bytes', value, NULL)) AS tx_bytes,
+  max(iif(counter_name = 'rx_packets', value, NULL)) AS rx_packets,
+  max(iif(counter_name = 'tx_packets', value, NULL)) AS tx_packets
+FROM netdev_counter
+GROUP BY
+  ts,
+  interface_name;
+
+-- Network throughput of each interface, computed from the /proc/net/dev
+-- counters polled by the linux.sys_stats data source (see
+-- `SysStatsConfig.netdev_period_ms`).
+--
+-- Each row covers the interval between two consecutive polls. Intervals in
+-- which a counter went backwards (e.g. because the interface was re-created)
+-- have NULL amounts and rates.
+CREATE PERFETTO TABLE linux_network_interface_throughput (
+  -- Timestamp of the poll starting the interval.
+  ts TIMESTAMP,
+  -- Duration of the interval, until the next poll.
+  dur DURATION,
+  -- Name of the network interface (e.g. "wlan0").
+  interface_name STRING,
+  -- Bytes received during the interval.
+  rx_bytes LONG,
+  -- Bytes transmitted during the interval.
+  tx_bytes LONG,
+  -- Packets received during the interval.
+  rx_packets LONG,
+  -- Packets transmitted during the interval.
+  tx_packets LONG,
+  -- Receive throughput, in bytes per second.
+  rx_bytes_per_sec DOUBLE,
+  -- Transmit throughput, in bytes per second.
+  tx_bytes_per_sec DOUBLE
+) AS
+WITH
+  deltas AS (
+    SELECT
+      ts,
+      lead(ts) OVER w - ts AS dur,
+      interface_name,
+      lead(rx_bytes) OVER w - rx_bytes AS rx_bytes,
+      lead(tx_bytes) OVER w - tx_bytes AS tx_bytes,
+      lead(rx_packets) OVER w - rx_packets AS rx_packets,
+      lead(tx_packets) OVER w - tx_packets AS tx_packets
+    FROM _linux_netdev_sample
+    WINDOW w AS (PARTITION BY interface_name ORDER BY ts)
+  ),
+  valid_deltas AS (
+    SELECT
+      ts,
+      dur,
+      interface_name,
+      cast_int!(iif(rx_bytes >= 0, rx_bytes, NULL)) AS rx_bytes,
+      cast_int!(iif(tx_bytes >= 0, tx_bytes, NULL)) AS tx_bytes,
+      cast_int!(iif(rx_packets >= 0, rx_packets, NULL)) AS rx_packets,
+      cast_int!(iif(tx_packets >= 0, tx_packets, NULL)) AS tx_packets
+    FROM deltas
+    WHERE
+      dur > 0
+  )
+SELECT
+  ts,
+  dur,
+  interface_name,
+  rx_bytes,
+  tx_bytes,
+  rx_packets,
+  tx_packets,
+  rx_bytes * 1e9 / dur AS rx_bytes_per_sec,
+  tx_bytes * 1e9 / dur AS tx_bytes_per_sec
+FROM valid_deltas
+ORDER BY
+  interface_name,
+  ts;
+
+-- Rate of the cumulative TCP counters of /proc/net/snmp and /proc/net/netstat
+-- (e.g. "Tcp:RetransSegs", "Tcp:OutRsts"), polled by the linux.sys_stats data
+-- source (see `SysStatsConfig.netstat_period_ms`).
+--
+-- Each row covers the interval between two consecutive polls. The
+-- "Tcp:CurrEstab" gauge is not a cumulative counter and is not reported.
+CREATE PERFETTO TABLE linux_tcp_counter_rate (
+  -- Timestamp of the poll starting the interval.
+  ts TIMESTAMP,
+  -- Duration of the interval, until the next poll.
+  dur DURATION,
+  -- Name of the counter, in the form "<section>:<name>".
+  key STRING,
+  -- Increment of the counter during the interval.
+  delta LONG,
+  -- Increment of the counter per second.
+  rate_per_sec DOUBLE
+) AS
+WITH
+  deltas AS (
+    SELECT
+      c.ts,
+      lead(c.ts) OVER w - c.ts AS dur,
+      extract_arg(t.dimension_arg_set_id, 'netstat_key') AS key,
+      lead(c.value) OVER w - c.value AS delta
+    FROM counter AS c
+    JOIN counter_track AS t
+      ON c.track_id = t.id
+    WHERE
+      t.type = 'netstat'
+    WINDOW w AS (PARTITION BY c.track_id ORDER BY c.ts)
+  )
+SELECT
+  ts,
+  dur,
+  key,
+  cast_int!(delta) AS delta,
+  delta * 1e9 / dur AS rate_per_sec
+FROM deltas
+WHERE
+  dur > 0 AND delta >= 0 AND key != 'Tcp:CurrEstab'
+ORDER BY
+  key,
+  ts;
@@ -0,0 +1,221 @@
+--
+-- Copyright 2025 The Android Open Source Project
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     https://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+-- Increments of the perf counters (timebase and followers) between two
+-- consecutive samples of the same perf session on the same CPU.
+--
+-- The increment is attributed to the thread of the later sample: with a high
+-- enough sampling frequency, this is the thread which was running for most of
+-- the interval. The first sample of each CPU has no increment and is not
+-- reported.
+CREATE PERFETTO TABLE linux_perf_sample_counter_delta (
+  -- Id of the sample.
+  id JOINID(perf_sample.id),
+  -- Timestamp of the sample.
+  ts TIMESTAMP,
+  -- Thread the sample was taken in.
+  utid JOINID(thread.id),
+  -- CPU the sample was taken on.
+  cpu LONG,
+  -- Perf session the sample belongs to.
+  perf_session_id LONG,
+  -- Name of the counter (e.g. "instructions", "cpu-cycles").
+  name STRING,
+  -- Increment of the counter since the previous sample.
+  delta LONG
+) AS
+WITH
+  sample_counter AS (
+    SELECT
+      s.id,
+      s.ts,
+      s.utid,
+      s.cpu,
+      s.perf_session_id,
+      t.name,
+      c.value - lag(c.value) OVER (
+        PARTITION BY c.track_id
+        ORDER BY c.ts
+      ) AS delta
+    FROM perf_sample AS s
+    JOIN perf_counter_track AS t
+      ON t.cpu = s.cpu AND t.perf_session_id = s.perf_session_id
+    JOIN counter AS c
+      ON c.track_id = t.id AND c.ts = s.ts
+  )
+SELECT
+  id,
+  ts,
+  utid,
+  cpu,
+  perf_session_id,
+  name,
+  cast_int!(delta) AS delta
+FROM sample_counter
+WHERE
+  delta IS NOT NULL
+ORDER BY
+  id,
+  name;
+
+-- Instructions and cycles executed between two consecutive samples of the same
+-- CPU, attributed to the thread of the later sample (see
+-- `linux_perf_sample_counter_delta`).
+--
+-- Requires the "instructions" (HW_INSTRUCTIONS) and "cpu-cycles"
+-- (HW_CPU_CYCLES) counters to be recorded as followers (or as the timebase)
+-- of the perf session, without a custom name.
+CREATE PERFETTO TABLE linux_perf_sample_ipc (
+  -- Id of the sample.
+  id JOINID(perf_sample.id),
+  -- Timestamp of the sample.
+  ts TIMESTAMP,
+  -- Thread the sample was taken in.
+  utid JOINID(thread.id),
+  -- CPU the sample was taken on.
+  cpu LONG,
+  -- Instructions executed since the previous sample.
+  instructions LONG,
+  -- CPU cycles elapsed since the previous sample.
+  cycles LONG,
+  -- Instructions per cycle.
+  ipc DOUBLE
+) AS
+SELECT
+  i.id,
+  i.ts,
+  i.utid,
+  i.cpu,
+  i.delta AS instructions,
+  c.delta AS cycles,
+  iif(c.delta > 0, i.delta * 1.0 / c.delta, NULL) AS ipc
+FROM linux_perf_sample_counter_delta AS i
+JOIN linux_perf_sample_counter_delta AS c
+  USING (id)
+WHERE
+  i.name = 'instructions' AND c.name = 'cpu-cycles'
+ORDER BY
+  i.id;
+
+-- Instructions per cycle of each thread, over the whole trace.
+CREATE PERFETTO TABLE linux_perf_thread_ipc (
+  -- Thread.
+  utid JOINID(thread.id),
+  -- Number of samples taken in the thread.
+  sample_count LONG,
+  -- Instructions executed by the thread.
+  instructions LONG,
+  -- CPU cycles elapsed in the thread.
+  cycles LONG,
+  -- Instructions per cycle.
+  ipc DOUBLE
+) AS
+SELECT
+  utid,
+  count() AS sample_count,
+  sum(instructions) AS instructions,
+  sum(cycles) AS cycles,
+  iif(sum(cycles) > 0, sum(instructions) * 1.0 / sum(cycles), NULL) AS ipc
+FROM linux_perf_sample_ipc
+GROUP BY
+  utid
+ORDER BY
+  utid;
+
+-- Instructions per cycle of each interval in which a thread was running on a
+-- CPU, computed from the samples taken in the thread on that CPU during the
+-- interval. Intervals without samples are not reported.
+CREATE PERFETTO TABLE linux_perf_thread_state_ipc (
+  -- Id of the thread state.
+  id JOINID(thread_state.id),
+  -- Start of the interval.
+  ts TIMESTAMP,
+  -- Duration of the interval.
+  dur DURATION,
+  -- Thread which was running.
+  utid JOINID(thread.id),
+  -- CPU the thread was running on.
+  cpu LONG,
+  -- Number of samples taken during the interval.
+  sample_count LONG,
+  -- Instructions executed during the interval.
+  instructions LONG,
+  -- CPU cycles elapsed during the interval.
+  cycles LONG,
+  -- Instructions per cycle.
+  ipc DOUBLE
+) AS
+SELECT
+  t.id,
+  t.ts,
+  t.dur,
+  t.utid,
+  t.cpu,
+  count() AS sample_count,
+  sum(s.instructions) AS instructions,
+  sum(s.cycles) AS cycles,
+  iif(sum(s.cycles) > 0, sum(s.instructions) * 1.0 / sum(s.cycles), NULL) AS ipc
+FROM thread_state AS t
+JOIN linux_perf_sample_ipc AS s
+  ON s.utid = t.utid AND s.cpu = t.cpu AND s.ts >= t.ts AND s.ts < t.ts + t.dur
+WHERE
+  t.state = 'Running'
+GROUP BY
+  t.id
+ORDER BY
+  t.id;
+
+-- Instructions per cycle of each thread slice, computed from the samples taken
+-- in the thread of the slice during the slice. Slices without samples are not
+-- reported.
+CREATE PERFETTO TABLE linux_perf_slice_ipc (
+  -- Id of the slice.
+  id JOINID(slice.id),
+  -- Start of the slice.
+  ts TIMESTAMP,
+  -- Duration of the slice.
+  dur DURATION,
+  -- Name of the slice.
+  name STRING,
+  -- Thread of the slice.
+  utid JOINID(thread.id),
+  -- Number of samples taken during the slice.
+  sample_count LONG,
+  -- Instructions executed during the slice.
+  instructions LONG,
+  -- CPU cycles elapsed during the slice.
+  cycles LONG,
+  -- Instructions per cycle.
+  ipc DOUBLE
+) AS
+SELECT
+  sl.id,
+  sl.ts,
+  sl.dur,
+  sl.name,
+  tt.utid,
+  count() AS sample_count,
+  sum(s.instructions) AS instructions,
+  sum(s.cycles) AS cycles,
+  iif(sum(s.cycles) > 0, sum(s.instructions) * 1.0 / sum(s.cycles), NULL) AS ipc
+FROM slice AS sl
+JOIN thread_track AS tt
+  ON sl.track_id = tt.id
+JOIN linux_perf_sample_ipc AS s
+  ON s.utid = tt.utid AND s.ts >= sl.ts AND s.ts < sl.ts + sl.dur
+GROUP BY
+  sl.id
+ORDER BY
+  sl.id;
@@ -0,0 +1,212 @@
+--
+-- Copyright 2025 The Android Open Source Project
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     https://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+INCLUDE PERFETTO MODULE callstacks.stack_profile;
+
+-- Perf sessions sampling on the sched_switch tracepoint (e.g. configured with
+-- `PerfEventConfig.off_cpu_sampling`). Their samples are taken in the thread
+-- being descheduled.
+CREATE PERFETTO VIEW _linux_perf_off_cpu_session AS
+SELECT DISTINCT
+  perf_session_id
+FROM perf_counter_track
+WHERE
+  is_timebase AND name IN ('sched:sched_switch', 'sched/sched_switch');
+
+-- Samples of the off-cpu perf sessions, each joined with the interval of
+-- `thread_state` in which the sampled thread was blocked after being
+-- descheduled.
+--
+-- Requires the scheduling data (i.e. the sched_switch and sched_waking ftrace
+-- events) to be recorded in the same trace. Samples of threads which were
+-- preempted rather than blocked, or which do not have a matching blocked
+-- interval, are not reported.
+CREATE PERFETTO TABLE linux_perf_off_cpu_sample (
+  -- Id of the sample.
+  id JOINID(perf_sample.id),
+  -- Timestamp of the sample.
+  ts TIMESTAMP,
+  -- Thread which was descheduled.
+  utid JOINID(thread.id),
+  -- CPU the thread was descheduled from.
+  cpu LONG,
+  -- Callstack of the thread at the time it was descheduled.
+  callsite_id JOINID(stack_profile_callsite.id),
+  -- Id of the thread state in which the thread was blocked.
+  thread_state_id JOINID(thread_state.id),
+  -- State the thread was blocked in (e.g. "S", "D").
+  state STRING,
+  -- Duration the thread was blocked for. Threads still blocked at the end of
+  -- the trace are considered blocked until the end of the trace.
+  blocked_dur DURATION,
+  -- Whether the thread was blocked on IO.
+  io_wait LONG,
+  -- The function in the kernel the thread was blocked on.
+  blocked_function STRING,
+  -- Thread which woke up the blocked thread.
+  waker_utid JOINID(thread.id)
+) AS
+WITH
+  thread_state_with_next AS (
+    SELECT
+      id,
+      ts,
+      iif(dur = -1, trace_end() - ts, dur) AS dur,
+      utid,
+      state,
+      lead(id) OVER (PARTITION BY utid ORDER BY ts) AS next_id
+    FROM thread_state
+  ),
+  -- The perf sample and the sched_switch ftrace event are emitted by the same
+  -- tracepoint, but can be timestamped slightly apart. The sample can
+  -- therefore fall either at the start of the blocked interval, or at the end
+  -- of the preceding running one.
+  sample_thread_state AS (
+    SELECT
+      s.id,
+      s.ts,
+      s.utid,
+      s.cpu,
+      s.callsite_id,
+      iif(t.state = 'Running', t.next_id, t.id) AS thread_state_id
+    FROM perf_sample AS s
+    JOIN thread_state_with_next AS t
+      ON t.utid = s.utid AND s.ts >= t.ts AND s.ts < t.ts + t.dur
+    WHERE
+      s.perf_session_id IN (
+        SELECT
+          perf_session_id
+        FROM _linux_perf_off_cpu_session
+      )
+  )
+SELECT
+  s.id,
+  s.ts,
+  s.utid,
+  s.cpu,
+  s.callsite_id,
+  s.thread_state_id,
+  t.state,
+  iif(t.dur = -1, trace_end() - t.ts, t.dur) AS blocked_dur,
+  t.io_wait,
+  t.blocked_function,
+  t.waker_utid
+FROM sample_thread_state AS s
+JOIN thread_state AS t
+  ON t.id = s.thread_state_id
+WHERE
+  t.state NOT IN ('Running', 'R', 'R+')
+ORDER BY
+  s.id;
+
+-- Blocked time of each thread, summed over the off-cpu samples of the thread.
+CREATE PERFETTO TABLE linux_perf_off_cpu_thread (
+  -- Thread.
+  utid JOINID(thread.id),
+  -- Number of times the thread was sampled while being descheduled.
+  sample_count LONG,
+  -- Total time the thread was blocked for, after being sampled.
+  blocked_dur DURATION,
+  -- Time the thread was blocked for in an uninterruptible sleep.
+  uninterruptible_dur DURATION
+) AS
+SELECT
+  utid,
+  count() AS sample_count,
+  sum(blocked_dur) AS blocked_dur,
+  sum(iif(state GLOB 'D*', blocked_dur, 0)) AS uninterruptible_dur
+FROM linux_perf_off_cpu_sample
+GROUP BY
+  utid
+ORDER BY
+  utid;
+
+CREATE PERFETTO TABLE _linux_perf_off_cpu_raw_callstacks AS
+SELECT
+  c.*,
+  d.self_count AS self_blocked_dur
+FROM _callstacks_for_callsites!((
+  SELECT callsite_id
+  FROM linux_perf_off_cpu_sample
+)) AS c
+JOIN _callstacks_for_weighted_callsites!((
+  SELECT callsite_id, blocked_dur AS weight
+  FROM linux_perf_off_cpu_sample
+)) AS d
+  USING (id)
+ORDER BY
+  c.id;
+
+-- Table summarising the callstacks at which threads were descheduled while
+-- blocked, weighted by how long they were blocked for ("blocked time by
+-- callstack").
+--
+-- Specifically, this table returns a tree containing all the callstacks of
+-- `linux_perf_off_cpu_sample`, with `self_blocked_dur` equal to the time spent
+-- blocked with that frame as the leaf and `cumulative_blocked_dur` equal to the
+-- time spent blocked with the frame anywhere in the tree. It can be rendered as
+-- a flamegraph in the same way as `linux_perf_samples_summary_tree`.
+CREATE PERFETTO TABLE linux_perf_off_cpu_summary_tree (
+  -- The id of the callstack. A callstack in this context
+  -- is a unique set of frames up to the root.
+  id LONG,
+  -- The id of the parent callstack for this callstack.
+  parent_id LONG,
+  -- The function name of the frame for this callstack.
+  name STRING,
+  -- The name of the mapping containing the frame. This
+  -- can be a native binary, library, JAR or APK.
+  mapping_name STRING,
+  -- The name of the file containing the function.
+  source_file STRING,
+  -- The line number in the file the function is located at.
+  line_number LONG,
+  -- The number of samples with this function as the leaf
+  -- frame.
+  self_count LONG,
+  -- The number of samples with this function appearing
+  -- anywhere on the callstack.
+  cumulative_count LONG,
+  -- The time spent blocked with this function as the leaf
+  -- frame.
+  self_blocked_dur DURATION,
+  -- The time spent blocked with this function appearing
+  -- anywhere on the callstack.
+  cumulative_blocked_dur DURATION
+) AS
+SELECT
+  r.id,
+  r.parent_id,
+  r.name,
+  r.mapping_name,
+  r.source_file,
+  r.line_number,
+  r.self_count,
+  ac.cumulative_count,
+  r.self_blocked_dur,
+  ad.cumulative_count AS cumulative_blocked_dur
+FROM _linux_perf_off_cpu_raw_callstacks AS r
+JOIN _callstacks_self_to_cumulative!((
+  SELECT id, parent_id, self_count
+  FROM _linux_perf_off_cpu_raw_callstacks
+)) AS ac
+  USING (id)
+JOIN _callstacks_self_to_cumulative!((
+  SELECT id, parent_id, self_blocked_dur AS self_count
+  FROM _linux_perf_off_cpu_raw_callstacks
+)) AS ad
+  USING (id)
+ORDER BY
+  r.id;
@@ -0,0 +1,447 @@
+--
+-- Copyright 2025 The Android Open Source Project
+--
+-- Licensed under the Apache License, Version 2.0 (the 'License');
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     https://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an 'AS IS' BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+-- Comparison of the trace loaded in trace processor (the "candidate") with a
+-- "baseline" trace.
+--
+-- This module requires the tables of the `trace_diff.stats` module computed on
+-- the baseline trace to be available in the `baseline` schema: this is done
+-- automatically by `trace_processor_shell --diff-baseline`.
+--
+-- Where there are enough samples, the differences come with the p-value of a
+-- statistical test for the null hypothesis that the two traces have the same
+-- underlying distribution. `is_significant` is true if the p-value is lower
+-- than 0.05.
+
+INCLUDE PERFETTO MODULE trace_diff.stats;
+
+-- Minimum number of samples in each trace for computing p-values.
+CREATE PERFETTO FUNCTION _trace_diff_min_samples()
+RETURNS LONG AS
+SELECT
+  5;
+
+-- Returns the p-value of the Welch's t-test comparing the means of two samples
+-- given their means, variances and sizes.
+CREATE PERFETTO FUNCTION _trace_diff_welch_p_value(
+    mean_a DOUBLE,
+    variance_a DOUBLE,
+    count_a LONG,
+    mean_b DOUBLE,
+    variance_b DOUBLE,
+    count_b LONG
+)
+RETURNS DOUBLE AS
+WITH
+  errs AS (
+    SELECT
+      $variance_a / $count_a AS err_a,
+      $variance_b / $count_b AS err_b
+    WHERE
+      $count_a >= _trace_diff_min_samples()
+      AND $count_b >= _trace_diff_min_samples()
+  )
+SELECT
+  iif(
+    err_a + err_b > 0,
+    __intrinsic_student_t_p_value(
+      ($mean_b - $mean_a) / sqrt(err_a + err_b),
+      (err_a + err_b) * (err_a + err_b) / (
+        err_a * err_a / ($count_a - 1) + err_b * err_b / ($count_b - 1)
+      )
+    ),
+    -- Both samples are constant: they either have the same value or not.
+    iif($mean_a = $mean_b, 1.0, 0.0)
+  )
+FROM errs;
+
+-- Returns the p-value of the two-proportion z-test comparing the fraction of
+-- `count_a` over `total_a` with the fraction of `count_b` over `total_b`.
+CREATE PERFETTO FUNCTION _trace_diff_proportion_p_value(
+    count_a LONG,
+    total_a LONG,
+    count_b LONG,
+    total_b LONG
+)
+RETURNS DOUBLE AS
+WITH
+  pooled AS (
+    SELECT
+      ($count_a + $count_b) * 1.0 / ($total_a + $total_b) AS p
+    WHERE
+      $total_a > 0 AND $total_b > 0
+  )
+SELECT
+  __intrinsic_normal_p_value(
+    ($count_b * 1.0 / $total_b - $count_a * 1.0 / $total_a) / sqrt(
+      p * (1 - p) * (1.0 / $total_a + 1.0 / $total_b)
+    )
+  )
+FROM pooled
+-- The normal approximation only holds if the expected counts are large enough.
+WHERE
+  min($total_a, $total_b) * min(p, 1 - p) >= _trace_diff_min_samples();
+
+-- Comparison of the durations of the slices in the baseline and candidate
+-- traces, aggregated by slice name, thread name and process name.
+CREATE PERFETTO TABLE trace_diff_slice (
+  -- Name of the slices.
+  name STRING,
+  -- Name of the thread of the slices.
+  thread_name STRING,
+  -- Name of the process of the slices.
+  process_name STRING,
+  -- Number of slices in the baseline trace.
+  baseline_count LONG,
+  -- Number of slices in the candidate trace.
+  candidate_count LONG,
+  -- Sum of the durations of the slices in the baseline trace.
+  baseline_total_dur DURATION,
+  -- Sum of the durations of the slices in the candidate trace.
+  candidate_total_dur DURATION,
+  -- Difference of the total durations (candidate - baseline).
+  delta_total_dur DURATION,
+  -- Mean duration of the slices in the baseline trace.
+  baseline_mean_dur DOUBLE,
+  -- Mean duration of the slices in the candidate trace.
+  candidate_mean_dur DOUBLE,
+  -- Difference of the mean durations (candidate - baseline).
+  delta_mean_dur DOUBLE,
+  -- The p-value of the Welch's t-test for the difference of the mean
+  -- durations. NULL if there are not enough slices in either trace.
+  p_value DOUBLE,
+  -- Whether the difference of the mean durations is statistically significant.
+  is_significant BOOL
+) AS
+WITH
+  keys AS (
+    SELECT
+      name,
+      thread_name,
+      process_name
+    FROM trace_diff_slice_stats
+    UNION
+    SELECT
+      name,
+      thread_name,
+      process_name
+    FROM baseline.trace_diff_slice_stats
+  ),
+  joined AS (
+    SELECT
+      k.name,
+      k.thread_name,
+      k.process_name,
+      coalesce(b.count, 0) AS baseline_count,
+      coalesce(c.count, 0) AS candidate_count,
+      coalesce(b.total_dur, 0) AS baseline_total_dur,
+      coalesce(c.total_dur, 0) AS candidate_total_dur,
+      b.mean_dur AS baseline_mean_dur,
+      c.mean_dur AS candidate_mean_dur,
+      _trace_diff_welch_p_value(
+        b.mean_dur, b.variance_dur, b.count,
+        c.mean_dur, c.variance_dur, c.count
+      ) AS p_value
+    FROM keys AS k
+    LEFT JOIN baseline.trace_diff_slice_stats AS b
+      ON k.name IS b.name
+      AND k.thread_name IS b.thread_name
+      AND k.process_name IS b.process_name
+    LEFT JOIN trace_diff_slice_stats AS c
+      ON k.name IS c.name
+      AND k.thread_name IS c.thread_name
+      AND k.process_name IS c.process_name
+  )
+SELECT
+  name,
+  thread_name,
+  process_name,
+  baseline_count,
+  candidate_count,
+  baseline_total_dur,
+  candidate_total_dur,
+  candidate_total_dur - baseline_total_dur AS delta_total_dur,
+  baseline_mean_dur,
+  candidate_mean_dur,
+  candidate_mean_dur - baseline_mean_dur AS delta_mean_dur,
+  p_value,
+  coalesce(p_value < 0.05, FALSE) AS is_significant
+FROM joined
+ORDER BY
+  abs(candidate_total_dur - baseline_total_dur) DESC;
+
+-- Comparison of the time spent by the threads in each scheduling state in the
+-- baseline and candidate traces, aggregated by process name, thread name and
+-- state.
+CREATE PERFETTO TABLE trace_diff_thread_state (
+  -- Name of the process of the thread.
+  process_name STRING,
+  -- Name of the thread.
+  thread_name STRING,
+  -- The scheduling state of the thread (see `thread_state.state`).
+  state STRING,
+  -- Number of thread state intervals in the baseline trace.
+  baseline_count LONG,
+  -- Number of thread state intervals in the candidate trace.
+  candidate_count LONG,
+  -- Time spent in the state in the baseline trace.
+  baseline_total_dur DURATION,
+  -- Time spent in the state in the candidate trace.
+  candidate_total_dur DURATION,
+  -- Difference of the time spent in the state (candidate - baseline).
+  delta_total_dur DURATION,
+  -- Mean duration of the intervals in the baseline trace.
+  baseline_mean_dur DOUBLE,
+  -- Mean duration of the intervals in the candidate trace.
+  candidate_mean_dur DOUBLE,
+  -- Difference of the mean durations (candidate - baseline).
+  delta_mean_dur DOUBLE,
+  -- The p-value of the Welch's t-test for the difference of the mean
+  -- durations. NULL if there are not enough intervals in either trace.
+  p_value DOUBLE,
+  -- Whether the difference of the mean durations is statistically significant.
+  is_significant BOOL
+) AS
+WITH
+  keys AS (
+    SELECT
+      process_name,
+      thread_name,
+      state
+    FROM trace_diff_thread_state_stats
+    UNION
+    SELECT
+      process_name,
+      thread_name,
+      state
+    FROM baseline.trace_diff_thread_state_stats
+  ),
+  joined AS (
+    SELECT
+      k.process_name,
+      k.thread_name,
+      k.state,
+      coalesce(b.count, 0) AS baseline_count,
+      coalesce(c.count, 0) AS candidate_count,
+      coalesce(b.total_dur, 0) AS baseline_total_dur,
+      coalesce(c.total_dur, 0) AS candidate_total_dur,
+      b.mean_dur AS baseline_mean_dur,
+      c.mean_dur AS candidate_mean_dur,
+      _trace_diff_welch_p_value(
+        b.mean_dur, b.variance_dur, b.count,
+        c.mean_dur, c.variance_dur, c.count
+      ) AS p_value
+    FROM keys AS k
+    LEFT JOIN baseline.trace_diff_thread_state_stats AS b
+      ON k.process_name IS b.process_name
+      AND k.thread_name IS b.thread_name
+      AND k.state IS b.state
+    LEFT JOIN trace_diff_thread_state_stats AS c
+      ON k.process_name IS c.process_name
+      AND k.thread_name IS c.thread_name
+      AND k.state IS c.state
+  )
+SELECT
+  process_name,
+  thread_name,
+  state,
+  baseline_count,
+  candidate_count,
+  baseline_total_dur,
+  candidate_total_dur,
+  candidate_total_dur - baseline_total_dur AS delta_total_dur,
+  baseline_mean_dur,
+  candidate_mean_dur,
+  candidate_mean_dur - baseline_mean_dur AS delta_mean_dur,
+  p_value,
+  coalesce(p_value < 0.05, FALSE) AS is_significant
+FROM joined
+ORDER BY
+  abs(candidate_total_dur - baseline_total_dur) DESC;
+
+-- Comparison of the CPU time of the processes in the baseline and candidate
+-- traces, aggregated by process name.
+CREATE PERFETTO TABLE trace_diff_process_cpu (
+  -- Name of the process.
+  process_name STRING,
+  -- CPU time of the process in the baseline trace.
+  baseline_cpu_dur DURATION,
+  -- CPU time of the process in the candidate trace.
+  candidate_cpu_dur DURATION,
+  -- Difference of the CPU times (candidate - baseline).
+  delta_cpu_dur DURATION,
+  -- Ratio of the CPU times (candidate / baseline). NULL if the process did not
+  -- run in the baseline trace.
+  cpu_dur_ratio DOUBLE
+) AS
+WITH
+  keys AS (
+    SELECT
+      process_name
+    FROM trace_diff_process_cpu_stats
+    UNION
+    SELECT
+      process_name
+    FROM baseline.trace_diff_process_cpu_stats
+  ),
+  joined AS (
+    SELECT
+      k.process_name,
+      coalesce(b.cpu_dur, 0) AS baseline_cpu_dur,
+      coalesce(c.cpu_dur, 0) AS candidate_cpu_dur
+    FROM keys AS k
+    LEFT JOIN baseline.trace_diff_process_cpu_stats AS b
+      ON k.process_name IS b.process_name
+    LEFT JOIN trace_diff_process_cpu_stats AS c
+      ON k.process_name IS c.process_name
+  )
+SELECT
+  process_name,
+  baseline_cpu_dur,
+  candidate_cpu_dur,
+  candidate_cpu_dur - baseline_cpu_dur AS delta_cpu_dur,
+  iif(
+    baseline_cpu_dur > 0,
+    candidate_cpu_dur * 1.0 / baseline_cpu_dur,
+    NULL
+  ) AS cpu_dur_ratio
+FROM joined
+ORDER BY
+  abs(candidate_cpu_dur - baseline_cpu_dur) DESC;
+
+-- Differential flamegraph of the CPU profiling samples of the baseline and
+-- candidate traces.
+--
+-- This is the union of the callstacks of both traces (see
+-- `trace_diff_cpu_profile_tree`) with the sample counts of each trace. The
+-- significance of the difference of the fraction of samples containing each
+-- callstack is evaluated with a two-proportion z-test.
+CREATE PERFETTO TABLE trace_diff_cpu_profile_flamegraph (
+  -- The id of the callstack. The same as `trace_diff_cpu_profile_tree.id`.
+  id LONG,
+  -- The id of the parent callstack. NULL if this is a root.
+  parent_id LONG,
+  -- The function name of the frame.
+  name STRING,
+  -- The name of the mapping containing the frame.
+  mapping_name STRING,
+  -- The number of samples with this callstack as the leaf in the baseline
+  -- trace.
+  baseline_self_count LONG,
+  -- The number of samples with this callstack as the leaf in the candidate
+  -- trace.
+  candidate_self_count LONG,
+  -- The number of samples with this callstack as a prefix in the baseline
+  -- trace.
+  baseline_cumulative_count LONG,
+  -- The number of samples with this callstack as a prefix in the candidate
+  -- trace.
+  candidate_cumulative_count LONG,
+  -- Difference of the cumulative counts (candidate - baseline).
+  delta_cumulative_count LONG,
+  -- Fraction of the samples of the baseline trace containing this callstack.
+  baseline_cumulative_fraction DOUBLE,
+  -- Fraction of the samples of the candidate trace containing this callstack.
+  candidate_cumulative_fraction DOUBLE,
+  -- The p-value of the two-proportion z-test for the difference of the
+  -- cumulative fractions. NULL if there are not enough samples.
+  p_value DOUBLE,
+  -- Whether the difference of the cumulative fractions is statistically
+  -- significant.
+  is_significant BOOL
+) AS
+WITH
+  totals AS (
+    SELECT
+      (
+        SELECT
+          coalesce(sum(self_count), 0)
+        FROM baseline.trace_diff_cpu_profile_tree
+      ) AS baseline_total,
+      (
+        SELECT
+          coalesce(sum(self_count), 0)
+        FROM trace_diff_cpu_profile_tree
+      ) AS candidate_total
+  ),
+  nodes AS (
+    SELECT
+      id,
+      parent_id,
+      name,
+      mapping_name
+    FROM trace_diff_cpu_profile_tree
+    UNION
+    SELECT
+      id,
+      parent_id,
+      name,
+      mapping_name
+    FROM baseline.trace_diff_cpu_profile_tree
+  ),
+  joined AS (
+    SELECT
+      n.id,
+      n.parent_id,
+      n.name,
+      n.mapping_name,
+      coalesce(b.self_count, 0) AS baseline_self_count,
+      coalesce(c.self_count, 0) AS candidate_self_count,
+      coalesce(b.cumulative_count, 0) AS baseline_cumulative_count,
+      coalesce(c.cumulative_count, 0) AS candidate_cumulative_count,
+      t.baseline_total,
+      t.candidate_total
+    FROM nodes AS n
+    CROSS JOIN totals AS t
+    LEFT JOIN baseline.trace_diff_cpu_profile_tree AS b
+      ON n.id = b.id
+    LEFT JOIN trace_diff_cpu_profile_tree AS c
+      ON n.id = c.id
+  ),
+  with_p_value AS (
+    SELECT
+      *,
+      _trace_diff_proportion_p_value(
+        baseline_cumulative_count, baseline_total,
+        candidate_cumulative_count, candidate_total
+      ) AS p_value
+    FROM joined
+  )
+SELECT
+  id,
+  parent_id,
+  name,
+  mapping_name,
+  baseline_self_count,
+  candidate_self_count,
+  baseline_cumulative_count,
+  candidate_cumulative_count,
+  candidate_cumulative_count - baseline_cumulative_count
+    AS delta_cumulative_count,
+  iif(
+    baseline_total > 0,
+    baseline_cumulative_count * 1.0 / baseline_total,
+    NULL
+  ) AS baseline_cumulative_fraction,
+  iif(
+    candidate_total > 0,
+    candidate_cumulative_count * 1.0 / candidate_total,
+    NULL
+  ) AS candidate_cumulative_fraction,
+  p_value,
+  coalesce(p_value < 0.05, FALSE) AS is_significant
+FROM with_p_value
+ORDER BY
+  id;
@@ -0,0 +1,217 @@
+--
+-- Copyright 2025 The Android Open Source Project
+--
+-- Licensed under the Apache License, Version 2.0 (the 'License');
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     https://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an 'AS IS' BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+-- Per-trace statistics which are compared by the `trace_diff.compare` module.
+--
+-- The tables in this module only depend on the trace they are computed on:
+-- when diffing two traces, they are computed on both of them and the ones of
+-- the baseline trace are made available in the `baseline` schema (see
+-- `trace_processor_shell --diff-baseline`).
+--
+-- As the ids (e.g. utid, upid) are not stable across traces, all the entities
+-- are keyed by their names instead.
+
+INCLUDE PERFETTO MODULE stacks.cpu_profiling;
+
+-- Returns the unbiased sample variance of a set of values given their count,
+-- sum and sum of squares. NULL if there are less than two values.
+CREATE PERFETTO FUNCTION _trace_diff_variance(
+    count LONG,
+    sum DOUBLE,
+    sum_sq DOUBLE
+)
+RETURNS DOUBLE AS
+SELECT
+  iif(
+    $count > 1,
+    max(0.0, ($sum_sq - $sum * $sum / $count) / ($count - 1)),
+    NULL
+  );
+
+-- Duration statistics of the slices in the trace, aggregated by slice name,
+-- thread name and process name.
+--
+-- Slices on tracks which are not associated with a thread or a process
+-- (e.g. global async slices) have NULL thread and process names. Incomplete
+-- slices are ignored.
+CREATE PERFETTO TABLE trace_diff_slice_stats (
+  -- Name of the slices.
+  name STRING,
+  -- Name of the thread of the slices, if they are on a thread track.
+  thread_name STRING,
+  -- Name of the process of the slices, if they are on a thread or process
+  -- track.
+  process_name STRING,
+  -- Number of slices.
+  count LONG,
+  -- Sum of the durations of the slices.
+  total_dur DURATION,
+  -- Mean of the durations of the slices.
+  mean_dur DOUBLE,
+  -- Sample variance of the durations of the slices. NULL if there is a single
+  -- slice.
+  variance_dur DOUBLE
+) AS
+WITH
+  slices AS (
+    SELECT
+      s.name,
+      t.name AS thread_name,
+      p.name AS process_name,
+      s.dur
+    FROM slice AS s
+    LEFT JOIN thread_track AS tt
+      ON s.track_id = tt.id
+    LEFT JOIN process_track AS pt
+      ON s.track_id = pt.id
+    LEFT JOIN thread AS t
+      ON tt.utid = t.utid
+    LEFT JOIN process AS p
+      ON p.upid = coalesce(t.upid, pt.upid)
+    WHERE
+      s.dur >= 0
+  )
+SELECT
+  name,
+  thread_name,
+  process_name,
+  count() AS count,
+  sum(dur) AS total_dur,
+  avg(dur) AS mean_dur,
+  _trace_diff_variance(count(), sum(dur), sum(dur * 1.0 * dur)) AS variance_dur
+FROM slices
+GROUP BY
+  name,
+  thread_name,
+  process_name;
+
+-- Duration statistics of the thread states in the trace, aggregated by process
+-- name, thread name and state.
+CREATE PERFETTO TABLE trace_diff_thread_state_stats (
+  -- Name of the process of the thread.
+  process_name STRING,
+  -- Name of the thread.
+  thread_name STRING,
+  -- The scheduling state of the thread (see `thread_state.state`).
+  state STRING,
+  -- Number of thread state intervals.
+  count LONG,
+  -- Sum of the durations of the intervals.
+  total_dur DURATION,
+  -- Mean of the durations of the intervals.
+  mean_dur DOUBLE,
+  -- Sample variance of the durations of the intervals. NULL if there is a
+  -- single interval.
+  variance_dur DOUBLE
+) AS
+SELECT
+  p.name AS process_name,
+  t.name AS thread_name,
+  ts.state,
+  count() AS count,
+  sum(ts.dur) AS total_dur,
+  avg(ts.dur) AS mean_dur,
+  _trace_diff_variance(count(), sum(ts.dur), sum(ts.dur * 1.0 * ts.dur))
+    AS variance_dur
+FROM thread_state AS ts
+JOIN thread AS t
+  USING (utid)
+LEFT JOIN process AS p
+  USING (upid)
+WHERE
+  ts.dur >= 0
+GROUP BY
+  process_name,
+  thread_name,
+  ts.state;
+
+-- CPU time of the processes in the trace, aggregated by process name.
+--
+-- The CPU time of threads without an associated process is reported with a
+-- NULL process name.
+CREATE PERFETTO TABLE trace_diff_process_cpu_stats (
+  -- Name of the process.
+  process_name STRING,
+  -- Number of sched slices of the threads of the process.
+  sched_count LONG,
+  -- Total time the threads of the process spent running on a CPU.
+  cpu_dur DURATION
+) AS
+SELECT
+  p.name AS process_name,
+  count() AS sched_count,
+  sum(s.dur) AS cpu_dur
+FROM sched AS s
+JOIN thread AS t
+  USING (utid)
+LEFT JOIN process AS p
+  USING (upid)
+WHERE
+  NOT t.is_idle AND s.dur >= 0
+GROUP BY
+  process_name;
+
+-- The callstacks of the CPU profiling samples in the trace (see
+-- `cpu_profiling_summary_tree`), keyed by the path of frames from the root.
+--
+-- `id` and `parent_id` are hashes of the path of (function name, mapping name)
+-- pairs leading to the frame: they are the same for the same callstack in
+-- different traces.
+CREATE PERFETTO TABLE trace_diff_cpu_profile_tree (
+  -- The id of the callstack.
+  id LONG,
+  -- The id of the parent callstack. NULL if this is a root.
+  parent_id LONG,
+  -- The function name of the frame.
+  name STRING,
+  -- The name of the mapping containing the frame.
+  mapping_name STRING,
+  -- The number of samples with this callstack as the leaf.
+  self_count LONG,
+  -- The number of samples with this callstack as a prefix.
+  cumulative_count LONG
+) AS
+WITH RECURSIVE
+  paths(tree_id, id, parent_id) AS (
+    SELECT
+      t.id AS tree_id,
+      hash(coalesce(t.name, ''), coalesce(t.mapping_name, '')) AS id,
+      NULL AS parent_id
+    FROM cpu_profiling_summary_tree AS t
+    WHERE
+      t.parent_id IS NULL
+    UNION ALL
+    SELECT
+      t.id AS tree_id,
+      hash(p.id, coalesce(t.name, ''), coalesce(t.mapping_name, '')) AS id,
+      p.id AS parent_id
+    FROM paths AS p
+    JOIN cpu_profiling_summary_tree AS t
+      ON t.parent_id = p.tree_id
+  )
+SELECT
+  p.id,
+  p.parent_id,
+  t.name,
+  t.mapping_name,
+  sum(t.self_count) AS self_count,
+  sum(t.cumulative_count) AS cumulative_count
+FROM paths AS p
+JOIN cpu_profiling_summary_tree AS t
+  ON p.tree_id = t.id
+GROUP BY
+  p.id
+ORDER BY
+  p.id;
@@ -0,0 +1,843 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/perfetto_sql/tooling/lsp_server.h"
+
+#include <algorithm>
+#include <cctype>
+#include <cstddef>
+#include <cstdint>
+#include <cstdio>
+#include <memory>
+#include <optional>
+#include <set>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "perfetto/base/build_config.h"
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/file_utils.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "perfetto/ext/base/utils.h"
+#include "perfetto/trace_processor/trace_processor.h"
+#include "src/trace_processor/perfetto_sql/grammar/perfettosql_grammar.h"
+#include "src/trace_processor/perfetto_sql/tokenizer/sqlite_tokenizer.h"
+#include "src/trace_processor/perfetto_sql/tooling/sql_module_index.h"
+#include "src/trace_processor/sqlite/sql_source.h"
+
+#include <json/reader.h>
+#include <json/value.h>
+#include <json/writer.h>
+
+#if !PERFETTO_BUILDFLAG(PERFETTO_OS_WIN)
+#include <unistd.h>
+#endif
+
+#if PERFETTO_BUILDFLAG(PERFETTO_OS_WIN) && !defined(STDIN_FILENO)
+#define STDIN_FILENO 0
+#define STDOUT_FILENO 1
+#endif
+
+namespace perfetto::trace_processor {
+namespace {
+
+// JSON-RPC error codes.
+constexpr int kParseError = -32700;
+constexpr int kInvalidRequest = -32600;
+constexpr int kMethodNotFound = -32601;
+constexpr int kInvalidParams = -32602;
+
+// Values of the LSP enums.
+constexpr int kTextDocumentSyncFull = 1;
+constexpr int kDiagnosticSeverityError = 1;
+constexpr int kCompletionItemKindFunction = 3;
+constexpr int kCompletionItemKindField = 5;
+constexpr int kCompletionItemKindModule = 9;
+constexpr int kCompletionItemKindStruct = 22;
+
+constexpr char kIncludePrefix[] = "INCLUDE PERFETTO MODULE ";
+constexpr char kFileUriPrefix[] = "file://";
+
+std::string ToJson(const Json::Value& value) {
+  Json::StreamWriterBuilder b;
+  b.settings_["indentation"] = "";
+  return Json::writeString(b, value);
+}
+
+Json::Value Position(uint32_t line, uint32_t character) {
+  Json::Value position(Json::objectValue);
+  position["line"] = line;
+  position["character"] = character;
+  return position;
+}
+
+Json::Value Range(const SqlPosition& start, size_t length) {
+  Json::Value range(Json::objectValue);
+  range["start"] = Position(start.line, start.col);
+  range["end"] =
+      Position(start.line, start.col + static_cast<uint32_t>(length));
+  return range;
+}
+
+Json::Value Location(const std::string& uri,
+                     const SqlPosition& start,
+                     size_t length) {
+  Json::Value location(Json::objectValue);
+  location["uri"] = uri;
+  location["range"] = Range(start, length);
+  return location;
+}
+
+bool IsIdentifierChar(char c) {
+  return isalnum(static_cast<unsigned char>(c)) || c == '_';
+}
+
+bool IsIdentifier(const std::string& str) {
+  return !str.empty() &&
+         std::all_of(str.begin(), str.end(), &IsIdentifierChar);
+}
+
+bool CaseInsensitiveStartsWith(const std::string& str,
+                               const std::string& prefix) {
+  return base::StartsWith(base::ToLower(str), base::ToLower(prefix));
+}
+
+// Returns the offset of |line|:|character| in |text|, clamped to the end of
+// the line.
+std::optional<size_t> ToOffset(const std::string& text,
+                               uint32_t line,
+                               uint32_t character) {
+  size_t begin = 0;
+  for (uint32_t i = 0; i < line; ++i) {
+    begin = text.find('\n', begin);
+    if (begin == std::string::npos) {
+      return std::nullopt;
+    }
+    ++begin;
+  }
+  size_t end = std::min(text.find('\n', begin), text.size());
+  return std::min(begin + character, end);
+}
+
+// Returns the bounds of the identifier containing |offset| in |text|.
+std::pair<size_t, size_t> IdentifierAt(const std::string& text,
+                                       size_t offset) {
+  size_t begin = offset;
+  while (begin > 0 && IsIdentifierChar(text[begin - 1])) {
+    --begin;
+  }
+  size_t end = offset;
+  while (end < text.size() && IsIdentifierChar(text[end])) {
+    ++end;
+  }
+  return {begin, end};
+}
+
+std::string UriToPath(const std::string& uri) {
+  if (!base::StartsWith(uri, kFileUriPrefix)) {
+    return "";
+  }
+  std::string path;
+  for (size_t i = sizeof(kFileUriPrefix) - 1; i < uri.size(); ++i) {
+    if (uri[i] == '%' && i + 2 < uri.size()) {
+      if (auto c = base::StringToUInt32(uri.substr(i + 1, 2), 16); c) {
+        path.push_back(static_cast<char>(*c));
+        i += 2;
+        continue;
+      }
+    }
+    path.push_back(uri[i]);
+  }
+  return path;
+}
+
+std::string PathToUri(const std::string& path) {
+  std::string uri = kFileUriPrefix;
+#if !PERFETTO_BUILDFLAG(PERFETTO_OS_WIN)
+  if (!path.empty() && path[0] != '/') {
+    char cwd[4096];
+    if (getcwd(cwd, sizeof(cwd))) {
+      uri += std::string(cwd) + "/";
+    }
+  }
+#endif
+  for (char c : path) {
+    if (IsIdentifierChar(c) || c == '/' || c == '.' || c == '-') {
+      uri.push_back(c);
+    } else {
+      char escaped[4];
+      snprintf(escaped, sizeof(escaped), "%%%02X", static_cast<uint8_t>(c));
+      uri += escaped;
+    }
+  }
+  return uri;
+}
+
+const char* KindName(SqlSymbol::Kind kind) {
+  switch (kind) {
+    case SqlSymbol::Kind::kTable:
+      return "TABLE";
+    case SqlSymbol::Kind::kView:
+      return "VIEW";
+    case SqlSymbol::Kind::kFunction:
+    case SqlSymbol::Kind::kTableFunction:
+      return "FUNCTION";
+    case SqlSymbol::Kind::kMacro:
+      return "MACRO";
+  }
+  PERFETTO_FATAL("For GCC");
+}
+
+std::string FieldList(const std::vector<SqlSymbol::Field>& fields) {
+  std::vector<std::string> parts;
+  for (const auto& field : fields) {
+    parts.push_back(field.name + " " + field.type);
+  }
+  return base::Join(parts, ", ");
+}
+
+std::string Signature(const SqlSymbol& symbol) {
+  std::string signature = std::string(KindName(symbol.kind)) + " " +
+                          symbol.name;
+  switch (symbol.kind) {
+    case SqlSymbol::Kind::kTable:
+    case SqlSymbol::Kind::kView:
+      break;
+    case SqlSymbol::Kind::kFunction:
+    case SqlSymbol::Kind::kMacro:
+      signature += "(" + FieldList(symbol.args) + ")";
+      signature += " RETURNS " + symbol.return_type;
+      break;
+    case SqlSymbol::Kind::kTableFunction:
+      signature += "(" + FieldList(symbol.args) + ") RETURNS TABLE";
+      break;
+  }
+  return signature;
+}
+
+void AppendFields(const char* title,
+                  const std::vector<SqlSymbol::Field>& fields,
+                  std::string* markdown) {
+  if (fields.empty()) {
+    return;
+  }
+  *markdown += "\n**" + std::string(title) + "**\n";
+  for (const auto& field : fields) {
+    *markdown += "- `" + field.name + "` `" + field.type + "`";
+    if (!field.description.empty()) {
+      *markdown += ": " + field.description;
+    }
+    *markdown += "\n";
+  }
+}
+
+std::string SymbolMarkdown(const SqlSymbol& symbol,
+                           const std::string& module_key) {
+  std::string markdown = "```sql\n" + Signature(symbol) + "\n```\n";
+  if (!symbol.description.empty()) {
+    markdown += "\n" + symbol.description + "\n";
+  }
+  AppendFields("Arguments", symbol.args, &markdown);
+  AppendFields("Columns", symbol.columns, &markdown);
+  if (!symbol.return_description.empty()) {
+    markdown += "\n**Returns** `" + symbol.return_type +
+                "`: " + symbol.return_description + "\n";
+  }
+  if (!module_key.empty()) {
+    markdown += "\nDefined in module `" + module_key + "`.\n";
+  }
+  return markdown;
+}
+
+Json::Value CompletionItem(const SqlSymbol& symbol,
+                           const std::string& module_key) {
+  Json::Value item(Json::objectValue);
+  item["label"] = symbol.name;
+  switch (symbol.kind) {
+    case SqlSymbol::Kind::kTable:
+    case SqlSymbol::Kind::kView:
+      item["kind"] = kCompletionItemKindStruct;
+      break;
+    case SqlSymbol::Kind::kFunction:
+    case SqlSymbol::Kind::kTableFunction:
+      item["kind"] = kCompletionItemKindFunction;
+      break;
+    case SqlSymbol::Kind::kMacro:
+      item["kind"] = kCompletionItemKindFunction;
+      item["insertText"] = symbol.name + "!";
+      break;
+  }
+  item["detail"] = Signature(symbol);
+  Json::Value documentation(Json::objectValue);
+  documentation["kind"] = "markdown";
+  documentation["value"] = SymbolMarkdown(symbol, module_key);
+  item["documentation"] = documentation;
+  return item;
+}
+
+// Returns the table aliased as |alias| in a FROM or JOIN clause of |text|, if
+// any.
+std::string ResolveAlias(const std::string& text, const std::string& alias) {
+  SqliteTokenizer tokenizer(SqlSource::FromTraceProcessorImplementation(text));
+  std::vector<SqliteTokenizer::Token> tokens;
+  for (auto t = tokenizer.NextNonWhitespace(); !t.str.empty();
+       t = tokenizer.NextNonWhitespace()) {
+    tokens.push_back(t);
+  }
+  for (size_t i = 0; i + 2 < tokens.size(); ++i) {
+    if (tokens[i].token_type != TK_FROM && tokens[i].token_type != TK_JOIN) {
+      continue;
+    }
+    if (tokens[i + 1].token_type != TK_ID) {
+      continue;
+    }
+    size_t alias_idx = tokens[i + 2].token_type == TK_AS ? i + 3 : i + 2;
+    if (alias_idx < tokens.size() && tokens[alias_idx].token_type == TK_ID &&
+        base::CaseInsensitiveEqual(std::string(tokens[alias_idx].str),
+                                   alias)) {
+      return std::string(tokens[i + 1].str);
+    }
+  }
+  return "";
+}
+
+Json::Value Notification(const std::string& method, Json::Value params) {
+  Json::Value notification(Json::objectValue);
+  notification["jsonrpc"] = "2.0";
+  notification["method"] = method;
+  notification["params"] = std::move(params);
+  return notification;
+}
+
+// jsoncpp is built without exceptions, so reading a value with the wrong
+// type aborts: the helpers below check the type first.
+
+// Returns |object|[|key|], or null if |object| is not an object.
+const Json::Value& Member(const Json::Value& object, const char* key) {
+  return object.isObject() ? object[key] : Json::Value::nullSingleton();
+}
+
+std::optional<std::string> GetString(const Json::Value& object,
+                                     const char* key) {
+  const Json::Value& value = Member(object, key);
+  if (!value.isString()) {
+    return std::nullopt;
+  }
+  return value.asString();
+}
+
+std::optional<uint32_t> GetUInt(const Json::Value& object, const char* key) {
+  const Json::Value& value = Member(object, key);
+  if (!value.isUInt()) {
+    return std::nullopt;
+  }
+  return value.asUInt();
+}
+
+Json::Value ErrorResponse(const Json::Value& id,
+                          int code,
+                          const std::string& message) {
+  Json::Value response(Json::objectValue);
+  response["jsonrpc"] = "2.0";
+  response["id"] = id;
+  response["error"]["code"] = code;
+  response["error"]["message"] = message;
+  return response;
+}
+
+Json::Value Capabilities() {
+  Json::Value result(Json::objectValue);
+  Json::Value& capabilities = result["capabilities"];
+  capabilities["textDocumentSync"] = kTextDocumentSyncFull;
+  capabilities["completionProvider"]["triggerCharacters"].append(".");
+  capabilities["hoverProvider"] = true;
+  capabilities["definitionProvider"] = true;
+  result["serverInfo"]["name"] = "trace_processor_shell";
+  return result;
+}
+
+}  // namespace
+
+LspServer::LspServer(SqlModuleIndex* index, TraceProcessor* tp)
+    : index_(index), tp_(tp) {}
+
+LspServer::~LspServer() = default;
+
+std::vector<std::string> LspServer::HandleMessage(const std::string& message) {
+  std::vector<std::string> out;
+
+  Json::CharReaderBuilder b;
+  std::unique_ptr<Json::CharReader> reader(b.newCharReader());
+  Json::Value parsed;
+  if (!reader->parse(message.data(), message.data() + message.size(),
+                     &parsed, nullptr) ||
+      !parsed.isObject()) {
+    out.push_back(ToJson(ErrorResponse(Json::Value(), kParseError,
+                                       "Failed to parse message")));
+    return out;
+  }
+  const Json::Value& msg = parsed;
+  // Responses to requests from the server: there are none.
+  if (!msg.isMember("method")) {
+    return out;
+  }
+  const bool is_request = msg.isMember("id");
+  if (!msg["method"].isString()) {
+    if (is_request) {
+      out.push_back(ToJson(
+          ErrorResponse(msg["id"], kInvalidRequest, "Invalid method")));
+    }
+    return out;
+  }
+  const std::string method = msg["method"].asString();
+  const Json::Value& params = msg["params"];
+  const std::optional<std::string> uri =
+      GetString(Member(params, "textDocument"), "uri");
+
+  if (shutdown_ && is_request) {
+    out.push_back(ToJson(ErrorResponse(msg["id"], kInvalidRequest,
+                                       "Server is shutting down")));
+    return out;
+  }
+
+  // The parameters of the requests about a position in a document.
+  auto position_params = [&]() -> std::optional<PositionParams> {
+    const Json::Value& position = Member(params, "position");
+    std::optional<uint32_t> line = GetUInt(position, "line");
+    std::optional<uint32_t> character = GetUInt(position, "character");
+    if (!uri || !line || !character) {
+      return std::nullopt;
+    }
+    return PositionParams{*uri, *line, *character};
+  };
+
+  // Notifications with invalid params are ignored as they can't be replied
+  // to.
+  bool invalid_params = false;
+  Json::Value result;
+  if (method == "initialize") {
+    result = Capabilities();
+  } else if (method == "shutdown") {
+    shutdown_ = true;
+  } else if (method == "exit") {
+    exited_ = true;
+  } else if (method == "textDocument/didOpen") {
+    std::optional<std::string> text =
+        GetString(Member(params, "textDocument"), "text");
+    if (uri && text) {
+      UpdateDocument(*uri, std::move(*text));
+      out.push_back(ToJson(Diagnostics(*uri)));
+    }
+  } else if (method == "textDocument/didChange") {
+    // With full synchronization, the last change contains the whole text.
+    const Json::Value& changes = Member(params, "contentChanges");
+    std::optional<std::string> text;
+    if (changes.isArray() && !changes.empty()) {
+      text = GetString(changes[changes.size() - 1], "text");
+    }
+    if (uri && text) {
+      UpdateDocument(*uri, std::move(*text));
+      out.push_back(ToJson(Diagnostics(*uri)));
+    }
+  } else if (method == "textDocument/didClose") {
+    if (uri) {
+      documents_.erase(*uri);
+      out.push_back(ToJson(Diagnostics(*uri)));
+    }
+  } else if (method == "textDocument/completion" ||
+             method == "textDocument/hover" ||
+             method == "textDocument/definition") {
+    std::optional<PositionParams> position = position_params();
+    if (!position) {
+      invalid_params = true;
+    } else if (method == "textDocument/completion") {
+      result = Completion(*position);
+    } else if (method == "textDocument/hover") {
+      result = Hover(*position);
+    } else {
+      result = Definition(*position);
+    }
+  } else if (is_request) {
+    out.push_back(ToJson(ErrorResponse(msg["id"], kMethodNotFound,
+                                       "Unsupported method " + method)));
+    return out;
+  }
+
+  if (invalid_params && is_request) {
+    out.push_back(ToJson(
+        ErrorResponse(msg["id"], kInvalidParams, "Invalid params")));
+    return out;
+  }
+  if (is_request) {
+    Json::Value response(Json::objectValue);
+    response["jsonrpc"] = "2.0";
+    response["id"] = msg["id"];
+    response["result"] = std::move(result);
+    out.push_back(ToJson(response));
+  }
+  return out;
+}
+
+void LspServer::UpdateDocument(const std::string& uri, std::string text) {
+  Document& doc = documents_[uri];
+  if (doc.key.empty()) {
+    doc.key = index_->FindModuleByPath(UriToPath(uri)).value_or(uri);
+  }
+  doc.text = std::move(text);
+  doc.info = index_->ParseModule(doc.key, doc.text);
+}
+
+Json::Value LspServer::Diagnostics(const std::string& uri) {
+  Json::Value params(Json::objectValue);
+  params["uri"] = uri;
+  Json::Value& diagnostics = params["diagnostics"] =
+      Json::Value(Json::arrayValue);
+  auto it = documents_.find(uri);
+  if (it != documents_.end()) {
+    const SqlModuleInfo& info = it->second.info;
+    auto add = [&](const SqlPosition& position, size_t length,
+                   const std::string& message) {
+      Json::Value diagnostic(Json::objectValue);
+      diagnostic["range"] = Range(position, length);
+      diagnostic["severity"] = kDiagnosticSeverityError;
+      diagnostic["source"] = "perfettosql";
+      diagnostic["message"] = message;
+      diagnostics.append(diagnostic);
+    };
+    for (const auto& include : info.includes) {
+      if (index_->MatchModules(include.key).empty()) {
+        add(include.position, include.key.size(),
+            "INCLUDE: unknown module '" + include.key + "'");
+      }
+    }
+    if (!info.status.ok()) {
+      add(info.error_position, 1, GetSqlErrorMessage(info.status));
+    }
+  }
+  return Notification("textDocument/publishDiagnostics", std::move(params));
+}
+
+Json::Value LspServer::Completion(const PositionParams& params) {
+  Json::Value items(Json::arrayValue);
+  Document* doc = FindDocument(params.uri);
+  if (!doc) {
+    return items;
+  }
+  const std::string& text = doc->text;
+  auto offset = ToOffset(text, params.line, params.character);
+  if (!offset) {
+    return items;
+  }
+
+  // Module names.
+  size_t line_begin = *offset == 0 ? 0 : text.rfind('\n', *offset - 1);
+  line_begin = line_begin == std::string::npos || *offset == 0
+                   ? 0
+                   : line_begin + 1;
+  std::string line = base::TrimWhitespace(
+      text.substr(line_begin, *offset - line_begin));
+  if (base::StartsWith(base::ToUpper(line), kIncludePrefix)) {
+    std::string partial = line.substr(sizeof(kIncludePrefix) - 1);
+    for (const std::string& key : index_->ModuleKeys()) {
+      if (base::StartsWith(key, partial)) {
+        Json::Value item(Json::objectValue);
+        item["label"] = key;
+        item["kind"] = kCompletionItemKindModule;
+        items.append(item);
+      }
+    }
+    return items;
+  }
+
+  auto [word_begin, word_end] = IdentifierAt(text, *offset);
+  std::string word = text.substr(word_begin, *offset - word_begin);
+
+  // Columns of "<table or alias>.".
+  if (word_begin > 0 && text[word_begin - 1] == '.') {
+    auto [qualifier_begin, qualifier_end] =
+        IdentifierAt(text, word_begin - 1);
+    std::string qualifier =
+        text.substr(qualifier_begin, word_begin - 1 - qualifier_begin);
+    for (const auto& column : Columns(qualifier, *doc)) {
+      if (!CaseInsensitiveStartsWith(column.name, word)) {
+        continue;
+      }
+      Json::Value item(Json::objectValue);
+      item["label"] = column.name;
+      item["kind"] = kCompletionItemKindField;
+      item["detail"] = column.type;
+      if (!column.description.empty()) {
+        item["documentation"] = column.description;
+      }
+      items.append(item);
+    }
+    return items;
+  }
+
+  // Tables, functions and macros visible from the document.
+  std::set<std::string> seen;
+  auto add_symbols = [&](const SqlModuleInfo& module, bool is_document) {
+    for (const SqlSymbol& symbol : module.symbols) {
+      if (CaseInsensitiveStartsWith(symbol.name, word) &&
+          seen.insert(symbol.name).second) {
+        items.append(CompletionItem(symbol, is_document ? "" : module.key));
+      }
+    }
+  };
+  add_symbols(doc->info, true);
+  for (const SqlModuleInfo* module : index_->VisibleModules(doc->info)) {
+    add_symbols(*module, false);
+  }
+  for (const std::string& table : LiveTables()) {
+    if (CaseInsensitiveStartsWith(table, word) && seen.insert(table).second) {
+      Json::Value item(Json::objectValue);
+      item["label"] = table;
+      item["kind"] = kCompletionItemKindStruct;
+      item["detail"] = "TABLE " + table;
+      items.append(item);
+    }
+  }
+  return items;
+}
+
+Json::Value LspServer::Hover(const PositionParams& params) {
+  Document* doc = FindDocument(params.uri);
+  if (!doc) {
+    return Json::Value();
+  }
+  const std::string& text = doc->text;
+  auto offset = ToOffset(text, params.line, params.character);
+  if (!offset) {
+    return Json::Value();
+  }
+  auto [begin, end] = IdentifierAt(text, *offset);
+  if (begin == end) {
+    return Json::Value();
+  }
+  std::string name = text.substr(begin, end - begin);
+
+  std::string markdown;
+  const SqlSymbol* symbol = nullptr;
+  const SqlModuleInfo* module = index_->FindSymbol(name, &doc->info, &symbol);
+  if (module) {
+    markdown = SymbolMarkdown(*symbol, module == &doc->info ? "" : module->key);
+  } else if (auto columns = LiveColumns(name, nullptr); !columns.empty()) {
+    markdown = "```sql\nTABLE " + name + "\n```\n";
+    AppendFields("Columns", columns, &markdown);
+  }
+
+  // Preview of the expansion of macro invocations.
+  if (end < text.size() && text[end] == '!') {
+    for (const auto& stmt : doc->info.statements) {
+      if (stmt.begin <= begin && end <= stmt.end && stmt.expanded_sql) {
+        markdown += "\n**Expanded statement**\n```sql\n" +
+                    *stmt.expanded_sql + "\n```\n";
+        break;
+      }
+    }
+  }
+  if (markdown.empty()) {
+    return Json::Value();
+  }
+
+  Json::Value hover(Json::objectValue);
+  hover["contents"]["kind"] = "markdown";
+  hover["contents"]["value"] = markdown;
+  uint32_t col = params.character - static_cast<uint32_t>(*offset - begin);
+  hover["range"] = Range(SqlPosition{params.line, col}, end - begin);
+  return hover;
+}
+
+Json::Value LspServer::Definition(const PositionParams& params) {
+  Document* doc = FindDocument(params.uri);
+  if (!doc) {
+    return Json::Value();
+  }
+  uint32_t line = params.line;
+  uint32_t character = params.character;
+  auto offset = ToOffset(doc->text, line, character);
+  if (!offset) {
+    return Json::Value();
+  }
+
+  // Included modules.
+  for (const auto& include : doc->info.includes) {
+    if (include.position.line != line || character < include.position.col ||
+        character > include.position.col + include.key.size()) {
+      continue;
+    }
+    const SqlModuleInfo* module = index_->GetModule(include.key);
+    if (!module || module->path.empty()) {
+      return Json::Value();
+    }
+    return Location(PathToUri(module->path), SqlPosition{}, 0);
+  }
+
+  auto [begin, end] = IdentifierAt(doc->text, *offset);
+  if (begin == end) {
+    return Json::Value();
+  }
+  const SqlSymbol* symbol = nullptr;
+  const SqlModuleInfo* module = index_->FindSymbol(
+      doc->text.substr(begin, end - begin), &doc->info, &symbol);
+  if (!module) {
+    return Json::Value();
+  }
+  if (module == &doc->info) {
+    return Location(params.uri, symbol->position, symbol->name.size());
+  }
+  if (module->path.empty()) {
+    return Json::Value();
+  }
+  return Location(PathToUri(module->path), symbol->position,
+                  symbol->name.size());
+}
+
+LspServer::Document* LspServer::FindDocument(const std::string& uri) {
+  auto it = documents_.find(uri);
+  return it == documents_.end() ? nullptr : &it->second;
+}
+
+std::vector<SqlSymbol::Field> LspServer::Columns(const std::string& name,
+                                                 const Document& doc) {
+  std::string table = name;
+  const SqlSymbol* symbol = nullptr;
+  const SqlModuleInfo* module = index_->FindSymbol(table, &doc.info, &symbol);
+  if (!module) {
+    std::string aliased = ResolveAlias(doc.text, name);
+    if (!aliased.empty()) {
+      table = aliased;
+      module = index_->FindSymbol(table, &doc.info, &symbol);
+    }
+  }
+  if (module && !symbol->columns.empty()) {
+    return symbol->columns;
+  }
+  return LiveColumns(table, module == &doc.info ? nullptr : module);
+}
+
+std::vector<SqlSymbol::Field> LspServer::LiveColumns(
+    const std::string& table,
+    const SqlModuleInfo* module) {
+  std::vector<SqlSymbol::Field> columns;
+  if (!tp_ || !IsIdentifier(table)) {
+    return columns;
+  }
+  auto query = [&] {
+    auto it = tp_->ExecuteQuery("SELECT name, type FROM pragma_table_info('" +
+                                table + "')");
+    while (it.Next()) {
+      SqlSymbol::Field field;
+      field.name = it.Get(0).AsString();
+      if (!it.Get(1).is_null()) {
+        field.type = it.Get(1).AsString();
+      }
+      columns.push_back(std::move(field));
+    }
+  };
+  query();
+  // The table might be defined by a module which was not included yet.
+  if (columns.empty() && module) {
+    auto it = tp_->ExecuteQuery("INCLUDE PERFETTO MODULE " + module->key);
+    while (it.Next()) {
+    }
+    if (it.Status().ok()) {
+      query();
+    }
+  }
+  return columns;
+}
+
+const std::vector<std::string>& LspServer::LiveTables() {
+  if (!live_tables_) {
+    live_tables_.emplace();
+    if (tp_) {
+      auto it = tp_->ExecuteQuery(R"(
+        SELECT name FROM perfetto_tables
+        UNION ALL
+        SELECT name FROM sqlite_master WHERE type = 'view'
+      )");
+      while (it.Next()) {
+        live_tables_->push_back(it.Get(0).AsString());
+      }
+    }
+  }
+  return *live_tables_;
+}
+
+base::Status RunLspStdioServer(LspServer* server) {
+  std::string buffer;
+  char chunk[4096];
+  auto read_more = [&]() {
+    ssize_t ret = base::Read(STDIN_FILENO, chunk, base::ArraySize(chunk));
+    if (ret > 0) {
+      buffer.append(chunk, static_cast<size_t>(ret));
+    }
+    return ret;
+  };
+  for (;;) {
+    size_t header_end = buffer.find("\r\n\r\n");
+    if (header_end == std::string::npos) {
+      ssize_t ret = read_more();
+      if (ret < 0) {
+        return base::ErrStatus("Failed to read from stdin");
+      }
+      if (ret == 0) {
+        return base::OkStatus();
+      }
+      continue;
+    }
+    std::optional<uint64_t> length;
+    for (const std::string& header :
+         base::SplitString(buffer.substr(0, header_end), "\r\n")) {
+      size_t colon = header.find(':');
+      if (colon != std::string::npos &&
+          base::CaseInsensitiveEqual(header.substr(0, colon),
+                                     "content-length")) {
+        length = base::StringToUInt64(
+            base::TrimWhitespace(header.substr(colon + 1)));
+      }
+    }
+    if (!length) {
+      return base::ErrStatus("Missing Content-Length header");
+    }
+    size_t body_begin = header_end + 4;
+    while (buffer.size() < body_begin + *length) {
+      if (read_more() <= 0) {
+        return base::ErrStatus("Unexpected end of input");
+      }
+    }
+    std::string message = buffer.substr(body_begin, *length);
+    buffer.erase(0, body_begin + *length);
+
+    for (const std::string& reply : server->HandleMessage(message)) {
+      std::string framed = "Content-Length: " + std::to_string(reply.size()) +
+                           "\r\n\r\n" + reply;
+      ssize_t ret = base::WriteAll(STDOUT_FILENO, framed.data(), framed.size());
+      if (ret < 0 || static_cast<size_t>(ret) != framed.size()) {
+        return base::ErrStatus("Failed to write to stdout");
+      }
+    }
+    if (server->exited()) {
+      return server->shutdown()
+                 ? base::OkStatus()
+                 : base::ErrStatus("Exit notification before shutdown");
+    }
+  }
+}
+
+}  // namespace perfetto::trace_processor
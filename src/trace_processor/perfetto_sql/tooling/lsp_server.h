@@ -0,0 +1,116 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_PERFETTO_SQL_TOOLING_LSP_SERVER_H_
+#define SRC_TRACE_PROCESSOR_PERFETTO_SQL_TOOLING_LSP_SERVER_H_
+
+#include <cstdint>
+#include <map>
+#include <optional>
+#include <string>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "src/trace_processor/perfetto_sql/tooling/sql_module_index.h"
+
+namespace Json {
+class Value;
+}  // namespace Json
+
+namespace perfetto::trace_processor {
+
+class TraceProcessor;
+
+// Language server for PerfettoSQL files implementing the subset of the
+// Language Server Protocol needed by editors for:
+//  * diagnostics: errors from the PerfettoSQL parser and unknown modules.
+//  * completion of module names in INCLUDE PERFETTO MODULE, of the tables,
+//    functions and macros visible from the file and of columns after "name.".
+//  * hover: the documentation of tables, functions and macros and, for macro
+//    invocations, the statement with all macros expanded.
+//  * go to definition of symbols and included modules.
+//
+// If a trace processor is provided, the tables and views of the loaded trace
+// (which are not defined by any module) are also completed and described.
+//
+// Positions are expressed as byte offsets in lines rather than in UTF-16
+// code units as required by the protocol: they only differ for lines with
+// non-ASCII characters.
+class LspServer {
+ public:
+  // |index| must outlive this object. |tp| can be null.
+  LspServer(SqlModuleIndex* index, TraceProcessor* tp);
+  ~LspServer();
+
+  LspServer(const LspServer&) = delete;
+  LspServer& operator=(const LspServer&) = delete;
+
+  // Handles a JSON-RPC message from the client and returns the messages to
+  // send to the client: the response, if |message| is a request, and any
+  // notification.
+  std::vector<std::string> HandleMessage(const std::string& message);
+
+  // Whether the client sent the "exit" notification.
+  bool exited() const { return exited_; }
+
+  // Whether the client sent the "shutdown" request.
+  bool shutdown() const { return shutdown_; }
+
+ private:
+  struct Document {
+    // The key of the module, if the document is part of the index, or its
+    // URI otherwise.
+    std::string key;
+    std::string text;
+    SqlModuleInfo info;
+  };
+
+  // The parameters of the requests about a position in a document.
+  struct PositionParams {
+    std::string uri;
+    uint32_t line;
+    uint32_t character;
+  };
+
+  void UpdateDocument(const std::string& uri, std::string text);
+  Json::Value Diagnostics(const std::string& uri);
+  Json::Value Completion(const PositionParams& params);
+  Json::Value Hover(const PositionParams& params);
+  Json::Value Definition(const PositionParams& params);
+
+  Document* FindDocument(const std::string& uri);
+  std::vector<SqlSymbol::Field> Columns(const std::string& name,
+                                        const Document& doc);
+  std::vector<SqlSymbol::Field> LiveColumns(const std::string& table,
+                                            const SqlModuleInfo* module);
+  const std::vector<std::string>& LiveTables();
+
+  SqlModuleIndex* const index_;
+  TraceProcessor* const tp_;
+  std::map<std::string, Document> documents_;
+  std::optional<std::vector<std::string>> live_tables_;
+  bool shutdown_ = false;
+  bool exited_ = false;
+};
+
+// Runs |server| on stdin and stdout, with the framing of the base protocol
+// (i.e. messages preceded by a Content-Length header), until the client exits
+// or closes stdin.
+base::Status RunLspStdioServer(LspServer* server);
+
+}  // namespace perfetto::trace_processor
+
+#endif  // SRC_TRACE_PROCESSOR_PERFETTO_SQL_TOOLING_LSP_SERVER_H_
@@ -0,0 +1,274 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/perfetto_sql/tooling/lsp_server.h"
+
+#include <memory>
+#include <string>
+#include <vector>
+
+#include "src/trace_processor/perfetto_sql/tooling/sql_module_index.h"
+#include "test/gtest_and_gmock.h"
+
+#include <json/reader.h>
+#include <json/value.h>
+#include <json/writer.h>
+
+namespace perfetto::trace_processor {
+namespace {
+
+using ::testing::HasSubstr;
+using ::testing::UnorderedElementsAre;
+
+constexpr char kUri[] = "file:///tmp/doc.sql";
+
+constexpr char kThreadsModule[] = R"(
+-- All the threads.
+CREATE PERFETTO TABLE foo_threads(
+  -- Id of the thread.
+  utid LONG,
+  -- Name of the thread.
+  name STRING
+) AS
+SELECT 1 AS utid, 'a' AS name;
+
+-- Doubles a value.
+CREATE PERFETTO MACRO foo_double(x Expr) RETURNS Expr AS $x * 2;
+)";
+
+Json::Value Parse(const std::string& json) {
+  Json::CharReaderBuilder b;
+  std::unique_ptr<Json::CharReader> reader(b.newCharReader());
+  Json::Value value;
+  EXPECT_TRUE(reader->parse(json.data(), json.data() + json.size(), &value,
+                            nullptr));
+  return value;
+}
+
+std::string ToString(const Json::Value& value) {
+  Json::StreamWriterBuilder b;
+  b.settings_["indentation"] = "";
+  return Json::writeString(b, value);
+}
+
+class LspServerTest : public ::testing::Test {
+ protected:
+  LspServerTest() : server_(&index_, nullptr) {
+    index_.AddModule("foo.threads", kThreadsModule, "/src/foo/threads.sql");
+  }
+
+  std::vector<Json::Value> Send(const std::string& message) {
+    std::vector<Json::Value> out;
+    for (const std::string& reply : server_.HandleMessage(message)) {
+      out.push_back(Parse(reply));
+    }
+    return out;
+  }
+
+  // Opens a document and returns its diagnostics.
+  Json::Value Open(const std::string& text) {
+    Json::Value params(Json::objectValue);
+    params["textDocument"]["uri"] = kUri;
+    params["textDocument"]["text"] = text;
+    auto out = Send(Message("textDocument/didOpen", params, false));
+    EXPECT_EQ(out.size(), 1u);
+    return out.empty() ? Json::Value() : out[0]["params"]["diagnostics"];
+  }
+
+  // Sends a request about the position |line|:|character| of the document and
+  // returns the result.
+  Json::Value Request(const std::string& method,
+                      uint32_t line,
+                      uint32_t character) {
+    Json::Value params(Json::objectValue);
+    params["textDocument"]["uri"] = kUri;
+    params["position"]["line"] = line;
+    params["position"]["character"] = character;
+    auto out = Send(Message(method, params, true));
+    EXPECT_EQ(out.size(), 1u);
+    return out.empty() ? Json::Value() : out[0]["result"];
+  }
+
+  std::string Message(const std::string& method,
+                      const Json::Value& params,
+                      bool request) {
+    std::string message = R"({"jsonrpc": "2.0", "method": ")" + method +
+                          R"(", "params": )" + ToString(params);
+    if (request) {
+      message += R"(, "id": )" + std::to_string(++last_id_);
+    }
+    return message + "}";
+  }
+
+  static std::vector<std::string> Labels(const Json::Value& items) {
+    std::vector<std::string> labels;
+    for (uint32_t i = 0; i < items.size(); ++i) {
+      labels.push_back(items[i]["label"].asString());
+    }
+    return labels;
+  }
+
+  SqlModuleIndex index_;
+  LspServer server_;
+  int last_id_ = 0;
+};
+
+TEST_F(LspServerTest, Initialize) {
+  auto out = Send(R"({"jsonrpc": "2.0", "id": 1, "method": "initialize",
+                      "params": {}})");
+  ASSERT_EQ(out.size(), 1u);
+  ASSERT_EQ(out[0]["id"].asInt(), 1);
+  const Json::Value& capabilities = out[0]["result"]["capabilities"];
+  ASSERT_TRUE(capabilities["hoverProvider"].asBool());
+  ASSERT_TRUE(capabilities["definitionProvider"].asBool());
+}
+
+TEST_F(LspServerTest, UnknownMethod) {
+  auto out = Send(R"({"jsonrpc": "2.0", "id": 1, "method": "foo/bar"})");
+  ASSERT_EQ(out.size(), 1u);
+  ASSERT_EQ(out[0]["error"]["code"].asInt(), -32601);
+
+  // Unknown notifications are ignored.
+  ASSERT_TRUE(Send(R"({"jsonrpc": "2.0", "method": "foo/bar"})").empty());
+}
+
+TEST_F(LspServerTest, Diagnostics) {
+  Json::Value diagnostics = Open(
+      "INCLUDE PERFETTO MODULE foo.missing;\n"
+      "SELECT 1;\n"
+      "SELECT foo_missing!();\n");
+  ASSERT_EQ(diagnostics.size(), 2u);
+  ASSERT_THAT(diagnostics[0]["message"].asString(),
+              HasSubstr("unknown module 'foo.missing'"));
+  ASSERT_EQ(diagnostics[0]["range"]["start"]["line"].asInt(), 0);
+  ASSERT_EQ(diagnostics[0]["range"]["start"]["character"].asInt(), 24);
+  ASSERT_THAT(diagnostics[1]["message"].asString(),
+              HasSubstr("no such macro"));
+  ASSERT_EQ(diagnostics[1]["range"]["start"]["line"].asInt(), 2);
+
+  ASSERT_EQ(Open("INCLUDE PERFETTO MODULE foo.threads;").size(), 0u);
+}
+
+TEST_F(LspServerTest, Completion) {
+  Open("INCLUDE PERFETTO MODULE foo.threads;\nSELECT * FROM foo_t");
+  ASSERT_THAT(Labels(Request("textDocument/completion", 1, 19)),
+              UnorderedElementsAre("foo_threads"));
+
+  Open("INCLUDE PERFETTO MODULE foo.\nSELECT 1");
+  ASSERT_THAT(Labels(Request("textDocument/completion", 0, 28)),
+              UnorderedElementsAre("foo.threads"));
+
+  Open("SELECT t.n FROM foo_threads t");
+  ASSERT_THAT(Labels(Request("textDocument/completion", 0, 10)),
+              UnorderedElementsAre("name"));
+  ASSERT_THAT(Labels(Request("textDocument/completion", 0, 9)),
+              UnorderedElementsAre("utid", "name"));
+}
+
+TEST_F(LspServerTest, Hover) {
+  Open(
+      "INCLUDE PERFETTO MODULE foo.threads;\n"
+      "SELECT foo_double!(utid) FROM foo_threads;\n");
+  Json::Value hover = Request("textDocument/hover", 1, 32);
+  std::string markdown = hover["contents"]["value"].asString();
+  ASSERT_THAT(markdown, HasSubstr("TABLE foo_threads"));
+  ASSERT_THAT(markdown, HasSubstr("All the threads."));
+  ASSERT_THAT(markdown, HasSubstr("Name of the thread."));
+  ASSERT_THAT(markdown, HasSubstr("`foo.threads`"));
+
+  hover = Request("textDocument/hover", 1, 9);
+  markdown = hover["contents"]["value"].asString();
+  ASSERT_THAT(markdown, HasSubstr("Doubles a value."));
+  ASSERT_THAT(markdown, HasSubstr("SELECT utid * 2 FROM foo_threads"));
+
+  ASSERT_TRUE(Request("textDocument/hover", 1, 0).isNull());
+}
+
+TEST_F(LspServerTest, Definition) {
+  Open(
+      "INCLUDE PERFETTO MODULE foo.threads;\n"
+      "CREATE PERFETTO TABLE bar AS SELECT 1;\n"
+      "SELECT * FROM foo_threads JOIN bar;\n");
+  Json::Value location = Request("textDocument/definition", 2, 16);
+  ASSERT_EQ(location["uri"].asString(), "file:///src/foo/threads.sql");
+  ASSERT_EQ(location["range"]["start"]["line"].asInt(), 2);
+  ASSERT_EQ(location["range"]["start"]["character"].asInt(), 22);
+
+  location = Request("textDocument/definition", 2, 32);
+  ASSERT_EQ(location["uri"].asString(), kUri);
+  ASSERT_EQ(location["range"]["start"]["line"].asInt(), 1);
+
+  location = Request("textDocument/definition", 0, 27);
+  ASSERT_EQ(location["uri"].asString(), "file:///src/foo/threads.sql");
+  ASSERT_EQ(location["range"]["start"]["line"].asInt(), 0);
+}
+
+TEST_F(LspServerTest, InvalidParams) {
+  Open("SELECT 1;");
+  auto error_code = [&](const std::string& message) {
+    auto out = Send(message);
+    EXPECT_EQ(out.size(), 1u);
+    return out.empty() ? 0 : out[0]["error"]["code"].asInt();
+  };
+  ASSERT_EQ(error_code(R"({"jsonrpc": "2.0", "id": 1, "method": 2})"), -32600);
+  ASSERT_EQ(error_code(R"({"jsonrpc": "2.0", "id": 1,
+                           "method": "textDocument/hover", "params": []})"),
+            -32602);
+  ASSERT_EQ(error_code(R"({"jsonrpc": "2.0", "id": 1,
+                           "method": "textDocument/hover",
+                           "params": {"textDocument": "file:///tmp/doc.sql",
+                                      "position": {"line": 0,
+                                                   "character": 0}}})"),
+            -32602);
+  ASSERT_EQ(error_code(R"({"jsonrpc": "2.0", "id": 1,
+                           "method": "textDocument/completion",
+                           "params": {"textDocument": {"uri": 1},
+                                      "position": {"line": 0,
+                                                   "character": 0}}})"),
+            -32602);
+  ASSERT_EQ(error_code(R"({"jsonrpc": "2.0", "id": 1,
+                           "method": "textDocument/definition",
+                           "params": {"textDocument":
+                                          {"uri": "file:///tmp/doc.sql"},
+                                      "position": {"line": -1,
+                                                   "character": "0"}}})"),
+            -32602);
+
+  // Notifications with invalid params are ignored.
+  ASSERT_TRUE(Send(R"({"jsonrpc": "2.0", "method": "textDocument/didOpen",
+                       "params": {"textDocument": {"uri": "file:///a.sql",
+                                                   "text": 1}}})")
+                  .empty());
+  ASSERT_TRUE(Send(R"({"jsonrpc": "2.0", "method": "textDocument/didChange",
+                       "params": {"textDocument": {"uri": "file:///a.sql"},
+                                  "contentChanges": [2]}})")
+                  .empty());
+  ASSERT_TRUE(Send(R"({"jsonrpc": "2.0", "method": "textDocument/didClose",
+                       "params": "file:///a.sql"})")
+                  .empty());
+}
+
+TEST_F(LspServerTest, ShutdownAndExit) {
+  auto out = Send(R"({"jsonrpc": "2.0", "id": 1, "method": "shutdown"})");
+  ASSERT_EQ(out.size(), 1u);
+  ASSERT_TRUE(server_.shutdown());
+  ASSERT_FALSE(server_.exited());
+  Send(R"({"jsonrpc": "2.0", "method": "exit"})");
+  ASSERT_TRUE(server_.exited());
+}
+
+}  // namespace
+}  // namespace perfetto::trace_processor
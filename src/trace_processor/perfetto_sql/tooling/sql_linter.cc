@@ -0,0 +1,395 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/perfetto_sql/tooling/sql_linter.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <functional>
+#include <set>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "perfetto/trace_processor/basic_types.h"
+#include "perfetto/trace_processor/iterator.h"
+#include "perfetto/trace_processor/trace_processor.h"
+#include "src/trace_processor/perfetto_sql/tokenizer/sqlite_tokenizer.h"
+#include "src/trace_processor/sqlite/sql_source.h"
+#include "src/trace_processor/util/sql_argument.h"
+
+namespace perfetto::trace_processor {
+namespace {
+
+constexpr char kPreludePrefix[] = "prelude.";
+
+class Linter {
+ public:
+  Linter(SqlModuleIndex* index, std::vector<SqlLintFinding>* findings)
+      : index_(index), findings_(findings) {}
+
+  void Lint(const SqlModuleInfo& module) {
+    module_ = &module;
+    if (!module.status.ok()) {
+      Add(module.error_position, "syntax", GetSqlErrorMessage(module.status));
+    }
+    for (const SqlSymbol& symbol : module.symbols) {
+      LintSymbol(symbol);
+    }
+    LintIncludes();
+  }
+
+ private:
+  void Add(SqlPosition position, std::string check, std::string message) {
+    findings_->push_back(SqlLintFinding{module_->key, module_->path, position,
+                                       std::move(check), std::move(message)});
+  }
+
+  void LintSymbol(const SqlSymbol& symbol) {
+    if (base::StartsWith(symbol.name, "_")) {
+      return;
+    }
+    const char* kind = KindName(symbol.kind);
+    if (symbol.description.empty()) {
+      Add(symbol.position, "undocumented",
+          std::string(kind) + " '" + symbol.name +
+              "' has no description: document it or prefix its name with "
+              "'_' to make it internal");
+    }
+    for (const SqlSymbol::Field& arg : symbol.args) {
+      if (arg.description.empty()) {
+        Add(symbol.position, "undocumented",
+            "argument '" + arg.name + "' of " + kind + " '" + symbol.name +
+                "' has no description");
+      }
+    }
+    for (const SqlSymbol::Field& column : symbol.columns) {
+      if (column.description.empty()) {
+        Add(symbol.position, "undocumented",
+            "column '" + column.name + "' of " + kind + " '" + symbol.name +
+                "' has no description");
+      }
+    }
+    if (symbol.kind == SqlSymbol::Kind::kFunction &&
+        symbol.return_description.empty()) {
+      Add(symbol.position, "undocumented",
+          "the return value of function '" + symbol.name +
+              "' has no description");
+    }
+    bool is_table = symbol.kind == SqlSymbol::Kind::kTable ||
+                    symbol.kind == SqlSymbol::Kind::kView;
+    if (is_table && symbol.columns.empty()) {
+      Add(symbol.position, "untyped",
+          std::string(kind) + " '" + symbol.name +
+              "' does not declare its columns: list them with their types "
+              "after its name");
+    }
+  }
+
+  void LintIncludes() {
+    // The names referenced by the module, including in the expansion of its
+    // macro invocations.
+    std::set<std::string> references;
+    for (const SqlModuleInfo::Statement& stmt : module_->statements) {
+      std::string sql =
+          module_->sql.substr(stmt.begin, stmt.end - stmt.begin);
+      if (IsInclude(sql)) {
+        continue;
+      }
+      references.merge(GetSqlIdentifiers(sql));
+      if (stmt.expanded_sql) {
+        references.merge(GetSqlIdentifiers(*stmt.expanded_sql));
+      }
+    }
+
+    std::set<std::string> reported_cycles;
+    for (const SqlModuleInfo::Include& include : module_->includes) {
+      std::vector<std::string> keys = index_->MatchModules(include.key);
+      if (keys.empty()) {
+        Add(include.position, "unknown-include",
+            "no module matches '" + include.key + "'");
+        continue;
+      }
+
+      bool is_wildcard = base::EndsWith(include.key, "*");
+      for (const std::string& key : keys) {
+        // A wildcard can match the module itself, which is then skipped.
+        if (is_wildcard && key == module_->key) {
+          continue;
+        }
+        std::vector<std::string> cycle = {module_->key};
+        std::set<std::string> visited;
+        if (FindIncludePath(key, module_->key, &visited, &cycle) &&
+            reported_cycles.insert(key).second) {
+          Add(include.position, "circular-include",
+              "'" + key + "' includes '" + module_->key +
+                  "' back: " + base::Join(cycle, " -> "));
+        }
+      }
+
+      // The names of the symbols made available by the include.
+      std::set<std::string> included;
+      for (const std::string& key : keys) {
+        AddIncludedModules(key, &included);
+      }
+      included.erase(module_->key);
+      std::set<std::string> names;
+      for (const std::string& key : included) {
+        const SqlModuleInfo* included_module = index_->GetModule(key);
+        for (const SqlSymbol& symbol : included_module->symbols) {
+          names.insert(base::ToLower(symbol.name));
+        }
+      }
+      // Modules without symbols can be included for their side effects, e.g.
+      // creating indexes.
+      if (names.empty()) {
+        continue;
+      }
+      bool used = false;
+      for (const std::string& name : names) {
+        if (references.count(name)) {
+          used = true;
+          break;
+        }
+      }
+      if (!used) {
+        Add(include.position, "unused-include",
+            "none of the tables, views, functions and macros of '" +
+                include.key + "' and of the modules it includes are used");
+      }
+    }
+  }
+
+  // Adds |key| and the modules it includes, directly or not, to |keys|. The
+  // prelude, which is always available, is skipped.
+  void AddIncludedModules(const std::string& key, std::set<std::string>* keys) {
+    if (base::StartsWith(key, kPreludePrefix) || !keys->insert(key).second) {
+      return;
+    }
+    const SqlModuleInfo* module = index_->GetModule(key);
+    if (!module) {
+      return;
+    }
+    for (const SqlModuleInfo::Include& include : module->includes) {
+      for (const std::string& included : index_->MatchModules(include.key)) {
+        AddIncludedModules(included, keys);
+      }
+    }
+  }
+
+  // Appends to |path| a chain of includes going from |from| to |to|, both
+  // included, and returns true if there is one.
+  bool FindIncludePath(const std::string& from,
+                       const std::string& to,
+                       std::set<std::string>* visited,
+                       std::vector<std::string>* path) {
+    path->push_back(from);
+    if (from == to) {
+      return true;
+    }
+    const SqlModuleInfo* module =
+        visited->insert(from).second ? index_->GetModule(from) : nullptr;
+    if (module) {
+      for (const SqlModuleInfo::Include& include : module->includes) {
+        bool is_wildcard = base::EndsWith(include.key, "*");
+        for (const std::string& key : index_->MatchModules(include.key)) {
+          if (is_wildcard && key == from) {
+            continue;
+          }
+          if (FindIncludePath(key, to, visited, path)) {
+            return true;
+          }
+        }
+      }
+    }
+    path->pop_back();
+    return false;
+  }
+
+  static bool IsInclude(const std::string& sql) {
+    SqliteTokenizer tokenizer(
+        SqlSource::FromTraceProcessorImplementation(sql));
+    return tokenizer.NextNonWhitespace().token_type == TK_INCLUDE;
+  }
+
+  static const char* KindName(SqlSymbol::Kind kind) {
+    switch (kind) {
+      case SqlSymbol::Kind::kTable:
+        return "table";
+      case SqlSymbol::Kind::kView:
+        return "view";
+      case SqlSymbol::Kind::kFunction:
+      case SqlSymbol::Kind::kTableFunction:
+        return "function";
+      case SqlSymbol::Kind::kMacro:
+        return "macro";
+    }
+    return "symbol";
+  }
+
+  SqlModuleIndex* index_;
+  std::vector<SqlLintFinding>* findings_;
+  const SqlModuleInfo* module_ = nullptr;
+};
+
+const char* ValueTypeName(SqlValue::Type type) {
+  switch (type) {
+    case SqlValue::kNull:
+      return "NULL";
+    case SqlValue::kLong:
+      return "LONG";
+    case SqlValue::kDouble:
+      return "DOUBLE";
+    case SqlValue::kString:
+      return "STRING";
+    case SqlValue::kBytes:
+      return "BYTES";
+  }
+  return "UNKNOWN";
+}
+
+bool HasType(const SqlValue& value, sql_argument::Type type) {
+  SqlValue::Type expected = sql_argument::TypeToSqlValueType(type);
+  return value.is_null() || value.type == expected ||
+         (expected == SqlValue::kDouble && value.type == SqlValue::kLong);
+}
+
+// Checks the values returned by |from| (e.g. a table or a call to a table
+// function) against the types of |columns|.
+void CheckColumns(TraceProcessor* tp,
+                  const std::string& from,
+                  const std::vector<SqlSymbol::Field>& columns,
+                  const std::function<void(std::string)>& add) {
+  std::vector<const SqlSymbol::Field*> typed;
+  std::vector<std::string> names;
+  for (const SqlSymbol::Field& column : columns) {
+    if (column.sql_type) {
+      typed.push_back(&column);
+      names.push_back(column.name);
+    }
+  }
+  if (typed.empty()) {
+    return;
+  }
+  auto it = tp->ExecuteQuery("SELECT " + base::Join(names, ", ") + " FROM " +
+                             from);
+  std::vector<bool> reported(typed.size());
+  size_t remaining = typed.size();
+  while (remaining > 0 && it.Next()) {
+    for (uint32_t i = 0; i < typed.size(); ++i) {
+      SqlValue value = it.Get(i);
+      if (reported[i] || HasType(value, *typed[i]->sql_type)) {
+        continue;
+      }
+      add("column '" + typed[i]->name + "' of '" + from +
+          "' is declared as " + typed[i]->type + " but contains a " +
+          ValueTypeName(value.type) + " value");
+      reported[i] = true;
+      remaining--;
+    }
+  }
+  if (!it.Status().ok()) {
+    add("querying '" + from + "' failed: " + GetSqlErrorMessage(it.Status()));
+  }
+}
+
+// Checks the value returned by the function without arguments |function|
+// against its return type.
+void CheckReturnValue(TraceProcessor* tp,
+                      const SqlSymbol& function,
+                      const std::function<void(std::string)>& add) {
+  auto it = tp->ExecuteQuery("SELECT " + function.name + "()");
+  if (it.Next()) {
+    SqlValue value = it.Get(0);
+    if (!HasType(value, *function.return_sql_type)) {
+      add("function '" + function.name + "' is declared to return " +
+          function.return_type + " but returned a " +
+          ValueTypeName(value.type) + " value");
+    }
+  }
+  if (!it.Status().ok()) {
+    add("calling '" + function.name +
+        "' failed: " + GetSqlErrorMessage(it.Status()));
+  }
+}
+
+}  // namespace
+
+std::vector<SqlLintFinding> LintSqlModules(
+    SqlModuleIndex* index,
+    const std::vector<std::string>& module_keys) {
+  std::vector<SqlLintFinding> findings;
+  Linter linter(index, &findings);
+  for (const std::string& key : module_keys) {
+    if (const SqlModuleInfo* module = index->GetModule(key)) {
+      linter.Lint(*module);
+    }
+  }
+  return findings;
+}
+
+std::vector<SqlLintFinding> LintSqlModuleTypes(
+    TraceProcessor* tp,
+    SqlModuleIndex* index,
+    const std::vector<std::string>& module_keys) {
+  std::vector<SqlLintFinding> findings;
+  for (const std::string& key : module_keys) {
+    const SqlModuleInfo* module = index->GetModule(key);
+    // Modules which cannot be parsed are reported by LintSqlModules.
+    if (!module || !module->status.ok()) {
+      continue;
+    }
+    auto include = tp->ExecuteQuery("INCLUDE PERFETTO MODULE " + key);
+    while (include.Next()) {
+    }
+    if (!include.Status().ok()) {
+      findings.push_back(SqlLintFinding{key, module->path, SqlPosition(),
+                                        "include-error",
+                                        GetSqlErrorMessage(include.Status())});
+      continue;
+    }
+
+    for (const SqlSymbol& symbol : module->symbols) {
+      auto add = [&](std::string message) {
+        findings.push_back(SqlLintFinding{key, module->path, symbol.position,
+                                          "type", std::move(message)});
+      };
+      switch (symbol.kind) {
+        case SqlSymbol::Kind::kTable:
+        case SqlSymbol::Kind::kView:
+          CheckColumns(tp, symbol.name, symbol.columns, add);
+          break;
+        case SqlSymbol::Kind::kTableFunction:
+          if (symbol.args.empty()) {
+            CheckColumns(tp, symbol.name + "()", symbol.columns, add);
+          }
+          break;
+        case SqlSymbol::Kind::kFunction:
+          if (symbol.args.empty() && symbol.return_sql_type) {
+            CheckReturnValue(tp, symbol, add);
+          }
+          break;
+        case SqlSymbol::Kind::kMacro:
+          break;
+      }
+    }
+  }
+  return findings;
+}
+
+}  // namespace perfetto::trace_processor
@@ -0,0 +1,70 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_PERFETTO_SQL_TOOLING_SQL_LINTER_H_
+#define SRC_TRACE_PROCESSOR_PERFETTO_SQL_TOOLING_SQL_LINTER_H_
+
+#include <string>
+#include <vector>
+
+#include "src/trace_processor/perfetto_sql/tooling/sql_module_index.h"
+
+namespace perfetto::trace_processor {
+
+class TraceProcessor;
+
+// A violation of the conventions of PerfettoSQL modules.
+struct SqlLintFinding {
+  std::string module_key;
+  // The file the module was read from, if any.
+  std::string path;
+  SqlPosition position;
+  // Name of the violated check, e.g. "undocumented".
+  std::string check;
+  std::string message;
+};
+
+// Checks the modules of |index| with keys |module_keys| against the
+// conventions of the standard library, without executing them:
+//  * "syntax": the module cannot be parsed.
+//  * "undocumented": a public table, view, function or macro (i.e. whose name
+//    does not start with '_') or one of its arguments, columns or return value
+//    has no documentation comment.
+//  * "untyped": a public table or view does not declare its columns and their
+//    types.
+//  * "unknown-include": an INCLUDE does not match any module.
+//  * "unused-include": none of the tables, views, functions and macros made
+//    available by an INCLUDE are used by the module.
+//  * "circular-include": a module includes itself, directly or not.
+std::vector<SqlLintFinding> LintSqlModules(
+    SqlModuleIndex* index,
+    const std::vector<std::string>& module_keys);
+
+// Includes the modules of |index| with keys |module_keys| in |tp| and checks
+// that the values of their tables, views and functions without arguments
+// have the declared types ("type"). Modules which cannot be included are
+// reported as "include-error".
+//
+// Only the values present in the trace loaded in |tp| are checked: modules
+// are best checked against traces exercising them.
+std::vector<SqlLintFinding> LintSqlModuleTypes(
+    TraceProcessor* tp,
+    SqlModuleIndex* index,
+    const std::vector<std::string>& module_keys);
+
+}  // namespace perfetto::trace_processor
+
+#endif  // SRC_TRACE_PROCESSOR_PERFETTO_SQL_TOOLING_SQL_LINTER_H_
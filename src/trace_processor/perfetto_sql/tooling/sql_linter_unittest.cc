@@ -0,0 +1,137 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/perfetto_sql/tooling/sql_linter.h"
+
+#include <string>
+#include <vector>
+
+#include "src/trace_processor/perfetto_sql/tooling/sql_module_index.h"
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto::trace_processor {
+namespace {
+
+using ::testing::ElementsAre;
+using ::testing::IsEmpty;
+
+// Returns the findings as "<module>:<line>: <check>: <message>".
+std::vector<std::string> Lint(SqlModuleIndex* index,
+                              const std::vector<std::string>& keys) {
+  std::vector<std::string> out;
+  for (const SqlLintFinding& f : LintSqlModules(index, keys)) {
+    out.push_back(f.module_key + ":" + std::to_string(f.position.line) +
+                  ": " + f.check + ": " + f.message);
+  }
+  return out;
+}
+
+TEST(SqlLinterTest, Documentation) {
+  SqlModuleIndex index;
+  index.AddModule("pkg.a", R"(-- Documented.
+CREATE PERFETTO TABLE pkg_good(
+  -- The id.
+  id LONG
+) AS
+SELECT 1 AS id;
+
+CREATE PERFETTO VIEW pkg_bad AS
+SELECT 1 AS id;
+
+CREATE PERFETTO TABLE _pkg_internal AS
+SELECT 1 AS id;
+
+-- Documented.
+CREATE PERFETTO FUNCTION pkg_fn(x LONG)
+RETURNS LONG AS
+SELECT $x;
+)");
+  ASSERT_THAT(
+      Lint(&index, {"pkg.a"}),
+      ElementsAre(
+          "pkg.a:7: undocumented: view 'pkg_bad' has no description: "
+          "document it or prefix its name with '_' to make it internal",
+          "pkg.a:7: untyped: view 'pkg_bad' does not declare its columns: "
+          "list them with their types after its name",
+          "pkg.a:14: undocumented: argument 'x' of function 'pkg_fn' has no "
+          "description",
+          "pkg.a:14: undocumented: the return value of function 'pkg_fn' has "
+          "no description"));
+}
+
+TEST(SqlLinterTest, Includes) {
+  SqlModuleIndex index;
+  index.AddModule("pkg.a", R"(INCLUDE PERFETTO MODULE pkg.b;
+INCLUDE PERFETTO MODULE pkg.c;
+INCLUDE PERFETTO MODULE pkg.missing;
+
+CREATE PERFETTO TABLE _pkg_a AS
+SELECT * FROM _pkg_b;
+)");
+  index.AddModule("pkg.b", R"(INCLUDE PERFETTO MODULE pkg.a;
+
+CREATE PERFETTO TABLE _pkg_b AS
+SELECT * FROM _pkg_a;
+)");
+  index.AddModule("pkg.c", "CREATE PERFETTO TABLE _pkg_c AS SELECT 1 AS x;");
+  index.AddModule("pkg.d", R"(INCLUDE PERFETTO MODULE pkg.*;
+
+CREATE PERFETTO TABLE _pkg_d AS
+SELECT * FROM _pkg_c;
+)");
+
+  ASSERT_THAT(
+      Lint(&index, {"pkg.a", "pkg.c", "pkg.d"}),
+      ElementsAre(
+          "pkg.a:0: circular-include: 'pkg.b' includes 'pkg.a' back: "
+          "pkg.a -> pkg.b -> pkg.a",
+          "pkg.a:1: unused-include: none of the tables, views, functions and "
+          "macros of 'pkg.c' and of the modules it includes are used",
+          "pkg.a:2: unknown-include: no module matches 'pkg.missing'"));
+}
+
+TEST(SqlLinterTest, Syntax) {
+  SqlModuleIndex index;
+  index.AddModule("pkg.a", "SELECT 1;\nCREATE PERFETTO TABLE AS;");
+  std::vector<SqlLintFinding> findings = LintSqlModules(&index, {"pkg.a"});
+  ASSERT_EQ(findings.size(), 1u);
+  ASSERT_EQ(findings[0].check, "syntax");
+  ASSERT_EQ(findings[0].position.line, 1u);
+}
+
+TEST(SqlLinterTest, Clean) {
+  SqlModuleIndex index;
+  index.AddModule("pkg.a", R"(-- Adds one.
+CREATE PERFETTO MACRO pkg_add_one(
+  -- The value.
+  x Expr
+)
+RETURNS Expr AS
+$x + 1;
+)");
+  index.AddModule("pkg.b", R"(INCLUDE PERFETTO MODULE pkg.a;
+
+-- Two.
+CREATE PERFETTO FUNCTION pkg_two()
+-- Always two.
+RETURNS LONG AS
+SELECT pkg_add_one!(1);
+)");
+  ASSERT_THAT(Lint(&index, {"pkg.a", "pkg.b"}), IsEmpty());
+}
+
+}  // namespace
+}  // namespace perfetto::trace_processor
@@ -0,0 +1,563 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/perfetto_sql/tooling/sql_module_index.h"
+
+#include <algorithm>
+#include <cctype>
+#include <cstdint>
+#include <cstdio>
+#include <optional>
+#include <set>
+#include <string>
+#include <utility>
+#include <variant>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/file_utils.h"
+#include "perfetto/ext/base/status_macros.h"
+#include "perfetto/ext/base/flat_hash_map.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "src/trace_processor/perfetto_sql/grammar/perfettosql_grammar.h"
+#include "src/trace_processor/perfetto_sql/parser/perfetto_sql_parser.h"
+#include "src/trace_processor/perfetto_sql/preprocessor/perfetto_sql_preprocessor.h"
+#include "src/trace_processor/perfetto_sql/stdlib/stdlib.h"
+#include "src/trace_processor/perfetto_sql/tokenizer/sqlite_tokenizer.h"
+#include "src/trace_processor/sqlite/sql_source.h"
+#include "src/trace_processor/util/sql_argument.h"
+#include "src/trace_processor/util/sql_modules.h"
+
+namespace perfetto::trace_processor {
+
+namespace {
+
+using Macros = base::FlatHashMap<std::string, PerfettoSqlPreprocessor::Macro>;
+
+constexpr char kPreludePrefix[] = "prelude.";
+
+// A field of a parenthesized list in the header of a statement, as written.
+struct HeaderField {
+  std::string name;
+  std::string type;
+  std::string description;
+};
+
+// The parts of the header of a CREATE PERFETTO statement (i.e. everything
+// before AS) which are not kept by the parser.
+struct StatementHeader {
+  // The parenthesized lists of the header, e.g. the arguments and the
+  // returned columns of a table function.
+  std::vector<std::vector<HeaderField>> lists;
+  std::string returns;
+  std::string returns_description;
+  std::optional<uint32_t> name_offset;
+};
+
+void AppendComment(std::string_view comment, std::string* out) {
+  std::string line = base::TrimWhitespace(std::string(comment.substr(2)));
+  if (line.empty()) {
+    return;
+  }
+  if (!out->empty()) {
+    out->push_back(' ');
+  }
+  out->append(line);
+}
+
+StatementHeader ParseHeader(const std::string& sql, const std::string& name) {
+  StatementHeader header;
+  SqliteTokenizer tokenizer(SqlSource::FromTraceProcessorImplementation(sql));
+  uint32_t offset = 0;
+  uint32_t depth = 0;
+  bool after_returns = false;
+  bool expecting_field = false;
+  std::string comment;
+  for (auto t = tokenizer.Next(); !t.str.empty(); t = tokenizer.Next()) {
+    uint32_t token_offset = offset;
+    offset += static_cast<uint32_t>(t.str.size());
+    if (t.token_type == TK_SPACE) {
+      if (base::StartsWith(std::string(t.str), "--")) {
+        AppendComment(t.str, &comment);
+      } else if (std::count(t.str.begin(), t.str.end(), '\n') > 1) {
+        // Comments separated by an empty line do not document what follows.
+        comment.clear();
+      }
+      continue;
+    }
+    if (t.token_type == TK_SEMI || (depth == 0 && t.token_type == TK_AS)) {
+      break;
+    }
+    if (!header.name_offset && t.str == name) {
+      header.name_offset = token_offset;
+    }
+    if (t.token_type == TK_LP) {
+      if (++depth == 1) {
+        header.lists.emplace_back();
+        expecting_field = true;
+        comment.clear();
+        continue;
+      }
+    } else if (t.token_type == TK_RP && depth > 0) {
+      if (--depth == 0) {
+        continue;
+      }
+    }
+    if (depth == 0) {
+      if (base::CaseInsensitiveEqual(std::string(t.str), "returns")) {
+        after_returns = true;
+        header.returns_description = std::move(comment);
+      } else if (after_returns) {
+        header.returns.append(t.str);
+      }
+      comment.clear();
+      continue;
+    }
+    if (depth == 1 && t.token_type == TK_COMMA) {
+      expecting_field = true;
+      continue;
+    }
+    auto& list = header.lists.back();
+    if (expecting_field) {
+      list.push_back(HeaderField{std::string(t.str), "", std::move(comment)});
+      comment.clear();
+      expecting_field = false;
+    } else if (!list.empty()) {
+      list.back().type.append(t.str);
+    }
+  }
+  return header;
+}
+
+// Returns the comment lines directly preceding |offset| in |sql|.
+std::string DocCommentBefore(const std::string& sql, uint32_t offset) {
+  if (offset == 0) {
+    return "";
+  }
+  std::vector<std::string> lines;
+  size_t end = sql.rfind('\n', offset - 1);
+  while (end != std::string::npos && end > 0) {
+    size_t prev = sql.rfind('\n', end - 1);
+    size_t begin = prev == std::string::npos ? 0 : prev + 1;
+    std::string line = base::TrimWhitespace(sql.substr(begin, end - begin));
+    if (!base::StartsWith(line, "--")) {
+      break;
+    }
+    lines.push_back(base::TrimWhitespace(line.substr(2)));
+    end = prev;
+  }
+  std::reverse(lines.begin(), lines.end());
+  return base::Join(lines, "\n");
+}
+
+SqlPosition OffsetToPosition(const std::string& sql, uint32_t offset) {
+  offset = std::min(offset, static_cast<uint32_t>(sql.size()));
+  SqlPosition position;
+  position.line = static_cast<uint32_t>(
+      std::count(sql.begin(), sql.begin() + offset, '\n'));
+  size_t nl = offset == 0 ? std::string::npos : sql.rfind('\n', offset - 1);
+  position.col = nl == std::string::npos
+                     ? offset
+                     : offset - static_cast<uint32_t>(nl) - 1;
+  return position;
+}
+
+// Converts the fields of |defs|, as kept by the parser, to symbol fields
+// using the names, types and descriptions of |written| where possible.
+std::vector<SqlSymbol::Field> ToFields(
+    const std::vector<sql_argument::ArgumentDefinition>& defs,
+    const std::vector<HeaderField>* written) {
+  std::vector<SqlSymbol::Field> fields;
+  for (const auto& def : defs) {
+    SqlSymbol::Field field;
+    field.name = def.name().ToStdString();
+    if (!field.name.empty() && field.name[0] == '$') {
+      field.name = field.name.substr(1);
+    }
+    field.type = sql_argument::TypeToHumanFriendlyString(def.type());
+    field.sql_type = def.type();
+    if (written) {
+      for (const HeaderField& w : *written) {
+        if (w.name == field.name) {
+          field.type = w.type;
+          field.description = w.description;
+          break;
+        }
+      }
+    }
+    fields.push_back(std::move(field));
+  }
+  return fields;
+}
+
+const std::vector<HeaderField>* HeaderList(const StatementHeader& header,
+                                           size_t i) {
+  return i < header.lists.size() ? &header.lists[i] : nullptr;
+}
+
+void AddMacro(const PerfettoSqlPreprocessor::Macro& macro, Macros* macros) {
+  if (auto* it = macros->Find(macro.name); it) {
+    *it = macro;
+  } else {
+    macros->Insert(macro.name, macro);
+  }
+}
+
+void AddMacros(const SqlModuleInfo& module, Macros* macros) {
+  for (const auto& macro : module.macros) {
+    AddMacro(macro, macros);
+  }
+}
+
+}  // namespace
+
+SqlModuleIndex::SqlModuleIndex() = default;
+SqlModuleIndex::~SqlModuleIndex() = default;
+
+void SqlModuleIndex::AddModule(std::string key,
+                               std::string sql,
+                               std::string path) {
+  modules_[std::move(key)] = Entry{std::move(sql), std::move(path), {}, false};
+}
+
+void SqlModuleIndex::AddStdlibModules(const std::string& source_dir) {
+  for (const auto& file_to_sql : stdlib::kFileToSql) {
+    std::string path = file_to_sql.path;
+    AddModule(sql_modules::GetIncludeKey(path), file_to_sql.sql,
+              source_dir.empty() ? "" : source_dir + "/" + path);
+  }
+}
+
+base::Status SqlModuleIndex::AddPackage(const std::string& dir) {
+  std::string root = dir;
+  if (!root.empty() && root.back() == '/') {
+    root.pop_back();
+  }
+  if (!base::FileExists(root)) {
+    return base::ErrStatus("Directory %s does not exist.", root.c_str());
+  }
+  size_t last_slash = root.rfind('/');
+  std::string package_name =
+      last_slash == std::string::npos ? root : root.substr(last_slash + 1);
+
+  std::vector<std::string> paths;
+  RETURN_IF_ERROR(base::ListFilesRecursive(root, paths));
+  for (const auto& path : paths) {
+    if (base::GetFileExtension(path) != ".sql") {
+      continue;
+    }
+    std::string path_no_extension = path.substr(0, path.rfind('.'));
+    if (path_no_extension.find('.') != std::string::npos) {
+      continue;
+    }
+    std::string filename = root + "/" + path;
+    std::string sql;
+    if (!base::ReadFile(filename, &sql)) {
+      return base::ErrStatus("Cannot read file %s", filename.c_str());
+    }
+    AddModule(package_name + "." + sql_modules::GetIncludeKey(path),
+              std::move(sql), filename);
+  }
+  return base::OkStatus();
+}
+
+const SqlModuleInfo* SqlModuleIndex::GetModule(const std::string& key) {
+  auto it = modules_.find(key);
+  if (it == modules_.end()) {
+    return nullptr;
+  }
+  Entry& entry = it->second;
+  if (!entry.info) {
+    // The module is being parsed: it includes itself, directly or not.
+    if (entry.parsing) {
+      return nullptr;
+    }
+    entry.parsing = true;
+    SqlModuleInfo info = Parse(key, entry.sql, entry.path);
+    entry.parsing = false;
+    entry.info = std::move(info);
+  }
+  return &*entry.info;
+}
+
+std::optional<std::string> SqlModuleIndex::FindModuleByPath(
+    const std::string& path) const {
+  for (const auto& [key, entry] : modules_) {
+    if (!entry.path.empty() && entry.path == path) {
+      return key;
+    }
+  }
+  return std::nullopt;
+}
+
+SqlModuleInfo SqlModuleIndex::ParseModule(const std::string& key,
+                                          const std::string& sql) {
+  return Parse(key, sql, "");
+}
+
+std::vector<std::string> SqlModuleIndex::MatchModules(
+    const std::string& include_key) const {
+  std::vector<std::string> keys;
+  if (include_key.empty() || include_key.back() != '*') {
+    if (modules_.count(include_key)) {
+      keys.push_back(include_key);
+    }
+    return keys;
+  }
+  std::string prefix = include_key.substr(0, include_key.size() - 1);
+  for (auto it = modules_.lower_bound(prefix);
+       it != modules_.end() && base::StartsWith(it->first, prefix); ++it) {
+    keys.push_back(it->first);
+  }
+  return keys;
+}
+
+std::vector<std::string> SqlModuleIndex::ModuleKeys() const {
+  std::vector<std::string> keys;
+  keys.reserve(modules_.size());
+  for (const auto& [key, entry] : modules_) {
+    keys.push_back(key);
+  }
+  return keys;
+}
+
+std::vector<const SqlModuleInfo*> SqlModuleIndex::VisibleModules(
+    const SqlModuleInfo& module) {
+  std::set<std::string> seen{module.key};
+  std::vector<const SqlModuleInfo*> modules;
+  if (!base::StartsWith(module.key, kPreludePrefix)) {
+    AddVisibleModules(std::string(kPreludePrefix) + "*", &seen, &modules);
+  }
+  for (const auto& include : module.includes) {
+    AddVisibleModules(include.key, &seen, &modules);
+  }
+  return modules;
+}
+
+void SqlModuleIndex::AddVisibleModules(
+    const std::string& include_key,
+    std::set<std::string>* seen,
+    std::vector<const SqlModuleInfo*>* out) {
+  for (const std::string& key : MatchModules(include_key)) {
+    if (!seen->insert(key).second) {
+      continue;
+    }
+    const SqlModuleInfo* module = GetModule(key);
+    if (!module) {
+      continue;
+    }
+    out->push_back(module);
+    for (const auto& include : module->includes) {
+      AddVisibleModules(include.key, seen, out);
+    }
+  }
+}
+
+const SqlModuleInfo* SqlModuleIndex::FindSymbol(const std::string& name,
+                                                const SqlModuleInfo* from,
+                                                const SqlSymbol** symbol) {
+  auto find_in = [&](const SqlModuleInfo& module) {
+    for (const SqlSymbol& s : module.symbols) {
+      if (base::CaseInsensitiveEqual(s.name, name)) {
+        *symbol = &s;
+        return true;
+      }
+    }
+    return false;
+  };
+  if (from) {
+    if (find_in(*from)) {
+      return from;
+    }
+    for (const SqlModuleInfo* module : VisibleModules(*from)) {
+      if (find_in(*module)) {
+        return module;
+      }
+    }
+  }
+  for (const auto& [key, entry] : modules_) {
+    const SqlModuleInfo* module = GetModule(key);
+    if (module && find_in(*module)) {
+      return module;
+    }
+  }
+  *symbol = nullptr;
+  return nullptr;
+}
+
+SqlModuleInfo SqlModuleIndex::Parse(const std::string& key,
+                                    const std::string& sql,
+                                    const std::string& path) {
+  SqlModuleInfo info;
+  info.key = key;
+  info.path = path;
+  info.sql = sql;
+
+  Macros macros;
+  if (!base::StartsWith(key, kPreludePrefix)) {
+    std::set<std::string> seen{key};
+    std::vector<const SqlModuleInfo*> prelude;
+    AddVisibleModules(std::string(kPreludePrefix) + "*", &seen, &prelude);
+    for (const SqlModuleInfo* module : prelude) {
+      AddMacros(*module, &macros);
+    }
+  }
+
+  PerfettoSqlParser parser(SqlSource::FromModuleInclude(sql, key), macros);
+  size_t cursor = 0;
+  while (parser.Next()) {
+    const SqlSource& stmt_sql = parser.statement_sql();
+    const std::string& original = stmt_sql.original_sql();
+    size_t found = sql.find(original, cursor);
+    auto begin = static_cast<uint32_t>(found == std::string::npos ? cursor
+                                                                  : found);
+    auto end = static_cast<uint32_t>(begin + original.size());
+    cursor = end;
+    info.statements.push_back(SqlModuleInfo::Statement{
+        begin, end,
+        stmt_sql.sql() != original ? std::make_optional(stmt_sql.sql())
+                                   : std::nullopt});
+
+    const auto& stmt = parser.statement();
+    if (const auto* include = std::get_if<PerfettoSqlParser::Include>(&stmt)) {
+      size_t key_offset = original.find(include->key);
+      info.includes.push_back(SqlModuleInfo::Include{
+          include->key,
+          OffsetToPosition(sql, begin + (key_offset == std::string::npos
+                                             ? 0
+                                             : static_cast<uint32_t>(
+                                                   key_offset)))});
+      // The macros of the included modules are visible from the following
+      // statements.
+      std::set<std::string> seen{key};
+      std::vector<const SqlModuleInfo*> included;
+      AddVisibleModules(include->key, &seen, &included);
+      for (const SqlModuleInfo* module : included) {
+        AddMacros(*module, &macros);
+      }
+      continue;
+    }
+
+    SqlSymbol symbol;
+    StatementHeader header;
+    if (const auto* cf =
+            std::get_if<PerfettoSqlParser::CreateFunction>(&stmt)) {
+      symbol.name = cf->prototype.function_name;
+      header = ParseHeader(original, symbol.name);
+      symbol.args = ToFields(cf->prototype.arguments, HeaderList(header, 0));
+      if (cf->returns.is_table) {
+        symbol.kind = SqlSymbol::Kind::kTableFunction;
+        symbol.columns =
+            ToFields(cf->returns.table_columns, HeaderList(header, 1));
+      } else {
+        symbol.kind = SqlSymbol::Kind::kFunction;
+        symbol.return_type = header.returns;
+        symbol.return_sql_type = cf->returns.scalar_type;
+      }
+      symbol.return_description = header.returns_description;
+    } else if (const auto* ct =
+                   std::get_if<PerfettoSqlParser::CreateTable>(&stmt)) {
+      symbol.kind = SqlSymbol::Kind::kTable;
+      symbol.name = ct->name;
+      header = ParseHeader(original, symbol.name);
+      symbol.columns = ToFields(ct->schema, HeaderList(header, 0));
+    } else if (const auto* cv =
+                   std::get_if<PerfettoSqlParser::CreateView>(&stmt)) {
+      symbol.kind = SqlSymbol::Kind::kView;
+      symbol.name = cv->name;
+      header = ParseHeader(original, symbol.name);
+      symbol.columns = ToFields(cv->schema, HeaderList(header, 0));
+    } else if (const auto* cm =
+                   std::get_if<PerfettoSqlParser::CreateMacro>(&stmt)) {
+      symbol.kind = SqlSymbol::Kind::kMacro;
+      symbol.name = cm->name.sql();
+      header = ParseHeader(original, symbol.name);
+      symbol.return_type = cm->returns.sql();
+      std::vector<std::string> arg_names;
+      for (const auto& [arg_name, arg_type] : cm->args) {
+        SqlSymbol::Field field{arg_name.sql(), arg_type.sql(), std::nullopt,
+                               ""};
+        if (const auto* written = HeaderList(header, 0); written) {
+          for (const HeaderField& w : *written) {
+            if (w.name == field.name) {
+              field.description = w.description;
+            }
+          }
+        }
+        symbol.args.push_back(std::move(field));
+        arg_names.push_back(arg_name.sql());
+      }
+      PerfettoSqlPreprocessor::Macro macro{cm->replace, symbol.name,
+                                           std::move(arg_names), cm->sql};
+      AddMacro(macro, &macros);
+      info.macros.push_back(std::move(macro));
+    } else {
+      continue;
+    }
+    symbol.description = DocCommentBefore(sql, begin);
+    symbol.position =
+        OffsetToPosition(sql, begin + header.name_offset.value_or(0));
+    info.symbols.push_back(std::move(symbol));
+  }
+  info.status = parser.status();
+  if (!info.status.ok()) {
+    info.error_position = GetSqlErrorPosition(info.status).value_or(
+        OffsetToPosition(sql, static_cast<uint32_t>(cursor)));
+  }
+  return info;
+}
+
+std::set<std::string> GetSqlIdentifiers(const std::string& sql) {
+  std::set<std::string> identifiers;
+  SqliteTokenizer tokenizer(SqlSource::FromTraceProcessorImplementation(sql));
+  for (auto t = tokenizer.NextNonWhitespace(); !t.str.empty();
+       t = tokenizer.NextNonWhitespace()) {
+    bool is_identifier = std::all_of(t.str.begin(), t.str.end(), [](char c) {
+      return isalnum(static_cast<unsigned char>(c)) || c == '_';
+    });
+    if (is_identifier) {
+      identifiers.insert(base::ToLower(std::string(t.str)));
+    }
+  }
+  return identifiers;
+}
+
+std::optional<SqlPosition> GetSqlErrorPosition(const base::Status& status) {
+  // Every frame of the traceback contains "<source> line <l> col <c>", with
+  // 1-based line and column.
+  const std::string& message = status.message();
+  for (size_t pos = message.find(" line "); pos != std::string::npos;
+       pos = message.find(" line ", pos + 1)) {
+    unsigned line = 0;
+    unsigned col = 0;
+    if (sscanf(message.c_str() + pos, " line %u col %u", &line, &col) == 2 &&
+        line > 0 && col > 0) {
+      return SqlPosition{line - 1, col - 1};
+    }
+  }
+  return std::nullopt;
+}
+
+std::string GetSqlErrorMessage(const base::Status& status) {
+  const std::string& message = status.message();
+  size_t caret = message.rfind("^\n");
+  if (caret == std::string::npos) {
+    return message;
+  }
+  return base::TrimWhitespace(message.substr(caret + 2));
+}
+
+}  // namespace perfetto::trace_processor
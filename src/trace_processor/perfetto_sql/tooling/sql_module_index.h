@@ -0,0 +1,194 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_PERFETTO_SQL_TOOLING_SQL_MODULE_INDEX_H_
+#define SRC_TRACE_PROCESSOR_PERFETTO_SQL_TOOLING_SQL_MODULE_INDEX_H_
+
+#include <cstdint>
+#include <map>
+#include <optional>
+#include <set>
+#include <string>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "src/trace_processor/perfetto_sql/preprocessor/perfetto_sql_preprocessor.h"
+#include "src/trace_processor/util/sql_argument.h"
+
+namespace perfetto::trace_processor {
+
+// A position in a module, 0-based.
+struct SqlPosition {
+  uint32_t line = 0;
+  uint32_t col = 0;
+};
+
+// A table, view, function or macro defined by a PerfettoSQL module, together
+// with the documentation from the comments preceding it.
+struct SqlSymbol {
+  enum class Kind {
+    kTable,
+    kView,
+    kFunction,
+    kTableFunction,
+    kMacro,
+  };
+  // An argument of a function or macro, or a column of a table, view or
+  // table function.
+  struct Field {
+    std::string name;
+    // The type as written in the module, e.g. "JOINID(thread.id)".
+    std::string type;
+    // Not set for macro arguments and for tables and views without a schema.
+    std::optional<sql_argument::Type> sql_type;
+    std::string description;
+  };
+
+  Kind kind;
+  std::string name;
+  std::string description;
+  std::vector<Field> args;
+  std::vector<Field> columns;
+  // Return type of scalar functions and macros.
+  std::string return_type;
+  std::optional<sql_argument::Type> return_sql_type;
+  std::string return_description;
+  // Position of the name of the symbol.
+  SqlPosition position;
+};
+
+// The result of parsing a PerfettoSQL module.
+struct SqlModuleInfo {
+  struct Include {
+    // Can end with a wildcard, e.g. "android.*".
+    std::string key;
+    SqlPosition position;
+  };
+  struct Statement {
+    // Offsets of the statement in the module, excluding the semicolon.
+    uint32_t begin;
+    uint32_t end;
+    // The statement with all macros expanded. Only set if the statement
+    // invokes a macro.
+    std::optional<std::string> expanded_sql;
+  };
+
+  std::string key;
+  // The file the module was read from, if any.
+  std::string path;
+  std::string sql;
+  std::vector<Include> includes;
+  std::vector<SqlSymbol> symbols;
+  std::vector<Statement> statements;
+  std::vector<PerfettoSqlPreprocessor::Macro> macros;
+
+  // The first error encountered while parsing the module: everything after it
+  // is missing from the fields above.
+  base::Status status;
+  // Position of |status| in the module, if it is an error.
+  SqlPosition error_position;
+};
+
+// Index of the symbols defined by PerfettoSQL modules (e.g. the standard
+// library and the packages passed with --add-sql-package), used by tools
+// working on the source of the modules rather than on a trace.
+//
+// Modules are parsed with PerfettoSqlParser without being executed: the
+// macros defined by the modules included by a module (directly or not) and
+// by the prelude are expanded as when the module is included in trace
+// processor.
+class SqlModuleIndex {
+ public:
+  SqlModuleIndex();
+  ~SqlModuleIndex();
+
+  SqlModuleIndex(const SqlModuleIndex&) = delete;
+  SqlModuleIndex& operator=(const SqlModuleIndex&) = delete;
+
+  // Adds a module to the index, replacing any module with the same key. The
+  // module is parsed the first time it is looked up.
+  void AddModule(std::string key, std::string sql, std::string path = "");
+
+  // Adds the modules of the standard library built into trace processor. If
+  // |source_dir| is not empty, it should point to the source of the standard
+  // library (i.e. src/trace_processor/perfetto_sql/stdlib) and is used to set
+  // the path of the modules.
+  void AddStdlibModules(const std::string& source_dir = "");
+
+  // Adds all the modules of the package rooted at |dir|, following the same
+  // rules as trace_processor_shell's --add-sql-package.
+  base::Status AddPackage(const std::string& dir);
+
+  // Returns the module with the given key, parsing it if needed, or nullptr if
+  // there is no such module.
+  const SqlModuleInfo* GetModule(const std::string& key);
+
+  // Returns the key of the module read from the file at |path|, if any.
+  std::optional<std::string> FindModuleByPath(const std::string& path) const;
+
+  // Parses |sql| as a module named |key| which is not added to the index, e.g.
+  // a file being edited.
+  SqlModuleInfo ParseModule(const std::string& key, const std::string& sql);
+
+  // Returns the keys of all the modules matching |include_key|, which can end
+  // with a wildcard.
+  std::vector<std::string> MatchModules(const std::string& include_key) const;
+
+  // Returns the keys of all the modules in the index, in order.
+  std::vector<std::string> ModuleKeys() const;
+
+  // Returns the modules visible from |module|: the ones it includes directly
+  // or not, and the prelude.
+  std::vector<const SqlModuleInfo*> VisibleModules(const SqlModuleInfo& module);
+
+  // Finds the module defining the symbol |name|, looking first at the modules
+  // visible from |from| (if not null) and then at all the modules.
+  const SqlModuleInfo* FindSymbol(const std::string& name,
+                                  const SqlModuleInfo* from,
+                                  const SqlSymbol** symbol);
+
+ private:
+  struct Entry {
+    std::string sql;
+    std::string path;
+    std::optional<SqlModuleInfo> info;
+    bool parsing = false;
+  };
+
+  SqlModuleInfo Parse(const std::string& key,
+                      const std::string& sql,
+                      const std::string& path);
+  void AddVisibleModules(const std::string& include_key,
+                         std::set<std::string>* seen,
+                         std::vector<const SqlModuleInfo*>* out);
+
+  std::map<std::string, Entry> modules_;
+};
+
+// Returns the lowercase names of all the identifiers and keywords in |sql|.
+std::set<std::string> GetSqlIdentifiers(const std::string& sql);
+
+// Returns the position of the error described by |status|, which must come
+// from PerfettoSQL parsing or execution, in the outermost SQL source of its
+// traceback.
+std::optional<SqlPosition> GetSqlErrorPosition(const base::Status& status);
+
+// Returns the message of |status| without the traceback.
+std::string GetSqlErrorMessage(const base::Status& status);
+
+}  // namespace perfetto::trace_processor
+
+#endif  // SRC_TRACE_PROCESSOR_PERFETTO_SQL_TOOLING_SQL_MODULE_INDEX_H_
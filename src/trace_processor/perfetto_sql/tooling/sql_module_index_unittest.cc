@@ -0,0 +1,200 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/perfetto_sql/tooling/sql_module_index.h"
+
+#include <string>
+#include <vector>
+
+#include "src/trace_processor/util/sql_argument.h"
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto::trace_processor {
+namespace {
+
+using ::testing::ElementsAre;
+using ::testing::HasSubstr;
+
+constexpr char kFooModule[] = R"(--
+-- License header.
+
+INCLUDE PERFETTO MODULE bar.baz;
+
+-- Returns the name of a thread.
+-- Second line.
+CREATE PERFETTO FUNCTION foo_thread_name(
+  -- Id of the thread.
+  utid JOINID(thread.id),
+  -- Unused.
+  flag BOOL
+)
+-- The name.
+RETURNS STRING AS
+SELECT name FROM thread WHERE utid = $utid;
+
+-- Threads.
+CREATE PERFETTO TABLE foo_threads(
+  -- Id of the thread.
+  utid LONG,
+  name STRING
+) AS
+SELECT utid, name FROM thread;
+
+CREATE PERFETTO VIEW _foo_internal AS
+SELECT 1;
+
+-- Adds one.
+CREATE PERFETTO MACRO foo_add_one(
+  -- The value.
+  x Expr
+)
+RETURNS Expr AS $x + 1;
+)";
+
+std::vector<std::string> SymbolNames(const SqlModuleInfo& module) {
+  std::vector<std::string> names;
+  for (const auto& symbol : module.symbols) {
+    names.push_back(symbol.name);
+  }
+  return names;
+}
+
+TEST(SqlModuleIndexTest, ExtractsSymbols) {
+  SqlModuleIndex index;
+  index.AddModule("foo.foo", kFooModule);
+  index.AddModule("bar.baz", "SELECT 1;");
+
+  const SqlModuleInfo* module = index.GetModule("foo.foo");
+  ASSERT_TRUE(module);
+  ASSERT_TRUE(module->status.ok()) << module->status.message();
+  ASSERT_EQ(module->includes.size(), 1u);
+  ASSERT_EQ(module->includes[0].key, "bar.baz");
+  ASSERT_EQ(module->includes[0].position.line, 3u);
+  ASSERT_THAT(SymbolNames(*module),
+              ElementsAre("foo_thread_name", "foo_threads", "_foo_internal",
+                          "foo_add_one"));
+
+  const SqlSymbol& fn = module->symbols[0];
+  ASSERT_EQ(fn.kind, SqlSymbol::Kind::kFunction);
+  ASSERT_EQ(fn.description, "Returns the name of a thread.\nSecond line.");
+  ASSERT_EQ(fn.position.line, 7u);
+  ASSERT_EQ(fn.position.col, 25u);
+  ASSERT_EQ(fn.args.size(), 2u);
+  ASSERT_EQ(fn.args[0].name, "utid");
+  ASSERT_EQ(fn.args[0].type, "JOINID(thread.id)");
+  ASSERT_EQ(fn.args[0].sql_type, sql_argument::Type::kLong);
+  ASSERT_EQ(fn.args[0].description, "Id of the thread.");
+  ASSERT_EQ(fn.args[1].type, "BOOL");
+  ASSERT_EQ(fn.return_type, "STRING");
+  ASSERT_EQ(fn.return_sql_type, sql_argument::Type::kString);
+  ASSERT_EQ(fn.return_description, "The name.");
+
+  const SqlSymbol& table = module->symbols[1];
+  ASSERT_EQ(table.kind, SqlSymbol::Kind::kTable);
+  ASSERT_EQ(table.description, "Threads.");
+  ASSERT_EQ(table.columns.size(), 2u);
+  ASSERT_EQ(table.columns[0].description, "Id of the thread.");
+  ASSERT_EQ(table.columns[1].name, "name");
+  ASSERT_EQ(table.columns[1].description, "");
+
+  const SqlSymbol& view = module->symbols[2];
+  ASSERT_EQ(view.kind, SqlSymbol::Kind::kView);
+  ASSERT_EQ(view.description, "");
+  ASSERT_TRUE(view.columns.empty());
+
+  const SqlSymbol& macro = module->symbols[3];
+  ASSERT_EQ(macro.kind, SqlSymbol::Kind::kMacro);
+  ASSERT_EQ(macro.return_type, "Expr");
+  ASSERT_EQ(macro.args.size(), 1u);
+  ASSERT_EQ(macro.args[0].type, "Expr");
+  ASSERT_EQ(macro.args[0].description, "The value.");
+  ASSERT_EQ(module->macros.size(), 1u);
+}
+
+TEST(SqlModuleIndexTest, ExpandsMacrosOfIncludedModules) {
+  SqlModuleIndex index;
+  index.AddModule("a.macros",
+                  "CREATE PERFETTO MACRO two() RETURNS Expr AS 2;");
+  index.AddModule("a.indirect", "INCLUDE PERFETTO MODULE a.macros;");
+  index.AddModule("prelude.casts",
+                  "CREATE PERFETTO MACRO three() RETURNS Expr AS 3;");
+
+  SqlModuleInfo module = index.ParseModule(
+      "doc", "INCLUDE PERFETTO MODULE a.indirect;\nSELECT two!() + three!();");
+  ASSERT_TRUE(module.status.ok()) << module.status.message();
+  ASSERT_EQ(module.statements.size(), 2u);
+  ASSERT_FALSE(module.statements[0].expanded_sql);
+  ASSERT_EQ(module.statements[1].expanded_sql, "SELECT 2 + 3");
+
+  std::vector<std::string> visible;
+  for (const SqlModuleInfo* m : index.VisibleModules(module)) {
+    visible.push_back(m->key);
+  }
+  ASSERT_THAT(visible, ElementsAre("prelude.casts", "a.indirect", "a.macros"));
+}
+
+TEST(SqlModuleIndexTest, ReportsErrorPosition) {
+  SqlModuleIndex index;
+  SqlModuleInfo module =
+      index.ParseModule("doc", "SELECT 1;\n\nSELECT missing!();\nSELECT 2;");
+  ASSERT_FALSE(module.status.ok());
+  ASSERT_EQ(module.statements.size(), 1u);
+  ASSERT_EQ(module.error_position.line, 2u);
+  ASSERT_EQ(module.error_position.col, 7u);
+  ASSERT_THAT(GetSqlErrorMessage(module.status), HasSubstr("no such macro"));
+}
+
+TEST(SqlModuleIndexTest, MatchModules) {
+  SqlModuleIndex index;
+  index.AddModule("android.a", "");
+  index.AddModule("android.b.c", "");
+  index.AddModule("androidx.d", "");
+  ASSERT_THAT(index.MatchModules("android.*"),
+              ElementsAre("android.a", "android.b.c"));
+  ASSERT_THAT(index.MatchModules("android.a"), ElementsAre("android.a"));
+  ASSERT_TRUE(index.MatchModules("android.x").empty());
+}
+
+TEST(SqlModuleIndexTest, FindSymbolPrefersVisibleModules) {
+  SqlModuleIndex index;
+  index.AddModule("a.one", "CREATE PERFETTO TABLE t AS SELECT 1 AS x;");
+  index.AddModule("a.two", "CREATE PERFETTO TABLE t AS SELECT 2 AS x;");
+  SqlModuleInfo doc =
+      index.ParseModule("doc", "INCLUDE PERFETTO MODULE a.two;");
+
+  const SqlSymbol* symbol = nullptr;
+  const SqlModuleInfo* module = index.FindSymbol("T", &doc, &symbol);
+  ASSERT_TRUE(module);
+  ASSERT_EQ(module->key, "a.two");
+  ASSERT_EQ(symbol->name, "t");
+
+  module = index.FindSymbol("t", nullptr, &symbol);
+  ASSERT_EQ(module->key, "a.one");
+  ASSERT_FALSE(index.FindSymbol("u", &doc, &symbol));
+}
+
+TEST(SqlModuleIndexTest, CircularInclude) {
+  SqlModuleIndex index;
+  index.AddModule("a.x", "INCLUDE PERFETTO MODULE a.y;");
+  index.AddModule("a.y", "INCLUDE PERFETTO MODULE a.x;");
+  const SqlModuleInfo* module = index.GetModule("a.x");
+  ASSERT_TRUE(module);
+  ASSERT_TRUE(module->status.ok());
+  ASSERT_EQ(index.VisibleModules(*module).size(), 1u);
+}
+
+}  // namespace
+}  // namespace perfetto::trace_processor
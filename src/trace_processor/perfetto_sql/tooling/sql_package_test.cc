@@ -0,0 +1,262 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/perfetto_sql/tooling/sql_package_test.h"
+
+#include <algorithm>
+#include <cctype>
+#include <cstddef>
+#include <cstdint>
+#include <set>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/status_or.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "src/trace_processor/perfetto_sql/tooling/sql_module_index.h"
+
+namespace perfetto::trace_processor {
+namespace {
+
+constexpr char kSectionPrefix[] = "=== ";
+
+// Splits |text| in lines, without trailing whitespace and trailing empty
+// lines.
+std::vector<std::string> NormalizedLines(const std::string& text) {
+  std::vector<std::string> lines;
+  size_t begin = 0;
+  while (begin <= text.size()) {
+    size_t end = std::min(text.find('\n', begin), text.size());
+    std::string line = text.substr(begin, end - begin);
+    while (!line.empty() && isspace(static_cast<unsigned char>(line.back()))) {
+      line.pop_back();
+    }
+    lines.push_back(std::move(line));
+    begin = end + 1;
+  }
+  while (!lines.empty() && lines.back().empty()) {
+    lines.pop_back();
+  }
+  return lines;
+}
+
+std::string TrimLines(const std::string& text) {
+  return base::Join(NormalizedLines(text), "\n");
+}
+
+// Returns the offset of |position| in |sql|.
+size_t ToOffset(const std::string& sql, const SqlPosition& position) {
+  size_t offset = 0;
+  for (uint32_t i = 0; i < position.line; ++i) {
+    offset = sql.find('\n', offset);
+    if (offset == std::string::npos) {
+      return sql.size();
+    }
+    ++offset;
+  }
+  return std::min(offset + position.col, sql.size());
+}
+
+}  // namespace
+
+base::StatusOr<SqlTestFile> ParseSqlTestFile(const std::string& path,
+                                             const std::string& contents) {
+  SqlTestFile file;
+  file.path = path;
+
+  enum class Section { kNone, kTrace, kTest, kOut };
+  Section section = Section::kNone;
+  bool has_trace = false;
+  std::set<std::string> names;
+  std::string* body = nullptr;
+
+  uint32_t line_number = 0;
+  size_t begin = 0;
+  while (begin < contents.size()) {
+    size_t end = std::min(contents.find('\n', begin), contents.size());
+    std::string line = contents.substr(begin, end - begin);
+    begin = end + 1;
+    ++line_number;
+    if (!line.empty() && line.back() == '\r') {
+      line.pop_back();
+    }
+
+    if (!base::StartsWith(line, kSectionPrefix)) {
+      if (body) {
+        *body += line;
+        *body += '\n';
+      }
+      continue;
+    }
+
+    std::string header = base::TrimWhitespace(
+        line.substr(sizeof(kSectionPrefix) - 1));
+    size_t space = header.find(' ');
+    std::string kind = header.substr(0, space);
+    std::string arg =
+        space == std::string::npos
+            ? ""
+            : base::TrimWhitespace(header.substr(space + 1));
+    if (kind == "trace") {
+      if (has_trace) {
+        return base::ErrStatus("%s:%u: duplicate trace section", path.c_str(),
+                               line_number);
+      }
+      if (arg == "textproto") {
+        file.trace_format = SqlTestFile::TraceFormat::kTextproto;
+      } else if (arg == "json") {
+        file.trace_format = SqlTestFile::TraceFormat::kJson;
+      } else {
+        return base::ErrStatus(
+            "%s:%u: unknown trace format '%s' (expected textproto or json)",
+            path.c_str(), line_number, arg.c_str());
+      }
+      has_trace = true;
+      section = Section::kTrace;
+      body = &file.trace;
+    } else if (kind == "test") {
+      if (section == Section::kTest) {
+        return base::ErrStatus("%s:%u: test '%s' has no out section",
+                               path.c_str(), file.tests.back().line,
+                               file.tests.back().name.c_str());
+      }
+      if (arg.empty()) {
+        return base::ErrStatus("%s:%u: missing test name", path.c_str(),
+                               line_number);
+      }
+      if (!names.insert(arg).second) {
+        return base::ErrStatus("%s:%u: duplicate test '%s'", path.c_str(),
+                               line_number, arg.c_str());
+      }
+      file.tests.emplace_back();
+      file.tests.back().name = arg;
+      file.tests.back().line = line_number;
+      section = Section::kTest;
+      body = &file.tests.back().query;
+    } else if (kind == "out") {
+      if (section != Section::kTest) {
+        return base::ErrStatus("%s:%u: out section without a test",
+                               path.c_str(), line_number);
+      }
+      section = Section::kOut;
+      body = &file.tests.back().expected_output;
+    } else {
+      return base::ErrStatus("%s:%u: unknown section '%s'", path.c_str(),
+                             line_number, kind.c_str());
+    }
+  }
+
+  if (section == Section::kTest) {
+    return base::ErrStatus("%s:%u: test '%s' has no out section", path.c_str(),
+                           file.tests.back().line,
+                           file.tests.back().name.c_str());
+  }
+  if (!has_trace) {
+    return base::ErrStatus("%s: missing trace section", path.c_str());
+  }
+  if (file.tests.empty()) {
+    return base::ErrStatus("%s: no tests", path.c_str());
+  }
+  for (auto& test : file.tests) {
+    test.query = TrimLines(test.query);
+    test.expected_output = TrimLines(test.expected_output);
+  }
+  return file;
+}
+
+std::string DiffSqlTestOutput(const std::string& expected,
+                              const std::string& actual) {
+  std::vector<std::string> a = NormalizedLines(expected);
+  std::vector<std::string> b = NormalizedLines(actual);
+  if (a == b) {
+    return "";
+  }
+
+  // Longest common subsequence of the suffixes of the lines.
+  std::vector<std::vector<uint32_t>> lcs(a.size() + 1,
+                                         std::vector<uint32_t>(b.size() + 1));
+  for (size_t i = a.size(); i-- > 0;) {
+    for (size_t j = b.size(); j-- > 0;) {
+      lcs[i][j] = a[i] == b[j] ? lcs[i + 1][j + 1] + 1
+                               : std::max(lcs[i + 1][j], lcs[i][j + 1]);
+    }
+  }
+
+  std::string diff;
+  size_t i = 0;
+  size_t j = 0;
+  while (i < a.size() || j < b.size()) {
+    if (i < a.size() && j < b.size() && a[i] == b[j]) {
+      diff += " " + a[i++] + "\n";
+      ++j;
+    } else if (i < a.size() &&
+               (j == b.size() || lcs[i + 1][j] >= lcs[i][j + 1])) {
+      diff += "-" + a[i++] + "\n";
+    } else {
+      diff += "+" + b[j++] + "\n";
+    }
+  }
+  return diff;
+}
+
+SqlPackageCoverage::SqlPackageCoverage(
+    SqlModuleIndex* index,
+    const std::vector<std::string>& packages) {
+  for (const std::string& key : index->ModuleKeys()) {
+    bool in_packages = std::any_of(
+        packages.begin(), packages.end(), [&key](const std::string& package) {
+          return base::StartsWith(key, package + ".");
+        });
+    if (!in_packages) {
+      continue;
+    }
+    const SqlModuleInfo* module = index->GetModule(key);
+    for (const SqlSymbol& symbol : module->symbols) {
+      size_t offset = ToOffset(module->sql, symbol.position);
+      std::set<std::string> references;
+      for (const auto& stmt : module->statements) {
+        if (stmt.begin <= offset && offset < stmt.end) {
+          references = GetSqlIdentifiers(
+              module->sql.substr(stmt.begin, stmt.end - stmt.begin));
+          break;
+        }
+      }
+      references.erase(base::ToLower(symbol.name));
+      symbols_.push_back(Symbol{key, &symbol, false});
+      references_.push_back(std::move(references));
+    }
+  }
+}
+
+void SqlPackageCoverage::AddQuery(const std::string& sql) {
+  MarkExercised(GetSqlIdentifiers(sql));
+}
+
+void SqlPackageCoverage::MarkExercised(
+    const std::set<std::string>& identifiers) {
+  for (size_t i = 0; i < symbols_.size(); ++i) {
+    if (symbols_[i].exercised ||
+        !identifiers.count(base::ToLower(symbols_[i].symbol->name))) {
+      continue;
+    }
+    symbols_[i].exercised = true;
+    MarkExercised(references_[i]);
+  }
+}
+
+}  // namespace perfetto::trace_processor
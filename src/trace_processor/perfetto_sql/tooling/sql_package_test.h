@@ -0,0 +1,116 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_PERFETTO_SQL_TOOLING_SQL_PACKAGE_TEST_H_
+#define SRC_TRACE_PROCESSOR_PERFETTO_SQL_TOOLING_SQL_PACKAGE_TEST_H_
+
+#include <cstdint>
+#include <set>
+#include <string>
+#include <vector>
+
+#include "perfetto/ext/base/status_or.h"
+#include "src/trace_processor/perfetto_sql/tooling/sql_module_index.h"
+
+namespace perfetto::trace_processor {
+
+// Extension of the files containing tests of PerfettoSQL packages.
+inline constexpr char kSqlTestFileExtension[] = ".sqltest";
+
+// A file containing tests of a PerfettoSQL package, run by
+// `trace_processor_shell test`. Each file contains a trace and any number of
+// tests querying it, in sections starting with a "=== " line:
+//
+//   Anything before the first section is ignored.
+//
+//   === trace textproto
+//   packet { ... }
+//
+//   === test thread_names
+//   INCLUDE PERFETTO MODULE my_package.threads;
+//   SELECT utid, name FROM my_package_threads;
+//   === out
+//   "utid","name"
+//   1,"main"
+//
+// The trace is either a textproto of a perfetto.protos.Trace message or a
+// JSON trace. The output of a test is the result of the last statement of its
+// query, formatted as with `trace_processor_shell -q`.
+struct SqlTestFile {
+  enum class TraceFormat {
+    kTextproto,
+    kJson,
+  };
+  struct Test {
+    std::string name;
+    std::string query;
+    std::string expected_output;
+    // Line of the "=== test" header, 1-based.
+    uint32_t line = 0;
+  };
+
+  std::string path;
+  TraceFormat trace_format = TraceFormat::kTextproto;
+  std::string trace;
+  std::vector<Test> tests;
+};
+
+// Parses the contents of the test file at |path|.
+base::StatusOr<SqlTestFile> ParseSqlTestFile(const std::string& path,
+                                             const std::string& contents);
+
+// Returns a line-by-line diff between |expected| and |actual|, ignoring
+// trailing whitespace, or an empty string if they match. Lines only in
+// |expected| are prefixed with '-', lines only in |actual| with '+'.
+std::string DiffSqlTestOutput(const std::string& expected,
+                              const std::string& actual);
+
+// Computes which tables, views, functions and macros of some packages are
+// exercised by queries.
+//
+// A symbol is exercised if its name appears in a query or in the definition
+// of another exercised symbol of the packages: this is a static
+// approximation, which does not need the queries to succeed.
+class SqlPackageCoverage {
+ public:
+  struct Symbol {
+    std::string module_key;
+    const SqlSymbol* symbol;
+    bool exercised = false;
+  };
+
+  // Tracks the symbols of the modules of |index| belonging to |packages|.
+  // |index| must outlive this object.
+  SqlPackageCoverage(SqlModuleIndex* index,
+                     const std::vector<std::string>& packages);
+
+  // Marks the symbols used by |sql| as exercised.
+  void AddQuery(const std::string& sql);
+
+  // The symbols of the packages, in the order of their modules.
+  const std::vector<Symbol>& symbols() const { return symbols_; }
+
+ private:
+  void MarkExercised(const std::set<std::string>& identifiers);
+
+  std::vector<Symbol> symbols_;
+  // The identifiers used by the definition of each symbol in |symbols_|.
+  std::vector<std::set<std::string>> references_;
+};
+
+}  // namespace perfetto::trace_processor
+
+#endif  // SRC_TRACE_PROCESSOR_PERFETTO_SQL_TOOLING_SQL_PACKAGE_TEST_H_
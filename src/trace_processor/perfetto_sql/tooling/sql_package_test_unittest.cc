@@ -0,0 +1,120 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/perfetto_sql/tooling/sql_package_test.h"
+
+#include <string>
+#include <vector>
+
+#include "src/trace_processor/perfetto_sql/tooling/sql_module_index.h"
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto::trace_processor {
+namespace {
+
+using ::testing::HasSubstr;
+using ::testing::UnorderedElementsAre;
+
+constexpr char kTestFile[] = R"(Tests of the threads module.
+
+=== trace textproto
+packet {
+  timestamp: 1
+}
+
+=== test names
+INCLUDE PERFETTO MODULE pkg.threads;
+SELECT name FROM pkg_threads;
+=== out
+"name"
+"main"
+
+=== test empty
+SELECT 1 WHERE 0;
+=== out
+)";
+
+TEST(SqlPackageTestTest, ParseFile) {
+  auto file = ParseSqlTestFile("a.sqltest", kTestFile);
+  ASSERT_TRUE(file.ok()) << file.status().message();
+  ASSERT_EQ(file->trace_format, SqlTestFile::TraceFormat::kTextproto);
+  ASSERT_EQ(file->trace, "packet {\n  timestamp: 1\n}\n\n");
+  ASSERT_EQ(file->tests.size(), 2u);
+  ASSERT_EQ(file->tests[0].name, "names");
+  ASSERT_EQ(file->tests[0].line, 8u);
+  ASSERT_EQ(file->tests[0].query,
+            "INCLUDE PERFETTO MODULE pkg.threads;\n"
+            "SELECT name FROM pkg_threads;");
+  ASSERT_EQ(file->tests[0].expected_output, "\"name\"\n\"main\"");
+  ASSERT_EQ(file->tests[1].name, "empty");
+  ASSERT_EQ(file->tests[1].expected_output, "");
+}
+
+TEST(SqlPackageTestTest, ParseErrors) {
+  auto file = ParseSqlTestFile("a.sqltest", "=== test x\nSELECT 1;\n");
+  ASSERT_THAT(file.status().message(),
+              HasSubstr("a.sqltest:1: test 'x' has no out section"));
+
+  file = ParseSqlTestFile("a.sqltest", "=== test x\n=== out\n");
+  ASSERT_THAT(file.status().message(), HasSubstr("missing trace section"));
+
+  file = ParseSqlTestFile("a.sqltest", "=== trace proto\n");
+  ASSERT_THAT(file.status().message(),
+              HasSubstr("a.sqltest:1: unknown trace format 'proto'"));
+
+  file = ParseSqlTestFile(
+      "a.sqltest", "=== trace json\n=== test x\n=== out\n=== test x\n");
+  ASSERT_THAT(file.status().message(),
+              HasSubstr("a.sqltest:4: duplicate test 'x'"));
+
+  file = ParseSqlTestFile("a.sqltest", "=== trace json\n=== out\n");
+  ASSERT_THAT(file.status().message(), HasSubstr("out section without"));
+}
+
+TEST(SqlPackageTestTest, Diff) {
+  ASSERT_EQ(DiffSqlTestOutput("\"a\"\n1\n2\n", "\"a\"  \n1\n2"), "");
+  ASSERT_EQ(DiffSqlTestOutput("\"a\"\n1\n2\n3", "\"a\"\n1\n4\n3\n5"),
+            " \"a\"\n 1\n-2\n+4\n 3\n+5\n");
+}
+
+TEST(SqlPackageTestTest, Coverage) {
+  SqlModuleIndex index;
+  index.AddModule("pkg.a", R"(
+CREATE PERFETTO FUNCTION pkg_helper() RETURNS LONG AS SELECT 1;
+CREATE PERFETTO TABLE pkg_table AS SELECT pkg_helper() AS x;
+CREATE PERFETTO VIEW pkg_unused AS SELECT 2 AS y;
+)");
+  index.AddModule("pkg.b", R"(
+CREATE PERFETTO MACRO pkg_macro(x Expr) RETURNS Expr AS $x + 1;
+)");
+  index.AddModule("other.c", "CREATE PERFETTO TABLE other_table AS SELECT 1;");
+
+  SqlPackageCoverage coverage(&index, {"pkg"});
+  coverage.AddQuery("SELECT pkg_macro!(x) FROM PKG_TABLE");
+
+  std::vector<std::string> exercised;
+  std::vector<std::string> not_exercised;
+  for (const auto& symbol : coverage.symbols()) {
+    (symbol.exercised ? exercised : not_exercised)
+        .push_back(symbol.symbol->name);
+  }
+  ASSERT_THAT(exercised,
+              UnorderedElementsAre("pkg_helper", "pkg_table", "pkg_macro"));
+  ASSERT_THAT(not_exercised, UnorderedElementsAre("pkg_unused"));
+}
+
+}  // namespace
+}  // namespace perfetto::trace_processor
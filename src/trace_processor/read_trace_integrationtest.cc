@@ -17,6 +17,7 @@
 #include "perfetto/ext/base/file_utils.h"
 #include "perfetto/ext/base/scoped_file.h"
 #include "perfetto/ext/base/utils.h"
+#include "perfetto/protozero/scattered_heap_buffer.h"
 #include "perfetto/trace_processor/read_trace.h"
 
 #include "src/base/test/utils.h"
@@ -25,6 +26,10 @@
 #include "protos/perfetto/trace/trace.pbzero.h"
 #include "protos/perfetto/trace/trace_packet.pbzero.h"
 
+#if PERFETTO_BUILDFLAG(PERFETTO_ZSTD)
+#include <zstd.h>
+#endif
+
 namespace perfetto {
 namespace trace_processor {
 namespace {
@@ -132,6 +137,54 @@ TEST_F(ReadTraceIntegrationTest, DoubleGzipDecompressTrace) {
   ASSERT_EQ(packet_count, 2412u);
 }
 
+#if PERFETTO_BUILDFLAG(PERFETTO_ZSTD)
+std::vector<uint8_t> ZstdCompress(const uint8_t* data, size_t size) {
+  std::vector<uint8_t> out(ZSTD_compressBound(size));
+  size_t out_size =
+      ZSTD_compress(out.data(), out.size(), data, size, ZSTD_CLEVEL_DEFAULT);
+  PERFETTO_CHECK(!ZSTD_isError(out_size));
+  out.resize(out_size);
+  return out;
+}
+
+TEST_F(ReadTraceIntegrationTest, OuterZstdDecompressTrace) {
+  base::ScopedFstream u =
+      OpenTestTrace("test/data/example_android_trace_30s.pb");
+  std::vector<uint8_t> raw_trace = ReadAllData(u);
+  std::vector<uint8_t> raw_compressed_trace =
+      ZstdCompress(raw_trace.data(), raw_trace.size());
+
+  std::vector<uint8_t> decompressed;
+  base::Status status = trace_processor::DecompressTrace(
+      raw_compressed_trace.data(), raw_compressed_trace.size(), &decompressed);
+  ASSERT_TRUE(status.ok()) << status.message();
+
+  ASSERT_EQ(decompressed.size(), raw_trace.size());
+  ASSERT_EQ(decompressed, raw_trace);
+}
+
+TEST_F(ReadTraceIntegrationTest, ZstdCompressedPackets) {
+  base::ScopedFstream u =
+      OpenTestTrace("test/data/example_android_trace_30s.pb");
+  std::vector<uint8_t> raw_trace = ReadAllData(u);
+
+  // Wrap the whole trace in a single TracePacket, as done by traced with
+  // COMPRESSION_TYPE_ZSTD.
+  std::vector<uint8_t> compressed =
+      ZstdCompress(raw_trace.data(), raw_trace.size());
+  protozero::HeapBuffered<protos::pbzero::Trace> trace;
+  trace->add_packet()->set_compressed_packets(compressed.data(),
+                                              compressed.size());
+  std::vector<uint8_t> compressed_trace = trace.SerializeAsArray();
+
+  std::vector<uint8_t> decompressed;
+  base::Status status = trace_processor::DecompressTrace(
+      compressed_trace.data(), compressed_trace.size(), &decompressed);
+  ASSERT_TRUE(status.ok()) << status.message();
+  ASSERT_EQ(decompressed, raw_trace);
+}
+#endif  // PERFETTO_BUILDFLAG(PERFETTO_ZSTD)
+
 }  // namespace
 }  // namespace trace_processor
 }  // namespace perfetto
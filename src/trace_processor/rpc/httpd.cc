@@ -14,12 +14,16 @@
  * limitations under the License.
  */
 
+#include <condition_variable>
 #include <cstddef>
 #include <cstdint>
+#include <functional>
 #include <initializer_list>
 #include <memory>
+#include <mutex>
 #include <optional>
 #include <string>
+#include <unordered_map>
 #include <utility>
 #include <vector>
 
@@ -28,6 +32,7 @@
 #include "perfetto/ext/base/http/http_server.h"
 #include "perfetto/ext/base/string_utils.h"
 #include "perfetto/ext/base/string_view.h"
+#include "perfetto/ext/base/thread_task_runner.h"
 #include "perfetto/ext/base/unix_task_runner.h"
 #include "perfetto/protozero/scattered_heap_buffer.h"
 #include "perfetto/trace_processor/trace_processor.h"
@@ -50,24 +55,111 @@ const char* kDefaultAllowedCORSOrigins[] = {
     "http://127.0.0.1:10000",
 };
 
+// Used by all the handlers which reply asynchronously, using chunked
+// transfer encoding.
+std::initializer_list<const char*> kChunkedHeaders = {
+    "Cache-Control: no-cache",               //
+    "Content-Type: application/x-protobuf",  //
+    "Transfer-Encoding: chunked",            //
+};
+
+// The maximum number of bytes of replies which have been produced by the RPC
+// thread but not sent yet by the HTTP server thread.
+constexpr size_t kMaxPendingBytes = 32 * 1024 * 1024;
+
+// Tracks the bytes of replies which have not been sent yet. Used to stop the
+// RPC thread from producing replies (e.g. the results of a large query)
+// faster than the HTTP server thread can send them.
+class PendingBytes {
+ public:
+  void Add(size_t bytes) {
+    std::unique_lock<std::mutex> lock(mutex_);
+    cv_.wait(lock, [this] { return stopped_ || pending_ < kMaxPendingBytes; });
+    pending_ += bytes;
+  }
+  void Remove(size_t bytes) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    pending_ -= bytes;
+    cv_.notify_all();
+  }
+  // Unblocks Add() forever: the replies won't be sent anymore.
+  void Stop() {
+    std::lock_guard<std::mutex> lock(mutex_);
+    stopped_ = true;
+    cv_.notify_all();
+  }
+
+ private:
+  std::mutex mutex_;
+  std::condition_variable cv_;
+  size_t pending_ = 0;
+  bool stopped_ = false;
+};
+
+// All the requests are handled by the Rpc instance on a dedicated thread, so
+// that the HTTP server can keep receiving requests, in particular the
+// cancellation of a long running query, while a request is being handled.
+// The replies are posted back to the HTTP server thread.
 class Httpd : public base::HttpRequestHandler {
  public:
-  explicit Httpd(std::unique_ptr<TraceProcessor>, bool is_preloaded_eof);
+  Httpd(std::unique_ptr<TraceProcessor>,
+        bool is_preloaded_eof,
+        const Config& config);
   ~Httpd() override;
   void Run(const std::string& listen_ip,
            int port,
            const std::vector<std::string>& additional_cors_origins);
 
  private:
+  // A connection which might be closed by the time a reply is ready. |id| is
+  // used to tell apart a closed connection from a new connection allocated at
+  // the same address.
+  struct ConnRef {
+    base::HttpServerConnection* conn;
+    uint64_t id;
+  };
+
   // HttpRequestHandler implementation.
   void OnHttpRequest(const base::HttpRequest&) override;
   void OnWebsocketMessage(const base::WebsocketMessage&) override;
+  void OnHttpConnectionClosed(base::HttpServerConnection*) override;
 
   static void ServeHelpPage(const base::HttpRequest&);
 
+  ConnRef GetConnRef(base::HttpServerConnection*);
+
+  // Passes |data| to Rpc::OnRpcRequest() on the RPC thread and sends the
+  // responses on |conn|. If |is_http| is true, the responses are sent as
+  // the chunks of the body of a HTTP response, which is then terminated.
+  void HandleRpcRequestAsync(base::HttpServerConnection* conn,
+                             base::StringView data,
+                             bool is_http);
+
+  // Runs |fn| on the RPC thread and sends the bytes it returns as the body
+  // of a chunked HTTP response on |conn|.
+  void ReplyAsync(base::HttpServerConnection* conn,
+                  std::function<std::vector<uint8_t>()> fn);
+
+  // Can be called on the RPC thread. Sends |data| on |conn| as a websocket
+  // message or as a chunk of a HTTP response. (nullptr, 0) closes the
+  // connection. Blocks while more than kMaxPendingBytes of replies are
+  // waiting to be sent.
+  void PostRpcChunk(ConnRef conn, const void* data, uint32_t len);
+
+  // Can be called on the RPC thread. Terminates the chunked HTTP response
+  // being sent on |conn|.
+  void PostHttpBodyEnd(ConnRef conn);
+
   Rpc global_trace_processor_rpc_;
   base::UnixTaskRunner task_runner_;
   base::HttpServer http_srv_;
+  std::unordered_map<base::HttpServerConnection*, uint64_t> live_conns_;
+  uint64_t last_conn_id_ = 0;
+  PendingBytes pending_bytes_;
+
+  // Declared last so that the thread is joined before the other members are
+  // destroyed.
+  base::ThreadTaskRunner rpc_thread_;
 };
 
 base::StringView Vec2Sv(const std::vector<uint8_t>& v) {
@@ -96,11 +188,20 @@ void SendRpcChunk(base::HttpServerConnection* conn,
 }
 
 Httpd::Httpd(std::unique_ptr<TraceProcessor> preloaded_instance,
-             bool is_preloaded_eof)
+             bool is_preloaded_eof,
+             const Config& config)
     : global_trace_processor_rpc_(std::move(preloaded_instance),
-                                  is_preloaded_eof),
-      http_srv_(&task_runner_, this) {}
-Httpd::~Httpd() = default;
+                                  is_preloaded_eof,
+                                  config),
+      http_srv_(&task_runner_, this),
+      rpc_thread_(base::ThreadTaskRunner::CreateAndStart("TraceProcessorRpc")) {
+}
+
+Httpd::~Httpd() {
+  // The replies posted to |task_runner_| won't be sent anymore: don't let the
+  // RPC thread wait for them before it's joined.
+  pending_bytes_.Stop();
+}
 
 void Httpd::Run(const std::string& listen_ip,
                 int port,
@@ -137,29 +238,20 @@ void Httpd::OnHttpRequest(const base::HttpRequest& req) {
     last_req_id = seq_id;
   }
 
-  // This is the default.
-  std::initializer_list<const char*> default_headers = {
-      "Cache-Control: no-cache",               //
-      "Content-Type: application/x-protobuf",  //
-  };
-  // Used by the /query and /rpc handlers for chunked replies.
-  std::initializer_list<const char*> chunked_headers = {
-      "Cache-Control: no-cache",               //
-      "Content-Type: application/x-protobuf",  //
-      "Transfer-Encoding: chunked",            //
-  };
-
-  if (req.uri == "/status") {
-    auto status = global_trace_processor_rpc_.GetStatus();
-    return conn.SendResponse("200 OK", default_headers, Vec2Sv(status));
-  }
-
   if (req.uri == "/websocket" && req.is_websocket_handshake) {
     // Will trigger OnWebsocketMessage() when is received.
     // It returns a 403 if the origin is not one of the allowed CORS origins.
     return conn.UpgradeToWebsocket(req);
   }
 
+  Rpc* rpc = &global_trace_processor_rpc_;
+  std::string body = req.body.ToStdString();
+
+  // All the endpoints below reply asynchronously with a chunked response.
+  if (req.uri == "/status") {
+    return ReplyAsync(&conn, [rpc] { return rpc->GetStatus(); });
+  }
+
   // --- Everything below this line is a legacy endpoint not used by the UI.
   // There are two generations of pre-websocket legacy-ness:
   // 1. The /rpc based endpoint. This is based on a chunked transfer, doing one
@@ -169,41 +261,34 @@ void Httpd::OnHttpRequest(const base::HttpRequest& req) {
   //    This is unused and will be removed at some point.
 
   if (req.uri == "/rpc") {
-    // Start the chunked reply.
-    conn.SendResponseHeaders("200 OK", chunked_headers,
-                             base::HttpServerConnection::kOmitContentLength);
-    global_trace_processor_rpc_.SetRpcResponseFunction(
-        [&](const void* data, uint32_t len) {
-          SendRpcChunk(&conn, data, len);
-        });
-    // OnRpcRequest() will call SendRpcChunk() one or more times.
-    global_trace_processor_rpc_.OnRpcRequest(req.body.data(), req.body.size());
-    global_trace_processor_rpc_.SetRpcResponseFunction(nullptr);
-
-    // Terminate chunked stream.
-    conn.SendResponseBody("0\r\n\r\n", 5);
-    return;
+    // Rpc::OnRpcRequest() will send one or more chunks.
+    return HandleRpcRequestAsync(&conn, req.body, /*is_http=*/true);
   }
 
   if (req.uri == "/parse") {
-    base::Status status = global_trace_processor_rpc_.Parse(
-        reinterpret_cast<const uint8_t*>(req.body.data()), req.body.size());
-    protozero::HeapBuffered<protos::pbzero::AppendTraceDataResult> result;
-    if (!status.ok()) {
-      result->set_error(status.c_message());
-    }
-    return conn.SendResponse("200 OK", default_headers,
-                             Vec2Sv(result.SerializeAsArray()));
+    return ReplyAsync(&conn, [rpc, body = std::move(body)] {
+      base::Status status = rpc->Parse(
+          reinterpret_cast<const uint8_t*>(body.data()), body.size());
+      protozero::HeapBuffered<protos::pbzero::AppendTraceDataResult> result;
+      if (!status.ok()) {
+        result->set_error(status.c_message());
+      }
+      return result.SerializeAsArray();
+    });
   }
 
   if (req.uri == "/notify_eof") {
-    global_trace_processor_rpc_.NotifyEndOfFile();
-    return conn.SendResponse("200 OK", default_headers);
+    return ReplyAsync(&conn, [rpc] {
+      rpc->NotifyEndOfFile();
+      return std::vector<uint8_t>();
+    });
   }
 
   if (req.uri == "/restore_initial_tables") {
-    global_trace_processor_rpc_.RestoreInitialTables();
-    return conn.SendResponse("200 OK", default_headers);
+    return ReplyAsync(&conn, [rpc] {
+      rpc->RestoreInitialTables();
+      return std::vector<uint8_t>();
+    });
   }
 
   // New endpoint, returns data in batches using chunked transfer encoding.
@@ -211,74 +296,154 @@ void Httpd::OnHttpRequest(const base::HttpRequest& req) {
   // |batch_split_threshold_| in query_result_serializer.h.
   // This is temporary, it will be switched to WebSockets soon.
   if (req.uri == "/query") {
-    std::vector<uint8_t> response;
-
     // Start the chunked reply.
-    conn.SendResponseHeaders("200 OK", chunked_headers,
+    conn.SendResponseHeaders("200 OK", kChunkedHeaders,
                              base::HttpServerConnection::kOmitContentLength);
-
-    // |on_result_chunk| will be called nested within the same callstack of the
-    // rpc.Query() call. No further calls will be made once Query() returns.
-    auto on_result_chunk = [&](const uint8_t* buf, size_t len, bool has_more) {
-      PERFETTO_DLOG("Sending response chunk, len=%zu eof=%d", len, !has_more);
-      base::StackString<32> chunk_hdr("%zx\r\n", len);
-      conn.SendResponseBody(chunk_hdr.c_str(), chunk_hdr.len());
-      conn.SendResponseBody(buf, len);
-      conn.SendResponseBody("\r\n", 2);
-      if (!has_more)
-        conn.SendResponseBody("0\r\n\r\n", 5);
-    };
-    global_trace_processor_rpc_.Query(
-        reinterpret_cast<const uint8_t*>(req.body.data()), req.body.size(),
-        on_result_chunk);
+    ConnRef ref = GetConnRef(&conn);
+    rpc_thread_.PostTask([this, rpc, ref, body = std::move(body)] {
+      // |on_result_chunk| will be called nested within the same callstack of
+      // the rpc.Query() call. No further calls will be made once Query()
+      // returns.
+      auto on_result_chunk = [&](const uint8_t* buf, size_t len,
+                                 bool has_more) {
+        PERFETTO_DLOG("Sending response chunk, len=%zu eof=%d", len,
+                      !has_more);
+        PostRpcChunk(ref, buf, static_cast<uint32_t>(len));
+        if (!has_more)
+          PostHttpBodyEnd(ref);
+      };
+      rpc->Query(reinterpret_cast<const uint8_t*>(body.data()), body.size(),
+                 on_result_chunk);
+    });
     return;
   }
 
   if (req.uri == "/compute_metric") {
-    std::vector<uint8_t> res = global_trace_processor_rpc_.ComputeMetric(
-        reinterpret_cast<const uint8_t*>(req.body.data()), req.body.size());
-    return conn.SendResponse("200 OK", default_headers, Vec2Sv(res));
+    return ReplyAsync(&conn, [rpc, body = std::move(body)] {
+      return rpc->ComputeMetric(reinterpret_cast<const uint8_t*>(body.data()),
+                                body.size());
+    });
   }
 
   if (req.uri == "/trace_summary") {
-    std::vector<uint8_t> res = global_trace_processor_rpc_.ComputeTraceSummary(
-        reinterpret_cast<const uint8_t*>(req.body.data()), req.body.size());
-    return conn.SendResponse("200 OK", default_headers, Vec2Sv(res));
+    return ReplyAsync(&conn, [rpc, body = std::move(body)] {
+      return rpc->ComputeTraceSummary(
+          reinterpret_cast<const uint8_t*>(body.data()), body.size());
+    });
   }
 
   if (req.uri == "/enable_metatrace") {
-    global_trace_processor_rpc_.EnableMetatrace(
-        reinterpret_cast<const uint8_t*>(req.body.data()), req.body.size());
-    return conn.SendResponse("200 OK", default_headers);
+    return ReplyAsync(&conn, [rpc, body = std::move(body)] {
+      rpc->EnableMetatrace(reinterpret_cast<const uint8_t*>(body.data()),
+                           body.size());
+      return std::vector<uint8_t>();
+    });
   }
 
   if (req.uri == "/disable_and_read_metatrace") {
-    std::vector<uint8_t> res =
-        global_trace_processor_rpc_.DisableAndReadMetatrace();
-    return conn.SendResponse("200 OK", default_headers, Vec2Sv(res));
+    return ReplyAsync(&conn, [rpc] { return rpc->DisableAndReadMetatrace(); });
   }
 
-  return conn.SendResponseAndClose("404 Not Found", default_headers);
+  return conn.SendResponseAndClose("404 Not Found",
+                                   {
+                                       "Cache-Control: no-cache",
+                                       "Content-Type: application/x-protobuf",
+                                   });
 }
 
 void Httpd::OnWebsocketMessage(const base::WebsocketMessage& msg) {
-  global_trace_processor_rpc_.SetRpcResponseFunction(
-      [&](const void* data, uint32_t len) {
-        SendRpcChunk(msg.conn, data, len);
-      });
-  // OnRpcRequest() will call SendRpcChunk() one or more times.
-  global_trace_processor_rpc_.OnRpcRequest(msg.data.data(), msg.data.size());
-  global_trace_processor_rpc_.SetRpcResponseFunction(nullptr);
+  HandleRpcRequestAsync(msg.conn, msg.data, /*is_http=*/false);
+}
+
+void Httpd::OnHttpConnectionClosed(base::HttpServerConnection* conn) {
+  live_conns_.erase(conn);
+}
+
+Httpd::ConnRef Httpd::GetConnRef(base::HttpServerConnection* conn) {
+  auto it = live_conns_.emplace(conn, ++last_conn_id_).first;
+  return ConnRef{conn, it->second};
+}
+
+void Httpd::HandleRpcRequestAsync(base::HttpServerConnection* conn,
+                                  base::StringView data,
+                                  bool is_http) {
+  // Cancellations must be handled here: the RPC thread might be busy running
+  // the query to cancel.
+  global_trace_processor_rpc_.HandleCancelRequests(data.data(), data.size());
+
+  if (is_http) {
+    // Start the chunked reply.
+    conn->SendResponseHeaders("200 OK", kChunkedHeaders,
+                              base::HttpServerConnection::kOmitContentLength);
+  }
+  ConnRef ref = GetConnRef(conn);
+  rpc_thread_.PostTask([this, ref, is_http, req = data.ToStdString()] {
+    Rpc& rpc = global_trace_processor_rpc_;
+    rpc.SetRpcResponseFunction([this, ref](const void* ptr, uint32_t len) {
+      PostRpcChunk(ref, ptr, len);
+    });
+    // OnRpcRequest() will call PostRpcChunk() one or more times.
+    rpc.OnRpcRequest(req.data(), req.size());
+    rpc.SetRpcResponseFunction(nullptr);
+    if (is_http) {
+      PostHttpBodyEnd(ref);
+    }
+  });
+}
+
+void Httpd::ReplyAsync(base::HttpServerConnection* conn,
+                       std::function<std::vector<uint8_t>()> fn) {
+  conn->SendResponseHeaders("200 OK", kChunkedHeaders,
+                            base::HttpServerConnection::kOmitContentLength);
+  ConnRef ref = GetConnRef(conn);
+  rpc_thread_.PostTask([this, ref, fn = std::move(fn)] {
+    std::vector<uint8_t> res = fn();
+    PostRpcChunk(ref, res.data(), static_cast<uint32_t>(res.size()));
+    PostHttpBodyEnd(ref);
+  });
+}
+
+void Httpd::PostRpcChunk(ConnRef ref, const void* data, uint32_t len) {
+  // Blocking the HTTP server thread would deadlock.
+  PERFETTO_DCHECK(rpc_thread_.get()->RunsTasksOnCurrentThread());
+  bool close = data == nullptr;
+  // An empty chunk would terminate a chunked HTTP response.
+  if (!close && len == 0) {
+    return;
+  }
+  const auto* begin = static_cast<const uint8_t*>(data);
+  std::vector<uint8_t> chunk(begin, close ? begin : begin + len);
+  pending_bytes_.Add(chunk.size());
+  task_runner_.PostTask([this, ref, close, chunk = std::move(chunk)] {
+    pending_bytes_.Remove(chunk.size());
+    auto it = live_conns_.find(ref.conn);
+    if (it == live_conns_.end() || it->second != ref.id) {
+      return;
+    }
+    SendRpcChunk(ref.conn, close ? nullptr : chunk.data(),
+                 static_cast<uint32_t>(chunk.size()));
+  });
+}
+
+void Httpd::PostHttpBodyEnd(ConnRef ref) {
+  task_runner_.PostTask([this, ref] {
+    auto it = live_conns_.find(ref.conn);
+    if (it == live_conns_.end() || it->second != ref.id) {
+      return;
+    }
+    ref.conn->SendResponseBody("0\r\n\r\n", 5);
+  });
 }
 
 }  // namespace
 
 void RunHttpRPCServer(std::unique_ptr<TraceProcessor> preloaded_instance,
                       bool is_preloaded_eof,
+                      const Config& config,
                       const std::string& listen_ip,
                       const std::string& port_number,
                       const std::vector<std::string>& additional_cors_origins) {
-  Httpd srv(std::move(preloaded_instance), is_preloaded_eof);
+  Httpd srv(std::move(preloaded_instance), is_preloaded_eof, config);
   std::optional<int> port_opt = base::StringToInt32(port_number);
   std::string ip = listen_ip.empty() ? "localhost" : listen_ip;
   int port = port_opt.has_value() ? *port_opt : kBindPort;
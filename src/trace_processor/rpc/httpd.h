@@ -21,6 +21,8 @@
 #include <string>
 #include <vector>
 
+#include "perfetto/trace_processor/basic_types.h"
+
 namespace perfetto::trace_processor {
 
 class TraceProcessor;
@@ -30,6 +32,8 @@ class TraceProcessor;
 // preloaded_instance is optional. If non-null, the HTTP server will adopt
 // an existing instance with a pre-loaded trace. If null, it will create a new
 // instance when pushing data into the /parse endpoint.
+// config holds the options of the instances created by the server which cannot
+// be set by the clients (see Rpc).
 // listen_ip is the ip address which http server will listen on,
 // it can be an ipv4 or an ipv6 or a domain.
 // port_number is the port which http server will listen on.
@@ -37,6 +41,7 @@ class TraceProcessor;
 // addition to the default origins defined in httpd.cc.
 void RunHttpRPCServer(std::unique_ptr<TraceProcessor> preloaded_instance,
                       bool is_preloaded_eof,
+                      const Config& config,
                       const std::string& listen_ip,
                       const std::string& port_number,
                       const std::vector<std::string>& additional_cors_origins);
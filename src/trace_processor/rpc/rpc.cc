@@ -16,11 +16,13 @@
 
 #include "src/trace_processor/rpc/rpc.h"
 
+#include <algorithm>
 #include <cinttypes>
 #include <cstdint>
 #include <cstdio>
 #include <cstring>
 #include <memory>
+#include <mutex>
 #include <optional>
 #include <string>
 #include <utility>
@@ -40,6 +42,7 @@
 #include "perfetto/trace_processor/metatrace_config.h"
 #include "perfetto/trace_processor/trace_processor.h"
 #include "src/trace_processor/tp_metatrace.h"
+#include "src/trace_processor/util/arrow_ipc_writer.h"
 
 #include "protos/perfetto/trace_processor/metatrace_categories.pbzero.h"
 #include "protos/perfetto/trace_processor/trace_processor.pbzero.h"
@@ -101,16 +104,34 @@ void Response::Send(Rpc::RpcResponseFunction send_fn) {
 
 Rpc::Rpc(std::unique_ptr<TraceProcessor> preloaded_instance,
          bool has_preloaded_eof)
-    : trace_processor_(std::move(preloaded_instance)),
+    : Rpc(std::move(preloaded_instance), has_preloaded_eof, Config()) {}
+
+Rpc::Rpc(std::unique_ptr<TraceProcessor> preloaded_instance,
+         bool has_preloaded_eof,
+         const Config& config)
+    : base_config_(config),
+      trace_processor_(std::move(preloaded_instance)),
       eof_(trace_processor_ ? has_preloaded_eof : false) {
-  if (!trace_processor_) {
-    ResetTraceProcessorInternal(Config());
+  if (trace_processor_) {
+    trace_processor_config_ = NewConfig();
+  } else {
+    ResetTraceProcessorInternal(NewConfig());
   }
 }
 
 Rpc::Rpc() : Rpc(nullptr, false) {}
 Rpc::~Rpc() = default;
 
+Config Rpc::NewConfig() const {
+  Config config;
+  config.table_cache_dir = base_config_.table_cache_dir;
+  config.table_cache_max_size_bytes = base_config_.table_cache_max_size_bytes;
+  config.query_timeout_ms = base_config_.query_timeout_ms;
+  config.query_max_table_memory_bytes =
+      base_config_.query_max_table_memory_bytes;
+  return config;
+}
+
 void Rpc::ResetTraceProcessorInternal(const Config& config) {
   trace_processor_config_ = config;
   trace_processor_ = TraceProcessor::CreateInstance(config);
@@ -140,6 +161,113 @@ void Rpc::OnRpcRequest(const void* data, size_t len) {
   }
 }
 
+void Rpc::HandleCancelRequests(const void* data, size_t len) {
+  namespace proto_utils = protozero::proto_utils;
+  // TPM_CANCEL_QUERY requests are much smaller than this. The bodies of the
+  // larger messages (e.g. TPM_APPEND_TRACE_DATA) are skipped without being
+  // copied.
+  constexpr size_t kMaxCancelRequestSize = 64;
+  // The field tag and the length of a message, both varints.
+  constexpr size_t kMaxHeaderSize = proto_utils::kMaxSimpleFieldEncodedSize;
+
+  const auto* ptr = static_cast<const uint8_t*>(data);
+  const auto* end = ptr + len;
+  // Framing errors are reported by OnRpcRequest(): just stop looking for
+  // cancellation requests.
+  while (ptr < end && !cancel_framing_error_) {
+    size_t avail = static_cast<size_t>(end - ptr);
+    if (cancel_skip_bytes_ > 0) {
+      size_t skip = std::min(cancel_skip_bytes_, avail);
+      ptr += skip;
+      cancel_skip_bytes_ -= skip;
+      continue;
+    }
+    if (cancel_msg_len_) {
+      size_t size = std::min(*cancel_msg_len_ - cancel_msg_buf_.size(), avail);
+      cancel_msg_buf_.insert(cancel_msg_buf_.end(), ptr, ptr + size);
+      ptr += size;
+      if (cancel_msg_buf_.size() < *cancel_msg_len_) {
+        continue;
+      }
+      RpcProto::Decoder req(cancel_msg_buf_.data(), cancel_msg_buf_.size());
+      if (req.request() == RpcProto::TPM_CANCEL_QUERY &&
+          req.has_cancel_query_args()) {
+        protos::pbzero::CancelQueryArgs::Decoder args(req.cancel_query_args());
+        CancelQuery(args.query_seq());
+      }
+      cancel_msg_buf_.clear();
+      cancel_msg_len_ = std::nullopt;
+      continue;
+    }
+
+    // Read the header one byte at a time: it's only a few bytes long.
+    cancel_msg_buf_.push_back(*ptr++);
+    const uint8_t* hdr_start = cancel_msg_buf_.data();
+    const uint8_t* hdr_end = hdr_start + cancel_msg_buf_.size();
+    uint64_t field_tag = 0;
+    uint64_t msg_len = 0;
+    const uint8_t* len_start =
+        proto_utils::ParseVarInt(hdr_start, hdr_end, &field_tag);
+    if (len_start == hdr_start ||
+        proto_utils::ParseVarInt(len_start, hdr_end, &msg_len) == len_start) {
+      cancel_framing_error_ = cancel_msg_buf_.size() >= kMaxHeaderSize;
+      continue;
+    }
+    cancel_msg_buf_.clear();
+    constexpr auto kLengthDelimited =
+        static_cast<uint64_t>(proto_utils::ProtoWireType::kLengthDelimited);
+    if ((field_tag & 0x07) != kLengthDelimited ||
+        msg_len > protozero::ProtoRingBuffer::kMaxMsgSize) {
+      cancel_framing_error_ = true;
+    } else if (msg_len > kMaxCancelRequestSize) {
+      cancel_skip_bytes_ = static_cast<size_t>(msg_len);
+    } else if (msg_len > 0) {
+      cancel_msg_len_ = static_cast<size_t>(msg_len);
+    }
+  }
+}
+
+void Rpc::CancelQuery(int64_t query_seq) {
+  std::lock_guard<std::mutex> lock(cancel_mutex_);
+  if (running_query_seq_ == query_seq) {
+    query_cancelled_.store(true);
+    // The cancellation flag is enough but interrupting SQLite makes it
+    // stop sooner.
+    trace_processor_->InterruptQuery();
+    return;
+  }
+  if (std::find(pending_cancel_seqs_.begin(), pending_cancel_seqs_.end(),
+                query_seq) == pending_cancel_seqs_.end()) {
+    pending_cancel_seqs_.push_back(query_seq);
+  }
+}
+
+QueryOptions Rpc::BeginQuery(int64_t seq,
+                             const protos::pbzero::QueryArgs::Decoder& args) {
+  std::lock_guard<std::mutex> lock(cancel_mutex_);
+  running_query_seq_ = seq;
+  auto it = std::find(pending_cancel_seqs_.begin(), pending_cancel_seqs_.end(),
+                      seq);
+  query_cancelled_.store(it != pending_cancel_seqs_.end());
+  // Cancellations of requests older than this one can't match any query
+  // anymore (e.g. because they arrived after the query had completed).
+  pending_cancel_seqs_.erase(
+      std::remove_if(pending_cancel_seqs_.begin(), pending_cancel_seqs_.end(),
+                     [seq](int64_t s) { return s <= seq; }),
+      pending_cancel_seqs_.end());
+
+  QueryOptions options;
+  options.timeout_ms = args.timeout_ms();
+  options.max_table_memory_bytes = args.max_table_memory_bytes();
+  options.cancel_flag = &query_cancelled_;
+  return options;
+}
+
+void Rpc::EndQuery() {
+  std::lock_guard<std::mutex> lock(cancel_mutex_);
+  running_query_seq_ = std::nullopt;
+}
+
 namespace {
 using ProtoEnum = protos::pbzero::MetatraceCategories;
 TraceProcessor::MetatraceCategories MetatraceCategoriesToPublicEnum(
@@ -193,6 +321,12 @@ void Rpc::ParseRpcRequest(const uint8_t* data, size_t len) {
     rpc_response_fn_(nullptr, 0);  // Disconnect.
     return;
   }
+  if (req.seq() == 0) {
+    // The client restarted the sequence so cancellations of the requests of
+    // the previous sequence must not affect the new requests.
+    std::lock_guard<std::mutex> lock(cancel_mutex_);
+    pending_cancel_seqs_.clear();
+  }
   rx_seq_id_ = req.seq();
 
   // The static cast is to prevent that the compiler breaks future proofness.
@@ -243,7 +377,8 @@ void Rpc::ParseRpcRequest(const uint8_t* data, size_t len) {
                             }
                           });
 
-        auto it = trace_processor_->ExecuteQuery(sql);
+        auto it =
+            trace_processor_->ExecuteQuery(sql, BeginQuery(req.seq(), query));
         QueryResultSerializer serializer(std::move(it));
         for (bool has_more = true; has_more;) {
           const auto seq_id = tx_seq_id_++;
@@ -274,6 +409,58 @@ void Rpc::ParseRpcRequest(const uint8_t* data, size_t len) {
           err_resp.Send(rpc_response_fn_);
           break;
         }
+        EndQuery();
+      }
+      break;
+    }
+    case RpcProto::TPM_QUERY_ARROW: {
+      if (!req.has_query_args()) {
+        Response resp(tx_seq_id_++, req_type);
+        auto* result = resp->set_arrow_query_result();
+        result->set_error(kErrFieldNotSet);
+        result->set_is_last_batch(true);
+        resp.Send(rpc_response_fn_);
+      } else {
+        protozero::ConstBytes args = req.query_args();
+        protos::pbzero::QueryArgs::Decoder query(args.data, args.size);
+        std::string sql = query.sql_query().ToStdString();
+
+        PERFETTO_TP_TRACE(metatrace::Category::API_TIMELINE, "RPC_QUERY_ARROW",
+                          [&](metatrace::Record* r) {
+                            r->AddArg("SQL", sql);
+                            if (query.has_tag()) {
+                              r->AddArg("tag", query.tag());
+                            }
+                          });
+
+        // Each IPC message is sent in its own response. The last message is
+        // held back so that it can be marked with |is_last_batch|.
+        std::vector<uint8_t> pending;
+        auto it =
+            trace_processor_->ExecuteQuery(sql, BeginQuery(req.seq(), query));
+        base::Status status = util::WriteIteratorAsArrow(
+            it, util::ArrowIpcWriter::Format::kStream,
+            [&](const uint8_t* data, size_t size) {
+              if (!pending.empty()) {
+                Response resp(tx_seq_id_++, req_type);
+                resp->set_arrow_query_result()->set_arrow_ipc(pending.data(),
+                                                              pending.size());
+                resp.Send(rpc_response_fn_);
+              }
+              pending.assign(data, data + size);
+              return base::OkStatus();
+            });
+        EndQuery();
+
+        Response resp(tx_seq_id_++, req_type);
+        auto* result = resp->set_arrow_query_result();
+        if (status.ok()) {
+          result->set_arrow_ipc(pending.data(), pending.size());
+        } else {
+          result->set_error(status.message());
+        }
+        result->set_is_last_batch(true);
+        resp.Send(rpc_response_fn_);
       }
       break;
     }
@@ -390,6 +577,46 @@ void Rpc::ParseRpcRequest(const uint8_t* data, size_t len) {
       resp.Send(rpc_response_fn_);
       break;
     }
+    case RpcProto::TPM_CANCEL_QUERY: {
+      // If the transport called HandleCancelRequests(), this has already
+      // been handled. Otherwise this cancels a query queued after this
+      // request, if any.
+      if (req.has_cancel_query_args()) {
+        protos::pbzero::CancelQueryArgs::Decoder args(req.cancel_query_args());
+        CancelQuery(args.query_seq());
+      }
+      Response resp(tx_seq_id_++, req_type);
+      resp.Send(rpc_response_fn_);
+      break;
+    }
+    case RpcProto::TPM_SAVE_SNAPSHOT: {
+      Response resp(tx_seq_id_++, req_type);
+      auto* result = resp->set_snapshot_result();
+      if (!req.has_save_snapshot_args()) {
+        result->set_error(kErrFieldNotSet);
+      } else {
+        base::Status status = SaveSnapshot(req.save_snapshot_args());
+        if (!status.ok()) {
+          result->set_error(status.message());
+        }
+      }
+      resp.Send(rpc_response_fn_);
+      break;
+    }
+    case RpcProto::TPM_RESTORE_SNAPSHOT: {
+      Response resp(tx_seq_id_++, req_type);
+      auto* result = resp->set_snapshot_result();
+      if (!req.has_restore_snapshot_args()) {
+        result->set_error(kErrFieldNotSet);
+      } else {
+        base::Status status = RestoreSnapshot(req.restore_snapshot_args());
+        if (!status.ok()) {
+          result->set_error(status.message());
+        }
+      }
+      resp.Send(rpc_response_fn_);
+      break;
+    }
     default: {
       // This can legitimately happen if the client is newer. We reply with a
       // generic "unknown request" response, so the client can do feature
@@ -440,7 +667,7 @@ base::Status Rpc::NotifyEndOfFile() {
 void Rpc::ResetTraceProcessor(const uint8_t* args, size_t len) {
   protos::pbzero::ResetTraceProcessorArgs::Decoder reset_trace_processor_args(
       args, len);
-  Config config;
+  Config config = NewConfig();
   if (reset_trace_processor_args.has_drop_track_event_data_before()) {
     config.drop_track_event_data_before =
         reset_trace_processor_args.drop_track_event_data_before() ==
@@ -475,9 +702,43 @@ void Rpc::ResetTraceProcessor(const uint8_t* args, size_t len) {
       config.parsing_mode = ParsingMode::kTokenizeAndSort;
       break;
   }
+  if (reset_trace_processor_args.has_enable_table_cache() &&
+      !reset_trace_processor_args.enable_table_cache()) {
+    config.table_cache_dir.clear();
+  }
   ResetTraceProcessorInternal(config);
 }
 
+base::Status Rpc::SaveSnapshot(protozero::ConstBytes bytes) {
+  protos::pbzero::SaveSnapshotArgs::Decoder args(bytes);
+  if (!eof_) {
+    return base::ErrStatus("No trace has been fully loaded");
+  }
+  return trace_processor_->SaveSnapshot(args.path().ToStdString());
+}
+
+base::Status Rpc::RestoreSnapshot(protozero::ConstBytes bytes) {
+  PERFETTO_TP_TRACE(metatrace::Category::API_TIMELINE, "RPC_RESTORE_SNAPSHOT");
+  protos::pbzero::RestoreSnapshotArgs::Decoder args(bytes);
+  // Snapshots can only be restored into a pristine instance.
+  ResetTraceProcessorInternal(trace_processor_config_);
+  eof_ = false;
+  base::Status status;
+  if (args.has_data()) {
+    status = trace_processor_->RestoreSnapshot(args.data().data,
+                                               args.data().size);
+  } else {
+    status = trace_processor_->RestoreSnapshot(args.path().ToStdString());
+  }
+  if (!status.ok()) {
+    // The instance is unusable after a failed restore.
+    ResetTraceProcessorInternal(trace_processor_config_);
+    return status;
+  }
+  eof_ = true;
+  return base::OkStatus();
+}
+
 base::Status Rpc::RegisterSqlPackage(protozero::ConstBytes bytes) {
   protos::pbzero::RegisterSqlPackageArgs::Decoder args(bytes);
   SqlPackage package;
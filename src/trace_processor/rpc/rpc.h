@@ -17,10 +17,13 @@
 #ifndef SRC_TRACE_PROCESSOR_RPC_RPC_H_
 #define SRC_TRACE_PROCESSOR_RPC_RPC_H_
 
+#include <atomic>
 #include <cstddef>
 #include <cstdint>
 #include <functional>
 #include <memory>
+#include <mutex>
+#include <optional>
 #include <string>
 #include <utility>
 #include <vector>
@@ -63,6 +66,14 @@ class Rpc {
   // instance and allow to directly query that. If null, a new instanace will be
   // created internally by calling Parse().
   explicit Rpc(std::unique_ptr<TraceProcessor>, bool has_preloaded_eof);
+
+  // As above but |config| specifies the options of the instances created
+  // internally which cannot be set by the RPC client (i.e. the table cache
+  // options). It should be the same config used to create the preloaded
+  // instance.
+  Rpc(std::unique_ptr<TraceProcessor>,
+      bool has_preloaded_eof,
+      const Config& config);
   Rpc();
   ~Rpc();
 
@@ -90,6 +101,20 @@ class Rpc {
     rpc_response_fn_ = std::move(f);
   }
 
+  // Cancels the TPM_QUERY_STREAMING or TPM_QUERY_ARROW request with sequence
+  // number |query_seq|, either while it runs or, if it has not started yet,
+  // as soon as it starts. Unlike the other methods, this can be called from
+  // any thread.
+  void CancelQuery(int64_t query_seq);
+
+  // Handles the TPM_CANCEL_QUERY requests in |data| immediately, without
+  // waiting for the requests received before them to be processed.
+  // Transports which run OnRpcRequest() on a separate thread should call this
+  // on the thread receiving the data, before passing the same data to
+  // OnRpcRequest(), so that a running query can be cancelled. Must always be
+  // called from the same thread.
+  void HandleCancelRequests(const void* data, size_t len);
+
   // 2. TraceProcessor legacy RPC endpoints.
   // The methods below are exposed for the old RPC interfaces, where each RPC
   // implementation deals with the method demuxing: (i) wasm_bridge.cc has one
@@ -134,9 +159,14 @@ class Rpc {
   void ParseRpcRequest(const uint8_t*, size_t);
   void ResetTraceProcessor(const uint8_t*, size_t);
   base::Status RegisterSqlPackage(protozero::ConstBytes);
+  base::Status SaveSnapshot(protozero::ConstBytes);
+  base::Status RestoreSnapshot(protozero::ConstBytes);
   void ResetTraceProcessorInternal(const Config&);
   void MaybePrintProgress();
   Iterator QueryInternal(const uint8_t*, size_t);
+  QueryOptions BeginQuery(int64_t seq,
+                          const protos::pbzero::QueryArgs::Decoder&);
+  void EndQuery();
   void ComputeMetricInternal(const uint8_t*,
                              size_t,
                              protos::pbzero::ComputeMetricResult*);
@@ -146,6 +176,11 @@ class Rpc {
   void DisableAndReadMetatraceInternal(
       protos::pbzero::DisableAndReadMetatraceResult*);
 
+  // Returns a new config with the options inherited from the config passed to
+  // the constructor.
+  Config NewConfig() const;
+
+  Config base_config_;
   Config trace_processor_config_;
   std::unique_ptr<TraceProcessor> trace_processor_;
   RpcResponseFunction rpc_response_fn_;
@@ -156,6 +191,24 @@ class Rpc {
   int64_t t_parse_started_ = 0;
   size_t bytes_last_progress_ = 0;
   size_t bytes_parsed_ = 0;
+
+  // Set when the running query is cancelled. Passed to the TraceProcessor in
+  // QueryOptions.
+  std::atomic<bool> query_cancelled_{false};
+
+  // Guards the fields below, which are also accessed by CancelQuery().
+  std::mutex cancel_mutex_;
+  std::optional<int64_t> running_query_seq_;
+  std::vector<int64_t> pending_cancel_seqs_;
+
+  // Only accessed by HandleCancelRequests(), which doesn't buffer the bodies
+  // of the messages which are too large to be cancellation requests.
+  // Contains the header of the next message or, once |cancel_msg_len_| is
+  // set, its body.
+  std::vector<uint8_t> cancel_msg_buf_;
+  std::optional<size_t> cancel_msg_len_;
+  size_t cancel_skip_bytes_ = 0;
+  bool cancel_framing_error_ = false;
 };
 
 }  // namespace trace_processor
@@ -0,0 +1,194 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/rpc/rpc.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "perfetto/ext/base/thread_task_runner.h"
+#include "perfetto/ext/base/waitable_event.h"
+#include "perfetto/protozero/scattered_heap_buffer.h"
+#include "test/gtest_and_gmock.h"
+
+#include "protos/perfetto/trace_processor/trace_processor.pbzero.h"
+
+namespace perfetto::trace_processor {
+namespace {
+
+using ::testing::ElementsAre;
+using ::testing::HasSubstr;
+using RpcProto = protos::pbzero::TraceProcessorRpc;
+using StreamProto = protos::pbzero::TraceProcessorRpcStream;
+
+// Never ends unless it's cancelled (or times out after a minute, so that a
+// broken cancellation fails the test rather than hanging it).
+constexpr char kEndlessQuery[] =
+    "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) "
+    "SELECT count(*) FROM c";
+
+std::vector<uint8_t> QueryRequest(int64_t seq,
+                                  const std::string& sql,
+                                  uint64_t timeout_ms = 0) {
+  protozero::HeapBuffered<StreamProto> stream;
+  auto* msg = stream->add_msg();
+  msg->set_seq(seq);
+  msg->set_request(RpcProto::TPM_QUERY_STREAMING);
+  auto* args = msg->set_query_args();
+  args->set_sql_query(sql);
+  if (timeout_ms) {
+    args->set_timeout_ms(timeout_ms);
+  }
+  return stream.SerializeAsArray();
+}
+
+std::vector<uint8_t> CancelRequest(int64_t seq, int64_t query_seq) {
+  protozero::HeapBuffered<StreamProto> stream;
+  auto* msg = stream->add_msg();
+  msg->set_seq(seq);
+  msg->set_request(RpcProto::TPM_CANCEL_QUERY);
+  msg->set_cancel_query_args()->set_query_seq(query_seq);
+  return stream.SerializeAsArray();
+}
+
+std::vector<uint8_t> AppendTraceDataRequest(int64_t seq, size_t size) {
+  protozero::HeapBuffered<StreamProto> stream;
+  auto* msg = stream->add_msg();
+  msg->set_seq(seq);
+  msg->set_request(RpcProto::TPM_APPEND_TRACE_DATA);
+  std::vector<uint8_t> data(size, 0);
+  msg->set_append_trace_data(data.data(), data.size());
+  return stream.SerializeAsArray();
+}
+
+class RpcTest : public ::testing::Test {
+ protected:
+  RpcTest() {
+    rpc_.SetRpcResponseFunction([this](const void* data, uint32_t len) {
+      const auto* ptr = static_cast<const uint8_t*>(data);
+      responses_.insert(responses_.end(), ptr, ptr + len);
+    });
+  }
+
+  // Passes |req| both to HandleCancelRequests() and to OnRpcRequest(), as
+  // the transports do.
+  void Send(const std::vector<uint8_t>& req) {
+    rpc_.HandleCancelRequests(req.data(), req.size());
+    rpc_.OnRpcRequest(req.data(), req.size());
+  }
+
+  // Returns the error of each query result received so far ("" for the
+  // successful batches).
+  std::vector<std::string> TakeQueryErrors() {
+    std::vector<std::string> errors;
+    StreamProto::Decoder stream(responses_.data(), responses_.size());
+    for (auto it = stream.msg(); it; ++it) {
+      RpcProto::Decoder msg(*it);
+      if (msg.has_query_result()) {
+        protos::pbzero::QueryResult::Decoder result(msg.query_result());
+        errors.push_back(result.error().ToStdString());
+      }
+    }
+    responses_.clear();
+    return errors;
+  }
+
+  Rpc rpc_;
+  std::vector<uint8_t> responses_;
+};
+
+TEST_F(RpcTest, CancelBeforeQueryStarts) {
+  // The cancellation is received by the transport before the RPC thread
+  // starts the query.
+  std::vector<uint8_t> cancel = CancelRequest(2, /*query_seq=*/1);
+  rpc_.HandleCancelRequests(cancel.data(), cancel.size());
+  std::vector<uint8_t> query = QueryRequest(1, kEndlessQuery, 60000);
+  rpc_.HandleCancelRequests(query.data(), query.size());
+  rpc_.OnRpcRequest(query.data(), query.size());
+  rpc_.OnRpcRequest(cancel.data(), cancel.size());
+  ASSERT_THAT(TakeQueryErrors(), ElementsAre(HasSubstr("cancelled")));
+
+  // The cancellation doesn't affect the next queries.
+  Send(QueryRequest(3, "SELECT 1"));
+  ASSERT_THAT(TakeQueryErrors(), ElementsAre(""));
+}
+
+TEST_F(RpcTest, CancelFinishedQuery) {
+  Send(QueryRequest(1, "SELECT 1"));
+  ASSERT_THAT(TakeQueryErrors(), ElementsAre(""));
+
+  Send(CancelRequest(2, /*query_seq=*/1));
+  Send(QueryRequest(3, "SELECT 1"));
+  ASSERT_THAT(TakeQueryErrors(), ElementsAre(""));
+}
+
+TEST_F(RpcTest, CancelUnknownQuery) {
+  Send(CancelRequest(1, /*query_seq=*/5));
+  Send(QueryRequest(2, "SELECT 1"));
+  ASSERT_THAT(TakeQueryErrors(), ElementsAre(""));
+}
+
+TEST_F(RpcTest, SeqReset) {
+  // A cancellation of a query of the previous sequence, which never ran...
+  std::vector<uint8_t> cancel = CancelRequest(2, /*query_seq=*/1);
+  rpc_.HandleCancelRequests(cancel.data(), cancel.size());
+
+  // ... must not cancel the query with the same seq in the new sequence.
+  Send(QueryRequest(0, "SELECT 1"));
+  Send(QueryRequest(1, "SELECT 1"));
+  ASSERT_THAT(TakeQueryErrors(), ElementsAre("", ""));
+}
+
+TEST_F(RpcTest, CancelAfterLargeMessageSplitInChunks) {
+  // The cancellation follows a large message (which is skipped without being
+  // buffered) and is received one byte at a time.
+  std::vector<uint8_t> stream = AppendTraceDataRequest(1, 4096);
+  std::vector<uint8_t> cancel = CancelRequest(2, /*query_seq=*/3);
+  stream.insert(stream.end(), cancel.begin(), cancel.end());
+  for (uint8_t byte : stream) {
+    rpc_.HandleCancelRequests(&byte, 1);
+  }
+
+  Send(QueryRequest(3, kEndlessQuery, 60000));
+  ASSERT_THAT(TakeQueryErrors(), ElementsAre(HasSubstr("cancelled")));
+}
+
+TEST_F(RpcTest, CancelRunningQueryFromAnotherThread) {
+  // Mimics httpd and stdiod: the requests are handled on a separate thread
+  // while the thread receiving them handles the cancellations.
+  base::ThreadTaskRunner rpc_thread =
+      base::ThreadTaskRunner::CreateAndStart("RpcTest");
+  auto post_request = [&](std::vector<uint8_t> req) {
+    rpc_.HandleCancelRequests(req.data(), req.size());
+    rpc_thread.PostTask([this, req = std::move(req)] {
+      rpc_.OnRpcRequest(req.data(), req.size());
+    });
+  };
+
+  post_request(QueryRequest(1, kEndlessQuery, 60000));
+  post_request(CancelRequest(2, /*query_seq=*/1));
+  post_request(QueryRequest(3, "SELECT 1"));
+
+  base::WaitableEvent done;
+  rpc_thread.PostTask([&done] { done.Notify(); });
+  done.Wait();
+  ASSERT_THAT(TakeQueryErrors(), ElementsAre(HasSubstr("cancelled"), ""));
+}
+
+}  // namespace
+}  // namespace perfetto::trace_processor
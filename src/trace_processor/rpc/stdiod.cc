@@ -16,16 +16,21 @@
 
 #include "src/trace_processor/rpc/stdiod.h"
 
+#include <condition_variable>
 #include <cstddef>
 #include <cstdint>
 #include <memory>
+#include <mutex>
+#include <string>
 #include <utility>
 
 #include "perfetto/base/build_config.h"
 #include "perfetto/base/logging.h"
 #include "perfetto/base/status.h"
 #include "perfetto/ext/base/file_utils.h"
+#include "perfetto/ext/base/thread_task_runner.h"
 #include "perfetto/ext/base/utils.h"
+#include "perfetto/ext/base/waitable_event.h"
 #include "perfetto/trace_processor/trace_processor.h"
 #include "src/trace_processor/rpc/rpc.h"
 
@@ -39,27 +44,75 @@
 #endif
 
 namespace perfetto::trace_processor {
+namespace {
+
+// The maximum number of bytes read from stdin which have not been processed
+// yet. Stops reading a trace piped into stdin much faster than it can be
+// parsed from using unbounded memory.
+constexpr size_t kMaxPendingBytes = 32 * 1024 * 1024;
+
+// Tracks the bytes read from stdin which have not been processed yet.
+class PendingBytes {
+ public:
+  void Add(size_t bytes) {
+    std::unique_lock<std::mutex> lock(mutex_);
+    cv_.wait(lock, [this] { return pending_ < kMaxPendingBytes; });
+    pending_ += bytes;
+  }
+  void Remove(size_t bytes) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    pending_ -= bytes;
+    cv_.notify_all();
+  }
+
+ private:
+  std::mutex mutex_;
+  std::condition_variable cv_;
+  size_t pending_ = 0;
+};
+
+}  // namespace
 
 base::Status RunStdioRpcServer(std::unique_ptr<TraceProcessor> tp,
-                               bool is_preloaded_eof) {
-  Rpc rpc(std::move(tp), is_preloaded_eof);
+                               bool is_preloaded_eof,
+                               const Config& config) {
+  Rpc rpc(std::move(tp), is_preloaded_eof, config);
+  rpc.SetRpcResponseFunction([](const void* ptr, uint32_t size) {
+    ssize_t ret = base::WriteAll(STDOUT_FILENO, ptr, size);
+    if (ret < 0 || static_cast<uint32_t>(ret) != size) {
+      PERFETTO_FATAL("Failed to write response");
+    }
+  });
+
+  // The requests are processed on a separate thread so that stdin can still
+  // be read, and TPM_CANCEL_QUERY requests handled, while a query runs.
+  PendingBytes pending;
+  base::ThreadTaskRunner rpc_thread =
+      base::ThreadTaskRunner::CreateAndStart("TraceProcessorRpc");
+  auto wait_for_pending_requests = [&rpc_thread] {
+    base::WaitableEvent done;
+    rpc_thread.PostTask([&done] { done.Notify(); });
+    done.Wait();
+  };
+
   char buffer[4096];
   for (;;) {
     ssize_t ret = base::Read(STDIN_FILENO, buffer, base::ArraySize(buffer));
     if (ret == -1) {
+      wait_for_pending_requests();
       return base::ErrStatus("Failed while reading the buffer");
     }
     if (ret == 0) {
+      wait_for_pending_requests();
       return base::OkStatus();
     }
-    rpc.SetRpcResponseFunction([](const void* ptr, uint32_t size) {
-      ssize_t ret = base::WriteAll(STDOUT_FILENO, ptr, size);
-      if (ret < 0 || static_cast<uint32_t>(ret) != size) {
-        PERFETTO_FATAL("Failed to write response");
-      }
+    auto size = static_cast<size_t>(ret);
+    rpc.HandleCancelRequests(buffer, size);
+    pending.Add(size);
+    rpc_thread.PostTask([&rpc, &pending, req = std::string(buffer, size)] {
+      rpc.OnRpcRequest(req.data(), req.size());
+      pending.Remove(req.size());
     });
-    rpc.OnRpcRequest(buffer, static_cast<size_t>(ret));
-    rpc.SetRpcResponseFunction(nullptr);
   }
 }
 
@@ -19,6 +19,7 @@
 
 #include <memory>
 #include "perfetto/base/status.h"
+#include "perfetto/trace_processor/basic_types.h"
 
 namespace perfetto {
 namespace trace_processor {
@@ -27,8 +28,11 @@ class TraceProcessor;
 
 // Starts a RPC server that handles requests using protobuf-over-stdio.
 // Returns when the server completes.
+// |config| holds the options of the instances created by the server which
+// cannot be set by the client (see Rpc).
 base::Status RunStdioRpcServer(std::unique_ptr<TraceProcessor>,
-                               bool is_preloaded_eof);
+                               bool is_preloaded_eof,
+                               const Config& config);
 
 }  // namespace trace_processor
 }  // namespace perfetto
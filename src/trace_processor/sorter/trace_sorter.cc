@@ -32,6 +32,7 @@
 #include "src/trace_processor/importers/art_method/art_method_event.h"
 #include "src/trace_processor/importers/common/parser_types.h"
 #include "src/trace_processor/importers/common/trace_parser.h"
+#include "src/trace_processor/importers/ctf/ctf_event.h"
 #include "src/trace_processor/importers/fuchsia/fuchsia_record.h"
 #include "src/trace_processor/importers/gecko/gecko_event.h"
 #include "src/trace_processor/importers/instruments/row.h"
@@ -295,6 +296,10 @@ void TraceSorter::ParseTracePacket(TraceProcessorContext& context,
           event.ts,
           token_buffer_.Extract<perf_text_importer::PerfTextEvent>(id));
       return;
+    case TimestampedEvent::Type::kCtfEvent:
+      context.ctf_parser->ParseCtfEvent(
+          event.ts, token_buffer_.Extract<ctf_importer::CtfEvent>(id));
+      return;
     case TimestampedEvent::Type::kInlineSchedSwitch:
     case TimestampedEvent::Type::kInlineSchedWaking:
     case TimestampedEvent::Type::kEtwEvent:
@@ -330,6 +335,7 @@ void TraceSorter::ParseEtwPacket(TraceProcessorContext& context,
     case TimestampedEvent::Type::kGeckoEvent:
     case TimestampedEvent::Type::kArtMethodEvent:
     case TimestampedEvent::Type::kPerfTextEvent:
+    case TimestampedEvent::Type::kCtfEvent:
       PERFETTO_FATAL("Invalid event type");
   }
   PERFETTO_FATAL("For GCC");
@@ -367,6 +373,7 @@ void TraceSorter::ParseFtracePacket(TraceProcessorContext& context,
     case TimestampedEvent::Type::kGeckoEvent:
     case TimestampedEvent::Type::kArtMethodEvent:
     case TimestampedEvent::Type::kPerfTextEvent:
+    case TimestampedEvent::Type::kCtfEvent:
       PERFETTO_FATAL("Invalid event type");
   }
   PERFETTO_FATAL("For GCC");
@@ -429,6 +436,9 @@ void TraceSorter::ExtractAndDiscardTokenizedObject(
       base::ignore_result(
           token_buffer_.Extract<perf_text_importer::PerfTextEvent>(id));
       return;
+    case TimestampedEvent::Type::kCtfEvent:
+      base::ignore_result(token_buffer_.Extract<ctf_importer::CtfEvent>(id));
+      return;
   }
   PERFETTO_FATAL("For GCC");
 }
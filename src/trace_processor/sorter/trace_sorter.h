@@ -41,6 +41,7 @@
 #include "src/trace_processor/importers/art_method/art_method_event.h"
 #include "src/trace_processor/importers/common/parser_types.h"
 #include "src/trace_processor/importers/common/trace_parser.h"
+#include "src/trace_processor/importers/ctf/ctf_event.h"
 #include "src/trace_processor/importers/fuchsia/fuchsia_record.h"
 #include "src/trace_processor/importers/gecko/gecko_event.h"
 #include "src/trace_processor/importers/instruments/row.h"
@@ -247,6 +248,11 @@ class TraceSorter {
                          event);
   }
 
+  void PushCtfEvent(int64_t timestamp, ctf_importer::CtfEvent event) {
+    AppendNonFtraceEvent(timestamp, TimestampedEvent::Type::kCtfEvent,
+                         std::move(event));
+  }
+
   void PushEtwEvent(uint32_t cpu,
                     int64_t timestamp,
                     TraceBlobView tbv,
@@ -366,7 +372,8 @@ class TraceSorter {
       kGeckoEvent,
       kArtMethodEvent,
       kPerfTextEvent,
-      kMax = kPerfTextEvent,
+      kCtfEvent,
+      kMax = kCtfEvent,
     };
 
     // Number of bits required to store the max element in |Type|.
@@ -247,6 +247,12 @@ void* SqliteEngine::SetRollbackCallback(RollbackCallback callback, void* ctx) {
   return sqlite3_rollback_hook(db_.get(), callback, ctx);
 }
 
+void SqliteEngine::SetProgressCallback(int instruction_count,
+                                       ProgressCallback callback,
+                                       void* ctx) {
+  sqlite3_progress_handler(db_.get(), instruction_count, callback, ctx);
+}
+
 SqliteEngine::PreparedStatement::PreparedStatement(ScopedStmt stmt,
                                                    SqlSource source)
     : stmt_(std::move(stmt)),
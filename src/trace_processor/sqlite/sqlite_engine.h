@@ -147,6 +147,17 @@ class SqliteEngine {
   using RollbackCallback = void(void*);
   void* SetRollbackCallback(RollbackCallback callback, void* ctx);
 
+  // Sets a callback to be called periodically, roughly every
+  // |instruction_count| virtual machine instructions, while statements are
+  // running. If the callback returns non-zero, the running statement fails
+  // with SQLITE_INTERRUPT.
+  //
+  // See https://www.sqlite.org/c3ref/progress_handler.html for more details.
+  using ProgressCallback = int(void*);
+  void SetProgressCallback(int instruction_count,
+                           ProgressCallback callback,
+                           void* ctx);
+
   sqlite3* db() const { return db_.get(); }
 
  private:
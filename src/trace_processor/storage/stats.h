@@ -250,6 +250,7 @@ namespace perfetto::trace_processor::stats {
       "before they were closed in reality"),                                   \
   F(tokenizer_skipped_packets,            kSingle,  kInfo,     kAnalysis, ""), \
   F(vmstat_unknown_keys,                  kSingle,  kError,    kAnalysis, ""), \
+  F(netstat_unknown_keys,                 kSingle,  kError,    kAnalysis, ""), \
   F(psi_unknown_resource,                 kSingle,  kError,    kAnalysis, ""), \
   F(vulkan_allocations_invalid_string_id,                                      \
                                           kSingle,  kError,    kTrace,    ""), \
@@ -312,6 +313,8 @@ namespace perfetto::trace_processor::stats {
        "The file to be parsed can't be opened. This can happend when "         \
        "the file name is not found or no permission to access the file"),      \
   F(compact_sched_has_parse_errors,       kSingle,  kError,    kTrace,    ""), \
+  F(syscall_latency_has_parse_errors,     kSingle,  kError,    kTrace,         \
+      "A syscall latency histogram had malformed buckets and was dropped."),   \
   F(misplaced_end_event,                  kSingle,  kDataLoss, kAnalysis, ""), \
   F(truncated_sys_write_duration,         kSingle,  kInfo,     kAnalysis,      \
       "Count of sys_write slices that have a truncated duration to resolve "   \
@@ -567,7 +570,35 @@ namespace perfetto::trace_processor::stats {
   F(perf_text_importer_sample_no_frames,        kSingle,  kError,  kTrace,     \
       "A perf sample was encountered that has no frames. This can happen "     \
       "if the kernel is unable to unwind the stack while sampling. Check "     \
-      "Linux kernel documentation for causes of this and potential fixes.")
+      "Linux kernel documentation for causes of this and potential fixes."),   \
+  F(ctf_events_discarded,                       kSingle,  kDataLoss, kTrace,   \
+      "Number of events the tracer reported as discarded in the packets of a " \
+      "CTF (e.g. LTTng) trace. This usually happens when the tracing buffers " \
+      "are too small or the consumer is too slow: increase the size of the "   \
+      "sub-buffers of the LTTng channels."),                                   \
+  F(ctf_stream_without_metadata,                kSingle,  kError,  kTrace,     \
+      "A CTF stream file was found before the metadata file describing it. "   \
+      "Make sure the metadata file is in the same directory as the stream "    \
+      "files when opening a CTF trace."),                                      \
+  F(ctf_ust_event_without_tid,                  kSingle,  kInfo,   kTrace,     \
+      "A userspace CTF event did not have the thread id in its context: the "  \
+      "event was put on a global track. Add the vtid and vpid contexts to "    \
+      "the LTTng channel to attribute events to threads."),                    \
+  F(folded_stack_parse_errors,                  kSingle,  kError,  kTrace,     \
+      "A line of a folded stack file could not be parsed and was skipped. "    \
+      "Each line should contain a list of frames separated by ';', followed "  \
+      "by a space and an integer sample count."),                              \
+  F(folded_stack_count_too_large,               kSingle,  kDataLoss, kTrace,   \
+      "A line of a folded stack file had a sample count larger than 1000000 "  \
+      "and was skipped. Folded stacks weighted by e.g. bytes or microseconds " \
+      "are not supported: each sample is imported as a row."),                 \
+  F(otlp_invalid_spans,                         kSingle,  kError,  kTrace,     \
+      "An OTLP span was skipped because it did not have a start time or its "  \
+      "timestamps could not be converted to the trace time."),                 \
+  F(otlp_unresolved_links,                      kSingle,  kInfo,   kTrace,     \
+      "An OTLP span link pointing to a span which is not in the trace could "  \
+      "not be turned into a flow. The ids of the linked span are still "       \
+      "available in the args of the slice.")
 // clang-format on
 
 enum Type {
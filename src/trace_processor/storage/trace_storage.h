@@ -388,6 +388,29 @@ class TraceStorage {
   const tables::FlowTable& flow_table() const { return flow_table_; }
   tables::FlowTable* mutable_flow_table() { return &flow_table_; }
 
+  const tables::FutexContentionTable& futex_contention_table() const {
+    return futex_contention_table_;
+  }
+  tables::FutexContentionTable* mutable_futex_contention_table() {
+    return &futex_contention_table_;
+  }
+
+  const tables::SyscallLatencyHistogramTable& syscall_latency_histogram_table()
+      const {
+    return syscall_latency_histogram_table_;
+  }
+  tables::SyscallLatencyHistogramTable*
+  mutable_syscall_latency_histogram_table() {
+    return &syscall_latency_histogram_table_;
+  }
+
+  const tables::SyscallArgSampleTable& syscall_arg_sample_table() const {
+    return syscall_arg_sample_table_;
+  }
+  tables::SyscallArgSampleTable* mutable_syscall_arg_sample_table() {
+    return &syscall_arg_sample_table_;
+  }
+
   const VirtualTrackSlices& virtual_track_slices() const {
     return virtual_track_slices_;
   }
@@ -440,6 +463,7 @@ class TraceStorage {
   }
 
   const StatsMap& stats() const { return stats_; }
+  StatsMap* mutable_stats() { return &stats_; }
 
   const tables::MetadataTable& metadata_table() const {
     return metadata_table_;
@@ -509,6 +533,15 @@ class TraceStorage {
     return &heap_profile_allocation_table_;
   }
 
+  const tables::HeapProfileUnreachableAllocationTable&
+  heap_profile_unreachable_allocation_table() const {
+    return heap_profile_unreachable_allocation_table_;
+  }
+  tables::HeapProfileUnreachableAllocationTable*
+  mutable_heap_profile_unreachable_allocation_table() {
+    return &heap_profile_unreachable_allocation_table_;
+  }
+
   const tables::PackageListTable& package_list_table() const {
     return package_list_table_;
   }
@@ -1084,6 +1117,15 @@ class TraceStorage {
 
   tables::SpuriousSchedWakeupTable spurious_sched_wakeup_table_{&string_pool_};
 
+  // Futex waits paired with the wakes which ended them.
+  tables::FutexContentionTable futex_contention_table_{&string_pool_};
+
+  // Syscall latency histograms and argument samples aggregated in the kernel
+  // by the linux.syscall_latency data source.
+  tables::SyscallLatencyHistogramTable syscall_latency_histogram_table_{
+      &string_pool_};
+  tables::SyscallArgSampleTable syscall_arg_sample_table_{&string_pool_};
+
   // Additional attributes for virtual track slices (sub-type of
   // NestableSlices).
   VirtualTrackSlices virtual_track_slices_;
@@ -1118,6 +1160,8 @@ class TraceStorage {
       &string_pool_};
   tables::HeapProfileAllocationTable heap_profile_allocation_table_{
       &string_pool_};
+  tables::HeapProfileUnreachableAllocationTable
+      heap_profile_unreachable_allocation_table_{&string_pool_};
   tables::CpuProfileStackSampleTable cpu_profile_stack_sample_table_{
       &string_pool_};
   tables::PerfSessionTable perf_session_table_{&string_pool_};
@@ -681,6 +681,46 @@ HEAP_PROFILE_ALLOCATION_TABLE = Table(
                 ''''''
         }))
 
+HEAP_PROFILE_UNREACHABLE_ALLOCATION_TABLE = Table(
+    python_module=__file__,
+    class_name='HeapProfileUnreachableAllocationTable',
+    sql_name='heap_profile_unreachable_allocation',
+    columns=[
+        C('ts', CppInt64()),
+        C('upid', CppUint32()),
+        C('heap_name', CppString()),
+        C('callsite_id', CppTableId(STACK_PROFILE_CALLSITE_TABLE)),
+        C('count', CppInt64()),
+        C('size', CppInt64()),
+    ],
+    tabledoc=TableDoc(
+        doc='''
+          Allocations that happened at a callsite and that heapprofd found
+          unreachable, i.e. not referenced anymore and likely leaked.
+
+          This is generated by heapprofd when configured with leak_detection.
+          Like heap_profile_allocation, each row is the change since the
+          previous dump, so the sum of the rows up to a timestamp is the
+          unreachable memory at that time.
+        ''',
+        group='Callstack profilers',
+        columns={
+            'ts':
+                '''The timestamp of the dump.''',
+            'upid':
+                '''The unique PID of the allocating process.''',
+            'heap_name':
+                '''The heap of the allocations.''',
+            'callsite_id':
+                '''The callsite the allocations happened at.''',
+            'count':
+                '''Change in the number of unreachable allocations at this
+                callsite since the previous dump.''',
+            'size':
+                '''Change in the size of the unreachable allocations at this
+                callsite since the previous dump.''',
+        }))
+
 EXPERIMENTAL_FLAMEGRAPH_TABLE = Table(
     python_module=__file__,
     class_name='ExperimentalFlamegraphTable',
@@ -1090,6 +1130,7 @@ ALL_TABLES = [
     HEAP_GRAPH_REFERENCE_TABLE,
     INSTRUMENTS_SAMPLE_TABLE,
     HEAP_PROFILE_ALLOCATION_TABLE,
+    HEAP_PROFILE_UNREACHABLE_ALLOCATION_TABLE,
     PACKAGE_LIST_TABLE,
     PERF_SAMPLE_TABLE,
     PERF_SESSION_TABLE,
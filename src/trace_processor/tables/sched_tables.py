@@ -15,6 +15,7 @@
 
 from python.generators.trace_processor_table.public import Column as C
 from python.generators.trace_processor_table.public import CppAccessDuration
+from python.generators.trace_processor_table.public import ColumnDoc
 from python.generators.trace_processor_table.public import ColumnFlag
 from python.generators.trace_processor_table.public import CppAccess
 from python.generators.trace_processor_table.public import CppInt32
@@ -29,6 +30,7 @@ from python.generators.trace_processor_table.public import TableDoc
 from python.generators.trace_processor_table.public import WrappingSqlView
 
 from src.trace_processor.tables.metadata_tables import CPU_TABLE
+from src.trace_processor.tables.slice_tables import SLICE_TABLE
 
 SCHED_SLICE_TABLE = Table(
     python_module=__file__,
@@ -220,9 +222,181 @@ THREAD_STATE_TABLE = Table(
                 ''',
         }))
 
+FUTEX_CONTENTION_TABLE = Table(
+    python_module=__file__,
+    class_name='FutexContentionTable',
+    sql_name='futex_contention',
+    columns=[
+        C('ts', CppInt64(), cpp_access=CppAccess.READ),
+        C('dur', CppInt64(), cpp_access=CppAccess.READ),
+        C('utid', CppUint32(), cpp_access=CppAccess.READ),
+        C('address', CppInt64(), cpp_access=CppAccess.READ),
+        C('op', CppString(), cpp_access=CppAccess.READ),
+        C('ret', CppInt64(), cpp_access=CppAccess.READ),
+        C('wait_slice_id', CppOptional(CppTableId(SLICE_TABLE))),
+        C('waker_utid', CppOptional(CppUint32()), cpp_access=CppAccess.READ),
+        C('wake_ts', CppOptional(CppInt64()), cpp_access=CppAccess.READ),
+        C('wake_slice_id',
+          CppOptional(CppTableId(SLICE_TABLE)),
+          cpp_access=CppAccess.READ),
+    ],
+    tabledoc=TableDoc(
+        doc='''
+          This table contains the futex waits of userspace threads (e.g. on
+          contended pthread mutexes and condition variables), paired with the
+          futex wake which ended them. It is populated from the sys_futex
+          syscalls recorded by the "raw_syscalls" ftrace events (see
+          |FtraceConfig.syscall_events|). Waits which returned immediately
+          because the futex value had already changed are not included.
+
+          The wake is matched by futex address: it is the earliest wake of
+          the same address which started during the wait and didn't already
+          end another wait, or the latest one if they all did. For private
+          futexes the address is only matched within the same process. Shared
+          futexes are matched by address across all processes, so a futex
+          mapped at different addresses by two processes isn't matched. The
+          futex slices of the waker and of the waiter are connected by a flow.
+        ''',
+        group='Events',
+        columns={
+            'ts':
+                'The timestamp at the start of the wait (in nanoseconds).',
+            'dur':
+                'The duration of the wait (in nanoseconds).',
+            'utid':
+                '''The waiting thread's unique id in the trace.''',
+            'address':
+                'The userspace address of the futex.',
+            'op':
+                '''
+                  The futex operation of the wait, e.g. "FUTEX_WAIT" or
+                  "FUTEX_LOCK_PI".
+                ''',
+            'ret':
+                '''
+                  The return value of the syscall: 0 if the thread was woken,
+                  a negated errno otherwise (e.g. -110 for a timeout).
+                ''',
+            'wait_slice_id':
+                'The id of the sys_futex slice of the waiting thread.',
+            'waker_utid':
+                '''
+                  The unique thread id of the thread which woke the waiter, if
+                  known.
+                ''',
+            'wake_ts':
+                '''
+                  The timestamp at which the waker entered the futex wake
+                  syscall.
+                ''',
+            'wake_slice_id':
+                'The id of the sys_futex slice of the waking thread.',
+        }))
+
+SYSCALL_LATENCY_HISTOGRAM_TABLE = Table(
+    python_module=__file__,
+    class_name='SyscallLatencyHistogramTable',
+    sql_name='syscall_latency_histogram',
+    columns=[
+        C('ts', CppInt64(), cpp_access=CppAccess.READ),
+        C('utid', CppUint32(), cpp_access=CppAccess.READ),
+        C('syscall_id', CppUint32(), cpp_access=CppAccess.READ),
+        C('name', CppString(), cpp_access=CppAccess.READ),
+        C('min_dur', CppInt64(), cpp_access=CppAccess.READ),
+        C('max_dur', CppInt64(), cpp_access=CppAccess.READ),
+        C('count', CppInt64(), cpp_access=CppAccess.READ),
+    ],
+    tabledoc=TableDoc(
+        doc='''
+          This table contains the syscall latency histograms aggregated in the
+          kernel by the "linux.syscall_latency" data source. Each row is a
+          bucket of the histogram of a syscall of a thread: the number of
+          calls which completed in the period ending at |ts| and which took
+          between |min_dur| and |max_dur|.
+
+          The sum of |count| over all the rows of a thread and syscall is the
+          total number of calls over the trace.
+        ''',
+        group='Events',
+        columns={
+            'ts':
+                '''
+                  The timestamp at which the histogram was dumped (in
+                  nanoseconds). The bucket counts the calls which completed
+                  since the previous dump.
+                ''',
+            'utid':
+                '''The calling thread's unique id in the trace.''',
+            'syscall_id':
+                '''
+                  The syscall number, for the architecture of the traced
+                  machine.
+                ''',
+            'name':
+                'The name of the syscall, e.g. "sys_read".',
+            'min_dur':
+                '''
+                  The lower bound (inclusive) of the latency of the calls of
+                  the bucket (in nanoseconds).
+                ''',
+            'max_dur':
+                '''
+                  The upper bound (exclusive) of the latency of the calls of
+                  the bucket (in nanoseconds).
+                ''',
+            'count':
+                'The number of calls in the bucket.',
+        }))
+
+SYSCALL_ARG_SAMPLE_TABLE = Table(
+    python_module=__file__,
+    class_name='SyscallArgSampleTable',
+    sql_name='syscall_arg_sample',
+    columns=[
+        C('ts', CppInt64(), cpp_access=CppAccess.READ),
+        C('utid', CppUint32(), cpp_access=CppAccess.READ),
+        C('syscall_id', CppUint32(), cpp_access=CppAccess.READ),
+        C('name', CppString(), cpp_access=CppAccess.READ),
+        C(
+            'arg_set_id',
+            CppOptional(CppUint32()),
+            cpp_access=CppAccess.READ_AND_LOW_PERF_WRITE,
+        ),
+    ],
+    tabledoc=TableDoc(
+        doc='''
+          This table contains the syscalls whose arguments were sampled by the
+          "linux.syscall_latency" data source (see
+          |SyscallLatencyConfig.arg_sampling_syscalls|).
+        ''',
+        group='Events',
+        columns={
+            'ts':
+                'The timestamp of the syscall entry (in nanoseconds).',
+            'utid':
+                '''The calling thread's unique id in the trace.''',
+            'syscall_id':
+                '''
+                  The syscall number, for the architecture of the traced
+                  machine.
+                ''',
+            'name':
+                'The name of the syscall, e.g. "sys_openat".',
+            'arg_set_id':
+                ColumnDoc(
+                    doc='''
+                      The raw values of the arguments of the syscall, with the
+                      keys "args[0]" to "args[5]".
+                    ''',
+                    joinable='args.arg_set_id'),
+        }))
+
 # Keep this list sorted.
 ALL_TABLES = [
+    FUTEX_CONTENTION_TABLE,
     SCHED_SLICE_TABLE,
     SPURIOUS_SCHED_WAKEUP_TABLE,
+    SYSCALL_ARG_SAMPLE_TABLE,
+    SYSCALL_LATENCY_HISTOGRAM_TABLE,
     THREAD_STATE_TABLE,
 ]
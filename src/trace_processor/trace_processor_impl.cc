@@ -23,6 +23,7 @@
 #include <limits>
 #include <memory>
 #include <optional>
+#include <set>
 #include <string>
 #include <unordered_map>
 #include <utility>
@@ -34,12 +35,16 @@
 #include "perfetto/base/thread_utils.h"
 #include "perfetto/base/time.h"
 #include "perfetto/ext/base/clock_snapshots.h"
+#include "perfetto/ext/base/file_utils.h"
 #include "perfetto/ext/base/flat_hash_map.h"
+#include "perfetto/ext/base/scoped_mmap.h"
+#include "perfetto/ext/base/sha256.h"
 #include "perfetto/ext/base/small_vector.h"
 #include "perfetto/ext/base/status_macros.h"
 #include "perfetto/ext/base/status_or.h"
 #include "perfetto/ext/base/string_splitter.h"
 #include "perfetto/ext/base/string_utils.h"
+#include "perfetto/ext/base/version.h"
 #include "perfetto/protozero/scattered_heap_buffer.h"
 #include "perfetto/public/compiler.h"
 #include "perfetto/trace_processor/basic_types.h"
@@ -53,12 +58,18 @@
 #include "src/trace_processor/importers/archive/gzip_trace_parser.h"
 #include "src/trace_processor/importers/archive/tar_trace_reader.h"
 #include "src/trace_processor/importers/archive/zip_trace_reader.h"
+#include "src/trace_processor/importers/archive/zstd_trace_parser.h"
 #include "src/trace_processor/importers/art_hprof/art_hprof_parser.h"
 #include "src/trace_processor/importers/art_method/art_method_parser_impl.h"
 #include "src/trace_processor/importers/art_method/art_method_tokenizer.h"
 #include "src/trace_processor/importers/common/clock_tracker.h"
+#include "src/trace_processor/importers/common/metadata_tracker.h"
+#include "src/trace_processor/importers/common/system_info_tracker.h"
 #include "src/trace_processor/importers/common/trace_file_tracker.h"
 #include "src/trace_processor/importers/common/trace_parser.h"
+#include "src/trace_processor/importers/ctf/ctf_trace_parser_impl.h"
+#include "src/trace_processor/importers/ctf/ctf_trace_tokenizer.h"
+#include "src/trace_processor/importers/folded_stack/folded_stack_trace_reader.h"
 #include "src/trace_processor/importers/fuchsia/fuchsia_trace_parser.h"
 #include "src/trace_processor/importers/fuchsia/fuchsia_trace_tokenizer.h"
 #include "src/trace_processor/importers/gecko/gecko_trace_parser_impl.h"
@@ -67,6 +78,7 @@
 #include "src/trace_processor/importers/json/json_trace_tokenizer.h"
 #include "src/trace_processor/importers/json/json_utils.h"
 #include "src/trace_processor/importers/ninja/ninja_log_parser.h"
+#include "src/trace_processor/importers/otlp/otlp_trace_reader.h"
 #include "src/trace_processor/importers/perf/perf_data_tokenizer.h"
 #include "src/trace_processor/importers/perf/perf_event.h"
 #include "src/trace_processor/importers/perf/perf_tracker.h"
@@ -84,6 +96,7 @@
 #include "src/trace_processor/metrics/sql/amalgamated_sql_metrics.h"
 #include "src/trace_processor/perfetto_sql/engine/dataframe_shared_storage.h"
 #include "src/trace_processor/perfetto_sql/engine/perfetto_sql_engine.h"
+#include "src/trace_processor/perfetto_sql/engine/sql_table_cache.h"
 #include "src/trace_processor/perfetto_sql/engine/table_pointer_module.h"
 #include "src/trace_processor/perfetto_sql/generator/structured_query_generator.h"
 #include "src/trace_processor/perfetto_sql/intrinsics/functions/base64.h"
@@ -129,10 +142,12 @@
 #include "src/trace_processor/sqlite/sql_source.h"
 #include "src/trace_processor/sqlite/sql_stats_table.h"
 #include "src/trace_processor/sqlite/stats_table.h"
+#include "src/trace_processor/storage/metadata.h"
 #include "src/trace_processor/storage/trace_storage.h"
 #include "src/trace_processor/tp_metatrace.h"
 #include "src/trace_processor/trace_processor_storage_impl.h"
 #include "src/trace_processor/trace_reader_registry.h"
+#include "src/trace_processor/trace_snapshot.h"
 #include "src/trace_processor/trace_summary/summary.h"
 #include "src/trace_processor/trace_summary/trace_summary.descriptor.h"
 #include "src/trace_processor/types/trace_processor_context.h"
@@ -143,6 +158,7 @@
 #include "src/trace_processor/util/regex.h"
 #include "src/trace_processor/util/sql_modules.h"
 #include "src/trace_processor/util/trace_type.h"
+#include "src/trace_processor/util/zstd_utils.h"
 
 #include "protos/perfetto/trace/clock_snapshot.pbzero.h"
 #include "protos/perfetto/trace/perfetto/perfetto_metatrace.pbzero.h"
@@ -453,6 +469,39 @@ std::pair<int64_t, int64_t> GetTraceTimestampBoundsNs(
   return std::make_pair(start_ns, end_ns);
 }
 
+// Returns a string identifying the contents of the stdlib: the version string
+// is not enough as it is not available in all the builds.
+std::string GetStdlibVersionForTableCache() {
+  base::Sha256Hasher hasher;
+  hasher.Update(base::GetVersionString());
+  for (const auto& file_to_sql : stdlib::kFileToSql) {
+    hasher.Update(file_to_sql.path);
+    hasher.Update(file_to_sql.sql);
+  }
+  return hasher.HexDigest();
+}
+
+// Returns the string identifying the trace for the purpose of the table cache
+// or an empty string if the trace cannot be identified.
+std::string GetTableCacheTraceKey(const std::string& content_hash,
+                                  uint64_t size,
+                                  const Config& config) {
+  if (content_hash.empty()) {
+    return "";
+  }
+  // The options changing how the trace is parsed also change the contents of
+  // the tables.
+  base::StackString<256> key(
+      "%s:%" PRIu64 ":%d:%d:%d:%d:%d:%d:%d", content_hash.c_str(), size,
+      static_cast<int>(config.parsing_mode),
+      static_cast<int>(config.sorting_mode), config.ingest_ftrace_in_raw_table,
+      static_cast<int>(config.drop_ftrace_data_before),
+      static_cast<int>(config.soft_drop_ftrace_data_before),
+      static_cast<int>(config.drop_track_event_data_before),
+      config.analyze_trace_proto_content);
+  return key.ToStdString();
+}
+
 }  // namespace
 
 TraceProcessorImpl::TraceProcessorImpl(const Config& cfg)
@@ -501,6 +550,11 @@ TraceProcessorImpl::TraceProcessorImpl(const Config& cfg)
     context_.reader_registry->RegisterTraceReader<ZipTraceReader>(kZipFile);
   }
 
+  if constexpr (util::IsZstdSupported()) {
+    context_.reader_registry->RegisterTraceReader<ZstdTraceParser>(
+        kZstdTraceType);
+  }
+
   if constexpr (json::IsJsonSupported()) {
     context_.reader_registry->RegisterTraceReader<JsonTraceTokenizer>(
         kJsonTraceType);
@@ -528,6 +582,18 @@ TraceProcessorImpl::TraceProcessorImpl(const Config& cfg)
   context_.perf_text_parser =
       std::make_unique<perf_text_importer::PerfTextTraceParserImpl>(&context_);
 
+  context_.reader_registry
+      ->RegisterTraceReader<ctf_importer::CtfTraceTokenizer>(kCtfTraceType);
+  context_.ctf_parser =
+      std::make_unique<ctf_importer::CtfTraceParserImpl>(&context_);
+
+  context_.reader_registry
+      ->RegisterTraceReader<folded_stack_importer::FoldedStackTraceReader>(
+          kFoldedStackTraceType);
+
+  context_.reader_registry
+      ->RegisterTraceReader<otlp_importer::OtlpTraceReader>(kOtlpTraceType);
+
   context_.reader_registry->RegisterTraceReader<TarTraceReader>(kTarTraceType);
 
 #if PERFETTO_BUILDFLAG(PERFETTO_ENABLE_ETM_IMPORTER)
@@ -563,6 +629,15 @@ TraceProcessorImpl::TraceProcessorImpl(const Config& cfg)
 
   // Register stdlib packages.
   auto packages = GetStdlibPackages();
+  if (!config_.table_cache_dir.empty()) {
+    std::set<std::string> stdlib_packages;
+    for (auto package = packages.GetIterator(); package; ++package) {
+      stdlib_packages.insert(package.key());
+    }
+    table_cache_ = std::make_unique<SqlTableCache>(
+        config_.table_cache_dir, config_.table_cache_max_size_bytes,
+        GetStdlibVersionForTableCache(), std::move(stdlib_packages));
+  }
   for (auto package = packages.GetIterator(); package; ++package) {
     registered_sql_packages_.emplace_back<SqlPackage>(
         {/*name=*/package.key(),
@@ -572,7 +647,7 @@ TraceProcessorImpl::TraceProcessorImpl(const Config& cfg)
 
   engine_ = InitPerfettoSqlEngine(
       &context_, context_.storage.get(), config_, &dataframe_shared_storage_,
-      registered_sql_packages_, sql_metrics_, &metrics_descriptor_pool_,
+      table_cache_.get(), registered_sql_packages_, sql_metrics_, &metrics_descriptor_pool_,
       &proto_fn_name_to_path_, this, notify_eof_called_);
 
   sqlite_objects_post_prelude_ = engine_->SqliteRegisteredObjectCount();
@@ -597,6 +672,9 @@ TraceProcessorImpl::~TraceProcessorImpl() = default;
 
 base::Status TraceProcessorImpl::Parse(TraceBlobView blob) {
   bytes_parsed_ += blob.size();
+  if (table_cache_) {
+    trace_hasher_.Update(blob.data(), blob.size());
+  }
   return TraceProcessorStorageImpl::Parse(std::move(blob));
 }
 
@@ -647,6 +725,13 @@ base::Status TraceProcessorImpl::NotifyEndOfFile() {
   BuildBoundsTable(engine_->sqlite_engine()->db(),
                    GetTraceTimestampBoundsNs(*context_.storage));
 
+  // The tables can only be cached once the trace has been fully parsed.
+  if (table_cache_) {
+    table_cache_->SetTraceKey(
+        GetTableCacheTraceKey(trace_hasher_.HexDigest(), bytes_parsed_,
+                              config_));
+  }
+
   TraceProcessorStorageImpl::DestroyContext();
   context_.storage->ShrinkToFitTables();
 
@@ -662,18 +747,38 @@ base::Status TraceProcessorImpl::NotifyEndOfFile() {
 // =================================================================
 
 Iterator TraceProcessorImpl::ExecuteQuery(const std::string& sql) {
+  return ExecuteQuery(sql, QueryOptions());
+}
+
+Iterator TraceProcessorImpl::ExecuteQuery(const std::string& sql,
+                                          const QueryOptions& options) {
   PERFETTO_TP_TRACE(metatrace::Category::API_TIMELINE, "EXECUTE_QUERY",
                     [&](metatrace::Record* r) { r->AddArg("query", sql); });
 
+  QueryBudget::Limits limits;
+  limits.timeout_ms =
+      options.timeout_ms ? options.timeout_ms : config_.query_timeout_ms;
+  limits.max_table_memory_bytes = options.max_table_memory_bytes
+                                      ? options.max_table_memory_bytes
+                                      : config_.query_max_table_memory_bytes;
+  limits.cancel_flag = options.cancel_flag;
+  QueryBudget* budget = engine_->query_budget();
+  budget->BeginQuery(limits);
+
   uint32_t sql_stats_row =
       context_.storage->mutable_sql_stats()->RecordQueryBegin(
           sql, base::GetWallTimeNs().count());
   std::string non_breaking_sql = base::ReplaceAll(sql, "\u00A0", " ");
   base::StatusOr<PerfettoSqlEngine::ExecutionResult> result =
-      engine_->ExecuteUntilLastStatement(
-          SqlSource::FromExecuteQuery(std::move(non_breaking_sql)));
+      budget->exceeded()
+          ? budget->status()
+          : engine_->ExecuteUntilLastStatement(
+                SqlSource::FromExecuteQuery(std::move(non_breaking_sql)));
+  if (!result.ok() && budget->exceeded()) {
+    result = budget->status();
+  }
   std::unique_ptr<IteratorImpl> impl(
-      new IteratorImpl(this, std::move(result), sql_stats_row));
+      new IteratorImpl(this, std::move(result), sql_stats_row, budget));
   return Iterator(std::move(impl));
 }
 
@@ -689,6 +794,9 @@ base::Status TraceProcessorImpl::RegisterSqlPackage(SqlPackage sql_package) {
         name.c_str());
   }
   ASSIGN_OR_RETURN(auto new_package, ToRegisteredPackage(sql_package));
+  if (table_cache_) {
+    table_cache_->OnPackageRegistered(name);
+  }
   registered_sql_packages_.emplace_back(std::move(sql_package));
   engine_->RegisterPackage(name, std::move(new_package));
   return base::OkStatus();
@@ -861,7 +969,7 @@ base::Status TraceProcessorImpl::RegisterFileContent(
 void TraceProcessorImpl::InterruptQuery() {
   if (!engine_->sqlite_engine()->db())
     return;
-  query_interrupted_.store(true);
+  engine_->query_budget()->Interrupt();
   sqlite3_interrupt(engine_->sqlite_engine()->db());
 }
 
@@ -874,7 +982,7 @@ size_t TraceProcessorImpl::RestoreInitialTables() {
   // Reset the engine to its initial state.
   engine_ = InitPerfettoSqlEngine(
       &context_, context_.storage.get(), config_, &dataframe_shared_storage_,
-      registered_sql_packages_, sql_metrics_, &metrics_descriptor_pool_,
+      table_cache_.get(), registered_sql_packages_, sql_metrics_, &metrics_descriptor_pool_,
       &proto_fn_name_to_path_, this, notify_eof_called_);
 
   // The registered count should now be the same as it was in the constructor.
@@ -883,6 +991,83 @@ size_t TraceProcessorImpl::RestoreInitialTables() {
   return static_cast<size_t>(registered_count_before - registered_count_after);
 }
 
+base::Status TraceProcessorImpl::SaveSnapshot(const std::string& path) {
+  if (!notify_eof_called_) {
+    return base::ErrStatus(
+        "Snapshots can only be saved after the trace has been fully loaded");
+  }
+  TraceSnapshotInfo info;
+  info.trace_name = current_trace_name_;
+  info.trace_size_bytes = bytes_parsed_;
+  if (table_cache_) {
+    info.trace_content_hash = trace_hasher_.HexDigest();
+  }
+  return WriteTraceSnapshot(path, *context_.storage,
+                            GetUnfinalizedStaticTables(context_.storage.get()),
+                            info);
+}
+
+base::Status TraceProcessorImpl::RestoreSnapshot(const std::string& path) {
+#if PERFETTO_HAS_MMAP()
+  base::ScopedMmap mapped = base::ReadMmapWholeFile(path.c_str());
+  if (!mapped.IsValid()) {
+    return base::ErrStatus("Failed to open snapshot file %s", path.c_str());
+  }
+  return RestoreSnapshot(static_cast<const uint8_t*>(mapped.data()),
+                         mapped.length());
+#else
+  std::string data;
+  if (!base::ReadFile(path, &data)) {
+    return base::ErrStatus("Failed to read snapshot file %s", path.c_str());
+  }
+  return RestoreSnapshot(reinterpret_cast<const uint8_t*>(data.data()),
+                         data.size());
+#endif
+}
+
+base::Status TraceProcessorImpl::RestoreSnapshot(const uint8_t* data,
+                                                 size_t size) {
+  if (notify_eof_called_ || bytes_parsed_ > 0) {
+    return base::ErrStatus(
+        "Snapshots can only be restored before loading any trace");
+  }
+  TraceSnapshotInfo info;
+  RETURN_IF_ERROR(ReadTraceSnapshot(
+      data, size, context_.storage.get(),
+      GetUnfinalizedStaticTables(context_.storage.get()), &info));
+
+  // From now on, this mirrors NotifyEndOfFile() except that there is no
+  // trace to finish parsing.
+  notify_eof_called_ = true;
+  current_trace_name_ = info.trace_name;
+  bytes_parsed_ = info.trace_size_bytes;
+
+  // The kernel version is needed to convert the ftrace events to text.
+  std::optional<SqlValue> system_name =
+      context_.metadata_tracker->GetMetadata(metadata::system_name);
+  std::optional<SqlValue> system_release =
+      context_.metadata_tracker->GetMetadata(metadata::system_release);
+  if (system_name && system_name->type == SqlValue::kString &&
+      system_release && system_release->type == SqlValue::kString) {
+    SystemInfoTracker::GetOrCreate(&context_)->SetKernelVersion(
+        base::StringView(system_name->string_value),
+        base::StringView(system_release->string_value));
+  }
+
+  BuildBoundsTable(engine_->sqlite_engine()->db(),
+                   GetTraceTimestampBoundsNs(*context_.storage));
+  if (table_cache_) {
+    table_cache_->SetTraceKey(GetTableCacheTraceKey(
+        info.trace_content_hash, bytes_parsed_, config_));
+  }
+
+  TraceProcessorStorageImpl::DestroyContext();
+  engine_->FinalizeAndShareAllStaticTables();
+  IncludeAfterEofPrelude(engine_.get());
+  sqlite_objects_post_prelude_ = engine_->SqliteRegisteredObjectCount();
+  return base::OkStatus();
+}
+
 // =================================================================
 // |  Trace-based metrics (v1) related functionality starts here   |
 // =================================================================
@@ -1026,6 +1211,7 @@ TraceProcessorImpl::GetUnfinalizedStaticTables(TraceStorage* storage) {
       tables, storage->mutable_experimental_proto_content_table());
   AddUnfinalizedStaticTable(tables, storage->mutable_file_table());
   AddUnfinalizedStaticTable(tables, storage->mutable_filedescriptor_table());
+  AddUnfinalizedStaticTable(tables, storage->mutable_futex_contention_table());
   AddUnfinalizedStaticTable(tables, storage->mutable_gpu_counter_group_table());
   AddUnfinalizedStaticTable(tables,
                             storage->mutable_instruments_sample_table());
@@ -1052,6 +1238,10 @@ TraceProcessorImpl::GetUnfinalizedStaticTables(TraceStorage* storage) {
                             storage->mutable_spurious_sched_wakeup_table());
   AddUnfinalizedStaticTable(
       tables, storage->mutable_surfaceflinger_transaction_flag_table());
+  AddUnfinalizedStaticTable(tables,
+                            storage->mutable_syscall_arg_sample_table());
+  AddUnfinalizedStaticTable(tables,
+                            storage->mutable_syscall_latency_histogram_table());
   AddUnfinalizedStaticTable(tables, storage->mutable_trace_file_table());
   AddUnfinalizedStaticTable(tables, storage->mutable_v8_isolate_table());
   AddUnfinalizedStaticTable(tables, storage->mutable_v8_js_function_table());
@@ -1110,6 +1300,8 @@ TraceProcessorImpl::GetUnfinalizedStaticTables(TraceStorage* storage) {
   AddUnfinalizedStaticTable(tables, storage->mutable_heap_graph_class_table());
   AddUnfinalizedStaticTable(tables,
                             storage->mutable_heap_profile_allocation_table());
+  AddUnfinalizedStaticTable(
+      tables, storage->mutable_heap_profile_unreachable_allocation_table());
   AddUnfinalizedStaticTable(tables, storage->mutable_perf_sample_table());
   AddUnfinalizedStaticTable(tables,
                             storage->mutable_stack_profile_mapping_table());
@@ -1186,6 +1378,7 @@ std::unique_ptr<PerfettoSqlEngine> TraceProcessorImpl::InitPerfettoSqlEngine(
     TraceStorage* storage,
     const Config& config,
     DataframeSharedStorage* dataframe_shared_storage,
+    SqlTableCache* table_cache,
     const std::vector<SqlPackage>& packages,
     std::vector<metrics::SqlMetricFile>& sql_metrics,
     const DescriptorPool* metrics_descriptor_pool,
@@ -1193,7 +1386,7 @@ std::unique_ptr<PerfettoSqlEngine> TraceProcessorImpl::InitPerfettoSqlEngine(
     TraceProcessor* trace_processor,
     bool notify_eof_called) {
   auto engine = std::make_unique<PerfettoSqlEngine>(
-      storage->mutable_string_pool(), dataframe_shared_storage,
+      storage->mutable_string_pool(), dataframe_shared_storage, table_cache,
       config.enable_extra_checks);
   auto functions =
       CreateStaticTableFunctions(context, storage, config, engine.get());
@@ -19,7 +19,6 @@
 
 #include <sqlite3.h>
 
-#include <atomic>
 #include <cstddef>
 #include <cstdint>
 #include <memory>
@@ -28,6 +27,7 @@
 #include <vector>
 
 #include "perfetto/base/status.h"
+#include "perfetto/ext/base/sha256.h"
 #include "perfetto/trace_processor/basic_types.h"
 #include "perfetto/trace_processor/trace_blob_view.h"
 #include "perfetto/trace_processor/trace_processor.h"
@@ -35,6 +35,7 @@
 #include "src/trace_processor/metrics/metrics.h"
 #include "src/trace_processor/perfetto_sql/engine/dataframe_shared_storage.h"
 #include "src/trace_processor/perfetto_sql/engine/perfetto_sql_engine.h"
+#include "src/trace_processor/perfetto_sql/engine/sql_table_cache.h"
 #include "src/trace_processor/perfetto_sql/intrinsics/functions/create_function.h"
 #include "src/trace_processor/perfetto_sql/intrinsics/functions/create_view_function.h"
 #include "src/trace_processor/perfetto_sql/intrinsics/table_functions/static_table_function.h"
@@ -73,6 +74,8 @@ class TraceProcessorImpl : public TraceProcessor,
   // =================================================================
 
   Iterator ExecuteQuery(const std::string& sql) override;
+  Iterator ExecuteQuery(const std::string& sql,
+                        const QueryOptions& options) override;
 
   base::Status RegisterSqlPackage(SqlPackage) override;
 
@@ -110,6 +113,10 @@ class TraceProcessorImpl : public TraceProcessor,
 
   size_t RestoreInitialTables() override;
 
+  base::Status SaveSnapshot(const std::string& path) override;
+  base::Status RestoreSnapshot(const std::string& path) override;
+  base::Status RestoreSnapshot(const uint8_t* data, size_t size) override;
+
   // =================================================================
   // |  Trace-based metrics (v1) related functionality starts here   |
   // =================================================================
@@ -152,6 +159,7 @@ class TraceProcessorImpl : public TraceProcessor,
       TraceStorage* storage,
       const Config& config,
       DataframeSharedStorage* dataframe_shared_storage,
+      SqlTableCache* table_cache,
       const std::vector<SqlPackage>&,
       std::vector<metrics::SqlMetricFile>& sql_metrics,
       const DescriptorPool* metrics_descriptor_pool,
@@ -173,6 +181,7 @@ class TraceProcessorImpl : public TraceProcessor,
   const Config config_;
 
   DataframeSharedStorage dataframe_shared_storage_;
+  std::unique_ptr<SqlTableCache> table_cache_;
   std::unique_ptr<PerfettoSqlEngine> engine_;
 
   DescriptorPool metrics_descriptor_pool_;
@@ -183,16 +192,16 @@ class TraceProcessorImpl : public TraceProcessor,
   std::unordered_map<std::string, std::string> proto_field_to_sql_metric_path_;
   std::unordered_map<std::string, std::string> proto_fn_name_to_path_;
 
-  // This is atomic because it is set by the CTRL-C signal handler and we need
-  // to prevent single-flow compiler optimizations in ExecuteQuery().
-  std::atomic<bool> query_interrupted_{false};
-
   // Track the number of objects registered with SQLite post prelude.
   uint64_t sqlite_objects_post_prelude_ = 0;
 
   std::string current_trace_name_;
   uint64_t bytes_parsed_ = 0;
 
+  // Hash of the contents of the trace, identifying it in the table cache. Only
+  // computed if the table cache is enabled.
+  base::Sha256Hasher trace_hasher_;
+
   // NotifyEndOfFile should only be called once. Set to true whenever it is
   // called.
   bool notify_eof_called_ = false;
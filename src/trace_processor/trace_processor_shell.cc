@@ -53,6 +53,8 @@
 #include "perfetto/ext/base/status_or.h"
 #include "perfetto/ext/base/string_splitter.h"
 #include "perfetto/ext/base/string_utils.h"
+#include "perfetto/ext/base/subprocess.h"
+#include "perfetto/ext/base/temp_file.h"
 #include "perfetto/ext/base/version.h"
 #include "perfetto/trace_processor/basic_types.h"
 #include "perfetto/trace_processor/iterator.h"
@@ -65,11 +67,17 @@
 #include "src/profiling/symbolizer/local_symbolizer.h"
 #include "src/profiling/symbolizer/symbolize_database.h"
 #include "src/profiling/symbolizer/symbolizer.h"
+#include "src/protozero/text_to_proto/text_to_proto.h"
+#include "src/trace_processor/importers/proto/trace.descriptor.h"
 #include "src/trace_processor/metrics/all_chrome_metrics.descriptor.h"
 #include "src/trace_processor/metrics/all_webview_metrics.descriptor.h"
 #include "src/trace_processor/metrics/metrics.descriptor.h"
+#include "src/trace_processor/perfetto_sql/tooling/sql_linter.h"
+#include "src/trace_processor/perfetto_sql/tooling/sql_module_index.h"
+#include "src/trace_processor/perfetto_sql/tooling/sql_package_test.h"
 #include "src/trace_processor/read_trace_internal.h"
 #include "src/trace_processor/rpc/stdiod.h"
+#include "src/trace_processor/util/arrow_ipc_writer.h"
 #include "src/trace_processor/util/sql_modules.h"
 
 #include "protos/perfetto/trace_processor/trace_processor.pbzero.h"
@@ -78,6 +86,10 @@
 #include "src/trace_processor/rpc/httpd.h"
 #endif
 
+#if PERFETTO_BUILDFLAG(PERFETTO_TP_JSON)
+#include "src/trace_processor/perfetto_sql/tooling/lsp_server.h"
+#endif
+
 #if PERFETTO_BUILDFLAG(PERFETTO_OS_LINUX) ||   \
     PERFETTO_BUILDFLAG(PERFETTO_OS_ANDROID) || \
     PERFETTO_BUILDFLAG(PERFETTO_OS_APPLE)
@@ -321,6 +333,73 @@ base::Status ExportTraceToDatabase(const std::string& output_name) {
   return detach_it.Status();
 }
 
+base::Status ExportQueryToArrowFile(const std::string& sql,
+                                    const std::string& output_name) {
+  base::ScopedFile fd(
+      base::OpenFile(output_name, O_CREAT | O_WRONLY | O_TRUNC, 0600));
+  if (!fd)
+    return base::ErrStatus("Failed to create file: %s", output_name.c_str());
+
+  auto it = g_tp->ExecuteQuery(sql);
+  base::Status status = util::WriteIteratorAsArrow(
+      it, util::ArrowIpcWriter::Format::kFile,
+      [&fd, &output_name](const uint8_t* data, size_t size) {
+        if (base::WriteAll(*fd, data, size) != static_cast<ssize_t>(size)) {
+          return base::ErrStatus("Failed to write to file: %s",
+                                 output_name.c_str());
+        }
+        return base::OkStatus();
+      });
+  if (!status.ok()) {
+    return base::ErrStatus("Failed to export %s: %s", output_name.c_str(),
+                           status.c_message());
+  }
+  return base::OkStatus();
+}
+
+// Exports the result of each query in |queries| (formatted as NAME=SQL) to
+// the file NAME.arrow in |output_dir|. If |queries| is empty, all the tables
+// and views are exported instead.
+base::Status ExportTraceToArrow(const std::string& output_dir,
+                                const std::vector<std::string>& queries) {
+  if (!base::Mkdir(output_dir) && errno != EEXIST) {
+    return base::ErrStatus("Failed to create directory: %s",
+                           output_dir.c_str());
+  }
+
+  std::vector<std::pair<std::string, std::string>> exports;
+  for (const std::string& query : queries) {
+    size_t eq = query.find('=');
+    if (eq == std::string::npos || eq == 0) {
+      return base::ErrStatus(
+          "Invalid --export-arrow-query %s: expected NAME=SQL", query.c_str());
+    }
+    exports.emplace_back(query.substr(0, eq), query.substr(eq + 1));
+  }
+  if (exports.empty()) {
+    auto names_it = g_tp->ExecuteQuery(R"(
+      SELECT name FROM perfetto_tables
+      UNION ALL
+      SELECT name FROM sqlite_master WHERE type = 'view'
+      ORDER BY name
+    )");
+    while (names_it.Next()) {
+      std::string name = names_it.Get(0).string_value;
+      exports.emplace_back(name, "SELECT * FROM " + name);
+    }
+    RETURN_IF_ERROR(names_it.Status());
+  }
+
+  for (const auto& [name, sql] : exports) {
+    if (base::Contains(name, '/')) {
+      return base::ErrStatus("Invalid export name: %s", name.c_str());
+    }
+    RETURN_IF_ERROR(
+        ExportQueryToArrowFile(sql, output_dir + "/" + name + ".arrow"));
+  }
+  return base::OkStatus();
+}
+
 class ErrorPrinter : public google::protobuf::io::ErrorCollector {
   void AddError(int line, int col, const std::string& msg) override {
     PERFETTO_ELOG("%d:%d: %s", line, col, msg.c_str());
@@ -502,7 +581,7 @@ base::StatusOr<QueryResult> ExtractQueryResult(Iterator* it, bool has_more) {
   QueryResult result;
 
   for (uint32_t c = 0; c < it->ColumnCount(); c++) {
-    fprintf(stderr, "column %u = %s\n", c, it->GetColumnName(c).c_str());
+    PERFETTO_DLOG("column %u = %s", c, it->GetColumnName(c).c_str());
     result.column_names.push_back(it->GetColumnName(c));
   }
 
@@ -539,22 +618,29 @@ base::StatusOr<QueryResult> ExtractQueryResult(Iterator* it, bool has_more) {
   return result;
 }
 
-void PrintQueryResultAsCsv(const QueryResult& result, FILE* output) {
+std::string QueryResultAsCsv(const QueryResult& result) {
+  std::string csv;
   for (uint32_t c = 0; c < result.column_names.size(); c++) {
     if (c > 0)
-      fprintf(output, ",");
-    fprintf(output, "\"%s\"", result.column_names[c].c_str());
+      csv += ",";
+    csv += "\"" + result.column_names[c] + "\"";
   }
-  fprintf(output, "\n");
+  csv += "\n";
 
   for (const auto& row : result.rows) {
     for (uint32_t c = 0; c < result.column_names.size(); c++) {
       if (c > 0)
-        fprintf(output, ",");
-      fprintf(output, "%s", row[c].c_str());
+        csv += ",";
+      csv += row[c];
     }
-    fprintf(output, "\n");
+    csv += "\n";
   }
+  return csv;
+}
+
+void PrintQueryResultAsCsv(const QueryResult& result, FILE* output) {
+  std::string csv = QueryResultAsCsv(result);
+  fwrite(csv.data(), sizeof(char), csv.size(), output);
 }
 
 base::Status RunQueriesWithoutOutput(const std::string& sql_query) {
@@ -710,6 +796,10 @@ struct CommandLineOptions {
   std::string query_string;
   std::vector<std::string> sql_package_paths;
   std::vector<std::string> override_sql_package_paths;
+  std::string table_cache_dir;
+  uint64_t table_cache_max_size_mb = 0;
+  uint64_t query_timeout_ms = 0;
+  uint64_t query_max_table_memory_mb = 0;
 
   bool summary = false;
   std::string summary_metrics_v2;
@@ -717,6 +807,12 @@ struct CommandLineOptions {
   std::vector<std::string> summary_specs;
   std::string summary_output;
 
+  std::string diff_baseline_path;
+  bool diff_report = false;
+
+  std::string snapshot_in_path;
+  std::string snapshot_out_path;
+
   std::string metatrace_path;
   size_t metatrace_buffer_capacity = 0;
   metatrace::MetatraceCategories metatrace_categories =
@@ -728,6 +824,8 @@ struct CommandLineOptions {
   std::vector<std::string> dev_flags;
   bool extra_checks = false;
   std::string export_file_path;
+  std::string export_arrow_dir;
+  std::vector<std::string> export_arrow_queries;
   std::string perf_file_path;
   bool wide = false;
   bool analyze_trace_proto_content = false;
@@ -745,6 +843,20 @@ void PrintUsage(char** argv) {
   PERFETTO_ELOG(R"(
 Interactive trace processor shell.
 Usage: %s [FLAGS] trace_file.pb
+       %s lsp [FLAGS] [trace_file.pb]
+       %s test [FLAGS] [TEST_PATH...]
+       %s format [FLAGS] SQL_PATH...
+       %s lint [FLAGS] [trace_file.pb]
+
+Subcommands:
+ lsp                                  Runs a language server for PerfettoSQL
+                                      on stdin/stdout. See "lsp --help".
+ test                                 Runs the tests of PerfettoSQL packages.
+                                      See "test --help".
+ format                               Formats PerfettoSQL files. See
+                                      "format --help".
+ lint                                 Checks the conventions of PerfettoSQL
+                                      packages. See "lint --help".
 
 General purpose:
  -h, --help                           Prints this guide.
@@ -791,6 +903,27 @@ PerfettoSQL:
  --override-sql-package PACKAGE_PATH  Will override trace processor package with
                                       passed contents. The outer directory will
                                       specify the package name.
+ --table-cache-dir DIR                Persists the tables created by CREATE
+                                      PERFETTO TABLE statements in the stdlib
+                                      modules in DIR and reuses them, instead
+                                      of recomputing them, when the same trace
+                                      is loaded again. Also applies to the
+                                      traces loaded through --httpd and
+                                      --stdiod.
+ --table-cache-max-size-mb N          Maximum size of the files in
+                                      --table-cache-dir (default: 1024). The
+                                      least recently used tables are deleted
+                                      when this is exceeded.
+ --query-timeout-ms N                 Fails the queries running for longer
+                                      than N milliseconds. Also applies to the
+                                      queries run through --httpd and
+                                      --stdiod, unless the client sets its own
+                                      limit. Queries can also be interrupted
+                                      with Ctrl-C in the interactive shell.
+ --query-max-table-memory-mb N        Fails the queries allocating more than N
+                                      MB in the tables they materialize (e.g.
+                                      with CREATE PERFETTO TABLE). Also
+                                      applies to --httpd and --stdiod.
 
 Trace summarization:
   --summary                           Enables the trace summarization features of
@@ -828,6 +961,28 @@ Trace summarization:
                                       protobuf. If unspecified or `text` then
                                       the output is a textproto.
 
+Trace diffing:
+  --diff-baseline BASELINE_PATH       Loads the trace at BASELINE_PATH as the
+                                      baseline to compare the trace against.
+                                      The statistics of the baseline (see the
+                                      trace_diff.stats module) are available
+                                      in the `baseline` schema and are compared
+                                      by the trace_diff.compare module.
+  --diff-report                       Prints a trace summary comparing the
+                                      trace with the one passed to
+                                      --diff-baseline. Implies --summary and
+                                      computes all the metrics unless
+                                      --summary-metrics-v2 is specified.
+
+Snapshots:
+ --snapshot-out FILE                  Writes a snapshot of the tables of the
+                                      trace to FILE after loading it. Loading
+                                      the snapshot with --snapshot-in is much
+                                      faster than parsing the trace again.
+ --snapshot-in FILE                   Restores the snapshot in FILE instead of
+                                      loading a trace. The trace file must not
+                                      be specified.
+
 Metatracing:
  -m, --metatrace FILE                 Enables metatracing of trace processor
                                       writing the resulting trace into FILE.
@@ -851,6 +1006,14 @@ Advanced:
  -e, --export FILE                    Export the contents of trace processor
                                       into an SQLite database after running any
                                       metrics or queries specified.
+ --export-arrow DIR                   Export the contents of all the tables and
+                                      views of trace processor into DIR, one
+                                      Apache Arrow IPC file (DIR/NAME.arrow)
+                                      for each of them, after running any
+                                      metrics or queries specified.
+ --export-arrow-query NAME=SQL        Only valid with --export-arrow. Export the
+                                      result of SQL to DIR/NAME.arrow instead
+                                      of all the tables. Can be repeated.
  -p, --perf-file FILE                 Writes the time taken to ingest the trace
                                       and execute the queries to the given file.
                                       Only valid with -q or --run-metrics and
@@ -904,7 +1067,7 @@ Metrics (v1):
                                       respectively, and mounts them onto
                                       VIRTUAL_PATH.
 )",
-                argv[0]);
+                argv[0], argv[0], argv[0], argv[0], argv[0]);
 }
 
 CommandLineOptions ParseCommandLineOptions(int argc, char** argv) {
@@ -920,6 +1083,10 @@ CommandLineOptions ParseCommandLineOptions(int argc, char** argv) {
 
     OPT_ADD_SQL_PACKAGE,
     OPT_OVERRIDE_SQL_PACKAGE,
+    OPT_TABLE_CACHE_DIR,
+    OPT_TABLE_CACHE_MAX_SIZE_MB,
+    OPT_QUERY_TIMEOUT_MS,
+    OPT_QUERY_MAX_TABLE_MEMORY_MB,
 
     OPT_SUMMARY,
     OPT_SUMMARY_METRICS_V2,
@@ -927,12 +1094,20 @@ CommandLineOptions ParseCommandLineOptions(int argc, char** argv) {
     OPT_SUMMARY_SPEC,
     OPT_SUMMARY_FORMAT,
 
+    OPT_DIFF_BASELINE,
+    OPT_DIFF_REPORT,
+
+    OPT_SNAPSHOT_IN,
+    OPT_SNAPSHOT_OUT,
+
     OPT_METATRACE_BUFFER_CAPACITY,
     OPT_METATRACE_CATEGORIES,
 
     OPT_DEV,
     OPT_DEV_FLAG,
     OPT_EXTRA_CHECKS,
+    OPT_EXPORT_ARROW,
+    OPT_EXPORT_ARROW_QUERY,
     OPT_ANALYZE_TRACE_PROTO_CONTENT,
     OPT_CROP_TRACK_EVENTS,
     OPT_REGISTER_FILES_DIR,
@@ -967,6 +1142,12 @@ CommandLineOptions ParseCommandLineOptions(int argc, char** argv) {
        OPT_OVERRIDE_SQL_PACKAGE},
       {"override-sql-package", required_argument, nullptr,
        OPT_OVERRIDE_SQL_PACKAGE},
+      {"table-cache-dir", required_argument, nullptr, OPT_TABLE_CACHE_DIR},
+      {"table-cache-max-size-mb", required_argument, nullptr,
+       OPT_TABLE_CACHE_MAX_SIZE_MB},
+      {"query-timeout-ms", required_argument, nullptr, OPT_QUERY_TIMEOUT_MS},
+      {"query-max-table-memory-mb", required_argument, nullptr,
+       OPT_QUERY_MAX_TABLE_MEMORY_MB},
 
       {"summary", no_argument, nullptr, OPT_SUMMARY},
       {"summary-metrics-v2", required_argument, nullptr,
@@ -976,6 +1157,12 @@ CommandLineOptions ParseCommandLineOptions(int argc, char** argv) {
       {"summary-spec", required_argument, nullptr, OPT_SUMMARY_SPEC},
       {"summary-format", required_argument, nullptr, OPT_SUMMARY_FORMAT},
 
+      {"diff-baseline", required_argument, nullptr, OPT_DIFF_BASELINE},
+      {"diff-report", no_argument, nullptr, OPT_DIFF_REPORT},
+
+      {"snapshot-in", required_argument, nullptr, OPT_SNAPSHOT_IN},
+      {"snapshot-out", required_argument, nullptr, OPT_SNAPSHOT_OUT},
+
       {"metatrace", required_argument, nullptr, 'm'},
       {"metatrace-buffer-capacity", required_argument, nullptr,
        OPT_METATRACE_BUFFER_CAPACITY},
@@ -986,6 +1173,9 @@ CommandLineOptions ParseCommandLineOptions(int argc, char** argv) {
       {"dev-flag", required_argument, nullptr, OPT_DEV_FLAG},
       {"extra-checks", no_argument, nullptr, OPT_EXTRA_CHECKS},
       {"export", required_argument, nullptr, 'e'},
+      {"export-arrow", required_argument, nullptr, OPT_EXPORT_ARROW},
+      {"export-arrow-query", required_argument, nullptr,
+       OPT_EXPORT_ARROW_QUERY},
       {"perf-file", required_argument, nullptr, 'p'},
       {"wide", no_argument, nullptr, 'W'},
       {"analyze-trace-proto-content", no_argument, nullptr,
@@ -1077,6 +1267,16 @@ CommandLineOptions ParseCommandLineOptions(int argc, char** argv) {
       continue;
     }
 
+    if (option == OPT_EXPORT_ARROW) {
+      command_line_options.export_arrow_dir = optarg;
+      continue;
+    }
+
+    if (option == OPT_EXPORT_ARROW_QUERY) {
+      command_line_options.export_arrow_queries.emplace_back(optarg);
+      continue;
+    }
+
     if (option == 'm') {
       command_line_options.metatrace_path = optarg;
       continue;
@@ -1134,6 +1334,41 @@ CommandLineOptions ParseCommandLineOptions(int argc, char** argv) {
       continue;
     }
 
+    if (option == OPT_TABLE_CACHE_DIR) {
+      command_line_options.table_cache_dir = optarg;
+      continue;
+    }
+
+    if (option == OPT_TABLE_CACHE_MAX_SIZE_MB) {
+      std::optional<uint64_t> size_mb = base::CStringToUInt64(optarg);
+      if (!size_mb || *size_mb == 0) {
+        PERFETTO_ELOG("Invalid --table-cache-max-size-mb: %s", optarg);
+        exit(1);
+      }
+      command_line_options.table_cache_max_size_mb = *size_mb;
+      continue;
+    }
+
+    if (option == OPT_QUERY_TIMEOUT_MS) {
+      std::optional<uint64_t> timeout_ms = base::CStringToUInt64(optarg);
+      if (!timeout_ms || *timeout_ms == 0) {
+        PERFETTO_ELOG("Invalid --query-timeout-ms: %s", optarg);
+        exit(1);
+      }
+      command_line_options.query_timeout_ms = *timeout_ms;
+      continue;
+    }
+
+    if (option == OPT_QUERY_MAX_TABLE_MEMORY_MB) {
+      std::optional<uint64_t> size_mb = base::CStringToUInt64(optarg);
+      if (!size_mb || *size_mb == 0) {
+        PERFETTO_ELOG("Invalid --query-max-table-memory-mb: %s", optarg);
+        exit(1);
+      }
+      command_line_options.query_max_table_memory_mb = *size_mb;
+      continue;
+    }
+
     if (option == OPT_OVERRIDE_STDLIB) {
       command_line_options.override_stdlib_path = optarg;
       continue;
@@ -1194,17 +1429,62 @@ CommandLineOptions ParseCommandLineOptions(int argc, char** argv) {
       continue;
     }
 
+    if (option == OPT_DIFF_BASELINE) {
+      command_line_options.diff_baseline_path = optarg;
+      continue;
+    }
+
+    if (option == OPT_DIFF_REPORT) {
+      command_line_options.diff_report = true;
+      continue;
+    }
+
+    if (option == OPT_SNAPSHOT_IN) {
+      command_line_options.snapshot_in_path = optarg;
+      continue;
+    }
+
+    if (option == OPT_SNAPSHOT_OUT) {
+      command_line_options.snapshot_out_path = optarg;
+      continue;
+    }
+
     PrintUsage(argv);
     exit(option == 'h' ? 0 : 1);
   }
 
+  if (command_line_options.diff_report) {
+    if (command_line_options.diff_baseline_path.empty()) {
+      PERFETTO_ELOG("--diff-report requires --diff-baseline");
+      exit(1);
+    }
+    command_line_options.summary = true;
+    if (command_line_options.summary_metrics_v2.empty()) {
+      command_line_options.summary_metrics_v2 = "all";
+    }
+  }
+
   command_line_options.launch_shell =
       explicit_interactive || (command_line_options.metric_v1_names.empty() &&
                                command_line_options.query_file_path.empty() &&
                                command_line_options.query_string.empty() &&
                                command_line_options.export_file_path.empty() &&
+                               command_line_options.export_arrow_dir.empty() &&
+                               command_line_options.snapshot_out_path.empty() &&
                                !command_line_options.summary);
 
+  if (command_line_options.table_cache_max_size_mb != 0 &&
+      command_line_options.table_cache_dir.empty()) {
+    PERFETTO_ELOG("--table-cache-max-size-mb requires --table-cache-dir");
+    exit(1);
+  }
+
+  if (!command_line_options.export_arrow_queries.empty() &&
+      command_line_options.export_arrow_dir.empty()) {
+    PERFETTO_ELOG("--export-arrow-query requires --export-arrow");
+    exit(1);
+  }
+
   // Only allow non-interactive queries to emit perf data.
   if (!command_line_options.perf_file_path.empty() &&
       command_line_options.launch_shell) {
@@ -1218,17 +1498,34 @@ CommandLineOptions ParseCommandLineOptions(int argc, char** argv) {
     exit(1);
   }
 
-  // The only case where we allow omitting the trace file path is when running
-  // in --httpd or --stdiod mode. In all other cases, the last argument must be
-  // the trace file.
+  // The only cases where we allow omitting the trace file path are when
+  // restoring a snapshot or running in --httpd or --stdiod mode. In all other
+  // cases, the last argument must be the trace file.
   if (optind == argc - 1 && argv[optind]) {
+    if (!command_line_options.snapshot_in_path.empty()) {
+      PERFETTO_ELOG("Cannot specify both a trace file and --snapshot-in");
+      exit(1);
+    }
     command_line_options.trace_file_path = argv[optind];
-  } else if (!command_line_options.enable_httpd &&
+  } else if (command_line_options.snapshot_in_path.empty() &&
+             !command_line_options.enable_httpd &&
              !command_line_options.enable_stdiod) {
     PrintUsage(argv);
     exit(1);
   }
 
+  bool has_trace = !command_line_options.trace_file_path.empty() ||
+                   !command_line_options.snapshot_in_path.empty();
+  if (!command_line_options.diff_baseline_path.empty() && !has_trace) {
+    PERFETTO_ELOG("--diff-baseline requires a trace file to compare");
+    exit(1);
+  }
+
+  if (!command_line_options.snapshot_out_path.empty() && !has_trace) {
+    PERFETTO_ELOG("--snapshot-out requires a trace file");
+    exit(1);
+  }
+
   return command_line_options;
 }
 
@@ -1246,9 +1543,11 @@ void ExtendPoolWithBinaryDescriptor(
   }
 }
 
-base::Status LoadTrace(const std::string& trace_file_path, double* size_mb) {
+base::Status LoadTrace(TraceProcessor* tp,
+                       const std::string& trace_file_path,
+                       double* size_mb) {
   base::Status read_status = ReadTraceUnfinalized(
-      g_tp, trace_file_path.c_str(), [&size_mb](size_t parsed_size) {
+      tp, trace_file_path.c_str(), [&size_mb](size_t parsed_size) {
         *size_mb = static_cast<double>(parsed_size) / 1E6;
         fprintf(stderr, "\rLoading trace: %.2f MB\r", *size_mb);
       });
@@ -1262,12 +1561,12 @@ base::Status LoadTrace(const std::string& trace_file_path, double* size_mb) {
                                       getenv("PERFETTO_SYMBOLIZER_MODE"));
 
   if (symbolizer) {
-    g_tp->Flush();
+    tp->Flush();
     profiling::SymbolizeDatabase(
-        g_tp, symbolizer.get(), [](const std::string& trace_proto) {
+        tp, symbolizer.get(), [tp](const std::string& trace_proto) {
           std::unique_ptr<uint8_t[]> buf(new uint8_t[trace_proto.size()]);
           memcpy(buf.get(), trace_proto.data(), trace_proto.size());
-          auto status = g_tp->Parse(std::move(buf), trace_proto.size());
+          auto status = tp->Parse(std::move(buf), trace_proto.size());
           if (!status.ok()) {
             PERFETTO_DFATAL_OR_ELOG("Failed to parse: %s",
                                     status.message().c_str());
@@ -1278,12 +1577,12 @@ base::Status LoadTrace(const std::string& trace_file_path, double* size_mb) {
 
   auto maybe_map = profiling::GetPerfettoProguardMapPath();
   if (!maybe_map.empty()) {
-    g_tp->Flush();
+    tp->Flush();
     profiling::ReadProguardMapsToDeobfuscationPackets(
-        maybe_map, [](const std::string& trace_proto) {
+        maybe_map, [tp](const std::string& trace_proto) {
           std::unique_ptr<uint8_t[]> buf(new uint8_t[trace_proto.size()]);
           memcpy(buf.get(), trace_proto.data(), trace_proto.size());
-          auto status = g_tp->Parse(std::move(buf), trace_proto.size());
+          auto status = tp->Parse(std::move(buf), trace_proto.size());
           if (!status.ok()) {
             PERFETTO_DFATAL_OR_ELOG("Failed to parse: %s",
                                     status.message().c_str());
@@ -1291,7 +1590,148 @@ base::Status LoadTrace(const std::string& trace_file_path, double* size_mb) {
           }
         });
   }
-  return g_tp->NotifyEndOfFile();
+  return tp->NotifyEndOfFile();
+}
+
+// The tables of the trace_diff.stats module which are computed on the baseline
+// trace when diffing traces (see --diff-baseline).
+constexpr const char* kTraceDiffStatsTables[] = {
+    "trace_diff_slice_stats",
+    "trace_diff_thread_state_stats",
+    "trace_diff_process_cpu_stats",
+    "trace_diff_cpu_profile_tree",
+};
+
+// The trace summary spec of the report printed by --diff-report.
+constexpr char kTraceDiffSummarySpec[] = R"(
+metric_template_spec {
+  id_prefix: "trace_diff_slice"
+  dimensions: "name"
+  dimensions: "thread_name"
+  dimensions: "process_name"
+  value_column_specs { name: "baseline_count" unit: COUNT }
+  value_column_specs { name: "candidate_count" unit: COUNT }
+  value_column_specs { name: "baseline_mean_dur" unit: TIME_NANOS }
+  value_column_specs { name: "candidate_mean_dur" unit: TIME_NANOS }
+  value_column_specs {
+    name: "delta_mean_dur"
+    unit: TIME_NANOS
+    polarity: LOWER_IS_BETTER
+  }
+  value_column_specs {
+    name: "delta_total_dur"
+    unit: TIME_NANOS
+    polarity: LOWER_IS_BETTER
+  }
+  value_column_specs { name: "p_value" }
+  value_column_specs { name: "is_significant" }
+  query {
+    table {
+      table_name: "trace_diff_slice"
+      module_name: "trace_diff.compare"
+    }
+  }
+  dimension_uniqueness: UNIQUE
+}
+metric_template_spec {
+  id_prefix: "trace_diff_thread_state"
+  dimensions: "process_name"
+  dimensions: "thread_name"
+  dimensions: "state"
+  value_column_specs { name: "baseline_total_dur" unit: TIME_NANOS }
+  value_column_specs { name: "candidate_total_dur" unit: TIME_NANOS }
+  value_column_specs { name: "delta_total_dur" unit: TIME_NANOS }
+  value_column_specs { name: "delta_mean_dur" unit: TIME_NANOS }
+  value_column_specs { name: "p_value" }
+  value_column_specs { name: "is_significant" }
+  query {
+    table {
+      table_name: "trace_diff_thread_state"
+      module_name: "trace_diff.compare"
+    }
+  }
+  dimension_uniqueness: UNIQUE
+}
+metric_template_spec {
+  id_prefix: "trace_diff_process_cpu"
+  dimensions: "process_name"
+  value_column_specs { name: "baseline_cpu_dur" unit: TIME_NANOS }
+  value_column_specs { name: "candidate_cpu_dur" unit: TIME_NANOS }
+  value_column_specs {
+    name: "delta_cpu_dur"
+    unit: TIME_NANOS
+    polarity: LOWER_IS_BETTER
+  }
+  query {
+    table {
+      table_name: "trace_diff_process_cpu"
+      module_name: "trace_diff.compare"
+    }
+  }
+  dimension_uniqueness: UNIQUE
+}
+metric_template_spec {
+  id_prefix: "trace_diff_cpu_profile"
+  dimensions: "id"
+  dimensions: "parent_id"
+  dimensions: "name"
+  dimensions: "mapping_name"
+  value_column_specs { name: "baseline_self_count" unit: COUNT }
+  value_column_specs { name: "candidate_self_count" unit: COUNT }
+  value_column_specs { name: "baseline_cumulative_count" unit: COUNT }
+  value_column_specs { name: "candidate_cumulative_count" unit: COUNT }
+  value_column_specs { name: "delta_cumulative_count" unit: COUNT }
+  value_column_specs { name: "p_value" }
+  value_column_specs { name: "is_significant" }
+  query {
+    table {
+      table_name: "trace_diff_cpu_profile_flamegraph"
+      module_name: "trace_diff.compare"
+    }
+  }
+  dimension_uniqueness: UNIQUE
+}
+)";
+
+// Loads the trace at |baseline_path| in a separate instance of trace
+// processor, stores the tables of the trace_diff.stats module computed on it
+// in the SQLite database at |db_path| and attaches the latter to |g_tp| as the
+// `baseline` schema.
+base::Status AttachDiffBaseline(const Config& config,
+                                const std::string& baseline_path,
+                                const std::string& db_path) {
+  PERFETTO_CHECK(db_path.find('\'') == std::string::npos);
+  {
+    std::unique_ptr<TraceProcessor> baseline_tp =
+        TraceProcessor::CreateInstance(config);
+    double size_mb = 0;
+    RETURN_IF_ERROR(LoadTrace(baseline_tp.get(), baseline_path, &size_mb));
+    PERFETTO_ILOG("Baseline trace loaded: %.2f MB", size_mb);
+
+    std::string export_sql =
+        "INCLUDE PERFETTO MODULE trace_diff.stats;"
+        "ATTACH DATABASE '" +
+        db_path + "' AS trace_diff_export;";
+    for (const char* table : kTraceDiffStatsTables) {
+      export_sql += "CREATE TABLE trace_diff_export." + std::string(table) +
+                    " AS SELECT * FROM " + table + ";";
+    }
+    export_sql += "DETACH DATABASE trace_diff_export;";
+
+    auto export_it = baseline_tp->ExecuteQuery(export_sql);
+    bool export_has_more = export_it.Next();
+    PERFETTO_DCHECK(!export_has_more);
+    if (!export_it.Status().ok()) {
+      return base::ErrStatus("Failed to compute the baseline tables: %s",
+                             export_it.Status().c_message());
+    }
+  }
+
+  auto attach_it =
+      g_tp->ExecuteQuery("ATTACH DATABASE '" + db_path + "' AS baseline");
+  bool attach_has_more = attach_it.Next();
+  PERFETTO_DCHECK(!attach_has_more);
+  return attach_it.Status();
 }
 
 base::Status RunQueries(const std::string& queries, bool expect_output) {
@@ -1821,6 +2261,543 @@ TraceSummaryOutputSpec::Format GetSummaryOutputFormat(
   exit(1);
 }
 
+#if PERFETTO_BUILDFLAG(PERFETTO_TP_JSON)
+void PrintLspUsage() {
+  PERFETTO_ELOG(R"(
+Language server for PerfettoSQL, speaking the Language Server Protocol on
+stdin/stdout. Provides diagnostics, completion, hover documentation and go to
+definition for the standard library and the packages passed with
+--add-sql-package. If a trace file is passed, the tables of the trace are also
+completed and described.
+Usage: trace_processor_shell lsp [FLAGS] [trace_file.pb]
+
+ -h, --help                           Prints this guide.
+ --add-sql-package PACKAGE_PATH       Indexes the SQL modules in the directory
+                                      PACKAGE_PATH, following the same rules as
+                                      for the main command. Can be repeated.
+ --stdlib-source STDLIB_PATH          Path to the source of the standard library
+                                      (src/trace_processor/perfetto_sql/stdlib),
+                                      used for go to definition into the
+                                      standard library.
+)");
+}
+
+// Runs the "lsp" subcommand. |argv| starts with "lsp".
+base::Status LspMain(int argc, char** argv) {
+  enum LongOption {
+    OPT_ADD_SQL_PACKAGE = 1000,
+    OPT_STDLIB_SOURCE,
+  };
+  static const option long_options[] = {
+      {"help", no_argument, nullptr, 'h'},
+      {"add-sql-package", required_argument, nullptr, OPT_ADD_SQL_PACKAGE},
+      {"stdlib-source", required_argument, nullptr, OPT_STDLIB_SOURCE},
+      {nullptr, 0, nullptr, 0}};
+
+  std::vector<std::string> sql_package_paths;
+  std::string stdlib_source;
+  for (;;) {
+    int option = getopt_long(argc, argv, "h", long_options, nullptr);
+    if (option == -1)
+      break;  // EOF.
+    if (option == OPT_ADD_SQL_PACKAGE) {
+      sql_package_paths.emplace_back(optarg);
+      continue;
+    }
+    if (option == OPT_STDLIB_SOURCE) {
+      stdlib_source = optarg;
+      continue;
+    }
+    PrintLspUsage();
+    exit(option == 'h' ? 0 : 1);
+  }
+  if (optind < argc - 1) {
+    PrintLspUsage();
+    exit(1);
+  }
+
+  SqlModuleIndex index;
+  index.AddStdlibModules(stdlib_source);
+  for (const auto& path : sql_package_paths) {
+    RETURN_IF_ERROR(index.AddPackage(path));
+  }
+
+  // The trace is only used to describe the tables it contains: the server
+  // works on the source of the modules otherwise.
+  std::unique_ptr<TraceProcessor> tp;
+  if (optind == argc - 1) {
+    tp = TraceProcessor::CreateInstance(Config());
+    g_tp = tp.get();
+    for (const auto& path : sql_package_paths) {
+      RETURN_IF_ERROR(IncludeSqlPackage(path, false));
+    }
+    double size_mb = 0;
+    RETURN_IF_ERROR(LoadTrace(g_tp, argv[optind], &size_mb));
+    PERFETTO_ILOG("Trace loaded: %.2f MB", size_mb);
+  }
+
+  LspServer server(&index, tp.get());
+  return RunLspStdioServer(&server);
+}
+#else
+base::Status LspMain(int, char**) {
+  return base::ErrStatus(
+      "The language server requires trace processor to be built with JSON "
+      "support");
+}
+#endif
+
+// Returns the files with |extension| in |paths|, which can be files or
+// directories searched recursively.
+base::StatusOr<std::vector<std::string>> ListFilesWithExtension(
+    const std::vector<std::string>& paths,
+    const std::string& extension) {
+  std::vector<std::string> out;
+  for (const auto& path : paths) {
+    if (base::GetFileExtension(path) == extension) {
+      out.push_back(path);
+      continue;
+    }
+    std::vector<std::string> files;
+    RETURN_IF_ERROR(base::ListFilesRecursive(path, files));
+    std::sort(files.begin(), files.end());
+    for (const auto& file : files) {
+      if (base::GetFileExtension(file) == extension) {
+        out.push_back(path + "/" + file);
+      }
+    }
+  }
+  return out;
+}
+
+void PrintTestUsage() {
+  PERFETTO_ELOG(R"(
+Runs the tests of PerfettoSQL packages. Each test file (*.sqltest) contains a
+textproto or JSON trace and queries to run on it with their expected output:
+see src/trace_processor/perfetto_sql/tooling/sql_package_test.h for the format.
+Prints the differences with the expected output of the failing tests and the
+tables, views, functions and macros of the packages which no test exercises.
+Usage: trace_processor_shell test [FLAGS] [TEST_PATH...]
+
+TEST_PATH is a test file or a directory searched recursively for test files.
+By default, the directories of the packages are searched.
+
+ -h, --help                           Prints this guide.
+ --add-sql-package PACKAGE_PATH       Package under test, made available to
+                                      the queries of the tests as in the main
+                                      command. Can be repeated.
+ --filter SUBSTRING                   Only runs the tests whose name, in the
+                                      form path/to/file.sqltest:test_name,
+                                      contains SUBSTRING.
+)");
+}
+
+// Loads the trace of |file| in |g_tp| and returns the output of |test|.
+base::StatusOr<std::string> RunSqlTest(
+    const SqlTestFile& file,
+    const SqlTestFile::Test& test,
+    const std::vector<std::string>& sql_package_paths) {
+  for (const auto& path : sql_package_paths) {
+    RETURN_IF_ERROR(IncludeSqlPackage(path, false));
+  }
+
+  std::vector<uint8_t> trace;
+  switch (file.trace_format) {
+    case SqlTestFile::TraceFormat::kTextproto: {
+      ASSIGN_OR_RETURN(trace, protozero::TextToProto(
+                                  kTraceDescriptor.data(),
+                                  kTraceDescriptor.size(),
+                                  ".perfetto.protos.Trace", file.path,
+                                  file.trace));
+      break;
+    }
+    case SqlTestFile::TraceFormat::kJson:
+      trace.assign(file.trace.begin(), file.trace.end());
+      break;
+  }
+  if (!trace.empty()) {
+    RETURN_IF_ERROR(g_tp->Parse(
+        TraceBlobView(TraceBlob::CopyFrom(trace.data(), trace.size()))));
+  }
+  RETURN_IF_ERROR(g_tp->NotifyEndOfFile());
+
+  auto it = g_tp->ExecuteQuery(test.query);
+  bool has_more = it.Next();
+  RETURN_IF_ERROR(it.Status());
+  if (it.ColumnCount() == 0) {
+    return std::string();
+  }
+  ASSIGN_OR_RETURN(QueryResult result, ExtractQueryResult(&it, has_more));
+  return QueryResultAsCsv(result);
+}
+
+// Runs the "test" subcommand. |argv| starts with "test".
+base::Status TestMain(int argc, char** argv) {
+  enum LongOption {
+    OPT_ADD_SQL_PACKAGE = 1000,
+    OPT_FILTER,
+  };
+  static const option long_options[] = {
+      {"help", no_argument, nullptr, 'h'},
+      {"add-sql-package", required_argument, nullptr, OPT_ADD_SQL_PACKAGE},
+      {"filter", required_argument, nullptr, OPT_FILTER},
+      {nullptr, 0, nullptr, 0}};
+
+  std::vector<std::string> sql_package_paths;
+  std::string filter;
+  for (;;) {
+    int option = getopt_long(argc, argv, "h", long_options, nullptr);
+    if (option == -1)
+      break;  // EOF.
+    if (option == OPT_ADD_SQL_PACKAGE) {
+      sql_package_paths.emplace_back(optarg);
+      continue;
+    }
+    if (option == OPT_FILTER) {
+      filter = optarg;
+      continue;
+    }
+    PrintTestUsage();
+    exit(option == 'h' ? 0 : 1);
+  }
+  if (sql_package_paths.empty()) {
+    PERFETTO_ELOG("At least one --add-sql-package is required");
+    PrintTestUsage();
+    exit(1);
+  }
+
+  SqlModuleIndex index;
+  index.AddStdlibModules();
+  std::vector<std::string> packages;
+  for (std::string path : sql_package_paths) {
+    RETURN_IF_ERROR(index.AddPackage(path));
+    while (path.size() > 1 && path.back() == '/') {
+      path.pop_back();
+    }
+    packages.push_back(BaseName(path));
+  }
+
+  std::vector<std::string> test_paths(argv + optind, argv + argc);
+  if (test_paths.empty()) {
+    test_paths = sql_package_paths;
+  }
+  ASSIGN_OR_RETURN(
+      std::vector<std::string> test_files,
+      ListFilesWithExtension(test_paths, kSqlTestFileExtension));
+
+  SqlPackageCoverage coverage(&index, packages);
+  std::vector<std::string> failures;
+  uint32_t test_count = 0;
+  for (const auto& path : test_files) {
+    std::string contents;
+    if (!base::ReadFile(path, &contents)) {
+      return base::ErrStatus("Unable to read file %s", path.c_str());
+    }
+    ASSIGN_OR_RETURN(SqlTestFile file, ParseSqlTestFile(path, contents));
+    for (const auto& test : file.tests) {
+      std::string name = path + ":" + test.name;
+      if (name.find(filter) == std::string::npos) {
+        continue;
+      }
+      ++test_count;
+      coverage.AddQuery(test.query);
+      printf("[ RUN      ] %s\n", name.c_str());
+
+      // Every test gets a new instance so that tests cannot affect each other.
+      std::unique_ptr<TraceProcessor> tp =
+          TraceProcessor::CreateInstance(Config());
+      g_tp = tp.get();
+      base::StatusOr<std::string> output =
+          RunSqlTest(file, test, sql_package_paths);
+      g_tp = nullptr;
+
+      std::string diff;
+      if (!output.ok()) {
+        printf("%s:%u: %s\n", path.c_str(), test.line,
+               output.status().c_message());
+      } else {
+        diff = DiffSqlTestOutput(test.expected_output, *output);
+        if (!diff.empty()) {
+          printf("%s:%u: unexpected output (-expected +actual):\n%s",
+                 path.c_str(), test.line, diff.c_str());
+        }
+      }
+      if (output.ok() && diff.empty()) {
+        printf("[       OK ] %s\n", name.c_str());
+      } else {
+        printf("[  FAILED  ] %s\n", name.c_str());
+        failures.push_back(name);
+      }
+    }
+  }
+
+  uint32_t exercised = 0;
+  for (const auto& symbol : coverage.symbols()) {
+    exercised += symbol.exercised;
+  }
+  printf("\n[==========] %u tests ran, %zu failed.\n", test_count,
+         failures.size());
+  for (const auto& name : failures) {
+    printf("[  FAILED  ] %s\n", name.c_str());
+  }
+  printf(
+      "\nCoverage: %u/%zu tables, views, functions and macros exercised.\n",
+      exercised, coverage.symbols().size());
+  for (const auto& symbol : coverage.symbols()) {
+    if (!symbol.exercised) {
+      printf("  Not exercised: %s (%s)\n", symbol.symbol->name.c_str(),
+             symbol.module_key.c_str());
+    }
+  }
+
+  if (!failures.empty()) {
+    return base::ErrStatus("%zu of %u tests failed", failures.size(),
+                           test_count);
+  }
+  return base::OkStatus();
+}
+
+// The formatter used for the standard library, relative to the root of the
+// Perfetto checkout.
+constexpr char kSqlFormatterPath[] = "python/tools/format_sql.py";
+
+void PrintFormatUsage() {
+  PERFETTO_ELOG(R"(
+Formats PerfettoSQL files in the style of the standard library, with the same
+formatter as tools/format-sql-sources (python/tools/format_sql.py). This
+requires a Perfetto checkout with its build deps installed.
+Usage: trace_processor_shell format [FLAGS] SQL_PATH...
+
+SQL_PATH is a SQL file or a directory searched recursively for SQL files. By
+default, the formatted files are printed to stdout.
+
+ -h, --help                           Prints this guide.
+ -i, --in-place                       Rewrites the files instead.
+ --check                              Lists the files which are not formatted
+                                      instead, failing if there are any. Meant
+                                      for presubmit checks.
+ --perfetto-root DIR                  The Perfetto checkout containing the
+                                      formatter. By default, it is searched in
+                                      the current directory and its parents.
+)");
+}
+
+// Returns the root of the Perfetto checkout containing the current directory
+// or std::nullopt if there is none.
+std::optional<std::string> FindPerfettoRoot() {
+  std::string dir = "./";
+  for (uint32_t i = 0; i < 32; ++i, dir += "../") {
+    if (base::FileExists(dir + kSqlFormatterPath)) {
+      return dir;
+    }
+  }
+  return std::nullopt;
+}
+
+// Runs the "format" subcommand. |argv| starts with "format".
+base::Status FormatMain(int argc, char** argv) {
+  enum LongOption {
+    OPT_CHECK = 1000,
+    OPT_PERFETTO_ROOT,
+  };
+  static const option long_options[] = {
+      {"help", no_argument, nullptr, 'h'},
+      {"in-place", no_argument, nullptr, 'i'},
+      {"check", no_argument, nullptr, OPT_CHECK},
+      {"perfetto-root", required_argument, nullptr, OPT_PERFETTO_ROOT},
+      {nullptr, 0, nullptr, 0}};
+
+  bool in_place = false;
+  bool check = false;
+  std::optional<std::string> root;
+  for (;;) {
+    int option = getopt_long(argc, argv, "hi", long_options, nullptr);
+    if (option == -1)
+      break;  // EOF.
+    if (option == 'i') {
+      in_place = true;
+      continue;
+    }
+    if (option == OPT_CHECK) {
+      check = true;
+      continue;
+    }
+    if (option == OPT_PERFETTO_ROOT) {
+      root = std::string(optarg) + "/";
+      continue;
+    }
+    PrintFormatUsage();
+    exit(option == 'h' ? 0 : 1);
+  }
+  if (optind == argc || (in_place && check)) {
+    PrintFormatUsage();
+    exit(1);
+  }
+
+  if (!root) {
+    root = FindPerfettoRoot();
+  }
+  if (!root || !base::FileExists(*root + kSqlFormatterPath)) {
+    return base::ErrStatus(
+        "Unable to find %s: run this from a Perfetto checkout or pass "
+        "--perfetto-root",
+        kSqlFormatterPath);
+  }
+  // The formatter depends on sqlglot, which tools/install-build-deps installs
+  // in the virtualenv of the checkout.
+  std::string python = *root + ".venv/bin/python3";
+  if (!base::FileExists(python)) {
+    python = "python3";
+  }
+  std::string formatter = *root + kSqlFormatterPath;
+
+  ASSIGN_OR_RETURN(
+      std::vector<std::string> files,
+      ListFilesWithExtension({argv + optind, argv + argc}, ".sql"));
+  if (files.empty()) {
+    return base::OkStatus();
+  }
+
+  if (!check && !in_place) {
+    // Without flags, the formatter formats its stdin.
+    for (const auto& path : files) {
+      std::string sql;
+      if (!base::ReadFile(path, &sql)) {
+        return base::ErrStatus("Unable to read file %s", path.c_str());
+      }
+      base::Subprocess format({python, formatter});
+      format.args.input = std::move(sql);
+      if (!format.Call() || format.returncode() != 0) {
+        return base::ErrStatus("Failed to format %s", path.c_str());
+      }
+    }
+    return base::OkStatus();
+  }
+
+  base::Subprocess format({python, formatter,
+                           check ? "--check-only" : "--in-place"});
+  format.args.exec_cmd.insert(format.args.exec_cmd.end(), files.begin(),
+                              files.end());
+  format.args.stdin_mode = base::Subprocess::InputMode::kDevNull;
+  if (!format.Call() && format.status() != base::Subprocess::kTerminated) {
+    return base::ErrStatus("Failed to run %s", formatter.c_str());
+  }
+  if (format.returncode() != 0) {
+    return base::ErrStatus(
+        check ? "Some files are not formatted: run 'trace_processor_shell "
+                "format -i' on them"
+              : "Failed to format the files");
+  }
+  return base::OkStatus();
+}
+
+void PrintLintUsage() {
+  PERFETTO_ELOG(R"(
+Checks PerfettoSQL packages against the conventions of the standard library:
+public tables, views, functions and macros (whose name does not start with
+'_') are documented and tables and views declare their columns, INCLUDEs are
+used and not circular, and the values of tables, views and functions without
+arguments have the declared types. Types are checked on the trace passed, if
+any, or else on an empty trace.
+Usage: trace_processor_shell lint [FLAGS] [trace_file.pb]
+
+ -h, --help                           Prints this guide.
+ --add-sql-package PACKAGE_PATH       Package to check, following the same
+                                      rules as for the main command. Can be
+                                      repeated.
+ --check                              Fails if there are any findings. Meant
+                                      for presubmit checks.
+)");
+}
+
+// Runs the "lint" subcommand. |argv| starts with "lint".
+base::Status LintMain(int argc, char** argv) {
+  enum LongOption {
+    OPT_ADD_SQL_PACKAGE = 1000,
+    OPT_CHECK,
+  };
+  static const option long_options[] = {
+      {"help", no_argument, nullptr, 'h'},
+      {"add-sql-package", required_argument, nullptr, OPT_ADD_SQL_PACKAGE},
+      {"check", no_argument, nullptr, OPT_CHECK},
+      {nullptr, 0, nullptr, 0}};
+
+  std::vector<std::string> sql_package_paths;
+  bool check = false;
+  for (;;) {
+    int option = getopt_long(argc, argv, "h", long_options, nullptr);
+    if (option == -1)
+      break;  // EOF.
+    if (option == OPT_ADD_SQL_PACKAGE) {
+      sql_package_paths.emplace_back(optarg);
+      continue;
+    }
+    if (option == OPT_CHECK) {
+      check = true;
+      continue;
+    }
+    PrintLintUsage();
+    exit(option == 'h' ? 0 : 1);
+  }
+  if (sql_package_paths.empty() || optind < argc - 1) {
+    PrintLintUsage();
+    exit(1);
+  }
+
+  SqlModuleIndex index;
+  index.AddStdlibModules();
+  std::vector<std::string> packages;
+  for (std::string path : sql_package_paths) {
+    RETURN_IF_ERROR(index.AddPackage(path));
+    while (path.size() > 1 && path.back() == '/') {
+      path.pop_back();
+    }
+    packages.push_back(BaseName(path));
+  }
+  std::vector<std::string> module_keys;
+  for (const std::string& key : index.ModuleKeys()) {
+    for (const std::string& package : packages) {
+      if (base::StartsWith(key, package + ".")) {
+        module_keys.push_back(key);
+        break;
+      }
+    }
+  }
+
+  std::vector<SqlLintFinding> findings = LintSqlModules(&index, module_keys);
+
+  std::unique_ptr<TraceProcessor> tp =
+      TraceProcessor::CreateInstance(Config());
+  g_tp = tp.get();
+  for (const auto& path : sql_package_paths) {
+    RETURN_IF_ERROR(IncludeSqlPackage(path, false));
+  }
+  if (optind == argc - 1) {
+    double size_mb = 0;
+    RETURN_IF_ERROR(LoadTrace(g_tp, argv[optind], &size_mb));
+    PERFETTO_ILOG("Trace loaded: %.2f MB", size_mb);
+  } else {
+    RETURN_IF_ERROR(g_tp->NotifyEndOfFile());
+  }
+  std::vector<SqlLintFinding> type_findings =
+      LintSqlModuleTypes(g_tp, &index, module_keys);
+  findings.insert(findings.end(), type_findings.begin(), type_findings.end());
+  g_tp = nullptr;
+
+  for (const auto& finding : findings) {
+    printf("%s:%u:%u: %s [%s]\n",
+           finding.path.empty() ? finding.module_key.c_str()
+                                : finding.path.c_str(),
+           finding.position.line + 1, finding.position.col + 1,
+           finding.message.c_str(), finding.check.c_str());
+  }
+  if (check && !findings.empty()) {
+    return base::ErrStatus("%zu findings in %zu modules", findings.size(),
+                           module_keys.size());
+  }
+  return base::OkStatus();
+}
+
 base::Status TraceProcessorMain(int argc, char** argv) {
   CommandLineOptions options = ParseCommandLineOptions(argc, argv);
 
@@ -1859,6 +2836,15 @@ base::Status TraceProcessorMain(int argc, char** argv) {
     config.enable_extra_checks = true;
   }
 
+  config.table_cache_dir = options.table_cache_dir;
+  if (options.table_cache_max_size_mb != 0) {
+    config.table_cache_max_size_bytes =
+        options.table_cache_max_size_mb * 1024 * 1024;
+  }
+  config.query_timeout_ms = options.query_timeout_ms;
+  config.query_max_table_memory_bytes =
+      options.query_max_table_memory_mb * 1024 * 1024;
+
   std::unique_ptr<TraceProcessor> tp = TraceProcessor::CreateInstance(config);
   g_tp = tp.get();
 
@@ -1897,16 +2883,38 @@ base::Status TraceProcessorMain(int argc, char** argv) {
   if (!options.trace_file_path.empty()) {
     base::TimeNanos t_load_start = base::GetWallTimeNs();
     double size_mb = 0;
-    RETURN_IF_ERROR(LoadTrace(options.trace_file_path, &size_mb));
+    RETURN_IF_ERROR(LoadTrace(g_tp, options.trace_file_path, &size_mb));
     t_load = base::GetWallTimeNs() - t_load_start;
 
     double t_load_s = static_cast<double>(t_load.count()) / 1E9;
     PERFETTO_ILOG("Trace loaded: %.2f MB in %.2fs (%.1f MB/s)", size_mb,
                   t_load_s, size_mb / t_load_s);
 
+    RETURN_IF_ERROR(PrintStats());
+  } else if (!options.snapshot_in_path.empty()) {
+    base::TimeNanos t_load_start = base::GetWallTimeNs();
+    RETURN_IF_ERROR(g_tp->RestoreSnapshot(options.snapshot_in_path));
+    t_load = base::GetWallTimeNs() - t_load_start;
+
+    double t_load_s = static_cast<double>(t_load.count()) / 1E9;
+    PERFETTO_ILOG("Snapshot restored in %.2fs", t_load_s);
+
     RETURN_IF_ERROR(PrintStats());
   }
 
+  if (!options.snapshot_out_path.empty()) {
+    RETURN_IF_ERROR(g_tp->SaveSnapshot(options.snapshot_out_path));
+  }
+
+  // The database with the tables of the baseline trace, which needs to exist
+  // for as long as it is attached to trace processor.
+  std::optional<base::TempFile> diff_baseline_db;
+  if (!options.diff_baseline_path.empty()) {
+    diff_baseline_db = base::TempFile::Create();
+    RETURN_IF_ERROR(AttachDiffBaseline(config, options.diff_baseline_path,
+                                       diff_baseline_db->path()));
+  }
+
 #if PERFETTO_HAS_SIGNAL_H()
   // Set up interrupt signal to allow the user to abort query.
   signal(SIGINT, [](int) { g_tp->InterruptQuery(); });
@@ -1932,7 +2940,7 @@ base::Status TraceProcessorMain(int argc, char** argv) {
     }
 
     std::vector<TraceSummarySpecBytes> specs;
-    specs.reserve(options.summary_specs.size());
+    specs.reserve(options.summary_specs.size() + 1);
     for (uint32_t i = 0; i < options.summary_specs.size(); ++i) {
       specs.emplace_back(TraceSummarySpecBytes{
           reinterpret_cast<const uint8_t*>(spec_content[i].data()),
@@ -1940,6 +2948,13 @@ base::Status TraceProcessorMain(int argc, char** argv) {
           GuessSummarySpecFormat(options.summary_specs[i], spec_content[i]),
       });
     }
+    if (options.diff_report) {
+      specs.emplace_back(TraceSummarySpecBytes{
+          reinterpret_cast<const uint8_t*>(kTraceDiffSummarySpec),
+          sizeof(kTraceDiffSummarySpec) - 1,
+          TraceSummarySpecBytes::Format::kTextProto,
+      });
+    }
 
     TraceSummaryComputationSpec computation_config;
 
@@ -2004,6 +3019,11 @@ base::Status TraceProcessorMain(int argc, char** argv) {
     RETURN_IF_ERROR(ExportTraceToDatabase(options.export_file_path));
   }
 
+  if (!options.export_arrow_dir.empty()) {
+    RETURN_IF_ERROR(ExportTraceToArrow(options.export_arrow_dir,
+                                       options.export_arrow_queries));
+  }
+
   if (options.enable_httpd) {
 #if PERFETTO_HAS_SIGNAL_H()
     if (options.metatrace_path.empty()) {
@@ -2023,7 +3043,9 @@ base::Status TraceProcessorMain(int argc, char** argv) {
 #if PERFETTO_BUILDFLAG(PERFETTO_TP_HTTPD)
     RunHttpRPCServer(
         /*preloaded_instance=*/std::move(tp),
-        /*is_preloaded_eof=*/!options.trace_file_path.empty(),
+        /*is_preloaded_eof=*/!options.trace_file_path.empty() ||
+            !options.snapshot_in_path.empty(),
+        /*config=*/config,
         /*listen_ip=*/options.listen_ip,
         /*port_number=*/options.port_number,
         /*additional_cors_origins=*/options.additional_cors_origins);
@@ -2034,7 +3056,10 @@ base::Status TraceProcessorMain(int argc, char** argv) {
   }
 
   if (options.enable_stdiod) {
-    return RunStdioRpcServer(std::move(tp), !options.trace_file_path.empty());
+    return RunStdioRpcServer(std::move(tp),
+                             !options.trace_file_path.empty() ||
+                                 !options.snapshot_in_path.empty(),
+                             config);
   }
 
   if (options.launch_shell) {
@@ -2055,7 +3080,19 @@ base::Status TraceProcessorMain(int argc, char** argv) {
 }  // namespace perfetto::trace_processor
 
 int main(int argc, char** argv) {
-  auto status = perfetto::trace_processor::TraceProcessorMain(argc, argv);
+  // Subcommands take the place of the trace file as the first argument.
+  perfetto::base::Status status;
+  if (argc > 1 && strcmp(argv[1], "lsp") == 0) {
+    status = perfetto::trace_processor::LspMain(argc - 1, argv + 1);
+  } else if (argc > 1 && strcmp(argv[1], "test") == 0) {
+    status = perfetto::trace_processor::TestMain(argc - 1, argv + 1);
+  } else if (argc > 1 && strcmp(argv[1], "format") == 0) {
+    status = perfetto::trace_processor::FormatMain(argc - 1, argv + 1);
+  } else if (argc > 1 && strcmp(argv[1], "lint") == 0) {
+    status = perfetto::trace_processor::LintMain(argc - 1, argv + 1);
+  } else {
+    status = perfetto::trace_processor::TraceProcessorMain(argc, argv);
+  }
   if (!status.ok()) {
     fprintf(stderr, "%s\n", status.c_message());
     return 1;
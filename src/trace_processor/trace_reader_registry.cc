@@ -26,11 +26,14 @@
 #include "src/trace_processor/types/trace_processor_context.h"
 #include "src/trace_processor/util/gzip_utils.h"
 #include "src/trace_processor/util/trace_type.h"
+#include "src/trace_processor/util/zstd_utils.h"
 
 namespace perfetto::trace_processor {
 namespace {
 const char kNoZlibErr[] =
     "Cannot open compressed trace. zlib not enabled in the build config";
+const char kNoZstdErr[] =
+    "Cannot open compressed trace. zstd not enabled in the build config";
 
 bool RequiresZlibSupport(TraceType type) {
   switch (type) {
@@ -56,6 +59,10 @@ bool RequiresZlibSupport(TraceType type) {
     case kArtHprofTraceType:
     case kPerfTextTraceType:
     case kTarTraceType:
+    case kCtfTraceType:
+    case kFoldedStackTraceType:
+    case kOtlpTraceType:
+    case kZstdTraceType:
       return false;
   }
   PERFETTO_FATAL("For GCC");
@@ -78,6 +85,11 @@ TraceReaderRegistry::CreateTraceReader(TraceType type) {
                            TraceTypeToString(type), kNoZlibErr);
   }
 
+  if (type == kZstdTraceType && !util::IsZstdSupported()) {
+    return base::ErrStatus("%s support is disabled. %s",
+                           TraceTypeToString(type), kNoZstdErr);
+  }
+
   return base::ErrStatus("%s support is disabled", TraceTypeToString(type));
 }
 
@@ -0,0 +1,401 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/trace_snapshot.h"
+
+#include <errno.h>
+#include <fcntl.h>
+
+#include <cstddef>
+#include <cstdint>
+#include <cstring>
+#include <string>
+#include <unordered_map>
+#include <utility>
+#include <vector>
+
+#include "perfetto/base/logging.h"
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/file_utils.h"
+#include "perfetto/ext/base/flat_hash_map.h"
+#include "perfetto/ext/base/scoped_file.h"
+#include "perfetto/ext/base/status_macros.h"
+#include "perfetto/ext/base/string_view.h"
+#include "perfetto/ext/base/version.h"
+#include "src/trace_processor/containers/string_pool.h"
+#include "src/trace_processor/dataframe/dataframe.h"
+#include "src/trace_processor/perfetto_sql/engine/perfetto_sql_engine.h"
+#include "src/trace_processor/storage/stats.h"
+#include "src/trace_processor/storage/trace_storage.h"
+
+namespace perfetto::trace_processor {
+namespace {
+
+constexpr char kMagic[] = "PFTPSNAP";
+constexpr char kEndMagic[] = "PFTPSEND";
+constexpr size_t kMagicSize = sizeof(kMagic) - 1;
+
+// Must be incremented whenever the layout of the snapshot changes. Changes
+// to the columns of the tables do not require this as they are detected when
+// the dataframes are deserialized.
+constexpr uint32_t kFormatVersion = 2;
+constexpr uint32_t kByteOrderMarker = 0x01020304;
+
+// Strings longer than this are considered corrupted.
+constexpr uint32_t kMaxStringSize = 1u << 30;
+
+constexpr size_t kWriteBufferSize = 1024 * 1024;
+
+// Size of the trailer: offset of the string table and end magic.
+constexpr size_t kTrailerSize = sizeof(uint64_t) + kMagicSize;
+
+class SnapshotWriter : public dataframe::Dataframe::Serializer {
+ public:
+  SnapshotWriter(base::ScopedFile fd, const StringPool* pool)
+      : fd_(std::move(fd)), pool_(pool) {}
+
+  void Write(const void* data, size_t size) override {
+    offset_ += size;
+    const char* ptr = static_cast<const char*>(data);
+    if (buffer_.size() + size > kWriteBufferSize) {
+      Flush();
+      if (size > kWriteBufferSize) {
+        WriteToFile(ptr, size);
+        return;
+      }
+    }
+    buffer_.append(ptr, size);
+  }
+
+  void WriteStrings(const StringPool::Id* ids, size_t count) override {
+    for (size_t i = 0; i < count; ++i) {
+      WriteValue(OrdinalForString(ids[i]));
+    }
+  }
+
+  template <typename T>
+  void WriteValue(const T& value) {
+    Write(&value, sizeof(T));
+  }
+
+  void WriteString(base::StringView str) {
+    WriteValue(static_cast<uint32_t>(str.size()));
+    Write(str.data(), str.size());
+  }
+
+  // Writes the strings referenced so far, followed by the trailer.
+  base::Status Finish() {
+    uint64_t string_table_offset = offset_;
+    WriteValue(static_cast<uint32_t>(strings_.size()));
+    for (StringPool::Id id : strings_) {
+      WriteString(pool_->Get(id));
+    }
+    WriteValue(string_table_offset);
+    Write(kEndMagic, kMagicSize);
+    Flush();
+    return status_;
+  }
+
+ private:
+  // Ordinal 0 is reserved for the null string, the strings in the string
+  // table start from ordinal 1.
+  uint32_t OrdinalForString(StringPool::Id id) {
+    if (id.is_null()) {
+      return 0;
+    }
+    auto [ordinal, inserted] = ordinals_.Insert(
+        id.raw_id(), static_cast<uint32_t>(strings_.size() + 1));
+    if (inserted) {
+      strings_.push_back(id);
+    }
+    return *ordinal;
+  }
+
+  void Flush() {
+    WriteToFile(buffer_.data(), buffer_.size());
+    buffer_.clear();
+  }
+
+  void WriteToFile(const char* data, size_t size) {
+    if (!status_.ok() || size == 0) {
+      return;
+    }
+    if (base::WriteAll(*fd_, data, size) != static_cast<ssize_t>(size)) {
+      status_ =
+          base::ErrStatus("Failed to write snapshot: %s", strerror(errno));
+    }
+  }
+
+  base::ScopedFile fd_;
+  const StringPool* pool_;
+  std::string buffer_;
+  uint64_t offset_ = 0;
+  base::Status status_;
+  base::FlatHashMap<uint32_t, uint32_t> ordinals_;
+  std::vector<StringPool::Id> strings_;
+};
+
+class SnapshotReader : public dataframe::Dataframe::Deserializer {
+ public:
+  SnapshotReader(const uint8_t* data, size_t size)
+      : ptr_(data), end_(data + size) {}
+
+  bool Read(void* data, size_t size) override {
+    if (static_cast<size_t>(end_ - ptr_) < size) {
+      return false;
+    }
+    if (size > 0) {
+      memcpy(data, ptr_, size);
+    }
+    ptr_ += size;
+    return true;
+  }
+
+  bool ReadStrings(StringPool::Id* ids, size_t count) override {
+    for (size_t i = 0; i < count; ++i) {
+      uint32_t ordinal;
+      if (!ReadValue(&ordinal) || ordinal >= strings_.size()) {
+        return false;
+      }
+      ids[i] = strings_[ordinal];
+    }
+    return true;
+  }
+
+  template <typename T>
+  bool ReadValue(T* value) {
+    return Read(value, sizeof(T));
+  }
+
+  bool ReadString(base::StringView* str) {
+    uint32_t size;
+    if (!ReadValue(&size) || size > kMaxStringSize ||
+        static_cast<size_t>(end_ - ptr_) < size) {
+      return false;
+    }
+    *str = base::StringView(reinterpret_cast<const char*>(ptr_), size);
+    ptr_ += size;
+    return true;
+  }
+
+  // Interns all the strings of the string table in |data| into |pool| so
+  // that ReadStrings() can map the ordinals to ids of |pool|.
+  bool ReadStringTable(const uint8_t* data, size_t size, StringPool* pool) {
+    SnapshotReader table_reader(data, size);
+    uint32_t count;
+    if (!table_reader.ReadValue(&count)) {
+      return false;
+    }
+    strings_.clear();
+    strings_.push_back(StringPool::Id::Null());
+    for (uint32_t i = 0; i < count; ++i) {
+      base::StringView str;
+      if (!table_reader.ReadString(&str)) {
+        return false;
+      }
+      strings_.push_back(pool->InternString(str));
+    }
+    return true;
+  }
+
+ private:
+  const uint8_t* ptr_;
+  const uint8_t* end_;
+  std::vector<StringPool::Id> strings_;
+};
+
+void WriteStats(SnapshotWriter* writer, const TraceStorage::StatsMap& stats) {
+  writer->WriteValue(static_cast<uint32_t>(stats.size()));
+  for (size_t key = 0; key < stats.size(); ++key) {
+    writer->WriteString(stats::kNames[key]);
+    writer->WriteValue(stats[key].value);
+    writer->WriteValue(static_cast<uint32_t>(stats[key].indexed_values.size()));
+    for (const auto& [index, value] : stats[key].indexed_values) {
+      writer->WriteValue(static_cast<int32_t>(index));
+      writer->WriteValue(value);
+    }
+  }
+}
+
+// Stats are matched by name, so that snapshots remain readable when stats
+// are added or removed. Unknown stats are ignored.
+base::Status ReadStats(SnapshotReader* reader, TraceStorage::StatsMap* stats) {
+  std::unordered_map<std::string, size_t> keys_by_name;
+  for (size_t key = 0; key < stats::kNumKeys; ++key) {
+    keys_by_name[stats::kNames[key]] = key;
+  }
+  *stats = TraceStorage::StatsMap();
+
+  uint32_t count;
+  if (!reader->ReadValue(&count)) {
+    return base::ErrStatus("Truncated snapshot");
+  }
+  for (uint32_t i = 0; i < count; ++i) {
+    base::StringView name;
+    int64_t value;
+    uint32_t indexed_count;
+    if (!reader->ReadString(&name) || !reader->ReadValue(&value) ||
+        !reader->ReadValue(&indexed_count)) {
+      return base::ErrStatus("Truncated snapshot");
+    }
+    auto it = keys_by_name.find(name.ToStdString());
+    TraceStorage::Stats* stat =
+        it == keys_by_name.end() ? nullptr : &(*stats)[it->second];
+    if (stat) {
+      stat->value = value;
+    }
+    for (uint32_t j = 0; j < indexed_count; ++j) {
+      int32_t index;
+      int64_t indexed_value;
+      if (!reader->ReadValue(&index) || !reader->ReadValue(&indexed_value)) {
+        return base::ErrStatus("Truncated snapshot");
+      }
+      if (stat) {
+        stat->indexed_values[index] = indexed_value;
+      }
+    }
+  }
+  return base::OkStatus();
+}
+
+}  // namespace
+
+base::Status WriteTraceSnapshot(
+    const std::string& path,
+    const TraceStorage& storage,
+    const std::vector<PerfettoSqlEngine::UnfinalizedStaticTable>& tables,
+    const TraceSnapshotInfo& info) {
+  base::ScopedFile fd(base::OpenFile(path, O_CREAT | O_WRONLY | O_TRUNC, 0600));
+  if (!fd) {
+    return base::ErrStatus("Failed to open snapshot file %s: %s", path.c_str(),
+                           strerror(errno));
+  }
+  SnapshotWriter writer(std::move(fd), &storage.string_pool());
+  writer.Write(kMagic, kMagicSize);
+  writer.WriteValue(kFormatVersion);
+  writer.WriteValue(kByteOrderMarker);
+  writer.WriteString(base::GetVersionString());
+
+  writer.WriteString(base::StringView(info.trace_name));
+  writer.WriteValue(info.trace_size_bytes);
+  writer.WriteString(base::StringView(info.trace_content_hash));
+  WriteStats(&writer, storage.stats());
+
+  writer.WriteValue(static_cast<uint32_t>(tables.size()));
+  for (const auto& table : tables) {
+    writer.WriteString(base::StringView(table.name));
+    table.dataframe->Serialize(&writer);
+  }
+  return writer.Finish();
+}
+
+base::Status ReadTraceSnapshot(
+    const uint8_t* data,
+    size_t size,
+    TraceStorage* storage,
+    const std::vector<PerfettoSqlEngine::UnfinalizedStaticTable>& tables,
+    TraceSnapshotInfo* info) {
+  // The trailer points to the string table, which is needed to read the
+  // tables.
+  if (size < kMagicSize + kTrailerSize || memcmp(data, kMagic, kMagicSize)) {
+    return base::ErrStatus("Not a trace processor snapshot");
+  }
+  const uint8_t* trailer = data + size - kTrailerSize;
+  uint64_t string_table_offset;
+  memcpy(&string_table_offset, trailer, sizeof(string_table_offset));
+  if (memcmp(trailer + sizeof(uint64_t), kEndMagic, kMagicSize) ||
+      string_table_offset < kMagicSize ||
+      string_table_offset > size - kTrailerSize) {
+    return base::ErrStatus("Truncated snapshot");
+  }
+  auto body_size = static_cast<size_t>(string_table_offset);
+
+  SnapshotReader reader(data + kMagicSize, body_size - kMagicSize);
+  uint32_t format_version;
+  uint32_t byte_order_marker;
+  if (!reader.ReadValue(&format_version) ||
+      !reader.ReadValue(&byte_order_marker)) {
+    return base::ErrStatus("Truncated snapshot");
+  }
+  if (format_version != kFormatVersion) {
+    return base::ErrStatus(
+        "Snapshot has format version %u, this version of trace processor "
+        "only supports version %u",
+        format_version, kFormatVersion);
+  }
+  if (byte_order_marker != kByteOrderMarker) {
+    return base::ErrStatus(
+        "Snapshot was written on a machine with a different byte order");
+  }
+  base::StringView writer_version;
+  if (!reader.ReadString(&writer_version)) {
+    return base::ErrStatus("Truncated snapshot");
+  }
+  std::string version = writer_version.ToStdString();
+  if (!reader.ReadStringTable(data + body_size,
+                              size - kTrailerSize - body_size,
+                              storage->mutable_string_pool())) {
+    return base::ErrStatus("Invalid string table in snapshot");
+  }
+
+  base::StringView trace_name;
+  base::StringView trace_content_hash;
+  if (!reader.ReadString(&trace_name) ||
+      !reader.ReadValue(&info->trace_size_bytes) ||
+      !reader.ReadString(&trace_content_hash)) {
+    return base::ErrStatus("Truncated snapshot");
+  }
+  info->trace_name = trace_name.ToStdString();
+  info->trace_content_hash = trace_content_hash.ToStdString();
+  RETURN_IF_ERROR(ReadStats(&reader, storage->mutable_stats()));
+
+  std::unordered_map<std::string, dataframe::Dataframe*> dataframes;
+  for (const auto& table : tables) {
+    dataframes[table.name] = table.dataframe;
+  }
+  uint32_t table_count;
+  if (!reader.ReadValue(&table_count)) {
+    return base::ErrStatus("Truncated snapshot");
+  }
+  for (uint32_t i = 0; i < table_count; ++i) {
+    base::StringView name;
+    if (!reader.ReadString(&name)) {
+      return base::ErrStatus("Truncated snapshot");
+    }
+    auto it = dataframes.find(name.ToStdString());
+    if (it == dataframes.end()) {
+      return base::ErrStatus(
+          "Snapshot contains the table %s which is unknown to this version of "
+          "trace processor (snapshot written by %s)",
+          name.ToStdString().c_str(), version.c_str());
+    }
+    base::Status status = it->second->Deserialize(&reader);
+    if (!status.ok()) {
+      return base::ErrStatus(
+          "Failed to read table %s from snapshot (snapshot written by %s): %s",
+          it->first.c_str(), version.c_str(), status.c_message());
+    }
+    dataframes.erase(it);
+  }
+  if (!dataframes.empty()) {
+    return base::ErrStatus(
+        "Snapshot does not contain the table %s (snapshot written by %s)",
+        dataframes.begin()->first.c_str(), version.c_str());
+  }
+  return base::OkStatus();
+}
+
+}  // namespace perfetto::trace_processor
@@ -0,0 +1,75 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_TRACE_SNAPSHOT_H_
+#define SRC_TRACE_PROCESSOR_TRACE_SNAPSHOT_H_
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "src/trace_processor/perfetto_sql/engine/perfetto_sql_engine.h"
+#include "src/trace_processor/storage/trace_storage.h"
+
+namespace perfetto::trace_processor {
+
+// Snapshots contain the state of trace processor after a trace has been
+// parsed: the contents of the static tables, the stats and the name of the
+// trace. Restoring a snapshot is much faster than parsing the trace again.
+//
+// Format (all integers are in the native byte order of the machine which
+// wrote the snapshot, which must match the one of the machine reading it):
+//   header: magic, format version, byte order marker, version of trace
+//           processor which wrote the snapshot.
+//   body: name, size and hash of the trace, stats, then the dataframe of
+//         every table. String values are written as indexes into the string
+//         table.
+//   string table: all the strings referenced by the tables.
+//   trailer: offset of the string table, end magic.
+
+// Information about the trace a snapshot was created from.
+struct TraceSnapshotInfo {
+  std::string trace_name;
+  uint64_t trace_size_bytes = 0;
+  // Hex SHA-256 of the contents of the trace, used to key the table cache.
+  // Empty if it was not computed.
+  std::string trace_content_hash;
+};
+
+// Writes a snapshot of |tables| and of the stats in |storage| to |path|. The
+// dataframes of |tables| must be finalized.
+base::Status WriteTraceSnapshot(
+    const std::string& path,
+    const TraceStorage& storage,
+    const std::vector<PerfettoSqlEngine::UnfinalizedStaticTable>& tables,
+    const TraceSnapshotInfo& info);
+
+// Reads the snapshot in |data| into |tables| and into the stats of |storage|.
+// |tables| must contain exactly the tables in the snapshot and their
+// dataframes must not be finalized. On error, the contents of |tables| are
+// unspecified.
+base::Status ReadTraceSnapshot(
+    const uint8_t* data,
+    size_t size,
+    TraceStorage* storage,
+    const std::vector<PerfettoSqlEngine::UnfinalizedStaticTable>& tables,
+    TraceSnapshotInfo* info);
+
+}  // namespace perfetto::trace_processor
+
+#endif  // SRC_TRACE_PROCESSOR_TRACE_SNAPSHOT_H_
@@ -39,6 +39,7 @@ class ChunkedTraceReader;
 class ClockConverter;
 class ClockTracker;
 class CpuTracker;
+class CtfTraceParser;
 class DeobfuscationMappingTable;
 class DeobfuscationTracker;
 class DescriptorPool;
@@ -151,6 +152,7 @@ class TraceProcessorContext {
   std::unique_ptr<Destructible> binder_tracker;                         // BinderTracker
   std::unique_ptr<Destructible> heap_graph_tracker;                     // HeapGraphTracker
   std::unique_ptr<Destructible> syscall_tracker;                        // SyscallTracker
+  std::unique_ptr<Destructible> futex_tracker;                          // FutexTracker
   std::unique_ptr<Destructible> system_info_tracker;                    // SystemInfoTracker
   std::unique_ptr<Destructible> v4l2_tracker;                           // V4l2Tracker
   std::unique_ptr<Destructible> virtio_video_tracker;                   // VirtioVideoTracker
@@ -168,6 +170,7 @@ class TraceProcessorContext {
   std::unique_ptr<Destructible> etm_tracker;                            // EtmTracker
   std::unique_ptr<Destructible> elf_tracker;                            // ElfTracker
   std::unique_ptr<Destructible> file_tracker;                           // FileTracker
+  std::unique_ptr<Destructible> ctf_tracker;                            // CtfTracker
   // clang-format on
 
   std::unique_ptr<ProtoTraceParser> proto_trace_parser;
@@ -185,6 +188,7 @@ class TraceProcessorContext {
   std::unique_ptr<GeckoTraceParser> gecko_trace_parser;
   std::unique_ptr<ArtMethodParser> art_method_parser;
   std::unique_ptr<PerfTextTraceParser> perf_text_parser;
+  std::unique_ptr<CtfTraceParser> ctf_parser;
 
   // This field contains the list of proto descriptors that can be used by
   // reflection-based parsers.
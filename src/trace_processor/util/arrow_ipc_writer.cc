@@ -0,0 +1,624 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/util/arrow_ipc_writer.h"
+
+#include <algorithm>
+#include <cinttypes>
+#include <cstddef>
+#include <cstdint>
+#include <cstdio>
+#include <cstring>
+#include <limits>
+#include <string>
+#include <string_view>
+#include <utility>
+#include <variant>
+#include <vector>
+
+#include "perfetto/base/logging.h"
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/status_macros.h"
+#include "perfetto/trace_processor/basic_types.h"
+#include "perfetto/trace_processor/iterator.h"
+
+namespace perfetto::trace_processor::util {
+
+namespace {
+
+// Batches are also split when their variable length data grows beyond this
+// size, to keep memory usage bounded and offsets in the int32 range.
+constexpr size_t kMaxBatchBytes = 64ul * 1024 * 1024;
+
+constexpr char kFileMagic[] = "ARROW1";
+constexpr uint32_t kContinuationMarker = 0xFFFFFFFF;
+
+// Enum values from the Arrow flatbuffers schema (Schema.fbs, Message.fbs).
+constexpr int16_t kMetadataVersionV5 = 4;
+constexpr uint8_t kTypeInt = 2;
+constexpr uint8_t kTypeFloatingPoint = 3;
+constexpr uint8_t kTypeBinary = 4;
+constexpr uint8_t kTypeUtf8 = 5;
+constexpr int16_t kPrecisionDouble = 2;
+constexpr int16_t kEndiannessLittle = 0;
+constexpr uint8_t kMessageHeaderSchema = 1;
+constexpr uint8_t kMessageHeaderRecordBatch = 3;
+
+// Field indices of the tables in the Arrow flatbuffers schema. Note that union
+// fields take two slots: one for the type and one for the value.
+enum IntSlots : uint16_t { kIntBitWidth = 0, kIntIsSigned = 1 };
+enum FloatingPointSlots : uint16_t { kFloatingPointPrecision = 0 };
+enum FieldSlots : uint16_t {
+  kFieldName = 0,
+  kFieldNullable = 1,
+  kFieldTypeType = 2,
+  kFieldType = 3,
+  kFieldChildren = 5,
+};
+enum SchemaSlots : uint16_t { kSchemaEndianness = 0, kSchemaFields = 1 };
+enum RecordBatchSlots : uint16_t {
+  kRecordBatchLength = 0,
+  kRecordBatchNodes = 1,
+  kRecordBatchBuffers = 2,
+};
+enum MessageSlots : uint16_t {
+  kMessageVersion = 0,
+  kMessageHeaderType = 1,
+  kMessageHeader = 2,
+  kMessageBodyLength = 3,
+};
+enum FooterSlots : uint16_t {
+  kFooterVersion = 0,
+  kFooterSchema = 1,
+  kFooterDictionaries = 2,
+  kFooterRecordBatches = 3,
+};
+
+size_t AlignTo8(size_t size) {
+  return (size + 7) & ~static_cast<size_t>(7);
+}
+
+template <typename T>
+void AppendLittleEndian(std::vector<uint8_t>& out, T value) {
+  uint8_t bytes[sizeof(T)];
+  memcpy(bytes, &value, sizeof(T));
+  out.insert(out.end(), bytes, bytes + sizeof(T));
+}
+
+// A minimal FlatBuffers builder, sufficient for the Arrow IPC metadata. As in
+// the reference implementation, the buffer is built back to front so that
+// children objects are written before the ones referencing them.
+class FlatBufferBuilder {
+ public:
+  // Position of an object, expressed as the distance from the end of the
+  // buffer.
+  using Offset = uint32_t;
+
+  Offset CreateString(std::string_view str) {
+    PreAlign(str.size() + 1, sizeof(uint32_t));
+    PrependZeros(1);
+    Prepend(str.data(), str.size());
+    PrependScalar(static_cast<uint32_t>(str.size()));
+    return Size();
+  }
+
+  // Creates a vector of structs, |data| being their concatenation.
+  Offset CreateStructVector(const std::vector<uint8_t>& data,
+                            size_t struct_size,
+                            size_t alignment) {
+    PreAlign(data.size(), sizeof(uint32_t));
+    PreAlign(data.size(), alignment);
+    Prepend(data.data(), data.size());
+    PrependScalar(static_cast<uint32_t>(data.size() / struct_size));
+    return Size();
+  }
+
+  Offset CreateOffsetVector(const std::vector<Offset>& offsets) {
+    PreAlign(offsets.size() * sizeof(uint32_t), sizeof(uint32_t));
+    for (auto it = offsets.rbegin(); it != offsets.rend(); ++it) {
+      PrependOffset(*it);
+    }
+    PrependScalar(static_cast<uint32_t>(offsets.size()));
+    return Size();
+  }
+
+  void StartTable() {
+    PERFETTO_DCHECK(fields_.empty());
+    table_start_ = Size();
+  }
+
+  template <typename T>
+  void AddScalar(uint16_t slot, T value) {
+    PrependScalar(value);
+    fields_.push_back({slot, Size()});
+  }
+
+  void AddOffset(uint16_t slot, Offset offset) {
+    PrependOffset(offset);
+    fields_.push_back({slot, Size()});
+  }
+
+  Offset EndTable() {
+    // Placeholder for the offset of the vtable.
+    PrependScalar(int32_t(0));
+    Offset table = Size();
+
+    uint16_t slot_count = 0;
+    for (const FieldLocation& field : fields_) {
+      slot_count = std::max(slot_count, static_cast<uint16_t>(field.slot + 1));
+    }
+    std::vector<uint16_t> vtable(2 + slot_count);
+    vtable[0] = static_cast<uint16_t>(vtable.size() * sizeof(uint16_t));
+    vtable[1] = static_cast<uint16_t>(table - table_start_);
+    for (const FieldLocation& field : fields_) {
+      vtable[2 + field.slot] = static_cast<uint16_t>(table - field.position);
+    }
+    fields_.clear();
+    for (auto it = vtable.rbegin(); it != vtable.rend(); ++it) {
+      PrependScalar(*it);
+    }
+
+    // The vtable is right before the table: the offset is always positive.
+    auto vtable_offset = static_cast<int32_t>(Size() - table);
+    memcpy(&buf_[Size() - table], &vtable_offset, sizeof(vtable_offset));
+    return table;
+  }
+
+  // Returns the final buffer, which has a size multiple of 8 bytes.
+  std::vector<uint8_t> Finish(Offset root) {
+    PreAlign(sizeof(uint32_t), max_alignment_);
+    PrependOffset(root);
+    return std::move(buf_);
+  }
+
+ private:
+  struct FieldLocation {
+    uint16_t slot;
+    Offset position;
+  };
+
+  Offset Size() const { return static_cast<Offset>(buf_.size()); }
+
+  void Prepend(const void* data, size_t size) {
+    const auto* bytes = static_cast<const uint8_t*>(data);
+    buf_.insert(buf_.begin(), bytes, bytes + size);
+  }
+
+  void PrependZeros(size_t count) { buf_.insert(buf_.begin(), count, 0); }
+
+  template <typename T>
+  void PrependScalar(T value) {
+    PreAlign(sizeof(T), sizeof(T));
+    Prepend(&value, sizeof(T));
+  }
+
+  void PrependOffset(Offset offset) {
+    PreAlign(sizeof(uint32_t), sizeof(uint32_t));
+    PrependScalar(static_cast<uint32_t>(Size() + sizeof(uint32_t) - offset));
+  }
+
+  // Adds padding so that, after prepending |size| bytes, the buffer is aligned
+  // to |alignment|.
+  void PreAlign(size_t size, size_t alignment) {
+    max_alignment_ = std::max(max_alignment_, alignment);
+    size_t padding = (alignment - ((buf_.size() + size) % alignment)) %
+                     alignment;
+    PrependZeros(padding);
+  }
+
+  std::vector<uint8_t> buf_;
+  std::vector<FieldLocation> fields_;
+  Offset table_start_ = 0;
+  size_t max_alignment_ = 8;
+};
+
+FlatBufferBuilder::Offset CreateEmptyTable(FlatBufferBuilder& fbb) {
+  fbb.StartTable();
+  return fbb.EndTable();
+}
+
+// Writes a message with the given header (a Schema or a RecordBatch table).
+std::vector<uint8_t> FinishMessage(FlatBufferBuilder& fbb,
+                                   uint8_t header_type,
+                                   FlatBufferBuilder::Offset header,
+                                   uint64_t body_size) {
+  fbb.StartTable();
+  fbb.AddScalar(kMessageBodyLength, static_cast<int64_t>(body_size));
+  fbb.AddOffset(kMessageHeader, header);
+  fbb.AddScalar(kMessageVersion, kMetadataVersionV5);
+  fbb.AddScalar(kMessageHeaderType, header_type);
+  return fbb.Finish(fbb.EndTable());
+}
+
+// Builds the Schema table for |columns| (ArrowIpcWriter::Column instances).
+template <typename Columns>
+FlatBufferBuilder::Offset BuildSchema(FlatBufferBuilder& fbb,
+                                      const Columns& columns) {
+  std::vector<FlatBufferBuilder::Offset> fields;
+  for (const auto& column : columns) {
+    FlatBufferBuilder::Offset name = fbb.CreateString(column.name);
+    FlatBufferBuilder::Offset children = fbb.CreateOffsetVector({});
+    FlatBufferBuilder::Offset type;
+    uint8_t type_type;
+    switch (column.type) {
+      case decltype(column.type)::kInt64:
+        fbb.StartTable();
+        fbb.AddScalar(kIntBitWidth, int32_t(64));
+        fbb.AddScalar(kIntIsSigned, uint8_t(1));
+        type = fbb.EndTable();
+        type_type = kTypeInt;
+        break;
+      case decltype(column.type)::kFloat64:
+        fbb.StartTable();
+        fbb.AddScalar(kFloatingPointPrecision, kPrecisionDouble);
+        type = fbb.EndTable();
+        type_type = kTypeFloatingPoint;
+        break;
+      case decltype(column.type)::kBinary:
+        type = CreateEmptyTable(fbb);
+        type_type = kTypeBinary;
+        break;
+      case decltype(column.type)::kUtf8:
+      case decltype(column.type)::kUnknown:
+        type = CreateEmptyTable(fbb);
+        type_type = kTypeUtf8;
+        break;
+    }
+    fbb.StartTable();
+    fbb.AddOffset(kFieldName, name);
+    fbb.AddOffset(kFieldType, type);
+    fbb.AddOffset(kFieldChildren, children);
+    fbb.AddScalar(kFieldNullable, uint8_t(1));
+    fbb.AddScalar(kFieldTypeType, type_type);
+    fields.push_back(fbb.EndTable());
+  }
+  FlatBufferBuilder::Offset fields_vector = fbb.CreateOffsetVector(fields);
+  fbb.StartTable();
+  fbb.AddOffset(kSchemaFields, fields_vector);
+  fbb.AddScalar(kSchemaEndianness, kEndiannessLittle);
+  return fbb.EndTable();
+}
+
+std::string DoubleToString(double value) {
+  char buf[32];
+  snprintf(buf, sizeof(buf), "%.17g", value);
+  return buf;
+}
+
+}  // namespace
+
+ArrowIpcWriter::ArrowIpcWriter(Format format,
+                               std::vector<std::string> column_names,
+                               Sink sink,
+                               uint32_t batch_rows)
+    : format_(format), sink_(std::move(sink)), batch_rows_(batch_rows) {
+  PERFETTO_CHECK(batch_rows_ > 0);
+  columns_.resize(column_names.size());
+  for (size_t i = 0; i < column_names.size(); ++i) {
+    columns_[i].name = std::move(column_names[i]);
+  }
+}
+
+ArrowIpcWriter::~ArrowIpcWriter() = default;
+
+base::Status ArrowIpcWriter::AppendRow(const std::vector<SqlValue>& row) {
+  PERFETTO_CHECK(!finished_);
+  if (row.size() != columns_.size()) {
+    return base::ErrStatus("Arrow: row has %zu values, expected %zu",
+                           row.size(), columns_.size());
+  }
+  for (size_t i = 0; i < row.size(); ++i) {
+    const SqlValue& value = row[i];
+    Column& column = columns_[i];
+    switch (value.type) {
+      case SqlValue::kNull:
+        column.cells.emplace_back();
+        break;
+      case SqlValue::kLong:
+        column.has_long = true;
+        column.cells.emplace_back(value.long_value);
+        break;
+      case SqlValue::kDouble:
+        column.has_double = true;
+        column.cells.emplace_back(value.double_value);
+        break;
+      case SqlValue::kString:
+        column.has_string = true;
+        column.cells.emplace_back(std::string(value.string_value));
+        pending_bytes_ += strlen(value.string_value);
+        break;
+      case SqlValue::kBytes: {
+        column.has_bytes = true;
+        const auto* bytes = static_cast<const uint8_t*>(value.bytes_value);
+        column.cells.emplace_back(
+            std::vector<uint8_t>(bytes, bytes + value.bytes_count));
+        pending_bytes_ += value.bytes_count;
+        break;
+      }
+    }
+  }
+  if (++pending_rows_ >= batch_rows_ || pending_bytes_ >= kMaxBatchBytes) {
+    return WriteRecordBatch();
+  }
+  return base::OkStatus();
+}
+
+base::Status ArrowIpcWriter::Finish() {
+  PERFETTO_CHECK(!finished_);
+  finished_ = true;
+  // Even empty results have a schema and, for consistency, a record batch.
+  if (pending_rows_ > 0 || !schema_written_) {
+    RETURN_IF_ERROR(WriteRecordBatch());
+  }
+  std::vector<uint8_t> eos;
+  AppendLittleEndian(eos, kContinuationMarker);
+  AppendLittleEndian(eos, uint32_t(0));
+  if (format_ == Format::kStream) {
+    return Write(std::move(eos));
+  }
+  RETURN_IF_ERROR(Write(std::move(eos)));
+  return WriteFooter();
+}
+
+base::Status ArrowIpcWriter::WriteSchema() {
+  for (Column& column : columns_) {
+    if (column.has_bytes) {
+      column.type = ColumnType::kBinary;
+    } else if (column.has_string) {
+      column.type = ColumnType::kUtf8;
+    } else if (column.has_double) {
+      column.type = ColumnType::kFloat64;
+    } else if (column.has_long) {
+      column.type = ColumnType::kInt64;
+    } else {
+      column.type = ColumnType::kUtf8;
+    }
+  }
+
+  if (format_ == Format::kFile) {
+    std::vector<uint8_t> magic(kFileMagic, kFileMagic + strlen(kFileMagic));
+    magic.resize(AlignTo8(magic.size()));
+    RETURN_IF_ERROR(Write(std::move(magic)));
+  }
+
+  FlatBufferBuilder fbb;
+  FlatBufferBuilder::Offset schema = BuildSchema(fbb, columns_);
+  schema_written_ = true;
+  return WriteMessage(FinishMessage(fbb, kMessageHeaderSchema, schema, 0), {},
+                      nullptr);
+}
+
+base::Status ArrowIpcWriter::WriteRecordBatch() {
+  if (!schema_written_) {
+    RETURN_IF_ERROR(WriteSchema());
+  }
+
+  std::vector<uint8_t> body;
+  std::vector<uint8_t> nodes;
+  std::vector<uint8_t> buffers;
+  auto add_buffer = [&](const void* data, size_t size) {
+    AppendLittleEndian(buffers, static_cast<int64_t>(body.size()));
+    AppendLittleEndian(buffers, static_cast<int64_t>(size));
+    const auto* bytes = static_cast<const uint8_t*>(data);
+    body.insert(body.end(), bytes, bytes + size);
+    body.resize(AlignTo8(body.size()));
+  };
+
+  for (Column& column : columns_) {
+    const size_t rows = column.cells.size();
+    PERFETTO_DCHECK(rows == pending_rows_);
+    std::vector<uint8_t> validity((rows + 7) / 8);
+    size_t null_count = 0;
+    std::vector<int64_t> longs;
+    std::vector<double> doubles;
+    std::vector<int32_t> offsets;
+    std::string data;
+
+    auto type_error = [&column](const char* value_type) {
+      return base::ErrStatus(
+          "Arrow: cannot store %s value in column '%s' (%s). Use CAST to "
+          "give the column a consistent type.",
+          value_type, column.name.c_str(),
+          column.type == ColumnType::kInt64     ? "int64"
+          : column.type == ColumnType::kFloat64 ? "float64"
+          : column.type == ColumnType::kBinary  ? "binary"
+                                                : "utf8");
+    };
+
+    switch (column.type) {
+      case ColumnType::kInt64:
+        longs.resize(rows);
+        break;
+      case ColumnType::kFloat64:
+        doubles.resize(rows);
+        break;
+      case ColumnType::kUtf8:
+      case ColumnType::kBinary:
+      case ColumnType::kUnknown:
+        offsets.reserve(rows + 1);
+        offsets.push_back(0);
+        break;
+    }
+
+    for (size_t i = 0; i < rows; ++i) {
+      const Cell& cell = column.cells[i];
+      if (std::holds_alternative<std::monostate>(cell)) {
+        ++null_count;
+      } else {
+        validity[i / 8] |= static_cast<uint8_t>(1u << (i % 8));
+      }
+      switch (column.type) {
+        case ColumnType::kInt64:
+          if (const auto* l = std::get_if<int64_t>(&cell); l) {
+            longs[i] = *l;
+          } else if (std::holds_alternative<double>(cell)) {
+            return type_error("a REAL");
+          } else if (!std::holds_alternative<std::monostate>(cell)) {
+            return type_error("a STRING or BLOB");
+          }
+          break;
+        case ColumnType::kFloat64:
+          if (const auto* d = std::get_if<double>(&cell); d) {
+            doubles[i] = *d;
+          } else if (const auto* l = std::get_if<int64_t>(&cell); l) {
+            doubles[i] = static_cast<double>(*l);
+          } else if (!std::holds_alternative<std::monostate>(cell)) {
+            return type_error("a STRING or BLOB");
+          }
+          break;
+        case ColumnType::kUtf8:
+        case ColumnType::kBinary:
+        case ColumnType::kUnknown:
+          if (const auto* l = std::get_if<int64_t>(&cell); l) {
+            data += std::to_string(*l);
+          } else if (const auto* d = std::get_if<double>(&cell); d) {
+            data += DoubleToString(*d);
+          } else if (const auto* str = std::get_if<std::string>(&cell); str) {
+            data += *str;
+          } else if (const auto* b = std::get_if<std::vector<uint8_t>>(&cell);
+                     b) {
+            // Blobs are not necessarily valid UTF-8.
+            if (column.type != ColumnType::kBinary) {
+              return type_error("a BLOB");
+            }
+            data.append(b->begin(), b->end());
+          }
+          if (data.size() > std::numeric_limits<int32_t>::max()) {
+            return base::ErrStatus(
+                "Arrow: too much data in column '%s' for a single batch",
+                column.name.c_str());
+          }
+          offsets.push_back(static_cast<int32_t>(data.size()));
+          break;
+      }
+    }
+
+    AppendLittleEndian(nodes, static_cast<int64_t>(rows));
+    AppendLittleEndian(nodes, static_cast<int64_t>(null_count));
+    // The validity bitmap can be omitted if all the values are valid.
+    add_buffer(validity.data(), null_count == 0 ? 0 : validity.size());
+    switch (column.type) {
+      case ColumnType::kInt64:
+        add_buffer(longs.data(), longs.size() * sizeof(int64_t));
+        break;
+      case ColumnType::kFloat64:
+        add_buffer(doubles.data(), doubles.size() * sizeof(double));
+        break;
+      case ColumnType::kUtf8:
+      case ColumnType::kBinary:
+      case ColumnType::kUnknown:
+        add_buffer(offsets.data(), offsets.size() * sizeof(int32_t));
+        add_buffer(data.data(), data.size());
+        break;
+    }
+    column.cells.clear();
+  }
+
+  FlatBufferBuilder fbb;
+  // FieldNode and Buffer are both structs of two int64.
+  FlatBufferBuilder::Offset nodes_vector =
+      fbb.CreateStructVector(nodes, 2 * sizeof(int64_t), sizeof(int64_t));
+  FlatBufferBuilder::Offset buffers_vector =
+      fbb.CreateStructVector(buffers, 2 * sizeof(int64_t), sizeof(int64_t));
+  fbb.StartTable();
+  fbb.AddScalar(kRecordBatchLength, static_cast<int64_t>(pending_rows_));
+  fbb.AddOffset(kRecordBatchNodes, nodes_vector);
+  fbb.AddOffset(kRecordBatchBuffers, buffers_vector);
+  FlatBufferBuilder::Offset record_batch = fbb.EndTable();
+
+  Block block;
+  RETURN_IF_ERROR(WriteMessage(FinishMessage(fbb, kMessageHeaderRecordBatch,
+                                             record_batch, body.size()),
+                               body, &block));
+  record_batches_.push_back(block);
+  pending_rows_ = 0;
+  pending_bytes_ = 0;
+  return base::OkStatus();
+}
+
+base::Status ArrowIpcWriter::WriteMessage(const std::vector<uint8_t>& metadata,
+                                          const std::vector<uint8_t>& body,
+                                          Block* block) {
+  PERFETTO_DCHECK(metadata.size() % 8 == 0 && body.size() % 8 == 0);
+  std::vector<uint8_t> message;
+  message.reserve(2 * sizeof(uint32_t) + metadata.size() + body.size());
+  AppendLittleEndian(message, kContinuationMarker);
+  AppendLittleEndian(message, static_cast<uint32_t>(metadata.size()));
+  message.insert(message.end(), metadata.begin(), metadata.end());
+  message.insert(message.end(), body.begin(), body.end());
+  if (block) {
+    block->offset = bytes_written_;
+    block->metadata_size =
+        static_cast<uint32_t>(2 * sizeof(uint32_t) + metadata.size());
+    block->body_size = body.size();
+  }
+  return Write(std::move(message));
+}
+
+base::Status ArrowIpcWriter::WriteFooter() {
+  FlatBufferBuilder fbb;
+  FlatBufferBuilder::Offset schema = BuildSchema(fbb, columns_);
+  std::vector<uint8_t> blocks;
+  for (const Block& block : record_batches_) {
+    AppendLittleEndian(blocks, static_cast<int64_t>(block.offset));
+    AppendLittleEndian(blocks, static_cast<int32_t>(block.metadata_size));
+    AppendLittleEndian(blocks, int32_t(0));  // Padding.
+    AppendLittleEndian(blocks, static_cast<int64_t>(block.body_size));
+  }
+  constexpr size_t kBlockSize = 24;
+  FlatBufferBuilder::Offset record_batches =
+      fbb.CreateStructVector(blocks, kBlockSize, sizeof(int64_t));
+  FlatBufferBuilder::Offset dictionaries =
+      fbb.CreateStructVector({}, kBlockSize, sizeof(int64_t));
+  fbb.StartTable();
+  fbb.AddOffset(kFooterSchema, schema);
+  fbb.AddOffset(kFooterDictionaries, dictionaries);
+  fbb.AddOffset(kFooterRecordBatches, record_batches);
+  fbb.AddScalar(kFooterVersion, kMetadataVersionV5);
+  std::vector<uint8_t> footer = fbb.Finish(fbb.EndTable());
+
+  auto footer_size = static_cast<int32_t>(footer.size());
+  AppendLittleEndian(footer, footer_size);
+  footer.insert(footer.end(), kFileMagic, kFileMagic + strlen(kFileMagic));
+  return Write(std::move(footer));
+}
+
+base::Status ArrowIpcWriter::Write(std::vector<uint8_t> data) {
+  bytes_written_ += data.size();
+  return sink_(data.data(), data.size());
+}
+
+base::Status WriteIteratorAsArrow(Iterator& it,
+                                  ArrowIpcWriter::Format format,
+                                  ArrowIpcWriter::Sink sink,
+                                  uint32_t batch_rows) {
+  std::vector<std::string> column_names;
+  for (uint32_t i = 0; i < it.ColumnCount(); ++i) {
+    column_names.push_back(it.GetColumnName(i));
+  }
+  ArrowIpcWriter writer(format, column_names, std::move(sink), batch_rows);
+  std::vector<SqlValue> row(column_names.size());
+  while (it.Next()) {
+    for (uint32_t i = 0; i < row.size(); ++i) {
+      row[i] = it.Get(i);
+    }
+    RETURN_IF_ERROR(writer.AppendRow(row));
+  }
+  RETURN_IF_ERROR(it.Status());
+  return writer.Finish();
+}
+
+}  // namespace perfetto::trace_processor::util
@@ -0,0 +1,151 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_UTIL_ARROW_IPC_WRITER_H_
+#define SRC_TRACE_PROCESSOR_UTIL_ARROW_IPC_WRITER_H_
+
+#include <cstddef>
+#include <cstdint>
+#include <functional>
+#include <string>
+#include <variant>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/trace_processor/basic_types.h"
+
+namespace perfetto::trace_processor {
+
+class Iterator;
+
+namespace util {
+
+// Serializes rows of SQL values in the Apache Arrow IPC format, which can be
+// read directly by pyarrow, pandas, Polars, DuckDB and most other data
+// analysis tools. See
+// https://arrow.apache.org/docs/format/Columnar.html#serialization-and-interprocess-communication-ipc
+//
+// SQL values are dynamically typed so the Arrow type of each column is inferred
+// from the values in the first record batch:
+//  - columns containing BLOBs are binary;
+//  - otherwise, columns containing strings are utf8;
+//  - otherwise, columns containing REALs are float64;
+//  - otherwise, columns containing INTEGERs are int64;
+//  - columns only containing NULLs are utf8.
+// All columns are nullable. Values in later batches are converted to the type
+// of their column where this is lossless (e.g. integers to float64 or to their
+// text representation). Otherwise (e.g. a string in an int64 column), an
+// error is returned: use CAST in the query to make the type of such columns
+// explicit.
+class ArrowIpcWriter {
+ public:
+  enum class Format {
+    // The streaming format: a sequence of IPC messages which can be decoded as
+    // they are received (e.g. with pyarrow.ipc.open_stream).
+    kStream,
+    // The random access file format (a.k.a. Feather V2), usually stored in
+    // files with the .arrow extension (e.g. pyarrow.ipc.open_file or
+    // pandas.read_feather).
+    kFile,
+  };
+
+  // Invoked with each chunk of serialized data, in order. Each chunk contains a
+  // whole number of IPC messages: with the streaming format, the first chunk
+  // contains the schema, each of the following ones a record batch and the
+  // last one the end of stream marker.
+  using Sink = std::function<base::Status(const uint8_t*, size_t)>;
+
+  // Maximum number of rows in each record batch.
+  static constexpr uint32_t kDefaultBatchRows = 64 * 1024;
+
+  ArrowIpcWriter(Format format,
+                 std::vector<std::string> column_names,
+                 Sink sink,
+                 uint32_t batch_rows = kDefaultBatchRows);
+  ~ArrowIpcWriter();
+
+  ArrowIpcWriter(const ArrowIpcWriter&) = delete;
+  ArrowIpcWriter& operator=(const ArrowIpcWriter&) = delete;
+
+  // Appends a row: |row| must have one value per column. The values are copied
+  // so they don't need to outlive this call.
+  base::Status AppendRow(const std::vector<SqlValue>& row);
+
+  // Writes the pending rows followed by the end of stream marker (or the
+  // footer for the file format). No rows can be appended afterwards.
+  base::Status Finish();
+
+ private:
+  enum class ColumnType { kUnknown, kInt64, kFloat64, kUtf8, kBinary };
+
+  using Cell = std::variant<std::monostate,
+                            int64_t,
+                            double,
+                            std::string,
+                            std::vector<uint8_t>>;
+
+  struct Column {
+    std::string name;
+    ColumnType type = ColumnType::kUnknown;
+
+    // Values of the current batch and which kind of values were seen, used to
+    // infer the type of the column.
+    std::vector<Cell> cells;
+    bool has_long = false;
+    bool has_double = false;
+    bool has_string = false;
+    bool has_bytes = false;
+  };
+
+  // Details of a message written to the output, needed for the footer of the
+  // file format.
+  struct Block {
+    uint64_t offset;
+    uint32_t metadata_size;
+    uint64_t body_size;
+  };
+
+  base::Status WriteSchema();
+  base::Status WriteRecordBatch();
+  base::Status WriteMessage(const std::vector<uint8_t>& metadata,
+                            const std::vector<uint8_t>& body,
+                            Block* block);
+  base::Status WriteFooter();
+  base::Status Write(std::vector<uint8_t> data);
+
+  const Format format_;
+  const Sink sink_;
+  const uint32_t batch_rows_;
+  std::vector<Column> columns_;
+  uint32_t pending_rows_ = 0;
+  size_t pending_bytes_ = 0;
+  bool schema_written_ = false;
+  bool finished_ = false;
+  uint64_t bytes_written_ = 0;
+  std::vector<Block> record_batches_;
+};
+
+// Serializes all the rows returned by |it| with an ArrowIpcWriter.
+base::Status WriteIteratorAsArrow(Iterator& it,
+                                  ArrowIpcWriter::Format format,
+                                  ArrowIpcWriter::Sink sink,
+                                  uint32_t batch_rows =
+                                      ArrowIpcWriter::kDefaultBatchRows);
+
+}  // namespace util
+}  // namespace perfetto::trace_processor
+
+#endif  // SRC_TRACE_PROCESSOR_UTIL_ARROW_IPC_WRITER_H_
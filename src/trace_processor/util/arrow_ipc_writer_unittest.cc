@@ -0,0 +1,318 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/util/arrow_ipc_writer.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <cstring>
+#include <string>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/trace_processor/basic_types.h"
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto::trace_processor::util {
+namespace {
+
+using Format = ArrowIpcWriter::Format;
+
+template <typename T>
+T ReadAt(const uint8_t* ptr) {
+  T value;
+  memcpy(&value, ptr, sizeof(T));
+  return value;
+}
+
+// Minimal accessor for flatbuffers tables, enough to check the metadata
+// written by ArrowIpcWriter.
+class Table {
+ public:
+  explicit Table(const uint8_t* table) : table_(table) {
+    vtable_ = table_ - ReadAt<int32_t>(table_);
+  }
+
+  static Table Root(const uint8_t* buf) {
+    return Table(buf + ReadAt<uint32_t>(buf));
+  }
+
+  template <typename T>
+  T Scalar(uint16_t slot) const {
+    const uint8_t* field = Field(slot);
+    return field ? ReadAt<T>(field) : T();
+  }
+
+  Table Child(uint16_t slot) const { return Table(Deref(Field(slot))); }
+
+  std::string String(uint16_t slot) const {
+    const uint8_t* str = Deref(Field(slot));
+    return std::string(reinterpret_cast<const char*>(str + 4),
+                       ReadAt<uint32_t>(str));
+  }
+
+  uint32_t VectorSize(uint16_t slot) const {
+    return ReadAt<uint32_t>(Deref(Field(slot)));
+  }
+
+  // Returns the |i|-th table of a vector of tables.
+  Table TableAt(uint16_t slot, uint32_t i) const {
+    const uint8_t* elem = Deref(Field(slot)) + 4 + 4 * i;
+    return Table(Deref(elem));
+  }
+
+  // Returns the |i|-th int64 of a vector of structs made of int64s.
+  int64_t Int64At(uint16_t slot, uint32_t i) const {
+    return ReadAt<int64_t>(Deref(Field(slot)) + 4 + 8 * i);
+  }
+
+ private:
+  const uint8_t* Field(uint16_t slot) const {
+    uint16_t vtable_size = ReadAt<uint16_t>(vtable_);
+    if (4u + 2u * slot >= vtable_size) {
+      return nullptr;
+    }
+    uint16_t offset = ReadAt<uint16_t>(vtable_ + 4 + 2 * slot);
+    return offset ? table_ + offset : nullptr;
+  }
+
+  static const uint8_t* Deref(const uint8_t* field) {
+    return field + ReadAt<uint32_t>(field);
+  }
+
+  const uint8_t* table_;
+  const uint8_t* vtable_;
+};
+
+struct Message {
+  std::vector<uint8_t> metadata;
+  std::vector<uint8_t> body;
+
+  Table header() const { return Table::Root(metadata.data()).Child(2); }
+  uint8_t header_type() const {
+    return Table::Root(metadata.data()).Scalar<uint8_t>(1);
+  }
+};
+
+// Splits an Arrow IPC stream into its messages. Returns false if the stream
+// is not terminated by the end of stream marker.
+bool ParseStream(const std::vector<uint8_t>& data,
+                 size_t offset,
+                 std::vector<Message>* messages) {
+  while (offset + 8 <= data.size()) {
+    EXPECT_EQ(ReadAt<uint32_t>(&data[offset]), 0xFFFFFFFFu);
+    uint32_t metadata_size = ReadAt<uint32_t>(&data[offset + 4]);
+    offset += 8;
+    if (metadata_size == 0) {
+      return true;
+    }
+    EXPECT_EQ(metadata_size % 8, 0u);
+    Message msg;
+    msg.metadata.assign(&data[offset], &data[offset] + metadata_size);
+    offset += metadata_size;
+    auto body_size = static_cast<size_t>(
+        Table::Root(msg.metadata.data()).Scalar<int64_t>(3));
+    msg.body.assign(&data[offset], &data[offset] + body_size);
+    offset += body_size;
+    messages->push_back(std::move(msg));
+  }
+  return false;
+}
+
+class ArrowIpcWriterTest : public ::testing::Test {
+ protected:
+  std::unique_ptr<ArrowIpcWriter> CreateWriter(
+      Format format,
+      std::vector<std::string> columns,
+      uint32_t batch_rows = ArrowIpcWriter::kDefaultBatchRows) {
+    return std::make_unique<ArrowIpcWriter>(
+        format, std::move(columns),
+        [this](const uint8_t* data, size_t size) {
+          ++chunks_;
+          output_.insert(output_.end(), data, data + size);
+          return base::OkStatus();
+        },
+        batch_rows);
+  }
+
+  std::vector<uint8_t> output_;
+  size_t chunks_ = 0;
+};
+
+TEST_F(ArrowIpcWriterTest, Schema) {
+  auto writer = CreateWriter(Format::kStream, {"id", "dur", "name", "nothing"});
+  ASSERT_TRUE(writer
+                  ->AppendRow({SqlValue::Long(1), SqlValue::Double(1.5),
+                               SqlValue::String("foo"), SqlValue()})
+                  .ok());
+  ASSERT_TRUE(writer->Finish().ok());
+
+  std::vector<Message> messages;
+  ASSERT_TRUE(ParseStream(output_, 0, &messages));
+  ASSERT_EQ(messages.size(), 2u);
+  // Schema message.
+  ASSERT_EQ(messages[0].header_type(), 1);
+  Table schema = messages[0].header();
+  ASSERT_EQ(schema.VectorSize(1), 4u);
+  std::vector<std::string> names;
+  std::vector<uint8_t> types;
+  for (uint32_t i = 0; i < 4; ++i) {
+    Table field = schema.TableAt(1, i);
+    names.push_back(field.String(0));
+    types.push_back(field.Scalar<uint8_t>(2));
+    EXPECT_TRUE(field.Scalar<uint8_t>(1));
+    EXPECT_EQ(field.VectorSize(5), 0u);
+  }
+  EXPECT_THAT(names, testing::ElementsAre("id", "dur", "name", "nothing"));
+  // Int, FloatingPoint, Utf8, Utf8.
+  EXPECT_THAT(types, testing::ElementsAre(2, 3, 5, 5));
+  EXPECT_EQ(schema.TableAt(1, 0).Child(3).Scalar<int32_t>(0), 64);
+  EXPECT_TRUE(schema.TableAt(1, 0).Child(3).Scalar<uint8_t>(1));
+  EXPECT_EQ(schema.TableAt(1, 1).Child(3).Scalar<int16_t>(0), 2);
+}
+
+TEST_F(ArrowIpcWriterTest, RecordBatch) {
+  auto writer = CreateWriter(Format::kStream, {"ts", "name"});
+  ASSERT_TRUE(
+      writer->AppendRow({SqlValue::Long(10), SqlValue::String("a")}).ok());
+  ASSERT_TRUE(writer->AppendRow({SqlValue(), SqlValue::String("bcd")}).ok());
+  ASSERT_TRUE(writer->AppendRow({SqlValue::Long(-5), SqlValue()}).ok());
+  ASSERT_TRUE(writer->Finish().ok());
+
+  std::vector<Message> messages;
+  ASSERT_TRUE(ParseStream(output_, 0, &messages));
+  ASSERT_EQ(messages.size(), 2u);
+  const Message& batch = messages[1];
+  ASSERT_EQ(batch.header_type(), 3);
+  Table record_batch = batch.header();
+  EXPECT_EQ(record_batch.Scalar<int64_t>(0), 3);
+
+  // Nodes: (length, null_count) for each column.
+  ASSERT_EQ(record_batch.VectorSize(1), 2u);
+  EXPECT_EQ(record_batch.Int64At(1, 0), 3);
+  EXPECT_EQ(record_batch.Int64At(1, 1), 1);
+  EXPECT_EQ(record_batch.Int64At(1, 2), 3);
+  EXPECT_EQ(record_batch.Int64At(1, 3), 1);
+
+  // Buffers: validity + values for "ts", validity + offsets + data for "name".
+  ASSERT_EQ(record_batch.VectorSize(2), 5u);
+  auto buffer = [&](uint32_t i) {
+    auto offset = static_cast<size_t>(record_batch.Int64At(2, 2 * i));
+    EXPECT_EQ(offset % 8, 0u);
+    return &batch.body[offset];
+  };
+  EXPECT_EQ(record_batch.Int64At(2, 1), 1);
+  EXPECT_EQ(*buffer(0), 0b101);
+  EXPECT_EQ(ReadAt<int64_t>(buffer(1)), 10);
+  EXPECT_EQ(ReadAt<int64_t>(buffer(1) + 16), -5);
+  EXPECT_EQ(*buffer(2), 0b011);
+  EXPECT_EQ(ReadAt<int32_t>(buffer(3) + 0), 0);
+  EXPECT_EQ(ReadAt<int32_t>(buffer(3) + 4), 1);
+  EXPECT_EQ(ReadAt<int32_t>(buffer(3) + 8), 4);
+  EXPECT_EQ(ReadAt<int32_t>(buffer(3) + 12), 4);
+  EXPECT_EQ(std::string(reinterpret_cast<const char*>(buffer(4)), 4), "abcd");
+}
+
+TEST_F(ArrowIpcWriterTest, SplitsBatches) {
+  auto writer = CreateWriter(Format::kStream, {"value"}, /*batch_rows=*/2);
+  for (int64_t i = 0; i < 5; ++i) {
+    ASSERT_TRUE(writer->AppendRow({SqlValue::Long(i)}).ok());
+  }
+  ASSERT_TRUE(writer->Finish().ok());
+
+  std::vector<Message> messages;
+  ASSERT_TRUE(ParseStream(output_, 0, &messages));
+  ASSERT_EQ(messages.size(), 4u);
+  EXPECT_EQ(messages[1].header().Scalar<int64_t>(0), 2);
+  EXPECT_EQ(messages[2].header().Scalar<int64_t>(0), 2);
+  EXPECT_EQ(messages[3].header().Scalar<int64_t>(0), 1);
+  // Schema + 3 batches + end of stream.
+  EXPECT_EQ(chunks_, 5u);
+}
+
+TEST_F(ArrowIpcWriterTest, EmptyResult) {
+  auto writer = CreateWriter(Format::kStream, {"a", "b"});
+  ASSERT_TRUE(writer->Finish().ok());
+
+  std::vector<Message> messages;
+  ASSERT_TRUE(ParseStream(output_, 0, &messages));
+  ASSERT_EQ(messages.size(), 2u);
+  EXPECT_EQ(messages[1].header().Scalar<int64_t>(0), 0);
+}
+
+TEST_F(ArrowIpcWriterTest, ConvertsValuesInLaterBatches) {
+  auto writer =
+      CreateWriter(Format::kStream, {"real", "text"}, /*batch_rows=*/1);
+  ASSERT_TRUE(
+      writer->AppendRow({SqlValue::Double(0.5), SqlValue::String("x")}).ok());
+  ASSERT_TRUE(writer->AppendRow({SqlValue::Long(2), SqlValue::Long(42)}).ok());
+  ASSERT_TRUE(writer->Finish().ok());
+
+  std::vector<Message> messages;
+  ASSERT_TRUE(ParseStream(output_, 0, &messages));
+  ASSERT_EQ(messages.size(), 3u);
+  Table batch = messages[2].header();
+  auto buffer = [&](uint32_t i) {
+    return &messages[2].body[static_cast<size_t>(batch.Int64At(2, 2 * i))];
+  };
+  EXPECT_EQ(ReadAt<double>(buffer(1)), 2.0);
+  EXPECT_EQ(std::string(reinterpret_cast<const char*>(buffer(4)), 2), "42");
+}
+
+TEST_F(ArrowIpcWriterTest, IncompatibleValueInLaterBatch) {
+  auto writer = CreateWriter(Format::kStream, {"id"}, /*batch_rows=*/1);
+  ASSERT_TRUE(writer->AppendRow({SqlValue::Long(1)}).ok());
+  base::Status status = writer->AppendRow({SqlValue::String("foo")});
+  ASSERT_FALSE(status.ok());
+  EXPECT_THAT(status.message(), testing::HasSubstr("'id'"));
+}
+
+TEST_F(ArrowIpcWriterTest, FileFormat) {
+  auto writer = CreateWriter(Format::kFile, {"id"}, /*batch_rows=*/2);
+  for (int64_t i = 0; i < 3; ++i) {
+    ASSERT_TRUE(writer->AppendRow({SqlValue::Long(i)}).ok());
+  }
+  ASSERT_TRUE(writer->Finish().ok());
+
+  ASSERT_GT(output_.size(), 16u);
+  EXPECT_EQ(std::string(output_.begin(), output_.begin() + 8),
+            std::string("ARROW1\0\0", 8));
+  EXPECT_EQ(std::string(output_.end() - 6, output_.end()), "ARROW1");
+
+  std::vector<Message> messages;
+  ASSERT_TRUE(ParseStream(output_, 8, &messages));
+  ASSERT_EQ(messages.size(), 3u);
+
+  auto footer_size = ReadAt<int32_t>(&output_[output_.size() - 10]);
+  const uint8_t* footer = &output_[output_.size() - 10 - size_t(footer_size)];
+  Table root = Table::Root(footer);
+  EXPECT_EQ(root.Scalar<int16_t>(0), 4);
+  EXPECT_EQ(root.Child(1).VectorSize(1), 1u);
+  EXPECT_EQ(root.VectorSize(2), 0u);
+  // Blocks are (offset, metadata length + padding, body length).
+  ASSERT_EQ(root.VectorSize(3), 2u);
+  for (uint32_t i = 0; i < 2; ++i) {
+    auto offset = static_cast<size_t>(root.Int64At(3, 3 * i));
+    EXPECT_EQ(ReadAt<uint32_t>(&output_[offset]), 0xFFFFFFFFu);
+    Table message = Table::Root(&output_[offset + 8]);
+    EXPECT_EQ(message.Scalar<uint8_t>(1), 3);
+    EXPECT_EQ(message.Child(2).Scalar<int64_t>(0), i == 0 ? 2 : 1);
+    EXPECT_EQ(root.Int64At(3, 3 * i + 2), message.Scalar<int64_t>(3));
+  }
+}
+
+}  // namespace
+}  // namespace perfetto::trace_processor::util
@@ -93,7 +93,11 @@ class Regex {
   // The first element is the full match. Subsequent elements are parenthesized
   // subexpressions.
   // Returns nullopt if there is no match.
-  void Submatch(const char* s, std::vector<std::string_view>& out) {
+  // If |not_bol| is true, |s| is not the beginning of the string (e.g. it's
+  // the remainder after a previous match) so "^" doesn't match at its start.
+  void Submatch(const char* s,
+                std::vector<std::string_view>& out,
+                bool not_bol = false) {
 #if !PERFETTO_BUILDFLAG(PERFETTO_OS_WIN)
     PERFETTO_CHECK(regex_);
     const auto& rgx = regex_.value();
@@ -101,7 +105,8 @@ class Regex {
     pmatch_.resize(nmatch);
 
     out.clear();
-    if (regexec(&rgx, s, nmatch, pmatch_.data(), 0) != 0) {
+    int eflags = not_bol ? REG_NOTBOL : 0;
+    if (regexec(&rgx, s, nmatch, pmatch_.data(), eflags) != 0) {
       return;
     }
     for (size_t i = 0; i < nmatch; ++i) {
@@ -115,7 +120,7 @@ class Regex {
       }
     }
 #else
-    base::ignore_result(s);
+    base::ignore_result(s, not_bol);
     PERFETTO_FATAL("Windows regex is not supported.");
 #endif
   }
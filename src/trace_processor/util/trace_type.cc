@@ -26,6 +26,9 @@
 #include "perfetto/ext/base/string_utils.h"
 #include "perfetto/protozero/proto_utils.h"
 #include "src/trace_processor/importers/android_bugreport/android_log_event.h"
+#include "src/trace_processor/importers/ctf/ctf_metadata.h"
+#include "src/trace_processor/importers/folded_stack/folded_stack_line_parser.h"
+#include "src/trace_processor/importers/otlp/otlp_proto_decoder.h"
 #include "src/trace_processor/importers/perf_text/perf_text_sample_line_parser.h"
 
 #include "protos/perfetto/trace/trace.pbzero.h"
@@ -40,6 +43,7 @@ constexpr char kFuchsiaMagic[] = {'\x10', '\x00', '\x04', '\x46',
 constexpr char kPerfMagic[] = {'P', 'E', 'R', 'F', 'I', 'L', 'E', '2'};
 constexpr char kZipMagic[] = {'P', 'K', '\x03', '\x04'};
 constexpr char kGzipMagic[] = {'\x1f', '\x8b'};
+constexpr char kZstdMagic[] = {'\x28', '\xb5', '\x2f', '\xfd'};
 constexpr char kArtMethodStreamingMagic[] = {'S', 'L', 'O', 'W'};
 constexpr char kArtHprofStreamingMagic[] = {'J', 'A', 'V', 'A', ' ', 'P',
                                             'R', 'O', 'F', 'I', 'L', 'E'};
@@ -149,6 +153,14 @@ const char* TraceTypeToString(TraceType trace_type) {
       return "unknown";
     case kTarTraceType:
       return "tar";
+    case kCtfTraceType:
+      return "ctf";
+    case kFoldedStackTraceType:
+      return "folded_stack";
+    case kOtlpTraceType:
+      return "otlp";
+    case kZstdTraceType:
+      return "zstd";
   }
   PERFETTO_FATAL("For GCC");
 }
@@ -182,6 +194,10 @@ TraceType GuessTraceType(const uint8_t* data, size_t size) {
     return kGzipTraceType;
   }
 
+  if (MatchesMagic(data, size, kZstdMagic)) {
+    return kZstdTraceType;
+  }
+
   if (MatchesMagic(data, size, kArtMethodStreamingMagic)) {
     return kArtMethodTraceType;
   }
@@ -190,6 +206,12 @@ TraceType GuessTraceType(const uint8_t* data, size_t size) {
     return kArtHprofTraceType;
   }
 
+  // Common Trace Format (e.g. LTTng): either a stream or a metadata file.
+  if (ctf_importer::IsCtfStream(data, size) ||
+      ctf_importer::IsCtfMetadata(data, size)) {
+    return kCtfTraceType;
+  }
+
   std::string start(reinterpret_cast<const char*>(data),
                     std::min<size_t>(size, kGuessTraceMaxLookahead));
 
@@ -200,6 +222,9 @@ TraceType GuessTraceType(const uint8_t* data, size_t size) {
   // Generated by the simpleperf conversion script.
   if (base::StartsWith(start_minus_white_space, "{\"libs\""))
     return kGeckoTraceType;
+  // OpenTelemetry (OTLP) JSON, either one or multiple TracesData messages.
+  if (base::StartsWith(start_minus_white_space, "{\"resourceSpans\""))
+    return kOtlpTraceType;
   if (base::StartsWith(start_minus_white_space, "{\""))
     return kJsonTraceType;
   if (base::StartsWith(start_minus_white_space, "[{\""))
@@ -246,6 +271,10 @@ TraceType GuessTraceType(const uint8_t* data, size_t size) {
   if (perf_text_importer::IsPerfTextFormatTrace(data, size))
     return kPerfTextTraceType;
 
+  // Folded stacks (e.g. the output of the FlameGraph stackcollapse scripts).
+  if (folded_stack_importer::IsFoldedStackFormatTrace(data, size))
+    return kFoldedStackTraceType;
+
   // Systrace with no header or leading HTML.
   if (base::StartsWith(start, " "))
     return kSystraceTraceType;
@@ -253,6 +282,11 @@ TraceType GuessTraceType(const uint8_t* data, size_t size) {
   if (IsProtoTraceWithSymbols(data, size))
     return kSymbolsTraceType;
 
+  // This needs to be checked before Perfetto traces as both start with a
+  // length-delimited field with id 1.
+  if (otlp_importer::IsOtlpProtoTrace(data, size))
+    return kOtlpTraceType;
+
   if (base::StartsWith(start, "\x0a"))
     return kProtoTraceType;
 
@@ -43,6 +43,10 @@ enum TraceType {
   kArtHprofTraceType,
   kPerfTextTraceType,
   kTarTraceType,
+  kCtfTraceType,
+  kFoldedStackTraceType,
+  kOtlpTraceType,
+  kZstdTraceType,
 };
 
 constexpr size_t kGuessTraceMaxLookahead = 64;
@@ -0,0 +1,124 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/util/zstd_utils.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <cstring>
+#include <memory>
+#include <vector>
+
+#include "perfetto/base/build_config.h"
+
+#if PERFETTO_BUILDFLAG(PERFETTO_ZSTD)
+#include <zstd.h>
+#else
+struct ZSTD_DCtx_s {};
+#endif
+
+namespace perfetto::trace_processor::util {
+
+bool IsZstdFrame(const uint8_t* data, size_t size) {
+  return size >= sizeof(kZstdMagic) &&
+         memcmp(data, kZstdMagic, sizeof(kZstdMagic)) == 0;
+}
+
+#if PERFETTO_BUILDFLAG(PERFETTO_ZSTD)  // Real Implementation
+
+ZstdDecompressor::ZstdDecompressor() : dctx_(ZSTD_createDCtx()) {}
+
+void ZstdDecompressor::Reset() {
+  ZSTD_DCtx_reset(dctx_.get(), ZSTD_reset_session_only);
+  output_pending_ = false;
+}
+
+void ZstdDecompressor::Feed(const uint8_t* data, size_t size) {
+  in_ = data;
+  in_size_ = size;
+  in_pos_ = 0;
+}
+
+ZstdDecompressor::Result ZstdDecompressor::ExtractOutput(uint8_t* out,
+                                                         size_t out_size) {
+  if (in_pos_ == in_size_ && !output_pending_)
+    return Result{ResultCode::kNeedsMoreInput, 0};
+
+  ZSTD_inBuffer input{in_, in_size_, in_pos_};
+  ZSTD_outBuffer output{out, out_size, 0};
+  size_t ret = ZSTD_decompressStream(dctx_.get(), &output, &input);
+  in_pos_ = input.pos;
+  if (ZSTD_isError(ret))
+    return Result{ResultCode::kError, 0};
+
+  // A return value of 0 means that a frame has been fully decoded and flushed.
+  if (ret == 0) {
+    output_pending_ = false;
+    return Result{ResultCode::kEof, output.pos};
+  }
+  output_pending_ = output.pos == output.size;
+  if (output.pos == 0 && in_pos_ == in_size_)
+    return Result{ResultCode::kNeedsMoreInput, 0};
+  return Result{ResultCode::kOk, output.pos};
+}
+
+size_t ZstdDecompressor::AvailIn() const {
+  return in_size_ - in_pos_;
+}
+
+void ZstdDecompressor::Deleter::operator()(ZSTD_DCtx_s* dctx) const {
+  ZSTD_freeDCtx(dctx);
+}
+
+#else  // Dummy Implementation
+
+ZstdDecompressor::ZstdDecompressor() = default;
+void ZstdDecompressor::Reset() {}
+void ZstdDecompressor::Feed(const uint8_t*, size_t) {}
+ZstdDecompressor::Result ZstdDecompressor::ExtractOutput(uint8_t*, size_t) {
+  return Result{ResultCode::kError, 0};
+}
+size_t ZstdDecompressor::AvailIn() const {
+  return 0;
+}
+void ZstdDecompressor::Deleter::operator()(ZSTD_DCtx_s*) const {}
+
+#endif  // PERFETTO_BUILDFLAG(PERFETTO_ZSTD)
+
+// static
+std::vector<uint8_t> ZstdDecompressor::DecompressFully(const uint8_t* data,
+                                                       size_t len) {
+  std::vector<uint8_t> whole_data;
+  ZstdDecompressor decompressor;
+  decompressor.Feed(data, len);
+  uint8_t buffer[4096];
+  for (;;) {
+    Result result = decompressor.ExtractOutput(buffer, sizeof(buffer));
+    if (result.ret == ResultCode::kError)
+      return {};
+    whole_data.insert(whole_data.end(), buffer, buffer + result.bytes_written);
+    if (result.ret == ResultCode::kNeedsMoreInput)
+      break;
+    if (result.ret == ResultCode::kEof) {
+      if (decompressor.AvailIn() == 0)
+        break;
+      decompressor.Reset();
+    }
+  }
+  return whole_data;
+}
+
+}  // namespace perfetto::trace_processor::util
@@ -0,0 +1,135 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_PROCESSOR_UTIL_ZSTD_UTILS_H_
+#define SRC_TRACE_PROCESSOR_UTIL_ZSTD_UTILS_H_
+
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <vector>
+
+#include "perfetto/base/build_config.h"
+
+struct ZSTD_DCtx_s;
+
+namespace perfetto::trace_processor::util {
+
+// The magic number at the start of each zstd frame, in file order.
+inline constexpr uint8_t kZstdMagic[] = {0x28, 0xb5, 0x2f, 0xfd};
+
+// Returns whether zstd related functionality is supported with the current
+// build flags.
+constexpr bool IsZstdSupported() {
+#if PERFETTO_BUILDFLAG(PERFETTO_ZSTD)
+  return true;
+#else
+  return false;
+#endif
+}
+
+// Returns whether |data| starts with a zstd frame. Used to tell zstd apart
+// from deflate streams in TracePacket.compressed_packets.
+bool IsZstdFrame(const uint8_t* data, size_t size);
+
+// A streaming zstd decompressor, with the same interface as GzipDecompressor
+// (see gzip_utils.h for the usage). The input can contain multiple frames:
+// 'kEof' is returned at the end of each of them and decompression can carry
+// on after calling 'Reset' if 'AvailIn' is not zero.
+class ZstdDecompressor {
+ public:
+  enum class ResultCode {
+    // Nothing bad happened so far, keep calling 'ExtractOutput'.
+    kOk,
+    // The end of a frame was reached.
+    kEof,
+    // Some error. Possibly invalid compressed stream or corrupted data.
+    kError,
+    // All the input fed so far has been decompressed, but the frame is not
+    // finished yet: 'Feed' must be called with the next mem-block.
+    kNeedsMoreInput,
+  };
+  struct Result {
+    // The return code of the decompression.
+    ResultCode ret;
+
+    // The amount of bytes written to output.
+    // Valid in all cases except |ResultCode::kError|.
+    size_t bytes_written;
+  };
+
+  ZstdDecompressor();
+
+  // Feed the next mem-block. The memory must stay valid until
+  // 'ExtractOutput' returns 'kNeedsMoreInput'.
+  void Feed(const uint8_t* data, size_t size);
+
+  // Feed the next mem-block and extract output in the callback consumer.
+  // callback can get invoked multiple times if there are multiple
+  // mem-blocks to output.
+  //
+  // Note the output of this function is guaranteed *not* to be kOk.
+  template <typename Callback = void(const uint8_t* ptr, size_t size)>
+  ResultCode FeedAndExtract(const uint8_t* data,
+                            size_t size,
+                            const Callback& output_consumer) {
+    Feed(data, size);
+    uint8_t buffer[4096];
+    Result result;
+    do {
+      result = ExtractOutput(buffer, sizeof(buffer));
+      if (result.ret != ResultCode::kError && result.bytes_written > 0) {
+        output_consumer(buffer, result.bytes_written);
+      }
+    } while (result.ret == ResultCode::kOk);
+    return result.ret;
+  }
+
+  // Extract the newly available partial output. On each 'Feed', this method
+  // should be called repeatedly until there is no more data to output
+  // i.e. (either 'kEof' or 'kNeedsMoreInput').
+  Result ExtractOutput(uint8_t* out, size_t out_capacity);
+
+  // Sets the state of the decompressor to start decoding a new frame, without
+  // paying the cost of reallocating the decompression context.
+  void Reset();
+
+  // Decompress the entire mem-block and return decompressed mem-block.
+  // All the frames in the mem-block are decompressed. Returns an empty vector
+  // on errors.
+  static std::vector<uint8_t> DecompressFully(const uint8_t* data, size_t len);
+
+  // Returns the amount of input bytes left unprocessed.
+  size_t AvailIn() const;
+
+ private:
+  struct Deleter {
+    void operator()(ZSTD_DCtx_s*) const;
+  };
+  std::unique_ptr<ZSTD_DCtx_s, Deleter> dctx_;
+
+  const uint8_t* in_ = nullptr;
+  size_t in_size_ = 0;
+  size_t in_pos_ = 0;
+
+  // Whether the last call to 'ExtractOutput' filled the whole output buffer,
+  // in which case zstd might still have data to flush without more input.
+  bool output_pending_ = false;
+};
+
+}  // namespace perfetto::trace_processor::util
+
+#endif  // SRC_TRACE_PROCESSOR_UTIL_ZSTD_UTILS_H_
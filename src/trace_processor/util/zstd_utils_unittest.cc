@@ -0,0 +1,106 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_processor/util/zstd_utils.h"
+
+#include <zstd.h>
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "perfetto/base/logging.h"
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto::trace_processor::util {
+namespace {
+
+std::string Compress(const std::string& input) {
+  std::string output(ZSTD_compressBound(input.size()), '\0');
+  size_t size = ZSTD_compress(output.data(), output.size(), input.data(),
+                              input.size(), ZSTD_CLEVEL_DEFAULT);
+  PERFETTO_CHECK(!ZSTD_isError(size));
+  output.resize(size);
+  return output;
+}
+
+std::string ToString(const std::vector<uint8_t>& v) {
+  return {reinterpret_cast<const char*>(v.data()), v.size()};
+}
+
+const uint8_t* ToU8(const std::string& s) {
+  return reinterpret_cast<const uint8_t*>(s.data());
+}
+
+TEST(ZstdDecompressor, Basic) {
+  std::string input = "Abc..Def..Ghi";
+  std::string compressed = Compress(input);
+  ASSERT_TRUE(IsZstdFrame(ToU8(compressed), compressed.size()));
+  EXPECT_EQ(ToString(ZstdDecompressor::DecompressFully(ToU8(compressed),
+                                                       compressed.size())),
+            input);
+}
+
+TEST(ZstdDecompressor, NotZstd) {
+  std::string input = "\x1f\x8b\x08\x00";
+  EXPECT_FALSE(IsZstdFrame(ToU8(input), input.size()));
+  EXPECT_FALSE(IsZstdFrame(ToU8(input), 2));
+  EXPECT_TRUE(ZstdDecompressor::DecompressFully(ToU8(input), input.size())
+                  .empty());
+}
+
+TEST(ZstdDecompressor, Streaming) {
+  std::string input;
+  for (int i = 0; i < 10000; i++)
+    input += "Line " + std::to_string(i) + "\n";
+  std::string compressed = Compress(input);
+
+  std::string decompressed;
+  auto consumer = [&](const uint8_t* data, size_t len) {
+    decompressed.append(reinterpret_cast<const char*>(data), len);
+  };
+  ZstdDecompressor decompressor;
+  ASSERT_GT(compressed.size(), 17u);
+  EXPECT_EQ(decompressor.FeedAndExtract(ToU8(compressed), 7, consumer),
+            ZstdDecompressor::ResultCode::kNeedsMoreInput);
+  EXPECT_EQ(decompressor.FeedAndExtract(ToU8(compressed) + 7, 10, consumer),
+            ZstdDecompressor::ResultCode::kNeedsMoreInput);
+  EXPECT_EQ(decompressor.FeedAndExtract(ToU8(compressed) + 17,
+                                        compressed.size() - 17, consumer),
+            ZstdDecompressor::ResultCode::kEof);
+  EXPECT_EQ(decompressor.AvailIn(), 0u);
+  EXPECT_EQ(input, decompressed);
+}
+
+TEST(ZstdDecompressor, MultipleFrames) {
+  std::string compressed = Compress("Abc..") + Compress("Def..Ghi");
+  EXPECT_EQ(ToString(ZstdDecompressor::DecompressFully(ToU8(compressed),
+                                                       compressed.size())),
+            "Abc..Def..Ghi");
+}
+
+TEST(ZstdDecompressor, Corrupted) {
+  std::string compressed = Compress("Abc..Def..Ghi");
+  compressed[compressed.size() / 2] ^= 0x55;
+  compressed.resize(compressed.size() - 1);
+  ZstdDecompressor decompressor;
+  EXPECT_NE(decompressor.FeedAndExtract(ToU8(compressed), compressed.size(),
+                                        [](const uint8_t*, size_t) {}),
+            ZstdDecompressor::ResultCode::kEof);
+}
+
+}  // namespace
+}  // namespace perfetto::trace_processor::util
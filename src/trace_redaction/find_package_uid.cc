@@ -15,6 +15,9 @@
  */
 
 #include "src/trace_redaction/find_package_uid.h"
+
+#include <algorithm>
+
 #include "perfetto/ext/base/string_view.h"
 #include "src/trace_redaction/trace_redaction_framework.h"
 
@@ -25,6 +28,10 @@ namespace perfetto::trace_redaction {
 
 base::Status FindPackageUid::Begin(Context* context) const {
   if (context->package_name.empty()) {
+    if (context->package_uid.has_value()) {
+      return base::OkStatus();
+    }
+
     return base::ErrStatus("FindPackageUid: missing package name.");
   }
 
@@ -38,11 +45,6 @@ base::Status FindPackageUid::Begin(Context* context) const {
 base::Status FindPackageUid::Collect(
     const protos::pbzero::TracePacket::Decoder& packet,
     Context* context) const {
-  // If a package has been found in a previous iteration, stop.
-  if (context->package_uid.has_value()) {
-    return base::OkStatus();
-  }
-
   if (!packet.has_packages_list()) {
     return base::OkStatus();
   }
@@ -57,22 +59,33 @@ base::Status FindPackageUid::Collect(
       continue;
     }
 
+    // See "trace_redaction_framework.cc" for info.uid() must be normalized.
+    auto uid = NormalizeUid(info.uid());
+
     // Package names should be lowercase, but this check is meant to be more
-    // forgiving.
-    base::StringView expected_name(context->package_name.data(),
-                                   context->package_name.size());
+    // forgiving. If the package was found in a previous iteration, keep it.
     base::StringView actual_name(info.name().data, info.name().size);
-    if (!actual_name.CaseInsensitiveEq(expected_name)) {
+    if (!context->package_uid.has_value() &&
+        actual_name.CaseInsensitiveEq(
+            base::StringView(context->package_name))) {
+      context->package_uid = uid;
       continue;
     }
 
-    // See "trace_redaction_framework.cc" for info.uid() must be normalized.
-    context->package_uid = NormalizeUid(info.uid());
-    return base::OkStatus();
+    for (const auto& name : context->extra_package_names) {
+      if (!actual_name.CaseInsensitiveEq(base::StringView(name))) {
+        continue;
+      }
+
+      auto& uids = context->extra_package_uids;
+      if (std::find(uids.begin(), uids.end(), uid) == uids.end()) {
+        uids.push_back(uid);
+      }
+    }
   }
 
-  // Nothing was found. There should only be one package list, but we keep
-  // looking just incase. The error case will be handled in End().
+  // There should only be one package list, but we keep looking just incase.
+  // If nothing was found, the error case will be handled in End().
   return base::OkStatus();
 }
 
@@ -84,4 +97,24 @@ base::Status FindPackageUid::End(Context* context) const {
   return base::OkStatus();
 }
 
+base::Status MergeExtraPackageUids::Build(Context* context) const {
+  if (context->extra_package_uids.empty()) {
+    return base::OkStatus();
+  }
+
+  if (!context->package_uid.has_value()) {
+    return base::ErrStatus("MergeExtraPackageUids: missing package uid.");
+  }
+
+  if (!context->timeline) {
+    return base::ErrStatus("MergeExtraPackageUids: missing timeline.");
+  }
+
+  for (auto uid : context->extra_package_uids) {
+    context->timeline->ReplaceUid(uid, *context->package_uid);
+  }
+
+  return base::OkStatus();
+}
+
 }  // namespace perfetto::trace_redaction
@@ -27,6 +27,13 @@ namespace perfetto::trace_redaction {
 // `kStop` with a package is found, otherwrise `kContinue`. If a package is not
 // found, `Context.package_uid` will remain unset and a later primitive will
 // need to report the failure.
+//
+// The uids of `Context.extra_package_names` are appended to
+// `Context.extra_package_uids`. Extra packages missing from the package list
+// are ignored.
+//
+// If `Context.package_name` is empty, `Context.package_uid` must be set by the
+// caller.
 class FindPackageUid final : public CollectPrimitive {
  public:
   base::Status Begin(Context*) const override;
@@ -37,6 +44,14 @@ class FindPackageUid final : public CollectPrimitive {
   base::Status End(Context*) const override;
 };
 
+// Changes the uid of all processes in `Context.extra_package_uids` to
+// `Context.package_uid` in the timeline, so that primitives treat the extra
+// packages as part of the target package.
+class MergeExtraPackageUids final : public BuildPrimitive {
+ public:
+  base::Status Build(Context* context) const override;
+};
+
 }  // namespace perfetto::trace_redaction
 
 #endif  // SRC_TRACE_REDACTION_FIND_PACKAGE_UID_H_
@@ -275,4 +275,43 @@ TEST(FindPackageUidTest, FailsIfUidStartsInitialized) {
   ASSERT_FALSE(status.ok()) << status.message();
 }
 
+TEST(FindPackageUidTest, FindsExtraPackageUids) {
+  const auto packet = CreatePackageListPacket();
+
+  Context context;
+  context.package_name = "com.google.android.uvexposurereporter";
+  context.extra_package_names.push_back("com.amazon.mShop.android.shopping");
+  context.extra_package_names.push_back("com.not.a.packagename");
+
+  const FindPackageUid find;
+
+  const auto decoder = protos::pbzero::TracePacket::Decoder(packet);
+
+  ASSERT_OK(find.Begin(&context));
+  ASSERT_OK(find.Collect(decoder, &context));
+  ASSERT_OK(find.End(&context));
+
+  ASSERT_EQ(context.package_uid, NormalizeUid(10205));
+
+  // Extra packages that are not in the package list are ignored.
+  ASSERT_THAT(context.extra_package_uids, testing::ElementsAre(10303));
+}
+
+TEST(FindPackageUidTest, AcceptsUidWithoutPackageName) {
+  const auto packet = CreatePackageListPacket();
+
+  Context context;
+  context.package_uid = 10205;
+
+  const FindPackageUid find;
+
+  const auto decoder = protos::pbzero::TracePacket::Decoder(packet);
+
+  ASSERT_OK(find.Begin(&context));
+  ASSERT_OK(find.Collect(decoder, &context));
+  ASSERT_OK(find.End(&context));
+
+  ASSERT_EQ(context.package_uid, 10205u);
+}
+
 }  // namespace perfetto::trace_redaction
@@ -14,27 +14,204 @@
  * limitations under the License.
  */
 
+#include <fcntl.h>
+
+#include <cstdint>
+#include <cstdio>
+#include <memory>
+#include <optional>
+#include <string>
+#include <vector>
+
 #include "perfetto/base/logging.h"
 #include "perfetto/base/status.h"
+#include "perfetto/ext/base/file_utils.h"
+#include "perfetto/ext/base/getopt.h"
+#include "perfetto/ext/base/scoped_file.h"
+#include "perfetto/ext/base/status_macros.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "src/trace_redaction/redaction_policy.h"
+#include "src/trace_redaction/redaction_report.h"
 #include "src/trace_redaction/trace_redaction_framework.h"
 #include "src/trace_redaction/trace_redactor.h"
-#include "src/trace_redaction/verify_integrity.h"
+
+#include "protos/perfetto/trace_redaction/policy.pbzero.h"
 
 namespace perfetto::trace_redaction {
+namespace {
+
+const char kUsage[] =
+    R"(Usage: %s [options] <input file> <output file> [<package name>...]
+
+Removes the data not connected to the target packages from a trace. Target
+packages can be given by name (as positional arguments or with --package) or by
+uid.
+
+Options:
+  --package NAME  Adds a target package. Can be repeated.
+  --uid UID       Adds the uid of a target package. Can be repeated.
+  --policy FILE   Reads a TraceRedactionPolicy textproto (see
+                  protos/perfetto/trace_redaction/policy.proto) selecting the
+                  primitives to run. Defaults to the built-in policy.
+  --report FILE   Writes a JSON report of what was redacted to FILE.
+)";
+
+struct Options {
+  std::string input;
+  std::string output;
+  std::vector<std::string> package_names;
+  std::vector<uint64_t> package_uids;
+  std::string policy;
+  std::string report;
+};
+
+base::Status WriteReport(const std::string& path, const Context& context) {
+  auto fd = base::OpenFile(path, O_WRONLY | O_CREAT | O_TRUNC, 0644);
+  if (!fd) {
+    return base::ErrStatus("Failed to open report file %s", path.c_str());
+  }
+
+  auto json = RedactionReportToJson(context);
+  if (base::WriteAll(*fd, json.data(), json.size()) !=
+      static_cast<ssize_t>(json.size())) {
+    return base::ErrStatus("Failed to write report file %s", path.c_str());
+  }
+
+  return base::OkStatus();
+}
 
 // Builds and runs a trace redactor.
-static base::Status Main(std::string_view input,
-                         std::string_view output,
-                         std::string_view package_name) {
-  TraceRedactor::Config config;
-  auto redactor = TraceRedactor::CreateInstance(config);
+base::Status Main(const Options& options) {
+  auto package_names = options.package_names;
+  auto package_uids = options.package_uids;
+
+  std::unique_ptr<TraceRedactor> redactor;
+
+  if (options.policy.empty()) {
+    TraceRedactor::Config config;
+    redactor = TraceRedactor::CreateInstance(config);
+  } else {
+    std::string policy_txt;
+    if (!base::ReadFile(options.policy, &policy_txt)) {
+      return base::ErrStatus("Failed to read policy %s",
+                             options.policy.c_str());
+    }
+
+    ASSIGN_OR_RETURN(auto policy,
+                     RedactionPolicyTxtToPb(policy_txt, options.policy));
+
+    protos::pbzero::TraceRedactionPolicy::Decoder decoder(policy.data(),
+                                                          policy.size());
+
+    for (auto it = decoder.package_name(); it; ++it) {
+      package_names.push_back(it->as_std_string());
+    }
+
+    for (auto it = decoder.package_uid(); it; ++it) {
+      package_uids.push_back(*it);
+    }
+
+    ASSIGN_OR_RETURN(redactor, CreateRedactorFromPolicy(
+                                   {policy.data(), policy.size()}));
+  }
 
   Context context;
-  context.package_name = package_name;
 
-  return redactor->Redact(input, output, &context);
+  // The first package is the target package, the others are merged into it.
+  // If there are no package names, the first uid is used instead.
+  if (!package_names.empty()) {
+    context.package_name = package_names.front();
+    context.extra_package_names.assign(package_names.begin() + 1,
+                                       package_names.end());
+  } else if (!package_uids.empty()) {
+    context.package_uid = NormalizeUid(package_uids.front());
+    package_uids.erase(package_uids.begin());
+  } else {
+    return base::ErrStatus("Missing target package");
+  }
+
+  for (auto uid : package_uids) {
+    context.extra_package_uids.push_back(NormalizeUid(uid));
+  }
+
+  context.count_modified_packets = !options.report.empty();
+
+  RETURN_IF_ERROR(redactor->Redact(options.input, options.output, &context));
+
+  if (!options.report.empty()) {
+    RETURN_IF_ERROR(WriteReport(options.report, context));
+  }
+
+  return base::OkStatus();
+}
+
+std::optional<Options> ParseOptions(int argc, char** argv) {
+  enum LongOption {
+    OPT_PACKAGE = 1000,
+    OPT_UID,
+    OPT_POLICY,
+    OPT_REPORT,
+  };
+
+  static const option long_options[] = {
+      {"package", required_argument, nullptr, OPT_PACKAGE},
+      {"uid", required_argument, nullptr, OPT_UID},
+      {"policy", required_argument, nullptr, OPT_POLICY},
+      {"report", required_argument, nullptr, OPT_REPORT},
+      {nullptr, 0, nullptr, 0}};
+
+  Options options;
+
+  for (;;) {
+    int option = getopt_long(argc, argv, "", long_options, nullptr);
+
+    if (option == -1) {
+      break;
+    }
+
+    switch (option) {
+      case OPT_PACKAGE:
+        options.package_names.emplace_back(optarg);
+        break;
+
+      case OPT_UID: {
+        auto uid = base::CStringToUInt64(optarg);
+        if (!uid) {
+          PERFETTO_ELOG("Invalid uid: %s", optarg);
+          return std::nullopt;
+        }
+        options.package_uids.push_back(*uid);
+        break;
+      }
+
+      case OPT_POLICY:
+        options.policy = optarg;
+        break;
+
+      case OPT_REPORT:
+        options.report = optarg;
+        break;
+
+      default:
+        return std::nullopt;
+    }
+  }
+
+  if (argc - optind < 2) {
+    return std::nullopt;
+  }
+
+  options.input = argv[optind];
+  options.output = argv[optind + 1];
+
+  for (int i = optind + 2; i < argc; ++i) {
+    options.package_names.emplace_back(argv[i]);
+  }
+
+  return options;
 }
 
+}  // namespace
 }  // namespace perfetto::trace_redaction
 
 int main(int argc, char** argv) {
@@ -42,14 +219,14 @@ int main(int argc, char** argv) {
   constexpr int kFailure = 1;
   constexpr int kInvalidArgs = 2;
 
-  if (argc != 4) {
-    PERFETTO_ELOG(
-        "Invalid arguments: %s <input file> <output file> <package name>",
-        argv[0]);
+  auto options = perfetto::trace_redaction::ParseOptions(argc, argv);
+
+  if (!options) {
+    fprintf(stderr, perfetto::trace_redaction::kUsage, argv[0]);
     return kInvalidArgs;
   }
 
-  auto result = perfetto::trace_redaction::Main(argv[1], argv[2], argv[3]);
+  auto result = perfetto::trace_redaction::Main(*options);
 
   if (result.ok()) {
     return kSuccess;
@@ -16,13 +16,38 @@
 
 #include "src/trace_redaction/populate_allow_lists.h"
 
+#include <bitset>
+#include <cstddef>
+#include <cstdint>
+#include <vector>
+
 #include "perfetto/base/status.h"
+#include "perfetto/ext/base/status_macros.h"
 #include "src/trace_redaction/trace_redaction_framework.h"
 
 #include "protos/perfetto/trace/ftrace/ftrace_event.pbzero.h"
 #include "protos/perfetto/trace/trace_packet.pbzero.h"
 
 namespace perfetto::trace_redaction {
+namespace {
+
+template <size_t N>
+base::Status UpdateMask(const std::vector<uint32_t>& field_ids,
+                        bool value,
+                        std::bitset<N>* mask) {
+  for (auto field_id : field_ids) {
+    if (field_id >= mask->size()) {
+      return base::ErrStatus("PopulateAllowlists: field id %u out of range.",
+                             field_id);
+    }
+
+    mask->set(field_id, value);
+  }
+
+  return base::OkStatus();
+}
+
+}  // namespace
 
 base::Status PopulateAllowlists::Build(Context* context) const {
   auto& packet_mask = context->packet_mask;
@@ -96,6 +121,12 @@ base::Status PopulateAllowlists::Build(Context* context) const {
   ftrace_masks.set(protos::pbzero::FtraceEvent::kTaskRenameFieldNumber);
   ftrace_masks.set(protos::pbzero::FtraceEvent::kTimestampFieldNumber);
 
+  RETURN_IF_ERROR(UpdateMask(added_packet_fields_, true, &packet_mask));
+  RETURN_IF_ERROR(UpdateMask(removed_packet_fields_, false, &packet_mask));
+  RETURN_IF_ERROR(UpdateMask(added_ftrace_event_fields_, true, &ftrace_masks));
+  RETURN_IF_ERROR(
+      UpdateMask(removed_ftrace_event_fields_, false, &ftrace_masks));
+
   return base::OkStatus();
 }
 
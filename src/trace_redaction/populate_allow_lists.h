@@ -17,6 +17,9 @@
 #ifndef SRC_TRACE_REDACTION_POPULATE_ALLOW_LISTS_H_
 #define SRC_TRACE_REDACTION_POPULATE_ALLOW_LISTS_H_
 
+#include <cstdint>
+#include <vector>
+
 #include "perfetto/base/status.h"
 #include "src/trace_redaction/trace_redaction_framework.h"
 
@@ -25,9 +28,34 @@ namespace perfetto::trace_redaction {
 // Populates the different allow-lists needed to remove data from the trace.
 // Configuration data in the context can be used to change the contents of the
 // lists.
+//
+// Fields can be added to or removed from the default lists. Removals are
+// applied after additions.
 class PopulateAllowlists final : public BuildPrimitive {
  public:
   base::Status Build(Context* context) const override;
+
+  void AddPacketField(uint32_t field_id) {
+    added_packet_fields_.push_back(field_id);
+  }
+
+  void RemovePacketField(uint32_t field_id) {
+    removed_packet_fields_.push_back(field_id);
+  }
+
+  void AddFtraceEventField(uint32_t field_id) {
+    added_ftrace_event_fields_.push_back(field_id);
+  }
+
+  void RemoveFtraceEventField(uint32_t field_id) {
+    removed_ftrace_event_fields_.push_back(field_id);
+  }
+
+ private:
+  std::vector<uint32_t> added_packet_fields_;
+  std::vector<uint32_t> removed_packet_fields_;
+  std::vector<uint32_t> added_ftrace_event_fields_;
+  std::vector<uint32_t> removed_ftrace_event_fields_;
 };
 
 }  // namespace perfetto::trace_redaction
@@ -45,6 +45,14 @@ void ProcessThreadTimeline::Sort() {
   mode_ = Mode::kRead;
 }
 
+void ProcessThreadTimeline::ReplaceUid(uint64_t from, uint64_t to) {
+  for (auto& event : events_) {
+    if (event.type == Event::Type::kOpen && event.uid == from) {
+      event.uid = to;
+    }
+  }
+}
+
 const ProcessThreadTimeline::Event* ProcessThreadTimeline::GetOpeningEvent(
     uint64_t ts,
     int32_t pid) const {
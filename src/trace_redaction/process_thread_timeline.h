@@ -96,6 +96,11 @@ class ProcessThreadTimeline {
   // subset of events will, on average, be trivially small.
   void Sort();
 
+  // Changes the uid of all open events from `from` to `to`. This is used to
+  // treat multiple packages as a single package. Does not change the order of
+  // the events.
+  void ReplaceUid(uint64_t from, uint64_t to);
+
   // Returns true if a process/thread is connected to a package.
   bool PidConnectsToUid(uint64_t ts, int32_t pid, uint64_t uid) const;
 
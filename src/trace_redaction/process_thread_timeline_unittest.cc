@@ -254,4 +254,13 @@ TEST_F(ProcessThreadTimelineIsConnectedTest, PrematureDirectPidAndUid) {
   ASSERT_FALSE(timeline_.PidConnectsToUid(kTimeA, kPidA, kUidA));
 }
 
+// After replacing UID C with UID A, PID C (and only PID C) moves to UID A.
+TEST_F(ProcessThreadTimelineIsConnectedTest, ReplaceUid) {
+  timeline_.ReplaceUid(kUidC, kUidA);
+
+  ASSERT_TRUE(timeline_.PidConnectsToUid(kTimeB, kPidC, kUidA));
+  ASSERT_FALSE(timeline_.PidConnectsToUid(kTimeB, kPidC, kUidC));
+  ASSERT_TRUE(timeline_.PidConnectsToUid(kTimeB, kPidB, kUidA));
+}
+
 }  // namespace perfetto::trace_redaction
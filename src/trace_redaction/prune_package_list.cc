@@ -16,6 +16,8 @@
 
 #include "src/trace_redaction/prune_package_list.h"
 
+#include <algorithm>
+#include <cstdint>
 #include <string>
 
 #include "perfetto/base/logging.h"
@@ -26,6 +28,19 @@
 #include "protos/perfetto/trace/android/packages_list.pbzero.h"
 
 namespace perfetto::trace_redaction {
+namespace {
+
+bool IsTargetUid(const Context& context, uint64_t uid) {
+  if (uid == context.package_uid) {
+    return true;
+  }
+
+  const auto& extra_uids = context.extra_package_uids;
+  return std::find(extra_uids.begin(), extra_uids.end(), uid) !=
+         extra_uids.end();
+}
+
+}  // namespace
 
 base::Status PrunePackageList::Transform(const Context& context,
                                          std::string* packet) const {
@@ -78,9 +93,11 @@ void PrunePackageList::OnPackageList(
       // If there are more than one package entry (see
       // trace_redaction_framework.h for more details), we need to match all
       // instances here because retained processes will reference them.
+      //
+      // Entries of the extra packages are kept too.
       protos::pbzero::PackagesList::PackageInfo::Decoder info(field.as_bytes());
 
-      if (info.has_uid() && NormalizeUid(info.uid()) == context.package_uid) {
+      if (info.has_uid() && IsTargetUid(context, NormalizeUid(info.uid()))) {
         proto_util::AppendField(field, message);
       }
     } else {
@@ -24,7 +24,8 @@
 
 namespace perfetto::trace_redaction {
 
-// Removes all package list entries that don't match `Context.package_uid`.
+// Removes all package list entries that don't match `Context.package_uid` or
+// `Context.extra_package_uids`.
 // Returns `base::ErrStatus()` if `Context.package_uid` was not set.
 class PrunePackageList final : public TransformPrimitive {
  public:
@@ -181,4 +181,24 @@ TEST(PrunePackageListTest, RemovesPackagesInfoFromPackageList) {
   ASSERT_EQ(kPackageName, after_packet.packages_list().packages().at(0).name());
 }
 
+TEST(PrunePackageListTest, KeepsExtraPackages) {
+  Context context;
+  context.package_uid.emplace(kPackageUid);
+  context.extra_package_uids.push_back(10367);
+
+  auto after = CreateTestPacket();
+
+  const PrunePackageList prune;
+  ASSERT_TRUE(prune.Transform(context, &after).ok());
+
+  protos::gen::TracePacket after_packet;
+  after_packet.ParseFromString(after);
+
+  ASSERT_TRUE(after_packet.has_packages_list());
+  ASSERT_EQ(2, after_packet.packages_list().packages_size());
+
+  ASSERT_EQ(kPackageUid, after_packet.packages_list().packages().at(0).uid());
+  ASSERT_EQ(10367u, after_packet.packages_list().packages().at(1).uid());
+}
+
 }  // namespace perfetto::trace_redaction
@@ -52,6 +52,11 @@ class RedactFtraceEvents : public TransformPrimitive {
     filter_ = std::make_unique<Filter>();
   }
 
+  template <typename Filter>
+  void emplace_ftrace_filter(std::unique_ptr<Filter> filter) {
+    filter_ = std::move(filter);
+  }
+
   // For ftrace events that pass the filter, they go through this modifier which
   // will optionally modify the event before adding it to the event bundle (or
   // even drop it).
@@ -60,6 +65,11 @@ class RedactFtraceEvents : public TransformPrimitive {
     modifier_ = std::make_unique<Modifier>();
   }
 
+  template <typename Modifier>
+  void emplace_post_filter_modifier(std::unique_ptr<Modifier> modifier) {
+    modifier_ = std::move(modifier);
+  }
+
  private:
   base::Status OnFtraceEvents(const Context& context,
                               protozero::Field ftrace_events,
@@ -54,6 +54,11 @@ class RedactProcessEvents : public TransformPrimitive {
     modifier_ = std::make_unique<Modifier>();
   }
 
+  template <class Modifier>
+  void emplace_modifier(std::unique_ptr<Modifier> modifier) {
+    modifier_ = std::move(modifier);
+  }
+
   template <class Filter>
   void emplace_filter() {
     filter_ = std::make_unique<Filter>();
@@ -56,11 +56,21 @@ class RedactSchedEvents : public TransformPrimitive {
     modifier_ = std::make_unique<Modifier>();
   }
 
+  template <class Modifier>
+  void emplace_modifier(std::unique_ptr<Modifier> modifier) {
+    modifier_ = std::move(modifier);
+  }
+
   template <class Filter>
   void emplace_waking_filter() {
     waking_filter_ = std::make_unique<Filter>();
   }
 
+  template <class Filter>
+  void emplace_waking_filter(std::unique_ptr<Filter> filter) {
+    waking_filter_ = std::move(filter);
+  }
+
  private:
   base::Status OnFtraceEvents(const Context& context,
                               protozero::Field ftrace_events,
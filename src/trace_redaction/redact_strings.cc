@@ -0,0 +1,449 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_redaction/redact_strings.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <string_view>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/http/sha1.h"
+#include "perfetto/ext/base/status_macros.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "perfetto/protozero/field.h"
+#include "perfetto/protozero/proto_decoder.h"
+#include "perfetto/protozero/scattered_heap_buffer.h"
+#include "src/trace_redaction/proto_util.h"
+
+#include "protos/perfetto/trace/android/android_log.pbzero.h"
+#include "protos/perfetto/trace/interned_data/interned_data.pbzero.h"
+#include "protos/perfetto/trace/profiling/profile_common.pbzero.h"
+#include "protos/perfetto/trace/trace_packet.pbzero.h"
+#include "protos/perfetto/trace/track_event/debug_annotation.pbzero.h"
+#include "protos/perfetto/trace/track_event/log_message.pbzero.h"
+#include "protos/perfetto/trace/track_event/source_location.pbzero.h"
+#include "protos/perfetto/trace/track_event/track_event.pbzero.h"
+
+namespace perfetto::trace_redaction {
+
+namespace {
+
+// The number of bytes (from the SHA1 digest) used in a token.
+constexpr size_t kTokenHashBytes = 8;
+
+constexpr uint32_t kInternedStringFieldNumber = 2;
+
+static_assert(protos::pbzero::EventName::kNameFieldNumber ==
+              kInternedStringFieldNumber);
+static_assert(protos::pbzero::LogMessageBody::kBodyFieldNumber ==
+              kInternedStringFieldNumber);
+static_assert(protos::pbzero::InternedString::kStrFieldNumber ==
+              kInternedStringFieldNumber);
+
+}  // namespace
+
+base::Status RedactStrings::AddRule(std::string name,
+                                    const std::string& pattern) {
+  if (!trace_processor::regex::IsRegexSupported()) {
+    return base::ErrStatus("RedactStrings: regex is not supported.");
+  }
+
+  if (name.empty()) {
+    return base::ErrStatus("RedactStrings: missing rule name.");
+  }
+
+  ASSIGN_OR_RETURN(auto regex,
+                   trace_processor::regex::Regex::Create(pattern.c_str()));
+  rules_.push_back({std::move(name), std::move(regex)});
+
+  return base::OkStatus();
+}
+
+base::Status RedactStrings::Transform(const Context&,
+                                      std::string* packet) const {
+  if (packet == nullptr || packet->empty()) {
+    return base::ErrStatus("RedactStrings: null or empty packet.");
+  }
+
+  protozero::ProtoDecoder decoder(*packet);
+
+  auto has_strings =
+      decoder.FindField(protos::pbzero::TracePacket::kTrackEventFieldNumber)
+          .valid() ||
+      decoder.FindField(protos::pbzero::TracePacket::kInternedDataFieldNumber)
+          .valid() ||
+      decoder.FindField(protos::pbzero::TracePacket::kAndroidLogFieldNumber)
+          .valid();
+
+  // Most packets have none of these fields. It's best to avoid
+  // reserialization whenever possible.
+  if (!has_strings) {
+    return base::OkStatus();
+  }
+
+  protozero::HeapBuffered<protos::pbzero::TracePacket> message;
+
+  for (auto field = decoder.ReadField(); field.valid();
+       field = decoder.ReadField()) {
+    switch (field.id()) {
+      case protos::pbzero::TracePacket::kTrackEventFieldNumber:
+        OnTrackEvent(field.as_bytes(),
+                     message->BeginNestedMessage<protozero::Message>(
+                         field.id()));
+        break;
+
+      case protos::pbzero::TracePacket::kInternedDataFieldNumber:
+        OnInternedData(field.as_bytes(),
+                       message->BeginNestedMessage<protozero::Message>(
+                           field.id()));
+        break;
+
+      case protos::pbzero::TracePacket::kAndroidLogFieldNumber:
+        OnAndroidLog(field.as_bytes(),
+                     message->BeginNestedMessage<protozero::Message>(
+                         field.id()));
+        break;
+
+      default:
+        proto_util::AppendField(field, message.get());
+        break;
+    }
+  }
+
+  packet->assign(message.SerializeAsString());
+
+  return base::OkStatus();
+}
+
+std::string RedactStrings::Redact(std::string_view value) const {
+  if (rules_.empty() || allowlist_.count(std::string(value))) {
+    return std::string(value);
+  }
+
+  // Regex only sees up to the first null character, so every null-separated
+  // segment is redacted on its own.
+  std::string output;
+
+  for (size_t start = 0;;) {
+    size_t end = value.find('\0', start);
+    RedactSegment(value.substr(start, end - start), &output);
+
+    if (end == std::string_view::npos) {
+      break;
+    }
+
+    output.push_back('\0');
+    start = end + 1;
+  }
+
+  return output;
+}
+
+void RedactStrings::RedactSegment(std::string_view segment,
+                                  std::string* output) const {
+  // Regex needs a null-terminated string.
+  std::string input(segment);
+
+  std::vector<std::string_view> matches;
+
+  size_t offset = 0;
+
+  while (offset < input.size()) {
+    const char* remaining = input.c_str() + offset;
+
+    const Rule* best_rule = nullptr;
+    std::string_view best_match;
+
+    for (auto& rule : rules_) {
+      // Past the first match, |remaining| is not the start of the string, so
+      // anchored rules must not match there.
+      rule.regex.Submatch(remaining, matches, /*not_bol=*/offset > 0);
+
+      if (matches.empty()) {
+        continue;
+      }
+
+      // Prefer the leftmost match. The first rule wins a tie.
+      if (!best_rule || matches[0].data() < best_match.data()) {
+        best_rule = &rule;
+        best_match = matches[0];
+      }
+    }
+
+    if (!best_rule) {
+      break;
+    }
+
+    auto match_start = static_cast<size_t>(best_match.data() - input.c_str());
+
+    output->append(input, offset, match_start - offset);
+
+    // An empty match would never advance. Keep the character after it and
+    // search again.
+    if (best_match.empty()) {
+      if (match_start < input.size()) {
+        output->push_back(input[match_start]);
+      }
+
+      offset = match_start + 1;
+      continue;
+    }
+
+    output->append(CreateToken(*best_rule, best_match));
+    offset = match_start + best_match.size();
+  }
+
+  if (offset < input.size()) {
+    output->append(input, offset, std::string::npos);
+  }
+}
+
+std::string RedactStrings::CreateToken(const Rule& rule,
+                                       std::string_view match) const {
+  std::string hash_input = hash_key_;
+  hash_input.push_back('\0');
+  hash_input.append(match);
+
+  auto digest = base::SHA1Hash(hash_input);
+  auto hash = base::ToHex(reinterpret_cast<const char*>(digest.data()),
+                          kTokenHashBytes);
+
+  return "<" + rule.name + ":" + hash + ">";
+}
+
+void RedactStrings::OnTrackEvent(protozero::ConstBytes bytes,
+                                 protozero::Message* message) const {
+  protozero::ProtoDecoder decoder(bytes);
+
+  for (auto field = decoder.ReadField(); field.valid();
+       field = decoder.ReadField()) {
+    switch (field.id()) {
+      case protos::pbzero::TrackEvent::kNameFieldNumber:
+        AppendString(field, message);
+        break;
+
+      case protos::pbzero::TrackEvent::kDebugAnnotationsFieldNumber:
+        OnDebugAnnotation(field.as_bytes(),
+                          message->BeginNestedMessage<protozero::Message>(
+                              field.id()));
+        break;
+
+      case protos::pbzero::TrackEvent::kSourceLocationFieldNumber:
+        OnSourceLocation(field.as_bytes(),
+                         message->BeginNestedMessage<protozero::Message>(
+                             field.id()));
+        break;
+
+      default:
+        proto_util::AppendField(field, message);
+        break;
+    }
+  }
+}
+
+void RedactStrings::OnDebugAnnotation(protozero::ConstBytes bytes,
+                                      protozero::Message* message) const {
+  protozero::ProtoDecoder decoder(bytes);
+
+  for (auto field = decoder.ReadField(); field.valid();
+       field = decoder.ReadField()) {
+    switch (field.id()) {
+      case protos::pbzero::DebugAnnotation::kStringValueFieldNumber:
+      case protos::pbzero::DebugAnnotation::kLegacyJsonValueFieldNumber:
+        AppendString(field, message);
+        break;
+
+      case protos::pbzero::DebugAnnotation::kDictEntriesFieldNumber:
+      case protos::pbzero::DebugAnnotation::kArrayValuesFieldNumber:
+        OnDebugAnnotation(field.as_bytes(),
+                          message->BeginNestedMessage<protozero::Message>(
+                              field.id()));
+        break;
+
+      case protos::pbzero::DebugAnnotation::kNestedValueFieldNumber:
+        OnNestedValue(field.as_bytes(),
+                      message->BeginNestedMessage<protozero::Message>(
+                          field.id()));
+        break;
+
+      default:
+        proto_util::AppendField(field, message);
+        break;
+    }
+  }
+}
+
+void RedactStrings::OnNestedValue(protozero::ConstBytes bytes,
+                                  protozero::Message* message) const {
+  using NestedValue = protos::pbzero::DebugAnnotation::NestedValue;
+
+  protozero::ProtoDecoder decoder(bytes);
+
+  for (auto field = decoder.ReadField(); field.valid();
+       field = decoder.ReadField()) {
+    switch (field.id()) {
+      case NestedValue::kStringValueFieldNumber:
+        AppendString(field, message);
+        break;
+
+      case NestedValue::kDictValuesFieldNumber:
+      case NestedValue::kArrayValuesFieldNumber:
+        OnNestedValue(field.as_bytes(),
+                      message->BeginNestedMessage<protozero::Message>(
+                          field.id()));
+        break;
+
+      default:
+        proto_util::AppendField(field, message);
+        break;
+    }
+  }
+}
+
+void RedactStrings::OnInternedData(protozero::ConstBytes bytes,
+                                   protozero::Message* message) const {
+  using InternedData = protos::pbzero::InternedData;
+
+  protozero::ProtoDecoder decoder(bytes);
+
+  for (auto field = decoder.ReadField(); field.valid();
+       field = decoder.ReadField()) {
+    switch (field.id()) {
+      case InternedData::kEventNamesFieldNumber:
+      case InternedData::kLogMessageBodyFieldNumber:
+      case InternedData::kDebugAnnotationStringValuesFieldNumber:
+        OnInternedString(field.as_bytes(),
+                         message->BeginNestedMessage<protozero::Message>(
+                             field.id()));
+        break;
+
+      case InternedData::kSourceLocationsFieldNumber:
+        OnSourceLocation(field.as_bytes(),
+                         message->BeginNestedMessage<protozero::Message>(
+                             field.id()));
+        break;
+
+      default:
+        proto_util::AppendField(field, message);
+        break;
+    }
+  }
+}
+
+void RedactStrings::OnInternedString(protozero::ConstBytes bytes,
+                                     protozero::Message* message) const {
+  protozero::ProtoDecoder decoder(bytes);
+
+  for (auto field = decoder.ReadField(); field.valid();
+       field = decoder.ReadField()) {
+    if (field.id() == kInternedStringFieldNumber) {
+      AppendString(field, message);
+    } else {
+      proto_util::AppendField(field, message);
+    }
+  }
+}
+
+void RedactStrings::OnSourceLocation(protozero::ConstBytes bytes,
+                                     protozero::Message* message) const {
+  using SourceLocation = protos::pbzero::SourceLocation;
+
+  protozero::ProtoDecoder decoder(bytes);
+
+  for (auto field = decoder.ReadField(); field.valid();
+       field = decoder.ReadField()) {
+    switch (field.id()) {
+      case SourceLocation::kFileNameFieldNumber:
+      case SourceLocation::kFunctionNameFieldNumber:
+        AppendString(field, message);
+        break;
+
+      default:
+        proto_util::AppendField(field, message);
+        break;
+    }
+  }
+}
+
+void RedactStrings::OnAndroidLog(protozero::ConstBytes bytes,
+                                 protozero::Message* message) const {
+  protozero::ProtoDecoder decoder(bytes);
+
+  for (auto field = decoder.ReadField(); field.valid();
+       field = decoder.ReadField()) {
+    if (field.id() == protos::pbzero::AndroidLogPacket::kEventsFieldNumber) {
+      OnAndroidLogEvent(
+          field.as_bytes(),
+          message->BeginNestedMessage<protozero::Message>(field.id()));
+    } else {
+      proto_util::AppendField(field, message);
+    }
+  }
+}
+
+void RedactStrings::OnAndroidLogEvent(protozero::ConstBytes bytes,
+                                      protozero::Message* message) const {
+  using LogEvent = protos::pbzero::AndroidLogPacket::LogEvent;
+
+  protozero::ProtoDecoder decoder(bytes);
+
+  for (auto field = decoder.ReadField(); field.valid();
+       field = decoder.ReadField()) {
+    switch (field.id()) {
+      case LogEvent::kMessageFieldNumber:
+        AppendString(field, message);
+        break;
+
+      case LogEvent::kArgsFieldNumber:
+        OnAndroidLogArg(field.as_bytes(),
+                        message->BeginNestedMessage<protozero::Message>(
+                            field.id()));
+        break;
+
+      default:
+        proto_util::AppendField(field, message);
+        break;
+    }
+  }
+}
+
+void RedactStrings::OnAndroidLogArg(protozero::ConstBytes bytes,
+                                    protozero::Message* message) const {
+  using Arg = protos::pbzero::AndroidLogPacket::LogEvent::Arg;
+
+  protozero::ProtoDecoder decoder(bytes);
+
+  for (auto field = decoder.ReadField(); field.valid();
+       field = decoder.ReadField()) {
+    if (field.id() == Arg::kStringValueFieldNumber) {
+      AppendString(field, message);
+    } else {
+      proto_util::AppendField(field, message);
+    }
+  }
+}
+
+void RedactStrings::AppendString(const protozero::Field& field,
+                                 protozero::Message* message) const {
+  PERFETTO_DCHECK(field.type() ==
+                  protozero::proto_utils::ProtoWireType::kLengthDelimited);
+  message->AppendString(field.id(), Redact(field.as_std_string()));
+}
+
+}  // namespace perfetto::trace_redaction
@@ -0,0 +1,126 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_REDACTION_REDACT_STRINGS_H_
+#define SRC_TRACE_REDACTION_REDACT_STRINGS_H_
+
+#include <string>
+#include <string_view>
+#include <unordered_set>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/protozero/field.h"
+#include "perfetto/protozero/message.h"
+#include "src/trace_processor/util/regex.h"
+#include "src/trace_redaction/trace_redaction_framework.h"
+
+namespace perfetto::trace_redaction {
+
+// Replaces user-supplied strings that match a set of rules (e.g. emails, urls,
+// account ids, file paths) with tokens. The strings are found in:
+//
+//  - track event names, debug annotation values and source locations
+//  - interned event names, debug annotation values, log message bodies and
+//    source locations
+//  - android log messages and string args
+//
+// A match is replaced with "<rule name:hash>", where the hash only depends on
+// the matched text and the hash key. This means that the same value gets the
+// same token everywhere in the trace, so it can still be joined on. Because
+// interned strings are replaced where they are defined, one replacement
+// covers every reference to the string.
+//
+// Strings in `allowlist` are never changed. Combined with a rule matching
+// everything (e.g. ".+"), this only keeps the allowed strings.
+class RedactStrings : public TransformPrimitive {
+ public:
+  base::Status Transform(const Context& context,
+                         std::string* packet) const override;
+
+  // Patterns use the POSIX extended regex syntax. Returns an error if the
+  // pattern is malformed or if regexes are not supported on this platform.
+  base::Status AddRule(std::string name, const std::string& pattern);
+
+  void AddAllowedString(std::string value) {
+    allowlist_.insert(std::move(value));
+  }
+
+  // The hash key is mixed into every token, so that a token cannot be
+  // reversed by hashing likely values without knowing the key.
+  void set_hash_key(std::string key) { hash_key_ = std::move(key); }
+
+  // Returns `value` with all matches replaced by tokens. When several rules
+  // match, the leftmost match wins, and the first rule among matches starting
+  // at the same position. Matches never span a null character.
+  std::string Redact(std::string_view value) const;
+
+ private:
+  struct Rule {
+    std::string name;
+    trace_processor::regex::Regex regex;
+  };
+
+  // Appends `segment`, which must not contain null characters, to `output`
+  // with all matches replaced by tokens.
+  void RedactSegment(std::string_view segment, std::string* output) const;
+
+  std::string CreateToken(const Rule& rule, std::string_view match) const;
+
+  void OnTrackEvent(protozero::ConstBytes bytes,
+                    protozero::Message* message) const;
+
+  void OnDebugAnnotation(protozero::ConstBytes bytes,
+                         protozero::Message* message) const;
+
+  void OnNestedValue(protozero::ConstBytes bytes,
+                     protozero::Message* message) const;
+
+  void OnInternedData(protozero::ConstBytes bytes,
+                      protozero::Message* message) const;
+
+  // Interned strings (event names, log message bodies, debug annotation
+  // values) all store the string in field 2.
+  void OnInternedString(protozero::ConstBytes bytes,
+                        protozero::Message* message) const;
+
+  // The file and function names of source locations.
+  void OnSourceLocation(protozero::ConstBytes bytes,
+                        protozero::Message* message) const;
+
+  void OnAndroidLog(protozero::ConstBytes bytes,
+                    protozero::Message* message) const;
+
+  void OnAndroidLogEvent(protozero::ConstBytes bytes,
+                         protozero::Message* message) const;
+
+  void OnAndroidLogArg(protozero::ConstBytes bytes,
+                       protozero::Message* message) const;
+
+  void AppendString(const protozero::Field& field,
+                    protozero::Message* message) const;
+
+  // Regex::Submatch() reuses an internal buffer, so it is not const.
+  mutable std::vector<Rule> rules_;
+
+  std::unordered_set<std::string> allowlist_;
+
+  std::string hash_key_;
+};
+
+}  // namespace perfetto::trace_redaction
+
+#endif  // SRC_TRACE_REDACTION_REDACT_STRINGS_H_
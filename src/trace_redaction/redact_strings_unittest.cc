@@ -0,0 +1,291 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_redaction/redact_strings.h"
+
+#include <string>
+
+#include "src/base/test/status_matchers.h"
+#include "src/trace_redaction/trace_redaction_framework.h"
+#include "test/gtest_and_gmock.h"
+
+#include "protos/perfetto/trace/android/android_log.gen.h"
+#include "protos/perfetto/trace/interned_data/interned_data.gen.h"
+#include "protos/perfetto/trace/profiling/profile_common.gen.h"
+#include "protos/perfetto/trace/ps/process_tree.gen.h"
+#include "protos/perfetto/trace/trace_packet.gen.h"
+#include "protos/perfetto/trace/track_event/debug_annotation.gen.h"
+#include "protos/perfetto/trace/track_event/log_message.gen.h"
+#include "protos/perfetto/trace/track_event/source_location.gen.h"
+#include "protos/perfetto/trace/track_event/track_event.gen.h"
+
+namespace perfetto::trace_redaction {
+namespace {
+
+constexpr char kEmailPattern[] = "[a-z0-9._]+@[a-z0-9.]+";
+constexpr char kAccountPattern[] = "account=[0-9]+";
+
+class RedactStringsTest : public testing::Test {
+ protected:
+  void SetUp() override {
+    ASSERT_OK(redact_.AddRule("email", kEmailPattern));
+    ASSERT_OK(redact_.AddRule("account", kAccountPattern));
+  }
+
+  protos::gen::TracePacket Transform(const protos::gen::TracePacket& packet) {
+    auto buffer = packet.SerializeAsString();
+    EXPECT_OK(redact_.Transform(context_, &buffer));
+
+    protos::gen::TracePacket redacted;
+    EXPECT_TRUE(redacted.ParseFromString(buffer));
+    return redacted;
+  }
+
+  Context context_;
+  RedactStrings redact_;
+};
+
+TEST_F(RedactStringsTest, ReplacesMatchesWithTokens) {
+  auto redacted = redact_.Redact("from alice@example.com to bob@example.com");
+
+  ASSERT_THAT(redacted, testing::Not(testing::HasSubstr("alice")));
+  ASSERT_THAT(redacted, testing::Not(testing::HasSubstr("bob")));
+  ASSERT_THAT(redacted, testing::StartsWith("from <email:"));
+  ASSERT_THAT(redacted, testing::HasSubstr("> to <email:"));
+
+  // Different values get different tokens.
+  ASSERT_NE(redact_.Redact("alice@example.com"),
+            redact_.Redact("bob@example.com"));
+}
+
+TEST_F(RedactStringsTest, SameValueGetsSameToken) {
+  auto a = redact_.Redact("alice@example.com");
+  auto b = redact_.Redact("sent to alice@example.com");
+
+  ASSERT_THAT(a, testing::MatchesRegex("<email:[0-9a-f]{16}>"));
+  ASSERT_EQ(b, "sent to " + a);
+}
+
+TEST_F(RedactStringsTest, HashKeyChangesTokens) {
+  auto before = redact_.Redact("alice@example.com");
+
+  redact_.set_hash_key("secret");
+  auto after = redact_.Redact("alice@example.com");
+
+  ASSERT_NE(before, after);
+}
+
+TEST_F(RedactStringsTest, LeftmostMatchWins) {
+  // The email rule is listed first, but the account match starts first.
+  auto redacted = redact_.Redact("account=1234 alice@example.com");
+
+  ASSERT_THAT(redacted, testing::MatchesRegex(
+                            "<account:[0-9a-f]{16}> <email:[0-9a-f]{16}>"));
+}
+
+TEST_F(RedactStringsTest, AnchoredRuleMatchesOnlyAtStart) {
+  RedactStrings redact;
+  ASSERT_OK(redact.AddRule("prefix", "^[a-z]{3}"));
+
+  // After the first match, the rule must not match again at the start of the
+  // rest of the string.
+  ASSERT_THAT(redact.Redact("alicebob"),
+              testing::MatchesRegex("<prefix:[0-9a-f]{16}>cebob"));
+
+  // An empty match at the start doesn't make the rule match further on.
+  RedactStrings redact_empty;
+  ASSERT_OK(redact_empty.AddRule("prefix", "^[a-z]*"));
+  ASSERT_EQ(redact_empty.Redact("1alice"), "1alice");
+}
+
+TEST_F(RedactStringsTest, RedactsPastNullCharacters) {
+  std::string value =
+      std::string("user") + '\0' + "alice@example.com" + '\0' + "account=42";
+
+  ASSERT_EQ(redact_.Redact(value), std::string("user") + '\0' +
+                                       redact_.Redact("alice@example.com") +
+                                       '\0' + redact_.Redact("account=42"));
+}
+
+TEST_F(RedactStringsTest, KeepsStringsWithoutMatches) {
+  ASSERT_EQ(redact_.Redact("nothing to see here"), "nothing to see here");
+  ASSERT_EQ(redact_.Redact(""), "");
+}
+
+TEST_F(RedactStringsTest, KeepsAllowedStrings) {
+  RedactStrings redact;
+  ASSERT_OK(redact.AddRule("string", ".+"));
+  redact.AddAllowedString("Choreographer#doFrame");
+
+  ASSERT_EQ(redact.Redact("Choreographer#doFrame"), "Choreographer#doFrame");
+  ASSERT_THAT(redact.Redact("Load alice@example.com"),
+              testing::MatchesRegex("<string:[0-9a-f]{16}>"));
+}
+
+TEST_F(RedactStringsTest, RejectsMalformedPattern) {
+  RedactStrings redact;
+  ASSERT_FALSE(redact.AddRule("broken", "[a-z").ok());
+  ASSERT_FALSE(redact.AddRule("", "[a-z]").ok());
+}
+
+TEST_F(RedactStringsTest, RedactsTrackEvents) {
+  protos::gen::TracePacket packet;
+  packet.set_timestamp(1000);
+
+  auto* track_event = packet.mutable_track_event();
+  track_event->set_name("Send alice@example.com");
+  track_event->set_track_uuid(1234);
+
+  auto* annotation = track_event->add_debug_annotations();
+  annotation->set_name("recipient");
+  annotation->set_string_value("bob@example.com");
+
+  auto* dict = track_event->add_debug_annotations();
+  dict->set_name("dict");
+
+  auto* entry = dict->add_dict_entries();
+  entry->set_name("user");
+  entry->set_string_value("account=42");
+
+  auto redacted = Transform(packet);
+
+  ASSERT_EQ(redacted.timestamp(), 1000u);
+  ASSERT_EQ(redacted.track_event().track_uuid(), 1234u);
+
+  ASSERT_EQ(redacted.track_event().name(),
+            "Send " + redact_.Redact("alice@example.com"));
+
+  const auto& annotations = redacted.track_event().debug_annotations();
+  ASSERT_EQ(annotations.size(), 2u);
+
+  ASSERT_EQ(annotations[0].name(), "recipient");
+  ASSERT_EQ(annotations[0].string_value(), redact_.Redact("bob@example.com"));
+
+  ASSERT_EQ(annotations[1].dict_entries().size(), 1u);
+  ASSERT_EQ(annotations[1].dict_entries()[0].name(), "user");
+  ASSERT_EQ(annotations[1].dict_entries()[0].string_value(),
+            redact_.Redact("account=42"));
+}
+
+// Interned strings are replaced where they are defined. The interning ids
+// don't change, so every track event referencing them sees the replacement.
+TEST_F(RedactStringsTest, RedactsInternedStrings) {
+  protos::gen::TracePacket packet;
+
+  auto* interned_data = packet.mutable_interned_data();
+
+  auto* event_name = interned_data->add_event_names();
+  event_name->set_iid(1);
+  event_name->set_name("Sync alice@example.com");
+
+  auto* body = interned_data->add_log_message_body();
+  body->set_iid(2);
+  body->set_body("Signed in as account=42");
+
+  auto* value = interned_data->add_debug_annotation_string_values();
+  value->set_iid(3);
+  value->set_str("bob@example.com");
+
+  auto redacted = Transform(packet);
+
+  const auto& interned = redacted.interned_data();
+
+  ASSERT_EQ(interned.event_names().size(), 1u);
+  ASSERT_EQ(interned.event_names()[0].iid(), 1u);
+  ASSERT_EQ(interned.event_names()[0].name(),
+            "Sync " + redact_.Redact("alice@example.com"));
+
+  ASSERT_EQ(interned.log_message_body().size(), 1u);
+  ASSERT_EQ(interned.log_message_body()[0].iid(), 2u);
+  ASSERT_EQ(interned.log_message_body()[0].body(),
+            "Signed in as " + redact_.Redact("account=42"));
+
+  ASSERT_EQ(interned.debug_annotation_string_values().size(), 1u);
+  ASSERT_EQ(interned.debug_annotation_string_values()[0].iid(), 3u);
+  ASSERT_EQ(interned.debug_annotation_string_values()[0].str(),
+            redact_.Redact("bob@example.com"));
+}
+
+TEST_F(RedactStringsTest, RedactsSourceLocations) {
+  protos::gen::TracePacket packet;
+
+  auto* interned = packet.mutable_interned_data()->add_source_locations();
+  interned->set_iid(1);
+  interned->set_file_name("/home/alice@example.com/main.cc");
+  interned->set_function_name("Login(account=42)");
+  interned->set_line_number(10);
+
+  auto* location = packet.mutable_track_event()->mutable_source_location();
+  location->set_file_name("/home/bob@example.com/main.cc");
+
+  auto redacted = Transform(packet);
+
+  const auto& locations = redacted.interned_data().source_locations();
+  ASSERT_EQ(locations.size(), 1u);
+  ASSERT_EQ(locations[0].iid(), 1u);
+  ASSERT_EQ(locations[0].file_name(),
+            "/home/" + redact_.Redact("alice@example.com/main.cc"));
+  ASSERT_EQ(locations[0].function_name(),
+            "Login(" + redact_.Redact("account=42") + ")");
+  ASSERT_EQ(locations[0].line_number(), 10u);
+
+  ASSERT_EQ(redacted.track_event().source_location().file_name(),
+            "/home/" + redact_.Redact("bob@example.com/main.cc"));
+}
+
+TEST_F(RedactStringsTest, RedactsAndroidLogs) {
+  protos::gen::TracePacket packet;
+
+  auto* event = packet.mutable_android_log()->add_events();
+  event->set_pid(1093);
+  event->set_tag("AccountManager");
+  event->set_message("Added alice@example.com");
+
+  auto* arg = event->add_args();
+  arg->set_name("user");
+  arg->set_string_value("account=42");
+
+  auto redacted = Transform(packet);
+
+  const auto& events = redacted.android_log().events();
+  ASSERT_EQ(events.size(), 1u);
+
+  ASSERT_EQ(events[0].pid(), 1093);
+  ASSERT_EQ(events[0].tag(), "AccountManager");
+  ASSERT_EQ(events[0].message(),
+            "Added " + redact_.Redact("alice@example.com"));
+
+  ASSERT_EQ(events[0].args().size(), 1u);
+  ASSERT_EQ(events[0].args()[0].name(), "user");
+  ASSERT_EQ(events[0].args()[0].string_value(), redact_.Redact("account=42"));
+}
+
+TEST_F(RedactStringsTest, IgnoresOtherPackets) {
+  protos::gen::TracePacket packet;
+
+  auto* process = packet.mutable_process_tree()->add_processes();
+  process->set_pid(1093);
+  process->add_cmdline("alice@example.com");
+
+  auto before = packet.SerializeAsString();
+  auto after = before;
+  ASSERT_OK(redact_.Transform(context_, &after));
+
+  ASSERT_EQ(before, after);
+}
+
+}  // namespace
+}  // namespace perfetto::trace_redaction
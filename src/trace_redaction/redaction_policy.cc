@@ -0,0 +1,282 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_redaction/redaction_policy.h"
+
+#include <cstdint>
+#include <memory>
+#include <string>
+#include <vector>
+
+#include "perfetto/base/status.h"
+#include "perfetto/ext/base/status_macros.h"
+#include "perfetto/ext/base/status_or.h"
+#include "src/protozero/text_to_proto/text_to_proto.h"
+#include "src/trace_redaction/add_synth_threads_to_process_trees.h"
+#include "src/trace_redaction/broadphase_packet_filter.h"
+#include "src/trace_redaction/collect_frame_cookies.h"
+#include "src/trace_redaction/collect_system_info.h"
+#include "src/trace_redaction/collect_timeline_events.h"
+#include "src/trace_redaction/drop_empty_ftrace_events.h"
+#include "src/trace_redaction/filtering.h"
+#include "src/trace_redaction/find_package_uid.h"
+#include "src/trace_redaction/merge_threads.h"
+#include "src/trace_redaction/modify.h"
+#include "src/trace_redaction/policy.descriptor.h"
+#include "src/trace_redaction/populate_allow_lists.h"
+#include "src/trace_redaction/prune_package_list.h"
+#include "src/trace_redaction/redact_ftrace_events.h"
+#include "src/trace_redaction/redact_process_events.h"
+#include "src/trace_redaction/redact_sched_events.h"
+#include "src/trace_redaction/redact_strings.h"
+#include "src/trace_redaction/reduce_threads_in_process_trees.h"
+#include "src/trace_redaction/scrub_process_stats.h"
+#include "src/trace_redaction/verify_integrity.h"
+
+#include "protos/perfetto/trace_redaction/policy.pbzero.h"
+
+namespace perfetto::trace_redaction {
+namespace {
+
+using Policy = protos::pbzero::TraceRedactionPolicy;
+
+constexpr char kPolicyProtoName[] = ".perfetto.protos.TraceRedactionPolicy";
+
+base::StatusOr<std::unique_ptr<PidFilter>> CreatePidFilter(
+    const char* transform,
+    int32_t filter) {
+  switch (filter) {
+    case Policy::PID_FILTER_ALLOW_ALL:
+      return std::unique_ptr<PidFilter>(std::make_unique<AllowAll>());
+    case Policy::PID_FILTER_CONNECTED_TO_PACKAGE:
+      return std::unique_ptr<PidFilter>(std::make_unique<ConnectedToPackage>());
+  }
+  return base::ErrStatus("%s: missing or unknown pid filter (%d)", transform,
+                         filter);
+}
+
+base::StatusOr<std::unique_ptr<FtraceEventFilter>> CreateFtraceEventFilter(
+    const char* transform,
+    int32_t filter) {
+  switch (filter) {
+    case Policy::FTRACE_EVENT_FILTER_ALLOW_ALL:
+      return std::unique_ptr<FtraceEventFilter>(std::make_unique<AllowAll>());
+    case Policy::FTRACE_EVENT_FILTER_RSS:
+      return std::unique_ptr<FtraceEventFilter>(std::make_unique<FilterRss>());
+    case Policy::FTRACE_EVENT_FILTER_SUSPEND_RESUME:
+      return std::unique_ptr<FtraceEventFilter>(
+          std::make_unique<FilterFtraceUsingSuspendResume>());
+  }
+  return base::ErrStatus("%s: missing or unknown ftrace event filter (%d)",
+                         transform, filter);
+}
+
+base::StatusOr<std::unique_ptr<PidCommModifier>> CreateModifier(
+    const char* transform,
+    int32_t modifier) {
+  switch (modifier) {
+    case Policy::MODIFIER_DO_NOTHING:
+      return std::unique_ptr<PidCommModifier>(std::make_unique<DoNothing>());
+    case Policy::MODIFIER_CLEAR_COMMS:
+      return std::unique_ptr<PidCommModifier>(std::make_unique<ClearComms>());
+    case Policy::MODIFIER_MERGE_THREADS_PIDS:
+      return std::unique_ptr<PidCommModifier>(
+          std::make_unique<MergeThreadsPids>());
+  }
+  return base::ErrStatus("%s: missing or unknown modifier (%d)", transform,
+                         modifier);
+}
+
+base::Status AddTransform(protozero::ConstBytes bytes,
+                          TraceRedactor* redactor) {
+  Policy::Transform::Decoder transform(bytes);
+
+  if (transform.has_broadphase_packet_filter()) {
+    redactor->emplace_transform<BroadphasePacketFilter>(
+        "broadphase_packet_filter");
+    return base::OkStatus();
+  }
+
+  if (transform.has_redact_ftrace_events()) {
+    constexpr char kName[] = "redact_ftrace_events";
+    Policy::RedactFtraceEvents::Decoder config(
+        transform.redact_ftrace_events());
+    ASSIGN_OR_RETURN(auto filter,
+                     CreateFtraceEventFilter(kName, config.filter()));
+    ASSIGN_OR_RETURN(auto modifier, CreateModifier(kName, config.modifier()));
+    auto* primitive = redactor->emplace_transform<RedactFtraceEvents>(kName);
+    primitive->emplace_ftrace_filter(std::move(filter));
+    primitive->emplace_post_filter_modifier(std::move(modifier));
+    return base::OkStatus();
+  }
+
+  if (transform.has_filter_frame_events()) {
+    redactor->emplace_transform<FilterFrameEvents>("filter_frame_events");
+    return base::OkStatus();
+  }
+
+  if (transform.has_prune_package_list()) {
+    redactor->emplace_transform<PrunePackageList>("prune_package_list");
+    return base::OkStatus();
+  }
+
+  if (transform.has_scrub_process_stats()) {
+    constexpr char kName[] = "scrub_process_stats";
+    Policy::ScrubProcessStats::Decoder config(transform.scrub_process_stats());
+    ASSIGN_OR_RETURN(auto filter, CreatePidFilter(kName, config.filter()));
+    auto* primitive = redactor->emplace_transform<ScrubProcessStats>(kName);
+    primitive->emplace_filter(std::move(filter));
+    return base::OkStatus();
+  }
+
+  if (transform.has_redact_sched_events()) {
+    constexpr char kName[] = "redact_sched_events";
+    Policy::RedactSchedEvents::Decoder config(transform.redact_sched_events());
+    ASSIGN_OR_RETURN(auto modifier, CreateModifier(kName, config.modifier()));
+    ASSIGN_OR_RETURN(auto filter,
+                     CreatePidFilter(kName, config.waking_filter()));
+    auto* primitive = redactor->emplace_transform<RedactSchedEvents>(kName);
+    primitive->emplace_modifier(std::move(modifier));
+    primitive->emplace_waking_filter(std::move(filter));
+    return base::OkStatus();
+  }
+
+  if (transform.has_redact_process_events()) {
+    constexpr char kName[] = "redact_process_events";
+    Policy::RedactProcessEvents::Decoder config(
+        transform.redact_process_events());
+    ASSIGN_OR_RETURN(auto modifier, CreateModifier(kName, config.modifier()));
+    ASSIGN_OR_RETURN(auto filter, CreatePidFilter(kName, config.filter()));
+    auto* primitive = redactor->emplace_transform<RedactProcessEvents>(kName);
+    primitive->emplace_modifier(std::move(modifier));
+    primitive->emplace_filter(std::move(filter));
+    return base::OkStatus();
+  }
+
+  if (transform.has_reduce_threads_in_process_trees()) {
+    redactor->emplace_transform<ReduceThreadsInProcessTrees>(
+        "reduce_threads_in_process_trees");
+    return base::OkStatus();
+  }
+
+  if (transform.has_add_synth_threads_to_process_trees()) {
+    redactor->emplace_transform<AddSythThreadsToProcessTrees>(
+        "add_synth_threads_to_process_trees");
+    return base::OkStatus();
+  }
+
+  if (transform.has_drop_empty_ftrace_events()) {
+    redactor->emplace_transform<DropEmptyFtraceEvents>(
+        "drop_empty_ftrace_events");
+    return base::OkStatus();
+  }
+
+  if (transform.has_redact_strings()) {
+    Policy::RedactStrings::Decoder config(transform.redact_strings());
+
+    if (!config.has_rule()) {
+      return base::ErrStatus("redact_strings: missing rules");
+    }
+
+    auto* primitive =
+        redactor->emplace_transform<RedactStrings>("redact_strings");
+
+    for (auto it = config.rule(); it; ++it) {
+      Policy::RedactStrings::Rule::Decoder rule(*it);
+      RETURN_IF_ERROR(primitive->AddRule(rule.name().ToStdString(),
+                                         rule.pattern().ToStdString()));
+    }
+
+    for (auto it = config.allowed_string(); it; ++it) {
+      primitive->AddAllowedString(it->as_std_string());
+    }
+
+    primitive->set_hash_key(config.hash_key().ToStdString());
+    return base::OkStatus();
+  }
+
+  return base::ErrStatus("TraceRedactionPolicy: transform without primitive");
+}
+
+void AddAllowlists(protozero::ConstBytes bytes, PopulateAllowlists* builder) {
+  Policy::Allowlists::Decoder allowlists(bytes);
+
+  for (auto it = allowlists.add_packet_field(); it; ++it) {
+    builder->AddPacketField(*it);
+  }
+
+  for (auto it = allowlists.remove_packet_field(); it; ++it) {
+    builder->RemovePacketField(*it);
+  }
+
+  for (auto it = allowlists.add_ftrace_event_field(); it; ++it) {
+    builder->AddFtraceEventField(*it);
+  }
+
+  for (auto it = allowlists.remove_ftrace_event_field(); it; ++it) {
+    builder->RemoveFtraceEventField(*it);
+  }
+}
+
+}  // namespace
+
+base::StatusOr<std::vector<uint8_t>> RedactionPolicyTxtToPb(
+    const std::string& input,
+    const std::string& file_name) {
+  return protozero::TextToProto(kPolicyDescriptor.data(),
+                                kPolicyDescriptor.size(), kPolicyProtoName,
+                                file_name, input);
+}
+
+base::StatusOr<std::unique_ptr<TraceRedactor>> CreateRedactorFromPolicy(
+    protozero::ConstBytes policy) {
+  Policy::Decoder decoder(policy);
+
+  auto redactor = std::make_unique<TraceRedactor>();
+
+  // See TraceRedactor::CreateInstance() for why VerifyIntegrity comes first.
+  if (!decoder.has_verify() || decoder.verify()) {
+    redactor->emplace_collect<VerifyIntegrity>();
+  }
+
+  // Collect and build primitives only write to the context, so they are added
+  // regardless of which transforms are used.
+  redactor->emplace_collect<FindPackageUid>();
+  redactor->emplace_collect<CollectTimelineEvents>();
+  redactor->emplace_collect<CollectFrameCookies>();
+  redactor->emplace_collect<CollectSystemInfo>();
+
+  redactor->emplace_build<MergeExtraPackageUids>();
+  redactor->emplace_build<ReduceFrameCookies>();
+  redactor->emplace_build<BuildSyntheticThreads>();
+
+  auto* allowlists = redactor->emplace_build<PopulateAllowlists>();
+  if (decoder.has_allowlists()) {
+    AddAllowlists(decoder.allowlists(), allowlists);
+  }
+
+  if (!decoder.has_transform()) {
+    return base::ErrStatus("TraceRedactionPolicy: no transforms");
+  }
+
+  for (auto it = decoder.transform(); it; ++it) {
+    RETURN_IF_ERROR(AddTransform(*it, redactor.get()));
+  }
+
+  return std::move(redactor);
+}
+
+}  // namespace perfetto::trace_redaction
@@ -0,0 +1,47 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACE_REDACTION_REDACTION_POLICY_H_
+#define SRC_TRACE_REDACTION_REDACTION_POLICY_H_
+
+#include <cstdint>
+#include <memory>
+#include <string>
+#include <vector>
+
+#include "perfetto/ext/base/status_or.h"
+#include "perfetto/protozero/field.h"
+#include "src/trace_redaction/trace_redactor.h"
+
+namespace perfetto::trace_redaction {
+
+// Converts a TraceRedactionPolicy (see
+// protos/perfetto/trace_redaction/policy.proto) from the protobuf text format
+// to the binary format.
+base::StatusOr<std::vector<uint8_t>> RedactionPolicyTxtToPb(
+    const std::string& input,
+    const std::string& file_name);
+
+// Creates a redactor that runs the transforms listed in `policy`, a binary
+// TraceRedactionPolicy, along with all the collect and build primitives they
+// depend on. The packages listed in the policy are not read, it is up to the
+// caller to add them to the context.
+base::StatusOr<std::unique_ptr<TraceRedactor>> CreateRedactorFromPolicy(
+    protozero::ConstBytes policy);
+
+}  // namespace perfetto::trace_redaction
+
+#endif  // SRC_TRACE_REDACTION_REDACTION_POLICY_H_
@@ -0,0 +1,275 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_redaction/redaction_policy.h"
+
+#include <cstdint>
+#include <memory>
+#include <string>
+#include <vector>
+
+#include "perfetto/ext/base/file_utils.h"
+#include "perfetto/ext/base/temp_file.h"
+#include "src/base/test/status_matchers.h"
+#include "src/base/test/utils.h"
+#include "src/trace_redaction/redaction_report.h"
+#include "src/trace_redaction/trace_redaction_framework.h"
+#include "src/trace_redaction/trace_redactor.h"
+#include "test/gtest_and_gmock.h"
+
+#include "protos/perfetto/trace/android/packages_list.gen.h"
+#include "protos/perfetto/trace/ps/process_stats.gen.h"
+#include "protos/perfetto/trace/ps/process_tree.gen.h"
+#include "protos/perfetto/trace/trace.gen.h"
+#include "protos/perfetto/trace/trace_packet.gen.h"
+
+namespace perfetto::trace_redaction {
+namespace {
+
+constexpr uint64_t kUidA = 10205;
+constexpr uint64_t kUidB = 10303;
+constexpr uint64_t kUidC = 10400;
+
+constexpr int32_t kPidA = 100;
+constexpr int32_t kPidB = 200;
+constexpr int32_t kPidC = 300;
+
+// Three packages, each with one process.
+std::string CreateTrace() {
+  protos::gen::Trace trace;
+
+  auto* packages = trace.add_packet();
+  packages->set_trusted_uid(1000);
+  auto* list = packages->mutable_packages_list();
+
+  auto* package_a = list->add_packages();
+  package_a->set_name("com.example.a");
+  package_a->set_uid(kUidA);
+
+  auto* package_b = list->add_packages();
+  package_b->set_name("com.example.b");
+  package_b->set_uid(kUidB);
+
+  auto* package_c = list->add_packages();
+  package_c->set_name("com.example.c");
+  package_c->set_uid(kUidC);
+
+  auto* tree_packet = trace.add_packet();
+  tree_packet->set_trusted_uid(1000);
+  tree_packet->set_timestamp(10);
+  auto* tree = tree_packet->mutable_process_tree();
+
+  for (auto [pid, uid] : {std::pair(kPidA, kUidA), std::pair(kPidB, kUidB),
+                          std::pair(kPidC, kUidC)}) {
+    auto* process = tree->add_processes();
+    process->set_pid(pid);
+    process->set_ppid(1);
+    process->set_uid(static_cast<int32_t>(uid));
+  }
+
+  auto* stats_packet = trace.add_packet();
+  stats_packet->set_trusted_uid(1000);
+  stats_packet->set_timestamp(20);
+  auto* stats = stats_packet->mutable_process_stats();
+
+  for (auto pid : {kPidA, kPidB, kPidC}) {
+    auto* process = stats->add_processes();
+    process->set_pid(pid);
+    process->set_vm_rss_kb(1024);
+  }
+
+  return trace.SerializeAsString();
+}
+
+class RedactionPolicyTest : public testing::Test {
+ protected:
+  void SetUp() override {
+    auto trace = CreateTrace();
+    ASSERT_TRUE(base::WriteAll(src_.fd(), trace.data(), trace.size()));
+  }
+
+  base::StatusOr<std::unique_ptr<TraceRedactor>> CreateRedactor(
+      const std::string& policy_txt) {
+    ASSIGN_OR_RETURN(auto policy, RedactionPolicyTxtToPb(policy_txt, "test"));
+    return CreateRedactorFromPolicy({policy.data(), policy.size()});
+  }
+
+  base::StatusOr<std::string> Redact(const TraceRedactor& redactor,
+                                     Context* context) {
+    RETURN_IF_ERROR(redactor.Redact(src_.path(), dest_.path(), context));
+
+    std::string redacted;
+    if (!base::ReadFile(dest_.path(), &redacted)) {
+      return base::ErrStatus("Failed to read %s", dest_.path().c_str());
+    }
+    return redacted;
+  }
+
+  base::TempFile src_ = base::TempFile::Create();
+  base::TempFile dest_ = base::TempFile::Create();
+};
+
+TEST_F(RedactionPolicyTest, RunsSelectedTransforms) {
+  ASSERT_OK_AND_ASSIGN(auto redactor, CreateRedactor(R"(
+    transform { prune_package_list {} }
+    transform {
+      scrub_process_stats { filter: PID_FILTER_CONNECTED_TO_PACKAGE }
+    }
+  )"));
+
+  Context context;
+  context.package_name = "com.example.a";
+  context.extra_package_names.push_back("com.example.b");
+  context.count_modified_packets = true;
+
+  ASSERT_OK_AND_ASSIGN(auto redacted, Redact(*redactor, &context));
+
+  protos::gen::Trace trace;
+  ASSERT_TRUE(trace.ParseFromString(redacted));
+  ASSERT_EQ(trace.packet_size(), 3);
+
+  const auto& packages = trace.packet()[0].packages_list().packages();
+  ASSERT_EQ(packages.size(), 2u);
+  ASSERT_EQ(packages[0].uid(), kUidA);
+  ASSERT_EQ(packages[1].uid(), kUidB);
+
+  // The process tree is not changed by the policy.
+  ASSERT_EQ(trace.packet()[1].process_tree().processes().size(), 3u);
+
+  const auto& stats = trace.packet()[2].process_stats().processes();
+  ASSERT_EQ(stats.size(), 2u);
+  ASSERT_EQ(stats[0].pid(), kPidA);
+  ASSERT_EQ(stats[1].pid(), kPidB);
+
+  ASSERT_EQ(context.stats.packets_in, 3u);
+  ASSERT_EQ(context.stats.packets_out, 3u);
+  ASSERT_EQ(context.stats.transforms.size(), 2u);
+
+  ASSERT_EQ(context.stats.transforms[0].name, "prune_package_list");
+  ASSERT_EQ(context.stats.transforms[0].packets_modified, 1u);
+  ASSERT_EQ(context.stats.transforms[0].packets_removed, 0u);
+
+  ASSERT_EQ(context.stats.transforms[1].name, "scrub_process_stats");
+  ASSERT_EQ(context.stats.transforms[1].packets_modified, 1u);
+  ASSERT_LT(context.stats.transforms[1].bytes_out,
+            context.stats.transforms[1].bytes_in);
+
+  auto report = RedactionReportToJson(context);
+  ASSERT_THAT(report,
+              testing::HasSubstr("\"package_name\": \"com.example.a\""));
+  ASSERT_THAT(report, testing::HasSubstr("\"extra_package_uids\": [10303]"));
+  ASSERT_THAT(report, testing::HasSubstr(
+                          "{\"name\": \"prune_package_list\", "
+                          "\"packets_modified\": 1, \"packets_removed\": 0"));
+}
+
+TEST_F(RedactionPolicyTest, AcceptsUidsAsTargets) {
+  ASSERT_OK_AND_ASSIGN(auto redactor, CreateRedactor(R"(
+    transform { prune_package_list {} }
+  )"));
+
+  Context context;
+  context.package_uid = kUidC;
+
+  ASSERT_OK_AND_ASSIGN(auto redacted, Redact(*redactor, &context));
+
+  protos::gen::Trace trace;
+  ASSERT_TRUE(trace.ParseFromString(redacted));
+
+  const auto& packages = trace.packet()[0].packages_list().packages();
+  ASSERT_EQ(packages.size(), 1u);
+  ASSERT_EQ(packages[0].uid(), kUidC);
+}
+
+// The default policy must match the redactor used when there is no policy.
+TEST_F(RedactionPolicyTest, DefaultPolicyMatchesDefaultRedactor) {
+  std::string policy;
+  ASSERT_TRUE(base::ReadFile(
+      base::GetTestDataPath("src/trace_redaction/policies/default.textproto"),
+      &policy));
+  ASSERT_OK_AND_ASSIGN(auto policy_redactor, CreateRedactor(policy));
+
+  Context policy_context;
+  policy_context.package_name = "com.example.a";
+  ASSERT_OK_AND_ASSIGN(auto policy_redacted,
+                       Redact(*policy_redactor, &policy_context));
+
+  TraceRedactor::Config config;
+  auto default_redactor = TraceRedactor::CreateInstance(config);
+
+  Context default_context;
+  default_context.package_name = "com.example.a";
+  ASSERT_OK_AND_ASSIGN(auto default_redacted,
+                       Redact(*default_redactor, &default_context));
+
+  ASSERT_EQ(policy_redacted, default_redacted);
+
+  const auto& policy_transforms = policy_context.stats.transforms;
+  const auto& default_transforms = default_context.stats.transforms;
+  ASSERT_EQ(policy_transforms.size(), default_transforms.size());
+
+  for (size_t i = 0; i < policy_transforms.size(); ++i) {
+    ASSERT_EQ(policy_transforms[i].name, default_transforms[i].name);
+  }
+}
+
+TEST_F(RedactionPolicyTest, RejectsMissingFilter) {
+  auto redactor = CreateRedactor(R"(
+    transform { scrub_process_stats {} }
+  )");
+  ASSERT_FALSE(redactor.ok());
+  ASSERT_THAT(redactor.status().message(),
+              testing::HasSubstr("scrub_process_stats"));
+}
+
+TEST_F(RedactionPolicyTest, RejectsEmptyTransform) {
+  ASSERT_FALSE(CreateRedactor("transform {}").ok());
+}
+
+TEST_F(RedactionPolicyTest, RejectsPolicyWithoutTransforms) {
+  ASSERT_FALSE(CreateRedactor("package_name: \"com.example.a\"").ok());
+}
+
+TEST_F(RedactionPolicyTest, RejectsUnknownFields) {
+  ASSERT_FALSE(RedactionPolicyTxtToPb("not_a_field: 1", "test").ok());
+}
+
+TEST_F(RedactionPolicyTest, RejectsRedactStringsWithoutRules) {
+  ASSERT_FALSE(CreateRedactor("transform { redact_strings {} }").ok());
+}
+
+TEST_F(RedactionPolicyTest, RejectsMalformedStringRule) {
+  auto redactor = CreateRedactor(R"(
+    transform {
+      redact_strings { rule { name: "broken" pattern: "[a-z" } }
+    }
+  )");
+  ASSERT_FALSE(redactor.ok());
+}
+
+TEST_F(RedactionPolicyTest, RejectsOutOfRangeAllowlistField) {
+  ASSERT_OK_AND_ASSIGN(auto redactor, CreateRedactor(R"(
+    allowlists { add_packet_field: 4096 }
+    transform { broadphase_packet_filter {} }
+  )"));
+
+  Context context;
+  context.package_name = "com.example.a";
+  ASSERT_FALSE(Redact(*redactor, &context).ok());
+}
+
+}  // namespace
+}  // namespace perfetto::trace_redaction
@@ -0,0 +1,103 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/trace_redaction/redaction_report.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "perfetto/ext/base/string_utils.h"
+#include "src/trace_redaction/trace_redaction_framework.h"
+
+namespace perfetto::trace_redaction {
+namespace {
+
+std::string QuoteString(const std::string& value) {
+  std::string quoted = "\"";
+  for (char c : value) {
+    switch (c) {
+      case '"':
+        quoted += "\\\"";
+        break;
+      case '\\':
+        quoted += "\\\\";
+        break;
+      default:
+        if (static_cast<unsigned char>(c) < 0x20) {
+          base::StackString<8> escaped("\\u%04x", static_cast<int>(c));
+          quoted += escaped.ToStdString();
+        } else {
+          quoted += c;
+        }
+        break;
+    }
+  }
+  quoted += "\"";
+  return quoted;
+}
+
+std::string Number(uint64_t value) {
+  return std::to_string(value);
+}
+
+}  // namespace
+
+std::string RedactionReportToJson(const Context& context) {
+  const auto& stats = context.stats;
+
+  std::string json = "{\n";
+
+  json += "  \"package_name\": " + QuoteString(context.package_name) + ",\n";
+  json += "  \"package_uid\": " +
+          (context.package_uid ? Number(*context.package_uid) : "null") +
+          ",\n";
+
+  std::vector<std::string> names;
+  for (const auto& name : context.extra_package_names) {
+    names.push_back(QuoteString(name));
+  }
+  json += "  \"extra_package_names\": [" + base::Join(names, ", ") + "],\n";
+
+  std::vector<std::string> uids;
+  for (auto uid : context.extra_package_uids) {
+    uids.push_back(Number(uid));
+  }
+  json += "  \"extra_package_uids\": [" + base::Join(uids, ", ") + "],\n";
+
+  json += "  \"packets_in\": " + Number(stats.packets_in) + ",\n";
+  json += "  \"packets_out\": " + Number(stats.packets_out) + ",\n";
+  json += "  \"bytes_in\": " + Number(stats.bytes_in) + ",\n";
+  json += "  \"bytes_out\": " + Number(stats.bytes_out) + ",\n";
+
+  json += "  \"transforms\": [";
+  for (size_t i = 0; i < stats.transforms.size(); ++i) {
+    const auto& transform = stats.transforms[i];
+    json += i == 0 ? "\n" : ",\n";
+    json += "    {\"name\": " + QuoteString(transform.name) +
+            ", \"packets_modified\": " + Number(transform.packets_modified) +
+            ", \"packets_removed\": " + Number(transform.packets_removed) +
+            ", \"bytes_in\": " + Number(transform.bytes_in) +
+            ", \"bytes_out\": " + Number(transform.bytes_out) + "}";
+  }
+  json += stats.transforms.empty() ? "]\n" : "\n  ]\n";
+
+  json += "}\n";
+  return json;
+}
+
+}  // namespace perfetto::trace_redaction
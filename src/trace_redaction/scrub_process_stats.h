@@ -34,6 +34,11 @@ class ScrubProcessStats : public TransformPrimitive {
     filter_ = std::make_unique<Filter>();
   }
 
+  template <class Filter>
+  void emplace_filter(std::unique_ptr<Filter> filter) {
+    filter_ = std::move(filter);
+  }
+
  private:
   base::Status OnProcessStats(const Context& context,
                               uint64_t ts,
@@ -114,6 +114,36 @@ class SyntheticProcess {
   std::vector<int32_t> tids_;
 };
 
+// Describes how a transform primitive changed the trace.
+struct TransformStats {
+  // The name the primitive was given when it was added to the redactor.
+  std::string name;
+
+  // The number of packets changed, but not removed, by the primitive. Only
+  // counted if `Context::count_modified_packets` is set.
+  uint64_t packets_modified = 0;
+
+  // The number of packets removed by the primitive.
+  uint64_t packets_removed = 0;
+
+  // The size of the packets before and after the primitive ran. Packets
+  // removed by an earlier primitive are not seen by the primitive.
+  uint64_t bytes_in = 0;
+  uint64_t bytes_out = 0;
+};
+
+// Describes how the trace was changed by the redactor.
+struct RedactionStats {
+  uint64_t packets_in = 0;
+  uint64_t packets_out = 0;
+
+  uint64_t bytes_in = 0;
+  uint64_t bytes_out = 0;
+
+  // One entry per transform primitive, in the order they ran.
+  std::vector<TransformStats> transforms;
+};
+
 // Primitives should be stateless. All state should be stored in the context.
 // Primitives should depend on data in the context, not the origin of the data.
 // This allows primitives to be swapped out or work together to populate data
@@ -136,8 +166,8 @@ class Context {
   // last allowed uid (allow all uids less than or equal to 9999).
   static constexpr int32_t kMaxTrustedUid = 9999;
 
-  // The package that should not be redacted. This must be populated before
-  // running any primitives.
+  // The package that should not be redacted. This or `package_uid` must be
+  // populated before running any primitives.
   std::string package_name;
 
   // The package list maps a package name to a uid. It is possible for multiple
@@ -226,6 +256,15 @@ class Context {
   // the uid to the timeline.
   std::optional<uint64_t> package_uid;
 
+  // Other packages that should not be redacted. Data connected to these
+  // packages is kept as if it was connected to `package_name`.
+  std::vector<std::string> extra_package_names;
+
+  // The normalized uids of other packages that should not be redacted.
+  // FindPackageUid appends the uids of `extra_package_names` and
+  // MergeExtraPackageUids merges all of them into `package_uid`.
+  std::vector<uint64_t> extra_package_uids;
+
   // Trace packets contain a "one of" entry called "data". This field can be
   // thought of as the message. A track packet with have other fields along
   // side "data" (e.g. "timestamp"). These fields can be thought of as metadata.
@@ -356,6 +395,14 @@ class Context {
   std::optional<SystemInfo> system_info;
 
   std::unique_ptr<SyntheticProcess> synthetic_process;
+
+  // Whether TraceRedactor counts the packets each transform modified. This
+  // requires a copy of every packet before each transform, so it is only
+  // done when the stats are reported.
+  bool count_modified_packets = false;
+
+  // Populated by TraceRedactor while transforming the trace.
+  RedactionStats stats;
 };
 
 // Extracts low-level data from the trace and writes it into the context. The
@@ -76,7 +76,7 @@ base::Status TraceRedactor::Redact(std::string_view source_filename,
     RETURN_IF_ERROR(builder->Build(context));
   }
 
-  return Transform(*context, whole_view, std::string(dest_filename));
+  return Transform(context, whole_view, std::string(dest_filename));
 }
 
 base::Status TraceRedactor::Collect(
@@ -104,10 +104,16 @@ base::Status TraceRedactor::Collect(
 }
 
 base::Status TraceRedactor::Transform(
-    const Context& context,
+    Context* context,
     const trace_processor::TraceBlobView& view,
     const std::string& dest_file) const {
-  std::ignore = context;
+  auto& stats = context->stats;
+  stats = RedactionStats();
+
+  for (const auto& transformer : transformers_) {
+    stats.transforms.emplace_back().name = transformer.name;
+  }
+
   const auto dest_fd = base::OpenFile(dest_file, O_RDWR | O_CREAT, 0666);
 
   if (dest_fd.get() == -1) {
@@ -115,11 +121,18 @@ base::Status TraceRedactor::Transform(
         "Failed to open destination file; can't write redacted trace.");
   }
 
+  // A copy of the packet before the current transform, used to detect which
+  // transforms changed the packet when they are counted.
+  std::string packet_before;
+
   const Trace::Decoder trace_decoder(view.data(), view.length());
   for (auto packet_it = trace_decoder.packet(); packet_it; ++packet_it) {
     auto packet = packet_it->as_std_string();
 
-    for (const auto& transformer : transformers_) {
+    stats.packets_in++;
+    stats.bytes_in += packet.size();
+
+    for (size_t i = 0; i < transformers_.size(); ++i) {
       // If the packet has been cleared, it means a transformation has removed
       // it from the trace. Stop processing it. This saves transforms from
       // having to check and handle empty packets.
@@ -127,7 +140,21 @@ base::Status TraceRedactor::Transform(
         break;
       }
 
-      RETURN_IF_ERROR(transformer->Transform(context, &packet));
+      auto& transform_stats = stats.transforms[i];
+      transform_stats.bytes_in += packet.size();
+      if (context->count_modified_packets) {
+        packet_before.assign(packet);
+      }
+
+      RETURN_IF_ERROR(transformers_[i].primitive->Transform(*context, &packet));
+
+      transform_stats.bytes_out += packet.size();
+
+      if (packet.empty()) {
+        transform_stats.packets_removed++;
+      } else if (context->count_modified_packets && packet != packet_before) {
+        transform_stats.packets_modified++;
+      }
     }
 
     // The packet has been removed from the trace. Don't write an empty packet
@@ -136,6 +163,9 @@ base::Status TraceRedactor::Transform(
       continue;
     }
 
+    stats.packets_out++;
+    stats.bytes_out += packet.size();
+
     protozero::HeapBuffered<protos::pbzero::Trace> serializer;
     serializer->add_packet()->AppendRawProtoBytes(packet.data(), packet.size());
     packet.assign(serializer.SerializeAsString());
@@ -169,7 +199,9 @@ std::unique_ptr<TraceRedactor> TraceRedactor::CreateInstance(
   redactor->emplace_collect<CollectFrameCookies>();
   redactor->emplace_collect<CollectSystemInfo>();
 
-  // Add all builders.
+  // Add all builders. The extra packages must be merged into the target
+  // package before any other builder reads the timeline.
+  redactor->emplace_build<MergeExtraPackageUids>();
   redactor->emplace_build<ReduceFrameCookies>();
   redactor->emplace_build<BuildSyntheticThreads>();
 
@@ -177,27 +209,30 @@ std::unique_ptr<TraceRedactor> TraceRedactor::CreateInstance(
     // In order for BroadphasePacketFilter to work, something needs to populate
     // the masks (i.e. PopulateAllowlists).
     redactor->emplace_build<PopulateAllowlists>();
-    redactor->emplace_transform<BroadphasePacketFilter>();
+    redactor->emplace_transform<BroadphasePacketFilter>(
+        "broadphase_packet_filter");
   }
 
   {
-    auto* primitive = redactor->emplace_transform<RedactFtraceEvents>();
+    auto* primitive =
+        redactor->emplace_transform<RedactFtraceEvents>("redact_ftrace_events");
     primitive->emplace_ftrace_filter<FilterRss>();
     primitive->emplace_post_filter_modifier<DoNothing>();
   }
 
   {
-    auto* primitive = redactor->emplace_transform<RedactFtraceEvents>();
+    auto* primitive =
+        redactor->emplace_transform<RedactFtraceEvents>("redact_ftrace_events");
     primitive->emplace_ftrace_filter<FilterFtraceUsingSuspendResume>();
     primitive->emplace_post_filter_modifier<DoNothing>();
   }
 
   {
     // Remove all frame timeline events that don't belong to the target package.
-    redactor->emplace_transform<FilterFrameEvents>();
+    redactor->emplace_transform<FilterFrameEvents>("filter_frame_events");
   }
 
-  redactor->emplace_transform<PrunePackageList>();
+  redactor->emplace_transform<PrunePackageList>("prune_package_list");
 
   // Process stats includes per-process information, such as:
   //
@@ -217,14 +252,16 @@ std::unique_ptr<TraceRedactor> TraceRedactor::CreateInstance(
   // Use the ConnectedToPackage primitive to ensure only the target package has
   // stats in the trace.
   {
-    auto* primitive = redactor->emplace_transform<ScrubProcessStats>();
+    auto* primitive =
+        redactor->emplace_transform<ScrubProcessStats>("scrub_process_stats");
     primitive->emplace_filter<ConnectedToPackage>();
   }
 
   // Redacts all switch and waking events. This should use the same modifier and
   // filter as the process events (see below).
   {
-    auto* primitive = redactor->emplace_transform<RedactSchedEvents>();
+    auto* primitive =
+        redactor->emplace_transform<RedactSchedEvents>("redact_sched_events");
     primitive->emplace_modifier<ClearComms>();
     primitive->emplace_waking_filter<ConnectedToPackage>();
   }
@@ -232,7 +269,8 @@ std::unique_ptr<TraceRedactor> TraceRedactor::CreateInstance(
   // Redacts all new task, rename task, process free events. This should use the
   // same modifier and filter as the schedule events (see above).
   {
-    auto* primitive = redactor->emplace_transform<RedactProcessEvents>();
+    auto* primitive = redactor->emplace_transform<RedactProcessEvents>(
+        "redact_process_events");
     primitive->emplace_modifier<ClearComms>();
     primitive->emplace_filter<ConnectedToPackage>();
   }
@@ -240,7 +278,8 @@ std::unique_ptr<TraceRedactor> TraceRedactor::CreateInstance(
   // Merge Threads (part 1): Remove all waking events that connected to the
   // target package. Change the pids not connected to the target package.
   {
-    auto* primitive = redactor->emplace_transform<RedactSchedEvents>();
+    auto* primitive =
+        redactor->emplace_transform<RedactSchedEvents>("redact_sched_events");
     primitive->emplace_modifier<MergeThreadsPids>();
     primitive->emplace_waking_filter<ConnectedToPackage>();
   }
@@ -248,7 +287,8 @@ std::unique_ptr<TraceRedactor> TraceRedactor::CreateInstance(
   // Merge Threads (part 2): Drop all process events not belonging to the
   // target package. No modification is needed.
   {
-    auto* primitive = redactor->emplace_transform<RedactProcessEvents>();
+    auto* primitive = redactor->emplace_transform<RedactProcessEvents>(
+        "redact_process_events");
     primitive->emplace_modifier<DoNothing>();
     primitive->emplace_filter<ConnectedToPackage>();
   }
@@ -256,7 +296,8 @@ std::unique_ptr<TraceRedactor> TraceRedactor::CreateInstance(
   // Merge Threads (part 3): Replace ftrace event's pid (not the task's pid)
   // for all pids not connected to the target package.
   {
-    auto* primitive = redactor->emplace_transform<RedactFtraceEvents>();
+    auto* primitive =
+        redactor->emplace_transform<RedactFtraceEvents>("redact_ftrace_events");
     primitive->emplace_post_filter_modifier<MergeThreadsPids>();
     primitive->emplace_ftrace_filter<AllowAll>();
   }
@@ -269,8 +310,10 @@ std::unique_ptr<TraceRedactor> TraceRedactor::CreateInstance(
   // If primitives are not in this order, newly added processes/threads may
   // get removed.
   {
-    redactor->emplace_transform<ReduceThreadsInProcessTrees>();
-    redactor->emplace_transform<AddSythThreadsToProcessTrees>();
+    redactor->emplace_transform<ReduceThreadsInProcessTrees>(
+        "reduce_threads_in_process_trees");
+    redactor->emplace_transform<AddSythThreadsToProcessTrees>(
+        "add_synth_threads_to_process_trees");
   }
 
   // Optimizations:
@@ -279,7 +322,8 @@ std::unique_ptr<TraceRedactor> TraceRedactor::CreateInstance(
   // other transforms. The most common function will be to remove empty
   // messages.
   {
-    redactor->emplace_transform<DropEmptyFtraceEvents>();
+    redactor->emplace_transform<DropEmptyFtraceEvents>(
+        "drop_empty_ftrace_events");
   }
 
   return redactor;
@@ -63,12 +63,13 @@ class TraceRedactor {
     return ptr;
   }
 
-  // T must be derived from trace_redaction::TransformPrimitive.
+  // T must be derived from trace_redaction::TransformPrimitive. `name`
+  // identifies the primitive in `Context::stats`.
   template <typename T>
-  T* emplace_transform() {
+  T* emplace_transform(std::string name = "") {
     auto uptr = std::make_unique<T>();
     auto* ptr = uptr.get();
-    transformers_.push_back(std::move(uptr));
+    transformers_.push_back({std::move(name), std::move(uptr)});
     return ptr;
   }
 
@@ -110,13 +111,20 @@ class TraceRedactor {
   //     for transform in transformers:
   //       transform(context, packet)
   // ```
-  base::Status Transform(const Context& context,
+  //
+  // Only `Context::stats` is written to.
+  base::Status Transform(Context* context,
                          const trace_processor::TraceBlobView& view,
                          const std::string& dest_file) const;
 
+  struct NamedTransform {
+    std::string name;
+    std::unique_ptr<TransformPrimitive> primitive;
+  };
+
   std::vector<std::unique_ptr<CollectPrimitive>> collectors_;
   std::vector<std::unique_ptr<BuildPrimitive>> builders_;
-  std::vector<std::unique_ptr<TransformPrimitive>> transformers_;
+  std::vector<NamedTransform> transformers_;
 };
 
 }  // namespace perfetto::trace_redaction
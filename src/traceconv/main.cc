@@ -21,6 +21,7 @@
 #include <fstream>
 #include <iostream>
 #include <iterator>
+#include <optional>
 #include <string>
 #include <vector>
 
@@ -33,9 +34,12 @@
 #include "src/traceconv/symbolize_profile.h"
 #include "src/traceconv/trace.descriptor.h"
 #include "src/traceconv/trace_to_firefox.h"
+#include "src/traceconv/trace_to_folded.h"
 #include "src/traceconv/trace_to_hprof.h"
 #include "src/traceconv/trace_to_json.h"
+#include "src/traceconv/trace_to_otlp.h"
 #include "src/traceconv/trace_to_profile.h"
+#include "src/traceconv/trace_to_speedscope.h"
 #include "src/traceconv/trace_to_systrace.h"
 #include "src/traceconv/trace_to_text.h"
 #include "src/traceconv/trace_unpack.h"
@@ -56,7 +60,7 @@ int Usage(const char* argv0) {
       "Usage: %s MODE [OPTIONS] [input file] [output file]\n"
       "modes:\n"
       "  systrace|json|ctrace|text|profile|hprof|symbolize|deobfuscate|firefox"
-      "|java_heap_profile|decompress_packets|binary\n"
+      "|folded|speedscope|otlp|java_heap_profile|decompress_packets|binary\n"
       "options:\n"
       "  [--truncate start|end]\n"
       "  [--full-sort]\n"
@@ -66,7 +70,13 @@ int Usage(const char* argv0) {
       "annotations\n"
       "  [--timestamps TIMESTAMP1,TIMESTAMP2,...] generate profiles "
       "only for these *specific* timestamps\n"
-      "  [--pid PID] generate profiles only for this process id\n",
+      "  [--pid PID] generate profiles only for this process id\n"
+      "  [--diff-baseline-ts TIMESTAMP] generate profiles of the difference "
+      "with the heap dump at TIMESTAMP\n"
+      "  [--diff-baseline-pid PID] with --diff-baseline-ts, compare with the "
+      "heap dump of this process id instead of the same process\n"
+      "\"folded\" mode options:\n"
+      "  [--heap] export heap profile allocations instead of CPU samples\n",
       argv0);
   return 1;
 }
@@ -106,6 +116,9 @@ int Main(int argc, char** argv) {
   bool full_sort = false;
   bool perf_profile = false;
   bool profile_no_annotations = false;
+  bool heap_folded = false;
+  std::optional<uint64_t> diff_baseline_ts;
+  uint64_t diff_baseline_pid = 0;
   for (int i = 1; i < argc; i++) {
     if (strcmp(argv[i], "-v") == 0 || strcmp(argv[i], "--version") == 0) {
       printf("%s\n", base::GetVersionString());
@@ -132,10 +145,18 @@ int Main(int argc, char** argv) {
       for (const std::string& ts : ts_strings) {
         timestamps.emplace_back(StringToUint64OrDie(ts.c_str()));
       }
+    } else if (i <= argc && strcmp(argv[i], "--diff-baseline-ts") == 0) {
+      i++;
+      diff_baseline_ts = StringToUint64OrDie(argv[i]);
+    } else if (i <= argc && strcmp(argv[i], "--diff-baseline-pid") == 0) {
+      i++;
+      diff_baseline_pid = StringToUint64OrDie(argv[i]);
     } else if (strcmp(argv[i], "--perf") == 0) {
       perf_profile = true;
     } else if (strcmp(argv[i], "--no-annotations") == 0) {
       profile_no_annotations = true;
+    } else if (strcmp(argv[i], "--heap") == 0) {
+      heap_folded = true;
     } else if (strcmp(argv[i], "--full-sort") == 0) {
       full_sort = true;
     } else {
@@ -198,6 +219,18 @@ int Main(int argc, char** argv) {
     PERFETTO_ELOG("--perf requires profile format.");
     return 1;
   }
+  if (diff_baseline_pid != 0 && !diff_baseline_ts) {
+    PERFETTO_ELOG("--diff-baseline-pid requires --diff-baseline-ts.");
+    return 1;
+  }
+  if (diff_baseline_ts && (format != "profile" || perf_profile)) {
+    PERFETTO_ELOG("--diff-baseline-ts requires profile format without --perf.");
+    return 1;
+  }
+  if (heap_folded && format != "folded") {
+    PERFETTO_ELOG("--heap requires folded format.");
+    return 1;
+  }
 
   if (format == "binary") {
     return TextToTrace(input_stream, output_stream);
@@ -233,6 +266,12 @@ int Main(int argc, char** argv) {
     return TraceToText(input_stream, output_stream) ? 0 : 1;
   }
 
+  if (format == "profile" && diff_baseline_ts) {
+    return TraceToHeapDiffProfile(input_stream, output_stream, pid, timestamps,
+                                  !profile_no_annotations, diff_baseline_pid,
+                                  *diff_baseline_ts);
+  }
+
   if (format == "profile") {
     return perf_profile
                ? TraceToPerfProfile(input_stream, output_stream, pid,
@@ -258,6 +297,21 @@ int Main(int argc, char** argv) {
   if (format == "firefox")
     return TraceToFirefoxProfile(input_stream, output_stream);
 
+  if (format == "folded") {
+    bool ok = TraceToFoldedStacks(input_stream, output_stream, heap_folded);
+    return ok ? 0 : 1;
+  }
+
+  if (format == "speedscope") {
+    bool ok = TraceToSpeedscope(input_stream, output_stream);
+    return ok ? 0 : 1;
+  }
+
+  if (format == "otlp") {
+    bool ok = TraceToOtlp(input_stream, output_stream);
+    return ok ? 0 : 1;
+  }
+
   if (format == "decompress_packets")
     return UnpackCompressedPackets(input_stream, output_stream);
 
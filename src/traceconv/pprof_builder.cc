@@ -25,7 +25,9 @@
 #include <algorithm>
 #include <cinttypes>
 #include <map>
+#include <optional>
 #include <set>
+#include <string>
 #include <unordered_map>
 #include <vector>
 
@@ -632,25 +634,33 @@ namespace heap_profile {
 struct View {
   const char* type;
   const char* unit;
-  const char* aggregator;
+  // Column of heap_profile_allocation summed by this view.
+  const char* column;
   const char* filter;
 };
 
 const View kMallocViews[] = {
-    {"Total malloc count", "count", "sum(count)", "size >= 0"},
-    {"Total malloc size", "bytes", "SUM(size)", "size >= 0"},
-    {"Unreleased malloc count", "count", "SUM(count)", nullptr},
-    {"Unreleased malloc size", "bytes", "SUM(size)", nullptr}};
+    {"Total malloc count", "count", "count", "size >= 0"},
+    {"Total malloc size", "bytes", "size", "size >= 0"},
+    {"Unreleased malloc count", "count", "count", nullptr},
+    {"Unreleased malloc size", "bytes", "size", nullptr}};
 
 const View kGenericViews[] = {
-    {"Total count", "count", "sum(count)", "size >= 0"},
-    {"Total size", "bytes", "SUM(size)", "size >= 0"},
-    {"Unreleased count", "count", "SUM(count)", nullptr},
-    {"Unreleased size", "bytes", "SUM(size)", nullptr}};
+    {"Total count", "count", "count", "size >= 0"},
+    {"Total size", "bytes", "size", "size >= 0"},
+    {"Unreleased count", "count", "count", nullptr},
+    {"Unreleased size", "bytes", "size", nullptr}};
 
 const View kJavaSamplesViews[] = {
-    {"Total allocation count", "count", "SUM(count)", nullptr},
-    {"Total allocation size", "bytes", "SUM(size)", nullptr}};
+    {"Total allocation count", "count", "count", nullptr},
+    {"Total allocation size", "bytes", "size", nullptr}};
+
+// The dump of the process |upid| at |ts|. For a diff profile, the samples of
+// the baseline dump are subtracted from the samples of the candidate dump.
+struct Dump {
+  uint64_t upid;
+  uint64_t ts;
+};
 
 static bool VerifyPIDStats(trace_processor::TraceProcessor* tp, uint64_t pid) {
   bool success = true;
@@ -688,21 +698,37 @@ static bool VerifyPIDStats(trace_processor::TraceProcessor* tp, uint64_t pid) {
   return success;
 }
 
+static std::string DumpCondition(const Dump& dump) {
+  return "(hpa.upid = " + std::to_string(dump.upid) +
+         " AND hpa.ts <= " + std::to_string(dump.ts) + ")";
+}
+
 static std::vector<Iterator> BuildViewIterators(
     trace_processor::TraceProcessor* tp,
-    uint64_t upid,
-    uint64_t ts,
+    const Dump& candidate,
+    const std::optional<Dump>& baseline,
     const char* heap_name,
     const std::vector<View>& views) {
   std::vector<Iterator> view_its;
   for (const View& v : views) {
-    std::string query = "SELECT hpa.callsite_id ";
-    query +=
-        ", " + std::string(v.aggregator) + " FROM heap_profile_allocation hpa ";
+    std::string query = "SELECT hpa.callsite_id, ";
+    if (baseline) {
+      // Rows that are part of both dumps (i.e. the baseline is an earlier
+      // dump of the same process) cancel out.
+      query += "SUM((" + DumpCondition(candidate) + " - " +
+               DumpCondition(*baseline) + ") * hpa." + v.column + ") ";
+    } else {
+      query += "SUM(hpa." + std::string(v.column) + ") ";
+    }
+    query += "FROM heap_profile_allocation hpa ";
     // TODO(fmayer): Figure out where negative callsite_id comes from.
     query += "WHERE hpa.callsite_id >= 0 ";
-    query += "AND hpa.upid = " + std::to_string(upid) + " ";
-    query += "AND hpa.ts <= " + std::to_string(ts) + " ";
+    if (baseline) {
+      query += "AND (" + DumpCondition(candidate) + " OR " +
+               DumpCondition(*baseline) + ") ";
+    } else {
+      query += "AND " + DumpCondition(candidate) + " ";
+    }
     query += "AND hpa.heap_name = '" + std::string(heap_name) + "' ";
     if (v.filter)
       query += "AND " + std::string(v.filter) + " ";
@@ -752,11 +778,33 @@ static bool WriteAllocations(GProfileBuilder* builder,
   return true;
 }
 
+// Returns the upid of the process |pid| with a dump of |heap_name| at |ts|.
+static std::optional<uint64_t> FindDumpUpid(trace_processor::TraceProcessor* tp,
+                                            uint64_t pid,
+                                            uint64_t ts,
+                                            const char* heap_name) {
+  Iterator it = tp->ExecuteQuery(
+      "SELECT hpa.upid FROM heap_profile_allocation hpa "
+      "JOIN process p USING (upid) "
+      "WHERE p.pid = " +
+      std::to_string(pid) + " AND hpa.ts = " + std::to_string(ts) +
+      " AND hpa.heap_name = '" + std::string(heap_name) + "' LIMIT 1;");
+  if (!it.Next()) {
+    if (!it.Status().ok()) {
+      PERFETTO_DFATAL_OR_ELOG("Invalid iterator: %s",
+                              it.Status().message().c_str());
+    }
+    return std::nullopt;
+  }
+  return static_cast<uint64_t>(it.Get(0).AsLong());
+}
+
 static bool TraceToHeapPprof(trace_processor::TraceProcessor* tp,
                              std::vector<SerializedProfile>* output,
                              bool annotate_frames,
                              uint64_t target_pid,
-                             const std::vector<uint64_t>& target_timestamps) {
+                             const std::vector<uint64_t>& target_timestamps,
+                             const HeapDiffBaseline* diff_baseline) {
   trace_processor::StringPool interner;
   LocationTracker locations =
       PreprocessLocations(tp, &interner, annotate_frames);
@@ -779,6 +827,27 @@ static bool TraceToHeapPprof(trace_processor::TraceProcessor* tp,
       continue;
     }
 
+    std::optional<Dump> baseline;
+    if (diff_baseline) {
+      uint64_t baseline_pid =
+          diff_baseline->pid ? diff_baseline->pid : profile_pid;
+      std::optional<uint64_t> baseline_upid =
+          baseline_pid == profile_pid
+              ? std::make_optional(upid)
+              : FindDumpUpid(tp, baseline_pid, diff_baseline->ts, heap_name);
+      if (!baseline_upid) {
+        PERFETTO_ELOG("No %s dump of %" PRIu64 " at %" PRIu64
+                      " to compare with.",
+                      heap_name, baseline_pid, diff_baseline->ts);
+        any_fail = true;
+        continue;
+      }
+      // The baseline dump itself would produce an empty diff.
+      if (*baseline_upid == upid && diff_baseline->ts == ts)
+        continue;
+      baseline = Dump{*baseline_upid, diff_baseline->ts};
+    }
+
     if (!VerifyPIDStats(tp, profile_pid))
       any_fail = true;
 
@@ -798,7 +867,7 @@ static bool TraceToHeapPprof(trace_processor::TraceProcessor* tp,
     builder.WriteSampleTypes(sample_types);
 
     std::vector<Iterator> view_its =
-        BuildViewIterators(tp, upid, ts, heap_name, views);
+        BuildViewIterators(tp, Dump{upid, ts}, baseline, heap_name, views);
     std::string profile_proto;
     if (WriteAllocations(&builder, &view_its)) {
       profile_proto = builder.CompleteProfile(tp);
@@ -1182,7 +1251,8 @@ bool TraceToPprof(trace_processor::TraceProcessor* tp,
   switch (mode) {
     case (ConversionMode::kHeapProfile):
       return heap_profile::TraceToHeapPprof(tp, output, annotate_frames, pid,
-                                            timestamps);
+                                            timestamps,
+                                            /*diff_baseline=*/nullptr);
     case (ConversionMode::kPerfProfile):
       return perf_profile::TraceToPerfPprof(tp, output, annotate_frames, pid);
     case (ConversionMode::kJavaHeapProfile):
@@ -1191,5 +1261,17 @@ bool TraceToPprof(trace_processor::TraceProcessor* tp,
   PERFETTO_FATAL("unknown conversion option");  // for gcc
 }
 
+bool TraceToHeapDiffPprof(trace_processor::TraceProcessor* tp,
+                          std::vector<SerializedProfile>* output,
+                          const HeapDiffBaseline& baseline,
+                          uint64_t flags,
+                          uint64_t pid,
+                          const std::vector<uint64_t>& timestamps) {
+  bool annotate_frames =
+      flags & static_cast<uint64_t>(ConversionFlags::kAnnotateFrames);
+  return heap_profile::TraceToHeapPprof(tp, output, annotate_frames, pid,
+                                        timestamps, &baseline);
+}
+
 }  // namespace trace_to_text
 }  // namespace perfetto
@@ -0,0 +1,218 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/traceconv/trace_to_folded.h"
+
+#include <cstdint>
+#include <istream>
+#include <map>
+#include <memory>
+#include <optional>
+#include <ostream>
+#include <string>
+#include <unordered_map>
+#include <utility>
+#include <vector>
+
+#include "perfetto/base/logging.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "perfetto/trace_processor/basic_types.h"
+#include "perfetto/trace_processor/trace_processor.h"
+#include "src/traceconv/utils.h"
+
+namespace perfetto {
+namespace trace_to_text {
+namespace {
+
+using ::perfetto::trace_processor::Iterator;
+using ::perfetto::trace_processor::TraceProcessor;
+
+constexpr char kCpuSamplesQuery[] = R"(
+  SELECT
+    s.callsite_id,
+    t.tid,
+    p.pid,
+    COALESCE(t.name, p.name) AS name,
+    COUNT(*) AS weight
+  FROM (
+    SELECT callsite_id, utid FROM perf_sample WHERE callsite_id IS NOT NULL
+    UNION ALL
+    SELECT callsite_id, utid FROM cpu_profile_stack_sample
+  ) s
+  LEFT JOIN thread t USING (utid)
+  LEFT JOIN process p USING (upid)
+  GROUP BY s.callsite_id, s.utid
+)";
+
+constexpr char kHeapAllocationsQuery[] = R"(
+  SELECT
+    a.callsite_id,
+    NULL AS tid,
+    p.pid,
+    p.name,
+    SUM(a.size) AS weight
+  FROM heap_profile_allocation a
+  LEFT JOIN process p USING (upid)
+  GROUP BY a.callsite_id, a.upid
+  HAVING weight > 0
+)";
+
+struct Callsite {
+  std::optional<int64_t> parent_id;
+  std::string frames;
+};
+
+// Frames are separated by ';' and stacks are terminated by a space followed by
+// the weight: make sure that frame names do not contain the separators.
+std::string SanitizeFrameName(std::string name) {
+  for (char& c : name) {
+    if (c == ';') {
+      c = ':';
+    } else if (c == '\n' || c == '\r') {
+      c = ' ';
+    }
+  }
+  return name;
+}
+
+std::unique_ptr<TraceProcessor> LoadTrace(std::istream* input) {
+  trace_processor::Config config;
+  std::unique_ptr<TraceProcessor> tp = TraceProcessor::CreateInstance(config);
+  if (!ReadTraceUnfinalized(tp.get(), input)) {
+    return nullptr;
+  }
+  if (auto status = tp->NotifyEndOfFile(); !status.ok()) {
+    return nullptr;
+  }
+  return tp;
+}
+
+std::unordered_map<int64_t, Callsite> GetFoldedCallsites(TraceProcessor& tp) {
+  auto profile_callsites = GetCallsites(&tp);
+  PERFETTO_CHECK(profile_callsites);
+  std::unordered_map<int64_t, Callsite> callsites;
+  for (auto& [id, profile_callsite] : *profile_callsites) {
+    Callsite callsite;
+    callsite.parent_id = profile_callsite.parent_id;
+    std::vector<std::string> names;
+    for (const ProfileSymbol& symbol : profile_callsite.symbols) {
+      names.push_back(SanitizeFrameName(symbol.name));
+    }
+    if (names.empty()) {
+      names.push_back(SanitizeFrameName(profile_callsite.function_name));
+    }
+    for (std::string& name : names) {
+      if (!name.empty()) {
+        continue;
+      }
+      // Keep unsymbolized frames distinguishable by the module they belong
+      // to, as done by stackcollapse-perf.pl.
+      name = profile_callsite.mapping_name.empty()
+                 ? "[unknown]"
+                 : "[" + SanitizeFrameName(profile_callsite.mapping_name) + "]";
+    }
+    callsite.frames = base::Join(names, ";");
+    callsites.emplace(id, std::move(callsite));
+  }
+  return callsites;
+}
+
+// Returns the frames of the stack ending at |callsite_id|, root first.
+std::string GetStack(const std::unordered_map<int64_t, Callsite>& callsites,
+                     int64_t callsite_id) {
+  std::vector<const std::string*> frames;
+  for (std::optional<int64_t> id = callsite_id; id;) {
+    auto it = callsites.find(*id);
+    if (it == callsites.end()) {
+      break;
+    }
+    frames.push_back(&it->second.frames);
+    id = it->second.parent_id;
+  }
+  std::string stack;
+  for (auto it = frames.rbegin(); it != frames.rend(); ++it) {
+    if (!stack.empty()) {
+      stack += ';';
+    }
+    stack += **it;
+  }
+  return stack;
+}
+
+// Returns the root frame identifying the thread or process of a stack. The
+// format matches the one of `stackcollapse-perf.pl --pid --tid` so that the
+// output can be imported back in trace processor.
+std::string GetRootFrame(Iterator& it) {
+  std::string name = it.Get(3).is_null() || it.Get(3).AsString()[0] == '\0'
+                         ? "[unknown]"
+                         : SanitizeFrameName(it.Get(3).AsString());
+  std::optional<int64_t> tid;
+  if (!it.Get(1).is_null()) {
+    tid = it.Get(1).AsLong();
+  }
+  std::optional<int64_t> pid;
+  if (!it.Get(2).is_null()) {
+    pid = it.Get(2).AsLong();
+  }
+  if (pid && tid) {
+    return name + "-" + std::to_string(*pid) + "/" + std::to_string(*tid);
+  }
+  if (pid || tid) {
+    return name + "-" + std::to_string(pid ? *pid : *tid);
+  }
+  return name;
+}
+
+}  // namespace
+
+bool TraceToFoldedStacks(std::istream* input, std::ostream* output, bool heap) {
+  std::unique_ptr<TraceProcessor> tp = LoadTrace(input);
+  if (!tp) {
+    return false;
+  }
+
+  std::unordered_map<int64_t, Callsite> callsites = GetFoldedCallsites(*tp);
+
+  // Different callsites can end up with the same folded stack (e.g. if they
+  // only differ by the mapping of unsymbolized frames): merge them.
+  std::map<std::string, int64_t> stacks;
+  Iterator it =
+      tp->ExecuteQuery(heap ? kHeapAllocationsQuery : kCpuSamplesQuery);
+  while (it.Next()) {
+    std::string stack = GetRootFrame(it);
+    stack += ';';
+    stack += GetStack(callsites, it.Get(0).AsLong());
+    stacks[std::move(stack)] += it.Get(4).AsLong();
+  }
+  if (!it.Status().ok()) {
+    PERFETTO_ELOG("Failed to query the callstacks: %s",
+                  it.Status().c_message());
+    return false;
+  }
+  if (stacks.empty()) {
+    PERFETTO_ELOG("No %s found in the trace.",
+                  heap ? "heap profile allocations" : "CPU samples");
+    return false;
+  }
+
+  for (const auto& [stack, weight] : stacks) {
+    *output << stack << ' ' << weight << '\n';
+  }
+  return true;
+}
+
+}  // namespace trace_to_text
+}  // namespace perfetto
@@ -0,0 +1,40 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACECONV_TRACE_TO_FOLDED_H_
+#define SRC_TRACECONV_TRACE_TO_FOLDED_H_
+
+#include <iostream>
+
+namespace perfetto {
+namespace trace_to_text {
+
+// Exports the callstacks in the trace as folded stacks, the text format used
+// by Brendan Gregg's flamegraph.pl. Each line contains the frames of a stack,
+// root first and separated by ';', followed by its weight. The root frame
+// identifies the thread (or the process, for heap profiles) the stack belongs
+// to.
+//
+// If |heap| is false, the CPU samples (perf_sample and
+// cpu_profile_stack_sample) are exported and the weight is the number of
+// samples. Otherwise the heapprofd allocations are exported and the weight is
+// the number of bytes allocated but not freed.
+bool TraceToFoldedStacks(std::istream* input, std::ostream* output, bool heap);
+
+}  // namespace trace_to_text
+}  // namespace perfetto
+
+#endif  // SRC_TRACECONV_TRACE_TO_FOLDED_H_
@@ -0,0 +1,551 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/traceconv/trace_to_otlp.h"
+
+#include <algorithm>
+#include <cinttypes>
+#include <cmath>
+#include <cstdint>
+#include <istream>
+#include <map>
+#include <memory>
+#include <optional>
+#include <ostream>
+#include <sstream>
+#include <string>
+#include <unordered_map>
+#include <utility>
+#include <vector>
+
+#include "perfetto/base/logging.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "perfetto/ext/base/string_view.h"
+#include "perfetto/trace_processor/basic_types.h"
+#include "perfetto/trace_processor/trace_processor.h"
+#include "src/traceconv/utils.h"
+
+namespace perfetto {
+namespace trace_to_text {
+namespace {
+
+using ::perfetto::trace_processor::Iterator;
+using ::perfetto::trace_processor::SqlValue;
+using ::perfetto::trace_processor::TraceProcessor;
+
+// The slices of thread and process tracks, parents first. Incomplete slices
+// last until the end of the trace.
+constexpr char kSlicesQuery[] = R"(
+  SELECT
+    s.id,
+    s.parent_id,
+    s.name,
+    s.category,
+    COALESCE(TO_REALTIME(s.ts), s.ts),
+    IIF(s.dur >= 0, s.dur, MAX((SELECT end_ts FROM trace_bounds) - s.ts, 0)),
+    s.arg_set_id,
+    COALESCE(t.upid, pt.upid),
+    t.tid,
+    t.name
+  FROM slice s
+  LEFT JOIN thread_track tt ON s.track_id = tt.id
+  LEFT JOIN thread t ON tt.utid = t.utid
+  LEFT JOIN process_track pt ON s.track_id = pt.id
+  WHERE tt.id IS NOT NULL OR pt.id IS NOT NULL
+  ORDER BY s.ts, s.depth
+)";
+
+constexpr char kArgsQuery[] = R"(
+  SELECT arg_set_id, key, value_type, int_value, string_value, real_value
+  FROM args
+  WHERE arg_set_id IN (
+    SELECT arg_set_id FROM slice
+    UNION
+    SELECT arg_set_id FROM process
+  )
+  ORDER BY arg_set_id, id
+)";
+
+// The names of the Span.SpanKind values, indexed by value.
+constexpr const char* kSpanKinds[] = {
+    "SPAN_KIND_UNSPECIFIED", "SPAN_KIND_INTERNAL", "SPAN_KIND_SERVER",
+    "SPAN_KIND_CLIENT",      "SPAN_KIND_PRODUCER", "SPAN_KIND_CONSUMER",
+};
+constexpr uint32_t kSpanKindInternal = 1;
+
+// The names of the Status.StatusCode values, indexed by value.
+constexpr const char* kStatusCodes[] = {
+    "STATUS_CODE_UNSET",
+    "STATUS_CODE_OK",
+    "STATUS_CODE_ERROR",
+};
+
+template <size_t N>
+std::optional<uint32_t> FindEnumValue(const char* const (&names)[N],
+                                      const std::string& name) {
+  for (uint32_t i = 0; i < N; ++i) {
+    if (name == names[i]) {
+      return i;
+    }
+  }
+  return std::nullopt;
+}
+
+struct Arg {
+  std::string key;
+  // The value encoded as an OTLP AnyValue JSON object.
+  std::string any_value;
+  // The value of string args.
+  std::optional<std::string> string_value;
+};
+
+struct SpanIds {
+  std::string trace_id;
+  std::string span_id;
+};
+
+struct Slice {
+  int64_t id = 0;
+  std::optional<int64_t> parent_id;
+  std::string name;
+  std::string category;
+  int64_t start = 0;
+  int64_t dur = 0;
+  std::optional<int64_t> arg_set_id;
+  std::optional<int64_t> upid;
+  std::optional<int64_t> tid;
+  std::optional<std::string> thread_name;
+};
+
+std::string ToAnyValue(Iterator& it) {
+  std::string type = it.Get(2).AsString();
+  std::ostringstream value;
+  if (type == "int" || type == "uint" || type == "pointer") {
+    value << "{\"intValue\":\"" << it.Get(3).AsLong() << "\"}";
+  } else if (type == "bool") {
+    value << "{\"boolValue\":" << (it.Get(3).AsLong() ? "true" : "false")
+          << '}';
+  } else if (type == "real" && std::isfinite(it.Get(5).AsDouble())) {
+    base::StackString<32> real("%.17g", it.Get(5).AsDouble());
+    value << "{\"doubleValue\":" << real.c_str() << '}';
+  } else if (type == "string" || type == "json") {
+    value << "{\"stringValue\":";
+    WriteJsonString(&value, it.Get(4).is_null() ? "" : it.Get(4).AsString());
+    value << '}';
+  } else {
+    // Null values, and NaN and infinities which JSON does not support.
+    value << "{}";
+  }
+  return value.str();
+}
+
+void WriteAttribute(std::ostream* output,
+                    const std::string& key,
+                    const std::string& any_value) {
+  *output << "{\"key\":";
+  WriteJsonString(output, key);
+  *output << ",\"value\":" << any_value << '}';
+}
+
+std::string StringValue(const std::string& str) {
+  std::ostringstream value;
+  value << "{\"stringValue\":";
+  WriteJsonString(&value, str);
+  value << '}';
+  return value.str();
+}
+
+class OtlpExporter {
+ public:
+  explicit OtlpExporter(TraceProcessor* tp) : tp_(tp) {}
+
+  bool Export(std::ostream* output);
+
+ private:
+  bool LoadArgs();
+  bool LoadResources();
+  bool LoadSlices();
+  bool LoadFlows();
+  void AssignIds();
+  std::string ToSpanJson(const Slice& slice);
+  const std::vector<Arg>& GetArgs(std::optional<int64_t> arg_set_id) const;
+
+  TraceProcessor* const tp_;
+  std::unordered_map<int64_t, std::vector<Arg>> args_;
+  // The JSON of the attributes of the resource of each process.
+  std::map<int64_t, std::string> resources_;
+  std::vector<Slice> slices_;
+  std::unordered_map<int64_t, SpanIds> span_ids_;
+  // The slices with flows to each slice.
+  std::unordered_map<int64_t, std::vector<int64_t>> flows_in_;
+};
+
+bool OtlpExporter::Export(std::ostream* output) {
+  if (!LoadArgs() || !LoadResources() || !LoadSlices() || !LoadFlows()) {
+    return false;
+  }
+  if (slices_.empty()) {
+    PERFETTO_ELOG("No thread or process slices found in the trace.");
+    return false;
+  }
+  AssignIds();
+
+  // Spans grouped by process (-1 if unknown) and instrumentation scope.
+  std::map<int64_t, std::map<std::string, std::vector<std::string>>> spans;
+  for (const Slice& slice : slices_) {
+    spans[slice.upid.value_or(-1)][slice.category].push_back(
+        ToSpanJson(slice));
+  }
+
+  *output << "{\"resourceSpans\":[";
+  bool first_resource = true;
+  for (const auto& [upid, scopes] : spans) {
+    auto resource_it = resources_.find(upid);
+    *output << (first_resource ? "" : ",") << "{\"resource\":{\"attributes\":"
+            << (resource_it == resources_.end()
+                    ? "[{\"key\":\"service.name\",\"value\":"
+                      "{\"stringValue\":\"unknown_service\"}}]"
+                    : resource_it->second)
+            << "},\"scopeSpans\":[";
+    first_resource = false;
+    bool first_scope = true;
+    for (const auto& [scope, scope_spans] : scopes) {
+      *output << (first_scope ? "" : ",") << "{\"scope\":{\"name\":";
+      WriteJsonString(output, scope);
+      *output << "},\"spans\":[";
+      first_scope = false;
+      for (size_t i = 0; i < scope_spans.size(); ++i) {
+        *output << (i == 0 ? "" : ",") << scope_spans[i];
+      }
+      *output << "]}";
+    }
+    *output << "]}";
+  }
+  *output << "]}\n";
+  return true;
+}
+
+bool OtlpExporter::LoadArgs() {
+  Iterator it = tp_->ExecuteQuery(kArgsQuery);
+  while (it.Next()) {
+    Arg arg;
+    arg.key = it.Get(1).AsString();
+    arg.any_value = ToAnyValue(it);
+    if (!it.Get(4).is_null()) {
+      arg.string_value = it.Get(4).AsString();
+    }
+    args_[it.Get(0).AsLong()].push_back(std::move(arg));
+  }
+  if (!it.Status().ok()) {
+    PERFETTO_ELOG("Failed to query the args: %s", it.Status().c_message());
+    return false;
+  }
+  return true;
+}
+
+const std::vector<Arg>& OtlpExporter::GetArgs(
+    std::optional<int64_t> arg_set_id) const {
+  static const std::vector<Arg>* kEmpty = new std::vector<Arg>();
+  if (!arg_set_id) {
+    return *kEmpty;
+  }
+  auto it = args_.find(*arg_set_id);
+  return it == args_.end() ? *kEmpty : it->second;
+}
+
+// The resource attributes of processes imported from OTLP traces are stored
+// in the "resource." args and are preserved. The others get the service.name
+// and process.pid attributes from the process.
+bool OtlpExporter::LoadResources() {
+  Iterator it =
+      tp_->ExecuteQuery("SELECT upid, pid, name, arg_set_id FROM process");
+  while (it.Next()) {
+    std::optional<int64_t> arg_set_id;
+    if (!it.Get(3).is_null()) {
+      arg_set_id = it.Get(3).AsLong();
+    }
+    std::ostringstream attributes;
+    attributes << '[';
+    bool has_service_name = false;
+    bool has_pid = false;
+    bool first = true;
+    for (const Arg& arg : GetArgs(arg_set_id)) {
+      base::StringView key(arg.key);
+      if (!key.StartsWith("resource.")) {
+        continue;
+      }
+      std::string attribute_key = key.substr(strlen("resource.")).ToStdString();
+      has_service_name |= attribute_key == "service.name";
+      has_pid |= attribute_key == "process.pid";
+      attributes << (first ? "" : ",");
+      WriteAttribute(&attributes, attribute_key, arg.any_value);
+      first = false;
+    }
+    if (!has_service_name) {
+      attributes << (first ? "" : ",");
+      WriteAttribute(&attributes, "service.name",
+                     StringValue(it.Get(2).is_null() ? "unknown_service"
+                                                     : it.Get(2).AsString()));
+      first = false;
+    }
+    if (!has_pid && !it.Get(1).is_null() && it.Get(1).AsLong() != 0) {
+      attributes << ",";
+      WriteAttribute(&attributes, "process.pid",
+                     "{\"intValue\":\"" + std::to_string(it.Get(1).AsLong()) +
+                         "\"}");
+    }
+    attributes << ']';
+    resources_[it.Get(0).AsLong()] = attributes.str();
+  }
+  if (!it.Status().ok()) {
+    PERFETTO_ELOG("Failed to query the processes: %s",
+                  it.Status().c_message());
+    return false;
+  }
+  return true;
+}
+
+bool OtlpExporter::LoadSlices() {
+  auto get_long = [](const SqlValue& value) -> std::optional<int64_t> {
+    return value.is_null() ? std::nullopt : std::make_optional(value.AsLong());
+  };
+  Iterator it = tp_->ExecuteQuery(kSlicesQuery);
+  while (it.Next()) {
+    Slice slice;
+    slice.id = it.Get(0).AsLong();
+    slice.parent_id = get_long(it.Get(1));
+    slice.name = it.Get(2).is_null() ? "" : it.Get(2).AsString();
+    slice.category = it.Get(3).is_null() ? "" : it.Get(3).AsString();
+    slice.start = std::max<int64_t>(it.Get(4).AsLong(), 0);
+    slice.dur = it.Get(5).AsLong();
+    slice.arg_set_id = get_long(it.Get(6));
+    slice.upid = get_long(it.Get(7));
+    slice.tid = get_long(it.Get(8));
+    if (!it.Get(9).is_null()) {
+      slice.thread_name = it.Get(9).AsString();
+    }
+    slices_.push_back(std::move(slice));
+  }
+  if (!it.Status().ok()) {
+    PERFETTO_ELOG("Failed to query the slices: %s", it.Status().c_message());
+    return false;
+  }
+  return true;
+}
+
+bool OtlpExporter::LoadFlows() {
+  Iterator it = tp_->ExecuteQuery("SELECT slice_out, slice_in FROM flow");
+  while (it.Next()) {
+    flows_in_[it.Get(1).AsLong()].push_back(it.Get(0).AsLong());
+  }
+  if (!it.Status().ok()) {
+    PERFETTO_ELOG("Failed to query the flows: %s", it.Status().c_message());
+    return false;
+  }
+  return true;
+}
+
+// Slices imported from OTLP traces keep their ids. The other slices get a
+// span id derived from the slice id and inherit the trace id of their parent;
+// each root slice starts a new trace.
+void OtlpExporter::AssignIds() {
+  for (const Slice& slice : slices_) {
+    SpanIds ids;
+    for (const Arg& arg : GetArgs(slice.arg_set_id)) {
+      if (arg.key == "otlp.trace_id" && arg.string_value) {
+        ids.trace_id = *arg.string_value;
+      } else if (arg.key == "otlp.span_id" && arg.string_value) {
+        ids.span_id = *arg.string_value;
+      }
+    }
+    uint64_t id = static_cast<uint64_t>(slice.id) + 1;
+    if (ids.span_id.empty()) {
+      ids.span_id = base::StackString<17>("%016" PRIx64, id).ToStdString();
+    }
+    if (ids.trace_id.empty() && slice.parent_id) {
+      auto parent_it = span_ids_.find(*slice.parent_id);
+      if (parent_it != span_ids_.end()) {
+        ids.trace_id = parent_it->second.trace_id;
+      }
+    }
+    if (ids.trace_id.empty()) {
+      ids.trace_id = base::StackString<33>("%032" PRIx64, id).ToStdString();
+    }
+    span_ids_.emplace(slice.id, std::move(ids));
+  }
+}
+
+std::string OtlpExporter::ToSpanJson(const Slice& slice) {
+  const SpanIds& ids = span_ids_[slice.id];
+  std::string parent_span_id;
+  if (slice.parent_id) {
+    auto parent_it = span_ids_.find(*slice.parent_id);
+    if (parent_it != span_ids_.end()) {
+      parent_span_id = parent_it->second.span_id;
+    }
+  }
+
+  std::string trace_state;
+  uint32_t kind = kSpanKindInternal;
+  std::optional<uint32_t> status_code;
+  std::optional<std::string> status_message;
+  std::ostringstream attributes;
+  bool first_attribute = true;
+  auto add_attribute = [&](const std::string& key, const std::string& value) {
+    attributes << (first_attribute ? "" : ",");
+    WriteAttribute(&attributes, key, value);
+    first_attribute = false;
+  };
+  // The links of slices imported from OTLP traces, by index.
+  std::map<uint32_t, SpanIds> links;
+  std::map<uint32_t, std::vector<std::pair<std::string, std::string>>>
+      link_attributes;
+  bool has_thread_id = false;
+
+  for (const Arg& arg : GetArgs(slice.arg_set_id)) {
+    base::StringView key(arg.key);
+    const std::string value = arg.string_value.value_or("");
+    if (key == "otlp.parent_span_id" && parent_span_id.empty()) {
+      // The parent of the span might not have been imported.
+      parent_span_id = value;
+    } else if (key == "otlp.trace_state") {
+      trace_state = value;
+    } else if (key == "otlp.kind") {
+      kind = FindEnumValue(kSpanKinds, value).value_or(kSpanKindInternal);
+    } else if (key == "otlp.status.code") {
+      status_code = FindEnumValue(kStatusCodes, value);
+    } else if (key == "otlp.status.message") {
+      status_message = value;
+    } else if (key.StartsWith("otlp.links[")) {
+      // otlp.links[<index>].<field>
+      base::StringView rest = key.substr(strlen("otlp.links["));
+      size_t end = rest.find(']');
+      std::optional<uint32_t> index =
+          base::StringToUInt32(rest.substr(0, end).ToStdString());
+      if (end == base::StringView::npos || !index) {
+        continue;
+      }
+      base::StringView field = rest.substr(end + 1);
+      if (field == ".trace_id") {
+        links[*index].trace_id = value;
+      } else if (field == ".span_id") {
+        links[*index].span_id = value;
+      } else if (field.StartsWith(".attributes.")) {
+        link_attributes[*index].emplace_back(
+            field.substr(strlen(".attributes.")).ToStdString(),
+            arg.any_value);
+      }
+    } else if (key.StartsWith("otlp.")) {
+      // The ids and the scope are already part of the output.
+    } else {
+      std::string attribute_key =
+          key.StartsWith("attributes.")
+              ? key.substr(strlen("attributes.")).ToStdString()
+              : arg.key;
+      has_thread_id |= attribute_key == "thread.id";
+      add_attribute(attribute_key, arg.any_value);
+    }
+  }
+  if (slice.tid && !has_thread_id) {
+    add_attribute("thread.id",
+                  "{\"intValue\":\"" + std::to_string(*slice.tid) + "\"}");
+    if (slice.thread_name) {
+      add_attribute("thread.name", StringValue(*slice.thread_name));
+    }
+  }
+
+  // The flows of other slices become links, unless they are the flows
+  // between parent and child spans created by the OTLP importer.
+  if (links.empty()) {
+    uint32_t index = 0;
+    for (int64_t flow_out : flows_in_[slice.id]) {
+      auto out_it = span_ids_.find(flow_out);
+      if (out_it == span_ids_.end() ||
+          out_it->second.span_id == parent_span_id) {
+        continue;
+      }
+      links[index++] = out_it->second;
+    }
+  }
+
+  std::ostringstream span;
+  span << "{\"traceId\":\"" << ids.trace_id << "\",\"spanId\":\""
+       << ids.span_id << '"';
+  if (!trace_state.empty()) {
+    span << ",\"traceState\":";
+    WriteJsonString(&span, trace_state);
+  }
+  if (!parent_span_id.empty()) {
+    span << ",\"parentSpanId\":\"" << parent_span_id << '"';
+  }
+  span << ",\"name\":";
+  WriteJsonString(&span, slice.name);
+  span << ",\"kind\":" << kind << ",\"startTimeUnixNano\":\"" << slice.start
+       << "\",\"endTimeUnixNano\":\"" << slice.start + slice.dur
+       << "\",\"attributes\":[" << attributes.str() << ']';
+  if (!links.empty()) {
+    span << ",\"links\":[";
+    bool first_link = true;
+    for (const auto& [index, link] : links) {
+      span << (first_link ? "" : ",") << "{\"traceId\":\"" << link.trace_id
+           << "\",\"spanId\":\"" << link.span_id << "\",\"attributes\":[";
+      bool first_link_attribute = true;
+      for (const auto& [key, value] : link_attributes[index]) {
+        span << (first_link_attribute ? "" : ",");
+        WriteAttribute(&span, key, value);
+        first_link_attribute = false;
+      }
+      span << "]}";
+      first_link = false;
+    }
+    span << ']';
+  }
+  if (status_code || status_message) {
+    span << ",\"status\":{\"code\":" << status_code.value_or(0);
+    if (status_message) {
+      span << ",\"message\":";
+      WriteJsonString(&span, *status_message);
+    }
+    span << '}';
+  }
+  span << '}';
+  return span.str();
+}
+
+std::unique_ptr<TraceProcessor> LoadTrace(std::istream* input) {
+  trace_processor::Config config;
+  std::unique_ptr<TraceProcessor> tp = TraceProcessor::CreateInstance(config);
+  if (!ReadTraceUnfinalized(tp.get(), input)) {
+    return nullptr;
+  }
+  if (auto status = tp->NotifyEndOfFile(); !status.ok()) {
+    return nullptr;
+  }
+  return tp;
+}
+
+}  // namespace
+
+bool TraceToOtlp(std::istream* input, std::ostream* output) {
+  std::unique_ptr<TraceProcessor> tp = LoadTrace(input);
+  if (!tp) {
+    return false;
+  }
+  return OtlpExporter(tp.get()).Export(output);
+}
+
+}  // namespace trace_to_text
+}  // namespace perfetto
@@ -0,0 +1,39 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACECONV_TRACE_TO_OTLP_H_
+#define SRC_TRACECONV_TRACE_TO_OTLP_H_
+
+#include <iostream>
+
+namespace perfetto {
+namespace trace_to_text {
+
+// Exports the slices of the thread and process tracks (e.g. the ones emitted
+// by the track event SDK) as OpenTelemetry spans, in the OTLP JSON file format
+// (a TracesData message). See
+// https://opentelemetry.io/docs/specs/otlp/#json-protobuf-encoding
+//
+// Each process becomes a resource and each slice a span, whose parent is the
+// parent slice. The trace and span ids of slices imported from OTLP traces
+// (the otlp.trace_id and otlp.span_id args) are preserved, while the other
+// slices get ids derived from the slice ids. Flows become span links.
+bool TraceToOtlp(std::istream* input, std::ostream* output);
+
+}  // namespace trace_to_text
+}  // namespace perfetto
+
+#endif  // SRC_TRACECONV_TRACE_TO_OTLP_H_
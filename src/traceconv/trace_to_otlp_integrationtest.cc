@@ -0,0 +1,157 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/traceconv/trace_to_otlp.h"
+
+#include <json/reader.h>
+#include <json/value.h>
+
+#include <memory>
+#include <sstream>
+#include <string>
+
+#include "perfetto/base/build_config.h"
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto {
+namespace trace_to_text {
+namespace {
+
+Json::Value ConvertToOtlp(const std::string& trace) {
+  std::istringstream input(trace);
+  std::ostringstream output;
+  EXPECT_TRUE(TraceToOtlp(&input, &output));
+
+  Json::Value root;
+  std::string errors;
+  std::unique_ptr<Json::CharReader> reader(
+      Json::CharReaderBuilder().newCharReader());
+  std::string json = output.str();
+  EXPECT_TRUE(reader->parse(json.data(), json.data() + json.size(), &root,
+                            &errors))
+      << errors;
+  return root;
+}
+
+// Returns the value of the attribute |key| as a string, or an empty string if
+// there is no such attribute.
+std::string GetAttribute(const Json::Value& attributes,
+                         const std::string& key) {
+  for (const Json::Value& attribute : attributes) {
+    if (attribute["key"].asString() != key) {
+      continue;
+    }
+    const Json::Value& value = attribute["value"];
+    if (value.isMember("stringValue")) {
+      return value["stringValue"].asString();
+    }
+    if (value.isMember("intValue")) {
+      return value["intValue"].asString();
+    }
+    return value.toStyledString();
+  }
+  return "";
+}
+
+class TraceToOtlpTest : public ::testing::Test {
+ public:
+  void SetUp() override {
+#if PERFETTO_BUILDFLAG(PERFETTO_OS_ANDROID)
+    GTEST_SKIP() << "do not run traceconv tests on Android target";
+#endif
+  }
+};
+
+TEST_F(TraceToOtlpTest, ThreadSlices) {
+  Json::Value root = ConvertToOtlp(
+      "# tracer: nop\n"
+      "  app-10    ( 10) [000] .... 1.000000: tracing_mark_write: B|10|outer\n"
+      "  app-10    ( 10) [000] .... 1.000010: tracing_mark_write: B|10|inner\n"
+      "  app-10    ( 10) [000] .... 1.000020: tracing_mark_write: E|10\n"
+      "  app-10    ( 10) [000] .... 1.000050: tracing_mark_write: E|10\n");
+
+  ASSERT_EQ(root["resourceSpans"].size(), 1u);
+  const Json::Value& resource_spans = root["resourceSpans"][0];
+  EXPECT_EQ(
+      GetAttribute(resource_spans["resource"]["attributes"], "process.pid"),
+      "10");
+  ASSERT_EQ(resource_spans["scopeSpans"].size(), 1u);
+  const Json::Value& spans = resource_spans["scopeSpans"][0]["spans"];
+  ASSERT_EQ(spans.size(), 2u);
+
+  const Json::Value& outer = spans[0];
+  EXPECT_EQ(outer["name"].asString(), "outer");
+  EXPECT_EQ(outer["traceId"].asString().size(), 32u);
+  EXPECT_EQ(outer["spanId"].asString().size(), 16u);
+  EXPECT_FALSE(outer.isMember("parentSpanId"));
+  EXPECT_EQ(outer["startTimeUnixNano"].asString(), "1000000000");
+  EXPECT_EQ(outer["endTimeUnixNano"].asString(), "1000050000");
+  EXPECT_EQ(GetAttribute(outer["attributes"], "thread.id"), "10");
+  EXPECT_EQ(GetAttribute(outer["attributes"], "thread.name"), "app");
+
+  const Json::Value& inner = spans[1];
+  EXPECT_EQ(inner["name"].asString(), "inner");
+  EXPECT_EQ(inner["traceId"], outer["traceId"]);
+  EXPECT_EQ(inner["parentSpanId"], outer["spanId"]);
+  EXPECT_NE(inner["spanId"], outer["spanId"]);
+}
+
+TEST_F(TraceToOtlpTest, PreservesOtlpIds) {
+  Json::Value root = ConvertToOtlp(R"({"resourceSpans":[{
+    "resource":{"attributes":[
+      {"key":"service.name","value":{"stringValue":"frontend"}}]},
+    "scopeSpans":[{"scope":{"name":"http"},"spans":[
+      {"traceId":"0102030405060708090a0b0c0d0e0f10",
+       "spanId":"1112131415161718","name":"GET /",
+       "kind":2,"startTimeUnixNano":"1000","endTimeUnixNano":"2000",
+       "attributes":[{"key":"http.status_code","value":{"intValue":"200"}}],
+       "status":{"code":2,"message":"failed"}},
+      {"traceId":"0102030405060708090a0b0c0d0e0f10",
+       "spanId":"2122232425262728","parentSpanId":"1112131415161718",
+       "name":"query","kind":3,
+       "startTimeUnixNano":"1100","endTimeUnixNano":"1900"}]}]}]})");
+
+  ASSERT_EQ(root["resourceSpans"].size(), 1u);
+  const Json::Value& resource_spans = root["resourceSpans"][0];
+  EXPECT_EQ(
+      GetAttribute(resource_spans["resource"]["attributes"], "service.name"),
+      "frontend");
+  ASSERT_EQ(resource_spans["scopeSpans"].size(), 1u);
+  const Json::Value& scope_spans = resource_spans["scopeSpans"][0];
+  EXPECT_EQ(scope_spans["scope"]["name"].asString(), "http");
+  const Json::Value& spans = scope_spans["spans"];
+  ASSERT_EQ(spans.size(), 2u);
+
+  const Json::Value& server = spans[0];
+  EXPECT_EQ(server["traceId"].asString(), "0102030405060708090a0b0c0d0e0f10");
+  EXPECT_EQ(server["spanId"].asString(), "1112131415161718");
+  EXPECT_EQ(server["kind"].asInt(), 2);
+  EXPECT_EQ(server["startTimeUnixNano"].asString(), "1000");
+  EXPECT_EQ(server["endTimeUnixNano"].asString(), "2000");
+  EXPECT_EQ(GetAttribute(server["attributes"], "http.status_code"), "200");
+  EXPECT_EQ(server["status"]["code"].asInt(), 2);
+  EXPECT_EQ(server["status"]["message"].asString(), "failed");
+
+  const Json::Value& client = spans[1];
+  EXPECT_EQ(client["spanId"].asString(), "2122232425262728");
+  EXPECT_EQ(client["parentSpanId"].asString(), "1112131415161718");
+  EXPECT_EQ(client["kind"].asInt(), 3);
+  EXPECT_FALSE(client.isMember("links"));
+}
+
+}  // namespace
+}  // namespace trace_to_text
+}  // namespace perfetto
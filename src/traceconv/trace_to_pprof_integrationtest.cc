@@ -16,6 +16,7 @@
 
 #include "test/gtest_and_gmock.h"
 
+#include <cstdint>
 #include <fstream>
 #include <ostream>
 #include <sstream>
@@ -25,15 +26,44 @@
 #include "perfetto/base/logging.h"
 #include "perfetto/ext/base/file_utils.h"
 #include "perfetto/ext/base/string_utils.h"
+#include "perfetto/ext/base/utils.h"
+#include "perfetto/protozero/scattered_heap_buffer.h"
 #include "src/base/test/utils.h"
 #include "src/traceconv/pprof_reader.h"
 #include "src/traceconv/trace_to_profile.h"
 
+#include "protos/perfetto/common/builtin_clock.pbzero.h"
+#include "protos/perfetto/trace/clock_snapshot.pbzero.h"
+#include "protos/perfetto/trace/profiling/profile_common.pbzero.h"
+#include "protos/perfetto/trace/profiling/profile_packet.pbzero.h"
+#include "protos/perfetto/trace/ps/process_tree.pbzero.h"
+#include "protos/perfetto/trace/trace.pbzero.h"
+#include "protos/perfetto/trace/trace_packet.pbzero.h"
+
 namespace perfetto {
 namespace {
 
 using testing::Contains;
 
+// Reads the only profile written by a traceconv conversion, given the
+// output of the conversion, and deletes it.
+pprof::PprofProfileReader ReadOnlyProfile(const std::string& conv_output) {
+  auto conv_stdout = base::SplitString(conv_output, " ");
+  PERFETTO_CHECK(!conv_stdout.empty());
+  std::string out_dirname = base::TrimWhitespace(conv_stdout.back());
+  std::vector<std::string> filenames;
+  base::ListFilesRecursive(out_dirname, filenames);
+  // assumption: all test inputs contain exactly one profile
+  PERFETTO_CHECK(filenames.size() == 1);
+  std::string profile_path = out_dirname + "/" + filenames[0];
+
+  // read in the profile contents and then clean up the temp files
+  pprof::PprofProfileReader pprof_reader(profile_path);
+  unlink(profile_path.c_str());
+  PERFETTO_CHECK(base::Rmdir(out_dirname));
+  return pprof_reader;
+}
+
 pprof::PprofProfileReader ConvertTraceToPprof(
     const std::string& input_file_name) {
   const std::string trace_file = base::GetTestDataPath(input_file_name);
@@ -46,21 +76,115 @@ pprof::PprofProfileReader ConvertTraceToPprof(
   trace_to_text::TraceToJavaHeapProfile(&file_istream, &os, /*pid=*/0,
                                         /*timestamps=*/{},
                                         /*annotate_frames=*/false);
+  return ReadOnlyProfile(ss.str());
+}
 
-  auto conv_stdout = base::SplitString(ss.str(), " ");
-  PERFETTO_CHECK(!conv_stdout.empty());
-  std::string out_dirname = base::TrimWhitespace(conv_stdout.back());
-  std::vector<std::string> filenames;
-  base::ListFilesRecursive(out_dirname, filenames);
-  // assumption: all test inputs contain exactly one profile
-  PERFETTO_CHECK(filenames.size() == 1);
-  std::string profile_path = out_dirname + "/" + filenames[0];
+// The frames of the heap profiles of CreateHeapProfileTrace().
+enum HeapFrame : uint64_t { kMain = 1, kGrow, kShrink };
 
-  // read in the profile contents and then clean up the temp files
-  pprof::PprofProfileReader pprof_reader(profile_path);
-  unlink(profile_path.c_str());
-  PERFETTO_CHECK(base::Rmdir(out_dirname));
-  return pprof_reader;
+struct HeapSample {
+  HeapFrame leaf;
+  uint64_t self_allocated;
+  uint64_t alloc_count;
+  uint64_t self_freed;
+  uint64_t free_count;
+};
+
+struct HeapDump {
+  uint64_t pid;
+  uint64_t ts;
+  // The callstack of every sample is main -> |leaf|.
+  std::vector<HeapSample> samples;
+};
+
+// Returns a trace with a libc.malloc profile packet for each of |dumps|.
+std::string CreateHeapProfileTrace(const std::vector<HeapDump>& dumps) {
+  protozero::HeapBuffered<protos::pbzero::Trace> trace;
+
+  auto* process_tree = trace->add_packet()->set_process_tree();
+  for (uint64_t pid : {2, 3}) {
+    auto* process = process_tree->add_processes();
+    process->set_pid(static_cast<int32_t>(pid));
+    process->set_ppid(1);
+    process->add_cmdline("/system/bin/foo");
+  }
+
+  // The timestamps of the dumps are in the MONOTONIC_COARSE clock.
+  auto* clock_snapshot = trace->add_packet()->set_clock_snapshot();
+  auto* boottime = clock_snapshot->add_clocks();
+  boottime->set_clock_id(protos::pbzero::BUILTIN_CLOCK_BOOTTIME);
+  boottime->set_timestamp(0);
+  auto* monotonic = clock_snapshot->add_clocks();
+  monotonic->set_clock_id(protos::pbzero::BUILTIN_CLOCK_MONOTONIC_COARSE);
+  monotonic->set_timestamp(0);
+
+  uint64_t index = 0;
+  for (const HeapDump& dump : dumps) {
+    auto* packet = trace->add_packet();
+    packet->set_trusted_packet_sequence_id(1);
+    if (index == 0) {
+      packet->set_previous_packet_dropped(true);
+      packet->set_incremental_state_cleared(true);
+    }
+    packet->set_timestamp(dump.ts);
+    auto* profile = packet->set_profile_packet();
+    profile->set_index(index++);
+
+    const char* kStrings[] = {"main", "Grow", "Shrink", "libfoo.so",
+                              "build-id"};
+    for (uint64_t i = 0; i < base::ArraySize(kStrings); i++) {
+      auto* interned = profile->add_strings();
+      interned->set_iid(i + 1);
+      interned->set_str(std::string(kStrings[i]));
+    }
+    auto* mapping = profile->add_mappings();
+    mapping->set_iid(1);
+    mapping->add_path_string_ids(4);
+    mapping->set_build_id(5);
+    for (uint64_t frame_id : {kMain, kGrow, kShrink}) {
+      auto* frame = profile->add_frames();
+      frame->set_iid(frame_id);
+      frame->set_function_name_id(frame_id);
+      frame->set_mapping_id(1);
+      frame->set_rel_pc(frame_id * 0x1000);
+    }
+    for (uint64_t leaf : {kGrow, kShrink}) {
+      auto* callstack = profile->add_callstacks();
+      callstack->set_iid(leaf);
+      callstack->add_frame_ids(kMain);
+      callstack->add_frame_ids(leaf);
+    }
+
+    auto* process_dump = profile->add_process_dumps();
+    process_dump->set_pid(dump.pid);
+    process_dump->set_heap_name("libc.malloc");
+    process_dump->set_timestamp(dump.ts);
+    for (const HeapSample& sample : dump.samples) {
+      auto* heap_sample = process_dump->add_samples();
+      heap_sample->set_callstack_id(sample.leaf);
+      heap_sample->set_self_allocated(sample.self_allocated);
+      heap_sample->set_alloc_count(sample.alloc_count);
+      heap_sample->set_self_freed(sample.self_freed);
+      heap_sample->set_free_count(sample.free_count);
+    }
+  }
+  return trace.SerializeAsString();
+}
+
+// Converts |trace| like `traceconv profile --pid PID --diff-baseline-ts
+// BASELINE_TS [--diff-baseline-pid BASELINE_PID]` does.
+pprof::PprofProfileReader ConvertTraceToHeapDiffPprof(
+    const std::string& trace,
+    uint64_t pid,
+    uint64_t baseline_pid,
+    uint64_t baseline_ts) {
+  std::istringstream input(trace);
+  std::stringstream ss;
+  PERFETTO_CHECK(trace_to_text::TraceToHeapDiffProfile(
+                     &input, &ss, pid, /*timestamps=*/{},
+                     /*annotate_frames=*/false, baseline_pid,
+                     baseline_ts) == 0);
+  return ReadOnlyProfile(ss.str());
 }
 
 std::vector<std::vector<std::string>> get_samples_function_names(
@@ -127,6 +251,60 @@ TEST_F(TraceToPprofTest, HugeSizes) {
             3000000000);
 }
 
+TEST_F(TraceToPprofTest, HeapDiffSameProcess) {
+  std::string trace = CreateHeapProfileTrace({
+      {2, 100, {{kGrow, 1000, 1, 0, 0}, {kShrink, 500, 5, 0, 0}}},
+      {2, 200, {{kGrow, 3000, 3, 0, 0}, {kShrink, 500, 5, 400, 4}}},
+  });
+  // The dump at 100 is the baseline, so only the dump at 200 is converted.
+  const auto pprof = ConvertTraceToHeapDiffPprof(
+      trace, /*pid=*/2, /*baseline_pid=*/0, /*baseline_ts=*/100);
+
+  EXPECT_EQ(pprof.get_samples_value_sum("Grow", "Unreleased malloc size"),
+            2000);
+  EXPECT_EQ(pprof.get_samples_value_sum("Grow", "Unreleased malloc count"), 2);
+  EXPECT_EQ(pprof.get_samples_value_sum("Grow", "Total malloc size"), 2000);
+  EXPECT_EQ(pprof.get_samples_value_sum("Shrink", "Unreleased malloc size"),
+            -400);
+  EXPECT_EQ(pprof.get_samples_value_sum("Shrink", "Unreleased malloc count"),
+            -4);
+  EXPECT_EQ(pprof.get_samples_value_sum("Shrink", "Total malloc size"), 0);
+  EXPECT_THAT(get_samples_function_names(pprof, "Grow"),
+              Contains(std::vector<std::string>{"Grow", "main"}));
+}
+
+TEST_F(TraceToPprofTest, HeapDiffOtherProcess) {
+  std::string trace = CreateHeapProfileTrace({
+      {2, 100, {{kGrow, 1000, 1, 0, 0}, {kShrink, 500, 5, 0, 0}}},
+      {3, 100, {{kGrow, 200, 2, 0, 0}, {kShrink, 800, 8, 0, 0}}},
+  });
+  const auto pprof = ConvertTraceToHeapDiffPprof(
+      trace, /*pid=*/3, /*baseline_pid=*/2, /*baseline_ts=*/100);
+
+  EXPECT_EQ(pprof.get_samples_value_sum("Grow", "Unreleased malloc size"),
+            -800);
+  EXPECT_EQ(pprof.get_samples_value_sum("Grow", "Unreleased malloc count"), 1);
+  EXPECT_EQ(pprof.get_samples_value_sum("Shrink", "Unreleased malloc size"),
+            300);
+  EXPECT_EQ(pprof.get_samples_value_sum("Shrink", "Unreleased malloc count"),
+            3);
+}
+
+TEST_F(TraceToPprofTest, HeapDiffMissingBaseline) {
+  std::string trace = CreateHeapProfileTrace({
+      {2, 100, {{kGrow, 1000, 1, 0, 0}}},
+  });
+  // There is no dump of process 3, so no profile is written.
+  std::istringstream input(trace);
+  std::stringstream output;
+  EXPECT_EQ(trace_to_text::TraceToHeapDiffProfile(
+                &input, &output, /*pid=*/0, /*timestamps=*/{},
+                /*annotate_frames=*/false, /*baseline_pid=*/3,
+                /*baseline_ts=*/100),
+            0);
+  EXPECT_EQ(output.str(), "");
+}
+
 class TraceToPprofRealTraceTest : public ::testing::Test {
  public:
   void SetUp() override {
@@ -98,6 +98,7 @@ int TraceToProfile(
     std::vector<uint64_t> timestamps,
     ConversionMode conversion_mode,
     uint64_t conversion_flags,
+    const HeapDiffBaseline* diff_baseline,
     std::string dirname_prefix,
     std::function<std::string(const SerializedProfile&)> filename_fn) {
   std::vector<SerializedProfile> profiles;
@@ -113,8 +114,14 @@ int TraceToProfile(
   if (auto status = tp->NotifyEndOfFile(); !status.ok()) {
     return -1;
   }
-  TraceToPprof(tp.get(), &profiles, conversion_mode, conversion_flags, pid,
-               timestamps);
+  if (diff_baseline) {
+    PERFETTO_CHECK(conversion_mode == ConversionMode::kHeapProfile);
+    TraceToHeapDiffPprof(tp.get(), &profiles, *diff_baseline, conversion_flags,
+                         pid, timestamps);
+  } else {
+    TraceToPprof(tp.get(), &profiles, conversion_mode, conversion_flags, pid,
+                 timestamps);
+  }
   if (profiles.empty()) {
     return 0;
   }
@@ -150,7 +157,30 @@ int TraceToHeapProfile(std::istream* input,
 
   return TraceToProfile(
       input, output, pid, timestamps, ConversionMode::kHeapProfile,
-      ToConversionFlags(annotate_frames), "heap_profile-", filename_fn);
+      ToConversionFlags(annotate_frames), /*diff_baseline=*/nullptr,
+      "heap_profile-", filename_fn);
+}
+
+int TraceToHeapDiffProfile(std::istream* input,
+                           std::ostream* output,
+                           uint64_t pid,
+                           std::vector<uint64_t> timestamps,
+                           bool annotate_frames,
+                           uint64_t baseline_pid,
+                           uint64_t baseline_ts) {
+  int file_idx = 0;
+  auto filename_fn = [&file_idx](const SerializedProfile& profile) {
+    return "heap_diff." + std::to_string(++file_idx) + "." +
+           std::to_string(profile.pid) + "." + profile.heap_name + ".pb";
+  };
+
+  HeapDiffBaseline baseline;
+  baseline.pid = baseline_pid;
+  baseline.ts = baseline_ts;
+  return TraceToProfile(input, output, pid, timestamps,
+                        ConversionMode::kHeapProfile,
+                        ToConversionFlags(annotate_frames), &baseline,
+                        "heap_profile_diff-", filename_fn);
 }
 
 int TraceToPerfProfile(std::istream* input,
@@ -166,7 +196,8 @@ int TraceToPerfProfile(std::istream* input,
 
   return TraceToProfile(
       input, output, pid, timestamps, ConversionMode::kPerfProfile,
-      ToConversionFlags(annotate_frames), "perf_profile-", filename_fn);
+      ToConversionFlags(annotate_frames), /*diff_baseline=*/nullptr,
+      "perf_profile-", filename_fn);
 }
 
 int TraceToJavaHeapProfile(std::istream* input,
@@ -182,7 +213,8 @@ int TraceToJavaHeapProfile(std::istream* input,
 
   return TraceToProfile(
       input, output, pid, timestamps, ConversionMode::kJavaHeapProfile,
-      ToConversionFlags(annotate_frames), "heap_profile-", filename_fn);
+      ToConversionFlags(annotate_frames), /*diff_baseline=*/nullptr,
+      "heap_profile-", filename_fn);
 }
 }  // namespace trace_to_text
 }  // namespace perfetto
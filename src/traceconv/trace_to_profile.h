@@ -31,6 +31,18 @@ int TraceToHeapProfile(std::istream* input,
                        std::vector<uint64_t> timestamps,
                        bool annotate_frames);
 
+// Like TraceToHeapProfile, but every profile contains the difference with the
+// dump of |baseline_pid| at |baseline_ts| (the dump of the same process if
+// |baseline_pid| is 0).
+// 0: success
+int TraceToHeapDiffProfile(std::istream* input,
+                           std::ostream* output,
+                           uint64_t pid,
+                           std::vector<uint64_t> timestamps,
+                           bool annotate_frames,
+                           uint64_t baseline_pid,
+                           uint64_t baseline_ts);
+
 // 0: success
 int TraceToPerfProfile(std::istream* input,
                        std::ostream* output,
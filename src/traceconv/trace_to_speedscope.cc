@@ -0,0 +1,419 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/traceconv/trace_to_speedscope.h"
+
+#include <algorithm>
+#include <cinttypes>
+#include <cstdint>
+#include <istream>
+#include <map>
+#include <memory>
+#include <optional>
+#include <ostream>
+#include <sstream>
+#include <string>
+#include <tuple>
+#include <unordered_map>
+#include <utility>
+#include <vector>
+
+#include "perfetto/base/logging.h"
+#include "perfetto/trace_processor/basic_types.h"
+#include "perfetto/trace_processor/trace_processor.h"
+#include "src/traceconv/utils.h"
+
+namespace perfetto {
+namespace trace_to_text {
+namespace {
+
+using ::perfetto::trace_processor::Iterator;
+using ::perfetto::trace_processor::TraceProcessor;
+
+constexpr char kThreadSlicesQuery[] = R"(
+  SELECT tt.utid, s.track_id, s.ts, s.dur, s.depth, s.name
+  FROM slice s
+  JOIN thread_track tt ON s.track_id = tt.id
+  ORDER BY tt.utid, s.track_id, s.ts, s.depth
+)";
+
+constexpr char kCpuSamplesQuery[] = R"(
+  SELECT utid, callsite_id
+  FROM (
+    SELECT ts, utid, callsite_id FROM perf_sample
+    WHERE callsite_id IS NOT NULL
+    UNION ALL
+    SELECT ts, utid, callsite_id FROM cpu_profile_stack_sample
+  )
+  ORDER BY utid, ts
+)";
+
+// The frames shared by all the profiles. Frames with the same name and
+// location are deduplicated.
+class FrameTable {
+ public:
+  uint32_t Intern(const std::string& name,
+                  const std::optional<std::string>& file = std::nullopt,
+                  std::optional<int64_t> line = std::nullopt) {
+    auto key = std::make_tuple(name, file, line);
+    auto it = index_.find(key);
+    if (it != index_.end()) {
+      return it->second;
+    }
+    auto id = static_cast<uint32_t>(frames_.size());
+    index_.emplace(key, id);
+    frames_.push_back(std::move(key));
+    return id;
+  }
+
+  void WriteJson(std::ostream* output) const {
+    *output << '[';
+    for (size_t i = 0; i < frames_.size(); ++i) {
+      const auto& [name, file, line] = frames_[i];
+      *output << (i == 0 ? "" : ",") << "{\"name\":";
+      WriteJsonString(output, name);
+      if (file) {
+        *output << ",\"file\":";
+        WriteJsonString(output, *file);
+      }
+      if (line) {
+        *output << ",\"line\":" << *line;
+      }
+      *output << '}';
+    }
+    *output << ']';
+  }
+
+ private:
+  using Key = std::tuple<std::string,
+                         std::optional<std::string>,
+                         std::optional<int64_t>>;
+
+  std::map<Key, uint32_t> index_;
+  std::vector<Key> frames_;
+};
+
+struct Callsite {
+  std::optional<int64_t> parent_id;
+  // Frames of the callsite (more than one if functions were inlined), root
+  // first.
+  std::vector<uint32_t> frames;
+};
+
+class SpeedscopeExporter {
+ public:
+  explicit SpeedscopeExporter(TraceProcessor* tp) : tp_(tp) {}
+
+  bool Export(std::ostream* output);
+
+ private:
+  bool LoadThreadNames();
+  bool ExportThreadSlices();
+  bool ExportCpuSamples();
+  bool LoadCallsites();
+  const std::vector<uint32_t>& GetStack(int64_t callsite_id);
+  std::string GetProfileName(std::optional<int64_t> utid) const;
+
+  TraceProcessor* const tp_;
+  FrameTable frames_;
+  std::unordered_map<int64_t, std::string> thread_names_;
+  std::unordered_map<int64_t, Callsite> callsites_;
+  std::unordered_map<int64_t, std::vector<uint32_t>> stacks_;
+
+  // The JSON of each profile, grouped by thread.
+  std::multimap<int64_t, std::string> profiles_;
+};
+
+bool SpeedscopeExporter::Export(std::ostream* output) {
+  if (!LoadThreadNames() || !ExportThreadSlices() || !ExportCpuSamples()) {
+    return false;
+  }
+  if (profiles_.empty()) {
+    PERFETTO_ELOG("No thread slices or CPU samples found in the trace.");
+    return false;
+  }
+
+  *output << "{\"$schema\":"
+             "\"https://www.speedscope.app/file-format-schema.json\","
+             "\"shared\":{\"frames\":";
+  frames_.WriteJson(output);
+  *output << "},\"profiles\":[";
+  bool first = true;
+  for (const auto& [utid, profile] : profiles_) {
+    *output << (first ? "" : ",") << profile;
+    first = false;
+  }
+  *output << "],\"activeProfileIndex\":0,\"exporter\":\"traceconv\"}\n";
+  return true;
+}
+
+bool SpeedscopeExporter::LoadThreadNames() {
+  Iterator it = tp_->ExecuteQuery(R"(
+    SELECT utid, tid, COALESCE(t.name, p.name)
+    FROM thread t
+    LEFT JOIN process p USING (upid)
+  )");
+  while (it.Next()) {
+    std::string name = it.Get(2).is_null() ? "[unknown]" : it.Get(2).AsString();
+    if (!it.Get(1).is_null()) {
+      name += " (" + std::to_string(it.Get(1).AsLong()) + ")";
+    }
+    thread_names_[it.Get(0).AsLong()] = std::move(name);
+  }
+  if (!it.Status().ok()) {
+    PERFETTO_ELOG("Failed to query the threads: %s", it.Status().c_message());
+    return false;
+  }
+  return true;
+}
+
+std::string SpeedscopeExporter::GetProfileName(
+    std::optional<int64_t> utid) const {
+  if (!utid) {
+    return "[unknown]";
+  }
+  auto it = thread_names_.find(*utid);
+  return it == thread_names_.end() ? "[unknown]" : it->second;
+}
+
+// Each track is exported as an evented profile: speedscope requires the open
+// and close events to be ordered by timestamp and properly nested, which is
+// guaranteed for the slices of a single track.
+bool SpeedscopeExporter::ExportThreadSlices() {
+  std::optional<int64_t> trace_end;
+  {
+    Iterator it = tp_->ExecuteQuery("SELECT end_ts FROM trace_bounds");
+    if (it.Next() && !it.Get(0).is_null()) {
+      trace_end = it.Get(0).AsLong();
+    }
+  }
+
+  struct OpenSlice {
+    uint32_t frame;
+    uint32_t depth;
+    int64_t end;
+  };
+  std::vector<OpenSlice> stack;
+  std::ostringstream events;
+  std::optional<int64_t> start;
+  int64_t last_at = 0;
+
+  // Timestamps are clamped so that they never go backwards, even if the
+  // slices are not properly nested.
+  auto add_event = [&](char type, uint32_t frame, int64_t at) {
+    if (!start) {
+      start = last_at = at;
+    }
+    last_at = std::max(last_at, at);
+    events << (events.tellp() == 0 ? "" : ",") << "{\"type\":\"" << type
+           << "\",\"frame\":" << frame << ",\"at\":" << last_at << '}';
+  };
+  auto close_slices = [&](auto pred) {
+    while (!stack.empty() && pred(stack.back())) {
+      add_event('C', stack.back().frame, stack.back().end);
+      stack.pop_back();
+    }
+  };
+  auto flush_profile = [&](int64_t utid) {
+    close_slices([](const OpenSlice&) { return true; });
+    std::ostringstream profile;
+    profile << "{\"type\":\"evented\",\"name\":";
+    WriteJsonString(&profile, GetProfileName(utid));
+    profile << ",\"unit\":\"nanoseconds\",\"startValue\":" << *start
+            << ",\"endValue\":" << last_at << ",\"events\":[" << events.str()
+            << "]}";
+    profiles_.emplace(utid, profile.str());
+    events.str("");
+    start = std::nullopt;
+  };
+
+  Iterator it = tp_->ExecuteQuery(kThreadSlicesQuery);
+  std::optional<std::pair<int64_t, int64_t>> current_track;
+  while (it.Next()) {
+    int64_t utid = it.Get(0).AsLong();
+    int64_t track_id = it.Get(1).AsLong();
+    if (current_track && current_track->second != track_id) {
+      flush_profile(current_track->first);
+    }
+    current_track = std::make_pair(utid, track_id);
+
+    int64_t ts = it.Get(2).AsLong();
+    int64_t dur = it.Get(3).AsLong();
+    auto depth = static_cast<uint32_t>(it.Get(4).AsLong());
+    std::string name = it.Get(5).is_null() ? "[unknown]" : it.Get(5).AsString();
+
+    // Incomplete slices last until the end of the trace.
+    int64_t end = dur >= 0 ? ts + dur : std::max(ts, trace_end.value_or(ts));
+    close_slices([&](const OpenSlice& open) {
+      return open.depth >= depth || open.end < ts;
+    });
+    if (!stack.empty()) {
+      end = std::min(end, stack.back().end);
+    }
+    uint32_t frame = frames_.Intern(name);
+    add_event('O', frame, ts);
+    stack.push_back({frame, depth, end});
+  }
+  if (current_track) {
+    flush_profile(current_track->first);
+  }
+  if (!it.Status().ok()) {
+    PERFETTO_ELOG("Failed to query the slices: %s", it.Status().c_message());
+    return false;
+  }
+  return true;
+}
+
+bool SpeedscopeExporter::LoadCallsites() {
+  auto profile_callsites = GetCallsites(tp_);
+  if (!profile_callsites) {
+    return false;
+  }
+  for (auto& [id, profile_callsite] : *profile_callsites) {
+    Callsite callsite;
+    callsite.parent_id = profile_callsite.parent_id;
+    for (const ProfileSymbol& symbol : profile_callsite.symbols) {
+      callsite.frames.push_back(frames_.Intern(
+          symbol.name.empty() ? "[unknown]" : symbol.name, symbol.file,
+          symbol.line));
+    }
+    if (callsite.frames.empty()) {
+      std::optional<std::string> mapping;
+      if (!profile_callsite.mapping_name.empty()) {
+        mapping = profile_callsite.mapping_name;
+      }
+      // Keep unsymbolized frames distinguishable by the module they belong to.
+      std::string name;
+      if (!profile_callsite.function_name.empty()) {
+        name = profile_callsite.function_name;
+      } else if (mapping) {
+        name = (*mapping)[0] == '[' ? *mapping : "[" + *mapping + "]";
+      } else {
+        name = "[unknown]";
+      }
+      // Pseudo-mappings (e.g. "[kernel.kallsyms]") are not files.
+      if (mapping && (*mapping)[0] == '[') {
+        mapping = std::nullopt;
+      }
+      callsite.frames.push_back(frames_.Intern(name, mapping));
+    }
+    callsites_.emplace(id, std::move(callsite));
+  }
+  return true;
+}
+
+// Returns the frames of the stack ending at |callsite_id|, root first.
+const std::vector<uint32_t>& SpeedscopeExporter::GetStack(
+    int64_t callsite_id) {
+  auto cached = stacks_.find(callsite_id);
+  if (cached != stacks_.end()) {
+    return cached->second;
+  }
+  std::vector<const Callsite*> callsites;
+  for (std::optional<int64_t> id = callsite_id; id;) {
+    auto it = callsites_.find(*id);
+    if (it == callsites_.end()) {
+      break;
+    }
+    callsites.push_back(&it->second);
+    id = it->second.parent_id;
+  }
+  std::vector<uint32_t> stack;
+  for (auto it = callsites.rbegin(); it != callsites.rend(); ++it) {
+    stack.insert(stack.end(), (*it)->frames.begin(), (*it)->frames.end());
+  }
+  return stacks_.emplace(callsite_id, std::move(stack)).first->second;
+}
+
+// The samples of each thread are exported as a sampled profile, in timestamp
+// order so that they can be inspected in the "Time Order" view.
+bool SpeedscopeExporter::ExportCpuSamples() {
+  if (!LoadCallsites()) {
+    return false;
+  }
+
+  std::ostringstream samples;
+  uint32_t sample_count = 0;
+  auto flush_profile = [&](std::optional<int64_t> utid) {
+    std::ostringstream profile;
+    profile << "{\"type\":\"sampled\",\"name\":";
+    WriteJsonString(&profile, GetProfileName(utid) + " samples");
+    profile << ",\"unit\":\"none\",\"startValue\":0,\"endValue\":"
+            << sample_count << ",\"samples\":[" << samples.str()
+            << "],\"weights\":[";
+    for (uint32_t i = 0; i < sample_count; ++i) {
+      profile << (i == 0 ? "1" : ",1");
+    }
+    profile << "]}";
+    profiles_.emplace(utid.value_or(-1), profile.str());
+    samples.str("");
+    sample_count = 0;
+  };
+
+  Iterator it = tp_->ExecuteQuery(kCpuSamplesQuery);
+  std::optional<std::optional<int64_t>> current_utid;
+  while (it.Next()) {
+    std::optional<int64_t> utid;
+    if (!it.Get(0).is_null()) {
+      utid = it.Get(0).AsLong();
+    }
+    if (current_utid && *current_utid != utid) {
+      flush_profile(*current_utid);
+    }
+    current_utid = utid;
+
+    samples << (sample_count == 0 ? "[" : ",[");
+    const std::vector<uint32_t>& stack = GetStack(it.Get(1).AsLong());
+    for (size_t i = 0; i < stack.size(); ++i) {
+      samples << (i == 0 ? "" : ",") << stack[i];
+    }
+    samples << ']';
+    ++sample_count;
+  }
+  if (current_utid) {
+    flush_profile(*current_utid);
+  }
+  if (!it.Status().ok()) {
+    PERFETTO_ELOG("Failed to query the samples: %s", it.Status().c_message());
+    return false;
+  }
+  return true;
+}
+
+std::unique_ptr<TraceProcessor> LoadTrace(std::istream* input) {
+  trace_processor::Config config;
+  std::unique_ptr<TraceProcessor> tp = TraceProcessor::CreateInstance(config);
+  if (!ReadTraceUnfinalized(tp.get(), input)) {
+    return nullptr;
+  }
+  if (auto status = tp->NotifyEndOfFile(); !status.ok()) {
+    return nullptr;
+  }
+  return tp;
+}
+
+}  // namespace
+
+bool TraceToSpeedscope(std::istream* input, std::ostream* output) {
+  std::unique_ptr<TraceProcessor> tp = LoadTrace(input);
+  if (!tp) {
+    return false;
+  }
+  return SpeedscopeExporter(tp.get()).Export(output);
+}
+
+}  // namespace trace_to_text
+}  // namespace perfetto
@@ -0,0 +1,36 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACECONV_TRACE_TO_SPEEDSCOPE_H_
+#define SRC_TRACECONV_TRACE_TO_SPEEDSCOPE_H_
+
+#include <iostream>
+
+namespace perfetto {
+namespace trace_to_text {
+
+// Exports the trace in the speedscope JSON file format. See
+// https://github.com/jlfwong/speedscope/wiki/Importing-from-custom-sources
+//
+// Each thread gets an evented profile with the slices of each of its tracks
+// (usually there is only one) and a sampled profile with its CPU samples
+// (perf_sample and cpu_profile_stack_sample) in timestamp order.
+bool TraceToSpeedscope(std::istream* input, std::ostream* output);
+
+}  // namespace trace_to_text
+}  // namespace perfetto
+
+#endif  // SRC_TRACECONV_TRACE_TO_SPEEDSCOPE_H_
@@ -0,0 +1,248 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/traceconv/trace_to_speedscope.h"
+
+#include <json/reader.h>
+#include <json/value.h>
+
+#include <cstdint>
+#include <memory>
+#include <set>
+#include <sstream>
+#include <string>
+#include <vector>
+
+#include "perfetto/base/build_config.h"
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto {
+namespace trace_to_text {
+namespace {
+
+using ::testing::ElementsAre;
+
+constexpr char kSchemaUrl[] =
+    "https://www.speedscope.app/file-format-schema.json";
+
+// Validates |root| against the speedscope file format schema (kSchemaUrl):
+// every object has the properties the schema requires, with the types and
+// enum values ("type", "unit") it allows. On top of that, checks the
+// constraints the schema can't express but speedscope relies on:
+// - Frame indices of events and samples are within "shared.frames".
+// - Events of evented profiles are sorted by "at" within
+//   [startValue, endValue], and the "O"pen and "C"lose events of each frame
+//   are properly nested and balanced.
+// - "samples" and "weights" of sampled profiles have the same length.
+void ExpectValidSpeedscopeFile(const Json::Value& root) {
+  auto expect_required = [](const Json::Value& object,
+                            const std::vector<std::string>& keys) {
+    ASSERT_TRUE(object.isObject());
+    for (const std::string& key : keys) {
+      ASSERT_TRUE(object.isMember(key)) << "Missing required property " << key;
+    }
+  };
+
+  expect_required(root, {"$schema", "shared", "profiles"});
+  EXPECT_EQ(root["$schema"].asString(), kSchemaUrl);
+  EXPECT_TRUE(!root.isMember("name") || root["name"].isString());
+  EXPECT_TRUE(!root.isMember("exporter") || root["exporter"].isString());
+  EXPECT_TRUE(!root.isMember("activeProfileIndex") ||
+              root["activeProfileIndex"].isNumeric());
+
+  expect_required(root["shared"], {"frames"});
+  const Json::Value& frames = root["shared"]["frames"];
+  ASSERT_TRUE(frames.isArray());
+  for (const Json::Value& frame : frames) {
+    expect_required(frame, {"name"});
+    EXPECT_TRUE(frame["name"].isString());
+    EXPECT_TRUE(!frame.isMember("file") || frame["file"].isString());
+    EXPECT_TRUE(!frame.isMember("line") || frame["line"].isNumeric());
+    EXPECT_TRUE(!frame.isMember("col") || frame["col"].isNumeric());
+  }
+  auto is_frame_index = [&frames](const Json::Value& value) {
+    return value.isUInt() && value.asUInt() < frames.size();
+  };
+
+  const std::set<std::string> kUnits = {"none",         "nanoseconds",
+                                        "microseconds", "milliseconds",
+                                        "seconds",      "bytes"};
+  const Json::Value& profiles = root["profiles"];
+  ASSERT_TRUE(profiles.isArray());
+  for (const Json::Value& profile : profiles) {
+    expect_required(profile,
+                    {"type", "name", "unit", "startValue", "endValue"});
+    EXPECT_TRUE(profile["name"].isString());
+    EXPECT_EQ(kUnits.count(profile["unit"].asString()), 1u);
+    ASSERT_TRUE(profile["startValue"].isNumeric());
+    ASSERT_TRUE(profile["endValue"].isNumeric());
+
+    std::string type = profile["type"].asString();
+    if (type == "evented") {
+      expect_required(profile, {"events"});
+      const Json::Value& events = profile["events"];
+      ASSERT_TRUE(events.isArray());
+      std::vector<uint32_t> stack;
+      double last_at = profile["startValue"].asDouble();
+      for (const Json::Value& event : events) {
+        expect_required(event, {"type", "at", "frame"});
+        ASSERT_TRUE(is_frame_index(event["frame"]));
+        ASSERT_TRUE(event["at"].isNumeric());
+        EXPECT_GE(event["at"].asDouble(), last_at);
+        last_at = event["at"].asDouble();
+        if (event["type"].asString() == "O") {
+          stack.push_back(event["frame"].asUInt());
+        } else {
+          ASSERT_EQ(event["type"].asString(), "C");
+          ASSERT_FALSE(stack.empty());
+          EXPECT_EQ(event["frame"].asUInt(), stack.back());
+          stack.pop_back();
+        }
+      }
+      EXPECT_TRUE(stack.empty());
+      EXPECT_LE(last_at, profile["endValue"].asDouble());
+    } else {
+      ASSERT_EQ(type, "sampled");
+      expect_required(profile, {"samples", "weights"});
+      const Json::Value& samples = profile["samples"];
+      const Json::Value& weights = profile["weights"];
+      ASSERT_TRUE(samples.isArray());
+      ASSERT_TRUE(weights.isArray());
+      EXPECT_EQ(samples.size(), weights.size());
+      for (const Json::Value& sample : samples) {
+        ASSERT_TRUE(sample.isArray());
+        for (const Json::Value& frame : sample) {
+          EXPECT_TRUE(is_frame_index(frame));
+        }
+      }
+      for (const Json::Value& weight : weights) {
+        EXPECT_TRUE(weight.isNumeric());
+      }
+    }
+  }
+}
+
+Json::Value ConvertToSpeedscope(const std::string& trace) {
+  std::istringstream input(trace);
+  std::ostringstream output;
+  EXPECT_TRUE(TraceToSpeedscope(&input, &output));
+
+  Json::Value root;
+  std::string errors;
+  std::unique_ptr<Json::CharReader> reader(
+      Json::CharReaderBuilder().newCharReader());
+  std::string json = output.str();
+  EXPECT_TRUE(reader->parse(json.data(), json.data() + json.size(), &root,
+                            &errors))
+      << errors;
+  ExpectValidSpeedscopeFile(root);
+  return root;
+}
+
+std::vector<std::string> FrameNames(const Json::Value& root,
+                                    const Json::Value& indices) {
+  std::vector<std::string> names;
+  const Json::Value& frames = root["shared"]["frames"];
+  for (const Json::Value& index : indices) {
+    names.push_back(frames[index.asUInt()]["name"].asString());
+  }
+  return names;
+}
+
+const Json::Value& FindProfile(const Json::Value& root,
+                               const std::string& name) {
+  for (const Json::Value& profile : root["profiles"]) {
+    if (profile["name"].asString() == name) {
+      return profile;
+    }
+  }
+  ADD_FAILURE() << "Profile not found: " << name;
+  return Json::Value::nullSingleton();
+}
+
+class TraceToSpeedscopeTest : public ::testing::Test {
+ public:
+  void SetUp() override {
+#if PERFETTO_BUILDFLAG(PERFETTO_OS_ANDROID)
+    GTEST_SKIP() << "do not run traceconv tests on Android target";
+#endif
+  }
+};
+
+TEST_F(TraceToSpeedscopeTest, ThreadSlices) {
+  Json::Value root = ConvertToSpeedscope(
+      "# tracer: nop\n"
+      "  app-10    ( 10) [000] .... 1.000000: tracing_mark_write: B|10|outer\n"
+      "  app-10    ( 10) [000] .... 1.000010: tracing_mark_write: B|10|inner\n"
+      "  app-10    ( 10) [000] .... 1.000020: tracing_mark_write: E|10\n"
+      "  app-10    ( 10) [000] .... 1.000030: tracing_mark_write: B|10|inner\n"
+      "  app-10    ( 10) [000] .... 1.000040: tracing_mark_write: E|10\n"
+      "  app-10    ( 10) [000] .... 1.000050: tracing_mark_write: E|10\n"
+      "  worker-11 ( 10) [001] .... 1.000000: tracing_mark_write: B|10|work\n");
+
+  ASSERT_EQ(root["profiles"].size(), 2u);
+
+  const Json::Value& app = FindProfile(root, "app (10)");
+  EXPECT_EQ(app["type"].asString(), "evented");
+  EXPECT_EQ(app["unit"].asString(), "nanoseconds");
+  EXPECT_EQ(app["startValue"].asInt64(), 1000000000);
+  EXPECT_EQ(app["endValue"].asInt64(), 1000050000);
+  std::vector<std::string> events;
+  for (const Json::Value& event : app["events"]) {
+    events.push_back(
+        event["type"].asString() + " " +
+        root["shared"]["frames"][event["frame"].asUInt()]["name"].asString() +
+        " " + std::to_string(event["at"].asInt64()));
+  }
+  EXPECT_THAT(events, ElementsAre("O outer 1000000000", "O inner 1000010000",
+                                  "C inner 1000020000", "O inner 1000030000",
+                                  "C inner 1000040000", "C outer 1000050000"));
+
+  // Slices which never end last until the end of the trace.
+  const Json::Value& worker = FindProfile(root, "worker (11)");
+  ASSERT_EQ(worker["events"].size(), 2u);
+  EXPECT_EQ(worker["events"][1]["type"].asString(), "C");
+  EXPECT_EQ(worker["events"][1]["at"].asInt64(), 1000050000);
+}
+
+TEST_F(TraceToSpeedscopeTest, CpuSamples) {
+  Json::Value root = ConvertToSpeedscope(
+      "app-10/10;main;foo;bar 2\n"
+      "app-10/10;main;foo 1\n"
+      "app-10/12;main;baz 1\n");
+
+  ASSERT_EQ(root["profiles"].size(), 2u);
+
+  const Json::Value& main_thread = FindProfile(root, "app (10) samples");
+  EXPECT_EQ(main_thread["type"].asString(), "sampled");
+  EXPECT_EQ(main_thread["unit"].asString(), "none");
+  EXPECT_EQ(main_thread["endValue"].asInt64(), 3);
+  const Json::Value& samples = main_thread["samples"];
+  ASSERT_EQ(samples.size(), 3u);
+  EXPECT_THAT(FrameNames(root, samples[0]), ElementsAre("main", "foo", "bar"));
+  EXPECT_THAT(FrameNames(root, samples[1]), ElementsAre("main", "foo", "bar"));
+  EXPECT_THAT(FrameNames(root, samples[2]), ElementsAre("main", "foo"));
+  EXPECT_EQ(samples[0], samples[1]);
+
+  const Json::Value& other_thread = FindProfile(root, "app (12) samples");
+  ASSERT_EQ(other_thread["samples"].size(), 1u);
+  EXPECT_THAT(FrameNames(root, other_thread["samples"][0]),
+              ElementsAre("main", "baz"));
+}
+
+}  // namespace
+}  // namespace trace_to_text
+}  // namespace perfetto
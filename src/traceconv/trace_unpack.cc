@@ -21,6 +21,7 @@
 #include <memory>
 #include <vector>
 
+#include "perfetto/base/logging.h"
 #include "perfetto/trace_processor/read_trace.h"
 #include "src/traceconv/utils.h"
 
@@ -35,8 +36,10 @@ bool UnpackCompressedPackets(std::istream* input, std::ostream* output) {
   std::vector<uint8_t> unpacked;
   auto status = trace_processor::DecompressTrace(
       reinterpret_cast<uint8_t*>(packed.data()), packed.size(), &unpacked);
-  if (!status.ok())
+  if (!status.ok()) {
+    PERFETTO_ELOG("Failed to decompress trace: %s", status.c_message());
     return false;
+  }
 
   TraceWriter trace_writer(output);
   trace_writer.Write(reinterpret_cast<char*>(unpacked.data()), unpacked.size());
@@ -19,17 +19,22 @@
 #include <stdio.h>
 
 #include <cinttypes>
+#include <map>
 #include <memory>
 #include <optional>
 #include <ostream>
 #include <set>
+#include <string>
+#include <unordered_map>
 #include <utility>
+#include <vector>
 
 #include "perfetto/base/logging.h"
 #include "perfetto/ext/base/file_utils.h"
 #include "perfetto/ext/base/scoped_file.h"
 #include "perfetto/ext/base/string_splitter.h"
 #include "perfetto/protozero/scattered_heap_buffer.h"
+#include "perfetto/trace_processor/basic_types.h"
 #include "perfetto/trace_processor/trace_processor.h"
 
 #include "protos/perfetto/trace/profiling/deobfuscation.pbzero.h"
@@ -98,6 +103,112 @@ void IngestTraceOrDie(trace_processor::TraceProcessor* tp,
   }
 }
 
+void WriteJsonString(std::ostream* output, const std::string& str) {
+  *output << '"';
+  for (char c : str) {
+    switch (c) {
+      case '"':
+        *output << "\\\"";
+        break;
+      case '\\':
+        *output << "\\\\";
+        break;
+      case '\n':
+        *output << "\\n";
+        break;
+      case '\r':
+        *output << "\\r";
+        break;
+      case '\t':
+        *output << "\\t";
+        break;
+      default:
+        if (static_cast<unsigned char>(c) < 0x20) {
+          char escaped[8];
+          snprintf(escaped, sizeof(escaped), "\\u%04x", c);
+          *output << escaped;
+        } else {
+          *output << c;
+        }
+    }
+  }
+  *output << '"';
+}
+
+std::optional<std::unordered_map<int64_t, std::vector<ProfileSymbol>>>
+GetInlinedFunctions(trace_processor::TraceProcessor* tp) {
+  std::unordered_map<int64_t, std::vector<ProfileSymbol>> inlines;
+  // Most-inlined function (leaf) has the lowest id within a symbol set.
+  trace_processor::Iterator it = tp->ExecuteQuery(R"(
+    SELECT symbol_set_id, name, source_file, line_number
+    FROM stack_profile_symbol
+    ORDER BY symbol_set_id ASC, id DESC
+  )");
+  while (it.Next()) {
+    ProfileSymbol symbol;
+    if (!it.Get(1).is_null()) {
+      symbol.name = it.Get(1).AsString();
+    }
+    if (!it.Get(2).is_null()) {
+      symbol.file = it.Get(2).AsString();
+    }
+    if (!it.Get(3).is_null()) {
+      symbol.line = it.Get(3).AsLong();
+    }
+    inlines[it.Get(0).AsLong()].push_back(std::move(symbol));
+  }
+  if (!it.Status().ok()) {
+    PERFETTO_ELOG("Failed to query the symbols: %s", it.Status().c_message());
+    return std::nullopt;
+  }
+  return inlines;
+}
+
+std::optional<std::map<int64_t, ProfileCallsite>> GetCallsites(
+    trace_processor::TraceProcessor* tp) {
+  auto inlines = GetInlinedFunctions(tp);
+  if (!inlines) {
+    return std::nullopt;
+  }
+  std::map<int64_t, ProfileCallsite> callsites;
+  trace_processor::Iterator it = tp->ExecuteQuery(R"(
+    SELECT
+      c.id,
+      c.parent_id,
+      COALESCE(spf.deobfuscated_name, demangle(spf.name), spf.name),
+      spf.symbol_set_id,
+      spm.name
+    FROM stack_profile_callsite c
+    JOIN stack_profile_frame spf ON c.frame_id = spf.id
+    JOIN stack_profile_mapping spm ON spf.mapping = spm.id
+  )");
+  while (it.Next()) {
+    ProfileCallsite callsite;
+    if (!it.Get(1).is_null()) {
+      callsite.parent_id = it.Get(1).AsLong();
+    }
+    if (!it.Get(2).is_null()) {
+      callsite.function_name = it.Get(2).AsString();
+    }
+    if (!it.Get(3).is_null()) {
+      auto inline_it = inlines->find(it.Get(3).AsLong());
+      if (inline_it != inlines->end()) {
+        callsite.symbols = inline_it->second;
+      }
+    }
+    if (!it.Get(4).is_null()) {
+      callsite.mapping_name = it.Get(4).AsString();
+    }
+    callsites.emplace(it.Get(0).AsLong(), std::move(callsite));
+  }
+  if (!it.Status().ok()) {
+    PERFETTO_ELOG("Failed to query the callsites: %s",
+                  it.Status().c_message());
+    return std::nullopt;
+  }
+  return callsites;
+}
+
 TraceWriter::TraceWriter(std::ostream* output) : output_(output) {}
 
 TraceWriter::~TraceWriter() {
@@ -19,10 +19,14 @@
 
 #include <stdio.h>
 
+#include <cstdint>
 #include <functional>
 #include <iostream>
+#include <map>
 #include <memory>
 #include <optional>
+#include <string>
+#include <unordered_map>
 #include <vector>
 
 #include "perfetto/base/build_config.h"
@@ -58,6 +62,40 @@ bool ReadTraceUnfinalized(trace_processor::TraceProcessor* tp,
 void IngestTraceOrDie(trace_processor::TraceProcessor* tp,
                       const std::string& trace_proto);
 
+// Writes |str| to |output| as a quoted JSON string, escaping it as needed.
+void WriteJsonString(std::ostream* output, const std::string& str);
+
+// A function of a symbolized frame.
+struct ProfileSymbol {
+  // Empty if unknown.
+  std::string name;
+  std::optional<std::string> file;
+  std::optional<int64_t> line;
+};
+
+// A row of the stack_profile_callsite table, with its frame.
+struct ProfileCallsite {
+  std::optional<int64_t> parent_id;
+  // The functions of the frame if it is symbolized, root first: more than one
+  // if functions were inlined.
+  std::vector<ProfileSymbol> symbols;
+  // The deobfuscated or demangled name of the function of the frame if
+  // available, its raw name otherwise. Empty if unknown.
+  std::string function_name;
+  // The name of the mapping of the frame. Empty if unknown.
+  std::string mapping_name;
+};
+
+// Returns the functions of each symbol set of |tp|, root first, or
+// std::nullopt if the query fails.
+std::optional<std::unordered_map<int64_t, std::vector<ProfileSymbol>>>
+GetInlinedFunctions(trace_processor::TraceProcessor* tp);
+
+// Returns the callsites of |tp| sorted by id, or std::nullopt if the query
+// fails.
+std::optional<std::map<int64_t, ProfileCallsite>> GetCallsites(
+    trace_processor::TraceProcessor* tp);
+
 class TraceWriter {
  public:
   TraceWriter(std::ostream* output);
@@ -0,0 +1,383 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/traced/probes/cgroup_stats/cgroup_stats_data_source.h"
+
+#include <dirent.h>
+#include <fnmatch.h>
+
+#include <array>
+#include <cinttypes>
+#include <optional>
+#include <utility>
+
+#include "perfetto/base/logging.h"
+#include "perfetto/base/task_runner.h"
+#include "perfetto/base/time.h"
+#include "perfetto/ext/base/file_utils.h"
+#include "perfetto/ext/base/scoped_file.h"
+#include "perfetto/ext/base/string_splitter.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "perfetto/ext/base/string_view.h"
+#include "perfetto/ext/tracing/core/trace_writer.h"
+
+#include "protos/perfetto/config/sys_stats/cgroup_stats_config.pbzero.h"
+#include "protos/perfetto/trace/sys_stats/cgroup_stats.pbzero.h"
+#include "protos/perfetto/trace/trace_packet.pbzero.h"
+
+namespace perfetto {
+
+namespace {
+
+using protos::pbzero::CgroupStats;
+using protos::pbzero::CgroupStatsConfig;
+
+uint32_t ClampTo10Ms(uint32_t period_ms, const char* counter_name) {
+  if (period_ms > 0 && period_ms < 10) {
+    PERFETTO_ILOG("%s %" PRIu32
+                  " is less than minimum of 10ms. Increasing to 10ms.",
+                  counter_name, period_ms);
+    return 10;
+  }
+  return period_ms;
+}
+
+bool IsGlob(const std::string& path_component) {
+  return path_component.find_first_of("*?[") != std::string::npos;
+}
+
+// Invokes |fn| with the key and the value of every "key value" line of a flat
+// keyed cgroup file (e.g. cpu.stat, memory.stat). Lines whose value is not an
+// integer are skipped.
+template <typename Fn>
+void ForEachKeyValue(std::string contents, Fn fn) {
+  for (base::StringSplitter lines(std::move(contents), '\n'); lines.Next();) {
+    base::StringSplitter words(&lines, ' ');
+    if (!words.Next())
+      continue;
+    base::StringView key(words.cur_token(), words.cur_token_size());
+    if (!words.Next())
+      continue;
+    std::optional<uint64_t> value = base::CStringToUInt64(words.cur_token());
+    if (value)
+      fn(key, *value);
+  }
+}
+
+}  // namespace
+
+// static
+const ProbesDataSource::Descriptor CgroupStatsDataSource::descriptor = {
+    /*name*/ "linux.cgroup_stats",
+    /*flags*/ Descriptor::kFlagsNone,
+    /*fill_descriptor_func*/ nullptr,
+};
+
+CgroupStatsDataSource::CgroupStatsDataSource(
+    const DataSourceConfig& ds_config,
+    base::TaskRunner* task_runner,
+    TracingSessionID session_id,
+    std::unique_ptr<TraceWriter> writer,
+    const char* cgroup_root_dir)
+    : ProbesDataSource(session_id, &descriptor),
+      task_runner_(task_runner),
+      writer_(std::move(writer)),
+      cgroup_root_dir_(cgroup_root_dir),
+      weak_factory_(this) {
+  CgroupStatsConfig::Decoder cfg(ds_config.cgroup_stats_config_raw());
+  for (auto it = cfg.cgroup_path_globs(); it; ++it)
+    cgroup_path_globs_.push_back((*it).ToStdString());
+  for (auto it = cfg.memory_stat_keys(); it; ++it)
+    memory_stat_keys_.insert((*it).ToStdString());
+
+  std::array<uint32_t, 4> periods_ms{};
+  std::array<uint32_t, 4> ticks{};
+  static_assert(periods_ms.size() == ticks.size(), "must have same size");
+
+  periods_ms[0] = ClampTo10Ms(cfg.cpu_period_ms(), "cpu_period_ms");
+  periods_ms[1] = ClampTo10Ms(cfg.memory_period_ms(), "memory_period_ms");
+  periods_ms[2] = ClampTo10Ms(cfg.io_period_ms(), "io_period_ms");
+  periods_ms[3] = ClampTo10Ms(cfg.pressure_period_ms(), "pressure_period_ms");
+
+  tick_period_ms_ = 0;
+  for (uint32_t ms : periods_ms) {
+    if (ms && (ms < tick_period_ms_ || tick_period_ms_ == 0))
+      tick_period_ms_ = ms;
+  }
+
+  if (tick_period_ms_ == 0)
+    return;  // No polling configured.
+
+  for (size_t i = 0; i < periods_ms.size(); i++) {
+    auto ms = periods_ms[i];
+    if (ms && ms % tick_period_ms_ != 0) {
+      PERFETTO_ELOG(
+          "CgroupStats periods are not integer multiples of each other");
+      tick_period_ms_ = 0;
+      return;
+    }
+    ticks[i] = ms / tick_period_ms_;
+  }
+  cpu_ticks_ = ticks[0];
+  memory_ticks_ = ticks[1];
+  io_ticks_ = ticks[2];
+  pressure_ticks_ = ticks[3];
+}
+
+CgroupStatsDataSource::~CgroupStatsDataSource() = default;
+
+void CgroupStatsDataSource::Start() {
+  if (tick_period_ms_ == 0)
+    return;
+  auto weak_this = GetWeakPtr();
+  task_runner_->PostTask(std::bind(&CgroupStatsDataSource::Tick, weak_this));
+}
+
+// static
+void CgroupStatsDataSource::Tick(
+    base::WeakPtr<CgroupStatsDataSource> weak_this) {
+  if (!weak_this)
+    return;
+  CgroupStatsDataSource& thiz = *weak_this;
+
+  uint32_t period_ms = thiz.tick_period_ms_;
+  uint32_t delay_ms =
+      period_ms -
+      static_cast<uint32_t>(base::GetWallTimeMs().count() % period_ms);
+  thiz.task_runner_->PostDelayedTask(
+      std::bind(&CgroupStatsDataSource::Tick, weak_this), delay_ms);
+  thiz.ReadCgroupStats();
+}
+
+std::vector<std::string> CgroupStatsDataSource::ListCgroups() const {
+  std::set<std::string> cgroups;
+  for (const std::string& glob : cgroup_path_globs_) {
+    // The paths of the directories matching the components of the glob seen so
+    // far, relative to the cgroup root (e.g. "/foo/bar", or "" for the root).
+    std::vector<std::string> paths = {""};
+    for (const std::string& component : base::SplitString(glob, "/")) {
+      std::vector<std::string> matches;
+      for (const std::string& path : paths) {
+        if (!IsGlob(component)) {
+          matches.push_back(path + "/" + component);
+          continue;
+        }
+        base::ScopedDir dir(opendir((cgroup_root_dir_ + path).c_str()));
+        if (!dir)
+          continue;
+        while (struct dirent* ent = readdir(*dir)) {
+          if (ent->d_type != DT_DIR)
+            continue;
+          if (fnmatch(component.c_str(), ent->d_name, FNM_PERIOD) == 0)
+            matches.push_back(path + "/" + ent->d_name);
+        }
+      }
+      paths = std::move(matches);
+    }
+    for (const std::string& path : paths) {
+      // Check that the directory exists, as components without wildcards are
+      // not matched against the filesystem.
+      if (base::FileExists(cgroup_root_dir_ + path))
+        cgroups.insert(path.empty() ? "/" : path);
+    }
+  }
+  return std::vector<std::string>(cgroups.begin(), cgroups.end());
+}
+
+void CgroupStatsDataSource::ReadCgroupStats() {
+  auto packet = writer_->NewTracePacket();
+  packet->set_timestamp(static_cast<uint64_t>(base::GetBootTimeNs().count()));
+  auto* cgroup_stats = packet->set_cgroup_stats();
+
+  for (const std::string& path : ListCgroups()) {
+    std::string dir = path == "/" ? cgroup_root_dir_ : cgroup_root_dir_ + path;
+    auto* cgroup = cgroup_stats->add_cgroups();
+    cgroup->set_path(path);
+
+    if (cpu_ticks_ && tick_ % cpu_ticks_ == 0)
+      ReadCpuStat(dir, cgroup);
+
+    if (memory_ticks_ && tick_ % memory_ticks_ == 0)
+      ReadMemoryStats(dir, cgroup);
+
+    if (io_ticks_ && tick_ % io_ticks_ == 0)
+      ReadIoStat(dir, cgroup);
+
+    if (pressure_ticks_ && tick_ % pressure_ticks_ == 0)
+      ReadPressure(dir, cgroup);
+  }
+
+  tick_++;
+}
+
+void CgroupStatsDataSource::ReadCpuStat(const std::string& dir,
+                                        Cgroup* cgroup) {
+  std::string contents;
+  if (!base::ReadFile(dir + "/cpu.stat", &contents))
+    return;
+  ForEachKeyValue(std::move(contents),
+                  [cgroup](base::StringView key, uint64_t value) {
+    // The times in cpu.stat are in microseconds.
+    if (key == "usage_usec") {
+      cgroup->set_cpu_usage_ns(value * 1000);
+    } else if (key == "user_usec") {
+      cgroup->set_cpu_user_ns(value * 1000);
+    } else if (key == "system_usec") {
+      cgroup->set_cpu_system_ns(value * 1000);
+    } else if (key == "nr_periods") {
+      cgroup->set_cpu_nr_periods(value);
+    } else if (key == "nr_throttled") {
+      cgroup->set_cpu_nr_throttled(value);
+    } else if (key == "throttled_usec") {
+      cgroup->set_cpu_throttled_ns(value * 1000);
+    }
+  });
+}
+
+void CgroupStatsDataSource::ReadMemoryStats(const std::string& dir,
+                                            Cgroup* cgroup) {
+  std::string contents;
+  if (base::ReadFile(dir + "/memory.current", &contents)) {
+    std::optional<uint64_t> current =
+        base::StringToUInt64(base::StripSuffix(contents, "\n"));
+    if (current)
+      cgroup->set_memory_current_bytes(*current);
+  }
+
+  contents.clear();
+  if (!base::ReadFile(dir + "/memory.stat", &contents))
+    return;
+  ForEachKeyValue(std::move(contents),
+                  [this, cgroup](base::StringView key, uint64_t value) {
+    if (!memory_stat_keys_.empty() &&
+        memory_stat_keys_.count(key.ToStdString()) == 0) {
+      return;
+    }
+    auto* memory_stat = cgroup->add_memory_stat();
+    memory_stat->set_key(key.data(), key.size());
+    memory_stat->set_value(value);
+  });
+}
+
+void CgroupStatsDataSource::ReadIoStat(const std::string& dir,
+                                       Cgroup* cgroup) {
+  std::string contents;
+  if (!base::ReadFile(dir + "/io.stat", &contents))
+    return;
+
+  // Each line is of the form:
+  //     8:0 rbytes=90112 wbytes=0 rios=4 wios=0 dbytes=0 dios=0
+  for (base::StringSplitter lines(std::move(contents), '\n'); lines.Next();) {
+    base::StringSplitter words(&lines, ' ');
+    if (!words.Next())
+      continue;
+    base::StringSplitter device(&words, ':');
+    std::optional<uint32_t> major;
+    std::optional<uint32_t> minor;
+    if (device.Next())
+      major = base::CStringToUInt32(device.cur_token());
+    if (device.Next())
+      minor = base::CStringToUInt32(device.cur_token());
+    if (!major || !minor)
+      continue;
+
+    auto* io_stat = cgroup->add_io_stat();
+    io_stat->set_major(*major);
+    io_stat->set_minor(*minor);
+    while (words.Next()) {
+      base::StringSplitter key_value(&words, '=');
+      if (!key_value.Next())
+        continue;
+      base::StringView key(key_value.cur_token(), key_value.cur_token_size());
+      if (!key_value.Next())
+        continue;
+      std::optional<uint64_t> value =
+          base::CStringToUInt64(key_value.cur_token());
+      if (!value)
+        continue;
+      if (key == "rbytes") {
+        io_stat->set_read_bytes(*value);
+      } else if (key == "wbytes") {
+        io_stat->set_write_bytes(*value);
+      } else if (key == "rios") {
+        io_stat->set_read_ios(*value);
+      } else if (key == "wios") {
+        io_stat->set_write_ios(*value);
+      } else if (key == "dbytes") {
+        io_stat->set_discard_bytes(*value);
+      } else if (key == "dios") {
+        io_stat->set_discard_ios(*value);
+      }
+    }
+  }
+}
+
+void CgroupStatsDataSource::ReadPressure(const std::string& dir,
+                                         Cgroup* cgroup) {
+  using Pressure = CgroupStats::Pressure;
+
+  auto read_pressure = [&dir](const char* file_name, auto add_pressure) {
+    std::string contents;
+    if (!base::ReadFile(dir + "/" + file_name, &contents))
+      return;
+    Pressure* pressure = add_pressure();
+
+    // Each line is of the form:
+    //     some avg10=0.00 avg60=0.00 avg300=0.00 total=0
+    for (base::StringSplitter lines(std::move(contents), '\n');
+         lines.Next();) {
+      base::StringSplitter words(&lines, ' ');
+      if (!words.Next())
+        continue;
+      base::StringView kind(words.cur_token(), words.cur_token_size());
+      while (words.Next()) {
+        base::StringView token(words.cur_token(), words.cur_token_size());
+        const base::StringView prefix("total=");
+        if (!token.StartsWith(prefix))
+          continue;
+        // The raw PSI total readings are in micros, so convert accordingly.
+        std::optional<uint64_t> total_us =
+            base::CStringToUInt64(token.substr(prefix.size()).data());
+        if (!total_us)
+          break;
+        if (kind == "some") {
+          pressure->set_some_total_ns(*total_us * 1000);
+        } else if (kind == "full") {
+          pressure->set_full_total_ns(*total_us * 1000);
+        }
+        break;
+      }
+    }
+  };
+
+  read_pressure("cpu.pressure",
+                [cgroup] { return cgroup->set_cpu_pressure(); });
+  read_pressure("memory.pressure",
+                [cgroup] { return cgroup->set_memory_pressure(); });
+  read_pressure("io.pressure", [cgroup] { return cgroup->set_io_pressure(); });
+}
+
+void CgroupStatsDataSource::Flush(FlushRequestID,
+                                  std::function<void()> callback) {
+  writer_->Flush(callback);
+}
+
+base::WeakPtr<CgroupStatsDataSource> CgroupStatsDataSource::GetWeakPtr()
+    const {
+  return weak_factory_.GetWeakPtr();
+}
+
+}  // namespace perfetto
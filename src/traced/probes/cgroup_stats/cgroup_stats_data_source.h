@@ -0,0 +1,101 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACED_PROBES_CGROUP_STATS_CGROUP_STATS_DATA_SOURCE_H_
+#define SRC_TRACED_PROBES_CGROUP_STATS_CGROUP_STATS_DATA_SOURCE_H_
+
+#include <functional>
+#include <memory>
+#include <set>
+#include <string>
+#include <vector>
+
+#include "perfetto/ext/base/weak_ptr.h"
+#include "perfetto/ext/tracing/core/basic_types.h"
+#include "perfetto/tracing/core/data_source_config.h"
+#include "src/traced/probes/probes_data_source.h"
+
+namespace perfetto {
+
+class TraceWriter;
+namespace base {
+class TaskRunner;
+}
+
+namespace protos {
+namespace pbzero {
+class CgroupStats_Cgroup;
+}
+}  // namespace protos
+
+// Polls the resource usage statistics (cpu.stat, memory.current, memory.stat,
+// io.stat and *.pressure) of the cgroup v2 control groups matching the globs
+// of the config.
+class CgroupStatsDataSource : public ProbesDataSource {
+ public:
+  static const ProbesDataSource::Descriptor descriptor;
+
+  CgroupStatsDataSource(const DataSourceConfig&,
+                        base::TaskRunner*,
+                        TracingSessionID,
+                        std::unique_ptr<TraceWriter> writer,
+                        const char* cgroup_root_dir = "/sys/fs/cgroup");
+  ~CgroupStatsDataSource() override;
+
+  // ProbesDataSource implementation.
+  void Start() override;
+  void Flush(FlushRequestID, std::function<void()> callback) override;
+
+  base::WeakPtr<CgroupStatsDataSource> GetWeakPtr() const;
+
+  // Returns the paths (relative to the cgroup root, e.g. "/foo/bar") of the
+  // cgroups matching the globs of the config, sorted and without duplicates.
+  std::vector<std::string> ListCgroups() const;
+
+  uint32_t tick_for_testing() const { return tick_; }
+
+ private:
+  using Cgroup = protos::pbzero::CgroupStats_Cgroup;
+
+  static void Tick(base::WeakPtr<CgroupStatsDataSource>);
+
+  CgroupStatsDataSource(const CgroupStatsDataSource&) = delete;
+  CgroupStatsDataSource& operator=(const CgroupStatsDataSource&) = delete;
+
+  void ReadCgroupStats();
+  void ReadCpuStat(const std::string& dir, Cgroup*);
+  void ReadMemoryStats(const std::string& dir, Cgroup*);
+  void ReadIoStat(const std::string& dir, Cgroup*);
+  void ReadPressure(const std::string& dir, Cgroup*);
+
+  base::TaskRunner* const task_runner_;
+  std::unique_ptr<TraceWriter> writer_;
+  const std::string cgroup_root_dir_;
+  std::vector<std::string> cgroup_path_globs_;
+  std::set<std::string> memory_stat_keys_;
+  uint32_t tick_ = 0;
+  uint32_t tick_period_ms_ = 0;
+  uint32_t cpu_ticks_ = 0;
+  uint32_t memory_ticks_ = 0;
+  uint32_t io_ticks_ = 0;
+  uint32_t pressure_ticks_ = 0;
+
+  base::WeakPtrFactory<CgroupStatsDataSource> weak_factory_;  // Keep last.
+};
+
+}  // namespace perfetto
+
+#endif  // SRC_TRACED_PROBES_CGROUP_STATS_CGROUP_STATS_DATA_SOURCE_H_
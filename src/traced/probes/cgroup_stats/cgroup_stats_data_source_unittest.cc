@@ -0,0 +1,188 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/traced/probes/cgroup_stats/cgroup_stats_data_source.h"
+
+#include "src/base/test/test_task_runner.h"
+#include "src/base/test/tmp_dir_tree.h"
+#include "src/tracing/core/trace_writer_for_testing.h"
+#include "test/gtest_and_gmock.h"
+
+#include "protos/perfetto/config/sys_stats/cgroup_stats_config.gen.h"
+#include "protos/perfetto/trace/sys_stats/cgroup_stats.gen.h"
+
+using ::testing::ElementsAre;
+using ::testing::Pair;
+using ::testing::UnorderedElementsAre;
+
+namespace perfetto {
+namespace {
+
+class CgroupStatsDataSourceTest : public ::testing::Test {
+ protected:
+  std::unique_ptr<CgroupStatsDataSource> GetCgroupStatsDataSource(
+      const protos::gen::CgroupStatsConfig& cgroup_cfg) {
+    DataSourceConfig config;
+    config.set_cgroup_stats_config_raw(cgroup_cfg.SerializeAsString());
+    auto writer = std::make_unique<TraceWriterForTesting>();
+    writer_raw_ = writer.get();
+    return std::make_unique<CgroupStatsDataSource>(
+        config, &task_runner_, 0, std::move(writer), tmpdir_.path().c_str());
+  }
+
+  void Poller(CgroupStatsDataSource* ds, std::function<void()> checkpoint) {
+    if (ds->tick_for_testing())
+      checkpoint();
+    else
+      task_runner_.PostDelayedTask(
+          [ds, checkpoint, this] { Poller(ds, checkpoint); }, 1);
+  }
+
+  void WaitTick(CgroupStatsDataSource* data_source) {
+    auto checkpoint = task_runner_.CreateCheckpoint("on_tick");
+    Poller(data_source, checkpoint);
+    task_runner_.RunUntilCheckpoint("on_tick");
+  }
+
+  base::TmpDirTree tmpdir_;
+  TraceWriterForTesting* writer_raw_ = nullptr;
+  base::TestTaskRunner task_runner_;
+};
+
+TEST_F(CgroupStatsDataSourceTest, ListCgroups) {
+  tmpdir_.AddDir("system.slice");
+  tmpdir_.AddDir("system.slice/a.service");
+  tmpdir_.AddDir("system.slice/b.service");
+  tmpdir_.AddDir("system.slice/c.scope");
+  tmpdir_.AddFile("system.slice/d.service", "");  // Not a directory.
+  tmpdir_.AddDir("user.slice");
+
+  protos::gen::CgroupStatsConfig cfg;
+  cfg.add_cgroup_path_globs("/");
+  cfg.add_cgroup_path_globs("/system.slice/*.service");
+  cfg.add_cgroup_path_globs("system.slice/a.service");
+  cfg.add_cgroup_path_globs("/user.slice");
+  cfg.add_cgroup_path_globs("/does_not_exist");
+  cfg.add_cgroup_path_globs("/*/does_not_exist");
+  auto data_source = GetCgroupStatsDataSource(cfg);
+
+  EXPECT_THAT(data_source->ListCgroups(),
+              ElementsAre("/", "/system.slice/a.service",
+                          "/system.slice/b.service", "/user.slice"));
+}
+
+TEST_F(CgroupStatsDataSourceTest, CpuAndMemory) {
+  tmpdir_.AddDir("app");
+  tmpdir_.AddFile("app/cpu.stat",
+                  "usage_usec 1500\n"
+                  "user_usec 1000\n"
+                  "system_usec 500\n"
+                  "nr_periods 10\n"
+                  "nr_throttled 2\n"
+                  "throttled_usec 30\n");
+  tmpdir_.AddFile("app/memory.current", "4096\n");
+  tmpdir_.AddFile("app/memory.stat",
+                  "anon 1024\n"
+                  "file 2048\n"
+                  "kernel 512\n");
+
+  protos::gen::CgroupStatsConfig cfg;
+  cfg.add_cgroup_path_globs("/app");
+  cfg.set_cpu_period_ms(10);
+  cfg.set_memory_period_ms(10);
+  cfg.add_memory_stat_keys("anon");
+  cfg.add_memory_stat_keys("file");
+  auto data_source = GetCgroupStatsDataSource(cfg);
+  data_source->Start();
+
+  WaitTick(data_source.get());
+
+  protos::gen::TracePacket packet = writer_raw_->GetOnlyTracePacket();
+  ASSERT_TRUE(packet.has_cgroup_stats());
+  ASSERT_EQ(packet.cgroup_stats().cgroups_size(), 1);
+  const auto& cgroup = packet.cgroup_stats().cgroups()[0];
+  EXPECT_EQ(cgroup.path(), "/app");
+  EXPECT_EQ(cgroup.cpu_usage_ns(), 1500000u);
+  EXPECT_EQ(cgroup.cpu_user_ns(), 1000000u);
+  EXPECT_EQ(cgroup.cpu_system_ns(), 500000u);
+  EXPECT_EQ(cgroup.cpu_nr_periods(), 10u);
+  EXPECT_EQ(cgroup.cpu_nr_throttled(), 2u);
+  EXPECT_EQ(cgroup.cpu_throttled_ns(), 30000u);
+  EXPECT_EQ(cgroup.memory_current_bytes(), 4096u);
+
+  std::vector<std::pair<std::string, uint64_t>> memory_stat;
+  for (const auto& kv : cgroup.memory_stat())
+    memory_stat.emplace_back(kv.key(), kv.value());
+  EXPECT_THAT(memory_stat,
+              UnorderedElementsAre(Pair("anon", 1024u), Pair("file", 2048u)));
+
+  EXPECT_EQ(cgroup.io_stat_size(), 0);
+  EXPECT_FALSE(cgroup.has_cpu_pressure());
+}
+
+TEST_F(CgroupStatsDataSourceTest, IoAndPressure) {
+  tmpdir_.AddDir("app");
+  tmpdir_.AddFile("app/io.stat",
+                  "8:0 rbytes=90112 wbytes=4096 rios=4 wios=1 dbytes=0 "
+                  "dios=0\n"
+                  "259:1 rbytes=10 wbytes=20 rios=1 wios=2 dbytes=30 dios=3\n");
+  tmpdir_.AddFile("app/cpu.pressure",
+                  "some avg10=0.00 avg60=0.00 avg300=0.00 total=1200\n"
+                  "full avg10=0.00 avg60=0.00 avg300=0.00 total=300\n");
+  tmpdir_.AddFile("app/memory.pressure",
+                  "some avg10=0.00 avg60=0.00 avg300=0.00 total=7\n"
+                  "full avg10=0.00 avg60=0.00 avg300=0.00 total=5\n");
+  // io.pressure is missing: the io controller is not enabled.
+
+  protos::gen::CgroupStatsConfig cfg;
+  cfg.add_cgroup_path_globs("/app");
+  cfg.set_io_period_ms(10);
+  cfg.set_pressure_period_ms(10);
+  auto data_source = GetCgroupStatsDataSource(cfg);
+  data_source->Start();
+
+  WaitTick(data_source.get());
+
+  protos::gen::TracePacket packet = writer_raw_->GetOnlyTracePacket();
+  ASSERT_TRUE(packet.has_cgroup_stats());
+  ASSERT_EQ(packet.cgroup_stats().cgroups_size(), 1);
+  const auto& cgroup = packet.cgroup_stats().cgroups()[0];
+  EXPECT_FALSE(cgroup.has_cpu_usage_ns());
+  EXPECT_FALSE(cgroup.has_memory_current_bytes());
+
+  ASSERT_EQ(cgroup.io_stat_size(), 2);
+  const auto& sda = cgroup.io_stat()[0];
+  EXPECT_EQ(sda.major(), 8u);
+  EXPECT_EQ(sda.minor(), 0u);
+  EXPECT_EQ(sda.read_bytes(), 90112u);
+  EXPECT_EQ(sda.write_bytes(), 4096u);
+  EXPECT_EQ(sda.read_ios(), 4u);
+  EXPECT_EQ(sda.write_ios(), 1u);
+  const auto& nvme = cgroup.io_stat()[1];
+  EXPECT_EQ(nvme.major(), 259u);
+  EXPECT_EQ(nvme.minor(), 1u);
+  EXPECT_EQ(nvme.discard_bytes(), 30u);
+  EXPECT_EQ(nvme.discard_ios(), 3u);
+
+  EXPECT_EQ(cgroup.cpu_pressure().some_total_ns(), 1200000u);
+  EXPECT_EQ(cgroup.cpu_pressure().full_total_ns(), 300000u);
+  EXPECT_EQ(cgroup.memory_pressure().some_total_ns(), 7000u);
+  EXPECT_EQ(cgroup.memory_pressure().full_total_ns(), 5000u);
+  EXPECT_FALSE(cgroup.has_io_pressure());
+}
+
+}  // namespace
+}  // namespace perfetto
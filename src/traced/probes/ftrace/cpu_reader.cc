@@ -691,11 +691,12 @@ protos::pbzero::FtraceParseStatus CpuReader::ParsePagePayload(
           const CompactSchedWakingFormat& sched_waking_format =
               table->compact_sched_format().sched_waking;
 
-          // Special-cased filtering of ftrace/print events to retain only the
-          // matching events.
+          // Special-cased filtering of ftrace/print and futex sys_enter events
+          // to retain only the matching events.
           bool event_written = true;
           bool ftrace_print_filter_enabled =
               ds_config->print_filter.has_value();
+          bool futex_filter_enabled = ds_config->futex_filter.has_value();
 
           if (compact_sched_enabled &&
               ftrace_event_id == sched_switch_format.event_id) {
@@ -725,6 +726,12 @@ protos::pbzero::FtraceParseStatus CpuReader::ParsePagePayload(
             } else {  // print event did NOT pass the filter
               event_written = false;
             }
+          } else if (futex_filter_enabled &&
+                     ftrace_event_id == ds_config->futex_filter->event_id() &&
+                     !ds_config->futex_filter->IsEventInteresting(start,
+                                                                  next)) {
+            // futex sys_enter event did NOT pass the filter
+            event_written = false;
           } else {
             // Common case: parse all other types of enabled events.
             protos::pbzero::FtraceEvent* event =
@@ -832,6 +832,7 @@ FtraceDataSourceConfig ConfigForTesting(CompactSchedConfig compact_cfg) {
       /*event_filter=*/EventFilter{},
       /*syscall_filter=*/EventFilter{}, compact_cfg,
       /*print_filter=*/std::nullopt,
+      /*futex_filter=*/std::nullopt,
       /*atrace_apps=*/{},
       /*atrace_categories=*/{},
       /*atrace_categories_sdk_optout=*/{},
@@ -54,6 +54,7 @@ void FuzzCpuReaderProcessPagesForDataSource(const uint8_t* data, size_t size) {
       /*event_filter=*/EventFilter{},
       /*syscall_filter=*/EventFilter{}, DisabledCompactSchedConfigForTesting(),
       /*print_filter=*/std::nullopt,
+      /*futex_filter=*/std::nullopt,
       /*atrace_apps=*/{},
       /*atrace_categories=*/{},
       /*atrace_categories_prefer_track_event=*/{},
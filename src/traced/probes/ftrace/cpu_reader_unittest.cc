@@ -30,6 +30,7 @@
 #include "src/traced/probes/ftrace/compact_sched.h"
 #include "src/traced/probes/ftrace/event_info.h"
 #include "src/traced/probes/ftrace/ftrace_config_muxer.h"
+#include "src/traced/probes/ftrace/ftrace_futex_filter.h"
 #include "src/traced/probes/ftrace/proto_translation_table.h"
 #include "src/traced/probes/ftrace/test/cpu_reader_support.h"
 #include "src/traced/probes/ftrace/tracefs.h"
@@ -87,6 +88,7 @@ FtraceDataSourceConfig ConfigForTesting(
       /*event_filter=*/EventFilter{},
       /*syscall_filter=*/EventFilter{}, compact_cfg,
       /*print_filter=*/std::nullopt,
+      /*futex_filter=*/std::nullopt,
       /*atrace_apps=*/{},
       /*atrace_categories=*/{},
       /*atrace_categories_sdk_optout=*/{},
@@ -1601,6 +1603,82 @@ TEST(CpuReaderTest, MAYBE_SysExitEvent) {
   EXPECT_THAT(metadata.fds, Contains(std::make_pair(kPid, kFd)));
 }
 
+#if PERFETTO_BUILDFLAG(PERFETTO_OS_APPLE)
+#define MAYBE_FutexFilter DISABLED_FutexFilter
+#else
+#define MAYBE_FutexFilter FutexFilter
+#endif
+TEST_F(CpuReaderParsePagePayloadTest, MAYBE_FutexFilter) {
+  ProtoTranslationTable* table = GetTable("synthetic");
+  const auto syscalls = SyscallTable::FromCurrentArch();
+  const std::optional<size_t> futex_id = syscalls.GetByName("sys_futex");
+  const std::optional<size_t> close_id = syscalls.GetByName("sys_close");
+  ASSERT_TRUE(futex_id.has_value());
+  ASSERT_TRUE(close_id.has_value());
+  const auto kSysEnterId = static_cast<uint16_t>(
+      table->EventToFtraceId(GroupAndName("raw_syscalls", "sys_enter")));
+  ASSERT_GT(kSysEnterId, 0ul);
+
+  constexpr uint64_t kFutexPrivate = 128;
+  constexpr uint64_t kFutexWait = 0;
+  constexpr uint64_t kFutexWake = 1;
+  constexpr uint64_t kFutexCmpRequeue = 4;
+  const std::vector<std::pair<int64_t, uint64_t>> syscall_and_op = {
+      {static_cast<int64_t>(*futex_id), kFutexWait | kFutexPrivate},
+      {static_cast<int64_t>(*futex_id), kFutexCmpRequeue | kFutexPrivate},
+      {static_cast<int64_t>(*futex_id), kFutexWake},
+      {static_cast<int64_t>(*close_id), kFutexCmpRequeue},
+  };
+
+  // A page payload with one raw_syscalls/sys_enter event per syscall.
+  BinaryWriter writer;
+  for (const auto& [syscall, op] : syscall_and_op) {
+    constexpr uint32_t kEventSize = 64;
+    writer.Write<uint32_t>(kEventSize / 4);  // Event header: length, delta 0.
+    writer.Write<uint16_t>(kSysEnterId);     // Common type.
+    writer.Write<uint16_t>(0);               // Common flags and preempt count.
+    writer.Write<int32_t>(23);               // Common pid.
+    writer.Write<int64_t>(syscall);          // id
+    writer.Write<uint64_t>(0x1000);          // args: uaddr
+    writer.Write<uint64_t>(op);              // op
+    for (uint32_t i = 2; i < 6; ++i) {
+      writer.Write<uint64_t>(0);
+    }
+  }
+  auto page = writer.GetCopy();
+  CpuReader::PageHeader page_header{/*timestamp=*/1000,
+                                    /*size=*/writer.written(),
+                                    /*lost_events=*/false};
+
+  FtraceDataSourceConfig ds_config = EmptyConfig();
+  ds_config.event_filter.AddEnabledEvent(kSysEnterId);
+  ds_config.futex_filter = FtraceFutexFilterConfig::Create(
+      {"FUTEX_WAIT", "FUTEX_WAKE"}, syscalls, table);
+  ASSERT_TRUE(ds_config.futex_filter.has_value());
+
+  FtraceParseStatus status = CpuReader::ParsePagePayload(
+      page.get(), &page_header, table, &ds_config, CreateBundler(ds_config),
+      &metadata_, &last_read_event_ts_);
+  EXPECT_EQ(status, FtraceParseStatus::FTRACE_STATUS_OK);
+
+  // The FUTEX_CMP_REQUEUE call is dropped, the other syscalls are kept.
+  std::vector<std::pair<int64_t, uint64_t>> parsed;
+  for (const auto& event : GetBundle().event()) {
+    ASSERT_TRUE(event.has_sys_enter());
+    parsed.emplace_back(event.sys_enter().id(), event.sys_enter().args()[1]);
+  }
+  EXPECT_THAT(parsed, ElementsAre(syscall_and_op[0], syscall_and_op[2],
+                                  syscall_and_op[3]));
+}
+
+TEST(CpuReaderTest, FutexFilterUnknownOp) {
+  ProtoTranslationTable* table = GetTable("synthetic");
+  EXPECT_FALSE(FtraceFutexFilterConfig::Create(
+                   {"FUTEX_WAIT", "FUTEX_SLEEP"},
+                   SyscallTable::FromCurrentArch(), table)
+                   .has_value());
+}
+
 TEST(CpuReaderTest, TaskRenameEvent) {
   BundleProvider bundle_provider(base::GetSysPageSize());
 
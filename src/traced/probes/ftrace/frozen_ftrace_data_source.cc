@@ -115,6 +115,7 @@ void FrozenFtraceDataSource::Start() {
           /*syscall_filter=*/EventFilter{},
           /*compact_sched_in=*/CompactSchedConfig{false},
           /*print_filter=*/std::nullopt,
+          /*futex_filter=*/std::nullopt,
           /*atrace_apps=*/{},
           /*atrace_categories=*/{},
           /*atrace_categories_sdk_optout=*/{},
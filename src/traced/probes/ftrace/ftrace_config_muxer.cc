@@ -586,6 +586,18 @@ bool FtraceConfigMuxer::SetupConfig(FtraceConfigId id,
     }
   }
 
+  std::optional<FtraceFutexFilterConfig> futex_filter;
+  if (!request.futex_ops().empty()) {
+    futex_filter =
+        FtraceFutexFilterConfig::Create(request.futex_ops(), syscalls_, table_);
+    if (!futex_filter.has_value()) {
+      if (errors) {
+        errors->failed_ftrace_events.emplace_back(
+            "raw_syscalls/sys_enter (cannot filter futex_ops)");
+      }
+    }
+  }
+
   std::vector<std::string> apps(request.atrace_apps());
   std::vector<std::string> categories(request.atrace_categories());
   std::vector<std::string> categories_sdk_optout = Subtract(
@@ -594,11 +606,12 @@ bool FtraceConfigMuxer::SetupConfig(FtraceConfigId id,
       std::piecewise_construct, std::forward_as_tuple(id),
       std::forward_as_tuple(
           std::move(filter), std::move(syscall_filter), compact_sched,
-          std::move(ftrace_print_filter), std::move(apps),
-          std::move(categories), std::move(categories_sdk_optout),
-          request.symbolize_ksyms(), request.drain_buffer_percent(),
-          GetSyscallsReturningFds(syscalls_), std::move(kprobes),
-          request.debug_ftrace_abi(), request.denser_generic_event_encoding()));
+          std::move(ftrace_print_filter), std::move(futex_filter),
+          std::move(apps), std::move(categories),
+          std::move(categories_sdk_optout), request.symbolize_ksyms(),
+          request.drain_buffer_percent(), GetSyscallsReturningFds(syscalls_),
+          std::move(kprobes), request.debug_ftrace_abi(),
+          request.denser_generic_event_encoding()));
   return true;
 }
 
@@ -26,6 +26,7 @@
 #include "src/traced/probes/ftrace/atrace_wrapper.h"
 #include "src/traced/probes/ftrace/compact_sched.h"
 #include "src/traced/probes/ftrace/ftrace_config_utils.h"
+#include "src/traced/probes/ftrace/ftrace_futex_filter.h"
 #include "src/traced/probes/ftrace/ftrace_print_filter.h"
 #include "src/traced/probes/ftrace/proto_translation_table.h"
 #include "src/traced/probes/ftrace/tracefs.h"
@@ -53,6 +54,7 @@ struct FtraceDataSourceConfig {
       EventFilter syscall_filter_in,
       CompactSchedConfig compact_sched_in,
       std::optional<FtracePrintFilterConfig> print_filter_in,
+      std::optional<FtraceFutexFilterConfig> futex_filter_in,
       std::vector<std::string> atrace_apps_in,
       std::vector<std::string> atrace_categories_in,
       std::vector<std::string> atrace_categories_sdk_optout_in,
@@ -67,6 +69,7 @@ struct FtraceDataSourceConfig {
         syscall_filter(std::move(syscall_filter_in)),
         compact_sched(compact_sched_in),
         print_filter(std::move(print_filter_in)),
+        futex_filter(std::move(futex_filter_in)),
         atrace_apps(std::move(atrace_apps_in)),
         atrace_categories(std::move(atrace_categories_in)),
         atrace_categories_sdk_optout(
@@ -93,6 +96,10 @@ struct FtraceDataSourceConfig {
   // the content of their "buf" field.
   std::optional<FtracePrintFilterConfig> print_filter;
 
+  // Optional configuration that's used to filter the "raw_syscalls/sys_enter"
+  // events of futex calls based on their operation.
+  std::optional<FtraceFutexFilterConfig> futex_filter;
+
   // Used only in Android for ATRACE_EVENT/os.Trace() userspace annotations.
   std::vector<std::string> atrace_apps;
   std::vector<std::string> atrace_categories;
@@ -0,0 +1,139 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/traced/probes/ftrace/ftrace_futex_filter.h"
+
+#include <string.h>
+
+#include <algorithm>
+#include <iterator>
+
+#include "perfetto/base/logging.h"
+
+namespace perfetto {
+namespace {
+
+// From include/uapi/linux/futex.h.
+struct FutexOp {
+  const char* name;
+  uint32_t op;
+};
+constexpr FutexOp kFutexOps[] = {
+    {"FUTEX_WAIT", 0},
+    {"FUTEX_WAKE", 1},
+    {"FUTEX_FD", 2},
+    {"FUTEX_REQUEUE", 3},
+    {"FUTEX_CMP_REQUEUE", 4},
+    {"FUTEX_WAKE_OP", 5},
+    {"FUTEX_LOCK_PI", 6},
+    {"FUTEX_UNLOCK_PI", 7},
+    {"FUTEX_TRYLOCK_PI", 8},
+    {"FUTEX_WAIT_BITSET", 9},
+    {"FUTEX_WAKE_BITSET", 10},
+    {"FUTEX_WAIT_REQUEUE_PI", 11},
+    {"FUTEX_CMP_REQUEUE_PI", 12},
+    {"FUTEX_LOCK_PI2", 13},
+};
+
+constexpr uint32_t kFutexPrivateFlag = 128;
+constexpr uint32_t kFutexClockRealtime = 256;
+
+}  // namespace
+
+// static
+std::optional<FtraceFutexFilterConfig> FtraceFutexFilterConfig::Create(
+    const std::vector<std::string>& ops,
+    const SyscallTable& syscalls,
+    ProtoTranslationTable* table) {
+  FtraceFutexFilterConfig ret;
+  for (const std::string& name : ops) {
+    auto it = std::find_if(
+        std::begin(kFutexOps), std::end(kFutexOps),
+        [&name](const FutexOp& op) { return name == op.name; });
+    if (it == std::end(kFutexOps)) {
+      PERFETTO_ELOG("Unknown futex operation: %s", name.c_str());
+      return std::nullopt;
+    }
+    ret.ops_.insert(it->op);
+  }
+
+  // futex_time64 is the variant with a 64-bit timeout of 32-bit architectures.
+  for (const char* name : {"sys_futex", "sys_futex_time64"}) {
+    std::optional<size_t> id = syscalls.GetByName(name);
+    if (id)
+      ret.futex_syscall_ids_.insert(static_cast<int64_t>(*id));
+  }
+  if (ret.futex_syscall_ids_.empty())
+    return std::nullopt;
+
+  const Event* sys_enter =
+      table->GetEvent(GroupAndName("raw_syscalls", "sys_enter"));
+  if (!sys_enter || sys_enter->fields.size() != 2)
+    return std::nullopt;
+
+  // field:long id;
+  const Field& id_field = sys_enter->fields[0];
+  if (id_field.ftrace_type != kFtraceInt32 &&
+      id_field.ftrace_type != kFtraceInt64) {
+    return std::nullopt;
+  }
+  // field:unsigned long args[6];
+  const Field& args_field = sys_enter->fields[1];
+  uint16_t arg_size;
+  if (args_field.ftrace_type == kFtraceUint32) {
+    arg_size = 4;
+  } else if (args_field.ftrace_type == kFtraceUint64) {
+    arg_size = 8;
+  } else {
+    return std::nullopt;
+  }
+
+  ret.event_id_ = sys_enter->ftrace_event_id;
+  ret.event_size_ = sys_enter->size;
+  ret.id_offset_ = id_field.ftrace_offset;
+  ret.id_type_ = id_field.ftrace_type;
+  // The op is the second argument: futex(uaddr, op, ...). Only its low 32
+  // bits are meaningful.
+  ret.op_offset_ = static_cast<uint16_t>(args_field.ftrace_offset + arg_size);
+  return ret;
+}
+
+bool FtraceFutexFilterConfig::IsEventInteresting(const uint8_t* start,
+                                                 const uint8_t* end) const {
+  PERFETTO_DCHECK(start < end);
+  // If the end of the buffer is before the end of the event, give up.
+  if (event_size_ > static_cast<size_t>(end - start)) {
+    PERFETTO_DFATAL("Buffer overflowed.");
+    return true;
+  }
+
+  int64_t id;
+  if (id_type_ == kFtraceInt32) {
+    int32_t value;
+    memcpy(&value, start + id_offset_, sizeof(value));
+    id = value;
+  } else {
+    memcpy(&id, start + id_offset_, sizeof(id));
+  }
+  if (!futex_syscall_ids_.count(id))
+    return true;
+
+  uint32_t op;
+  memcpy(&op, start + op_offset_, sizeof(op));
+  return ops_.count(op & ~(kFutexPrivateFlag | kFutexClockRealtime)) > 0;
+}
+
+}  // namespace perfetto
@@ -0,0 +1,73 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACED_PROBES_FTRACE_FTRACE_FUTEX_FILTER_H_
+#define SRC_TRACED_PROBES_FTRACE_FTRACE_FUTEX_FILTER_H_
+
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <vector>
+
+#include "perfetto/base/flat_set.h"
+#include "src/kernel_utils/syscall_table.h"
+#include "src/traced/probes/ftrace/event_info_constants.h"
+#include "src/traced/probes/ftrace/proto_translation_table.h"
+
+namespace perfetto {
+
+// Filters the "raw_syscalls/sys_enter" events of futex(2) calls by operation
+// (FtraceConfig.futex_ops). The kernel can't do this as raw_syscalls records
+// the arguments of the syscall as an array, which event filters don't
+// support.
+//
+// The "raw_syscalls/sys_exit" events don't carry the operation, so the ones
+// of futex calls are all kept.
+class FtraceFutexFilterConfig {
+ public:
+  // Returns std::nullopt if any of |ops| is not a known futex operation name
+  // (e.g. "FUTEX_WAIT"), if the futex syscall is not known for the
+  // architecture or if "raw_syscalls/sys_enter" doesn't have the expected
+  // format.
+  static std::optional<FtraceFutexFilterConfig> Create(
+      const std::vector<std::string>& ops,
+      const SyscallTable& syscalls,
+      ProtoTranslationTable* table);
+
+  uint32_t event_id() const { return event_id_; }
+
+  // Returns true if the "raw_syscalls/sys_enter" event (encoded from `start`
+  // to `end`) should be allowed: it's not a futex call, its operation is one
+  // of the configured ones, or **there was a problem parsing it**. Returns
+  // false if the event should be ignored.
+  bool IsEventInteresting(const uint8_t* start, const uint8_t* end) const;
+
+ private:
+  FtraceFutexFilterConfig() = default;
+
+  base::FlatSet<int64_t> futex_syscall_ids_;
+  // Operations without the FUTEX_PRIVATE_FLAG and FUTEX_CLOCK_REALTIME flags.
+  base::FlatSet<uint32_t> ops_;
+  uint32_t event_id_ = 0;
+  uint16_t event_size_ = 0;
+  uint16_t id_offset_ = 0;
+  FtraceFieldType id_type_ = kInvalidFtraceFieldType;
+  uint16_t op_offset_ = 0;
+};
+
+}  // namespace perfetto
+
+#endif  // SRC_TRACED_PROBES_FTRACE_FTRACE_FUTEX_FILTER_H_
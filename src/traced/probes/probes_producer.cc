@@ -37,6 +37,7 @@
 #include "src/traced/probes/android_kernel_wakelocks/android_kernel_wakelocks_data_source.h"
 #include "src/traced/probes/android_log/android_log_data_source.h"
 #include "src/traced/probes/android_system_property/android_system_property_data_source.h"
+#include "src/traced/probes/cgroup_stats/cgroup_stats_data_source.h"
 #include "src/traced/probes/filesystem/inode_file_data_source.h"
 #include "src/traced/probes/ftrace/frozen_ftrace_data_source.h"
 #include "src/traced/probes/ftrace/ftrace_data_source.h"
@@ -49,6 +50,7 @@
 #include "src/traced/probes/ps/process_stats_data_source.h"
 #include "src/traced/probes/statsd_client/statsd_binder_data_source.h"
 #include "src/traced/probes/sys_stats/sys_stats_data_source.h"
+#include "src/traced/probes/syscall_latency/syscall_latency_data_source.h"
 #include "src/traced/probes/system_info/system_info_data_source.h"
 
 namespace perfetto {
@@ -271,6 +273,29 @@ ProbesProducer::CreateDSInstance<SysStatsDataSource>(
       config, std::make_unique<CpuFreqInfo>());
 }
 
+template <>
+std::unique_ptr<ProbesDataSource>
+ProbesProducer::CreateDSInstance<CgroupStatsDataSource>(
+    TracingSessionID session_id,
+    const DataSourceConfig& config) {
+  auto buffer_id = static_cast<BufferID>(config.target_buffer());
+  return std::make_unique<CgroupStatsDataSource>(
+      config, task_runner_, session_id,
+      endpoint_->CreateTraceWriter(buffer_id, BufferExhaustedPolicy::kStall));
+}
+
+template <>
+std::unique_ptr<ProbesDataSource>
+ProbesProducer::CreateDSInstance<SyscallLatencyDataSource>(
+    TracingSessionID session_id,
+    const DataSourceConfig& config) {
+  auto buffer_id = static_cast<BufferID>(config.target_buffer());
+  return std::make_unique<SyscallLatencyDataSource>(
+      config, task_runner_, session_id,
+      endpoint_->CreateTraceWriter(buffer_id, BufferExhaustedPolicy::kStall),
+      CreateKernelSyscallLatencyBpfLoader());
+}
+
 template <>
 std::unique_ptr<ProbesDataSource>
 ProbesProducer::CreateDSInstance<MetatraceDataSource>(
@@ -352,6 +377,7 @@ constexpr const DataSourceTraits kAllDataSources[] = {
     Ds<AndroidLogDataSource>(),
     Ds<AndroidPowerDataSource>(),
     Ds<AndroidSystemPropertyDataSource>(),
+    Ds<CgroupStatsDataSource>(),
     Ds<FrozenFtraceDataSource>(),
     Ds<FtraceDataSource>(),
     Ds<InitialDisplayStateDataSource>(),
@@ -364,6 +390,7 @@ constexpr const DataSourceTraits kAllDataSources[] = {
     Ds<StatsdBinderDataSource>(),
 #endif
     Ds<SysStatsDataSource>(),
+    Ds<SyscallLatencyDataSource>(),
     Ds<SystemInfoDataSource>(),
 };
 
@@ -17,12 +17,14 @@
 #include "src/traced/probes/sys_stats/sys_stats_data_source.h"
 
 #include <stdlib.h>
+#include <string.h>
 #include <unistd.h>
 #include <algorithm>
 #include <array>
 #include <bitset>
 #include <limits>
 #include <utility>
+#include <vector>
 
 #include "perfetto/base/task_runner.h"
 #include "perfetto/base/time.h"
@@ -95,6 +97,9 @@ SysStatsDataSource::SysStatsDataSource(
   psi_cpu_fd_ = open_fn("/proc/pressure/cpu");
   psi_io_fd_ = open_fn("/proc/pressure/io");
   psi_memory_fd_ = open_fn("/proc/pressure/memory");
+  netdev_fd_ = open_fn("/proc/net/dev");
+  net_snmp_fd_ = open_fn("/proc/net/snmp");
+  net_netstat_fd_ = open_fn("/proc/net/netstat");
   read_buf_ = base::PagedMemory::Allocate(kReadBufSize);
 
   // Build a lookup map that allows to quickly translate strings like "MemTotal"
@@ -140,14 +145,32 @@ SysStatsDataSource::SysStatsDataSource(
       vmstat_counters_.emplace(k.str, k.id);
   }
 
+  constexpr size_t kMaxNetstatEnum = protos::pbzero::NetstatCounters_MAX;
+  std::bitset<kMaxNetstatEnum + 1> netstat_counters_enabled{};
+  if (!cfg.has_netstat_counters())
+    netstat_counters_enabled.set();
+  for (auto it = cfg.netstat_counters(); it; ++it) {
+    uint32_t counter = static_cast<uint32_t>(*it);
+    if (counter > 0 && counter <= kMaxNetstatEnum) {
+      netstat_counters_enabled.set(counter);
+    } else {
+      PERFETTO_DFATAL("Netstat counter out of bounds %u", counter);
+    }
+  }
+  for (size_t i = 0; i < base::ArraySize(kNetstatKeys); i++) {
+    const auto& k = kNetstatKeys[i];
+    if (netstat_counters_enabled[static_cast<size_t>(k.id)])
+      netstat_counters_.emplace(k.str, k.id);
+  }
+
   if (!cfg.has_stat_counters())
     stat_enabled_fields_ = ~0u;
   for (auto counter = cfg.stat_counters(); counter; ++counter) {
     stat_enabled_fields_ |= 1ul << static_cast<uint32_t>(*counter);
   }
 
-  std::array<uint32_t, 11> periods_ms{};
-  std::array<uint32_t, 11> ticks{};
+  std::array<uint32_t, 13> periods_ms{};
+  std::array<uint32_t, 13> ticks{};
   static_assert(periods_ms.size() == ticks.size(), "must have same size");
 
   periods_ms[0] = ClampTo10Ms(cfg.meminfo_period_ms(), "meminfo_period_ms");
@@ -161,6 +184,8 @@ SysStatsDataSource::SysStatsDataSource(
   periods_ms[8] = ClampTo10Ms(cfg.thermal_period_ms(), "thermal_period_ms");
   periods_ms[9] = ClampTo10Ms(cfg.cpuidle_period_ms(), "cpuidle_period_ms");
   periods_ms[10] = ClampTo10Ms(cfg.gpufreq_period_ms(), "gpufreq_period_ms");
+  periods_ms[11] = ClampTo10Ms(cfg.netdev_period_ms(), "netdev_period_ms");
+  periods_ms[12] = ClampTo10Ms(cfg.netstat_period_ms(), "netstat_period_ms");
 
   tick_period_ms_ = 0;
   for (uint32_t ms : periods_ms) {
@@ -190,6 +215,8 @@ SysStatsDataSource::SysStatsDataSource(
   thermal_ticks_ = ticks[8];
   cpuidle_ticks_ = ticks[9];
   gpufreq_ticks_ = ticks[10];
+  netdev_ticks_ = ticks[11];
+  netstat_ticks_ = ticks[12];
 }
 
 void SysStatsDataSource::Start() {
@@ -254,6 +281,12 @@ void SysStatsDataSource::ReadSysStats() {
   if (gpufreq_ticks_ && tick_ % gpufreq_ticks_ == 0)
     ReadGpuFrequency(sys_stats);
 
+  if (netdev_ticks_ && tick_ % netdev_ticks_ == 0)
+    ReadNetDev(sys_stats);
+
+  if (netstat_ticks_ && tick_ % netstat_ticks_ == 0)
+    ReadNetstat(sys_stats);
+
   sys_stats->set_collection_end_timestamp(
       static_cast<uint64_t>(base::GetBootTimeNs().count()));
 
@@ -526,6 +559,113 @@ void SysStatsDataSource::ReadPsi(protos::pbzero::SysStats* sys_stats) {
                     PsiSample::PSI_RESOURCE_MEMORY_FULL);
 }
 
+void SysStatsDataSource::ReadNetDev(protos::pbzero::SysStats* sys_stats) {
+  size_t rsize = ReadFile(&netdev_fd_, "/proc/net/dev");
+  if (!rsize)
+    return;
+
+  char* buf = static_cast<char*>(read_buf_.Get());
+  for (base::StringSplitter lines(buf, rsize, '\n'); lines.Next();) {
+    // After two header lines, each line is of the form:
+    //   eth0: rx_bytes rx_packets rx_errs rx_drop [4 more rx fields]
+    //         tx_bytes tx_packets tx_errs tx_drop [4 more tx fields]
+    // Large counters can be glued to the colon (e.g. "eth0:123456789"), so
+    // replace it with a space before splitting the line.
+    char* colon = strchr(lines.cur_token(), ':');
+    if (!colon)
+      continue;
+    *colon = ' ';
+
+    uint32_t index = 0;
+    auto* netdev = sys_stats->add_netdev();
+    for (base::StringSplitter words(&lines, ' '); words.Next(); index++) {
+      if (index == 0) {
+        netdev->set_interface_name(words.cur_token());
+        continue;
+      }
+      std::optional<uint64_t> value = base::CStringToUInt64(words.cur_token());
+      if (!value)
+        continue;
+      switch (index) {
+        case 1:
+          netdev->set_rx_bytes(*value);
+          break;
+        case 2:
+          netdev->set_rx_packets(*value);
+          break;
+        case 3:
+          netdev->set_rx_errors(*value);
+          break;
+        case 4:
+          netdev->set_rx_drops(*value);
+          break;
+        case 9:
+          netdev->set_tx_bytes(*value);
+          break;
+        case 10:
+          netdev->set_tx_packets(*value);
+          break;
+        case 11:
+          netdev->set_tx_errors(*value);
+          break;
+        case 12:
+          netdev->set_tx_drops(*value);
+          break;
+      }
+      if (index == 12)
+        break;
+    }
+  }
+}
+
+void SysStatsDataSource::ReadNetstat(protos::pbzero::SysStats* sys_stats) {
+  ReadNetstatFile(&net_snmp_fd_, "/proc/net/snmp", sys_stats);
+  ReadNetstatFile(&net_netstat_fd_, "/proc/net/netstat", sys_stats);
+}
+
+void SysStatsDataSource::ReadNetstatFile(base::ScopedFile* fd,
+                                         const char* path,
+                                         protos::pbzero::SysStats* sys_stats) {
+  size_t rsize = ReadFile(fd, path);
+  if (!rsize)
+    return;
+
+  // Both files are made of pairs of lines: a header line with the counter
+  // names followed by a line with their values, both prefixed by the name of
+  // the section, e.g.:
+  //   Tcp: RtoAlgorithm RtoMin ... RetransSegs InErrs OutRsts InCsumErrors
+  //   Tcp: 1 200 ... 123 0 45 0
+  char* buf = static_cast<char*>(read_buf_.Get());
+  std::vector<const char*> header;
+  for (base::StringSplitter lines(buf, rsize, '\n'); lines.Next();) {
+    base::StringSplitter words(&lines, ' ');
+    if (!words.Next())
+      continue;
+    const char* section = words.cur_token();
+    if (header.empty() || strcmp(header[0], section) != 0) {
+      header.clear();
+      header.push_back(section);
+      while (words.Next())
+        header.push_back(words.cur_token());
+      continue;
+    }
+    for (size_t i = 1; i < header.size() && words.Next(); i++) {
+      base::StackString<128> key("%s%s", section, header[i]);
+      auto it = netstat_counters_.find(key.c_str());
+      if (it == netstat_counters_.end())
+        continue;
+      std::optional<uint64_t> value = base::CStringToUInt64(words.cur_token());
+      if (!value)
+        continue;
+      auto* netstat = sys_stats->add_netstat();
+      netstat->set_key(
+          static_cast<protos::pbzero::NetstatCounters>(it->second));
+      netstat->set_value(*value);
+    }
+    header.clear();
+  }
+}
+
 void SysStatsDataSource::ReadBuddyInfo(protos::pbzero::SysStats* sys_stats) {
   size_t rsize = ReadFile(&buddy_fd_, "/proc/buddyinfo");
   if (!rsize) {
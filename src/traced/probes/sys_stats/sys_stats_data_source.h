@@ -23,6 +23,7 @@
 #include <memory>
 #include <optional>
 #include <string>
+#include <vector>
 
 #include "perfetto/ext/base/paged_memory.h"
 #include "perfetto/ext/base/scoped_file.h"
@@ -103,6 +104,11 @@ class SysStatsDataSource : public ProbesDataSource {
   void ReadThermalZones(protos::pbzero::SysStats* sys_stats);
   void ReadCpuIdleStates(protos::pbzero::SysStats* sys_stats);
   void ReadGpuFrequency(protos::pbzero::SysStats* sys_stats);
+  void ReadNetDev(protos::pbzero::SysStats* sys_stats);
+  void ReadNetstat(protos::pbzero::SysStats* sys_stats);
+  void ReadNetstatFile(base::ScopedFile*,
+                       const char* path,
+                       protos::pbzero::SysStats* sys_stats);
   std::optional<uint64_t> ReadAMDGpuFreq();
 
   size_t ReadFile(base::ScopedFile*, const char* path);
@@ -117,10 +123,14 @@ class SysStatsDataSource : public ProbesDataSource {
   base::ScopedFile psi_cpu_fd_;
   base::ScopedFile psi_io_fd_;
   base::ScopedFile psi_memory_fd_;
+  base::ScopedFile netdev_fd_;
+  base::ScopedFile net_snmp_fd_;
+  base::ScopedFile net_netstat_fd_;
   base::PagedMemory read_buf_;
   TraceWriter::TracePacketHandle cur_packet_;
   std::map<const char*, int, CStrCmp> meminfo_counters_;
   std::map<const char*, int, CStrCmp> vmstat_counters_;
+  std::map<const char*, int, CStrCmp> netstat_counters_;
   uint64_t ns_per_user_hz_ = 0;
   uint32_t tick_ = 0;
   uint32_t tick_period_ms_ = 0;
@@ -136,6 +146,8 @@ class SysStatsDataSource : public ProbesDataSource {
   uint32_t thermal_ticks_ = 0;
   uint32_t cpuidle_ticks_ = 0;
   uint32_t gpufreq_ticks_ = 0;
+  uint32_t netdev_ticks_ = 0;
+  uint32_t netstat_ticks_ = 0;
 
   std::unique_ptr<CpuFreqInfo> cpu_freq_info_;
 
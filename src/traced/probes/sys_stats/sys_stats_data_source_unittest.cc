@@ -209,6 +209,26 @@ const char kMockPsi[] = R"(
 some avg10=23.10 avg60=5.06 avg300=15.10 total=417963
 full avg10=9.00 avg60=19.20 avg300=3.23 total=205933)";
 
+const char kMockNetDev[] = R"(
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:   82432     920    0    0    0     0          0         0    82432     920    0    0    0     0       0          0
+ wlan0:12345678901 9876543    3   17    0     0          0      1024 2345678   34567    1    2    0     0       0          0)";
+
+const char kMockNetSnmp[] = R"(
+Ip: Forwarding DefaultTTL InReceives
+Ip: 2 64 7431904
+Tcp: RtoAlgorithm RtoMin RtoMax MaxConn ActiveOpens PassiveOpens AttemptFails EstabResets CurrEstab InSegs OutSegs RetransSegs InErrs OutRsts InCsumErrors
+Tcp: 1 200 120000 -1 4512 38 316 420 23 6843270 7259166 11846 2 9034 0
+Udp: InDatagrams NoPorts InErrors OutDatagrams
+Udp: 356872 1234 0 412345)";
+
+const char kMockNetNetstat[] = R"(
+TcpExt: SyncookiesSent SyncookiesRecv TCPTimeouts TCPLostRetransmit TCPSynRetrans
+TcpExt: 0 0 734 12 431
+IpExt: InNoRoutes InTruncatedPkts
+IpExt: 0 0)";
+
 const uint64_t kMockThermalTemp = 25000;
 const char kMockThermalType[] = "TSR0";
 const uint64_t kMockCpuIdleStateTime = 10000;
@@ -270,6 +290,13 @@ base::ScopedFile MockOpenReadOnly(const char* path) {
     EXPECT_GT(pwrite(tmp_.fd(), kMockDiskStat, strlen(kMockDiskStat), 0), 0);
   } else if (base::StartsWith(path, "/proc/pressure/")) {
     EXPECT_GT(pwrite(tmp_.fd(), kMockPsi, strlen(kMockPsi), 0), 0);
+  } else if (!strcmp(path, "/proc/net/dev")) {
+    EXPECT_GT(pwrite(tmp_.fd(), kMockNetDev, strlen(kMockNetDev), 0), 0);
+  } else if (!strcmp(path, "/proc/net/snmp")) {
+    EXPECT_GT(pwrite(tmp_.fd(), kMockNetSnmp, strlen(kMockNetSnmp), 0), 0);
+  } else if (!strcmp(path, "/proc/net/netstat")) {
+    EXPECT_GT(pwrite(tmp_.fd(), kMockNetNetstat, strlen(kMockNetNetstat), 0),
+              0);
   } else {
     PERFETTO_FATAL("Unexpected file opened %s", path);
   }
@@ -859,5 +886,85 @@ TEST_F(SysStatsDataSourceTest, Psi) {
   EXPECT_EQ(sys_stats.psi()[5].total_ns(), 205933000U);
 }
 
+TEST_F(SysStatsDataSourceTest, NetDev) {
+  protos::gen::SysStatsConfig cfg;
+  cfg.set_netdev_period_ms(10);
+  DataSourceConfig config_obj;
+  config_obj.set_sys_stats_config_raw(cfg.SerializeAsString());
+  auto data_source = GetSysStatsDataSource(config_obj);
+
+  WaitTick(data_source.get());
+
+  protos::gen::TracePacket packet = writer_raw_->GetOnlyTracePacket();
+  ASSERT_TRUE(packet.has_sys_stats());
+  const auto& sys_stats = packet.sys_stats();
+  ASSERT_EQ(sys_stats.netdev_size(), 2);
+
+  const auto& lo = sys_stats.netdev()[0];
+  EXPECT_EQ(lo.interface_name(), "lo");
+  EXPECT_EQ(lo.rx_bytes(), 82432u);
+  EXPECT_EQ(lo.rx_packets(), 920u);
+  EXPECT_EQ(lo.tx_bytes(), 82432u);
+  EXPECT_EQ(lo.tx_packets(), 920u);
+
+  const auto& wlan = sys_stats.netdev()[1];
+  EXPECT_EQ(wlan.interface_name(), "wlan0");
+  EXPECT_EQ(wlan.rx_bytes(), 12345678901u);
+  EXPECT_EQ(wlan.rx_packets(), 9876543u);
+  EXPECT_EQ(wlan.rx_errors(), 3u);
+  EXPECT_EQ(wlan.rx_drops(), 17u);
+  EXPECT_EQ(wlan.tx_bytes(), 2345678u);
+  EXPECT_EQ(wlan.tx_packets(), 34567u);
+  EXPECT_EQ(wlan.tx_errors(), 1u);
+  EXPECT_EQ(wlan.tx_drops(), 2u);
+}
+
+TEST_F(SysStatsDataSourceTest, Netstat) {
+  using C = protos::gen::NetstatCounters;
+  protos::gen::SysStatsConfig cfg;
+  cfg.set_netstat_period_ms(10);
+  cfg.add_netstat_counters(C::NETSTAT_TCP_RETRANS_SEGS);
+  cfg.add_netstat_counters(C::NETSTAT_TCP_OUT_RSTS);
+  cfg.add_netstat_counters(C::NETSTAT_TCP_ESTAB_RESETS);
+  cfg.add_netstat_counters(C::NETSTAT_TCPEXT_TCP_TIMEOUTS);
+  cfg.add_netstat_counters(C::NETSTAT_TCPEXT_LISTEN_DROPS);
+  DataSourceConfig config_obj;
+  config_obj.set_sys_stats_config_raw(cfg.SerializeAsString());
+  auto data_source = GetSysStatsDataSource(config_obj);
+
+  WaitTick(data_source.get());
+
+  protos::gen::TracePacket packet = writer_raw_->GetOnlyTracePacket();
+  ASSERT_TRUE(packet.has_sys_stats());
+  const auto& sys_stats = packet.sys_stats();
+
+  using KV = std::pair<int, uint64_t>;
+  std::vector<KV> kvs;
+  for (const auto& kv : sys_stats.netstat())
+    kvs.push_back({kv.key(), kv.value()});
+
+  // TcpExt:ListenDrops is not present in the mock file.
+  EXPECT_THAT(kvs,
+              UnorderedElementsAre(KV{C::NETSTAT_TCP_ESTAB_RESETS, 420},     //
+                                   KV{C::NETSTAT_TCP_RETRANS_SEGS, 11846},   //
+                                   KV{C::NETSTAT_TCP_OUT_RSTS, 9034},        //
+                                   KV{C::NETSTAT_TCPEXT_TCP_TIMEOUTS, 734}));
+}
+
+TEST_F(SysStatsDataSourceTest, NetstatAll) {
+  protos::gen::SysStatsConfig cfg;
+  cfg.set_netstat_period_ms(10);
+  DataSourceConfig config_obj;
+  config_obj.set_sys_stats_config_raw(cfg.SerializeAsString());
+  auto data_source = GetSysStatsDataSource(config_obj);
+
+  WaitTick(data_source.get());
+
+  protos::gen::TracePacket packet = writer_raw_->GetOnlyTracePacket();
+  ASSERT_TRUE(packet.has_sys_stats());
+  // 10 counters from the Tcp section and 3 from the TcpExt one.
+  EXPECT_EQ(packet.sys_stats().netstat_size(), 13);
+}
+
 }  // namespace
 }  // namespace perfetto
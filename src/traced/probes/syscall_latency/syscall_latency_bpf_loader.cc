@@ -0,0 +1,550 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/traced/probes/syscall_latency/syscall_latency_bpf_loader.h"
+
+#include <errno.h>
+#include <linux/bpf.h>
+#include <linux/perf_event.h>
+#include <string.h>
+#include <sys/ioctl.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#include <string>
+#include <utility>
+
+#include "perfetto/base/logging.h"
+#include "perfetto/ext/base/scoped_file.h"
+#include "perfetto/ext/base/status_macros.h"
+#include "perfetto/ext/base/status_or.h"
+#include "perfetto/ext/base/utils.h"
+#include "src/kernel_utils/syscall_table.h"
+#include "src/traced/probes/ftrace/format_parser/format_parser.h"
+#include "src/traced/probes/ftrace/tracefs.h"
+
+namespace perfetto {
+
+namespace {
+
+// Helper ids, from include/uapi/linux/bpf.h. Defined here as the newer ones
+// are missing from the headers of older sysroots.
+constexpr int32_t kMapLookupElem = 1;
+constexpr int32_t kMapUpdateElem = 2;
+constexpr int32_t kMapDeleteElem = 3;
+constexpr int32_t kGetPrandomU32 = 7;
+constexpr int32_t kGetCurrentPidTgid = 14;
+constexpr int32_t kMapPushElem = 87;
+constexpr int32_t kKtimeGetBootNs = 125;
+
+constexpr uint32_t kMapTypeQueue = 22;  // BPF_MAP_TYPE_QUEUE
+constexpr int kMapLookupAndDeleteElem = 21;  // BPF_MAP_LOOKUP_AND_DELETE_ELEM
+
+constexpr uint32_t kMaxThreadsInSyscall = 16384;
+constexpr uint32_t kMaxHistogramBuckets = 65536;
+constexpr uint32_t kMaxQueuedArgSamples = 4096;
+
+// Layout of the map keys and values, shared with the BPF program below.
+struct StartValue {
+  uint64_t ts;
+  uint64_t syscall_id;
+};
+
+struct HistogramKey {
+  uint32_t tid;
+  uint32_t syscall_id;
+  uint32_t bucket;
+  uint32_t pad;
+};
+
+struct HistogramValue {
+  uint64_t count;
+  uint64_t tgid;
+};
+
+struct ArgSampleValue {
+  uint64_t ts;
+  uint64_t pid_tgid;
+  uint64_t syscall_id;
+  uint64_t args[6];
+};
+
+// Offsets of the fields in the raw_syscalls tracepoint records. Load() checks
+// them against the format of the events.
+constexpr int16_t kSysEnterIdOffset = 8;
+constexpr int16_t kSysEnterArgsOffset = 16;
+
+// A minimal assembler for the handful of instructions used by the programs.
+//
+// The programs are assembled at runtime rather than checked in as an object
+// compiled by clang -target bpf: the build has no BPF toolchain, and loading
+// an object would need an ELF loader (e.g. libbpf) to resolve the references
+// to the maps. Assembling them here amounts to the same thing: the
+// instructions are fixed, and the only values filled in at runtime are the
+// fds of the maps, as a loader would do when relocating a compiled object.
+// The programs only use helpers available since Linux 5.8 and no BTF or CO-RE
+// relocations, as the fields of the tracepoint records are read at offsets
+// checked against the tracefs event formats.
+class BpfAssembler {
+ public:
+  using Label = size_t;
+
+  enum Reg : uint8_t { R0 = 0, R1, R2, R3, R4, R5, R6, R7, R8, R9, R10 };
+
+  Label NewLabel() {
+    labels_.push_back(kUnbound);
+    return labels_.size() - 1;
+  }
+
+  void Bind(Label label) { labels_[label] = insns_.size(); }
+
+  void MovImm(Reg dst, int32_t imm) {
+    Emit(BPF_ALU64 | BPF_MOV | BPF_K, dst, 0, 0, imm);
+  }
+  void MovReg(Reg dst, Reg src) {
+    Emit(BPF_ALU64 | BPF_MOV | BPF_X, dst, src, 0, 0);
+  }
+  void AddImm(Reg dst, int32_t imm) {
+    Emit(BPF_ALU64 | BPF_ADD | BPF_K, dst, 0, 0, imm);
+  }
+  void SubReg(Reg dst, Reg src) {
+    Emit(BPF_ALU64 | BPF_SUB | BPF_X, dst, src, 0, 0);
+  }
+  void ModReg(Reg dst, Reg src) {
+    Emit(BPF_ALU64 | BPF_MOD | BPF_X, dst, src, 0, 0);
+  }
+  void RshImm(Reg dst, int32_t imm) {
+    Emit(BPF_ALU64 | BPF_RSH | BPF_K, dst, 0, 0, imm);
+  }
+
+  // |size| is one of BPF_W, BPF_DW.
+  void Load(uint8_t size, Reg dst, Reg src, int16_t off) {
+    Emit(BPF_LDX | BPF_MEM | size, dst, src, off, 0);
+  }
+  void Store(uint8_t size, Reg dst, int16_t off, Reg src) {
+    Emit(BPF_STX | BPF_MEM | size, dst, src, off, 0);
+  }
+  void StoreImm(uint8_t size, Reg dst, int16_t off, int32_t imm) {
+    Emit(BPF_ST | BPF_MEM | size, dst, 0, off, imm);
+  }
+  void AtomicAdd(Reg dst, int16_t off, Reg src) {
+    Emit(BPF_STX | BPF_XADD | BPF_DW, dst, src, off, 0);
+  }
+
+  void LoadMapFd(Reg dst, int fd) {
+    Emit(BPF_LD | BPF_DW | BPF_IMM, dst, BPF_PSEUDO_MAP_FD, 0, fd);
+    Emit(0, 0, 0, 0, 0);
+  }
+  // Sets |dst| to a pointer to the stack slot at r10 + |off|.
+  void StackPtr(Reg dst, int16_t off) {
+    MovReg(dst, R10);
+    AddImm(dst, off);
+  }
+
+  // |op| is one of BPF_JEQ, BPF_JNE, BPF_JGE.
+  void JumpIfImm(uint8_t op, Reg dst, int32_t imm, Label target) {
+    jumps_.emplace_back(insns_.size(), target);
+    Emit(BPF_JMP | op | BPF_K, dst, 0, 0, imm);
+  }
+  void Jump(Label target) {
+    jumps_.emplace_back(insns_.size(), target);
+    Emit(BPF_JMP | BPF_JA, 0, 0, 0, 0);
+  }
+  void Call(int32_t helper) { Emit(BPF_JMP | BPF_CALL, 0, 0, 0, helper); }
+  void Exit() { Emit(BPF_JMP | BPF_EXIT, 0, 0, 0, 0); }
+
+  std::vector<bpf_insn> Finish() {
+    for (const auto& [pc, label] : jumps_) {
+      PERFETTO_CHECK(labels_[label] != kUnbound);
+      insns_[pc].off = static_cast<int16_t>(labels_[label] - pc - 1);
+    }
+    return std::move(insns_);
+  }
+
+ private:
+  static constexpr size_t kUnbound = static_cast<size_t>(-1);
+
+  void Emit(uint8_t code, uint8_t dst, uint8_t src, int16_t off, int32_t imm) {
+    bpf_insn insn{};
+    insn.code = code;
+    insn.dst_reg = dst & 0xf;
+    insn.src_reg = src & 0xf;
+    insn.off = off;
+    insn.imm = imm;
+    insns_.push_back(insn);
+  }
+
+  std::vector<bpf_insn> insns_;
+  std::vector<size_t> labels_;
+  std::vector<std::pair<size_t, Label>> jumps_;
+};
+
+using Asm = BpfAssembler;
+
+// raw_syscalls/sys_enter: records the start of the syscall in |start_map| and
+// pushes the arguments of the sampled calls to |samples_map|.
+std::vector<bpf_insn> SysEnterProgram(int start_map,
+                                      int rates_map,
+                                      int samples_map) {
+  Asm a;
+  Asm::Label out = a.NewLabel();
+  // Stack: [-4] tid, [-24] StartValue, [-28] syscall id, [-32] sampling rate,
+  // [-104] ArgSampleValue.
+  a.MovReg(Asm::R6, Asm::R1);
+  a.Call(kGetCurrentPidTgid);
+  a.MovReg(Asm::R7, Asm::R0);
+  a.Store(BPF_W, Asm::R10, -4, Asm::R0);
+  a.Call(kKtimeGetBootNs);
+  a.MovReg(Asm::R8, Asm::R0);
+  a.Load(BPF_DW, Asm::R9, Asm::R6, kSysEnterIdOffset);
+  a.Store(BPF_DW, Asm::R10, -24, Asm::R8);
+  a.Store(BPF_DW, Asm::R10, -16, Asm::R9);
+  a.LoadMapFd(Asm::R1, start_map);
+  a.StackPtr(Asm::R2, -4);
+  a.StackPtr(Asm::R3, -24);
+  a.MovImm(Asm::R4, BPF_ANY);
+  a.Call(kMapUpdateElem);
+
+  // if (bpf_get_prandom_u32() % rates[id] == 0) push the arguments.
+  a.JumpIfImm(BPF_JGE, Asm::R9, static_cast<int32_t>(kMaxSyscalls), out);
+  a.Store(BPF_W, Asm::R10, -28, Asm::R9);
+  a.LoadMapFd(Asm::R1, rates_map);
+  a.StackPtr(Asm::R2, -28);
+  a.Call(kMapLookupElem);
+  a.JumpIfImm(BPF_JEQ, Asm::R0, 0, out);
+  a.Load(BPF_W, Asm::R1, Asm::R0, 0);
+  a.JumpIfImm(BPF_JEQ, Asm::R1, 0, out);
+  a.Store(BPF_W, Asm::R10, -32, Asm::R1);
+  a.Call(kGetPrandomU32);
+  a.Load(BPF_W, Asm::R1, Asm::R10, -32);
+  a.ModReg(Asm::R0, Asm::R1);
+  a.JumpIfImm(BPF_JNE, Asm::R0, 0, out);
+  a.Store(BPF_DW, Asm::R10, -104, Asm::R8);
+  a.Store(BPF_DW, Asm::R10, -96, Asm::R7);
+  a.Store(BPF_DW, Asm::R10, -88, Asm::R9);
+  for (int i = 0; i < 6; i++) {
+    a.Load(BPF_DW, Asm::R1, Asm::R6,
+           static_cast<int16_t>(kSysEnterArgsOffset + i * 8));
+    a.Store(BPF_DW, Asm::R10, static_cast<int16_t>(-80 + i * 8), Asm::R1);
+  }
+  a.LoadMapFd(Asm::R1, samples_map);
+  a.StackPtr(Asm::R2, -104);
+  a.MovImm(Asm::R3, 0);
+  a.Call(kMapPushElem);
+
+  a.Bind(out);
+  a.MovImm(Asm::R0, 0);
+  a.Exit();
+  return a.Finish();
+}
+
+// raw_syscalls/sys_exit: increments the log2 latency bucket of the syscall in
+// |histogram_map|.
+std::vector<bpf_insn> SysExitProgram(int start_map, int histogram_map) {
+  Asm a;
+  Asm::Label out = a.NewLabel();
+  Asm::Label create = a.NewLabel();
+  // Stack: [-4] tid, [-24] HistogramKey, [-40] HistogramValue.
+  a.Call(kGetCurrentPidTgid);
+  a.MovReg(Asm::R7, Asm::R0);
+  a.Store(BPF_W, Asm::R10, -4, Asm::R0);
+  a.LoadMapFd(Asm::R1, start_map);
+  a.StackPtr(Asm::R2, -4);
+  a.Call(kMapLookupElem);
+  a.JumpIfImm(BPF_JEQ, Asm::R0, 0, out);
+  a.Load(BPF_DW, Asm::R8, Asm::R0, 0);
+  a.Load(BPF_DW, Asm::R9, Asm::R0, 8);
+  a.LoadMapFd(Asm::R1, start_map);
+  a.StackPtr(Asm::R2, -4);
+  a.Call(kMapDeleteElem);
+  a.Call(kKtimeGetBootNs);
+  a.SubReg(Asm::R0, Asm::R8);
+
+  // r1 = floor(log2(r0)), with a binary search on the highest set bit.
+  a.MovImm(Asm::R1, 0);
+  a.MovReg(Asm::R2, Asm::R0);
+  for (int32_t shift : {32, 16, 8, 4, 2, 1}) {
+    Asm::Label next = a.NewLabel();
+    a.MovReg(Asm::R3, Asm::R2);
+    a.RshImm(Asm::R3, shift);
+    a.JumpIfImm(BPF_JEQ, Asm::R3, 0, next);
+    a.AddImm(Asm::R1, shift);
+    a.MovReg(Asm::R2, Asm::R3);
+    a.Bind(next);
+  }
+
+  a.Store(BPF_W, Asm::R10, -24, Asm::R7);
+  a.Store(BPF_W, Asm::R10, -20, Asm::R9);
+  a.Store(BPF_W, Asm::R10, -16, Asm::R1);
+  a.StoreImm(BPF_W, Asm::R10, -12, 0);
+  a.LoadMapFd(Asm::R1, histogram_map);
+  a.StackPtr(Asm::R2, -24);
+  a.Call(kMapLookupElem);
+  a.JumpIfImm(BPF_JEQ, Asm::R0, 0, create);
+  a.MovImm(Asm::R1, 1);
+  a.AtomicAdd(Asm::R0, 0, Asm::R1);
+  a.Jump(out);
+
+  // Racing with another CPU creating the same bucket loses one call, which
+  // is fine for a histogram.
+  a.Bind(create);
+  a.StoreImm(BPF_DW, Asm::R10, -40, 1);
+  a.RshImm(Asm::R7, 32);
+  a.Store(BPF_DW, Asm::R10, -32, Asm::R7);
+  a.LoadMapFd(Asm::R1, histogram_map);
+  a.StackPtr(Asm::R2, -24);
+  a.StackPtr(Asm::R3, -40);
+  a.MovImm(Asm::R4, BPF_NOEXIST);
+  a.Call(kMapUpdateElem);
+
+  a.Bind(out);
+  a.MovImm(Asm::R0, 0);
+  a.Exit();
+  return a.Finish();
+}
+
+int Bpf(int cmd, bpf_attr* attr) {
+  return static_cast<int>(syscall(__NR_bpf, cmd, attr, sizeof(*attr)));
+}
+
+base::ScopedFile CreateMap(uint32_t type,
+                           uint32_t key_size,
+                           uint32_t value_size,
+                           uint32_t max_entries) {
+  bpf_attr attr{};
+  attr.map_type = type;
+  attr.key_size = key_size;
+  attr.value_size = value_size;
+  attr.max_entries = max_entries;
+  return base::ScopedFile(Bpf(BPF_MAP_CREATE, &attr));
+}
+
+base::StatusOr<base::ScopedFile> LoadProgram(
+    const std::vector<bpf_insn>& insns) {
+  // None of the helpers used are GPL-only.
+  static const char kLicense[] = "Apache-2.0";
+  bpf_attr attr{};
+  attr.prog_type = BPF_PROG_TYPE_TRACEPOINT;
+  attr.insns = reinterpret_cast<uintptr_t>(insns.data());
+  attr.insn_cnt = static_cast<uint32_t>(insns.size());
+  attr.license = reinterpret_cast<uintptr_t>(kLicense);
+  base::ScopedFile fd(Bpf(BPF_PROG_LOAD, &attr));
+  if (fd)
+    return fd;
+
+  // Load again with the verifier log, to explain the failure.
+  int load_errno = errno;
+  std::string log(16 * 1024, '\0');
+  attr.log_level = 1;
+  attr.log_buf = reinterpret_cast<uintptr_t>(log.data());
+  attr.log_size = static_cast<uint32_t>(log.size());
+  base::ScopedFile retry(Bpf(BPF_PROG_LOAD, &attr));
+  log.resize(strlen(log.c_str()));
+  return base::ErrStatus("Failed to load the BPF program: %s. %s",
+                         strerror(load_errno), log.c_str());
+}
+
+const FtraceEvent::Field* FindField(const FtraceEvent& event,
+                                    const char* name) {
+  for (const FtraceEvent::Field& field : event.fields) {
+    if (GetNameFromTypeAndName(field.type_and_name) == name)
+      return &field;
+  }
+  return nullptr;
+}
+
+bool HasField(const FtraceEvent& event,
+              const char* name,
+              uint16_t offset,
+              uint16_t size) {
+  const FtraceEvent::Field* field = FindField(event, name);
+  return field && field->offset == offset && field->size == size;
+}
+
+class KernelSyscallLatencyBpfLoader : public SyscallLatencyBpfLoader {
+ public:
+  ~KernelSyscallLatencyBpfLoader() override = default;
+
+  base::Status Load(const std::vector<uint32_t>& arg_sampling_rates) override;
+  void ReadHistograms(std::vector<HistogramBucket>* buckets) override;
+  void ReadArgSamples(size_t max_samples,
+                      std::vector<ArgSample>* samples) override;
+
+ private:
+  base::Status Attach(uint32_t tracepoint_id, int prog_fd);
+
+  base::ScopedFile start_map_;
+  base::ScopedFile histogram_map_;
+  base::ScopedFile rates_map_;
+  base::ScopedFile samples_map_;
+  base::ScopedFile sys_enter_prog_;
+  base::ScopedFile sys_exit_prog_;
+  // Closing these detaches the programs.
+  std::vector<base::ScopedFile> perf_fds_;
+};
+
+base::Status KernelSyscallLatencyBpfLoader::Load(
+    const std::vector<uint32_t>& arg_sampling_rates) {
+  std::unique_ptr<Tracefs> tracefs = Tracefs::CreateGuessingMountPoint("");
+  if (!tracefs)
+    return base::ErrStatus("Failed to find tracefs");
+
+  // The programs read the fields at fixed offsets, which match the records of
+  // all 64-bit kernels.
+  FtraceEvent sys_enter;
+  FtraceEvent sys_exit;
+  if (!ParseFtraceEvent(tracefs->ReadEventFormat("raw_syscalls", "sys_enter"),
+                        &sys_enter) ||
+      !ParseFtraceEvent(tracefs->ReadEventFormat("raw_syscalls", "sys_exit"),
+                        &sys_exit)) {
+    return base::ErrStatus("Failed to read the raw_syscalls event formats");
+  }
+  if (!HasField(sys_enter, "id", kSysEnterIdOffset, 8) ||
+      !HasField(sys_enter, "args", kSysEnterArgsOffset, 48)) {
+    return base::ErrStatus("Unsupported raw_syscalls/sys_enter format");
+  }
+
+  start_map_ = CreateMap(BPF_MAP_TYPE_LRU_HASH, sizeof(uint32_t),
+                         sizeof(StartValue), kMaxThreadsInSyscall);
+  histogram_map_ = CreateMap(BPF_MAP_TYPE_LRU_HASH, sizeof(HistogramKey),
+                             sizeof(HistogramValue), kMaxHistogramBuckets);
+  rates_map_ = CreateMap(BPF_MAP_TYPE_ARRAY, sizeof(uint32_t),
+                         sizeof(uint32_t), kMaxSyscalls);
+  samples_map_ =
+      CreateMap(kMapTypeQueue, 0, sizeof(ArgSampleValue), kMaxQueuedArgSamples);
+  if (!start_map_ || !histogram_map_ || !rates_map_ || !samples_map_)
+    return base::ErrStatus("Failed to create BPF maps: %s", strerror(errno));
+
+  for (uint32_t id = 0; id < arg_sampling_rates.size() && id < kMaxSyscalls;
+       id++) {
+    uint32_t rate = arg_sampling_rates[id];
+    if (!rate)
+      continue;
+    bpf_attr attr{};
+    attr.map_fd = static_cast<uint32_t>(*rates_map_);
+    attr.key = reinterpret_cast<uintptr_t>(&id);
+    attr.value = reinterpret_cast<uintptr_t>(&rate);
+    if (Bpf(BPF_MAP_UPDATE_ELEM, &attr) != 0)
+      return base::ErrStatus("Failed to set sampling rate: %s",
+                             strerror(errno));
+  }
+
+  ASSIGN_OR_RETURN(sys_enter_prog_,
+                   LoadProgram(SysEnterProgram(*start_map_, *rates_map_,
+                                               *samples_map_)));
+  ASSIGN_OR_RETURN(sys_exit_prog_,
+                   LoadProgram(SysExitProgram(*start_map_, *histogram_map_)));
+  RETURN_IF_ERROR(Attach(sys_exit.id, *sys_exit_prog_));
+  RETURN_IF_ERROR(Attach(sys_enter.id, *sys_enter_prog_));
+  return base::OkStatus();
+}
+
+base::Status KernelSyscallLatencyBpfLoader::Attach(uint32_t tracepoint_id,
+                                                   int prog_fd) {
+  long num_cpus = sysconf(_SC_NPROCESSORS_CONF);
+  for (int cpu = 0; cpu < num_cpus; cpu++) {
+    perf_event_attr attr{};
+    attr.type = PERF_TYPE_TRACEPOINT;
+    attr.size = sizeof(attr);
+    attr.config = tracepoint_id;
+    attr.sample_period = 1;
+    base::ScopedFile fd(static_cast<int>(syscall(__NR_perf_event_open, &attr,
+                                                 /*pid=*/-1, cpu,
+                                                 /*group_fd=*/-1,
+                                                 PERF_FLAG_FD_CLOEXEC)));
+    if (!fd) {
+      if (errno == ENODEV)
+        continue;  // Offline CPU.
+      return base::ErrStatus("perf_event_open failed on cpu %d: %s", cpu,
+                             strerror(errno));
+    }
+    if (ioctl(*fd, PERF_EVENT_IOC_SET_BPF, prog_fd) != 0 ||
+        ioctl(*fd, PERF_EVENT_IOC_ENABLE, 0) != 0) {
+      return base::ErrStatus("Failed to attach the BPF program: %s",
+                             strerror(errno));
+    }
+    perf_fds_.push_back(std::move(fd));
+  }
+  return base::OkStatus();
+}
+
+void KernelSyscallLatencyBpfLoader::ReadHistograms(
+    std::vector<HistogramBucket>* buckets) {
+  buckets->clear();
+  if (!histogram_map_)
+    return;
+  HistogramKey key{};
+  HistogramKey next_key{};
+  bool first = true;
+  for (;;) {
+    bpf_attr attr{};
+    attr.map_fd = static_cast<uint32_t>(*histogram_map_);
+    attr.key = first ? 0 : reinterpret_cast<uintptr_t>(&key);
+    attr.next_key = reinterpret_cast<uintptr_t>(&next_key);
+    if (Bpf(BPF_MAP_GET_NEXT_KEY, &attr) != 0)
+      break;
+    first = false;
+    key = next_key;
+
+    HistogramValue value{};
+    bpf_attr lookup{};
+    lookup.map_fd = static_cast<uint32_t>(*histogram_map_);
+    lookup.key = reinterpret_cast<uintptr_t>(&key);
+    lookup.value = reinterpret_cast<uintptr_t>(&value);
+    if (Bpf(BPF_MAP_LOOKUP_ELEM, &lookup) != 0)
+      continue;  // Evicted in the meantime.
+    HistogramBucket bucket;
+    bucket.pid = static_cast<int32_t>(value.tgid);
+    bucket.tid = static_cast<int32_t>(key.tid);
+    bucket.syscall_id = key.syscall_id;
+    bucket.bucket = key.bucket;
+    bucket.count = value.count;
+    buckets->push_back(bucket);
+  }
+}
+
+void KernelSyscallLatencyBpfLoader::ReadArgSamples(
+    size_t max_samples,
+    std::vector<ArgSample>* samples) {
+  if (!samples_map_)
+    return;
+  for (size_t i = 0; i < max_samples; i++) {
+    ArgSampleValue value{};
+    bpf_attr attr{};
+    attr.map_fd = static_cast<uint32_t>(*samples_map_);
+    attr.value = reinterpret_cast<uintptr_t>(&value);
+    if (Bpf(kMapLookupAndDeleteElem, &attr) != 0)
+      break;  // Empty.
+    ArgSample sample;
+    sample.ts = value.ts;
+    sample.pid = static_cast<int32_t>(value.pid_tgid >> 32);
+    sample.tid = static_cast<int32_t>(value.pid_tgid & 0xffffffff);
+    sample.syscall_id = static_cast<uint32_t>(value.syscall_id);
+    for (size_t j = 0; j < sample.args.size(); j++)
+      sample.args[j] = value.args[j];
+    samples->push_back(sample);
+  }
+}
+
+}  // namespace
+
+SyscallLatencyBpfLoader::~SyscallLatencyBpfLoader() = default;
+
+std::unique_ptr<SyscallLatencyBpfLoader> CreateKernelSyscallLatencyBpfLoader() {
+  return std::make_unique<KernelSyscallLatencyBpfLoader>();
+}
+
+}  // namespace perfetto
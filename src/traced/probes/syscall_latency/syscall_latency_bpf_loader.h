@@ -0,0 +1,78 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACED_PROBES_SYSCALL_LATENCY_SYSCALL_LATENCY_BPF_LOADER_H_
+#define SRC_TRACED_PROBES_SYSCALL_LATENCY_SYSCALL_LATENCY_BPF_LOADER_H_
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include <array>
+#include <memory>
+#include <vector>
+
+#include "perfetto/base/status.h"
+
+namespace perfetto {
+
+// Loads the BPF program of the linux.syscall_latency data source and reads
+// back what it aggregates in the kernel. This is an interface so that the
+// userspace side of the data source can be tested without root.
+class SyscallLatencyBpfLoader {
+ public:
+  // The number of calls of a syscall by a thread which took between
+  // [2^|bucket|, 2^(|bucket| + 1)) ns (the calls which took 0ns go in bucket 0
+  // too). Counts are cumulative since Load().
+  struct HistogramBucket {
+    int32_t pid = 0;
+    int32_t tid = 0;
+    uint32_t syscall_id = 0;
+    uint32_t bucket = 0;
+    uint64_t count = 0;
+  };
+
+  // The arguments of a sampled syscall, recorded on syscall entry.
+  struct ArgSample {
+    uint64_t ts = 0;  // CLOCK_BOOTTIME.
+    int32_t pid = 0;
+    int32_t tid = 0;
+    uint32_t syscall_id = 0;
+    std::array<uint64_t, 6> args{};
+  };
+
+  virtual ~SyscallLatencyBpfLoader();
+
+  // Loads the program and attaches it to the raw_syscalls tracepoints of all
+  // CPUs. |arg_sampling_rates| is indexed by syscall id: the arguments of one
+  // in N calls of each syscall are sampled, 0 disables the sampling.
+  virtual base::Status Load(const std::vector<uint32_t>& arg_sampling_rates) = 0;
+
+  // Replaces the contents of |buckets| with all the non-empty buckets.
+  virtual void ReadHistograms(std::vector<HistogramBucket>* buckets) = 0;
+
+  // Appends up to |max_samples| of the oldest pending samples to |samples|,
+  // removing them from the kernel queue.
+  virtual void ReadArgSamples(size_t max_samples,
+                              std::vector<ArgSample>* samples) = 0;
+};
+
+// Returns a loader which uses the bpf(2) and perf_event_open(2) syscalls.
+// Requires CAP_BPF and CAP_PERFMON (or root) and Linux 5.8+.
+std::unique_ptr<SyscallLatencyBpfLoader> CreateKernelSyscallLatencyBpfLoader();
+
+}  // namespace perfetto
+
+#endif  // SRC_TRACED_PROBES_SYSCALL_LATENCY_SYSCALL_LATENCY_BPF_LOADER_H_
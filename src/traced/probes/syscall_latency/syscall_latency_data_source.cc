@@ -0,0 +1,206 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/traced/probes/syscall_latency/syscall_latency_data_source.h"
+
+#include <algorithm>
+#include <cinttypes>
+#include <optional>
+#include <string>
+#include <utility>
+
+#include "perfetto/base/logging.h"
+#include "perfetto/base/task_runner.h"
+#include "perfetto/base/time.h"
+#include "perfetto/ext/tracing/core/trace_writer.h"
+#include "perfetto/protozero/packed_repeated_fields.h"
+#include "src/kernel_utils/syscall_table.h"
+
+#include "protos/perfetto/config/sys_stats/syscall_latency_config.pbzero.h"
+#include "protos/perfetto/trace/sys_stats/syscall_latency.pbzero.h"
+#include "protos/perfetto/trace/trace_packet.pbzero.h"
+
+namespace perfetto {
+
+namespace {
+
+using protos::pbzero::SyscallLatency;
+using protos::pbzero::SyscallLatencyConfig;
+
+constexpr uint32_t kDefaultDumpPeriodMs = 1000;
+constexpr uint32_t kMinDumpPeriodMs = 100;
+
+}  // namespace
+
+// static
+const ProbesDataSource::Descriptor SyscallLatencyDataSource::descriptor = {
+    /*name*/ "linux.syscall_latency",
+    /*flags*/ Descriptor::kFlagsNone,
+    /*fill_descriptor_func*/ nullptr,
+};
+
+SyscallLatencyDataSource::SyscallLatencyDataSource(
+    const DataSourceConfig& ds_config,
+    base::TaskRunner* task_runner,
+    TracingSessionID session_id,
+    std::unique_ptr<TraceWriter> writer,
+    std::unique_ptr<SyscallLatencyBpfLoader> loader)
+    : ProbesDataSource(session_id, &descriptor),
+      task_runner_(task_runner),
+      writer_(std::move(writer)),
+      loader_(std::move(loader)),
+      weak_factory_(this) {
+  SyscallLatencyConfig::Decoder cfg(ds_config.syscall_latency_config_raw());
+  dump_period_ms_ =
+      cfg.has_dump_period_ms() ? cfg.dump_period_ms() : kDefaultDumpPeriodMs;
+  if (dump_period_ms_ < kMinDumpPeriodMs) {
+    PERFETTO_ILOG("dump_period_ms %" PRIu32
+                  " is less than minimum of %" PRIu32 "ms. Increasing it.",
+                  dump_period_ms_, kMinDumpPeriodMs);
+    dump_period_ms_ = kMinDumpPeriodMs;
+  }
+
+  uint32_t rate = cfg.has_arg_sampling_rate() ? cfg.arg_sampling_rate() : 1;
+  if (rate == 0 || !cfg.has_arg_sampling_syscalls())
+    return;
+  SyscallTable syscalls = SyscallTable::FromCurrentArch();
+  for (auto it = cfg.arg_sampling_syscalls(); it; ++it) {
+    std::string name = (*it).ToStdString();
+    std::optional<size_t> id = syscalls.GetByName(name);
+    if (!id) {
+      PERFETTO_ELOG("Can't sample the arguments of %s, syscall not known",
+                    name.c_str());
+      continue;
+    }
+    if (*id >= arg_sampling_rates_.size())
+      arg_sampling_rates_.resize(*id + 1);
+    arg_sampling_rates_[*id] = rate;
+  }
+}
+
+SyscallLatencyDataSource::~SyscallLatencyDataSource() = default;
+
+void SyscallLatencyDataSource::Start() {
+  base::Status status = loader_->Load(arg_sampling_rates_);
+  if (!status.ok()) {
+    PERFETTO_ELOG("Failed to load the syscall latency BPF program: %s",
+                  status.c_message());
+    return;
+  }
+  loaded_ = true;
+  auto weak_this = GetWeakPtr();
+  task_runner_->PostDelayedTask(
+      std::bind(&SyscallLatencyDataSource::Tick, weak_this), dump_period_ms_);
+}
+
+// static
+void SyscallLatencyDataSource::Tick(
+    base::WeakPtr<SyscallLatencyDataSource> weak_this) {
+  if (!weak_this)
+    return;
+  SyscallLatencyDataSource& thiz = *weak_this;
+
+  uint32_t period_ms = thiz.dump_period_ms_;
+  uint32_t delay_ms =
+      period_ms -
+      static_cast<uint32_t>(base::GetWallTimeMs().count() % period_ms);
+  thiz.task_runner_->PostDelayedTask(
+      std::bind(&SyscallLatencyDataSource::Tick, weak_this), delay_ms);
+  thiz.Dump();
+}
+
+void SyscallLatencyDataSource::Dump() {
+  if (!loaded_)
+    return;
+
+  buckets_.clear();
+  loader_->ReadHistograms(&buckets_);
+  std::sort(buckets_.begin(), buckets_.end(),
+            [](const SyscallLatencyBpfLoader::HistogramBucket& a,
+               const SyscallLatencyBpfLoader::HistogramBucket& b) {
+              return std::tie(a.tid, a.syscall_id, a.bucket) <
+                     std::tie(b.tid, b.syscall_id, b.bucket);
+            });
+  samples_.clear();
+  loader_->ReadArgSamples(kMaxArgSamplesPerDump, &samples_);
+
+  auto packet = writer_->NewTracePacket();
+  packet->set_timestamp(static_cast<uint64_t>(base::GetBootTimeNs().count()));
+  auto* syscall_latency = packet->set_syscall_latency();
+
+  std::map<BucketKey, uint64_t> counts;
+  protozero::PackedVarInt bucket_ids;
+  protozero::PackedVarInt bucket_counts;
+  bool has_buckets = false;
+  for (size_t i = 0; i < buckets_.size(); i++) {
+    const auto& b = buckets_[i];
+    BucketKey key{b.tid, b.syscall_id, b.bucket};
+    counts[key] = b.count;
+
+    // The kernel counts are cumulative. A count lower than the previous one
+    // means that the entry was evicted from the (LRU) map and re-created.
+    auto prev = prev_counts_.find(key);
+    uint64_t delta = b.count;
+    if (prev != prev_counts_.end() && prev->second <= b.count)
+      delta = b.count - prev->second;
+    if (delta > 0) {
+      bucket_ids.Append(b.bucket);
+      bucket_counts.Append(delta);
+      has_buckets = true;
+    }
+
+    bool last_of_syscall = i + 1 == buckets_.size() ||
+                           buckets_[i + 1].tid != b.tid ||
+                           buckets_[i + 1].syscall_id != b.syscall_id;
+    if (!last_of_syscall || !has_buckets)
+      continue;
+    auto* syscall = syscall_latency->add_syscalls();
+    syscall->set_pid(b.pid);
+    syscall->set_tid(b.tid);
+    syscall->set_syscall_id(b.syscall_id);
+    syscall->set_buckets(bucket_ids);
+    syscall->set_counts(bucket_counts);
+    bucket_ids.Reset();
+    bucket_counts.Reset();
+    has_buckets = false;
+  }
+  prev_counts_ = std::move(counts);
+
+  for (const auto& s : samples_) {
+    auto* sample = syscall_latency->add_arg_samples();
+    sample->set_ts(s.ts);
+    sample->set_pid(s.pid);
+    sample->set_tid(s.tid);
+    sample->set_syscall_id(s.syscall_id);
+    protozero::PackedVarInt args;
+    for (uint64_t arg : s.args)
+      args.Append(arg);
+    sample->set_args(args);
+  }
+}
+
+void SyscallLatencyDataSource::Flush(FlushRequestID,
+                                     std::function<void()> callback) {
+  Dump();
+  writer_->Flush(callback);
+}
+
+base::WeakPtr<SyscallLatencyDataSource> SyscallLatencyDataSource::GetWeakPtr()
+    const {
+  return weak_factory_.GetWeakPtr();
+}
+
+}  // namespace perfetto
@@ -0,0 +1,97 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACED_PROBES_SYSCALL_LATENCY_SYSCALL_LATENCY_DATA_SOURCE_H_
+#define SRC_TRACED_PROBES_SYSCALL_LATENCY_SYSCALL_LATENCY_DATA_SOURCE_H_
+
+#include <functional>
+#include <map>
+#include <memory>
+#include <tuple>
+#include <vector>
+
+#include "perfetto/ext/base/weak_ptr.h"
+#include "perfetto/ext/tracing/core/basic_types.h"
+#include "perfetto/tracing/core/data_source_config.h"
+#include "src/traced/probes/probes_data_source.h"
+#include "src/traced/probes/syscall_latency/syscall_latency_bpf_loader.h"
+
+namespace perfetto {
+
+class TraceWriter;
+namespace base {
+class TaskRunner;
+}
+
+// Periodically dumps the per-thread syscall latency histograms aggregated in
+// the kernel by a BPF program (see SyscallLatencyBpfLoader), together with the
+// syscall arguments sampled since the previous dump.
+class SyscallLatencyDataSource : public ProbesDataSource {
+ public:
+  static const ProbesDataSource::Descriptor descriptor;
+
+  // The maximum number of argument samples written in each dump. The others
+  // stay in the kernel queue until the next dump.
+  static constexpr size_t kMaxArgSamplesPerDump = 4096;
+
+  SyscallLatencyDataSource(const DataSourceConfig&,
+                           base::TaskRunner*,
+                           TracingSessionID,
+                           std::unique_ptr<TraceWriter> writer,
+                           std::unique_ptr<SyscallLatencyBpfLoader> loader);
+  ~SyscallLatencyDataSource() override;
+
+  // ProbesDataSource implementation.
+  void Start() override;
+  void Flush(FlushRequestID, std::function<void()> callback) override;
+
+  base::WeakPtr<SyscallLatencyDataSource> GetWeakPtr() const;
+
+  uint32_t dump_period_ms() const { return dump_period_ms_; }
+  const std::vector<uint32_t>& arg_sampling_rates() const {
+    return arg_sampling_rates_;
+  }
+
+ private:
+  // (tid, syscall_id, bucket).
+  using BucketKey = std::tuple<int32_t, uint32_t, uint32_t>;
+
+  static void Tick(base::WeakPtr<SyscallLatencyDataSource>);
+
+  SyscallLatencyDataSource(const SyscallLatencyDataSource&) = delete;
+  SyscallLatencyDataSource& operator=(const SyscallLatencyDataSource&) =
+      delete;
+
+  void Dump();
+
+  base::TaskRunner* const task_runner_;
+  std::unique_ptr<TraceWriter> writer_;
+  std::unique_ptr<SyscallLatencyBpfLoader> loader_;
+  uint32_t dump_period_ms_ = 0;
+  std::vector<uint32_t> arg_sampling_rates_;
+  bool loaded_ = false;
+
+  // The cumulative counts read by the previous dump, to compute the deltas.
+  std::map<BucketKey, uint64_t> prev_counts_;
+  std::vector<SyscallLatencyBpfLoader::HistogramBucket> buckets_;
+  std::vector<SyscallLatencyBpfLoader::ArgSample> samples_;
+
+  base::WeakPtrFactory<SyscallLatencyDataSource> weak_factory_;  // Keep last.
+};
+
+}  // namespace perfetto
+
+#endif  // SRC_TRACED_PROBES_SYSCALL_LATENCY_SYSCALL_LATENCY_DATA_SOURCE_H_
@@ -0,0 +1,243 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/traced/probes/syscall_latency/syscall_latency_data_source.h"
+
+#include "src/base/test/test_task_runner.h"
+#include "src/kernel_utils/syscall_table.h"
+#include "src/tracing/core/trace_writer_for_testing.h"
+#include "test/gtest_and_gmock.h"
+
+#include "protos/perfetto/config/sys_stats/syscall_latency_config.gen.h"
+#include "protos/perfetto/trace/sys_stats/syscall_latency.gen.h"
+
+using ::testing::_;
+using ::testing::ElementsAre;
+using ::testing::Invoke;
+using ::testing::IsEmpty;
+using ::testing::Return;
+
+namespace perfetto {
+namespace {
+
+using HistogramBucket = SyscallLatencyBpfLoader::HistogramBucket;
+using ArgSample = SyscallLatencyBpfLoader::ArgSample;
+
+class MockSyscallLatencyBpfLoader : public SyscallLatencyBpfLoader {
+ public:
+  MOCK_METHOD(base::Status,
+              Load,
+              (const std::vector<uint32_t>&),
+              (override));
+  MOCK_METHOD(void,
+              ReadHistograms,
+              (std::vector<HistogramBucket>*),
+              (override));
+  MOCK_METHOD(void,
+              ReadArgSamples,
+              (size_t, std::vector<ArgSample>*),
+              (override));
+};
+
+HistogramBucket Bucket(int32_t pid,
+                       int32_t tid,
+                       uint32_t syscall_id,
+                       uint32_t bucket,
+                       uint64_t count) {
+  HistogramBucket b;
+  b.pid = pid;
+  b.tid = tid;
+  b.syscall_id = syscall_id;
+  b.bucket = bucket;
+  b.count = count;
+  return b;
+}
+
+class SyscallLatencyDataSourceTest : public ::testing::Test {
+ protected:
+  std::unique_ptr<SyscallLatencyDataSource> GetSyscallLatencyDataSource(
+      const protos::gen::SyscallLatencyConfig& syscall_latency_cfg) {
+    DataSourceConfig config;
+    config.set_syscall_latency_config_raw(
+        syscall_latency_cfg.SerializeAsString());
+    auto writer = std::make_unique<TraceWriterForTesting>();
+    writer_raw_ = writer.get();
+    auto loader = std::make_unique<MockSyscallLatencyBpfLoader>();
+    loader_raw_ = loader.get();
+    return std::make_unique<SyscallLatencyDataSource>(
+        config, &task_runner_, 0, std::move(writer), std::move(loader));
+  }
+
+  // Makes ReadHistograms() return |buckets| on its next call.
+  void SetHistograms(std::vector<HistogramBucket> buckets) {
+    EXPECT_CALL(*loader_raw_, ReadHistograms(_))
+        .WillOnce(Invoke([buckets](std::vector<HistogramBucket>* out) {
+          *out = buckets;
+        }));
+  }
+
+  TraceWriterForTesting* writer_raw_ = nullptr;
+  MockSyscallLatencyBpfLoader* loader_raw_ = nullptr;
+  base::TestTaskRunner task_runner_;
+};
+
+TEST_F(SyscallLatencyDataSourceTest, Config) {
+  protos::gen::SyscallLatencyConfig cfg;
+  cfg.set_dump_period_ms(10);
+  cfg.add_arg_sampling_syscalls("sys_openat");
+  cfg.add_arg_sampling_syscalls("sys_does_not_exist");
+  cfg.set_arg_sampling_rate(8);
+  auto data_source = GetSyscallLatencyDataSource(cfg);
+
+  EXPECT_EQ(data_source->dump_period_ms(), 100u);
+  std::optional<size_t> openat =
+      SyscallTable::FromCurrentArch().GetByName("sys_openat");
+  if (!openat)
+    return;  // Not a supported architecture.
+  const std::vector<uint32_t>& rates = data_source->arg_sampling_rates();
+  ASSERT_EQ(rates.size(), *openat + 1);
+  EXPECT_EQ(rates[*openat], 8u);
+  for (size_t i = 0; i < *openat; i++)
+    EXPECT_EQ(rates[i], 0u);
+}
+
+TEST_F(SyscallLatencyDataSourceTest, LoadFailure) {
+  auto data_source =
+      GetSyscallLatencyDataSource(protos::gen::SyscallLatencyConfig());
+  EXPECT_CALL(*loader_raw_, Load(IsEmpty()))
+      .WillOnce(Return(base::ErrStatus("no BPF")));
+  EXPECT_CALL(*loader_raw_, ReadHistograms(_)).Times(0);
+  data_source->Start();
+  data_source->Flush(1, [] {});
+  EXPECT_THAT(writer_raw_->GetAllTracePackets(), IsEmpty());
+}
+
+TEST_F(SyscallLatencyDataSourceTest, Histograms) {
+  auto data_source =
+      GetSyscallLatencyDataSource(protos::gen::SyscallLatencyConfig());
+  EXPECT_CALL(*loader_raw_, Load(_)).WillOnce(Return(base::OkStatus()));
+  EXPECT_CALL(*loader_raw_, ReadArgSamples(_, _)).Times(2);
+  data_source->Start();
+
+  SetHistograms({
+      Bucket(10, 11, 0, 12, 3),
+      Bucket(10, 10, 1, 4, 1),
+      Bucket(10, 11, 0, 10, 2),
+      Bucket(10, 11, 1, 20, 5),
+  });
+  data_source->Flush(1, [] {});
+
+  // The buckets of 11/0 have not changed, 11/1 has been evicted and created
+  // again and 10/2 is new.
+  SetHistograms({
+      Bucket(10, 11, 0, 12, 3),
+      Bucket(10, 10, 1, 4, 4),
+      Bucket(10, 11, 0, 10, 2),
+      Bucket(10, 11, 1, 20, 1),
+      Bucket(10, 10, 2, 7, 6),
+  });
+  data_source->Flush(2, [] {});
+
+  auto packets = writer_raw_->GetAllTracePackets();
+  ASSERT_EQ(packets.size(), 2u);
+
+  const auto& first = packets[0].syscall_latency().syscalls();
+  ASSERT_EQ(first.size(), 3u);
+  EXPECT_EQ(first[0].pid(), 10);
+  EXPECT_EQ(first[0].tid(), 10);
+  EXPECT_EQ(first[0].syscall_id(), 1u);
+  EXPECT_THAT(first[0].buckets(), ElementsAre(4u));
+  EXPECT_THAT(first[0].counts(), ElementsAre(1u));
+  EXPECT_EQ(first[1].tid(), 11);
+  EXPECT_EQ(first[1].syscall_id(), 0u);
+  EXPECT_THAT(first[1].buckets(), ElementsAre(10u, 12u));
+  EXPECT_THAT(first[1].counts(), ElementsAre(2u, 3u));
+  EXPECT_EQ(first[2].tid(), 11);
+  EXPECT_EQ(first[2].syscall_id(), 1u);
+  EXPECT_THAT(first[2].buckets(), ElementsAre(20u));
+  EXPECT_THAT(first[2].counts(), ElementsAre(5u));
+
+  const auto& second = packets[1].syscall_latency().syscalls();
+  ASSERT_EQ(second.size(), 3u);
+  EXPECT_EQ(second[0].tid(), 10);
+  EXPECT_EQ(second[0].syscall_id(), 1u);
+  EXPECT_THAT(second[0].counts(), ElementsAre(3u));
+  EXPECT_EQ(second[1].tid(), 10);
+  EXPECT_EQ(second[1].syscall_id(), 2u);
+  EXPECT_THAT(second[1].buckets(), ElementsAre(7u));
+  EXPECT_THAT(second[1].counts(), ElementsAre(6u));
+  EXPECT_EQ(second[2].tid(), 11);
+  EXPECT_EQ(second[2].syscall_id(), 1u);
+  EXPECT_THAT(second[2].counts(), ElementsAre(1u));
+}
+
+TEST_F(SyscallLatencyDataSourceTest, ArgSamples) {
+  auto data_source =
+      GetSyscallLatencyDataSource(protos::gen::SyscallLatencyConfig());
+  EXPECT_CALL(*loader_raw_, Load(_)).WillOnce(Return(base::OkStatus()));
+  data_source->Start();
+
+  SetHistograms({});
+  EXPECT_CALL(*loader_raw_,
+              ReadArgSamples(SyscallLatencyDataSource::kMaxArgSamplesPerDump,
+                             _))
+      .WillOnce(Invoke([](size_t, std::vector<ArgSample>* out) {
+        ArgSample sample;
+        sample.ts = 1234;
+        sample.pid = 10;
+        sample.tid = 11;
+        sample.syscall_id = 257;
+        sample.args = {1, 2, 3, 4, 5, 6};
+        out->push_back(sample);
+      }));
+  data_source->Flush(1, [] {});
+
+  auto packet = writer_raw_->GetOnlyTracePacket();
+  ASSERT_TRUE(packet.has_syscall_latency());
+  EXPECT_THAT(packet.syscall_latency().syscalls(), IsEmpty());
+  const auto& samples = packet.syscall_latency().arg_samples();
+  ASSERT_EQ(samples.size(), 1u);
+  EXPECT_EQ(samples[0].ts(), 1234u);
+  EXPECT_EQ(samples[0].pid(), 10);
+  EXPECT_EQ(samples[0].tid(), 11);
+  EXPECT_EQ(samples[0].syscall_id(), 257u);
+  EXPECT_THAT(samples[0].args(), ElementsAre(1u, 2u, 3u, 4u, 5u, 6u));
+}
+
+TEST_F(SyscallLatencyDataSourceTest, PeriodicDump) {
+  protos::gen::SyscallLatencyConfig cfg;
+  cfg.set_dump_period_ms(100);
+  auto data_source = GetSyscallLatencyDataSource(cfg);
+  EXPECT_CALL(*loader_raw_, Load(_)).WillOnce(Return(base::OkStatus()));
+  EXPECT_CALL(*loader_raw_, ReadArgSamples(_, _));
+
+  auto dumped = task_runner_.CreateCheckpoint("dumped");
+  EXPECT_CALL(*loader_raw_, ReadHistograms(_))
+      .WillOnce(Invoke([&dumped](std::vector<HistogramBucket>* out) {
+        out->push_back(Bucket(1, 1, 0, 0, 1));
+        dumped();
+      }))
+      .WillRepeatedly(Return());
+  data_source->Start();
+  task_runner_.RunUntilCheckpoint("dumped");
+
+  auto packets = writer_raw_->GetAllTracePackets();
+  ASSERT_GE(packets.size(), 1u);
+  EXPECT_EQ(packets[0].syscall_latency().syscalls_size(), 1);
+}
+
+}  // namespace
+}  // namespace perfetto
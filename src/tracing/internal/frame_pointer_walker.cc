@@ -0,0 +1,118 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/tracing/internal/frame_pointer_walker.h"
+
+#include "perfetto/base/build_config.h"
+#include "src/profiling/common/frame_record.h"
+
+#if PERFETTO_BUILDFLAG(PERFETTO_OS_LINUX) || \
+    PERFETTO_BUILDFLAG(PERFETTO_OS_ANDROID)
+#include <sys/syscall.h>
+#include <sys/uio.h>
+#include <unistd.h>
+#endif
+
+namespace perfetto {
+namespace internal {
+
+namespace {
+
+using profiling::FrameRecordArch;
+
+#if PERFETTO_BUILDFLAG(PERFETTO_OS_LINUX) || \
+    PERFETTO_BUILDFLAG(PERFETTO_OS_ANDROID)
+#if defined(__aarch64__)
+constexpr FrameRecordArch kArch = FrameRecordArch::kArm64;
+// Return addresses point past the call instruction.
+constexpr uint64_t kPcAdjustment = 4;
+#elif defined(__x86_64__)
+constexpr FrameRecordArch kArch = FrameRecordArch::kX86_64;
+constexpr uint64_t kPcAdjustment = 1;
+#else
+constexpr FrameRecordArch kArch = FrameRecordArch::kUnsupported;
+constexpr uint64_t kPcAdjustment = 0;
+#endif
+#else
+constexpr FrameRecordArch kArch = FrameRecordArch::kUnsupported;
+#endif
+
+constexpr bool kArchSupported = kArch != FrameRecordArch::kUnsupported;
+
+}  // namespace
+
+bool IsFramePointerWalkingSupported() {
+  return kArchSupported;
+}
+
+bool SafeReadMemory(uint64_t addr, void* dst, size_t size) {
+#if PERFETTO_BUILDFLAG(PERFETTO_OS_LINUX) || \
+    PERFETTO_BUILDFLAG(PERFETTO_OS_ANDROID)
+  // process_vm_readv on the current process fails with EFAULT on unmapped or
+  // unreadable addresses, instead of raising a signal. The raw syscall is used
+  // as the libc wrapper isn't available on older Android versions.
+  struct iovec local = {dst, size};
+  struct iovec remote = {reinterpret_cast<void*>(addr), size};
+  long res = syscall(__NR_process_vm_readv, getpid(), &local, 1, &remote, 1, 0);
+  return res == static_cast<long>(size);
+#else
+  (void)addr;
+  (void)dst;
+  (void)size;
+  return false;
+#endif
+}
+
+size_t WalkFramePointers(uint64_t pc,
+                         uint64_t fp,
+                         uint64_t sp,
+                         uint64_t stack_end,
+                         uint64_t* pcs,
+                         size_t max_frames) {
+  if (max_frames == 0)
+    return 0;
+
+  size_t num_frames = 0;
+  pcs[num_frames++] = pc;
+#if PERFETTO_BUILDFLAG(PERFETTO_OS_LINUX) || \
+    PERFETTO_BUILDFLAG(PERFETTO_OS_ANDROID)
+  if (!kArchSupported)
+    return num_frames;
+
+  while (num_frames < max_frames &&
+         profiling::IsFrameRecordValid(kArch, fp, sp, stack_end)) {
+    uint64_t record[2];
+    if (!SafeReadMemory(fp, record, sizeof(record)))
+      break;
+    uint64_t next_pc = 0;
+    fp = profiling::DecodeFrameRecord(kArch, fp, record, &next_pc, &sp);
+    // A null return address terminates the chain (e.g. at the thread entry
+    // point).
+    if (next_pc == 0)
+      break;
+    pcs[num_frames++] =
+        next_pc >= kPcAdjustment ? next_pc - kPcAdjustment : next_pc;
+  }
+#else
+  (void)fp;
+  (void)sp;
+  (void)stack_end;
+#endif
+  return num_frames;
+}
+
+}  // namespace internal
+}  // namespace perfetto
@@ -0,0 +1,59 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SRC_TRACING_INTERNAL_FRAME_POINTER_WALKER_H_
+#define SRC_TRACING_INTERNAL_FRAME_POINTER_WALKER_H_
+
+#include <stddef.h>
+#include <stdint.h>
+
+namespace perfetto {
+namespace internal {
+
+// In-process counterpart of profiling::FramePointerUnwinder (see
+// src/profiling/perf/frame_pointer_unwinder.cc), used by the SDK CPU profiler
+// to unwind the interrupted thread from within a signal handler. Both share
+// the frame record validation rules of src/profiling/common/frame_record.h,
+// but this reads the stack of the current process directly instead of a copy
+// of it, and doesn't resolve mappings (which is left to the caller, outside of
+// the signal handler).
+//
+// All functions below are async-signal-safe.
+
+// Whether frame pointer walking is implemented for the current architecture.
+bool IsFramePointerWalkingSupported();
+
+// Copies |size| bytes at |addr| of the current process into |dst|. Returns
+// false, rather than faulting, if the memory is not readable.
+bool SafeReadMemory(uint64_t addr, void* dst, size_t size);
+
+// Walks the chain of frame records starting from the register state of the
+// interrupted code (|pc|, |fp|, |sp|), storing the program counters of up to
+// |max_frames| frames into |pcs|, leaf first. The walk stops at the first
+// frame record which is not within [sp, stack_end), is not properly aligned or
+// can't be read. The return addresses of non-leaf frames are adjusted to point
+// to the call instruction. Returns the number of frames stored.
+size_t WalkFramePointers(uint64_t pc,
+                         uint64_t fp,
+                         uint64_t sp,
+                         uint64_t stack_end,
+                         uint64_t* pcs,
+                         size_t max_frames);
+
+}  // namespace internal
+}  // namespace perfetto
+
+#endif  // SRC_TRACING_INTERNAL_FRAME_POINTER_WALKER_H_
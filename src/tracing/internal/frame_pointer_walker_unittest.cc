@@ -0,0 +1,155 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "src/tracing/internal/frame_pointer_walker.h"
+
+#include <sys/mman.h>
+#include <unistd.h>
+
+#include <cstdint>
+#include <vector>
+
+#include "test/gtest_and_gmock.h"
+
+namespace perfetto {
+namespace internal {
+namespace {
+
+using ::testing::ElementsAre;
+
+#if defined(__aarch64__)
+constexpr uint64_t kPcAdjustment = 4;
+#else
+constexpr uint64_t kPcAdjustment = 1;
+#endif
+
+uint64_t Addr(const void* p) {
+  return static_cast<uint64_t>(reinterpret_cast<uintptr_t>(p));
+}
+
+// A fake stack with three frame records, at words 2, 6 and 10. The outermost
+// one has a null frame pointer, which terminates the chain.
+class FramePointerWalkerTest : public ::testing::Test {
+ protected:
+  void SetUp() override {
+    if (!IsFramePointerWalkingSupported())
+      GTEST_SKIP() << "Frame pointer walking not supported";
+    stack_[2] = Addr(&stack_[6]);
+    stack_[3] = 0x2004;
+    stack_[6] = Addr(&stack_[10]);
+    stack_[7] = 0x3004;
+    stack_[10] = 0;
+    stack_[11] = 0x4004;
+  }
+
+  std::vector<uint64_t> Walk(uint64_t fp, size_t max_frames = 16) {
+    return Walk(fp, Addr(&stack_[16]), max_frames);
+  }
+
+  std::vector<uint64_t> Walk(uint64_t fp,
+                             uint64_t stack_end,
+                             size_t max_frames) {
+    std::vector<uint64_t> pcs(max_frames);
+    size_t n = WalkFramePointers(kLeafPc, fp, Addr(&stack_[0]), stack_end,
+                                 pcs.data(), max_frames);
+    pcs.resize(n);
+    return pcs;
+  }
+
+  static constexpr uint64_t kLeafPc = 0x1000;
+  alignas(16) uint64_t stack_[16] = {};
+};
+
+TEST_F(FramePointerWalkerTest, WalksFrameRecords) {
+  EXPECT_THAT(Walk(Addr(&stack_[2])),
+              ElementsAre(kLeafPc, 0x2004 - kPcAdjustment,
+                          0x3004 - kPcAdjustment, 0x4004 - kPcAdjustment));
+}
+
+TEST_F(FramePointerWalkerTest, RespectsMaxFrames) {
+  EXPECT_THAT(Walk(Addr(&stack_[2]), 2),
+              ElementsAre(kLeafPc, 0x2004 - kPcAdjustment));
+  EXPECT_THAT(Walk(Addr(&stack_[2]), 1), ElementsAre(kLeafPc));
+  EXPECT_THAT(Walk(Addr(&stack_[2]), 0), ElementsAre());
+}
+
+TEST_F(FramePointerWalkerTest, StopsAtNullReturnAddress) {
+  stack_[7] = 0;
+  EXPECT_THAT(Walk(Addr(&stack_[2])),
+              ElementsAre(kLeafPc, 0x2004 - kPcAdjustment));
+}
+
+TEST_F(FramePointerWalkerTest, StopsAtNullFramePointer) {
+  EXPECT_THAT(Walk(0), ElementsAre(kLeafPc));
+}
+
+TEST_F(FramePointerWalkerTest, StopsAtFrameBelowStackPointer) {
+  // The second record points back into the first one.
+  stack_[6] = Addr(&stack_[2]);
+  EXPECT_THAT(Walk(Addr(&stack_[2])),
+              ElementsAre(kLeafPc, 0x2004 - kPcAdjustment,
+                          0x3004 - kPcAdjustment));
+}
+
+TEST_F(FramePointerWalkerTest, AcceptsFrameAtStackPointer) {
+  // E.g. a leaf function which doesn't adjust the stack pointer.
+  std::vector<uint64_t> pcs(16);
+  size_t n = WalkFramePointers(kLeafPc, Addr(&stack_[2]), Addr(&stack_[2]),
+                               Addr(&stack_[16]), pcs.data(), pcs.size());
+  EXPECT_EQ(n, 4u);
+}
+
+TEST_F(FramePointerWalkerTest, StopsAtFrameBeyondStackEnd) {
+  EXPECT_THAT(Walk(Addr(&stack_[2]), Addr(&stack_[8]), 16),
+              ElementsAre(kLeafPc, 0x2004 - kPcAdjustment,
+                          0x3004 - kPcAdjustment));
+}
+
+TEST_F(FramePointerWalkerTest, StopsAtMisalignedFrame) {
+  stack_[6] = Addr(&stack_[10]) + 1;
+  EXPECT_THAT(Walk(Addr(&stack_[2])),
+              ElementsAre(kLeafPc, 0x2004 - kPcAdjustment,
+                          0x3004 - kPcAdjustment));
+}
+
+TEST_F(FramePointerWalkerTest, StopsAtUnreadableFrame) {
+  size_t page_size = static_cast<size_t>(sysconf(_SC_PAGESIZE));
+  void* page = mmap(nullptr, page_size, PROT_NONE, MAP_PRIVATE | MAP_ANONYMOUS,
+                    -1, 0);
+  ASSERT_NE(page, MAP_FAILED);
+  uint64_t dst[2];
+  EXPECT_FALSE(SafeReadMemory(Addr(page), dst, sizeof(dst)));
+
+  // Pretend the stack extends over the inaccessible page.
+  std::vector<uint64_t> pcs(16);
+  size_t n = WalkFramePointers(kLeafPc, Addr(page), Addr(page) - 16,
+                               Addr(page) + page_size, pcs.data(), pcs.size());
+  EXPECT_EQ(n, 1u);
+  munmap(page, page_size);
+}
+
+TEST(SafeReadMemoryTest, ReadsOwnMemory) {
+  uint64_t src[2] = {0x1234, 0x5678};
+  uint64_t dst[2] = {};
+  ASSERT_TRUE(SafeReadMemory(Addr(src), dst, sizeof(dst)));
+  EXPECT_EQ(dst[0], 0x1234u);
+  EXPECT_EQ(dst[1], 0x5678u);
+  EXPECT_FALSE(SafeReadMemory(0, dst, sizeof(dst)));
+}
+
+}  // namespace
+}  // namespace internal
+}  // namespace perfetto
@@ -0,0 +1,760 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "perfetto/tracing/sdk_cpu_profiler.h"
+
+#include "perfetto/base/build_config.h"
+#include "perfetto/base/logging.h"
+
+#if (PERFETTO_BUILDFLAG(PERFETTO_OS_LINUX) ||   \
+     PERFETTO_BUILDFLAG(PERFETTO_OS_ANDROID)) && \
+    (defined(__x86_64__) || defined(__aarch64__))
+#define PERFETTO_SDK_CPU_PROFILER_SUPPORTED 1
+#else
+#define PERFETTO_SDK_CPU_PROFILER_SUPPORTED 0
+#endif
+
+#if PERFETTO_SDK_CPU_PROFILER_SUPPORTED
+
+#include <dirent.h>
+#include <elf.h>
+#include <errno.h>
+#include <link.h>
+#include <sched.h>
+#include <signal.h>
+#include <sys/syscall.h>
+#include <time.h>
+#include <ucontext.h>
+#include <unistd.h>
+
+#include <algorithm>
+#include <atomic>
+#include <cinttypes>
+#include <condition_variable>
+#include <functional>
+#include <map>
+#include <mutex>
+#include <optional>
+#include <set>
+#include <string>
+#include <thread>
+#include <utility>
+#include <vector>
+
+#include "perfetto/base/thread_utils.h"
+#include "perfetto/base/time.h"
+#include "perfetto/ext/base/file_utils.h"
+#include "perfetto/ext/base/scoped_file.h"
+#include "perfetto/ext/base/string_utils.h"
+#include "perfetto/ext/base/thread_utils.h"
+#include "perfetto/ext/base/utils.h"
+#include "perfetto/tracing/core/data_source_config.h"
+#include "perfetto/tracing/core/data_source_descriptor.h"
+#include "perfetto/tracing/data_source.h"
+#include "src/tracing/internal/frame_pointer_walker.h"
+
+#include "protos/perfetto/common/perf_events.pbzero.h"
+#include "protos/perfetto/config/profiling/sdk_cpu_profiler_config.pbzero.h"
+#include "protos/perfetto/trace/interned_data/interned_data.pbzero.h"
+#include "protos/perfetto/trace/profiling/profile_common.pbzero.h"
+#include "protos/perfetto/trace/profiling/profile_packet.pbzero.h"
+#include "protos/perfetto/trace/trace_packet.pbzero.h"
+#include "protos/perfetto/trace/trace_packet_defaults.pbzero.h"
+
+// Older libc headers only expose the union member.
+#ifndef sigev_notify_thread_id
+#define sigev_notify_thread_id _sigev_un._tid
+#endif
+
+namespace perfetto {
+namespace internal {
+
+namespace {
+
+constexpr uint32_t kDefaultSamplingFrequencyHz = 100;
+constexpr uint32_t kMaxSamplingFrequencyHz = 1000;
+constexpr uint32_t kDefaultMaxFrames = 64;
+constexpr uint32_t kMaxFrames = 128;
+constexpr uint32_t kDefaultPollPeriodMs = 100;
+
+// Number of samples that can be buffered between two polls of the worker
+// thread. Samples taken while the buffer is full are dropped.
+constexpr uint32_t kNumSampleSlots = 512;
+
+// Upper bound of the stack size of the sampled threads. Only used to bound the
+// frame pointer walk, reads past the actual end of the stack fail safely.
+constexpr uint64_t kMaxStackSize = 8 * 1024 * 1024;
+
+// A sample recorded by the signal handler. The handler moves a slot from
+// kFree to kWriting to kReady, the worker thread moves it back to kFree once
+// the sample has been written into the trace.
+struct SampleSlot {
+  enum State : uint32_t { kFree = 0, kWriting, kReady };
+
+  std::atomic<uint32_t> state{kFree};
+  uint64_t timestamp_ns;
+  int32_t tid;
+  int32_t cpu;
+  size_t num_frames;
+  uint64_t pcs[kMaxFrames];
+};
+
+struct SamplerState {
+  std::atomic<bool> enabled{false};
+  std::atomic<uint32_t> handlers_running{0};
+  std::atomic<uint32_t> next_slot{0};
+  std::atomic<uint32_t> max_frames{kDefaultMaxFrames};
+  std::atomic<uint64_t> samples_dropped{0};
+  SampleSlot slots[kNumSampleSlots];
+};
+
+// Allocated on the first start and intentionally leaked: a SIGPROF delivered
+// after the data source has been destroyed must still find valid memory.
+std::atomic<SamplerState*> g_sampler_state{nullptr};
+
+SamplerState* GetOrCreateSamplerState() {
+  SamplerState* state = g_sampler_state.load(std::memory_order_acquire);
+  if (!state) {
+    state = new SamplerState();
+    g_sampler_state.store(state, std::memory_order_release);
+  }
+  return state;
+}
+
+void GetRegisters(const ucontext_t* uc,
+                  uint64_t* pc,
+                  uint64_t* fp,
+                  uint64_t* sp) {
+#if defined(__x86_64__)
+  *pc = static_cast<uint64_t>(uc->uc_mcontext.gregs[REG_RIP]);
+  *fp = static_cast<uint64_t>(uc->uc_mcontext.gregs[REG_RBP]);
+  *sp = static_cast<uint64_t>(uc->uc_mcontext.gregs[REG_RSP]);
+#elif defined(__aarch64__)
+  *pc = uc->uc_mcontext.pc;
+  *fp = uc->uc_mcontext.regs[29];
+  *sp = uc->uc_mcontext.sp;
+#endif
+}
+
+void RecordSample(SamplerState* state, siginfo_t* info, ucontext_t* uc) {
+  uint32_t idx = state->next_slot.fetch_add(1, std::memory_order_relaxed) %
+                 kNumSampleSlots;
+  SampleSlot& slot = state->slots[idx];
+  uint32_t expected = SampleSlot::kFree;
+  if (!slot.state.compare_exchange_strong(expected, SampleSlot::kWriting,
+                                          std::memory_order_acquire)) {
+    state->samples_dropped.fetch_add(1, std::memory_order_relaxed);
+    return;
+  }
+
+  uint64_t pc = 0;
+  uint64_t fp = 0;
+  uint64_t sp = 0;
+  GetRegisters(uc, &pc, &fp, &sp);
+  uint64_t stack_end = sp + std::min(kMaxStackSize, ~uint64_t{0} - sp);
+
+  slot.timestamp_ns = static_cast<uint64_t>(base::GetBootTimeNs().count());
+  slot.tid = info->si_value.sival_int;
+  slot.cpu = sched_getcpu();
+  slot.num_frames =
+      WalkFramePointers(pc, fp, sp, stack_end, slot.pcs,
+                        state->max_frames.load(std::memory_order_relaxed));
+  slot.state.store(SampleSlot::kReady, std::memory_order_release);
+}
+
+// Must be async-signal-safe.
+void SigprofHandler(int, siginfo_t* info, void* ucontext) {
+  // Ignore SIGPROFs which weren't raised by our timers.
+  if (info->si_code != SI_TIMER)
+    return;
+  SamplerState* state = g_sampler_state.load(std::memory_order_acquire);
+  if (!state)
+    return;
+
+  int saved_errno = errno;
+  // Incremented before checking |enabled|, so that the worker thread can wait
+  // for the in-flight handlers after disabling the sampling.
+  state->handlers_running.fetch_add(1);
+  if (state->enabled.load())
+    RecordSample(state, info, static_cast<ucontext_t*>(ucontext));
+  state->handlers_running.fetch_sub(1);
+  errno = saved_errno;
+}
+
+// Installs SigprofHandler, unless the process has its own SIGPROF handler.
+// The handler is never uninstalled, as timer signals can still be pending
+// after the timers are deleted, and the default action of SIGPROF is to
+// terminate the process.
+bool InstallSignalHandler() {
+  struct sigaction old_action {};
+  if (sigaction(SIGPROF, nullptr, &old_action) != 0) {
+    PERFETTO_PLOG("sigaction(SIGPROF)");
+    return false;
+  }
+  if (old_action.sa_flags & SA_SIGINFO)
+    return old_action.sa_sigaction == SigprofHandler;
+  if (old_action.sa_handler != SIG_DFL && old_action.sa_handler != SIG_IGN)
+    return false;
+
+  struct sigaction action {};
+  action.sa_sigaction = SigprofHandler;
+  action.sa_flags = SA_SIGINFO | SA_RESTART;
+  sigemptyset(&action.sa_mask);
+  if (sigaction(SIGPROF, &action, nullptr) != 0) {
+    PERFETTO_PLOG("sigaction(SIGPROF)");
+    return false;
+  }
+  return true;
+}
+
+// Equivalent of MAKE_THREAD_CPUCLOCK(tid, CPUCLOCK_SCHED) from the kernel.
+clockid_t ThreadCpuClock(int32_t tid) {
+  return static_cast<clockid_t>((~static_cast<uint32_t>(tid)) << 3) | 6;
+}
+
+// Creates a timer which sends a SIGPROF to |tid| every 1/|frequency_hz|
+// seconds of CPU time consumed by the thread. The raw syscalls are used as the
+// libc wrappers don't support thread-directed notifications portably.
+std::optional<int> CreateThreadTimer(int32_t tid, uint32_t frequency_hz) {
+  struct sigevent sev {};
+  sev.sigev_notify = SIGEV_THREAD_ID;
+  sev.sigev_signo = SIGPROF;
+  sev.sigev_value.sival_int = tid;
+  sev.sigev_notify_thread_id = tid;
+  int timer_id = 0;
+  if (syscall(__NR_timer_create, ThreadCpuClock(tid), &sev, &timer_id) != 0)
+    return std::nullopt;  // The thread has likely exited.
+
+  uint64_t interval_ns = 1000000000ull / frequency_hz;
+  struct itimerspec its {};
+  its.it_interval.tv_sec = static_cast<time_t>(interval_ns / 1000000000ull);
+  its.it_interval.tv_nsec = static_cast<long>(interval_ns % 1000000000ull);
+  its.it_value = its.it_interval;
+  if (syscall(__NR_timer_settime, timer_id, 0, &its, nullptr) != 0) {
+    syscall(__NR_timer_delete, timer_id);
+    return std::nullopt;
+  }
+  return timer_id;
+}
+
+void DeleteThreadTimer(int timer_id) {
+  syscall(__NR_timer_delete, timer_id);
+}
+
+// An executable segment of an ELF file loaded in the process.
+struct CodeMapping {
+  uint64_t start = 0;
+  uint64_t end = 0;
+  uint64_t exact_offset = 0;
+  uint64_t load_bias = 0;
+  // Address which ELF virtual addresses are relative to (dlpi_addr).
+  uint64_t base = 0;
+  std::string path;
+  std::string build_id;
+};
+
+std::string ReadBuildId(const dl_phdr_info* info) {
+  for (size_t i = 0; i < info->dlpi_phnum; i++) {
+    const ElfW(Phdr)& phdr = info->dlpi_phdr[i];
+    if (phdr.p_type != PT_NOTE)
+      continue;
+    const char* note = reinterpret_cast<const char*>(info->dlpi_addr +
+                                                     phdr.p_vaddr);
+    const char* note_end = note + phdr.p_memsz;
+    while (note + sizeof(ElfW(Nhdr)) <= note_end) {
+      const auto* nhdr = reinterpret_cast<const ElfW(Nhdr)*>(note);
+      const char* name = note + sizeof(ElfW(Nhdr));
+      const char* desc = name + ((nhdr->n_namesz + 3) & ~3u);
+      const char* next = desc + ((nhdr->n_descsz + 3) & ~3u);
+      if (next > note_end)
+        break;
+      if (nhdr->n_type == NT_GNU_BUILD_ID && nhdr->n_namesz == 4 &&
+          memcmp(name, "GNU", 4) == 0) {
+        return std::string(desc, nhdr->n_descsz);
+      }
+      note = next;
+    }
+  }
+  return "";
+}
+
+std::vector<CodeMapping> ReadCodeMappings() {
+  std::vector<CodeMapping> mappings;
+  dl_iterate_phdr(
+      [](dl_phdr_info* info, size_t, void* data) {
+        auto* out = static_cast<std::vector<CodeMapping>*>(data);
+        std::string path = info->dlpi_name ? info->dlpi_name : "";
+        if (path.empty()) {
+          // The main executable.
+          char buf[4096];
+          ssize_t len = readlink("/proc/self/exe", buf, sizeof(buf));
+          if (len > 0)
+            path.assign(buf, static_cast<size_t>(len));
+        }
+        std::string build_id = ReadBuildId(info);
+        uint64_t page_size = static_cast<uint64_t>(base::GetSysPageSize());
+        for (size_t i = 0; i < info->dlpi_phnum; i++) {
+          const ElfW(Phdr)& phdr = info->dlpi_phdr[i];
+          if (phdr.p_type != PT_LOAD || !(phdr.p_flags & PF_X))
+            continue;
+          CodeMapping mapping;
+          uint64_t vaddr = info->dlpi_addr + phdr.p_vaddr;
+          mapping.start = vaddr & ~(page_size - 1);
+          mapping.end = (vaddr + phdr.p_memsz + page_size - 1) &
+                        ~(page_size - 1);
+          mapping.exact_offset = phdr.p_offset & ~(page_size - 1);
+          mapping.load_bias = phdr.p_vaddr - phdr.p_offset;
+          mapping.base = info->dlpi_addr;
+          mapping.path = path;
+          mapping.build_id = build_id;
+          out->push_back(std::move(mapping));
+        }
+        return 0;
+      },
+      &mappings);
+  std::sort(mappings.begin(), mappings.end(),
+            [](const CodeMapping& a, const CodeMapping& b) {
+              return a.start < b.start;
+            });
+  return mappings;
+}
+
+// A sample copied out of its SampleSlot by the worker thread.
+struct Sample {
+  uint64_t timestamp_ns;
+  int32_t tid;
+  int32_t cpu;
+  std::vector<uint64_t> pcs;
+};
+
+}  // namespace
+
+struct SdkCpuProfilerIncrementalState {
+  bool was_cleared = true;
+  uint64_t last_iid = 0;
+  std::map<std::string, uint64_t> path_strings;
+  std::map<std::string, uint64_t> build_ids;
+  std::map<uint64_t, uint64_t> mappings;  // Keyed by the mapping start.
+  std::map<std::pair<uint64_t, uint64_t>, uint64_t> frames;
+  std::map<std::vector<uint64_t>, uint64_t> callstacks;
+};
+
+struct SdkCpuProfilerDataSourceTraits : public DefaultDataSourceTraits {
+  using IncrementalStateType = SdkCpuProfilerIncrementalState;
+};
+
+class SdkCpuProfilerDataSource
+    : public DataSource<SdkCpuProfilerDataSource,
+                        SdkCpuProfilerDataSourceTraits> {
+ public:
+  static constexpr bool kSupportsMultipleInstances = false;
+
+  ~SdkCpuProfilerDataSource() override;
+
+  void OnSetup(const SetupArgs&) override;
+  void OnStart(const StartArgs&) override;
+  void OnStop(const StopArgs&) override;
+
+ private:
+  using InternedData = protos::pbzero::InternedData;
+
+  void Run();
+  void UpdateTimers();
+  bool ShouldSample(int32_t tid);
+  void DeleteAllTimers();
+  void DrainSamples();
+  void WriteSamples(const std::vector<Sample>& samples);
+  // Returns the iid of the callstack of |pcs| (leaf first), emitting the
+  // newly interned entries into the message returned by |interned_data|.
+  uint64_t InternCallstack(SdkCpuProfilerIncrementalState* incr,
+                           const std::vector<uint64_t>& pcs,
+                           const std::function<InternedData*()>& interned_data);
+  const CodeMapping* FindMapping(uint64_t pc);
+
+  // Config, set in OnSetup().
+  uint32_t sampling_frequency_ = kDefaultSamplingFrequencyHz;
+  uint32_t max_frames_ = kDefaultMaxFrames;
+  uint32_t poll_period_ms_ = kDefaultPollPeriodMs;
+  std::set<int32_t> target_tids_;
+  std::set<std::string> target_thread_names_;
+  std::set<std::string> exclude_thread_names_;
+
+  SamplerState* sampler_state_ = nullptr;
+  int32_t pid_ = 0;
+
+  // Accessed only on the worker thread.
+  int32_t worker_tid_ = 0;
+  std::map<int32_t, int> timers_;  // tid -> kernel timer id.
+  std::vector<CodeMapping> code_mappings_;
+  bool code_mappings_stale_ = true;
+
+  std::thread worker_;
+  std::mutex mutex_;
+  std::condition_variable cv_;
+  bool stop_requested_ = false;  // Guarded by |mutex_|.
+  std::function<void()> stop_closure_;  // Guarded by |mutex_|.
+};
+
+SdkCpuProfilerDataSource::~SdkCpuProfilerDataSource() {
+  if (!worker_.joinable())
+    return;
+  {
+    std::lock_guard<std::mutex> lock(mutex_);
+    stop_requested_ = true;
+  }
+  cv_.notify_one();
+  worker_.join();
+}
+
+void SdkCpuProfilerDataSource::OnSetup(const SetupArgs& args) {
+  protos::pbzero::SdkCpuProfilerConfig::Decoder cfg(
+      args.config->sdk_cpu_profiler_config_raw());
+  if (cfg.sampling_frequency() > 0) {
+    sampling_frequency_ =
+        std::min(cfg.sampling_frequency(), kMaxSamplingFrequencyHz);
+  }
+  if (cfg.max_frames() > 0)
+    max_frames_ = std::min(cfg.max_frames(), kMaxFrames);
+  if (cfg.poll_period_ms() > 0)
+    poll_period_ms_ = cfg.poll_period_ms();
+  for (auto it = cfg.target_tid(); it; ++it)
+    target_tids_.insert(*it);
+  for (auto it = cfg.target_thread_name(); it; ++it)
+    target_thread_names_.insert(it->as_std_string());
+  for (auto it = cfg.exclude_thread_name(); it; ++it)
+    exclude_thread_names_.insert(it->as_std_string());
+}
+
+void SdkCpuProfilerDataSource::OnStart(const StartArgs&) {
+  if (!InstallSignalHandler()) {
+    PERFETTO_ELOG(
+        "sdk_cpu_profiler: the process has its own SIGPROF handler, not "
+        "starting");
+    return;
+  }
+  sampler_state_ = GetOrCreateSamplerState();
+  for (SampleSlot& slot : sampler_state_->slots)
+    slot.state.store(SampleSlot::kFree, std::memory_order_relaxed);
+  sampler_state_->samples_dropped.store(0, std::memory_order_relaxed);
+  sampler_state_->max_frames.store(max_frames_, std::memory_order_relaxed);
+  sampler_state_->enabled.store(true);
+  pid_ = static_cast<int32_t>(getpid());
+  worker_ = std::thread([this] { Run(); });
+}
+
+void SdkCpuProfilerDataSource::OnStop(const StopArgs& args) {
+  if (!worker_.joinable())
+    return;
+  // The worker thread writes the last samples and then acks the stop.
+  {
+    std::lock_guard<std::mutex> lock(mutex_);
+    stop_closure_ = args.HandleStopAsynchronously();
+    stop_requested_ = true;
+  }
+  cv_.notify_one();
+}
+
+void SdkCpuProfilerDataSource::Run() {
+  base::MaybeSetThreadName("perfetto-cpuprof");
+  worker_tid_ = static_cast<int32_t>(base::GetThreadId());
+  for (;;) {
+    UpdateTimers();
+    DrainSamples();
+    std::unique_lock<std::mutex> lock(mutex_);
+    cv_.wait_for(lock, std::chrono::milliseconds(poll_period_ms_),
+                 [this] { return stop_requested_; });
+    if (stop_requested_)
+      break;
+  }
+
+  // Disable the sampling, and wait for the signal handlers which might still
+  // be writing a sample before draining the buffer for the last time.
+  sampler_state_->enabled.store(false);
+  DeleteAllTimers();
+  while (sampler_state_->handlers_running.load() != 0)
+    std::this_thread::yield();
+  DrainSamples();
+
+  uint64_t dropped =
+      sampler_state_->samples_dropped.load(std::memory_order_relaxed);
+  if (dropped > 0) {
+    PERFETTO_ELOG("sdk_cpu_profiler: dropped %" PRIu64 " samples", dropped);
+  }
+
+  std::function<void()> stop_closure;
+  {
+    std::lock_guard<std::mutex> lock(mutex_);
+    stop_closure = std::move(stop_closure_);
+  }
+  Trace([](TraceContext ctx) { ctx.Flush(); });
+  if (stop_closure)
+    stop_closure();
+}
+
+bool SdkCpuProfilerDataSource::ShouldSample(int32_t tid) {
+  if (tid == worker_tid_)
+    return false;
+  if (target_tids_.empty() && target_thread_names_.empty() &&
+      exclude_thread_names_.empty()) {
+    return true;
+  }
+
+  std::string comm;
+  if (!target_thread_names_.empty() || !exclude_thread_names_.empty()) {
+    base::ReadFile("/proc/self/task/" + std::to_string(tid) + "/comm", &comm);
+    comm = base::StripSuffix(comm, "\n");
+  }
+  if (exclude_thread_names_.count(comm))
+    return false;
+  if (target_tids_.empty() && target_thread_names_.empty())
+    return true;
+  return target_tids_.count(tid) || target_thread_names_.count(comm);
+}
+
+void SdkCpuProfilerDataSource::UpdateTimers() {
+  base::ScopedDir task_dir(opendir("/proc/self/task"));
+  if (!task_dir) {
+    PERFETTO_PLOG("opendir(/proc/self/task)");
+    return;
+  }
+  std::set<int32_t> live_tids;
+  while (struct dirent* entry = readdir(*task_dir)) {
+    std::optional<int32_t> tid = base::CStringToInt32(entry->d_name);
+    if (!tid)
+      continue;
+    live_tids.insert(*tid);
+    if (timers_.count(*tid) || !ShouldSample(*tid))
+      continue;
+    std::optional<int> timer_id = CreateThreadTimer(*tid, sampling_frequency_);
+    if (timer_id)
+      timers_[*tid] = *timer_id;
+  }
+  for (auto it = timers_.begin(); it != timers_.end();) {
+    if (live_tids.count(it->first)) {
+      ++it;
+      continue;
+    }
+    DeleteThreadTimer(it->second);
+    it = timers_.erase(it);
+  }
+}
+
+void SdkCpuProfilerDataSource::DeleteAllTimers() {
+  for (const auto& tid_and_timer : timers_)
+    DeleteThreadTimer(tid_and_timer.second);
+  timers_.clear();
+}
+
+void SdkCpuProfilerDataSource::DrainSamples() {
+  std::vector<Sample> samples;
+  for (SampleSlot& slot : sampler_state_->slots) {
+    if (slot.state.load(std::memory_order_acquire) != SampleSlot::kReady)
+      continue;
+    samples.push_back(Sample{slot.timestamp_ns, slot.tid, slot.cpu,
+                             std::vector<uint64_t>(
+                                 slot.pcs, slot.pcs + slot.num_frames)});
+    slot.state.store(SampleSlot::kFree, std::memory_order_release);
+  }
+  if (samples.empty())
+    return;
+  std::sort(samples.begin(), samples.end(),
+            [](const Sample& a, const Sample& b) {
+              return a.timestamp_ns < b.timestamp_ns;
+            });
+  // New libraries might have been loaded since the last drain.
+  code_mappings_stale_ = true;
+  WriteSamples(samples);
+}
+
+const CodeMapping* SdkCpuProfilerDataSource::FindMapping(uint64_t pc) {
+  for (int attempt = 0; attempt < 2; attempt++) {
+    auto it = std::upper_bound(
+        code_mappings_.begin(), code_mappings_.end(), pc,
+        [](uint64_t addr, const CodeMapping& m) { return addr < m.start; });
+    if (it != code_mappings_.begin() && pc < std::prev(it)->end)
+      return &*std::prev(it);
+    // Rescan the loaded libraries at most once per drain.
+    if (!code_mappings_stale_)
+      break;
+    code_mappings_ = ReadCodeMappings();
+    code_mappings_stale_ = false;
+  }
+  return nullptr;
+}
+
+uint64_t SdkCpuProfilerDataSource::InternCallstack(
+    SdkCpuProfilerIncrementalState* incr,
+    const std::vector<uint64_t>& pcs,
+    const std::function<InternedData*()>& interned_data) {
+  // Used for pcs outside of any loaded ELF file (e.g. JIT code).
+  static const CodeMapping* kUnknownMapping = [] {
+    auto* mapping = new CodeMapping();
+    mapping->path = "[unknown]";
+    return mapping;
+  }();
+  auto intern_string = [&](std::map<std::string, uint64_t>* map,
+                           const std::string& str, bool is_build_id) {
+    auto it = map->find(str);
+    if (it != map->end())
+      return it->second;
+    uint64_t iid = ++incr->last_iid;
+    map->emplace(str, iid);
+    InternedData* interned = interned_data();
+    auto* msg = is_build_id ? interned->add_build_ids()
+                            : interned->add_mapping_paths();
+    msg->set_iid(iid);
+    msg->set_str(reinterpret_cast<const uint8_t*>(str.data()), str.size());
+    return iid;
+  };
+
+  std::vector<uint64_t> frame_iids;
+  frame_iids.reserve(pcs.size());
+  // Callstacks are stored outermost frame first.
+  for (auto pc_it = pcs.rbegin(); pc_it != pcs.rend(); ++pc_it) {
+    const CodeMapping* mapping = FindMapping(*pc_it);
+    if (!mapping)
+      mapping = kUnknownMapping;
+
+    uint64_t mapping_key = mapping == kUnknownMapping ? ~uint64_t{0}
+                                                       : mapping->start;
+    uint64_t mapping_iid;
+    auto mapping_it = incr->mappings.find(mapping_key);
+    if (mapping_it != incr->mappings.end()) {
+      mapping_iid = mapping_it->second;
+    } else {
+      std::vector<uint64_t> path_iids;
+      for (const std::string& part : base::SplitString(mapping->path, "/"))
+        path_iids.push_back(intern_string(&incr->path_strings, part, false));
+      std::optional<uint64_t> build_id_iid;
+      if (!mapping->build_id.empty())
+        build_id_iid = intern_string(&incr->build_ids, mapping->build_id, true);
+
+      mapping_iid = ++incr->last_iid;
+      incr->mappings.emplace(mapping_key, mapping_iid);
+      auto* msg = interned_data()->add_mappings();
+      msg->set_iid(mapping_iid);
+      if (build_id_iid)
+        msg->set_build_id(*build_id_iid);
+      msg->set_start(mapping->start);
+      msg->set_end(mapping->end);
+      msg->set_start_offset(0);
+      msg->set_exact_offset(mapping->exact_offset);
+      msg->set_load_bias(mapping->load_bias);
+      for (uint64_t path_iid : path_iids)
+        msg->add_path_string_ids(path_iid);
+    }
+
+    // Relative pcs are ELF virtual addresses, like the ones computed by
+    // libunwindstack for traced_perf.
+    uint64_t rel_pc = *pc_it - mapping->base;
+    auto frame_key = std::make_pair(mapping_iid, rel_pc);
+    auto frame_it = incr->frames.find(frame_key);
+    if (frame_it != incr->frames.end()) {
+      frame_iids.push_back(frame_it->second);
+      continue;
+    }
+    uint64_t frame_iid = ++incr->last_iid;
+    incr->frames.emplace(frame_key, frame_iid);
+    auto* msg = interned_data()->add_frames();
+    msg->set_iid(frame_iid);
+    msg->set_mapping_id(mapping_iid);
+    msg->set_rel_pc(rel_pc);
+    frame_iids.push_back(frame_iid);
+  }
+
+  auto callstack_it = incr->callstacks.find(frame_iids);
+  if (callstack_it != incr->callstacks.end())
+    return callstack_it->second;
+  uint64_t callstack_iid = ++incr->last_iid;
+  auto* msg = interned_data()->add_callstacks();
+  msg->set_iid(callstack_iid);
+  for (uint64_t frame_iid : frame_iids)
+    msg->add_frame_ids(frame_iid);
+  incr->callstacks.emplace(std::move(frame_iids), callstack_iid);
+  return callstack_iid;
+}
+
+void SdkCpuProfilerDataSource::WriteSamples(
+    const std::vector<Sample>& samples) {
+  Trace([&](TraceContext ctx) {
+    SdkCpuProfilerIncrementalState* incr = ctx.GetIncrementalState();
+    if (incr->was_cleared) {
+      *incr = SdkCpuProfilerIncrementalState();
+      incr->was_cleared = false;
+      auto packet = ctx.NewTracePacket();
+      packet->set_timestamp(samples.front().timestamp_ns);
+      packet->set_sequence_flags(
+          protos::pbzero::TracePacket::SEQ_INCREMENTAL_STATE_CLEARED);
+      auto* timebase = packet->set_trace_packet_defaults()
+                           ->set_perf_sample_defaults()
+                           ->set_timebase();
+      timebase->set_frequency(sampling_frequency_);
+      timebase->set_counter(protos::pbzero::PerfEvents::SW_TASK_CLOCK);
+      timebase->set_name("sdk_cpu_profiler");
+    }
+
+    for (const Sample& sample : samples) {
+      auto packet = ctx.NewTracePacket();
+      packet->set_timestamp(sample.timestamp_ns);
+      packet->set_sequence_flags(
+          protos::pbzero::TracePacket::SEQ_NEEDS_INCREMENTAL_STATE);
+
+      // The interned data must be written before the sample.
+      InternedData* interned_data = nullptr;
+      uint64_t callstack_iid =
+          InternCallstack(incr, sample.pcs, [&packet, &interned_data] {
+            if (!interned_data)
+              interned_data = packet->set_interned_data();
+            return interned_data;
+          });
+
+      auto* perf_sample = packet->set_perf_sample();
+      perf_sample->set_cpu(static_cast<uint32_t>(sample.cpu));
+      perf_sample->set_pid(static_cast<uint32_t>(pid_));
+      perf_sample->set_tid(static_cast<uint32_t>(sample.tid));
+      perf_sample->set_cpu_mode(protos::pbzero::Profiling::MODE_USER);
+      perf_sample->set_callstack_iid(callstack_iid);
+    }
+  });
+}
+
+}  // namespace internal
+
+// static
+void SdkCpuProfiler::Register() {
+  DataSourceDescriptor dsd;
+  dsd.set_name("sdk_cpu_profiler");
+  internal::SdkCpuProfilerDataSource::Register(dsd);
+}
+
+}  // namespace perfetto
+
+PERFETTO_DEFINE_DATA_SOURCE_STATIC_MEMBERS(
+    perfetto::internal::SdkCpuProfilerDataSource,
+    perfetto::internal::SdkCpuProfilerDataSourceTraits);
+
+#else  // PERFETTO_SDK_CPU_PROFILER_SUPPORTED
+
+namespace perfetto {
+
+// static
+void SdkCpuProfiler::Register() {
+  PERFETTO_ELOG("sdk_cpu_profiler is not supported on this platform");
+}
+
+}  // namespace perfetto
+
+#endif  // PERFETTO_SDK_CPU_PROFILER_SUPPORTED
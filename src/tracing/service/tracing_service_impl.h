@@ -648,8 +648,9 @@ class TracingServiceImpl : public TracingService {
     // Whether we emitted clock offsets for relay clients yet.
     bool did_emit_remote_clock_sync_ = false;
 
-    // Whether we should compress TracePackets after reading them.
-    bool compress_deflate = false;
+    // The function used to compress TracePackets after reading them, or
+    // nullptr if the trace should not be compressed.
+    InitOpts::CompressorFn compressor_fn = nullptr;
 
     // The number of received triggers we've emitted into the trace output.
     size_t num_triggers_emitted_into_trace = 0;
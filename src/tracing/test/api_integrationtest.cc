@@ -16,6 +16,7 @@
 
 #include <fcntl.h>
 
+#include <atomic>
 #include <chrono>
 #include <condition_variable>
 #include <fstream>
@@ -38,9 +39,12 @@
 #if PERFETTO_BUILDFLAG(PERFETTO_OS_WIN)
 #include <Windows.h>  // For CreateFile().
 #else
+#include <signal.h>
 #include <sys/socket.h>
 #include <sys/types.h>
 #include <sys/un.h>
+#include <time.h>
+#include <unistd.h>
 #endif
 
 // Deliberately not pulling any non-public perfetto header to spot accidental
@@ -62,11 +66,13 @@
 // checks that the results are valid).
 #include "protos/perfetto/common/builtin_clock.pbzero.h"
 #include "protos/perfetto/common/interceptor_descriptor.gen.h"
+#include "protos/perfetto/common/perf_events.gen.h"
 #include "protos/perfetto/common/trace_stats.gen.h"
 #include "protos/perfetto/common/tracing_service_state.gen.h"
 #include "protos/perfetto/common/track_event_descriptor.gen.h"
 #include "protos/perfetto/common/track_event_descriptor.pbzero.h"
 #include "protos/perfetto/config/interceptor_config.gen.h"
+#include "protos/perfetto/config/profiling/sdk_cpu_profiler_config.gen.h"
 #include "protos/perfetto/config/track_event/track_event_config.gen.h"
 #include "protos/perfetto/trace/clock_snapshot.gen.h"
 #include "protos/perfetto/trace/clock_snapshot.pbzero.h"
@@ -75,6 +81,7 @@
 #include "protos/perfetto/trace/interned_data/interned_data.gen.h"
 #include "protos/perfetto/trace/interned_data/interned_data.pbzero.h"
 #include "protos/perfetto/trace/profiling/profile_common.gen.h"
+#include "protos/perfetto/trace/profiling/profile_packet.gen.h"
 #include "protos/perfetto/trace/test_event.gen.h"
 #include "protos/perfetto/trace/test_event.pbzero.h"
 #include "protos/perfetto/trace/test_extensions.pbzero.h"
@@ -213,6 +220,7 @@ using perfetto::internal::TrackEventInternal;
 using ::perfetto::test::DataSourceInternalForTest;
 using ::testing::_;
 using ::testing::AllOf;
+using ::testing::AnyOf;
 using ::testing::ContainerEq;
 using ::testing::Contains;
 using ::testing::Each;
@@ -7728,6 +7736,157 @@ TEST(PerfettoApiInitTest, AsyncSocketStartupTracing) {
 }
 #endif  // !PERFETTO_BUILDFLAG(PERFETTO_OS_WIN)
 
+#if (PERFETTO_BUILDFLAG(PERFETTO_OS_LINUX) ||   \
+     PERFETTO_BUILDFLAG(PERFETTO_OS_ANDROID)) && \
+    (defined(__x86_64__) || defined(__aarch64__))
+
+// Keeps a CPU busy until destroyed, so that the CPU time timers of the SDK CPU
+// profiler fire on it.
+class BusyThread {
+ public:
+  BusyThread() : thread_([this] { Run(); }) { started_.Wait(); }
+
+  ~BusyThread() {
+    stop_.store(true);
+    thread_.join();
+  }
+
+  int32_t tid() const { return tid_; }
+
+  // Waits until the thread has consumed |ms| more milliseconds of CPU time.
+  // Returns false if this didn't happen within ~10 seconds.
+  bool WaitForCpuTime(uint64_t ms) {
+    uint64_t target_ns = cpu_time_ns_.load() + ms * 1000000;
+    for (int i = 0; i < 10000; i++) {
+      if (cpu_time_ns_.load() >= target_ns)
+        return true;
+      std::this_thread::sleep_for(std::chrono::milliseconds(1));
+    }
+    return false;
+  }
+
+ private:
+  void Run() {
+    tid_ = static_cast<int32_t>(perfetto::base::GetThreadId());
+    started_.Notify();
+    while (!stop_.load(std::memory_order_relaxed)) {
+      struct timespec ts {};
+      clock_gettime(CLOCK_THREAD_CPUTIME_ID, &ts);
+      cpu_time_ns_.store(static_cast<uint64_t>(ts.tv_sec) * 1000000000 +
+                         static_cast<uint64_t>(ts.tv_nsec));
+    }
+  }
+
+  int32_t tid_ = 0;
+  WaitableTestEvent started_;
+  std::atomic<bool> stop_{false};
+  std::atomic<uint64_t> cpu_time_ns_{0};
+  std::thread thread_;  // Must be the last member, it uses all the others.
+};
+
+perfetto::TraceConfig SdkCpuProfilerTraceConfig(
+    const std::vector<int32_t>& tids,
+    uint32_t poll_period_ms) {
+  perfetto::TraceConfig cfg;
+  cfg.add_buffers()->set_size_kb(4096);
+  auto* ds_cfg = cfg.add_data_sources()->mutable_config();
+  ds_cfg->set_name("sdk_cpu_profiler");
+  perfetto::protos::gen::SdkCpuProfilerConfig profiler_cfg;
+  profiler_cfg.set_sampling_frequency(1000);
+  profiler_cfg.set_poll_period_ms(poll_period_ms);
+  for (int32_t tid : tids)
+    profiler_cfg.add_target_tid(tid);
+  ds_cfg->set_sdk_cpu_profiler_config_raw(profiler_cfg.SerializeAsString());
+  return cfg;
+}
+
+// Returns the tids of the PerfSample packets in |trace|, checking that each
+// sample refers to a non-empty callstack interned earlier on its sequence.
+std::vector<int32_t> ReadSdkCpuProfilerSampleTids(
+    const perfetto::protos::gen::Trace& trace) {
+  std::vector<int32_t> tids;
+  // Sequence id -> callstack iid -> number of frames.
+  std::map<uint32_t, std::map<uint64_t, size_t>> callstacks;
+  for (const auto& packet : trace.packet()) {
+    auto& seq_callstacks = callstacks[packet.trusted_packet_sequence_id()];
+    for (const auto& callstack : packet.interned_data().callstacks())
+      seq_callstacks[callstack.iid()] = callstack.frame_ids().size();
+    if (!packet.has_perf_sample())
+      continue;
+    const auto& sample = packet.perf_sample();
+    EXPECT_EQ(sample.pid(), static_cast<uint32_t>(getpid()));
+    auto it = seq_callstacks.find(sample.callstack_iid());
+    if (it == seq_callstacks.end()) {
+      ADD_FAILURE() << "Callstack " << sample.callstack_iid()
+                    << " was not interned";
+    } else {
+      EXPECT_GT(it->second, 0u);
+    }
+    tids.push_back(static_cast<int32_t>(sample.tid()));
+  }
+  return tids;
+}
+
+TEST_P(PerfettoApiTest, SdkCpuProfiler) {
+  perfetto::SdkCpuProfiler::Register();
+  perfetto::test::SyncProducers();
+
+  BusyThread busy_thread;
+  auto* tracing_session = NewTrace(
+      SdkCpuProfilerTraceConfig({busy_thread.tid()}, /*poll_period_ms=*/10));
+  tracing_session->get()->StartBlocking();
+  ASSERT_TRUE(busy_thread.WaitForCpuTime(100));
+
+  // The busy thread keeps running, so the session stops with timers armed and
+  // possibly with signals in flight.
+  auto trace = StopSessionAndReturnParsedTrace(tracing_session);
+  bool found_timebase = false;
+  for (const auto& packet : trace.packet()) {
+    const auto& defaults = packet.trace_packet_defaults();
+    if (defaults.has_perf_sample_defaults()) {
+      const auto& timebase = defaults.perf_sample_defaults().timebase();
+      EXPECT_EQ(timebase.name(), "sdk_cpu_profiler");
+      EXPECT_EQ(timebase.frequency(), 1000u);
+      found_timebase = true;
+    }
+  }
+  EXPECT_TRUE(found_timebase);
+  std::vector<int32_t> tids = ReadSdkCpuProfilerSampleTids(trace);
+  EXPECT_THAT(tids, Not(IsEmpty()));
+  EXPECT_THAT(tids, Each(busy_thread.tid()));
+
+  // The signal handler stays installed after the stop: a SIGPROF which isn't
+  // sent by the profiler timers is ignored rather than killing the process.
+  raise(SIGPROF);
+}
+
+TEST_P(PerfettoApiTest, SdkCpuProfilerStopWhileSampling) {
+  perfetto::SdkCpuProfiler::Register();
+  perfetto::test::SyncProducers();
+
+  BusyThread busy_thread1;
+  BusyThread busy_thread2;
+  for (int i = 0; i < 10; i++) {
+    // With a long poll period the samples are written only when stopping,
+    // after the timers are deleted and the in-flight handlers are done.
+    auto* tracing_session = NewTrace(SdkCpuProfilerTraceConfig(
+        {busy_thread1.tid(), busy_thread2.tid()}, /*poll_period_ms=*/60000));
+    tracing_session->get()->StartBlocking();
+    ASSERT_TRUE(busy_thread1.WaitForCpuTime(50));
+    ASSERT_TRUE(busy_thread2.WaitForCpuTime(50));
+    auto trace = StopSessionAndReturnParsedTrace(tracing_session);
+    std::vector<int32_t> tids = ReadSdkCpuProfilerSampleTids(trace);
+    EXPECT_THAT(tids, Not(IsEmpty()));
+    EXPECT_THAT(tids,
+                Each(AnyOf(busy_thread1.tid(), busy_thread2.tid())));
+  }
+
+  // Signals still pending after the last stop must not kill the process.
+  ASSERT_TRUE(busy_thread1.WaitForCpuTime(10));
+}
+
+#endif  // (OS_LINUX || OS_ANDROID) && (x86_64 || aarch64)
+
 struct BackendTypeAsString {
   std::string operator()(
       const ::testing::TestParamInfo<perfetto::BackendType>& info) const {
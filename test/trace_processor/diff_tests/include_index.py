@@ -68,7 +68,9 @@ from diff_tests.parser.chrome.tests import ChromeParser
 from diff_tests.parser.chrome.tests_memory_snapshots import ChromeMemorySnapshots
 from diff_tests.parser.chrome.tests_v8 import ChromeV8Parser
 from diff_tests.parser.cros.tests import Cros
+from diff_tests.parser.ctf.tests import Ctf
 from diff_tests.parser.etm.tests import Etm
+from diff_tests.parser.folded_stack.tests import FoldedStackParser
 from diff_tests.parser.fs.tests import Fs
 from diff_tests.parser.ftrace.block_io_tests import BlockIo
 from diff_tests.parser.ftrace.ftrace_crop_tests import FtraceCrop
@@ -85,6 +87,7 @@ from diff_tests.parser.instruments.tests import Instruments
 from diff_tests.parser.json.tests import JsonParser
 from diff_tests.parser.memory.tests import MemoryParser
 from diff_tests.parser.network.tests import NetworkParser
+from diff_tests.parser.otlp.tests import OtlpParser
 from diff_tests.parser.parsing.tests import Parsing
 from diff_tests.parser.parsing.tests_debug_annotation import ParsingDebugAnnotation
 from diff_tests.parser.parsing.tests_memory_counters import ParsingMemoryCounters
@@ -154,6 +157,7 @@ from diff_tests.stdlib.span_join.tests_regression import SpanJoinRegression
 from diff_tests.stdlib.span_join.tests_smoke import SpanJoinSmoke
 from diff_tests.stdlib.tests import StdlibSmoke
 from diff_tests.stdlib.timestamps.tests import Timestamps
+from diff_tests.stdlib.trace_diff.tests import TraceDiff
 from diff_tests.stdlib.traced.stats import TracedStats
 from diff_tests.stdlib.viz.tests import Viz
 from diff_tests.stdlib.wattson.tests import WattsonStdlib
@@ -185,6 +189,7 @@ def fetch_all_diff_tests(index_path: str) -> List['testing.TestCase']:
       ChromeParser,
       ChromeV8Parser,
       Cros,
+      Ctf,
       Deobfuscation,
       Etm,
       Fs,
@@ -243,6 +248,8 @@ def fetch_all_diff_tests(index_path: str) -> List['testing.TestCase']:
       ArtHprofParser,
       ArtMethodParser,
       PerfTextParser,
+      FoldedStackParser,
+      OtlpParser,
   ]
 
   metrics_tests = [
@@ -306,6 +313,7 @@ def fetch_all_diff_tests(index_path: str) -> List['testing.TestCase']:
       IntervalsIntersect,
       Startups,
       Timestamps,
+      TraceDiff,
       TracedStats,
       Viz,
       WattsonStdlib,
@@ -0,0 +1,311 @@
+#!/usr/bin/env python3
+# Copyright (C) 2025 The Android Open Source Project
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#      http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+# Writes a small LTTng-like CTF trace: a kernel trace with one stream per CPU
+# and a userspace trace. Like the traces opened in the UI, both trace
+# directories are packaged into a tar archive.
+
+import io
+import struct
+import sys
+import tarfile
+
+COMMON_METADATA = """
+typealias integer { size = 8; align = 8; signed = false; } := uint8_t;
+typealias integer { size = 32; align = 8; signed = false; } := uint32_t;
+typealias integer { size = 64; align = 8; signed = false; } := uint64_t;
+typealias integer { size = 32; align = 8; signed = true; } := int32_t;
+typealias integer { size = 64; align = 8; signed = true; } := int64_t;
+typealias integer {
+  size = 8; align = 8; signed = false; encoding = UTF8;
+} := char_t;
+
+trace {
+  major = 1;
+  minor = 8;
+  byte_order = le;
+  packet.header := struct {
+    uint32_t magic;
+    uint32_t stream_id;
+  };
+};
+
+clock {
+  name = "monotonic";
+  freq = 1000000000;
+  offset_s = 1700000000;
+};
+
+typealias integer {
+  size = 64; align = 8; signed = false;
+  map = clock.monotonic.value;
+} := uint64_clock_monotonic_t;
+"""
+
+STREAM_METADATA = """
+stream {
+  id = 0;
+  packet.context := struct {
+    uint64_clock_monotonic_t timestamp_begin;
+    uint64_clock_monotonic_t timestamp_end;
+    uint64_t content_size;
+    uint64_t packet_size;
+    uint64_t events_discarded;
+    uint32_t cpu_id;
+  };
+  event.header := struct {
+    uint32_t id;
+    uint64_clock_monotonic_t timestamp;
+  };
+  %s
+};
+"""
+
+KERNEL_METADATA = '/* CTF 1.8 */' + COMMON_METADATA + """
+env {
+  domain = "kernel";
+  tracer_name = "lttng-modules";
+};
+""" + STREAM_METADATA % '' + """
+event {
+  name = "sched_switch";
+  id = 0;
+  stream_id = 0;
+  fields := struct {
+    char_t _prev_comm[16];
+    int32_t _prev_tid;
+    int32_t _prev_prio;
+    int64_t _prev_state;
+    char_t _next_comm[16];
+    int32_t _next_tid;
+    int32_t _next_prio;
+  };
+};
+
+event {
+  name = "sched_waking";
+  id = 1;
+  stream_id = 0;
+  fields := struct {
+    char_t _comm[16];
+    int32_t _tid;
+    int32_t _prio;
+    int32_t _target_cpu;
+  };
+};
+
+event {
+  name = "irq_handler_entry";
+  id = 2;
+  stream_id = 0;
+  fields := struct {
+    int32_t _irq;
+    string _name;
+  };
+};
+
+event {
+  name = "irq_handler_exit";
+  id = 3;
+  stream_id = 0;
+  fields := struct {
+    int32_t _irq;
+    int32_t _ret;
+  };
+};
+
+event {
+  name = "irq_softirq_entry";
+  id = 4;
+  stream_id = 0;
+  fields := struct {
+    uint32_t _vec;
+  };
+};
+
+event {
+  name = "irq_softirq_exit";
+  id = 5;
+  stream_id = 0;
+  fields := struct {
+    uint32_t _vec;
+  };
+};
+
+event {
+  name = "lttng_statedump_process_state";
+  id = 6;
+  stream_id = 0;
+  fields := struct {
+    int32_t _tid;
+    int32_t _pid;
+    char_t _name[16];
+  };
+};
+"""
+
+UST_METADATA = '/* CTF 1.8 */' + COMMON_METADATA + """
+env {
+  domain = "ust";
+  tracer_name = "lttng-ust";
+};
+""" + STREAM_METADATA % """event.context := struct {
+    int32_t _vpid;
+    int32_t _vtid;
+    string _procname;
+  };""" + """
+event {
+  name = "my_provider:request_begin";
+  id = 0;
+  stream_id = 0;
+  fields := struct {
+    string _url;
+  };
+};
+
+event {
+  name = "my_provider:request_end";
+  id = 1;
+  stream_id = 0;
+  fields := struct {
+    int32_t _status;
+  };
+};
+
+event {
+  name = "my_provider:checkpoint";
+  id = 2;
+  stream_id = 0;
+  fields := struct {
+    uint32_t _value;
+  };
+};
+
+event {
+  name = "my_provider:download_start";
+  id = 3;
+  stream_id = 0;
+  fields := struct {
+    uint32_t _size;
+  };
+};
+
+event {
+  name = "my_provider:download_stop";
+  id = 4;
+  stream_id = 0;
+  fields := struct {
+    int32_t _status;
+  };
+};
+"""
+
+STREAM_PACKET_MAGIC = 0xC1FC1FC1
+
+# LTTng reports priorities relative to MAX_RT_PRIO.
+PRIO = 120 - 100
+
+# Values of prev_state.
+TASK_RUNNING = 0
+TASK_INTERRUPTIBLE = 1
+
+
+def comm(name):
+  return name.encode().ljust(16, b'\0')
+
+
+def string(value):
+  return value.encode() + b'\0'
+
+
+def i32(value):
+  return struct.pack('<i', value)
+
+
+def u32(value):
+  return struct.pack('<I', value)
+
+
+def i64(value):
+  return struct.pack('<q', value)
+
+
+class Stream:
+  """A stream file made of a single packet."""
+
+  def __init__(self, cpu):
+    self.cpu = cpu
+    self.events = []
+
+  def add(self, event_id, ts, payload):
+    self.events.append((ts, struct.pack('<IQ', event_id, ts) + payload))
+
+  def serialize(self):
+    events = b''.join(e for _, e in self.events)
+    # magic, stream_id and the packet context up to cpu_id.
+    preamble_size = 4 + 4 + 8 * 5 + 4
+    size_bits = (preamble_size + len(events)) * 8
+    return struct.pack('<IIQQQQQI', STREAM_PACKET_MAGIC, 0, self.events[0][0],
+                       self.events[-1][0], size_bits, size_bits, 0,
+                       self.cpu) + events
+
+
+def sched_switch(stream, ts, prev_comm, prev_tid, prev_state, next_comm,
+                 next_tid):
+  stream.add(
+      0, ts,
+      comm(prev_comm) + i32(prev_tid) + i32(PRIO) + i64(prev_state) +
+      comm(next_comm) + i32(next_tid) + i32(PRIO))
+
+
+cpu0 = Stream(cpu=0)
+cpu0.add(6, 1000, i32(10) + i32(10) + comm('app'))
+cpu0.add(6, 1000, i32(11) + i32(10) + comm('worker'))
+sched_switch(cpu0, 2000, 'swapper/0', 0, TASK_RUNNING, 'app', 10)
+cpu0.add(1, 3000, comm('worker') + i32(11) + i32(PRIO) + i32(1))
+sched_switch(cpu0, 5000, 'app', 10, TASK_INTERRUPTIBLE, 'swapper/0', 0)
+
+cpu1 = Stream(cpu=1)
+cpu1.add(2, 1500, i32(42) + string('eth0'))
+cpu1.add(3, 1800, i32(42) + i32(1))
+cpu1.add(4, 1850, u32(3))
+cpu1.add(5, 1950, u32(3))
+sched_switch(cpu1, 4000, 'swapper/1', 0, TASK_RUNNING, 'worker', 11)
+sched_switch(cpu1, 6000, 'worker', 11, TASK_RUNNING, 'swapper/1', 0)
+
+# Userspace events of the main thread of the "server" process.
+ust = Stream(cpu=0)
+ust_context = i32(20) + i32(20) + string('server')
+ust.add(0, 2500, ust_context + string('/index'))
+ust.add(2, 2600, ust_context + u32(7))
+ust.add(1, 2900, ust_context + i32(200))
+ust.add(3, 3000, ust_context + u32(4096))
+ust.add(4, 3500, ust_context + i32(0))
+
+files = [
+    ('kernel/metadata', KERNEL_METADATA.encode()),
+    ('kernel/channel0_0', cpu0.serialize()),
+    ('kernel/channel0_1', cpu1.serialize()),
+    ('ust/metadata', UST_METADATA.encode()),
+    ('ust/channel0_0', ust.serialize()),
+]
+
+archive = io.BytesIO()
+with tarfile.open(fileobj=archive, mode='w', format=tarfile.USTAR_FORMAT) as tar:
+  for name, data in files:
+    info = tarfile.TarInfo(name)
+    info.size = len(data)
+    tar.addfile(info, io.BytesIO(data))
+sys.stdout.buffer.write(archive.getvalue())
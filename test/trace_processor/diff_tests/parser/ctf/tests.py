@@ -0,0 +1,140 @@
+#!/usr/bin/env python3
+# Copyright (C) 2025 The Android Open Source Project
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#      http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+from python.generators.diff_tests.testing import Csv, Path
+from python.generators.diff_tests.testing import DiffTestBlueprint
+from python.generators.diff_tests.testing import TestSuite
+
+
+class Ctf(TestSuite):
+
+  def test_ctf_sched_switch(self):
+    return DiffTestBlueprint(
+        trace=Path('lttng_trace.py'),
+        query="""
+        SELECT ts, dur, cpu, tid, thread.name AS thread_name, end_state,
+          priority
+        FROM sched
+        JOIN thread USING (utid)
+        WHERE tid != 0
+        ORDER BY ts;
+        """,
+        out=Csv("""
+        "ts","dur","cpu","tid","thread_name","end_state","priority"
+        2000,3000,0,10,"app","S",120
+        4000,2000,1,11,"worker","R",120
+        """))
+
+  def test_ctf_sched_waking(self):
+    return DiffTestBlueprint(
+        trace=Path('lttng_trace.py'),
+        query="""
+        SELECT s.ts, s.dur, s.state, s.cpu, waker.tid AS waker_tid
+        FROM thread_state s
+        JOIN thread t USING (utid)
+        LEFT JOIN thread waker ON s.waker_utid = waker.utid
+        WHERE t.tid = 11 AND s.ts < 6000
+        ORDER BY s.ts;
+        """,
+        out=Csv("""
+        "ts","dur","state","cpu","waker_tid"
+        3000,1000,"R","[NULL]",10
+        4000,2000,"Running",1,"[NULL]"
+        """))
+
+  def test_ctf_irq(self):
+    return DiffTestBlueprint(
+        trace=Path('lttng_trace.py'),
+        query="""
+        SELECT
+          s.ts,
+          s.dur,
+          s.category,
+          s.name,
+          t.name AS track_name,
+          extract_arg(s.arg_set_id, 'ret') AS ret,
+          extract_arg(s.arg_set_id, 'vec') AS vec
+        FROM slice s
+        JOIN track t ON s.track_id = t.id
+        WHERE t.name GLOB '*Irq Cpu *'
+        ORDER BY s.ts;
+        """,
+        out=Csv("""
+        "ts","dur","category","name","track_name","ret","vec"
+        1500,300,"irq","IRQ (eth0)","Irq Cpu 1","handled","[NULL]"
+        1850,100,"irq","NET_RX","SoftIrq Cpu 1","[NULL]",3
+        """))
+
+  def test_ctf_userspace_slices(self):
+    return DiffTestBlueprint(
+        trace=Path('lttng_trace.py'),
+        query="""
+        SELECT
+          s.ts,
+          s.dur,
+          s.category,
+          s.name,
+          s.depth,
+          t.tid,
+          extract_arg(s.arg_set_id, 'args.url') AS url,
+          extract_arg(s.arg_set_id, 'args.status') AS status,
+          extract_arg(s.arg_set_id, 'args.value') AS value
+        FROM slice s
+        JOIN thread_track tt ON s.track_id = tt.id
+        JOIN thread t USING (utid)
+        ORDER BY s.ts;
+        """,
+        out=Csv("""
+        "ts","dur","category","name","depth","tid","url","status","value"
+        2500,400,"my_provider","request",0,20,"/index",200,"[NULL]"
+        2600,0,"my_provider","checkpoint",1,20,"[NULL]","[NULL]",7
+        3000,500,"my_provider","download",0,20,"[NULL]",0,"[NULL]"
+        """))
+
+  def test_ctf_userspace_start_stop_slices(self):
+    return DiffTestBlueprint(
+        trace=Path('lttng_trace.py'),
+        query="""
+        SELECT
+          s.ts,
+          s.dur,
+          s.name,
+          extract_arg(s.arg_set_id, 'args.size') AS size,
+          extract_arg(s.arg_set_id, 'args.status') AS status
+        FROM slice s
+        WHERE s.name GLOB 'download*'
+        ORDER BY s.ts;
+        """,
+        out=Csv("""
+        "ts","dur","name","size","status"
+        3000,500,"download",4096,0
+        """))
+
+  def test_ctf_threads_and_processes(self):
+    return DiffTestBlueprint(
+        trace=Path('lttng_trace.py'),
+        query="""
+        SELECT tid, thread.name AS thread_name, pid, process.name AS process_name
+        FROM thread
+        LEFT JOIN process USING (upid)
+        WHERE tid != 0
+        ORDER BY tid;
+        """,
+        out=Csv("""
+        "tid","thread_name","pid","process_name"
+        10,"app",10,"app"
+        11,"worker",10,"app"
+        20,"server",20,"server"
+        """))
@@ -0,0 +1,86 @@
+#!/usr/bin/env python3
+# Copyright (C) 2025 The Android Open Source Project
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#      http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+from python.generators.diff_tests.testing import Path
+from python.generators.diff_tests.testing import Csv
+from python.generators.diff_tests.testing import DiffTestBlueprint
+from python.generators.diff_tests.testing import TestSuite
+
+
+class FoldedStackParser(TestSuite):
+
+  def test_folded_stack_callsites(self):
+    return DiffTestBlueprint(
+        trace=Path('stacks.folded'),
+        query="""
+          SELECT c.id, c.parent_id, c.depth, f.name, m.name AS mapping_name
+          FROM stack_profile_callsite c
+          JOIN stack_profile_frame f ON c.frame_id = f.id
+          JOIN stack_profile_mapping m ON f.mapping = m.id
+          ORDER BY c.id
+        """,
+        out=Csv('''
+          "id","parent_id","depth","name","mapping_name"
+          0,"[NULL]",0,"main","[unknown]"
+          1,0,1,"foo","[unknown]"
+          2,1,2,"bar","[unknown]"
+          3,0,1,"baz","[unknown]"
+          4,"[NULL]",0,"start","libc.so"
+          5,4,1,"main","[unknown]"
+        '''))
+
+  def test_folded_stack_samples(self):
+    return DiffTestBlueprint(
+        trace=Path('stacks.folded'),
+        query="""
+          SELECT t.tid, t.name, p.pid, COUNT(*) AS samples, MIN(s.ts) AS ts
+          FROM cpu_profile_stack_sample s
+          JOIN thread t USING (utid)
+          LEFT JOIN process p USING (upid)
+          GROUP BY s.utid
+          ORDER BY ts
+        """,
+        out=Csv('''
+          "tid","name","pid","samples","ts"
+          11,"app",10,4,0
+          12,"app",10,2,4000000
+          0,"[NULL]","[NULL]",1,6000000
+        '''))
+
+  def test_folded_stack_parse_errors(self):
+    return DiffTestBlueprint(
+        trace=Path('stacks.folded'),
+        query="""
+          SELECT name, value FROM stats
+          WHERE name = 'folded_stack_parse_errors'
+        """,
+        out=Csv('''
+          "name","value"
+          "folded_stack_parse_errors",1
+        '''))
+
+  def test_folded_stack_count_too_large(self):
+    return DiffTestBlueprint(
+        trace=Path('large_count.folded'),
+        query="""
+          SELECT
+            (SELECT COUNT(*) FROM cpu_profile_stack_sample) AS samples,
+            (SELECT value FROM stats
+             WHERE name = 'folded_stack_count_too_large') AS skipped_lines
+        """,
+        out=Csv('''
+          "samples","skipped_lines"
+          3,1
+        '''))
@@ -0,0 +1,127 @@
+#!/usr/bin/env python3
+# Copyright (C) 2025 The Android Open Source Project
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#      http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+from python.generators.diff_tests.testing import Path
+from python.generators.diff_tests.testing import Csv
+from python.generators.diff_tests.testing import DiffTestBlueprint
+from python.generators.diff_tests.testing import TestSuite
+
+
+class OtlpParser(TestSuite):
+
+  def test_otlp_slices(self):
+    return DiffTestBlueprint(
+        trace=Path('spans.json'),
+        query="""
+          SELECT
+            s.id, s.ts, s.dur, s.depth, s.parent_id, s.category, s.name,
+            t.type AS track_type
+          FROM slice s
+          JOIN track t ON s.track_id = t.id
+          ORDER BY s.id
+        """,
+        out=Csv('''
+          "id","ts","dur","depth","parent_id","category","name","track_type"
+          0,1000000000,100000000,0,"[NULL]","http","GET /checkout","thread_execution"
+          1,1010000000,10000000,1,0,"http","render","thread_execution"
+          2,1020000000,30000000,0,"[NULL]","db","query","otlp_span"
+          3,1025000000,0,1,2,"db","exception","otlp_span"
+          4,1030000000,10000000,1,2,"db","retry","otlp_span"
+        '''))
+
+  def test_otlp_span_args(self):
+    return DiffTestBlueprint(
+        trace=Path('spans.json'),
+        query="""
+          SELECT a.key, a.display_value
+          FROM slice s
+          JOIN args a USING (arg_set_id)
+          WHERE s.name = 'query'
+          ORDER BY a.key
+        """,
+        out=Csv('''
+          "key","display_value"
+          "attributes.db.system","postgresql"
+          "otlp.kind","SPAN_KIND_CLIENT"
+          "otlp.links[0].span_id","eee19b7ec3c1b173"
+          "otlp.links[0].trace_id","5b8efff798038103d269b633813fc60c"
+          "otlp.parent_span_id","eee19b7ec3c1b174"
+          "otlp.span_id","0102030405060708"
+          "otlp.status.code","STATUS_CODE_ERROR"
+          "otlp.status.message","timeout"
+          "otlp.trace_id","5b8efff798038103d269b633813fc60c"
+        '''))
+
+  def test_otlp_flows(self):
+    return DiffTestBlueprint(
+        trace=Path('spans.json'),
+        query="""
+          SELECT s_out.name AS slice_out, s_in.name AS slice_in
+          FROM flow f
+          JOIN slice s_out ON f.slice_out = s_out.id
+          JOIN slice s_in ON f.slice_in = s_in.id
+          ORDER BY f.id
+        """,
+        out=Csv('''
+          "slice_out","slice_in"
+          "GET /checkout","query"
+          "render","query"
+        '''))
+
+  def test_otlp_processes(self):
+    return DiffTestBlueprint(
+        trace=Path('spans.json'),
+        query="""
+          SELECT p.name, a.key, a.display_value
+          FROM process p
+          JOIN args a USING (arg_set_id)
+          WHERE a.key GLOB 'resource.*'
+          ORDER BY p.name, a.key
+        """,
+        out=Csv('''
+          "name","key","display_value"
+          "backend","resource.service.name","backend"
+          "backend","resource.service.version","1.2"
+          "frontend","resource.process.pid","100"
+          "frontend","resource.service.name","frontend"
+        '''))
+
+  def test_otlp_threads(self):
+    return DiffTestBlueprint(
+        trace=Path('spans.json'),
+        query="""
+          SELECT t.tid, t.name, p.pid, p.name AS process_name
+          FROM thread t
+          JOIN process p USING (upid)
+          WHERE t.tid = 101
+        """,
+        out=Csv('''
+          "tid","name","pid","process_name"
+          101,"main",100,"frontend"
+        '''))
+
+  def test_otlp_stats(self):
+    return DiffTestBlueprint(
+        trace=Path('spans.json'),
+        query="""
+          SELECT name, value FROM stats
+          WHERE name GLOB 'otlp_*'
+          ORDER BY name
+        """,
+        out=Csv('''
+          "name","value"
+          "otlp_invalid_spans",1
+          "otlp_unresolved_links",1
+        '''))
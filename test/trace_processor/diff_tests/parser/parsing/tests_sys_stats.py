@@ -135,3 +135,242 @@ class ParsingSysStats(TestSuite):
     115835063108,"gpufreq",300.000000
     115900182490,"gpufreq",350.000000
     """))
+
+  def test_netdev_and_netstat(self):
+    return DiffTestBlueprint(
+        trace=TextProto(r"""
+        packet {
+          sys_stats {
+            netdev {
+              interface_name: "wlan0"
+              rx_bytes: 12345678901
+              rx_packets: 9876543
+              rx_errors: 3
+              rx_drops: 17
+              tx_bytes: 2345678
+              tx_packets: 34567
+              tx_errors: 1
+              tx_drops: 2
+            }
+            netstat {
+              key: NETSTAT_TCP_RETRANS_SEGS
+              value: 11846
+            }
+            netstat {
+              key: NETSTAT_TCP_OUT_RSTS
+              value: 9034
+            }
+          }
+          timestamp: 71625871363623
+          trusted_packet_sequence_id: 2
+        }
+        """),
+        query="""
+        SELECT
+          c.ts,
+          t.type,
+          t.name,
+          c.value
+        FROM counter_track t
+        JOIN counter c ON t.id = c.track_id
+        ORDER BY t.name, c.ts;
+        """,
+        out=Csv("""
+        "ts","type","name","value"
+        71625871363623,"netdev","netdev.wlan0.rx_bytes",12345678901.000000
+        71625871363623,"netdev","netdev.wlan0.rx_drops",17.000000
+        71625871363623,"netdev","netdev.wlan0.rx_errors",3.000000
+        71625871363623,"netdev","netdev.wlan0.rx_packets",9876543.000000
+        71625871363623,"netdev","netdev.wlan0.tx_bytes",2345678.000000
+        71625871363623,"netdev","netdev.wlan0.tx_drops",2.000000
+        71625871363623,"netdev","netdev.wlan0.tx_errors",1.000000
+        71625871363623,"netdev","netdev.wlan0.tx_packets",34567.000000
+        71625871363623,"netstat","netstat.Tcp:OutRsts",9034.000000
+        71625871363623,"netstat","netstat.Tcp:RetransSegs",11846.000000
+        """))
+
+  def test_cgroup_stats(self):
+    return DiffTestBlueprint(
+        trace=TextProto(r"""
+        packet {
+          cgroup_stats {
+            cgroups {
+              path: "/app"
+              cpu_usage_ns: 1500000
+              memory_current_bytes: 4096
+              memory_stat {
+                key: "anon"
+                value: 1024
+              }
+              io_stat {
+                major: 8
+                minor: 0
+                read_bytes: 90112
+                write_bytes: 4096
+              }
+              cpu_pressure {
+                some_total_ns: 1200000
+                full_total_ns: 300000
+              }
+            }
+            cgroups {
+              path: "/"
+              cpu_usage_ns: 9000000
+            }
+          }
+          timestamp: 71625871363623
+          trusted_packet_sequence_id: 2
+        }
+        packet {
+          cgroup_stats {
+            cgroups {
+              path: "/app"
+              cpu_usage_ns: 2500000
+              memory_current_bytes: 8192
+            }
+          }
+          timestamp: 71626000387166
+          trusted_packet_sequence_id: 2
+        }
+        """),
+        query="""
+        SELECT
+          c.ts,
+          t.name,
+          EXTRACT_ARG(t.dimension_arg_set_id, 'cgroup_path') AS path,
+          EXTRACT_ARG(t.dimension_arg_set_id, 'cgroup_counter') AS counter,
+          c.value
+        FROM counter_track t
+        JOIN counter c ON t.id = c.track_id
+        ORDER BY t.name, c.ts;
+        """,
+        out=Csv("""
+        "ts","name","path","counter","value"
+        71625871363623,"cgroup[/].cpu.usage_ns","/","cpu.usage_ns",9000000.000000
+        71625871363623,"cgroup[/app].cpu.pressure.full_ns","/app","cpu.pressure.full_ns",300000.000000
+        71625871363623,"cgroup[/app].cpu.pressure.some_ns","/app","cpu.pressure.some_ns",1200000.000000
+        71625871363623,"cgroup[/app].cpu.usage_ns","/app","cpu.usage_ns",1500000.000000
+        71626000387166,"cgroup[/app].cpu.usage_ns","/app","cpu.usage_ns",2500000.000000
+        71625871363623,"cgroup[/app].io.[8:0].read_bytes","/app","io.[8:0].read_bytes",90112.000000
+        71625871363623,"cgroup[/app].io.[8:0].write_bytes","/app","io.[8:0].write_bytes",4096.000000
+        71625871363623,"cgroup[/app].memory.current_bytes","/app","memory.current_bytes",4096.000000
+        71626000387166,"cgroup[/app].memory.current_bytes","/app","memory.current_bytes",8192.000000
+        71625871363623,"cgroup[/app].memory.stat.anon","/app","memory.stat.anon",1024.000000
+        """))
+
+  def test_syscall_latency_histogram(self):
+    return DiffTestBlueprint(
+        trace=TextProto(r"""
+        packet {
+          system_info {
+            utsname {
+              sysname: "Linux"
+              machine: "x86_64"
+            }
+          }
+          trusted_packet_sequence_id: 1
+        }
+        packet {
+          syscall_latency {
+            syscalls {
+              pid: 10
+              tid: 11
+              syscall_id: 0
+              buckets: 0
+              buckets: 12
+              counts: 3
+              counts: 2
+            }
+            syscalls {
+              pid: 10
+              tid: 10
+              syscall_id: 7
+              buckets: 20
+              counts: 1
+            }
+          }
+          timestamp: 1000000000
+          trusted_packet_sequence_id: 2
+        }
+        packet {
+          syscall_latency {
+            syscalls {
+              pid: 10
+              tid: 11
+              syscall_id: 0
+              buckets: 12
+              counts: 4
+            }
+          }
+          timestamp: 2000000000
+          trusted_packet_sequence_id: 2
+        }
+        """),
+        query="""
+        SELECT
+          h.ts,
+          t.tid,
+          h.syscall_id,
+          h.name,
+          h.min_dur,
+          h.max_dur,
+          h.count
+        FROM syscall_latency_histogram h
+        JOIN thread t USING (utid)
+        ORDER BY h.ts, t.tid, h.syscall_id, h.min_dur;
+        """,
+        out=Csv("""
+        "ts","tid","syscall_id","name","min_dur","max_dur","count"
+        1000000000,10,7,"sys_poll",1048576,2097152,1
+        1000000000,11,0,"sys_read",0,2,3
+        1000000000,11,0,"sys_read",4096,8192,2
+        2000000000,11,0,"sys_read",4096,8192,4
+        """))
+
+  def test_syscall_arg_sample(self):
+    return DiffTestBlueprint(
+        trace=TextProto(r"""
+        packet {
+          system_info {
+            utsname {
+              sysname: "Linux"
+              machine: "x86_64"
+            }
+          }
+          trusted_packet_sequence_id: 1
+        }
+        packet {
+          syscall_latency {
+            arg_samples {
+              ts: 999000000
+              pid: 10
+              tid: 11
+              syscall_id: 257
+              args: 4294967196
+              args: 140737488351232
+              args: 524288
+              args: 0
+              args: 0
+              args: 0
+            }
+          }
+          timestamp: 1000000000
+          trusted_packet_sequence_id: 2
+        }
+        """),
+        query="""
+        SELECT
+          s.ts,
+          t.tid,
+          s.name,
+          EXTRACT_ARG(s.arg_set_id, 'args[0]') AS arg0,
+          EXTRACT_ARG(s.arg_set_id, 'args[2]') AS arg2,
+          (SELECT COUNT(*) FROM args a WHERE a.arg_set_id = s.arg_set_id)
+            AS arg_count
+        FROM syscall_arg_sample s
+        JOIN thread t USING (utid);
+        """,
+        out=Csv("""
+        "ts","tid","name","arg0","arg2","arg_count"
+        999000000,11,"sys_openat",4294967196,524288,6
+        """))
@@ -76,3 +76,35 @@ class ProfilingHeapProfiling(TestSuite):
         0,-10,2,"unknown",2,6,1000
         1,-10,2,"unknown",3,1,90
         """))
+
+  def test_heap_profile_unreachable(self):
+    return DiffTestBlueprint(
+        trace=Path('heap_profile_unreachable.textproto'),
+        query="""
+        SELECT ts, callsite_id, count, size
+        FROM heap_profile_unreachable_allocation
+        ORDER BY id;
+        """,
+        out=Csv("""
+        "ts","callsite_id","count","size"
+        10,2,1,200
+        10,3,1,90
+        20,2,1,300
+        20,3,-1,-90
+        """))
+
+  def test_heap_profile_unreachable_allocations_unchanged(self):
+    return DiffTestBlueprint(
+        trace=Path('heap_profile_unreachable.textproto'),
+        query="""
+        SELECT ts, callsite_id, count, size
+        FROM heap_profile_allocation
+        ORDER BY id;
+        """,
+        out=Csv("""
+        "ts","callsite_id","count","size"
+        10,2,4,1000
+        10,3,1,90
+        20,2,-1,-300
+        20,3,-1,-90
+        """))
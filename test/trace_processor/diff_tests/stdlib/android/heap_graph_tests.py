@@ -101,3 +101,26 @@ class HeapGraph(TestSuite):
           "java.lang.String",1,10000,1,10000
           "B",1,1000,1,1000
         """))
+
+  def test_heap_graph_class_diff(self):
+    return DiffTestBlueprint(
+        trace=Path('heap_graph_diff.textproto'),
+        query="""
+          INCLUDE PERFETTO MODULE android.memory.heap_graph.diff;
+
+          SELECT
+            type_name,
+            baseline_obj_count,
+            candidate_obj_count,
+            delta_obj_count,
+            baseline_size_bytes,
+            candidate_size_bytes,
+            delta_size_bytes
+          FROM android_heap_graph_class_diff(2, 10, 2, 20);
+        """,
+        out=Csv("""
+          "type_name","baseline_obj_count","candidate_obj_count","delta_obj_count","baseline_size_bytes","candidate_size_bytes","delta_size_bytes"
+          "A",2,2,0,200,200,0
+          "B",1,2,1,1000,2000,1000
+          "java.lang.String",2,1,-1,10666,10000,-666
+        """))
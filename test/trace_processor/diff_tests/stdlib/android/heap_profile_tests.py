@@ -14,6 +14,7 @@
 # limitations under the License.
 
 from python.generators.diff_tests.testing import DataPath
+from python.generators.diff_tests.testing import Path
 from python.generators.diff_tests.testing import Csv
 from python.generators.diff_tests.testing import DiffTestBlueprint
 from python.generators.diff_tests.testing import TestSuite
@@ -50,3 +51,82 @@ class HeapProfile(TestSuite):
           "android::AndroidRuntime::javaThreadShell(void*)",0,27704,0,348050
           "(anonymous namespace)::nativeInitSensorEventQueue(_JNIEnv*, _jclass*, long, _jobject*, _jobject*, _jstring*, int)",0,26624,0,26624
         """))
+
+  def test_heap_profile_diff_same_process(self):
+    return DiffTestBlueprint(
+        trace=Path('heap_profile_diff.textproto'),
+        query="""
+          INCLUDE PERFETTO MODULE android.memory.heap_profile.diff;
+
+          SELECT
+            name,
+            parent_id IS NULL AS is_root,
+            baseline_cumulative_size,
+            candidate_cumulative_size,
+            delta_self_size,
+            delta_cumulative_size,
+            delta_self_count,
+            delta_cumulative_count
+          FROM android_heap_profile_diff(
+            (SELECT upid FROM process WHERE pid = 2), 10,
+            (SELECT upid FROM process WHERE pid = 2), 20, 'libc.malloc'
+          )
+          ORDER BY name;
+        """,
+        out=Csv("""
+          "name","is_root","baseline_cumulative_size","candidate_cumulative_size","delta_self_size","delta_cumulative_size","delta_self_count","delta_cumulative_count"
+          "f1",1,1100,1300,0,200,0,0
+          "f2",0,1100,1300,-100,200,-1,0
+          "f3",0,1000,1300,300,300,1,1
+        """))
+
+  def test_heap_profile_diff_two_processes(self):
+    return DiffTestBlueprint(
+        trace=Path('heap_profile_diff.textproto'),
+        query="""
+          INCLUDE PERFETTO MODULE android.memory.heap_profile.diff;
+
+          SELECT
+            name,
+            baseline_self_size,
+            candidate_self_size,
+            delta_self_size,
+            delta_cumulative_size,
+            delta_cumulative_count
+          FROM android_heap_profile_diff(
+            (SELECT upid FROM process WHERE pid = 2), 10,
+            (SELECT upid FROM process WHERE pid = 3), 10, 'libc.malloc'
+          )
+          ORDER BY name;
+        """,
+        out=Csv("""
+          "name","baseline_self_size","candidate_self_size","delta_self_size","delta_cumulative_size","delta_cumulative_count"
+          "f1",0,0,0,-400,0
+          "f2",100,300,200,-400,0
+          "f3",1000,400,-600,-600,-2
+        """))
+
+  def test_heap_profile_diff_heap_name(self):
+    return DiffTestBlueprint(
+        trace=Path('heap_profile_diff.textproto'),
+        query="""
+          INCLUDE PERFETTO MODULE android.memory.heap_profile.diff;
+
+          SELECT
+            name,
+            baseline_cumulative_size,
+            candidate_cumulative_size,
+            delta_self_size,
+            delta_cumulative_size,
+            delta_cumulative_count
+          FROM android_heap_profile_diff(
+            (SELECT upid FROM process WHERE pid = 2), 10,
+            (SELECT upid FROM process WHERE pid = 2), 20, 'custom'
+          )
+          ORDER BY name;
+        """,
+        out=Csv("""
+          "name","baseline_cumulative_size","candidate_cumulative_size","delta_self_size","delta_cumulative_size","delta_cumulative_count"
+          "f1",0,5000,0,5000,5
+          "f2",0,5000,5000,5000,5
+        """))
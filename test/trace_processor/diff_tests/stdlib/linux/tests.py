@@ -20,6 +20,610 @@ from python.generators.diff_tests.testing import TestSuite
 from python.generators.diff_tests.testing import PrintProfileProto
 
 
+# Three polls of the network counters of the linux.sys_stats data source. The
+# tx_bytes counter of wlan0 goes backwards in the last poll.
+NETWORK_TRACE = TextProto(r"""
+        packet {
+          sys_stats {
+            netdev {
+              interface_name: "wlan0"
+              rx_bytes: 1000
+              rx_packets: 10
+              tx_bytes: 100
+              tx_packets: 1
+            }
+            netstat {
+              key: NETSTAT_TCP_RETRANS_SEGS
+              value: 5
+            }
+            netstat {
+              key: NETSTAT_TCP_CURR_ESTAB
+              value: 3
+            }
+          }
+          timestamp: 1000000000
+          trusted_packet_sequence_id: 2
+        }
+        packet {
+          sys_stats {
+            netdev {
+              interface_name: "wlan0"
+              rx_bytes: 3000
+              rx_packets: 20
+              tx_bytes: 600
+              tx_packets: 5
+            }
+            netstat {
+              key: NETSTAT_TCP_RETRANS_SEGS
+              value: 9
+            }
+            netstat {
+              key: NETSTAT_TCP_CURR_ESTAB
+              value: 4
+            }
+          }
+          timestamp: 2000000000
+          trusted_packet_sequence_id: 2
+        }
+        packet {
+          sys_stats {
+            netdev {
+              interface_name: "wlan0"
+              rx_bytes: 6000
+              rx_packets: 40
+              tx_bytes: 400
+              tx_packets: 9
+            }
+            netstat {
+              key: NETSTAT_TCP_RETRANS_SEGS
+              value: 12
+            }
+            netstat {
+              key: NETSTAT_TCP_CURR_ESTAB
+              value: 5
+            }
+          }
+          timestamp: 3000000000
+          trusted_packet_sequence_id: 2
+        }
+        """)
+
+
+# Perf samples on CPU 0 with the cycles and instructions counters as followers,
+# along with the scheduling of the sampled threads and a slice on one of them.
+PERF_IPC_TRACE = TextProto(r"""
+        packet {
+          first_packet_on_sequence: true
+          sequence_flags: 1
+          trace_packet_defaults {
+            perf_sample_defaults {
+              timebase {
+                frequency: 1000
+                counter: SW_CPU_CLOCK
+              }
+              followers {
+                counter: HW_CPU_CYCLES
+              }
+              followers {
+                counter: HW_INSTRUCTIONS
+              }
+            }
+          }
+          trusted_packet_sequence_id: 4
+        }
+        packet {
+          ftrace_events {
+            cpu: 0
+            event {
+              timestamp: 500
+              pid: 0
+              sched_switch {
+                prev_comm: "swapper/0"
+                prev_pid: 0
+                prev_prio: 120
+                prev_state: 0
+                next_comm: "worker"
+                next_pid: 10
+                next_prio: 120
+              }
+            }
+            event {
+              timestamp: 3500
+              pid: 10
+              sched_switch {
+                prev_comm: "worker"
+                prev_pid: 10
+                prev_prio: 120
+                prev_state: 1
+                next_comm: "helper"
+                next_pid: 20
+                next_prio: 120
+              }
+            }
+            event {
+              timestamp: 4500
+              pid: 20
+              sched_switch {
+                prev_comm: "helper"
+                prev_pid: 20
+                prev_prio: 120
+                prev_state: 1
+                next_comm: "swapper/0"
+                next_pid: 0
+                next_prio: 120
+              }
+            }
+          }
+        }
+        packet {
+          track_descriptor {
+            uuid: 1
+            thread {
+              pid: 10
+              tid: 10
+            }
+          }
+        }
+        packet {
+          timestamp: 1500
+          trusted_packet_sequence_id: 5
+          track_event {
+            type: TYPE_SLICE_BEGIN
+            track_uuid: 1
+            name: "work"
+          }
+        }
+        packet {
+          timestamp: 3500
+          trusted_packet_sequence_id: 5
+          track_event {
+            type: TYPE_SLICE_END
+            track_uuid: 1
+          }
+        }
+        packet {
+          timestamp: 1000
+          sequence_flags: 2
+          perf_sample {
+            cpu: 0
+            pid: 10
+            tid: 10
+            timebase_count: 1
+            follower_counts: 1000
+            follower_counts: 500
+          }
+          trusted_packet_sequence_id: 4
+        }
+        packet {
+          timestamp: 2000
+          sequence_flags: 2
+          perf_sample {
+            cpu: 0
+            pid: 10
+            tid: 10
+            timebase_count: 2
+            follower_counts: 3000
+            follower_counts: 2500
+          }
+          trusted_packet_sequence_id: 4
+        }
+        packet {
+          timestamp: 3000
+          sequence_flags: 2
+          perf_sample {
+            cpu: 0
+            pid: 10
+            tid: 10
+            timebase_count: 3
+            follower_counts: 4000
+            follower_counts: 4500
+          }
+          trusted_packet_sequence_id: 4
+        }
+        packet {
+          timestamp: 4000
+          sequence_flags: 2
+          perf_sample {
+            cpu: 0
+            pid: 20
+            tid: 20
+            timebase_count: 4
+            follower_counts: 8000
+            follower_counts: 5500
+          }
+          trusted_packet_sequence_id: 4
+        }
+        """)
+
+
+PERF_OFF_CPU_TRACE = TextProto(r"""
+        packet {
+          first_packet_on_sequence: true
+          sequence_flags: 1
+          trace_packet_defaults {
+            perf_sample_defaults {
+              timebase {
+                period: 1
+                tracepoint {
+                  name: "sched:sched_switch"
+                  filter: "prev_state & 3"
+                }
+              }
+            }
+          }
+          trusted_packet_sequence_id: 4
+        }
+        packet {
+          interned_data {
+            mappings {
+              iid: 1
+              path_string_ids: 1
+            }
+            mapping_paths {
+              iid: 1
+              str: "libc.so"
+            }
+            function_names {
+              iid: 1
+              str: "main"
+            }
+            function_names {
+              iid: 2
+              str: "read"
+            }
+            function_names {
+              iid: 3
+              str: "futex_wait"
+            }
+            frames {
+              iid: 1
+              function_name_id: 1
+              mapping_id: 1
+            }
+            frames {
+              iid: 2
+              function_name_id: 2
+              mapping_id: 1
+            }
+            frames {
+              iid: 3
+              function_name_id: 3
+              mapping_id: 1
+            }
+            callstacks {
+              iid: 1
+              frame_ids: 1
+              frame_ids: 2
+            }
+            callstacks {
+              iid: 2
+              frame_ids: 1
+              frame_ids: 3
+            }
+          }
+          sequence_flags: 2
+          trusted_packet_sequence_id: 4
+        }
+        packet {
+          ftrace_events {
+            cpu: 0
+            event {
+              timestamp: 500
+              pid: 0
+              sched_switch {
+                prev_comm: "swapper/0"
+                prev_pid: 0
+                prev_prio: 120
+                prev_state: 0
+                next_comm: "worker"
+                next_pid: 10
+                next_prio: 120
+              }
+            }
+            event {
+              timestamp: 1000
+              pid: 10
+              sched_switch {
+                prev_comm: "worker"
+                prev_pid: 10
+                prev_prio: 120
+                prev_state: 1
+                next_comm: "reader"
+                next_pid: 20
+                next_prio: 120
+              }
+            }
+            event {
+              timestamp: 1800
+              pid: 20
+              sched_waking {
+                comm: "worker"
+                pid: 10
+                prio: 120
+                success: 1
+                target_cpu: 0
+              }
+            }
+            event {
+              timestamp: 2000
+              pid: 20
+              sched_switch {
+                prev_comm: "reader"
+                prev_pid: 20
+                prev_prio: 120
+                prev_state: 2
+                next_comm: "worker"
+                next_pid: 10
+                next_prio: 120
+              }
+            }
+            event {
+              timestamp: 2600
+              pid: 10
+              sched_waking {
+                comm: "reader"
+                pid: 20
+                prio: 120
+                success: 1
+                target_cpu: 1
+              }
+            }
+            event {
+              timestamp: 3000
+              pid: 10
+              sched_switch {
+                prev_comm: "worker"
+                prev_pid: 10
+                prev_prio: 120
+                prev_state: 1
+                next_comm: "swapper/0"
+                next_pid: 0
+                next_prio: 120
+              }
+            }
+          }
+        }
+        packet {
+          ftrace_events {
+            cpu: 1
+            event {
+              timestamp: 2700
+              pid: 0
+              sched_switch {
+                prev_comm: "swapper/1"
+                prev_pid: 0
+                prev_prio: 120
+                prev_state: 0
+                next_comm: "reader"
+                next_pid: 20
+                next_prio: 120
+              }
+            }
+            event {
+              timestamp: 4000
+              pid: 20
+              sched_waking {
+                comm: "worker"
+                pid: 10
+                prio: 120
+                success: 1
+                target_cpu: 0
+              }
+            }
+          }
+        }
+        packet {
+          timestamp: 1000
+          sequence_flags: 2
+          perf_sample {
+            cpu: 0
+            pid: 10
+            tid: 10
+            cpu_mode: MODE_USER
+            timebase_count: 1
+            callstack_iid: 2
+          }
+          trusted_packet_sequence_id: 4
+        }
+        packet {
+          timestamp: 1999
+          sequence_flags: 2
+          perf_sample {
+            cpu: 0
+            pid: 20
+            tid: 20
+            cpu_mode: MODE_USER
+            timebase_count: 2
+            callstack_iid: 1
+          }
+          trusted_packet_sequence_id: 4
+        }
+        packet {
+          timestamp: 3000
+          sequence_flags: 2
+          perf_sample {
+            cpu: 0
+            pid: 10
+            tid: 10
+            cpu_mode: MODE_USER
+            timebase_count: 3
+            callstack_iid: 2
+          }
+          trusted_packet_sequence_id: 4
+        }
+        """)
+
+# Futex contention between the threads of process 10: thread 12 is woken by
+# thread 10 and then wakes thread 11, which was waiting on the same lock. The
+# wait of thread 11 at ts 2000 returns EAGAIN and the wait of process 20 times
+# out. 202 is futex(2) on x86_64; 128 and 129 are FUTEX_WAIT_PRIVATE and
+# FUTEX_WAKE_PRIVATE.
+FUTEX_TRACE = TextProto(r"""
+        packet {
+          system_info {
+            utsname {
+              sysname: "Linux"
+              release: "6.1.0"
+              machine: "x86_64"
+            }
+          }
+        }
+        packet {
+          process_tree {
+            processes {
+              pid: 10
+              ppid: 1
+              cmdline: "app"
+            }
+            processes {
+              pid: 20
+              ppid: 1
+              cmdline: "other"
+            }
+            threads {
+              tid: 11
+              tgid: 10
+            }
+            threads {
+              tid: 12
+              tgid: 10
+            }
+          }
+        }
+        packet {
+          ftrace_events {
+            cpu: 0
+            event {
+              timestamp: 900
+              pid: 12
+              sys_enter {
+                id: 202
+                args: 4096
+                args: 128
+                args: 0
+                args: 0
+                args: 0
+                args: 0
+              }
+            }
+            event {
+              timestamp: 1000
+              pid: 11
+              sys_enter {
+                id: 202
+                args: 4096
+                args: 128
+                args: 0
+                args: 0
+                args: 0
+                args: 0
+              }
+            }
+            event {
+              timestamp: 1400
+              pid: 10
+              sys_enter {
+                id: 202
+                args: 4096
+                args: 129
+                args: 1
+                args: 0
+                args: 0
+                args: 0
+              }
+            }
+            event {
+              timestamp: 1405
+              pid: 10
+              sys_exit {
+                id: 202
+                ret: 1
+              }
+            }
+            event {
+              timestamp: 1450
+              pid: 12
+              sys_exit {
+                id: 202
+                ret: 0
+              }
+            }
+            event {
+              timestamp: 1500
+              pid: 12
+              sys_enter {
+                id: 202
+                args: 4096
+                args: 129
+                args: 1
+                args: 0
+                args: 0
+                args: 0
+              }
+            }
+            event {
+              timestamp: 1510
+              pid: 12
+              sys_exit {
+                id: 202
+                ret: 1
+              }
+            }
+            event {
+              timestamp: 1600
+              pid: 11
+              sys_exit {
+                id: 202
+                ret: 0
+              }
+            }
+            event {
+              timestamp: 2000
+              pid: 11
+              sys_enter {
+                id: 202
+                args: 4096
+                args: 128
+                args: 0
+                args: 0
+                args: 0
+                args: 0
+              }
+            }
+            event {
+              timestamp: 2001
+              pid: 11
+              sys_exit {
+                id: 202
+                ret: -11
+              }
+            }
+            event {
+              timestamp: 3000
+              pid: 20
+              sys_enter {
+                id: 202
+                args: 4096
+                args: 128
+                args: 0
+                args: 0
+                args: 0
+                args: 0
+              }
+            }
+            event {
+              timestamp: 3500
+              pid: 20
+              sys_exit {
+                id: 202
+                ret: -110
+              }
+            }
+          }
+        }
+        """)
+
+
 class LinuxTests(TestSuite):
 
   def test_kernel_threads(self):
@@ -165,3 +769,288 @@ class LinuxTests(TestSuite):
         1703001573489,447,"SCHED",46634,"[NULL]",1
         1702673567426,1098,"TIMER",39947,"[NULL]",1
         """))
+
+  def test_network_interface_throughput(self):
+    return DiffTestBlueprint(
+        trace=NETWORK_TRACE,
+        query="""
+        INCLUDE PERFETTO MODULE linux.network;
+
+        SELECT *
+        FROM linux_network_interface_throughput;
+        """,
+        out=Csv("""
+        "ts","dur","interface_name","rx_bytes","tx_bytes","rx_packets","tx_packets","rx_bytes_per_sec","tx_bytes_per_sec"
+        1000000000,1000000000,"wlan0",2000,500,10,4,2000.000000,500.000000
+        2000000000,1000000000,"wlan0",3000,"[NULL]",20,4,3000.000000,"[NULL]"
+        """))
+
+  def test_tcp_counter_rate(self):
+    return DiffTestBlueprint(
+        trace=NETWORK_TRACE,
+        query="""
+        INCLUDE PERFETTO MODULE linux.network;
+
+        SELECT *
+        FROM linux_tcp_counter_rate;
+        """,
+        out=Csv("""
+        "ts","dur","key","delta","rate_per_sec"
+        1000000000,1000000000,"Tcp:RetransSegs",4,4.000000
+        2000000000,1000000000,"Tcp:RetransSegs",3,3.000000
+        """))
+
+  def test_perf_sample_ipc(self):
+    return DiffTestBlueprint(
+        trace=PERF_IPC_TRACE,
+        query="""
+        INCLUDE PERFETTO MODULE linux.perf.ipc;
+
+        SELECT ts, tid, cpu, instructions, cycles, ipc
+        FROM linux_perf_sample_ipc
+        JOIN thread USING (utid)
+        ORDER BY ts;
+        """,
+        out=Csv("""
+        "ts","tid","cpu","instructions","cycles","ipc"
+        2000,10,0,2000,2000,1.000000
+        3000,10,0,2000,1000,2.000000
+        4000,20,0,1000,4000,0.250000
+        """))
+
+  def test_perf_thread_ipc(self):
+    return DiffTestBlueprint(
+        trace=PERF_IPC_TRACE,
+        query="""
+        INCLUDE PERFETTO MODULE linux.perf.ipc;
+
+        SELECT tid, sample_count, instructions, cycles, ipc
+        FROM linux_perf_thread_ipc
+        JOIN thread USING (utid)
+        ORDER BY tid;
+        """,
+        out=Csv("""
+        "tid","sample_count","instructions","cycles","ipc"
+        10,2,4000,3000,1.333333
+        20,1,1000,4000,0.250000
+        """))
+
+  def test_perf_thread_state_and_slice_ipc(self):
+    return DiffTestBlueprint(
+        trace=PERF_IPC_TRACE,
+        query="""
+        INCLUDE PERFETTO MODULE linux.perf.ipc;
+
+        SELECT
+          'thread_state' AS type,
+          ts,
+          dur,
+          tid,
+          sample_count,
+          instructions,
+          cycles,
+          ipc
+        FROM linux_perf_thread_state_ipc
+        JOIN thread USING (utid)
+        UNION ALL
+        SELECT
+          'slice' AS type,
+          ts,
+          dur,
+          tid,
+          sample_count,
+          instructions,
+          cycles,
+          ipc
+        FROM linux_perf_slice_ipc
+        JOIN thread USING (utid)
+        ORDER BY type, ts;
+        """,
+        out=Csv("""
+        "type","ts","dur","tid","sample_count","instructions","cycles","ipc"
+        "slice",1500,2000,10,2,4000,3000,1.333333
+        "thread_state",500,3000,10,2,4000,3000,1.333333
+        "thread_state",3500,1000,20,1,1000,4000,0.250000
+        """))
+
+  def test_perf_off_cpu_sample(self):
+    return DiffTestBlueprint(
+        trace=PERF_OFF_CPU_TRACE,
+        query="""
+        INCLUDE PERFETTO MODULE linux.perf.off_cpu;
+
+        SELECT
+          s.ts,
+          t.tid,
+          s.cpu,
+          s.state,
+          s.blocked_dur,
+          w.tid AS waker_tid
+        FROM linux_perf_off_cpu_sample AS s
+        JOIN thread AS t USING (utid)
+        LEFT JOIN thread AS w ON w.utid = s.waker_utid
+        ORDER BY s.ts;
+        """,
+        out=Csv("""
+        "ts","tid","cpu","state","blocked_dur","waker_tid"
+        1000,10,0,"S",800,20
+        1999,20,0,"D",600,10
+        3000,10,0,"S",1000,20
+        """))
+
+  def test_perf_off_cpu_thread(self):
+    return DiffTestBlueprint(
+        trace=PERF_OFF_CPU_TRACE,
+        query="""
+        INCLUDE PERFETTO MODULE linux.perf.off_cpu;
+
+        SELECT tid, sample_count, blocked_dur, uninterruptible_dur
+        FROM linux_perf_off_cpu_thread
+        JOIN thread USING (utid)
+        ORDER BY tid;
+        """,
+        out=Csv("""
+        "tid","sample_count","blocked_dur","uninterruptible_dur"
+        10,2,1800,0
+        20,1,600,600
+        """))
+
+  def test_perf_off_cpu_summary_tree(self):
+    return DiffTestBlueprint(
+        trace=PERF_OFF_CPU_TRACE,
+        query="""
+        INCLUDE PERFETTO MODULE linux.perf.off_cpu;
+
+        SELECT
+          name,
+          mapping_name,
+          self_count,
+          cumulative_count,
+          self_blocked_dur,
+          cumulative_blocked_dur
+        FROM linux_perf_off_cpu_summary_tree
+        ORDER BY name;
+        """,
+        out=Csv("""
+        "name","mapping_name","self_count","cumulative_count","self_blocked_dur","cumulative_blocked_dur"
+        "futex_wait","/libc.so",2,2,1800,1800
+        "main","/libc.so",0,3,0,2400
+        "read","/libc.so",1,1,600,600
+        """))
+
+  def test_futex_contention_table(self):
+    return DiffTestBlueprint(
+        trace=FUTEX_TRACE,
+        query="""
+        SELECT
+          f.ts,
+          f.dur,
+          t.tid,
+          f.address,
+          f.op,
+          f.ret,
+          waker.tid AS waker_tid,
+          f.wake_ts
+        FROM futex_contention AS f
+        JOIN thread AS t USING (utid)
+        LEFT JOIN thread AS waker ON waker.utid = f.waker_utid
+        ORDER BY f.ts;
+        """,
+        out=Csv("""
+        "ts","dur","tid","address","op","ret","waker_tid","wake_ts"
+        900,550,12,4096,"FUTEX_WAIT",0,10,1400
+        1000,600,11,4096,"FUTEX_WAIT",0,12,1500
+        3000,500,20,4096,"FUTEX_WAIT",-110,"[NULL]","[NULL]"
+        """))
+
+  def test_futex_contention_flows(self):
+    return DiffTestBlueprint(
+        trace=FUTEX_TRACE,
+        query="""
+        SELECT
+          s_out.ts AS wake_ts,
+          s_in.ts AS wait_ts,
+          s_in.name
+        FROM flow
+        JOIN slice AS s_out ON s_out.id = flow.slice_out
+        JOIN slice AS s_in ON s_in.id = flow.slice_in
+        ORDER BY s_out.ts;
+        """,
+        out=Csv("""
+        "wake_ts","wait_ts","name"
+        1400,900,"sys_futex"
+        1500,1000,"sys_futex"
+        """))
+
+  def test_futex_lock_summary(self):
+    return DiffTestBlueprint(
+        trace=FUTEX_TRACE,
+        query="""
+        INCLUDE PERFETTO MODULE linux.futex;
+
+        SELECT
+          p.pid,
+          s.address,
+          s.wait_count,
+          s.waiter_count,
+          s.waker_count,
+          s.total_dur,
+          s.max_dur,
+          s.avg_dur
+        FROM linux_futex_lock_summary AS s
+        JOIN process AS p USING (upid)
+        ORDER BY s.total_dur DESC;
+        """,
+        out=Csv("""
+        "pid","address","wait_count","waiter_count","waker_count","total_dur","max_dur","avg_dur"
+        10,4096,2,2,2,1150,600,575.000000
+        20,4096,1,1,0,500,500,500.000000
+        """))
+
+  def test_futex_contention_by_thread(self):
+    return DiffTestBlueprint(
+        trace=FUTEX_TRACE,
+        query="""
+        INCLUDE PERFETTO MODULE linux.futex;
+
+        SELECT
+          t.tid,
+          waker.tid AS waker_tid,
+          c.wait_count,
+          c.total_dur
+        FROM linux_futex_contention_by_thread AS c
+        JOIN thread AS t USING (utid)
+        LEFT JOIN thread AS waker ON waker.utid = c.waker_utid
+        ORDER BY t.tid;
+        """,
+        out=Csv("""
+        "tid","waker_tid","wait_count","total_dur"
+        11,12,1,600
+        12,10,1,550
+        20,"[NULL]",1,500
+        """))
+
+  def test_futex_blocking_chain(self):
+    return DiffTestBlueprint(
+        trace=FUTEX_TRACE,
+        query="""
+        INCLUDE PERFETTO MODULE linux.futex;
+
+        SELECT
+          c.depth,
+          c.ts,
+          t.tid,
+          waker.tid AS waker_tid,
+          c.wake_ts
+        FROM linux_futex_blocking_chain(
+          (SELECT id FROM futex_contention WHERE ts = 1000)
+        ) AS c
+        JOIN thread AS t USING (utid)
+        JOIN thread AS waker ON waker.utid = c.waker_utid
+        ORDER BY c.depth;
+        """,
+        out=Csv("""
+        "depth","ts","tid","waker_tid","wake_ts"
+        0,1000,11,12,1500
+        1,900,12,10,1400
+        """))
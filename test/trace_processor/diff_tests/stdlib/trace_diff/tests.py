@@ -0,0 +1,235 @@
+#!/usr/bin/env python3
+# Copyright (C) 2025 The Android Open Source Project
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#      http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+from python.generators.diff_tests.testing import Csv, Systrace, TextProto
+from python.generators.diff_tests.testing import DiffTestBlueprint
+from python.generators.diff_tests.testing import TestSuite
+
+# Creates the `baseline` schema with empty copies of the tables of the
+# trace_diff.stats module, which the tests then fill in with the statistics of
+# a (fake) baseline trace.
+BASELINE_SCHEMA = """
+  INCLUDE PERFETTO MODULE trace_diff.stats;
+
+  ATTACH DATABASE ':memory:' AS baseline;
+  CREATE TABLE baseline.trace_diff_slice_stats AS
+  SELECT * FROM trace_diff_slice_stats WHERE FALSE;
+  CREATE TABLE baseline.trace_diff_thread_state_stats AS
+  SELECT * FROM trace_diff_thread_state_stats WHERE FALSE;
+  CREATE TABLE baseline.trace_diff_process_cpu_stats AS
+  SELECT * FROM trace_diff_process_cpu_stats WHERE FALSE;
+  CREATE TABLE baseline.trace_diff_cpu_profile_tree AS
+  SELECT * FROM trace_diff_cpu_profile_tree WHERE FALSE;
+"""
+
+
+def _slice(track_uuid, name, ts, dur):
+  return f"""
+  packet {{
+    timestamp: {ts}
+    trusted_packet_sequence_id: 1
+    track_event {{
+      type: TYPE_SLICE_BEGIN
+      track_uuid: {track_uuid}
+      name: "{name}"
+    }}
+  }}
+  packet {{
+    timestamp: {ts + dur}
+    trusted_packet_sequence_id: 1
+    track_event {{
+      type: TYPE_SLICE_END
+      track_uuid: {track_uuid}
+    }}
+  }}
+  """
+
+
+SLICES_TRACE = TextProto(r"""
+  packet {
+    track_descriptor {
+      uuid: 1
+      process {
+        pid: 1
+        process_name: "proc"
+      }
+    }
+  }
+  packet {
+    track_descriptor {
+      uuid: 2
+      parent_uuid: 1
+      thread {
+        pid: 1
+        tid: 2
+        thread_name: "main"
+      }
+    }
+  }
+  packet {
+    track_descriptor {
+      uuid: 3
+      name: "Global"
+    }
+  }
+  """ + ''.join(
+    _slice(2, 'draw', 1000 * (i + 1), dur)
+    for i, dur in enumerate([100, 110, 90, 105, 95])) + _slice(
+        1, 'load', 10000, 50) + _slice(3, 'orphan', 20000, 20))
+
+
+def _perf_sample(ts_us, frames):
+  return f'app 10/10 100.{ts_us:06d}:          1 cycles:\n' + ''.join(
+      f'\t    {i:x} {name}+0x10 (/lib/app.so)\n'
+      for i, name in enumerate(frames)) + '\n'
+
+
+# 30 samples in foo, 10 in bar and 10 in main.
+PERF_TRACE = Systrace(''.join(
+    _perf_sample(i + 1, ['foo', 'main'] if i < 30 else
+                 ['bar', 'main'] if i < 40 else ['main']) for i in range(50)))
+
+
+class TraceDiff(TestSuite):
+
+  def test_slice_stats(self):
+    return DiffTestBlueprint(
+        trace=SLICES_TRACE,
+        query="""
+        INCLUDE PERFETTO MODULE trace_diff.stats;
+
+        SELECT *
+        FROM trace_diff_slice_stats
+        ORDER BY name;
+        """,
+        out=Csv("""
+        "name","thread_name","process_name","count","total_dur","mean_dur","variance_dur"
+        "draw","main","proc",5,500,100.000000,62.500000
+        "load","[NULL]","proc",1,50,50.000000,"[NULL]"
+        "orphan","[NULL]","[NULL]",1,20,20.000000,"[NULL]"
+        """))
+
+  def test_slice_diff(self):
+    return DiffTestBlueprint(
+        trace=SLICES_TRACE,
+        query=BASELINE_SCHEMA + """
+        INSERT INTO baseline.trace_diff_slice_stats VALUES
+          ('draw', 'main', 'proc', 5, 400, 80.0, 62.5),
+          ('load', NULL, 'proc', 1, 40, 40.0, NULL),
+          ('gone', NULL, NULL, 2, 30, 15.0, 50.0);
+
+        INCLUDE PERFETTO MODULE trace_diff.compare;
+
+        SELECT
+          name,
+          baseline_count,
+          candidate_count,
+          delta_total_dur,
+          delta_mean_dur,
+          p_value,
+          is_significant
+        FROM trace_diff_slice
+        ORDER BY name;
+        """,
+        out=Csv("""
+        "name","baseline_count","candidate_count","delta_total_dur","delta_mean_dur","p_value","is_significant"
+        "draw",5,5,100,20.000000,0.003950,1
+        "gone",2,0,-30,"[NULL]","[NULL]",0
+        "load",1,1,10,10.000000,"[NULL]",0
+        "orphan",0,1,20,"[NULL]","[NULL]",0
+        """))
+
+  def test_thread_state_and_cpu_diff(self):
+    return DiffTestBlueprint(
+        trace=Systrace("""
+          <idle>-0     [000] d..3   100.000000: sched_switch: prev_comm=swapper/0 prev_pid=0 prev_prio=120 prev_state=R ==> next_comm=worker next_pid=10 next_prio=120
+          worker-10    [000] d..3   100.000100: sched_switch: prev_comm=worker prev_pid=10 prev_prio=120 prev_state=S ==> next_comm=swapper/0 next_pid=0 next_prio=120
+          <idle>-0     [000] d..3   100.000300: sched_switch: prev_comm=swapper/0 prev_pid=0 prev_prio=120 prev_state=R ==> next_comm=worker next_pid=10 next_prio=120
+          worker-10    [000] d..3   100.000350: sched_switch: prev_comm=worker prev_pid=10 prev_prio=120 prev_state=S ==> next_comm=swapper/0 next_pid=0 next_prio=120
+          """),
+        query=BASELINE_SCHEMA + """
+        INSERT INTO baseline.trace_diff_thread_state_stats VALUES
+          (NULL, 'worker', 'Running', 2, 100000, 50000.0, 0.0);
+        INSERT INTO baseline.trace_diff_process_cpu_stats VALUES
+          (NULL, 2, 100000),
+          ('gone', 1, 5000);
+
+        INCLUDE PERFETTO MODULE trace_diff.compare;
+
+        SELECT
+          'thread_state' AS type,
+          thread_name AS name,
+          baseline_total_dur AS baseline,
+          candidate_total_dur AS candidate,
+          delta_total_dur AS delta
+        FROM trace_diff_thread_state
+        WHERE thread_name = 'worker' AND state = 'Running'
+        UNION ALL
+        SELECT
+          'process_cpu' AS type,
+          process_name AS name,
+          baseline_cpu_dur AS baseline,
+          candidate_cpu_dur AS candidate,
+          delta_cpu_dur AS delta
+        FROM trace_diff_process_cpu
+        ORDER BY type, name;
+        """,
+        out=Csv("""
+        "type","name","baseline","candidate","delta"
+        "process_cpu","[NULL]",100000,150000,50000
+        "process_cpu","gone",5000,0,-5000
+        "thread_state","worker",100000,150000,50000
+        """))
+
+  def test_cpu_profile_flamegraph_diff(self):
+    return DiffTestBlueprint(
+        trace=PERF_TRACE,
+        query=BASELINE_SCHEMA + """
+        -- In the baseline, the samples of foo and bar are swapped.
+        INSERT INTO baseline.trace_diff_cpu_profile_tree
+        SELECT
+          id,
+          parent_id,
+          name,
+          mapping_name,
+          CASE name WHEN 'foo' THEN 10 WHEN 'bar' THEN 30 ELSE self_count END,
+          CASE name
+            WHEN 'foo' THEN 10
+            WHEN 'bar' THEN 30
+            ELSE cumulative_count
+          END
+        FROM trace_diff_cpu_profile_tree;
+
+        INCLUDE PERFETTO MODULE trace_diff.compare;
+
+        SELECT
+          f.name,
+          p.name AS parent_name,
+          f.baseline_cumulative_count,
+          f.candidate_cumulative_count,
+          f.delta_cumulative_count,
+          f.p_value,
+          f.is_significant
+        FROM trace_diff_cpu_profile_flamegraph AS f
+        LEFT JOIN trace_diff_cpu_profile_flamegraph AS p
+          ON f.parent_id = p.id
+        ORDER BY f.name;
+        """,
+        out=Csv("""
+        "name","parent_name","baseline_cumulative_count","candidate_cumulative_count","delta_cumulative_count","p_value","is_significant"
+        "bar","main",30,10,-20,0.000045,1
+        "foo","main",10,30,20,0.000045,1
+        "main","[NULL]",50,50,0,"[NULL]",0
+        """))
@@ -57,6 +57,11 @@ def main():
       '--quiet', action='store_true', help='Only print if the test failed.')
   parser.add_argument(
       '--no-colors', action='store_true', help='Print without coloring')
+  parser.add_argument(
+      '--snapshot-roundtrip',
+      action='store_true',
+      help='Run the queries on a snapshot of the trace (see '
+      'trace_processor_shell --snapshot-out) instead of on the trace')
   parser.add_argument(
       'trace_processor', type=str, help='location of trace processor binary')
   args = parser.parse_args()
@@ -85,6 +90,7 @@ def main():
       args.override_sql_package,
       args.test_dir,
       args.quiet,
+      args.snapshot_roundtrip,
   )
   sys.stderr.write(f"[==========] Running {len(test_runner.tests)} tests.\n")
 
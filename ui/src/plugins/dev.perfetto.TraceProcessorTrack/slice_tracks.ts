@@ -256,4 +256,9 @@ export const SLICE_TRACK_SCHEMAS: ReadonlyArray<SliceTrackTypeSchema> = [
     topLevelGroup: 'THREAD',
     group: undefined,
   },
+  {
+    type: 'otlp_span',
+    topLevelGroup: 'PROCESS',
+    group: undefined,
+  },
 ];